@@ -0,0 +1,58 @@
+CREATE TABLE IF NOT EXISTS klines (
+    symbol TEXT NOT NULL,
+    interval TEXT NOT NULL,
+    open_time BIGINT NOT NULL,
+    close_time BIGINT NOT NULL,
+    open DOUBLE PRECISION NOT NULL,
+    high DOUBLE PRECISION NOT NULL,
+    low DOUBLE PRECISION NOT NULL,
+    close DOUBLE PRECISION NOT NULL,
+    volume DOUBLE PRECISION NOT NULL,
+    PRIMARY KEY (symbol, interval, open_time)
+);
+
+CREATE TABLE IF NOT EXISTS market_trades (
+    id BIGSERIAL PRIMARY KEY,
+    symbol TEXT NOT NULL,
+    timestamp BIGINT NOT NULL,
+    qty DOUBLE PRECISION NOT NULL,
+    price DOUBLE PRECISION NOT NULL,
+    order_side TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS market_trades_symbol_timestamp_idx
+    ON market_trades (symbol, timestamp);
+
+CREATE TABLE IF NOT EXISTS strategy_summaries (
+    id UUID PRIMARY KEY,
+    summary JSONB NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS active_strategies (
+    id UUID PRIMARY KEY,
+    info JSONB NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS strategy_params_changes (
+    id BIGSERIAL PRIMARY KEY,
+    strategy_id UUID NOT NULL,
+    timestamp BIGINT NOT NULL,
+    change JSONB NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS strategy_params_changes_strategy_id_idx
+    ON strategy_params_changes (strategy_id, timestamp);
+
+CREATE TABLE IF NOT EXISTS back_tests (
+    id UUID PRIMARY KEY,
+    created_at BIGINT NOT NULL,
+    record JSONB NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS back_test_reports (
+    id UUID NOT NULL,
+    format TEXT NOT NULL,
+    created_at BIGINT NOT NULL,
+    content TEXT NOT NULL,
+    PRIMARY KEY (id, format)
+);
@@ -0,0 +1,25 @@
+CREATE TABLE IF NOT EXISTS account_positions (
+    account TEXT NOT NULL,
+    id UUID NOT NULL,
+    position JSONB NOT NULL,
+    PRIMARY KEY (account, id)
+);
+
+CREATE TABLE IF NOT EXISTS account_trades (
+    seq BIGSERIAL PRIMARY KEY,
+    account TEXT NOT NULL,
+    id UUID NOT NULL,
+    trade_tx JSONB NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS account_trades_account_idx ON account_trades (account, seq);
+
+CREATE TABLE IF NOT EXISTS account_orders (
+    seq BIGSERIAL PRIMARY KEY,
+    account TEXT NOT NULL,
+    id UUID NOT NULL,
+    timestamp BIGINT NOT NULL,
+    record JSONB NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS account_orders_account_idx ON account_orders (account, seq);
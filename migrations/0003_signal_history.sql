@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS signal_records (
+    id UUID PRIMARY KEY,
+    strategy_id UUID NOT NULL,
+    timestamp BIGINT NOT NULL,
+    handled_at BIGINT NOT NULL,
+    record JSONB NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS signal_records_strategy_idx ON signal_records (strategy_id, timestamp);
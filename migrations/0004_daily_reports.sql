@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS daily_reports (
+    id UUID PRIMARY KEY,
+    from_ts BIGINT NOT NULL,
+    to_ts BIGINT NOT NULL,
+    report JSONB NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS daily_reports_to_ts_idx ON daily_reports (to_ts);
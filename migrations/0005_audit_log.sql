@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS audit_log (
+    id UUID PRIMARY KEY,
+    timestamp BIGINT NOT NULL,
+    action TEXT NOT NULL,
+    subject TEXT,
+    entry JSONB NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS audit_log_timestamp_idx ON audit_log (timestamp);
@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS external_data (
+    series TEXT NOT NULL,
+    timestamp BIGINT NOT NULL,
+    value DOUBLE PRECISION NOT NULL,
+    PRIMARY KEY (series, timestamp)
+);
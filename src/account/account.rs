@@ -1,16 +1,26 @@
-use std::collections::hash_map::Values;
+use std::collections::hash_map::{Values, ValuesMut};
+use std::slice::IterMut;
 use std::{collections::HashMap, sync::Arc};
 
+use log::info;
 use serde::{Deserialize, Serialize};
-
-use crate::exchange::api::ExchangeInfo;
+use uuid::Uuid;
+
+use crate::error::{StorageError, StorageResult};
+use crate::events::{BotEvent, EventPublisher};
+use crate::exchange::api::{ExchangeInfo, ExchangePosition};
+use crate::exchange::types::ApiResult;
+use crate::market::market::Market;
+use crate::storage::manager::StorageManager;
 use crate::strategy::strategy::StrategyId;
+use crate::utils::time::{generate_ts, string_to_timestamp, MIN_AS_MILI, SEC_AS_MILI};
 use crate::{
     account::trade::{OrderSide, Position},
     exchange::api::ExchangeApi,
 };
 
-use super::trade::{PositionId, TradeTx};
+use super::money::{Price, SymbolPrecision};
+use super::trade::{IcebergOrder, OrderRecord, PositionId, TradeTx};
 
 /// Represents a trading account with positions, trades, and an exchange API.
 pub struct Account {
@@ -18,10 +28,26 @@ pub struct Account {
     positions: HashMap<PositionId, Position>,
     /// A vector containing trade transactions.
     trades: Vec<TradeTx>,
+    /// A vector containing the orders filled to open and close positions.
+    orders: Vec<OrderRecord>,
+    /// The iceberg orders placed by split signals, each filled by several orders.
+    iceberg_orders: Vec<IcebergOrder>,
     /// A thread-safe reference to the exchange API.
     exchange_api: Arc<Box<dyn ExchangeApi>>,
     /// A flag indicating whether the account is in dry run mode.
     dry_run: bool,
+    /// The storage positions, trades and orders are persisted to, if any.
+    storage_manager: Option<Arc<Box<dyn StorageManager>>>,
+    /// The name the account's history is stored under.
+    name: String,
+    /// Publishes opened and closed positions, if set.
+    events: Option<EventPublisher>,
+    /// The precision of each symbol of the exchange, fetched when the first position is opened.
+    precisions: Option<HashMap<String, SymbolPrecision>>,
+    /// The number of failed fetches of the precisions in a row.
+    precision_failures: u32,
+    /// The time in milliseconds before which the precisions aren't fetched again after a failure.
+    precision_retry_ts: u64,
 }
 
 impl Account {
@@ -46,7 +72,15 @@ impl Account {
             exchange_api,
             positions: HashMap::new(),
             trades: vec![],
+            orders: vec![],
+            iceberg_orders: vec![],
             dry_run,
+            storage_manager: None,
+            name: String::new(),
+            events: None,
+            precisions: None,
+            precision_failures: 0,
+            precision_retry_ts: 0,
         };
 
         if init_workers {
@@ -77,19 +111,41 @@ impl Account {
         margin_usd: f64,
         leverage: u32,
         order_side: OrderSide,
-        open_price: f64,
+        open_price: Price,
         strategy_id: Option<StrategyId>,
-        stop_loss: Option<f64>,
+        stop_loss: Option<Price>,
     ) -> Option<&mut Position> {
-        if let Ok(mut position) = self
+        let precision = self.symbol_precision(symbol).await;
+        let result = self
             .exchange_api
             .clone()
-            .open_position(symbol, margin_usd, leverage, order_side, open_price)
-            .await
-        {
+            .open_position(
+                symbol, margin_usd, leverage, order_side, open_price, precision,
+            )
+            .await;
+        if let Err(e) = &result {
+            self.publish(BotEvent::Error {
+                source: self.name.clone(),
+                message: format!("Unable to open {order_side} position of {symbol}, e: {e}"),
+            });
+        }
+
+        if let Ok(mut position) = result {
             position.set_stop_loss(stop_loss);
             position.set_strategy_id(strategy_id);
             let position_id = position.id;
+
+            let order = OrderRecord::open(&position);
+            self.persist_open(&position, &order).await;
+            self.publish(BotEvent::OrderFilled {
+                account: self.name.clone(),
+                order: order.clone(),
+            });
+            self.publish(BotEvent::PositionOpened {
+                account: self.name.clone(),
+                position: position.clone(),
+            });
+            self.orders.push(order);
             // insert new position into account positions
             self.positions.insert(position.id, position);
 
@@ -113,16 +169,35 @@ impl Account {
     pub async fn close_position(
         &mut self,
         position_id: PositionId,
-        close_price: f64,
+        close_price: Price,
     ) -> Option<&TradeTx> {
         if let Some(position) = self.positions.get(&position_id).cloned() {
-            if let Ok(trade_tx) = self
+            let result = self
                 .exchange_api
                 .close_position(position.clone(), close_price)
-                .await
-            {
+                .await;
+            if let Err(e) = &result {
+                self.publish(BotEvent::Error {
+                    source: self.name.clone(),
+                    message: format!("Unable to close position {position_id}, e: {e}"),
+                });
+            }
+
+            if let Ok(trade_tx) = result {
                 self.positions.remove(&position.id);
 
+                let order = OrderRecord::close(&trade_tx);
+                self.persist_close(&trade_tx, &order).await;
+                self.publish(BotEvent::OrderFilled {
+                    account: self.name.clone(),
+                    order: order.clone(),
+                });
+                self.publish(BotEvent::PositionClosed {
+                    account: self.name.clone(),
+                    trade: trade_tx.clone(),
+                });
+                self.orders.push(order);
+
                 let trade_tx_id = trade_tx.id;
 
                 self.trades.push(trade_tx);
@@ -146,6 +221,15 @@ impl Account {
         self.positions.values()
     }
 
+    /// Returns an iterator over the account's positions that allows modifying them.
+    ///
+    /// # Returns
+    ///
+    /// An iterator yielding mutable references to positions.
+    pub fn positions_mut(&mut self) -> ValuesMut<'_, PositionId, Position> {
+        self.positions.values_mut()
+    }
+
     /// Returns a clone of the list of trade transactions.
     ///
     /// # Returns
@@ -156,6 +240,15 @@ impl Account {
         self.trades.clone()
     }
 
+    /// Returns an iterator over the account's trade transactions that allows modifying them.
+    ///
+    /// # Returns
+    ///
+    /// An iterator yielding mutable references to trade transactions.
+    pub fn trades_mut(&mut self) -> IterMut<'_, TradeTx> {
+        self.trades.iter_mut()
+    }
+
     /// Returns positions and trades associated with a specific strategy ID.
     ///
     /// # Parameters
@@ -221,11 +314,11 @@ impl Account {
     ///
     /// A vector containing references to trade transactions associated with the strategy.
 
-    pub fn strategy_trades(&self, _strategy_id: StrategyId) -> Vec<&TradeTx> {
+    pub fn strategy_trades(&self, strategy_id: StrategyId) -> Vec<&TradeTx> {
         let mut trades = vec![];
         for trade in &self.trades {
-            if let Some(strategy_id) = trade.position.strategy_id {
-                if strategy_id == strategy_id {
+            if let Some(trade_strategy_id) = trade.position.strategy_id {
+                if trade_strategy_id == strategy_id {
                     trades.push(trade)
                 }
             }
@@ -253,6 +346,157 @@ impl Account {
     pub fn set_exchange_api(&mut self, api: Arc<Box<dyn ExchangeApi>>, dry_run: bool) {
         self.dry_run = dry_run;
         self.exchange_api = api;
+        self.precisions = None;
+        self.precision_failures = 0;
+        self.precision_retry_ts = 0;
+    }
+
+    /// Persists positions, trades and orders of the account to a storage from now on, restoring
+    /// the history saved under the same name so it survives restarts.
+    ///
+    /// # Parameters
+    ///
+    /// * `storage_manager` - The storage to persist the history to.
+    /// * `name` - The name the history is stored under, e.g. `live` or `shadow`.
+    ///
+    /// # Returns
+    ///
+    /// An error if the history can't be restored, the account isn't persisted then.
+    pub async fn set_storage_manager(
+        &mut self,
+        storage_manager: Arc<Box<dyn StorageManager>>,
+        name: &str,
+    ) -> StorageResult<()> {
+        let positions = storage_manager.list_positions(name).await?;
+        let trades = storage_manager.list_trade_txs(name).await?;
+        let orders = storage_manager.list_orders(name).await?;
+
+        self.positions = positions
+            .into_iter()
+            .map(|position| (position.id, position))
+            .collect();
+        self.trades = trades;
+        self.orders = orders;
+        self.storage_manager = Some(storage_manager);
+        self.name = name.to_string();
+
+        Ok(())
+    }
+
+    /// Writes every open position to storage again, so exits moved since they were opened and
+    /// positions whose earlier saves failed are persisted before the bot shuts down.
+    ///
+    /// # Returns
+    ///
+    /// An error naming the positions that couldn't be written.
+    pub async fn persist_positions(&self) -> StorageResult<()> {
+        let Some(storage_manager) = &self.storage_manager else {
+            return Ok(());
+        };
+
+        let mut errors = vec![];
+        for position in self.positions.values() {
+            if let Err(e) = storage_manager
+                .save_position(&self.name, position.clone())
+                .await
+            {
+                errors.push(format!("{}: {e}", position.id));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(StorageError::Backend(format!(
+                "Unable to save positions of {}: {}",
+                self.name,
+                errors.join(", ")
+            )))
+        }
+    }
+
+    /// Replaces the open positions, e.g. by those of a snapshot, and writes them to storage.
+    /// Stored positions which aren't open anymore are removed from it.
+    pub async fn replace_positions(&mut self, positions: Vec<Position>) -> StorageResult<()> {
+        let removed: Vec<PositionId> = self
+            .positions
+            .keys()
+            .filter(|id| !positions.iter().any(|position| position.id == **id))
+            .copied()
+            .collect();
+        self.positions = positions
+            .into_iter()
+            .map(|position| (position.id, position))
+            .collect();
+
+        if let Some(storage_manager) = &self.storage_manager {
+            for position_id in removed {
+                storage_manager
+                    .remove_position(&self.name, position_id)
+                    .await?;
+            }
+        }
+
+        self.persist_positions().await
+    }
+
+    /// Splits positions, e.g. those of a snapshot, into the ones the exchange still holds and
+    /// the ones closed since. Dry run accounts don't trade on the exchange, so they hold every
+    /// position.
+    ///
+    /// # Arguments
+    ///
+    /// * `positions` - The positions to reconcile.
+    ///
+    /// # Returns
+    ///
+    /// The held and the closed positions, or an error if the positions of the exchange can't be
+    /// listed.
+    pub async fn reconcile_positions(
+        &self,
+        positions: Vec<Position>,
+    ) -> ApiResult<(Vec<Position>, Vec<Position>)> {
+        if self.dry_run {
+            return Ok((positions, vec![]));
+        }
+
+        let held = self.exchange_api.open_positions().await?;
+        Ok(split_held_positions(positions, &held))
+    }
+
+    /// Publishes opened and closed positions, tagged with the name of the account.
+    pub fn set_event_publisher(&mut self, events: EventPublisher) {
+        self.events = Some(events);
+    }
+
+    /// Returns the orders filled to open and close positions.
+    ///
+    /// # Returns
+    ///
+    /// A slice of the account's orders, oldest first.
+    pub fn orders(&self) -> &[OrderRecord] {
+        &self.orders
+    }
+
+    /// Returns the iceberg orders, working or ended, whose filled slices are among the orders.
+    ///
+    /// # Returns
+    ///
+    /// A slice of the account's iceberg orders, oldest first.
+    pub fn iceberg_orders(&self) -> &[IcebergOrder] {
+        &self.iceberg_orders
+    }
+
+    /// Adds an iceberg order, which is worked by the execution of its signal.
+    pub fn place_iceberg_order(&mut self, order: IcebergOrder) {
+        self.iceberg_orders.push(order);
+    }
+
+    /// Retrieves an iceberg order to record its fills or end it.
+    pub fn iceberg_order_mut(&mut self, order_id: &Uuid) -> Option<&mut IcebergOrder> {
+        self.iceberg_orders
+            .iter_mut()
+            .find(|order| order.id == *order_id)
     }
 
     /// Retrieves account information.
@@ -285,6 +529,262 @@ impl Account {
         self.positions.get(position_id)
     }
 
+    /// Samples the equity of the account, marking open positions at the last market prices.
+    ///
+    /// # Arguments
+    ///
+    /// * `market` - The market the last prices are read from, positions of symbols without a
+    ///   price are left out of the unrealized profit.
+    ///
+    /// # Returns
+    ///
+    /// The `AccountEquity` at the current time.
+    pub async fn equity(&self, market: Arc<Market>) -> AccountEquity {
+        let realized_profit = self.trades.iter().map(|trade| trade.calc_profit()).sum();
+        let unrealized_profit = self
+            .position_pnls(market)
+            .await
+            .iter()
+            .map(|pnl| pnl.unrealized_profit)
+            .sum();
+
+        AccountEquity {
+            timestamp: generate_ts(),
+            realized_profit,
+            unrealized_profit,
+            open_positions: self.positions.len(),
+        }
+    }
+
+    /// Summarizes the trading activity of the account over a period.
+    ///
+    /// # Arguments
+    ///
+    /// * `market` - The market open positions are marked at.
+    /// * `from_ts` - Start of the period in milliseconds.
+    /// * `to_ts` - End of the period in milliseconds.
+    ///
+    /// # Returns
+    ///
+    /// The `TradingSummary` of the trades closed during the period and the open positions.
+    pub async fn trading_summary(
+        &self,
+        market: Arc<Market>,
+        from_ts: u64,
+        to_ts: u64,
+    ) -> TradingSummary {
+        let trades: Vec<&TradeTx> = self
+            .trades
+            .iter()
+            .filter(|trade| {
+                string_to_timestamp(&trade.close_time)
+                    .is_ok_and(|close_ts| close_ts >= from_ts && close_ts <= to_ts)
+            })
+            .collect();
+        let equity = self.equity(market).await;
+
+        TradingSummary {
+            from_ts,
+            to_ts,
+            trades: trades.len(),
+            winning_trades: trades
+                .iter()
+                .filter(|trade| trade.calc_profit() > 0.0)
+                .count(),
+            realized_profit: trades.iter().map(|trade| trade.calc_profit()).sum(),
+            unrealized_profit: equity.unrealized_profit,
+            open_positions: equity.open_positions,
+        }
+    }
+
+    /// Changes the stop loss and take profit of an open position.
+    ///
+    /// # Parameters
+    ///
+    /// * `position_id` - The ID of the position.
+    /// * `stop_loss` - The new stop loss price, `None` removes it.
+    /// * `take_profit` - The new take profit price, `None` removes it.
+    ///
+    /// # Returns
+    ///
+    /// The updated position, `None` if no position has the ID.
+    pub async fn set_position_exits(
+        &mut self,
+        position_id: PositionId,
+        stop_loss: Option<Price>,
+        take_profit: Option<Price>,
+    ) -> Option<&Position> {
+        let position = self.positions.get_mut(&position_id)?;
+        position.set_stop_loss(stop_loss);
+        position.set_take_profit(take_profit);
+
+        if let Some(storage_manager) = &self.storage_manager {
+            if let Err(e) = storage_manager
+                .save_position(&self.name, position.clone())
+                .await
+            {
+                info!("Unable to save position {position_id}, e: {e}");
+            }
+        }
+
+        self.positions.get(&position_id)
+    }
+
+    /// Closes the positions whose stop loss or take profit the last market price reached.
+    ///
+    /// # Parameters
+    ///
+    /// * `market` - The market the last prices are read from.
+    ///
+    /// # Returns
+    ///
+    /// The trade transactions of the closed positions.
+    pub async fn close_exit_hit_positions(&mut self, market: Arc<Market>) -> Vec<TradeTx> {
+        let mut hit_positions = vec![];
+        for position in self.positions.values() {
+            if let Some(price) = market.last_price(&position.symbol).await {
+                let price = Price::from_f64(price);
+                if position.is_stop_loss_hit(price) || position.is_take_profit_hit(price) {
+                    hit_positions.push((position.id, price));
+                }
+            }
+        }
+
+        let mut trades = vec![];
+        for (position_id, price) in hit_positions {
+            info!("Closing position {position_id}, stop loss or take profit reached at {price}");
+            if let Some(trade) = self.close_position(position_id, price).await {
+                trades.push(trade.clone());
+            }
+        }
+
+        trades
+    }
+
+    /// Closes every open position at the last market price.
+    ///
+    /// # Parameters
+    ///
+    /// * `market` - The market the last prices are read from, positions of symbols without a
+    ///   price stay open.
+    ///
+    /// # Returns
+    ///
+    /// The trade transactions of the closed positions.
+    pub async fn close_all_positions(&mut self, market: Arc<Market>) -> Vec<TradeTx> {
+        let positions: Vec<Position> = self.positions.values().cloned().collect();
+
+        let mut trades = vec![];
+        for position in positions {
+            let price = market.last_price(&position.symbol).await;
+            match price {
+                Some(price) => {
+                    if let Some(trade) = self.close_position(position.id, price.into()).await {
+                        trades.push(trade.clone());
+                    }
+                }
+                None => info!(
+                    "Unable to close position {}, last price of {} not found",
+                    position.id, position.symbol
+                ),
+            }
+        }
+
+        trades
+    }
+
+    /// Cancels the working orders of a symbol on the exchange of the account.
+    ///
+    /// # Parameters
+    ///
+    /// * `symbol` - The symbol whose orders are cancelled.
+    ///
+    /// # Returns
+    ///
+    /// The response of the exchange, or an error if the orders couldn't be cancelled.
+    pub async fn cancel_all_orders(&self, symbol: &str) -> ApiResult<serde_json::Value> {
+        self.exchange_api.cancel_all_orders(symbol).await
+    }
+
+    /// Marks the open positions at the last market prices.
+    ///
+    /// # Arguments
+    ///
+    /// * `market` - The market the last prices are read from, positions of symbols without a
+    ///   price are left out.
+    ///
+    /// # Returns
+    ///
+    /// The unrealized profit of each position, oldest position first.
+    pub async fn position_pnls(&self, market: Arc<Market>) -> Vec<PositionPnl> {
+        let mut pnls = vec![];
+        for position in self.positions.values() {
+            if let Some(price) = market.last_price(&position.symbol).await {
+                pnls.push(PositionPnl {
+                    position_id: position.id,
+                    symbol: position.symbol.clone(),
+                    strategy_id: position.strategy_id,
+                    open_time: position.open_time.clone(),
+                    last_price: price,
+                    unrealized_profit: position.calc_unrealized_profit(price.into()),
+                });
+            }
+        }
+        pnls.sort_by(|a, b| a.open_time.cmp(&b.open_time));
+
+        pnls
+    }
+
+    /// Fetches the balance of the exchange account, `None` in dry run mode as no exchange
+    /// account is traded on, or if the exchange can't be reached.
+    pub async fn balance(&self) -> Option<f64> {
+        if self.dry_run {
+            return None;
+        }
+
+        match self.exchange_api.get_account_balance().await {
+            Ok(balance) => Some(balance),
+            Err(e) => {
+                info!("Unable to get account balance, e: {e:?}");
+                None
+            }
+        }
+    }
+
+    /// Lists a page of the trade transactions, most recently closed first.
+    ///
+    /// # Arguments
+    ///
+    /// * `strategy_id` - Only list the trades of this strategy, if given.
+    /// * `offset` - The number of trades skipped.
+    /// * `limit` - The maximum number of trades listed, all if not given.
+    ///
+    /// # Returns
+    ///
+    /// The trades of the page and the number of trades matching the strategy.
+    pub fn trade_page(
+        &self,
+        strategy_id: Option<StrategyId>,
+        offset: usize,
+        limit: Option<usize>,
+    ) -> (Vec<TradeTx>, usize) {
+        let trades: Vec<&TradeTx> = self
+            .trades
+            .iter()
+            .rev()
+            .filter(|trade| strategy_id.is_none() || trade.position.strategy_id == strategy_id)
+            .collect();
+        let total = trades.len();
+
+        let page = trades.into_iter().skip(offset).cloned();
+        let page = match limit {
+            Some(limit) => page.take(limit).collect(),
+            None => page.collect(),
+        };
+
+        (page, total)
+    }
+
     // ---
     // Private Methods
     // ---
@@ -293,6 +793,83 @@ impl Account {
     async fn init(&self) {
         // start any worker threads for account
     }
+
+    /// The precision a symbol is traded at, fetched from the exchange once. Symbols the exchange
+    /// doesn't list, or every symbol while the exchange can't be reached, use the default.
+    ///
+    /// A failed fetch isn't retried before `precision_retry_delay`, so opens don't each wait on
+    /// the full exchange info while the account is locked.
+    async fn symbol_precision(&mut self, symbol: &str) -> SymbolPrecision {
+        if self.precisions.is_none() && generate_ts() >= self.precision_retry_ts {
+            match self.exchange_api.info().await {
+                Ok(info) => {
+                    self.precisions = Some(
+                        info.symbols
+                            .iter()
+                            .map(|info| (info.symbol.clone(), SymbolPrecision::from(info)))
+                            .collect(),
+                    );
+                    self.precision_failures = 0;
+                }
+                Err(e) => {
+                    self.precision_failures += 1;
+                    let delay = precision_retry_delay(self.precision_failures);
+                    self.precision_retry_ts = generate_ts() + delay;
+                    info!(
+                        "Unable to fetch the precision of {symbol}, retrying in {}s, e: {e}",
+                        delay / SEC_AS_MILI
+                    );
+                }
+            }
+        }
+
+        self.precisions
+            .as_ref()
+            .and_then(|precisions| precisions.get(symbol).copied())
+            .unwrap_or_default()
+    }
+
+    fn publish(&self, event: BotEvent) {
+        if let Some(events) = &self.events {
+            events.publish(event);
+        }
+    }
+
+    /// Persists an opened position and its order, failing to persist never fails the order.
+    async fn persist_open(&self, position: &Position, order: &OrderRecord) {
+        if let Some(storage_manager) = &self.storage_manager {
+            if let Err(e) = storage_manager
+                .save_position(&self.name, position.clone())
+                .await
+            {
+                info!("Unable to save position {}, e: {e}", position.id);
+            }
+            if let Err(e) = storage_manager.save_order(&self.name, order.clone()).await {
+                info!("Unable to save order {}, e: {e}", order.id);
+            }
+        }
+    }
+
+    /// Persists a closed position's trade transaction and order and removes the position.
+    async fn persist_close(&self, trade_tx: &TradeTx, order: &OrderRecord) {
+        if let Some(storage_manager) = &self.storage_manager {
+            if let Err(e) = storage_manager
+                .save_trade_tx(&self.name, trade_tx.clone())
+                .await
+            {
+                info!("Unable to save trade {}, e: {e}", trade_tx.id);
+            }
+            if let Err(e) = storage_manager
+                .remove_position(&self.name, trade_tx.position.id)
+                .await
+            {
+                info!("Unable to remove position {}, e: {e}", trade_tx.position.id);
+            }
+            if let Err(e) = storage_manager.save_order(&self.name, order.clone()).await {
+                info!("Unable to save order {}, e: {e}", order.id);
+            }
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -303,9 +880,96 @@ pub struct AccountInfo {
     trade_transactions: Vec<TradeTx>,
 }
 
+/// A sample of an account's equity, relative to the capital it started with.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountEquity {
+    pub timestamp: u64,
+    /// The profit of closed trades.
+    pub realized_profit: f64,
+    /// The profit open positions would realize if closed at the last price.
+    pub unrealized_profit: f64,
+    pub open_positions: usize,
+}
+
+impl AccountEquity {
+    pub fn equity(&self) -> f64 {
+        self.realized_profit + self.unrealized_profit
+    }
+}
+
+/// The trading activity of an account over a period, such as the last day.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TradingSummary {
+    pub from_ts: u64,
+    pub to_ts: u64,
+    /// Trades closed during the period.
+    pub trades: usize,
+    pub winning_trades: usize,
+    /// The profit of the trades closed during the period.
+    pub realized_profit: f64,
+    /// The profit open positions would realize if closed at the last price.
+    pub unrealized_profit: f64,
+    pub open_positions: usize,
+}
+
+/// Splits positions into the ones held on an exchange and the others. The exchange nets the
+/// positions of a symbol and side, so positions are held while their quantities add up to at
+/// most the quantity held on the exchange.
+///
+/// # Arguments
+///
+/// * `positions` - The positions to split.
+/// * `held` - The positions held on the exchange.
+///
+/// # Returns
+///
+/// The held and the other positions.
+fn split_held_positions(
+    positions: Vec<Position>,
+    held: &[ExchangePosition],
+) -> (Vec<Position>, Vec<Position>) {
+    let mut held_quantities: HashMap<(String, OrderSide), f64> = HashMap::new();
+    for position in held {
+        *held_quantities
+            .entry((position.symbol.clone(), position.order_side))
+            .or_default() += position.quantity;
+    }
+
+    positions.into_iter().partition(|position| {
+        let quantity = position.quantity.to_f64();
+        match held_quantities.get_mut(&(position.symbol.clone(), position.order_side)) {
+            // quantities are parsed from decimals, allow for the rounding of their sum
+            Some(held_quantity) if *held_quantity >= quantity - 1e-9 => {
+                *held_quantity -= quantity;
+                true
+            }
+            _ => false,
+        }
+    })
+}
+
+/// The delay before fetching the precisions again after failures in a row, doubling from 15
+/// seconds up to 10 minutes.
+fn precision_retry_delay(failures: u32) -> u64 {
+    ((15 * SEC_AS_MILI) << failures.saturating_sub(1).min(6)).min(10 * MIN_AS_MILI)
+}
+
+/// The unrealized profit of an open position at the last market price.
+#[derive(Debug, Clone, Serialize)]
+pub struct PositionPnl {
+    pub position_id: PositionId,
+    pub symbol: String,
+    pub strategy_id: Option<StrategyId>,
+    pub open_time: String,
+    pub last_price: f64,
+    pub unrealized_profit: f64,
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    #[cfg(feature = "object-store")]
+    use crate::storage::object_store::ObjectStoreStorage;
     use crate::utils::number::generate_random_id;
     use crate::{
         account::trade::OrderSide,
@@ -321,7 +985,15 @@ mod test {
 
         // Open a position
         let position = account
-            .open_position("BTCUSD", 1000.0, 10, OrderSide::Buy, 50000.0, None, None)
+            .open_position(
+                "BTCUSD",
+                1000.0,
+                10,
+                OrderSide::Buy,
+                50000.0.into(),
+                None,
+                None,
+            )
             .await
             .unwrap();
 
@@ -340,22 +1012,176 @@ mod test {
 
         // Open a position
         let position = account
-            .open_position("BTCUSD", 1000.0, 10, OrderSide::Buy, 50000.0, None, None)
+            .open_position(
+                "BTCUSD",
+                1000.0,
+                10,
+                OrderSide::Buy,
+                50000.0.into(),
+                None,
+                None,
+            )
             .await
             .unwrap();
 
         let position = position.clone();
 
-        let trade_tx = account.close_position(position.id, 55000.0).await.unwrap();
+        let trade_tx = account
+            .close_position(position.id, 55000.0.into())
+            .await
+            .unwrap();
         let trade_tx = trade_tx.clone();
 
-        assert_eq!(trade_tx.close_price, 55000.0);
+        assert_eq!(trade_tx.close_price, 55000.0.into());
         assert_eq!(account.positions.len(), 0);
         assert_eq!(account.trades.len(), 1);
         assert_eq!(account.trades[0].id, trade_tx.id);
         // Close the opened position
     }
 
+    /// Tests trades are filtered by the strategy of their position.
+    #[test]
+    async fn test_strategy_trades() {
+        let exchange_api: Arc<Box<dyn ExchangeApi>> = Arc::new(Box::new(MockExchangeApi {}));
+        let mut account = Account::new(exchange_api.clone(), false, true).await;
+        let (strategy_a, strategy_b) = (Uuid::new_v4(), Uuid::new_v4());
+
+        for strategy_id in [Some(strategy_a), Some(strategy_b), Some(strategy_b), None] {
+            let position_id = account
+                .open_position(
+                    "BTCUSD",
+                    1000.0,
+                    10,
+                    OrderSide::Buy,
+                    50000.0.into(),
+                    strategy_id,
+                    None,
+                )
+                .await
+                .unwrap()
+                .id;
+            account.close_position(position_id, 55000.0.into()).await;
+        }
+
+        assert_eq!(account.strategy_trades(strategy_a).len(), 1);
+        assert_eq!(account.strategy_trades(strategy_b).len(), 2);
+        assert!(account.strategy_trades(Uuid::new_v4()).is_empty());
+    }
+
+    #[test]
+    async fn test_trade_page() {
+        let exchange_api: Arc<Box<dyn ExchangeApi>> = Arc::new(Box::new(MockExchangeApi {}));
+        let mut account = Account::new(exchange_api.clone(), false, true).await;
+        let strategy_id = Uuid::new_v4();
+
+        let mut closed_ids = vec![];
+        for (price, position_strategy_id) in [
+            (100.0, Some(strategy_id)),
+            (200.0, None),
+            (300.0, Some(strategy_id)),
+        ] {
+            let position_id = account
+                .open_position(
+                    "BTCUSD",
+                    100.0,
+                    1,
+                    OrderSide::Buy,
+                    price.into(),
+                    position_strategy_id,
+                    None,
+                )
+                .await
+                .unwrap()
+                .id;
+            account
+                .close_position(position_id, price.into())
+                .await
+                .unwrap();
+            closed_ids.push(position_id);
+        }
+
+        // most recently closed first
+        let (page, total) = account.trade_page(None, 0, Some(2));
+        assert_eq!(total, 3);
+        let page_ids: Vec<PositionId> = page.iter().map(|trade| trade.position.id).collect();
+        assert_eq!(page_ids, vec![closed_ids[2], closed_ids[1]]);
+
+        let (page, total) = account.trade_page(Some(strategy_id), 1, None);
+        assert_eq!(total, 2);
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].position.id, closed_ids[0]);
+    }
+
+    #[cfg(feature = "object-store")]
+    #[test]
+    async fn test_history_survives_restart() {
+        let exchange_api: Arc<Box<dyn ExchangeApi>> = Arc::new(Box::new(MockExchangeApi {}));
+        let storage_manager: Arc<Box<dyn StorageManager>> = Arc::new(Box::new(
+            ObjectStoreStorage::new("memory:///raderbot").unwrap(),
+        ));
+
+        let mut account = Account::new(exchange_api.clone(), false, true).await;
+        account
+            .set_storage_manager(storage_manager.clone(), "live")
+            .await
+            .unwrap();
+
+        let closed_id = account
+            .open_position(
+                "BTCUSD",
+                1000.0,
+                10,
+                OrderSide::Buy,
+                50000.0.into(),
+                None,
+                None,
+            )
+            .await
+            .unwrap()
+            .id;
+        let open_id = account
+            .open_position(
+                "ETHUSD",
+                500.0,
+                5,
+                OrderSide::Sell,
+                2000.0.into(),
+                None,
+                None,
+            )
+            .await
+            .unwrap()
+            .id;
+        account
+            .close_position(closed_id, 55000.0.into())
+            .await
+            .unwrap();
+
+        // a new account restores the history saved under the same name only
+        let mut restored = Account::new(exchange_api.clone(), false, true).await;
+        restored
+            .set_storage_manager(storage_manager.clone(), "live")
+            .await
+            .unwrap();
+
+        assert_eq!(restored.positions.len(), 1);
+        assert!(restored.get_position(&open_id).is_some());
+        assert_eq!(restored.trades.len(), 1);
+        assert_eq!(restored.trades[0].position.id, closed_id);
+        assert_eq!(restored.orders().len(), 3);
+        assert!(account
+            .orders()
+            .iter()
+            .all(|order| restored.orders().contains(order)));
+
+        let mut shadow = Account::new(exchange_api, false, true).await;
+        shadow
+            .set_storage_manager(storage_manager, "shadow")
+            .await
+            .unwrap();
+        assert!(shadow.positions.is_empty() && shadow.orders().is_empty());
+    }
+
     #[test]
     async fn test_close_multiple_positions() {
         let exchange_api: Arc<Box<dyn ExchangeApi>> = Arc::new(Box::new(MockExchangeApi {}));
@@ -380,7 +1206,13 @@ mod test {
 
             let position = account
                 .open_position(
-                    symbol, margin_usd, leverage, order_side, open_price, None, None,
+                    symbol,
+                    margin_usd,
+                    leverage,
+                    order_side,
+                    open_price.into(),
+                    None,
+                    None,
                 )
                 .await
                 .unwrap();
@@ -429,7 +1261,15 @@ mod test {
 
         // Open a position
         account
-            .open_position("BTCUSD", 1000.0, 10, OrderSide::Buy, 50000.0, None, None)
+            .open_position(
+                "BTCUSD",
+                1000.0,
+                10,
+                OrderSide::Buy,
+                50000.0.into(),
+                None,
+                None,
+            )
             .await
             .unwrap();
 
@@ -458,7 +1298,7 @@ mod test {
                 1000.0,
                 10,
                 OrderSide::Buy,
-                50000.0,
+                50000.0.into(),
                 Some(strategy_id_1),
                 None,
             )
@@ -472,7 +1312,7 @@ mod test {
                 500.0,
                 5,
                 OrderSide::Sell,
-                2000.0,
+                2000.0.into(),
                 Some(strategy_id_1),
                 None,
             )
@@ -487,7 +1327,7 @@ mod test {
                 200.0,
                 2,
                 OrderSide::Buy,
-                48000.0,
+                48000.0.into(),
                 Some(strategy_id_2),
                 None,
             )
@@ -498,7 +1338,7 @@ mod test {
 
         // Close one position to test if it doesn't appear in the strategy_positions
         account
-            .close_position(position_1_id, 51000.0)
+            .close_position(position_1_id, 51000.0.into())
             .await
             .unwrap();
 
@@ -524,4 +1364,56 @@ mod test {
         assert!(open_positions_strategy_1.contains(&position_2_id));
         assert!(open_positions_strategy_2.contains(&position_3_id));
     }
+
+    /// Tests positions are only held while the exchange holds their symbol and side in at
+    /// least their quantity.
+    #[test]
+    async fn test_split_held_positions() {
+        let position = |symbol: &str, order_side: OrderSide| {
+            Position::new(
+                symbol,
+                100.0.into(),
+                order_side,
+                100.0,
+                1,
+                None,
+                Default::default(),
+            )
+        };
+        let positions = vec![
+            position("BTCUSDT", OrderSide::Buy),
+            position("BTCUSDT", OrderSide::Buy),
+            position("ETHUSDT", OrderSide::Sell),
+        ];
+        let held = vec![
+            ExchangePosition {
+                symbol: "BTCUSDT".to_string(),
+                order_side: OrderSide::Buy,
+                quantity: 1.5,
+            },
+            ExchangePosition {
+                symbol: "ETHUSDT".to_string(),
+                order_side: OrderSide::Buy,
+                quantity: 1.0,
+            },
+        ];
+
+        let (kept, closed) = split_held_positions(positions.clone(), &held);
+        assert_eq!(kept, vec![positions[0].clone()]);
+        assert_eq!(closed, vec![positions[1].clone(), positions[2].clone()]);
+
+        let (kept, closed) = split_held_positions(positions.clone(), &[]);
+        assert!(kept.is_empty());
+        assert_eq!(closed.len(), 3);
+    }
+
+    /// Tests failed precision fetches back off exponentially up to a cap.
+    #[test]
+    async fn test_precision_retry_delay() {
+        assert_eq!(precision_retry_delay(1), 15 * SEC_AS_MILI);
+        assert_eq!(precision_retry_delay(2), 30 * SEC_AS_MILI);
+        assert_eq!(precision_retry_delay(6), 8 * MIN_AS_MILI);
+        assert_eq!(precision_retry_delay(7), 10 * MIN_AS_MILI);
+        assert_eq!(precision_retry_delay(u32::MAX), 10 * MIN_AS_MILI);
+    }
 }
@@ -0,0 +1,376 @@
+//! # Daily Report
+//!
+//! The profit and activity of every strategy over the last day: closed trades, win rate, realized
+//! and unrealized profit, estimated fees and the notable events of the day, such as errors and
+//! strategies started or stopped. Reports are created by the `daily_report` scheduled job, saved
+//! to storage and published on the event bus for the notifiers.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use rust_decimal::prelude::ToPrimitive;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+use uuid::Uuid;
+
+use crate::account::trade::{Position, PositionId, TradeTx};
+use crate::events::{BotEvent, EventTopic};
+use crate::strategy::strategy::StrategyId;
+use crate::utils::time::{generate_ts, string_to_timestamp};
+
+/// Notable events kept for the next report, older events are dropped first.
+const MAX_NOTABLE_EVENTS: usize = 500;
+
+/// The profit and activity of positions over a period.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Activity {
+    /// Trades closed during the period.
+    pub trades: usize,
+    pub winning_trades: usize,
+    /// Share of the closed trades that were profitable, 0 without trades.
+    pub win_rate: f64,
+    /// The profit of the trades closed during the period, net of funding.
+    pub realized_profit: f64,
+    /// The profit the open positions would realize if closed at the last price.
+    pub unrealized_profit: f64,
+    /// Fees of the fills during the period, estimated from their value and the fee rate.
+    pub fees: f64,
+    /// Funding paid by the trades closed during the period.
+    pub funding_paid: f64,
+    pub open_positions: usize,
+}
+
+impl Activity {
+    fn add(&mut self, other: &Activity) {
+        self.trades += other.trades;
+        self.winning_trades += other.winning_trades;
+        self.realized_profit += other.realized_profit;
+        self.unrealized_profit += other.unrealized_profit;
+        self.fees += other.fees;
+        self.funding_paid += other.funding_paid;
+        self.open_positions += other.open_positions;
+        self.update_win_rate();
+    }
+
+    fn update_win_rate(&mut self) {
+        self.win_rate = match self.trades {
+            0 => 0.0,
+            trades => self.winning_trades as f64 / trades as f64,
+        };
+    }
+}
+
+/// The activity of a strategy on an account, positions opened manually have no strategy.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StrategyActivity {
+    pub account: String,
+    pub strategy_id: Option<StrategyId>,
+    /// The algorithm of the strategy, if it is running.
+    pub strategy_name: Option<String>,
+    #[serde(flatten)]
+    pub activity: Activity,
+}
+
+impl StrategyActivity {
+    /// Collects the activity of every strategy of an account over a period.
+    ///
+    /// # Arguments
+    ///
+    /// * `account` - The name of the account.
+    /// * `trades` - The trades of the account, trades closed outside the period are skipped.
+    /// * `positions` - The open positions of the account.
+    /// * `unrealized` - The unrealized profit of the open positions by id.
+    /// * `(from_ts, to_ts)` - The period.
+    /// * `fee_rate` - The share of the value of a fill paid as fee.
+    ///
+    /// # Returns
+    ///
+    /// The activity of each strategy that traded or holds positions, ordered by strategy id.
+    pub fn collect(
+        account: &str,
+        trades: &[TradeTx],
+        positions: &[Position],
+        unrealized: &HashMap<PositionId, f64>,
+        (from_ts, to_ts): (u64, u64),
+        fee_rate: f64,
+    ) -> Vec<Self> {
+        let notional_fee =
+            |notional: rust_decimal::Decimal| notional.to_f64().unwrap_or(0.0) * fee_rate;
+        let in_period =
+            |time: &str| string_to_timestamp(time).is_ok_and(|ts| ts >= from_ts && ts <= to_ts);
+        let mut activities: BTreeMap<Option<StrategyId>, Activity> = BTreeMap::new();
+
+        for trade in trades.iter().filter(|trade| in_period(&trade.close_time)) {
+            let position = &trade.position;
+            let activity = activities.entry(position.strategy_id).or_default();
+            let profit = trade.calc_profit();
+
+            activity.trades += 1;
+            if profit > 0.0 {
+                activity.winning_trades += 1;
+            }
+            activity.realized_profit += profit;
+            activity.funding_paid += position.funding_paid;
+            activity.fees += notional_fee(trade.close_price.notional(position.quantity));
+            if in_period(&position.open_time) {
+                activity.fees += notional_fee(position.open_price.notional(position.quantity));
+            }
+        }
+
+        for position in positions {
+            let activity = activities.entry(position.strategy_id).or_default();
+            activity.open_positions += 1;
+            activity.unrealized_profit += unrealized.get(&position.id).copied().unwrap_or(0.0);
+            if in_period(&position.open_time) {
+                activity.fees += notional_fee(position.open_price.notional(position.quantity));
+            }
+        }
+
+        activities
+            .into_iter()
+            .map(|(strategy_id, mut activity)| {
+                activity.update_win_rate();
+                Self {
+                    account: account.to_string(),
+                    strategy_id,
+                    strategy_name: None,
+                    activity,
+                }
+            })
+            .collect()
+    }
+}
+
+/// An event worth reading about the next day, such as an error.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NotableEvent {
+    pub timestamp: u64,
+    pub topic: EventTopic,
+    pub message: String,
+}
+
+impl NotableEvent {
+    /// Describes errors, stream failures and started and stopped strategies, `None` for any
+    /// other event.
+    pub fn from_event(event: &BotEvent, timestamp: u64) -> Option<Self> {
+        let message = match event {
+            BotEvent::Error { source, message } => format!("Error on {source}: {message}"),
+            BotEvent::StreamError { stream_id, message } => {
+                format!("Stream {stream_id} failed: {message}")
+            }
+            BotEvent::StrategyStarted(info) => format!(
+                "Started {} on {} {} ({})",
+                info.name, info.symbol, info.interval, info.id
+            ),
+            BotEvent::StrategyStopped(info) => format!(
+                "Stopped {} on {} {} ({})",
+                info.name, info.symbol, info.interval, info.id
+            ),
+            _ => return None,
+        };
+
+        Some(Self {
+            timestamp,
+            topic: event.topic(),
+            message,
+        })
+    }
+}
+
+/// Records the notable events published on the event bus for the reports. Clones share the
+/// same events.
+#[derive(Debug, Clone, Default)]
+pub struct NotableEvents {
+    events: Arc<Mutex<VecDeque<NotableEvent>>>,
+}
+
+impl NotableEvents {
+    /// Records the notable events received until the publisher is dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `events` - A subscription to the bot events.
+    pub fn spawn(mut events: Receiver<BotEvent>) -> Self {
+        let notable_events = Self::default();
+
+        let recorder = notable_events.clone();
+        tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(event) => recorder.record(&event),
+                    Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+
+        notable_events
+    }
+
+    /// Records an event if it is notable.
+    pub fn record(&self, event: &BotEvent) {
+        let Some(event) = NotableEvent::from_event(event, generate_ts()) else {
+            return;
+        };
+
+        let mut events = self.events.lock().unwrap_or_else(|e| e.into_inner());
+        if events.len() == MAX_NOTABLE_EVENTS {
+            events.pop_front();
+        }
+        events.push_back(event);
+    }
+
+    /// Lists the events recorded during a period, oldest first.
+    pub fn between(&self, from_ts: u64, to_ts: u64) -> Vec<NotableEvent> {
+        let events = self.events.lock().unwrap_or_else(|e| e.into_inner());
+        events
+            .iter()
+            .filter(|event| event.timestamp >= from_ts && event.timestamp <= to_ts)
+            .cloned()
+            .collect()
+    }
+}
+
+/// The profit and activity of every strategy over a day.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DailyReport {
+    pub id: Uuid,
+    pub from_ts: u64,
+    pub to_ts: u64,
+    /// The activity of all accounts and strategies together.
+    pub total: Activity,
+    pub strategies: Vec<StrategyActivity>,
+    pub events: Vec<NotableEvent>,
+}
+
+impl DailyReport {
+    pub fn new(
+        from_ts: u64,
+        to_ts: u64,
+        strategies: Vec<StrategyActivity>,
+        events: Vec<NotableEvent>,
+    ) -> Self {
+        let mut total = Activity::default();
+        for strategy in &strategies {
+            total.add(&strategy.activity);
+        }
+
+        Self {
+            id: Uuid::new_v4(),
+            from_ts,
+            to_ts,
+            total,
+            strategies,
+            events,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::account::trade::OrderSide;
+    use crate::strategy::strategy::StrategyInfo;
+    use crate::utils::time::{timestamp_to_string, year_month_day_to_ts, HOUR_AS_MILI};
+
+    fn position(strategy_id: Option<StrategyId>, open_ts: u64) -> Position {
+        Position {
+            id: Uuid::new_v4(),
+            symbol: "BTCUSDT".to_string(),
+            order_side: OrderSide::Buy,
+            open_time: timestamp_to_string(open_ts),
+            open_price: 100.0.into(),
+            quantity: 2.0.into(),
+            margin_usd: 200.0,
+            leverage: 1,
+            strategy_id,
+            stop_loss: None,
+            take_profit: None,
+            funding_paid: 0.0,
+        }
+    }
+
+    fn trade(position: Position, close_ts: u64, close_price: f64) -> TradeTx {
+        TradeTx {
+            id: Uuid::new_v4(),
+            close_time: timestamp_to_string(close_ts),
+            close_price: close_price.into(),
+            position,
+        }
+    }
+
+    /// Tests activity is collected per strategy within the period and summed up by the report.
+    #[test]
+    fn test_daily_report() {
+        let from_ts = year_month_day_to_ts(2024, 1, 1).unwrap();
+        let to_ts = year_month_day_to_ts(2024, 1, 2).unwrap();
+        let strategy_id = Some(Uuid::new_v4());
+
+        let trades = vec![
+            trade(
+                position(strategy_id, from_ts + HOUR_AS_MILI),
+                from_ts + 2 * HOUR_AS_MILI,
+                110.0,
+            ),
+            trade(
+                position(strategy_id, from_ts - HOUR_AS_MILI),
+                from_ts + 3 * HOUR_AS_MILI,
+                95.0,
+            ),
+            // closed the day before
+            trade(
+                position(None, from_ts - 3 * HOUR_AS_MILI),
+                from_ts - HOUR_AS_MILI,
+                120.0,
+            ),
+        ];
+        let open = position(None, from_ts + 4 * HOUR_AS_MILI);
+        let unrealized = HashMap::from([(open.id, 30.0)]);
+
+        let strategies = StrategyActivity::collect(
+            "live",
+            &trades,
+            &[open],
+            &unrealized,
+            (from_ts, to_ts),
+            0.001,
+        );
+        assert_eq!(strategies.len(), 2);
+        let manual = &strategies[0].activity;
+        assert_eq!(strategies[0].strategy_id, None);
+        assert_eq!(manual.trades, 0);
+        assert_eq!(manual.open_positions, 1);
+        assert_eq!(manual.unrealized_profit, 30.0);
+
+        let activity = &strategies[1].activity;
+        assert_eq!(activity.trades, 2);
+        assert_eq!(activity.win_rate, 0.5);
+        assert_eq!(activity.realized_profit, 10.0);
+        // both fills of the first trade, the close of the second
+        assert!((activity.fees - (0.2 + 0.22 + 0.19)).abs() < 1e-9);
+
+        let error = BotEvent::Error {
+            source: "live".to_string(),
+            message: "Order rejected".to_string(),
+        };
+        let started = BotEvent::StrategyStarted(StrategyInfo::default());
+        let notable_events = NotableEvents::default();
+        notable_events.record(&error);
+        notable_events.record(&started);
+        notable_events.record(&BotEvent::Error {
+            source: "shadow".to_string(),
+            message: "Order rejected".to_string(),
+        });
+        let events = notable_events.between(0, generate_ts());
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].message, "Error on live: Order rejected");
+        assert_eq!(events[1].topic, EventTopic::Strategies);
+
+        let report = DailyReport::new(from_ts, to_ts, strategies, events);
+        assert_eq!(report.total.trades, 2);
+        assert_eq!(report.total.open_positions, 1);
+        assert_eq!(report.total.realized_profit, 10.0);
+        assert_eq!(report.total.unrealized_profit, 30.0);
+        assert_eq!(report.total.win_rate, 0.5);
+    }
+}
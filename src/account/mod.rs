@@ -1,2 +1,5 @@
 pub mod account;
+pub mod daily_report;
+pub mod money;
+pub mod router;
 pub mod trade;
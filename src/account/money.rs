@@ -0,0 +1,162 @@
+//! # Prices And Quantities
+//!
+//! Exact decimal prices and quantities of positions, trades and orders. Market data comes in as
+//! `f64` and is converted once at the account, after which sizing, exits and profits are worked
+//! out without the rounding drift of floating point math. Both types are serialized as plain
+//! JSON numbers, so stored positions and trades read the same as before.
+
+use std::fmt;
+
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Serialize};
+
+use crate::exchange::api::SymbolInfo;
+
+/// Decimal places of symbols whose precision isn't known.
+pub const DEFAULT_PRECISION: u32 = 8;
+
+macro_rules! decimal_type {
+    ($name:ident) => {
+        impl $name {
+            pub const ZERO: Self = Self(Decimal::ZERO);
+
+            /// Converts a floating point value, e.g. of market data, to its shortest decimal
+            /// representation. Values that aren't finite become zero.
+            pub fn from_f64(value: f64) -> Self {
+                Self(Decimal::from_f64(value).unwrap_or_default())
+            }
+
+            pub fn to_f64(self) -> f64 {
+                self.0.to_f64().unwrap_or_default()
+            }
+        }
+
+        impl From<f64> for $name {
+            fn from(value: f64) -> Self {
+                Self::from_f64(value)
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{}", self.0.normalize())
+            }
+        }
+    };
+}
+
+/// A price in the quote asset of a symbol, e.g. USDT.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(transparent)]
+pub struct Price(#[serde(with = "rust_decimal::serde::float")] Decimal);
+
+decimal_type!(Price);
+
+impl Price {
+    /// Rounds the price to the nearest tick of a precision.
+    pub fn round_to(self, precision: u32) -> Self {
+        Self(
+            self.0
+                .round_dp_with_strategy(precision, RoundingStrategy::MidpointAwayFromZero),
+        )
+    }
+
+    /// The value of a quantity at the price, in the quote asset.
+    pub fn notional(self, quantity: Qty) -> Decimal {
+        self.0 * quantity.0
+    }
+}
+
+/// A quantity of the base asset of a symbol, e.g. BTC.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(transparent)]
+pub struct Qty(#[serde(with = "rust_decimal::serde::float")] Decimal);
+
+decimal_type!(Qty);
+
+impl Qty {
+    /// The quantity a margin buys at a leverage and price, rounded down to a precision so the
+    /// order never exceeds the margin.
+    ///
+    /// # Returns
+    ///
+    /// The quantity, zero if the price isn't positive.
+    pub fn for_margin(margin_usd: f64, leverage: u32, price: Price, precision: u32) -> Self {
+        if price <= Price::ZERO {
+            return Self::ZERO;
+        }
+
+        let notional = Decimal::from_f64(margin_usd).unwrap_or_default() * Decimal::from(leverage);
+        Self(notional / price.0).round_down_to(precision)
+    }
+
+    /// Rounds the quantity down to a step of a precision.
+    pub fn round_down_to(self, precision: u32) -> Self {
+        Self(
+            self.0
+                .round_dp_with_strategy(precision, RoundingStrategy::ToZero),
+        )
+    }
+}
+
+/// Decimal places prices and quantities of a symbol are traded at, from the filters of the
+/// exchange.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct SymbolPrecision {
+    pub price: u32,
+    pub quantity: u32,
+}
+
+impl Default for SymbolPrecision {
+    fn default() -> Self {
+        Self {
+            price: DEFAULT_PRECISION,
+            quantity: DEFAULT_PRECISION,
+        }
+    }
+}
+
+impl From<&SymbolInfo> for SymbolPrecision {
+    fn from(info: &SymbolInfo) -> Self {
+        Self {
+            price: info.price_precision,
+            quantity: info.quantity_precision,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    /// Tests quantities are sized exactly and rounded down, and both types read and write plain
+    /// JSON numbers.
+    #[test]
+    fn test_price_and_qty() {
+        assert_eq!(Price::from_f64(0.1).round_to(2).to_string(), "0.1");
+        assert_eq!(Price::from_f64(123.456).round_to(2).to_string(), "123.46");
+        assert_eq!(Price::from_f64(f64::NAN), Price::ZERO);
+
+        // 0.1 + 0.2 drifts in floating point, not in decimals
+        let sum = Price::from_f64(0.1).0 + Price::from_f64(0.2).0;
+        assert_eq!(sum, Decimal::from_str("0.3").unwrap());
+
+        let quantity = Qty::for_margin(100.0, 10, Price::from_f64(30000.0), 3);
+        assert_eq!(quantity.to_string(), "0.033");
+        assert_eq!(Qty::for_margin(100.0, 10, Price::ZERO, 3), Qty::ZERO);
+        assert_eq!(
+            Price::from_f64(30000.0).notional(quantity),
+            Decimal::from(990)
+        );
+
+        let json = serde_json::to_string(&quantity).unwrap();
+        assert_eq!(json, "0.033");
+        assert_eq!(serde_json::from_str::<Qty>("0.033").unwrap(), quantity);
+        assert_eq!(
+            serde_json::from_str::<Price>("50000").unwrap(),
+            Price::from_f64(50000.0)
+        );
+    }
+}
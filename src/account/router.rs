@@ -0,0 +1,193 @@
+use std::collections::BTreeMap;
+
+use log::warn;
+use serde::Serialize;
+
+use crate::account::account::{Account, AccountEquity};
+use crate::exchange::profile::CredentialProfile;
+use crate::market::types::ArcMutex;
+use crate::strategy::execution::ExecutionStyle;
+use crate::strategy::strategy::StrategySettings;
+
+/// A credential profile with the state of its account.
+#[derive(Serialize, Debug, Clone)]
+pub struct ProfileAccount {
+    #[serde(flatten)]
+    pub profile: CredentialProfile,
+    pub open_positions: usize,
+    pub equity: AccountEquity,
+}
+
+/// The accounts strategies trade on: the live account, the shadow account of strategies in
+/// shadow mode and one account per credential profile. Clones share the same accounts.
+#[derive(Clone)]
+pub struct AccountRouter {
+    pub live: ArcMutex<Account>,
+    pub shadow: ArcMutex<Account>,
+    /// Accounts of the credential profiles by profile name.
+    pub profiles: BTreeMap<String, ArcMutex<Account>>,
+}
+
+impl AccountRouter {
+    /// Picks the account a strategy trades on.
+    ///
+    /// # Arguments
+    ///
+    /// * `settings` - The settings of the strategy, `None` for signals of unknown strategies.
+    ///
+    /// # Returns
+    ///
+    /// The shadow account in shadow mode, the account of the profile the settings reference, or
+    /// the live account. Unknown profiles trade on the shadow account rather than on an account
+    /// they weren't meant for.
+    pub fn account_for(&self, settings: Option<&StrategySettings>) -> ArcMutex<Account> {
+        let Some(settings) = settings else {
+            return self.live.clone();
+        };
+        if settings.shadow {
+            return self.shadow.clone();
+        }
+
+        match &settings.profile {
+            Some(profile) => match self.profiles.get(profile) {
+                Some(account) => account.clone(),
+                None => {
+                    warn!("Unknown credential profile {profile}, trading on the shadow account");
+                    self.shadow.clone()
+                }
+            },
+            None => self.live.clone(),
+        }
+    }
+
+    /// Checks whether settings reference a configured credential profile, if any.
+    pub fn check_profile(&self, settings: &StrategySettings) -> Result<(), String> {
+        match &settings.profile {
+            Some(profile) if !self.profiles.contains_key(profile) => {
+                Err(format!("unknown credential profile {profile}"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks whether the account the settings trade on can work their execution style. Iceberg
+    /// slices are simulated against the market price, so only dry run accounts take them.
+    pub async fn check_execution(&self, settings: &StrategySettings) -> Result<(), String> {
+        if !matches!(settings.execution, ExecutionStyle::Iceberg { .. }) {
+            return Ok(());
+        }
+        if self.account_for(Some(settings)).lock().await.is_dry_run() {
+            Ok(())
+        } else {
+            Err("iceberg execution is only supported on dry run accounts".to_string())
+        }
+    }
+
+    /// Lists every account with the name its history is stored under.
+    pub fn named(&self) -> Vec<(String, ArcMutex<Account>)> {
+        let mut accounts = vec![
+            ("live".to_string(), self.live.clone()),
+            ("shadow".to_string(), self.shadow.clone()),
+        ];
+        for (name, account) in &self.profiles {
+            accounts.push((name.clone(), account.clone()));
+        }
+
+        accounts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchange::{api::ExchangeApi, mock::MockExchangeApi};
+    use std::sync::Arc;
+
+    /// Tests strategies in shadow mode and of unknown profiles trade on the shadow account,
+    /// strategies of a profile on its account and any other on the live account.
+    #[tokio::test]
+    async fn test_account_for() {
+        let exchange_api: Arc<Box<dyn ExchangeApi>> =
+            Arc::new(Box::new(MockExchangeApi::default()));
+        let account =
+            || async { ArcMutex::new(Account::new(exchange_api.clone(), false, true).await) };
+        let router = AccountRouter {
+            live: account().await,
+            shadow: account().await,
+            profiles: BTreeMap::from([("main".to_string(), account().await)]),
+        };
+        let is = |picked: ArcMutex<Account>, account: &ArcMutex<Account>| {
+            std::ptr::eq(&*picked, &**account)
+        };
+
+        let settings = |shadow: bool, profile: Option<&str>| StrategySettings {
+            shadow,
+            profile: profile.map(str::to_string),
+            ..Default::default()
+        };
+        assert!(is(router.account_for(None), &router.live));
+        assert!(is(
+            router.account_for(Some(&settings(false, None))),
+            &router.live
+        ));
+        assert!(is(
+            router.account_for(Some(&settings(true, None))),
+            &router.shadow
+        ));
+        assert!(is(
+            router.account_for(Some(&settings(true, Some("main")))),
+            &router.shadow
+        ));
+        assert!(is(
+            router.account_for(Some(&settings(false, Some("main")))),
+            &router.profiles["main"]
+        ));
+        assert!(is(
+            router.account_for(Some(&settings(false, Some("other")))),
+            &router.shadow
+        ));
+        assert!(router
+            .check_profile(&settings(false, Some("other")))
+            .is_err());
+        assert!(router.check_profile(&settings(false, Some("main"))).is_ok());
+    }
+
+    /// Tests iceberg execution is refused on live accounts and allowed on dry run accounts.
+    #[tokio::test]
+    async fn test_check_execution() {
+        let exchange_api: Arc<Box<dyn ExchangeApi>> =
+            Arc::new(Box::new(MockExchangeApi::default()));
+        let account = |dry_run: bool| {
+            let exchange_api = exchange_api.clone();
+            async move { ArcMutex::new(Account::new(exchange_api, false, dry_run).await) }
+        };
+        let router = AccountRouter {
+            live: account(false).await,
+            shadow: account(true).await,
+            profiles: BTreeMap::new(),
+        };
+
+        let settings = |shadow: bool, execution: ExecutionStyle| StrategySettings {
+            shadow,
+            execution,
+            ..Default::default()
+        };
+        let iceberg = ExecutionStyle::Iceberg {
+            slices: 4,
+            offset_pct: 0.1,
+            timeout_secs: 600,
+        };
+        assert!(router
+            .check_execution(&settings(false, iceberg))
+            .await
+            .is_err());
+        assert!(router
+            .check_execution(&settings(true, iceberg))
+            .await
+            .is_ok());
+        assert!(router
+            .check_execution(&settings(false, ExecutionStyle::Market))
+            .await
+            .is_ok());
+    }
+}
@@ -1,8 +1,11 @@
 use std::fmt::Display;
 
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
 use crate::{
+    account::money::{Price, Qty, SymbolPrecision},
     strategy::strategy::StrategyId,
     utils::time::{generate_ts, timestamp_to_string},
 };
@@ -50,9 +53,9 @@ pub struct Position {
     /// The time when the position was opened.
     pub open_time: String,
     /// The price at which the position was opened.
-    pub open_price: f64,
+    pub open_price: Price,
     /// The quantity of the asset in the position.
-    pub quantity: f64,
+    pub quantity: Qty,
     /// The margin used for the position in USD.
     pub margin_usd: f64,
     /// The leverage used for the position.
@@ -60,7 +63,13 @@ pub struct Position {
     /// The optional strategy ID associated with the position.
     pub strategy_id: Option<StrategyId>,
     /// The optional stop loss price for the position.
-    pub stop_loss: Option<f64>,
+    pub stop_loss: Option<Price>,
+    /// The optional take profit price for the position.
+    #[serde(default)]
+    pub take_profit: Option<Price>,
+    /// The funding paid while the position was open in USD, negative when funding was received.
+    #[serde(default)]
+    pub funding_paid: f64,
 }
 
 impl Position {
@@ -74,20 +83,23 @@ impl Position {
     /// * `margin_usd` - The margin used for the position in USD.
     /// * `leverage` - The leverage used for the position.
     /// * `stop_loss` - The optional stop loss price for the position.
+    /// * `precision` - The precision of the symbol, the open price is rounded to its ticks and
+    ///   the quantity down to its steps.
     ///
     /// # Returns
     ///
     /// A new `Position` instance.
     pub fn new(
         symbol: &str,
-        open_price: f64,
+        open_price: Price,
         order_side: OrderSide,
         margin_usd: f64,
         leverage: u32,
-        stop_loss: Option<f64>,
+        stop_loss: Option<Price>,
+        precision: SymbolPrecision,
     ) -> Self {
-        let total = margin_usd * leverage as f64;
-        let qty = total / open_price;
+        let open_price = open_price.round_to(precision.price);
+        let quantity = Qty::for_margin(margin_usd, leverage, open_price, precision.quantity);
 
         Self {
             id: Uuid::new_v4(),
@@ -95,11 +107,13 @@ impl Position {
             order_side,
             open_price,
             stop_loss,
-            quantity: qty,
+            take_profit: None,
+            quantity,
             margin_usd,
             leverage,
             strategy_id: None,
             open_time: timestamp_to_string(generate_ts()),
+            funding_paid: 0.0,
         }
     }
 
@@ -109,10 +123,19 @@ impl Position {
     ///
     /// * `stop_loss` - The optional stop loss price for the position.
 
-    pub fn set_stop_loss(&mut self, stop_loss: Option<f64>) {
+    pub fn set_stop_loss(&mut self, stop_loss: Option<Price>) {
         self.stop_loss = stop_loss
     }
 
+    /// Sets the take profit price for the position.
+    ///
+    /// # Arguments
+    ///
+    /// * `take_profit` - The optional take profit price for the position.
+    pub fn set_take_profit(&mut self, take_profit: Option<Price>) {
+        self.take_profit = take_profit
+    }
+
     /// Sets the strategy ID associated with the position.
     ///
     /// # Arguments
@@ -122,6 +145,127 @@ impl Position {
     pub fn set_strategy_id(&mut self, strategy_id: Option<StrategyId>) {
         self.strategy_id = strategy_id
     }
+
+    /// Calculates the profit the position would realize if closed at a given price.
+    ///
+    /// # Arguments
+    ///
+    /// * `price` - The price to mark the position at.
+    ///
+    /// # Returns
+    ///
+    /// The unrealized profit or loss in USD.
+    pub fn calc_unrealized_profit(&self, price: Price) -> f64 {
+        net_profit(
+            self.order_side,
+            self.open_price,
+            price,
+            self.quantity,
+            self.funding_paid,
+        )
+    }
+
+    /// Settles a perpetual funding payment on the position, longs pay shorts when the rate is
+    /// positive and receive from them when it is negative.
+    ///
+    /// # Arguments
+    ///
+    /// * `rate` - The funding rate of the settlement, e.g. `0.0001` for 0.01%.
+    /// * `mark_price` - The price the position's notional is valued at.
+    ///
+    /// # Returns
+    ///
+    /// The funding paid in USD, negative when received.
+    pub fn apply_funding(&mut self, rate: f64, mark_price: Price) -> f64 {
+        let notional = mark_price
+            .notional(self.quantity)
+            .to_f64()
+            .unwrap_or_default();
+        let payment = match self.order_side {
+            OrderSide::Buy => rate * notional,
+            OrderSide::Sell => -rate * notional,
+        };
+        self.funding_paid += payment;
+        payment
+    }
+
+    /// Checks whether a traded price reaches the stop loss of the position.
+    ///
+    /// # Arguments
+    ///
+    /// * `price` - The traded price.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the position has a stop loss and the price is at or beyond it.
+    pub fn is_stop_loss_hit(&self, price: Price) -> bool {
+        match (self.stop_loss, &self.order_side) {
+            (Some(stop_loss), OrderSide::Buy) => price <= stop_loss,
+            (Some(stop_loss), OrderSide::Sell) => price >= stop_loss,
+            (None, _) => false,
+        }
+    }
+
+    /// Checks whether the price reached the take profit of the position.
+    ///
+    /// # Arguments
+    ///
+    /// * `price` - The traded price.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the position has a take profit and the price is at or beyond it.
+    pub fn is_take_profit_hit(&self, price: Price) -> bool {
+        match (self.take_profit, &self.order_side) {
+            (Some(take_profit), OrderSide::Buy) => price >= take_profit,
+            (Some(take_profit), OrderSide::Sell) => price <= take_profit,
+            (None, _) => false,
+        }
+    }
+}
+
+/// Checks the stop loss and take profit of a position are on the losing and winning side of a
+/// price, so neither is hit right away.
+///
+/// # Arguments
+///
+/// * `order_side` - The side of the position.
+/// * `price` - The price the position is opened at or currently traded at.
+/// * `stop_loss` - The optional stop loss price.
+/// * `take_profit` - The optional take profit price.
+///
+/// # Returns
+///
+/// An error describing the invalid price, if any.
+pub fn validate_exit_prices(
+    order_side: OrderSide,
+    price: Price,
+    stop_loss: Option<Price>,
+    take_profit: Option<Price>,
+) -> Result<(), String> {
+    let (stop_loss_valid, take_profit_valid) = match order_side {
+        OrderSide::Buy => (
+            stop_loss.is_none_or(|stop_loss| stop_loss < price),
+            take_profit.is_none_or(|take_profit| take_profit > price),
+        ),
+        OrderSide::Sell => (
+            stop_loss.is_none_or(|stop_loss| stop_loss > price),
+            take_profit.is_none_or(|take_profit| take_profit < price),
+        ),
+    };
+
+    if !stop_loss_valid {
+        return Err(format!(
+            "Stop loss is on the wrong side of the price {price}"
+        ));
+    }
+    if !take_profit_valid {
+        return Err(format!(
+            "Take profit is on the wrong side of the price {price}"
+        ));
+    }
+
+    Ok(())
 }
 
 /// Struct representing a trading transaction.
@@ -132,7 +276,7 @@ pub struct TradeTx {
     /// The time when the position was closed.
     pub close_time: String,
     /// The price at which the position was closed.
-    pub close_price: f64,
+    pub close_price: Price,
     /// The position associated with the trade transaction.
     pub position: Position,
 }
@@ -149,7 +293,7 @@ impl TradeTx {
     ///
     /// A new `TradeTx` instance.
 
-    pub fn new(close_price: f64, close_time: u64, position: Position) -> Self {
+    pub fn new(close_price: Price, close_time: u64, position: Position) -> Self {
         Self {
             id: Uuid::new_v4(),
             close_price,
@@ -162,35 +306,225 @@ impl TradeTx {
     ///
     /// # Returns
     ///
-    /// The profit of the trade transaction.
-
+    /// The profit of the trade transaction, net of the funding paid while the position was open.
     pub fn calc_profit(&self) -> f64 {
-        let total_open_usd = self.position.open_price * self.position.quantity;
-        let total_close_usd = self.close_price * self.position.quantity;
-        match self.position.order_side {
-            OrderSide::Buy => total_close_usd - total_open_usd,
-            OrderSide::Sell => total_open_usd - total_close_usd,
+        net_profit(
+            self.position.order_side,
+            self.position.open_price,
+            self.close_price,
+            self.position.quantity,
+            self.position.funding_paid,
+        )
+    }
+}
+
+/// Whether an order opened or closed a position.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum OrderAction {
+    Open,
+    Close,
+}
+
+/// Struct representing an order filled on the exchange to open or close a position.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OrderRecord {
+    /// The unique identifier of the order.
+    pub id: Uuid,
+    /// The position the order opened or closed.
+    pub position_id: PositionId,
+    /// The optional strategy ID associated with the position.
+    pub strategy_id: Option<StrategyId>,
+    pub symbol: String,
+    /// The side of the order, the opposite of the position's side when closing it.
+    pub order_side: OrderSide,
+    pub action: OrderAction,
+    /// The price the order was filled at.
+    pub price: Price,
+    pub quantity: Qty,
+    /// The time the order was filled.
+    pub timestamp: u64,
+}
+
+impl OrderRecord {
+    /// Creates the record of the order opening a position.
+    pub fn open(position: &Position) -> Self {
+        Self::new(
+            position,
+            position.order_side,
+            OrderAction::Open,
+            position.open_price,
+        )
+    }
+
+    /// Creates the record of the order closing a position.
+    pub fn close(trade_tx: &TradeTx) -> Self {
+        let order_side = match trade_tx.position.order_side {
+            OrderSide::Buy => OrderSide::Sell,
+            OrderSide::Sell => OrderSide::Buy,
+        };
+        Self::new(
+            &trade_tx.position,
+            order_side,
+            OrderAction::Close,
+            trade_tx.close_price,
+        )
+    }
+
+    // ---
+    // Private Methods
+    // ---
+
+    fn new(position: &Position, order_side: OrderSide, action: OrderAction, price: Price) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            position_id: position.id,
+            strategy_id: position.strategy_id,
+            symbol: position.symbol.clone(),
+            order_side,
+            action,
+            price,
+            quantity: position.quantity,
+            timestamp: generate_ts(),
+        }
+    }
+}
+
+/// The state of an `IcebergOrder`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IcebergStatus {
+    /// A slice is resting on the book.
+    Working,
+    /// Every slice was filled.
+    Filled,
+    /// The order timed out before every slice was filled.
+    Expired,
+    /// The order was cancelled, e.g. by an opposite signal or by pausing trading.
+    Cancelled,
+}
+
+/// A large order traded as limit orders for one slice of its size at a time, of which only the
+/// visible slice rests on the book. The account shows it as a single order, however many
+/// slices were filled and positions opened or closed by them.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct IcebergOrder {
+    pub id: Uuid,
+    pub symbol: String,
+    pub strategy_id: Option<StrategyId>,
+    /// The side of the order, the opposite of the positions' side when closing them.
+    pub order_side: OrderSide,
+    pub action: OrderAction,
+    /// The number of slices the order is traded in.
+    pub slices: u32,
+    pub filled_slices: u32,
+    /// The limit price of the visible slice, `None` until a slice is placed.
+    pub limit_price: Option<Price>,
+    /// The positions opened or closed by the filled slices.
+    pub position_ids: Vec<PositionId>,
+    pub status: IcebergStatus,
+    /// The time the order was placed.
+    pub timestamp: u64,
+}
+
+impl IcebergOrder {
+    pub fn new(
+        symbol: &str,
+        strategy_id: Option<StrategyId>,
+        order_side: OrderSide,
+        action: OrderAction,
+        slices: u32,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            symbol: symbol.to_string(),
+            strategy_id,
+            order_side,
+            action,
+            slices,
+            filled_slices: 0,
+            limit_price: None,
+            position_ids: vec![],
+            status: IcebergStatus::Working,
+            timestamp: generate_ts(),
+        }
+    }
+
+    /// Records the fill of the visible slice, the order is filled with its last slice.
+    pub fn record_fill(&mut self, position_ids: Vec<PositionId>) {
+        if self.status != IcebergStatus::Working {
+            return;
+        }
+        self.filled_slices += 1;
+        self.position_ids.extend(position_ids);
+        self.limit_price = None;
+        if self.filled_slices >= self.slices {
+            self.status = IcebergStatus::Filled;
+        }
+    }
+
+    /// Ends a working order, the slices not yet filled are no longer traded. Orders that
+    /// already ended keep their status.
+    pub fn finish(&mut self, status: IcebergStatus) {
+        if self.status == IcebergStatus::Working {
+            self.status = status;
+            self.limit_price = None;
         }
     }
 }
 
+/// The profit of a quantity opened at one price and closed at another, net of the funding paid
+/// while it was open, worked out in decimals.
+fn net_profit(
+    order_side: OrderSide,
+    open_price: Price,
+    close_price: Price,
+    quantity: Qty,
+    funding_paid: f64,
+) -> f64 {
+    let profit = match order_side {
+        OrderSide::Buy => close_price.notional(quantity) - open_price.notional(quantity),
+        OrderSide::Sell => open_price.notional(quantity) - close_price.notional(quantity),
+    };
+    let funding_paid = Decimal::from_f64(funding_paid).unwrap_or_default();
+
+    (profit - funding_paid).to_f64().unwrap_or_default()
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use crate::utils::time::{generate_ts, string_to_timestamp};
     use tokio::test;
 
+    /// A position of 1000 USD at 10x leverage.
+    fn position(order_side: OrderSide, open_price: f64) -> Position {
+        let precision = SymbolPrecision::default();
+        Position::new(
+            "BTCUSD",
+            open_price.into(),
+            order_side,
+            1000.0,
+            10,
+            None,
+            precision,
+        )
+    }
+
     #[test]
     async fn test_position_new() {
         let symbol = "BTCUSD";
-        let open_price = 50000.0;
+        let open_price = Price::from_f64(30000.0);
         let order_side = OrderSide::Buy;
         let margin_usd = 1000.0;
         let leverage = 10;
-        let stop_loss = Some(49000.0);
+        let stop_loss = Some(Price::from_f64(29000.0));
+        let precision = SymbolPrecision {
+            price: 1,
+            quantity: 3,
+        };
 
         let position = Position::new(
-            symbol, open_price, order_side, margin_usd, leverage, stop_loss,
+            symbol, open_price, order_side, margin_usd, leverage, stop_loss, precision,
         );
 
         assert_eq!(position.symbol, symbol);
@@ -200,9 +534,8 @@ mod test {
         assert_eq!(position.leverage, leverage);
         assert_eq!(position.stop_loss, stop_loss);
 
-        // Assert that quantity is calculated correctly
-        let expected_quantity = margin_usd * leverage as f64 / open_price;
-        assert_eq!(position.quantity, expected_quantity);
+        // 0.3333.. BTC is rounded down to the quantity step
+        assert_eq!(position.quantity.to_string(), "0.333");
 
         // Assert that other fields have default values
         assert!(position.strategy_id.is_none());
@@ -211,10 +544,10 @@ mod test {
 
     #[test]
     async fn test_trade_tx_new() {
-        let close_price = 51000.0;
+        let close_price = Price::from_f64(50000.3);
         let close_time = generate_ts();
 
-        let position = Position::new("BTCUSD", 50000.0, OrderSide::Buy, 1000.0, 10, Some(49000.0));
+        let position = position(OrderSide::Buy, 50000.0);
 
         let trade_tx = TradeTx::new(close_price, close_time, position.clone());
 
@@ -223,11 +556,104 @@ mod test {
         assert_eq!(trade_tx.position, position);
 
         // Assert that trade_tx has a unique ID
-        let another_trade_tx = TradeTx::new(52000.0, generate_ts(), position.clone());
+        let another_trade_tx = TradeTx::new(close_price, generate_ts(), position.clone());
         assert_ne!(trade_tx.id, another_trade_tx.id);
 
-        // Assert that calc_profit calculates correctly
-        let expected_profit = (close_price - position.open_price) * position.quantity;
-        assert_eq!(trade_tx.calc_profit(), expected_profit);
+        // 0.2 BTC sold 0.3 above the open price, without the drift of (50000.3 - 50000.0) * 0.2
+        assert_eq!(position.quantity.to_string(), "0.2");
+        assert_eq!(trade_tx.calc_profit(), 0.06);
+    }
+
+    #[test]
+    async fn test_position_calc_unrealized_profit() {
+        let long = position(OrderSide::Buy, 50000.0);
+        let short = position(OrderSide::Sell, 50000.0);
+
+        // 0.2 BTC marked 1000 above the open price
+        assert_eq!(long.calc_unrealized_profit(51000.0.into()), 200.0);
+        assert_eq!(short.calc_unrealized_profit(51000.0.into()), -200.0);
+    }
+
+    #[test]
+    async fn test_position_is_stop_loss_hit() {
+        let mut long = position(OrderSide::Buy, 50000.0);
+        long.set_stop_loss(Some(49000.0.into()));
+        let mut short = position(OrderSide::Sell, 50000.0);
+        short.set_stop_loss(Some(51000.0.into()));
+        let no_stop = position(OrderSide::Buy, 50000.0);
+
+        assert!(!long.is_stop_loss_hit(49500.0.into()));
+        assert!(long.is_stop_loss_hit(49000.0.into()));
+        assert!(!short.is_stop_loss_hit(50500.0.into()));
+        assert!(short.is_stop_loss_hit(51200.0.into()));
+        assert!(!no_stop.is_stop_loss_hit(Price::ZERO));
+    }
+
+    #[test]
+    async fn test_position_take_profit() {
+        let mut long = position(OrderSide::Buy, 50000.0);
+        let mut short = position(OrderSide::Sell, 50000.0);
+        assert!(!long.is_take_profit_hit(1e12.into()));
+
+        long.set_take_profit(Some(52000.0.into()));
+        short.set_take_profit(Some(48000.0.into()));
+        assert!(!long.is_take_profit_hit(51500.0.into()));
+        assert!(long.is_take_profit_hit(52000.0.into()));
+        assert!(!short.is_take_profit_hit(48500.0.into()));
+        assert!(short.is_take_profit_hit(47900.0.into()));
+
+        let price = Price::from_f64(50000.0);
+        let exits = |stop_loss: Option<f64>, take_profit: Option<f64>| {
+            (stop_loss.map(Price::from), take_profit.map(Price::from))
+        };
+        let (stop_loss, take_profit) = exits(Some(49000.0), Some(52000.0));
+        assert!(validate_exit_prices(OrderSide::Buy, price, stop_loss, take_profit).is_ok());
+        let (stop_loss, take_profit) = exits(Some(51000.0), None);
+        assert!(validate_exit_prices(OrderSide::Buy, price, stop_loss, take_profit).is_err());
+        let (stop_loss, take_profit) = exits(None, Some(52000.0));
+        assert!(validate_exit_prices(OrderSide::Sell, price, stop_loss, take_profit).is_err());
+        let (stop_loss, take_profit) = exits(Some(51000.0), Some(48000.0));
+        assert!(validate_exit_prices(OrderSide::Sell, price, stop_loss, take_profit).is_ok());
+    }
+
+    #[test]
+    async fn test_position_apply_funding() {
+        let mut long = position(OrderSide::Buy, 50000.0);
+        let mut short = position(OrderSide::Sell, 50000.0);
+
+        // 0.01% of a 10000 USD notional
+        assert!((long.apply_funding(0.0001, 50000.0.into()) - 1.0).abs() < 1e-9);
+        assert!((short.apply_funding(0.0001, 50000.0.into()) + 1.0).abs() < 1e-9);
+        assert!((long.calc_unrealized_profit(50000.0.into()) + 1.0).abs() < 1e-9);
+
+        let trade_tx = TradeTx::new(50000.0.into(), generate_ts(), short);
+        assert!((trade_tx.calc_profit() - 1.0).abs() < 1e-9);
+    }
+
+    /// Tests iceberg orders are filled with their last slice and keep their status once they
+    /// ended.
+    #[test]
+    async fn test_iceberg_order() {
+        let mut order = IcebergOrder::new("BTCUSDT", None, OrderSide::Buy, OrderAction::Open, 2);
+        order.limit_price = Some(Price::from_f64(50000.0));
+
+        order.record_fill(vec![Uuid::new_v4()]);
+        assert_eq!(order.status, IcebergStatus::Working);
+        assert_eq!(order.limit_price, None);
+        order.record_fill(vec![Uuid::new_v4()]);
+        assert_eq!(order.status, IcebergStatus::Filled);
+        assert_eq!(order.position_ids.len(), 2);
+
+        order.finish(IcebergStatus::Cancelled);
+        assert_eq!(order.status, IcebergStatus::Filled);
+
+        let mut order = IcebergOrder::new("BTCUSDT", None, OrderSide::Sell, OrderAction::Close, 3);
+        order.record_fill(vec![]);
+        order.finish(IcebergStatus::Expired);
+        order.record_fill(vec![Uuid::new_v4()]);
+        assert_eq!(
+            (order.status, order.filled_slices),
+            (IcebergStatus::Expired, 1)
+        );
     }
 }
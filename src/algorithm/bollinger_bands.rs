@@ -1,84 +1,72 @@
+use crate::algorithm::indicators::RollingWindow;
 use crate::market::kline::Kline;
 use crate::strategy::{
     algorithm::Algorithm,
+    data_points::AlgorithmDataPointManager,
     types::{AlgorithmError, AlgorithmEvalResult},
 };
 use crate::utils::number::parse_usize_from_value;
-use serde_json::Value;
+use serde_json::{json, Value};
 use std::time::Duration;
 
 pub struct BollingerBands {
-    data_points: Vec<Kline>,
     interval: Duration,
     params: Value,
     period: usize,
     multiplier: f64, // Typically, the multiplier is set to 2 for the standard deviation calculation.
+    window: RollingWindow,
 }
 
 impl BollingerBands {
+    /// Returns the JSON schema of the parameters accepted by the algorithm.
+    pub fn params_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "period": { "type": "integer", "minimum": 1, "default": 20 },
+                "multiplier": { "type": "integer", "minimum": 1, "default": 2 }
+            },
+            "required": []
+        })
+    }
+
     pub fn new(interval: Duration, params: Value) -> Result<Self, AlgorithmError> {
         let period = parse_usize_from_value("period", &params).unwrap_or(20); // Default period is 20
         let multiplier = parse_usize_from_value("multiplier", &params).unwrap_or(2) as f64; // Default multiplier is 2
 
         Ok(Self {
-            data_points: Vec::new(),
             interval,
             params,
             period,
             multiplier,
+            window: RollingWindow::new(period),
         })
     }
 
-    fn calculate_sma(&self) -> f64 {
-        if self.data_points.len() < self.period {
-            return 0.0; // Not enough data
-        }
-        self.data_points
-            .iter()
-            .rev()
-            .take(self.period)
-            .map(|k| k.close)
-            .sum::<f64>()
-            / self.period as f64
-    }
-
-    fn calculate_std_dev(&self, sma: f64) -> f64 {
-        if self.data_points.len() < self.period {
-            return 0.0;
-        }
-        let variance: f64 = self
-            .data_points
-            .iter()
-            .rev()
-            .take(self.period)
-            .map(|k| {
-                let diff = k.close - sma;
-                diff * diff
-            })
-            .sum::<f64>()
-            / self.period as f64;
-
-        variance.sqrt()
-    }
-
-    fn calculate_bollinger_bands(&self) -> (f64, f64, f64) {
-        let sma = self.calculate_sma();
-        let std_dev = self.calculate_std_dev(sma);
+    fn calculate_bollinger_bands(&mut self, close: f64) -> Option<(f64, f64, f64)> {
+        let sma = self.window.next(close)?;
+        let std_dev = self.window.std_dev()?;
         let upper_band = sma + std_dev * self.multiplier;
         let lower_band = sma - std_dev * self.multiplier;
 
-        (upper_band, sma, lower_band)
+        Some((upper_band, sma, lower_band))
     }
 }
 
 impl Algorithm for BollingerBands {
-    fn evaluate(&mut self, kline: Kline) -> AlgorithmEvalResult {
-        self.data_points.push(kline.clone());
-
-        let (upper_band, _middle_band, lower_band) = self.calculate_bollinger_bands();
+    fn evaluate(
+        &mut self,
+        kline: Kline,
+        _data_points: &AlgorithmDataPointManager,
+    ) -> AlgorithmEvalResult {
+        let (upper_band, _middle_band, lower_band) =
+            match self.calculate_bollinger_bands(kline.close) {
+                Some(bands) => bands,
+                None => return AlgorithmEvalResult::Ignore, // Not enough data
+            };
 
         // Example trading logic based on Bollinger Bands
-        let result = if kline.close > upper_band {
+        if kline.close > upper_band {
             // Price is above the upper band - potential sell signal (overbought condition)
             AlgorithmEvalResult::Sell
         } else if kline.close < lower_band {
@@ -87,16 +75,7 @@ impl Algorithm for BollingerBands {
         } else {
             // Price is within the bands - no clear signal
             AlgorithmEvalResult::Ignore
-        };
-
-        self.clean_data_points();
-
-        result
-    }
-
-    // Implement the rest of the required methods from the Algorithm trait...
-    fn data_points(&self) -> Vec<Kline> {
-        self.data_points.clone()
+        }
     }
 
     fn interval(&self) -> Duration {
@@ -109,7 +88,8 @@ impl Algorithm for BollingerBands {
 
     fn set_params(&mut self, params: Value) -> Result<(), AlgorithmError> {
         if let Ok(period) = parse_usize_from_value("period", &params) {
-            self.period = period
+            self.period = period;
+            self.window = RollingWindow::new(period);
         }
         if let Ok(multiplier) = parse_usize_from_value("multiplier", &params) {
             self.multiplier = multiplier as f64;
@@ -119,13 +99,7 @@ impl Algorithm for BollingerBands {
         Ok(())
     }
 
-    fn clean_data_points(&mut self) {
-        // TODO: Change length to be checked
-        // based on individual algorithm
-        let two_weeks_minutes = 10080 * 2;
-        if self.data_points.len() > two_weeks_minutes {
-            // reduce back to 1 week worth on data
-            self.data_points.drain(0..10080);
-        }
+    fn max_period(&self) -> usize {
+        self.period
     }
 }
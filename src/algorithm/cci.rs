@@ -0,0 +1,164 @@
+use crate::algorithm::indicators::RollingWindow;
+use crate::market::kline::Kline;
+use crate::strategy::{
+    algorithm::Algorithm,
+    data_points::AlgorithmDataPointManager,
+    types::{AlgorithmError, AlgorithmEvalResult},
+};
+use crate::utils::number::parse_usize_from_value;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+/// Lambert's constant, scales the CCI so most values fall between -100 and 100.
+const CCI_CONSTANT: f64 = 0.015;
+
+pub struct Cci {
+    interval: Duration,
+    params: Value,
+    period: usize,
+    threshold: f64, // CCI level above which is overbought and below the negative of which is oversold
+    typical_prices: RollingWindow,
+}
+
+impl Cci {
+    /// Returns the JSON schema of the parameters accepted by the algorithm.
+    pub fn params_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "period": { "type": "integer", "minimum": 1, "default": 20 },
+                "threshold": { "type": "number", "minimum": 0, "default": 100.0 }
+            },
+            "required": []
+        })
+    }
+
+    pub fn new(interval: Duration, params: Value) -> Result<Self, AlgorithmError> {
+        let period = parse_usize_from_value("period", &params).unwrap_or(20); // Default period is 20
+        Self::validate_period(period)?;
+        let threshold = params
+            .get("threshold")
+            .and_then(Value::as_f64)
+            .unwrap_or(100.0) // Default levels are ±100
+            .abs();
+
+        Ok(Self {
+            interval,
+            params,
+            period,
+            threshold,
+            typical_prices: RollingWindow::new(period),
+        })
+    }
+
+    fn validate_period(period: usize) -> Result<(), AlgorithmError> {
+        // the mean deviation is divided by the period
+        if period < 1 {
+            return Err(AlgorithmError::InvalidParams(
+                "period must be greater than 0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn calculate_cci(&mut self, kline: &Kline) -> Option<f64> {
+        let typical_price = (kline.high + kline.low + kline.close) / 3.0;
+        let sma = self.typical_prices.next(typical_price)?;
+
+        let mean_deviation = self
+            .typical_prices
+            .values()
+            .map(|price| (price - sma).abs())
+            .sum::<f64>()
+            / self.period as f64;
+
+        if mean_deviation == 0.0 {
+            return Some(0.0); // Prices haven't moved, prevent division by zero
+        }
+
+        Some((typical_price - sma) / (CCI_CONSTANT * mean_deviation))
+    }
+}
+
+impl Algorithm for Cci {
+    fn evaluate(
+        &mut self,
+        kline: Kline,
+        _data_points: &AlgorithmDataPointManager,
+    ) -> AlgorithmEvalResult {
+        let cci = match self.calculate_cci(&kline) {
+            Some(cci) => cci,
+            None => return AlgorithmEvalResult::Ignore, // Not enough data
+        };
+
+        if cci < -self.threshold {
+            // Price is well below its average - potential buy signal (oversold condition)
+            AlgorithmEvalResult::Buy
+        } else if cci > self.threshold {
+            // Price is well above its average - potential sell signal (overbought condition)
+            AlgorithmEvalResult::Sell
+        } else {
+            AlgorithmEvalResult::Ignore
+        }
+    }
+
+    fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    fn get_params(&self) -> &Value {
+        &self.params
+    }
+
+    fn set_params(&mut self, params: Value) -> Result<(), AlgorithmError> {
+        if let Ok(period) = parse_usize_from_value("period", &params) {
+            Self::validate_period(period)?;
+            self.period = period;
+            self.typical_prices = RollingWindow::new(period);
+        }
+        if let Some(threshold) = params.get("threshold").and_then(Value::as_f64) {
+            self.threshold = threshold.abs();
+        }
+
+        self.params = params;
+        Ok(())
+    }
+
+    fn max_period(&self) -> usize {
+        self.period
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a kline whose high, low and close, and so its typical price, are the price.
+    fn kline(price: f64) -> Kline {
+        Kline {
+            high: price,
+            low: price,
+            close: price,
+            ..Default::default()
+        }
+    }
+
+    /// Tests the CCI of typical prices against their mean and mean deviation.
+    #[test]
+    fn test_calculate_cci() {
+        let mut cci = Cci::new(Duration::from_secs(60), json!({"period": 3})).unwrap();
+
+        assert_eq!(cci.calculate_cci(&kline(10.0)), None);
+        assert_eq!(cci.calculate_cci(&kline(12.0)), None);
+        // mean 12, mean deviation 4 / 3
+        let value = cci.calculate_cci(&kline(14.0)).unwrap();
+        assert!((value - 100.0).abs() < 1e-9);
+        // mean 37 / 3, mean deviation 10 / 9
+        let value = cci.calculate_cci(&kline(11.0)).unwrap();
+        assert!((value - -80.0).abs() < 1e-9);
+
+        let mut cci = Cci::new(Duration::from_secs(60), json!({"period": 2})).unwrap();
+        cci.calculate_cci(&kline(10.0));
+        assert_eq!(cci.calculate_cci(&kline(10.0)), Some(0.0));
+    }
+}
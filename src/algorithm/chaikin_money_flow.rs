@@ -0,0 +1,168 @@
+use crate::algorithm::indicators::RollingWindow;
+use crate::market::kline::Kline;
+use crate::strategy::{
+    algorithm::Algorithm,
+    data_points::AlgorithmDataPointManager,
+    types::{AlgorithmError, AlgorithmEvalResult},
+};
+use crate::utils::number::parse_usize_from_value;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+/// Chaikin Money Flow filtered by a moving average of the close price.
+///
+/// Buys when money flows in, CMF above `threshold`, while price is above its moving average and
+/// sells when money flows out while price is below it.
+pub struct ChaikinMoneyFlow {
+    interval: Duration,
+    params: Value,
+    period: usize,
+    ma_period: usize,
+    threshold: f64,
+    money_flow_volume: RollingWindow,
+    volume: RollingWindow,
+    price_ma: RollingWindow,
+}
+
+impl ChaikinMoneyFlow {
+    /// Returns the JSON schema of the parameters accepted by the algorithm.
+    pub fn params_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "period": { "type": "integer", "minimum": 1, "default": 20 },
+                "ma_period": { "type": "integer", "minimum": 1, "default": 50 },
+                "threshold": { "type": "number", "minimum": 0, "maximum": 1, "default": 0.05 }
+            },
+            "required": []
+        })
+    }
+
+    pub fn new(interval: Duration, params: Value) -> Result<Self, AlgorithmError> {
+        let period = parse_usize_from_value("period", &params).unwrap_or(20);
+        let ma_period = parse_usize_from_value("ma_period", &params).unwrap_or(50);
+        let threshold = params
+            .get("threshold")
+            .and_then(Value::as_f64)
+            .unwrap_or(0.05)
+            .abs();
+
+        Ok(Self {
+            interval,
+            params,
+            period,
+            ma_period,
+            threshold,
+            money_flow_volume: RollingWindow::new(period),
+            volume: RollingWindow::new(period),
+            price_ma: RollingWindow::new(ma_period),
+        })
+    }
+
+    /// Updates the money flow windows with a new kline.
+    ///
+    /// # Returns
+    ///
+    /// The CMF once `period` klines have been seen and traded volume, otherwise `None`.
+    fn update_cmf(&mut self, kline: &Kline) -> Option<f64> {
+        let range = kline.high - kline.low;
+        // klines without a range don't move money either way
+        let multiplier = if range > 0.0 {
+            ((kline.close - kline.low) - (kline.high - kline.close)) / range
+        } else {
+            0.0
+        };
+
+        // both windows are updated before either is checked, so they cover the same klines
+        let money_flow_volume = self.money_flow_volume.next(multiplier * kline.volume);
+        let volume = self.volume.next(kline.volume);
+        let (money_flow_volume, volume) = (money_flow_volume?, volume?);
+        if volume <= 0.0 {
+            return None;
+        }
+
+        // both windows share the period, so the ratio of their means is the ratio of their sums
+        Some(money_flow_volume / volume)
+    }
+}
+
+impl Algorithm for ChaikinMoneyFlow {
+    fn evaluate(
+        &mut self,
+        kline: Kline,
+        _data_points: &AlgorithmDataPointManager,
+    ) -> AlgorithmEvalResult {
+        let cmf = self.update_cmf(&kline);
+        let price_ma = self.price_ma.next(kline.close);
+
+        let (cmf, price_ma) = match (cmf, price_ma) {
+            (Some(cmf), Some(price_ma)) => (cmf, price_ma),
+            _ => return AlgorithmEvalResult::Ignore, // Not enough data
+        };
+
+        if cmf > self.threshold && kline.close > price_ma {
+            // Buying pressure in an uptrend - potential buy signal
+            AlgorithmEvalResult::Buy
+        } else if cmf < -self.threshold && kline.close < price_ma {
+            // Selling pressure in a downtrend - potential sell signal
+            AlgorithmEvalResult::Sell
+        } else {
+            AlgorithmEvalResult::Ignore
+        }
+    }
+
+    fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    fn get_params(&self) -> &Value {
+        &self.params
+    }
+
+    fn set_params(&mut self, params: Value) -> Result<(), AlgorithmError> {
+        if let Ok(period) = parse_usize_from_value("period", &params) {
+            self.period = period;
+            self.money_flow_volume = RollingWindow::new(period);
+            self.volume = RollingWindow::new(period);
+        }
+        if let Ok(ma_period) = parse_usize_from_value("ma_period", &params) {
+            self.ma_period = ma_period;
+            self.price_ma = RollingWindow::new(ma_period);
+        }
+        if let Some(threshold) = params.get("threshold").and_then(Value::as_f64) {
+            self.threshold = threshold.abs();
+        }
+
+        self.params = params;
+        Ok(())
+    }
+
+    fn max_period(&self) -> usize {
+        self.period.max(self.ma_period)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests the money flow volume of each kline is summed against the volume of the period.
+    #[test]
+    fn test_update_cmf() {
+        let mut cmf = ChaikinMoneyFlow::new(Duration::from_secs(60), json!({"period": 2})).unwrap();
+        let kline = |high: f64, low: f64, close: f64, volume: f64| Kline {
+            high,
+            low,
+            close,
+            volume,
+            ..Default::default()
+        };
+
+        // closes at the high, a multiplier of 1
+        assert_eq!(cmf.update_cmf(&kline(12.0, 10.0, 12.0, 10.0)), None);
+        // closes a quarter up the range, a multiplier of -0.5: (10 - 15) / (10 + 30)
+        assert_eq!(cmf.update_cmf(&kline(12.0, 10.0, 10.5, 30.0)), Some(-0.125));
+        // no range, a multiplier of 0: (-15 + 0) / (30 + 10)
+        assert_eq!(cmf.update_cmf(&kline(11.0, 11.0, 11.0, 10.0)), Some(-0.375));
+    }
+}
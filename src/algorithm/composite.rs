@@ -0,0 +1,608 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::algorithm::indicators::{Ema, RollingWindow, WilderRsi};
+use crate::market::{kline::Kline, regime::MarketRegime};
+use crate::strategy::{
+    algorithm::Algorithm,
+    data_points::AlgorithmDataPointManager,
+    types::{AlgorithmError, AlgorithmEvalResult},
+};
+
+/// K-line fields that can be referenced by name in expressions.
+const PRICE_FIELDS: [&str; 5] = ["open", "high", "low", "close", "volume"];
+
+/// Prefix of the names referencing an external series in expressions, e.g. `external:fear_greed`.
+const EXTERNAL_PREFIX: &str = "external:";
+
+/// Band of the Bollinger Bands used as an indicator value.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Band {
+    Upper,
+    Middle,
+    Lower,
+}
+
+/// Named sub-indicator declared in the params, all indicators are fed the close price.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type")]
+enum IndicatorConfig {
+    Sma {
+        period: usize,
+    },
+    Ema {
+        period: usize,
+    },
+    Rsi {
+        period: usize,
+    },
+    BollingerBands {
+        period: usize,
+        #[serde(default = "default_multiplier")]
+        multiplier: f64,
+        band: Band,
+    },
+}
+
+fn default_multiplier() -> f64 {
+    2.0
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+enum Comparison {
+    #[serde(rename = "<")]
+    Lt,
+    #[serde(rename = "<=")]
+    Le,
+    #[serde(rename = ">")]
+    Gt,
+    #[serde(rename = ">=")]
+    Ge,
+    /// The left operand moved from at or below to above the right operand on this k-line.
+    #[serde(rename = "crosses_above")]
+    CrossesAbove,
+    /// The left operand moved from at or above to below the right operand on this k-line.
+    #[serde(rename = "crosses_below")]
+    CrossesBelow,
+}
+
+/// Either a constant or the name of an indicator, k-line field or external series.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum Operand {
+    Constant(f64),
+    Name(String),
+}
+
+/// Boolean expression tree evaluated on every k-line. An expression is undecided, and no signal
+/// is generated, until every indicator it references has warmed up, every external series it
+/// references has a value and, for `regime`, enough k-lines are retained to classify the market.
+///
+/// ```json
+/// { "and": [
+///     { "compare": { "left": "rsi", "op": "<", "right": 30 } },
+///     { "compare": { "left": "external:fear_greed", "op": "<", "right": 25 } },
+///     { "not": { "regime": "HighVolatility" } }
+/// ] }
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Expression {
+    And(Vec<Expression>),
+    Or(Vec<Expression>),
+    Not(Box<Expression>),
+    Compare {
+        left: Operand,
+        op: Comparison,
+        right: Operand,
+    },
+    /// Holds while the retained k-lines are classified as the given market regime.
+    Regime(MarketRegime),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct CompositeConfig {
+    indicators: HashMap<String, IndicatorConfig>,
+    buy: Option<Expression>,
+    sell: Option<Expression>,
+}
+
+/// Running state of a sub-indicator.
+enum Indicator {
+    Sma(RollingWindow),
+    Ema(Ema),
+    Rsi(WilderRsi),
+    BollingerBands(RollingWindow, f64, Band),
+}
+
+impl Indicator {
+    fn new(config: &IndicatorConfig) -> Self {
+        match *config {
+            IndicatorConfig::Sma { period } => Indicator::Sma(RollingWindow::new(period)),
+            IndicatorConfig::Ema { period } => Indicator::Ema(Ema::new(period)),
+            IndicatorConfig::Rsi { period } => Indicator::Rsi(WilderRsi::new(period)),
+            IndicatorConfig::BollingerBands {
+                period,
+                multiplier,
+                band,
+            } => Indicator::BollingerBands(RollingWindow::new(period), multiplier, band),
+        }
+    }
+
+    fn next(&mut self, close: f64) -> Option<f64> {
+        match self {
+            Indicator::Sma(window) => window.next(close),
+            Indicator::Ema(ema) => {
+                let value = ema.next(close);
+                ema.is_ready().then_some(value)
+            }
+            Indicator::Rsi(rsi) => rsi.next(close),
+            Indicator::BollingerBands(window, multiplier, band) => {
+                let sma = window.next(close)?;
+                let std_dev = window.std_dev()?;
+                match band {
+                    Band::Upper => Some(sma + std_dev * *multiplier),
+                    Band::Middle => Some(sma),
+                    Band::Lower => Some(sma - std_dev * *multiplier),
+                }
+            }
+        }
+    }
+}
+
+/// Algorithm configured with a JSON expression tree over named sub-indicators, so RSI, moving
+/// average and Bollinger Bands conditions can be combined without writing Rust, along with the
+/// market regime and external series such as news sentiment.
+///
+/// Buys when the `buy` expression holds, otherwise sells when the `sell` expression holds.
+/// Comparisons referencing an indicator that isn't warmed up yet are false.
+pub struct Composite {
+    interval: Duration,
+    params: Value,
+    config: CompositeConfig,
+    indicators: Vec<(String, Indicator)>,
+    /// The names of the external series referenced by the expressions, with their prefix.
+    external_names: Vec<String>,
+    values: HashMap<String, f64>,
+    prev_values: HashMap<String, f64>,
+    regime: Option<MarketRegime>,
+}
+
+impl Composite {
+    /// Returns the JSON schema of the parameters accepted by the algorithm.
+    pub fn params_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "indicators": {
+                    "type": "object",
+                    "additionalProperties": {
+                        "type": "object",
+                        "properties": {
+                            "type": { "enum": ["Sma", "Ema", "Rsi", "BollingerBands"] },
+                            "period": { "type": "integer", "minimum": 1 },
+                            "multiplier": { "type": "number", "minimum": 0, "default": 2.0 },
+                            "band": { "enum": ["upper", "middle", "lower"] }
+                        },
+                        "required": ["type", "period"]
+                    }
+                },
+                "buy": { "$ref": "#/definitions/expression" },
+                "sell": { "$ref": "#/definitions/expression" }
+            },
+            "definitions": {
+                "operand": { "type": ["number", "string"] },
+                "expression": {
+                    "oneOf": [
+                        { "type": "object", "properties": { "and": { "type": "array", "items": { "$ref": "#/definitions/expression" } } }, "required": ["and"] },
+                        { "type": "object", "properties": { "or": { "type": "array", "items": { "$ref": "#/definitions/expression" } } }, "required": ["or"] },
+                        { "type": "object", "properties": { "not": { "$ref": "#/definitions/expression" } }, "required": ["not"] },
+                        { "type": "object", "properties": { "regime": { "enum": ["TrendingUp", "TrendingDown", "Ranging", "HighVolatility"] } }, "required": ["regime"] },
+                        {
+                            "type": "object",
+                            "properties": {
+                                "compare": {
+                                    "type": "object",
+                                    "properties": {
+                                        "left": { "$ref": "#/definitions/operand" },
+                                        "op": { "enum": ["<", "<=", ">", ">=", "crosses_above", "crosses_below"] },
+                                        "right": { "$ref": "#/definitions/operand" }
+                                    },
+                                    "required": ["left", "op", "right"]
+                                }
+                            },
+                            "required": ["compare"]
+                        }
+                    ]
+                }
+            },
+            "required": ["indicators"]
+        })
+    }
+
+    pub fn new(interval: Duration, params: Value) -> Result<Self, AlgorithmError> {
+        let config = Self::parse_config(&params)?;
+
+        Ok(Self {
+            interval,
+            params,
+            indicators: Self::build_indicators(&config),
+            external_names: Self::external_names(&config),
+            config,
+            values: HashMap::new(),
+            prev_values: HashMap::new(),
+            regime: None,
+        })
+    }
+
+    /// Parses and validates the expression config.
+    ///
+    /// # Returns
+    ///
+    /// The `CompositeConfig`, or an `AlgorithmError` if the config is malformed, an indicator
+    /// shadows a k-line field or an expression references an unknown name.
+    fn parse_config(params: &Value) -> Result<CompositeConfig, AlgorithmError> {
+        let config: CompositeConfig = serde_json::from_value(params.clone())
+            .map_err(|e| AlgorithmError::InvalidParams(e.to_string()))?;
+
+        if config.buy.is_none() && config.sell.is_none() {
+            return Err(AlgorithmError::InvalidParams(
+                "At least one of buy or sell expressions is required".to_string(),
+            ));
+        }
+
+        for (name, indicator) in config.indicators.iter() {
+            if PRICE_FIELDS.contains(&name.as_str()) {
+                return Err(AlgorithmError::InvalidParams(format!(
+                    "Indicator name {name} is reserved for k-line fields"
+                )));
+            }
+            let period = match indicator {
+                IndicatorConfig::Sma { period }
+                | IndicatorConfig::Ema { period }
+                | IndicatorConfig::Rsi { period }
+                | IndicatorConfig::BollingerBands { period, .. } => *period,
+            };
+            if period == 0 {
+                return Err(AlgorithmError::InvalidParams(format!(
+                    "Indicator {name} period must be greater than 0"
+                )));
+            }
+        }
+
+        for expression in config.buy.iter().chain(config.sell.iter()) {
+            Self::validate_expression(expression, &config.indicators)?;
+        }
+
+        Ok(config)
+    }
+
+    fn validate_expression(
+        expression: &Expression,
+        indicators: &HashMap<String, IndicatorConfig>,
+    ) -> Result<(), AlgorithmError> {
+        match expression {
+            Expression::And(expressions) | Expression::Or(expressions)
+                if expressions.is_empty() =>
+            {
+                Err(AlgorithmError::InvalidParams(
+                    "Expressions and and or need at least one operand".to_string(),
+                ))
+            }
+            Expression::And(expressions) | Expression::Or(expressions) => expressions
+                .iter()
+                .try_for_each(|expression| Self::validate_expression(expression, indicators)),
+            Expression::Not(expression) => Self::validate_expression(expression, indicators),
+            Expression::Compare { left, right, .. } => {
+                for operand in [left, right] {
+                    if let Operand::Name(name) = operand {
+                        let is_external = name
+                            .strip_prefix(EXTERNAL_PREFIX)
+                            .is_some_and(|series| !series.is_empty());
+                        if !indicators.contains_key(name)
+                            && !PRICE_FIELDS.contains(&name.as_str())
+                            && !is_external
+                        {
+                            return Err(AlgorithmError::InvalidParams(format!(
+                                "Expression references unknown indicator {name}"
+                            )));
+                        }
+                    }
+                }
+                Ok(())
+            }
+            Expression::Regime(_) => Ok(()),
+        }
+    }
+
+    /// Collects the names of the external series referenced by the expressions of the config.
+    fn external_names(config: &CompositeConfig) -> Vec<String> {
+        fn collect(expression: &Expression, names: &mut Vec<String>) {
+            match expression {
+                Expression::And(expressions) | Expression::Or(expressions) => expressions
+                    .iter()
+                    .for_each(|expression| collect(expression, names)),
+                Expression::Not(expression) => collect(expression, names),
+                Expression::Compare { left, right, .. } => {
+                    for operand in [left, right] {
+                        if let Operand::Name(name) = operand {
+                            if name.starts_with(EXTERNAL_PREFIX) && !names.contains(name) {
+                                names.push(name.clone());
+                            }
+                        }
+                    }
+                }
+                Expression::Regime(_) => {}
+            }
+        }
+
+        let mut names = vec![];
+        for expression in config.buy.iter().chain(config.sell.iter()) {
+            collect(expression, &mut names);
+        }
+        names
+    }
+
+    fn build_indicators(config: &CompositeConfig) -> Vec<(String, Indicator)> {
+        config
+            .indicators
+            .iter()
+            .map(|(name, indicator)| (name.clone(), Indicator::new(indicator)))
+            .collect()
+    }
+
+    /// Updates the sub-indicators with a k-line and the referenced external series with their
+    /// values as of the k-line, keeping the values of the previous k-line for crossings.
+    fn update_values(&mut self, kline: &Kline, data_points: &AlgorithmDataPointManager) {
+        let mut values: HashMap<String, f64> = PRICE_FIELDS
+            .iter()
+            .map(|field| field.to_string())
+            .zip([kline.open, kline.high, kline.low, kline.close, kline.volume])
+            .collect();
+
+        for (name, indicator) in self.indicators.iter_mut() {
+            if let Some(value) = indicator.next(kline.close) {
+                values.insert(name.clone(), value);
+            }
+        }
+
+        for name in self.external_names.iter() {
+            if let Some(value) = data_points.external(&name[EXTERNAL_PREFIX.len()..]) {
+                values.insert(name.clone(), value);
+            }
+        }
+
+        self.prev_values = std::mem::replace(&mut self.values, values);
+    }
+
+    fn resolve(operand: &Operand, values: &HashMap<String, f64>) -> Option<f64> {
+        match operand {
+            Operand::Constant(value) => Some(*value),
+            Operand::Name(name) => values.get(name).copied(),
+        }
+    }
+
+    /// Evaluates an expression, `None` while any indicator it references hasn't warmed up.
+    fn evaluate_expression(&self, expression: &Expression) -> Option<bool> {
+        let evaluate_all = |expressions: &[Expression]| {
+            expressions
+                .iter()
+                .map(|expression| self.evaluate_expression(expression))
+                .collect::<Option<Vec<bool>>>()
+        };
+
+        match expression {
+            Expression::And(expressions) => {
+                Some(evaluate_all(expressions)?.into_iter().all(|holds| holds))
+            }
+            Expression::Or(expressions) => {
+                Some(evaluate_all(expressions)?.into_iter().any(|holds| holds))
+            }
+            Expression::Not(expression) => self.evaluate_expression(expression).map(|holds| !holds),
+            Expression::Compare { left, op, right } => {
+                let left_value = Self::resolve(left, &self.values)?;
+                let right_value = Self::resolve(right, &self.values)?;
+
+                Some(match op {
+                    Comparison::Lt => left_value < right_value,
+                    Comparison::Le => left_value <= right_value,
+                    Comparison::Gt => left_value > right_value,
+                    Comparison::Ge => left_value >= right_value,
+                    Comparison::CrossesAbove | Comparison::CrossesBelow => {
+                        let prev_left = Self::resolve(left, &self.prev_values)?;
+                        let prev_right = Self::resolve(right, &self.prev_values)?;
+
+                        match op {
+                            Comparison::CrossesAbove => {
+                                prev_left <= prev_right && left_value > right_value
+                            }
+                            _ => prev_left >= prev_right && left_value < right_value,
+                        }
+                    }
+                })
+            }
+            Expression::Regime(regime) => self.regime.map(|current| current == *regime),
+        }
+    }
+}
+
+impl Algorithm for Composite {
+    fn evaluate(
+        &mut self,
+        kline: Kline,
+        data_points: &AlgorithmDataPointManager,
+    ) -> AlgorithmEvalResult {
+        self.update_values(&kline, data_points);
+        self.regime = data_points.regime();
+
+        let holds = |expression: &Option<Expression>| {
+            expression
+                .as_ref()
+                .is_some_and(|expression| self.evaluate_expression(expression) == Some(true))
+        };
+
+        if holds(&self.config.buy) {
+            AlgorithmEvalResult::Buy
+        } else if holds(&self.config.sell) {
+            AlgorithmEvalResult::Sell
+        } else {
+            AlgorithmEvalResult::Ignore
+        }
+    }
+
+    fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    fn get_params(&self) -> &Value {
+        &self.params
+    }
+
+    fn set_params(&mut self, params: Value) -> Result<(), AlgorithmError> {
+        let config = Self::parse_config(&params)?;
+
+        self.indicators = Self::build_indicators(&config);
+        self.external_names = Self::external_names(&config);
+        self.config = config;
+        self.values.clear();
+        self.prev_values.clear();
+        self.regime = None;
+        self.params = params;
+
+        Ok(())
+    }
+
+    fn warm_up(&mut self, data_points: &AlgorithmDataPointManager) {
+        for kline in data_points.iter() {
+            self.update_values(kline, data_points);
+        }
+    }
+
+    fn max_period(&self) -> usize {
+        self.config
+            .indicators
+            .values()
+            .map(|indicator| match indicator {
+                // one extra data point is needed to calculate the first price change
+                IndicatorConfig::Rsi { period } => period + 1,
+                IndicatorConfig::Sma { period }
+                | IndicatorConfig::Ema { period }
+                | IndicatorConfig::BollingerBands { period, .. } => *period,
+            })
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::harness::{assert_signals, Fixture};
+
+    /// Tests expressions referencing undeclared indicators are rejected.
+    #[test]
+    fn test_composite_rejects_unknown_names() {
+        let params = json!({
+            "indicators": { "rsi": { "type": "Rsi", "period": 14 } },
+            "buy": { "compare": { "left": "sma", "op": ">", "right": "close" } }
+        });
+
+        assert!(matches!(
+            Composite::new(Duration::from_secs(60), params),
+            Err(AlgorithmError::InvalidParams(_))
+        ));
+    }
+
+    /// Tests `and` and `or` expressions without operands are rejected.
+    #[test]
+    fn test_composite_rejects_empty_operands() {
+        for operator in ["and", "or"] {
+            let params = json!({
+                "indicators": {},
+                "buy": { operator: [] }
+            });
+
+            assert!(matches!(
+                Composite::new(Duration::from_secs(60), params),
+                Err(AlgorithmError::InvalidParams(_))
+            ));
+        }
+    }
+
+    /// Tests negated expressions don't hold while their indicators are warming up.
+    #[test]
+    fn test_composite_waits_for_warm_up() {
+        let params = json!({
+            "indicators": { "sma": { "type": "Sma", "period": 3 } },
+            "buy": { "not": { "compare": { "left": "close", "op": "<", "right": "sma" } } }
+        });
+        let mut composite = Composite::new(Duration::from_secs(60), params).unwrap();
+
+        assert_signals(&mut composite, Fixture::TrendingUp, "..BBB");
+    }
+
+    /// Tests regime expressions are undecided until the market regime can be classified.
+    #[test]
+    fn test_composite_regime() {
+        let params = json!({
+            "indicators": {},
+            "buy": { "regime": "TrendingUp" },
+            "sell": { "not": { "regime": "TrendingUp" } }
+        });
+        let mut composite = Composite::new(Duration::from_secs(60), params).unwrap();
+
+        assert_signals(
+            &mut composite,
+            Fixture::TrendingUp,
+            &format!("{}BB", ".".repeat(20)),
+        );
+        let mut composite =
+            Composite::new(Duration::from_secs(60), composite.params.clone()).unwrap();
+        assert_signals(
+            &mut composite,
+            Fixture::Choppy,
+            &format!("{}SS", ".".repeat(20)),
+        );
+    }
+
+    /// Tests expressions compare external series as of the evaluated k-line and are undecided
+    /// while a series has no value.
+    #[test]
+    fn test_composite_external_series() {
+        let params = json!({
+            "indicators": {},
+            "buy": { "compare": { "left": "external:fear_greed", "op": "<", "right": 25 } }
+        });
+        let mut composite = Composite::new(Duration::from_secs(60), params).unwrap();
+        let mut data_points = AlgorithmDataPointManager::new(10);
+        let klines = Fixture::Flat.klines(3);
+
+        let mut evaluate = |kline: &Kline, external: &[(&str, f64)]| {
+            data_points.set_external(
+                external
+                    .iter()
+                    .map(|(series, value)| (series.to_string(), *value))
+                    .collect(),
+            );
+            data_points.push(kline.clone());
+            composite.evaluate(kline.clone(), &data_points)
+        };
+
+        assert!(matches!(
+            evaluate(&klines[0], &[]),
+            AlgorithmEvalResult::Ignore
+        ));
+        assert!(matches!(
+            evaluate(&klines[1], &[("fear_greed", 20.0)]),
+            AlgorithmEvalResult::Buy
+        ));
+        assert!(matches!(
+            evaluate(&klines[2], &[("fear_greed", 40.0)]),
+            AlgorithmEvalResult::Ignore
+        ));
+    }
+}
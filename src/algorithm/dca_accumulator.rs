@@ -0,0 +1,173 @@
+use crate::algorithm::indicators::RollingWindow;
+use crate::market::kline::Kline;
+use crate::strategy::{
+    algorithm::Algorithm,
+    data_points::AlgorithmDataPointManager,
+    types::{AlgorithmError, AlgorithmEvalResult},
+};
+use crate::utils::number::parse_usize_from_value;
+use crate::utils::time::MIN_AS_MILI;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+/// Dollar cost averaging, enters a long position at a fixed time interval regardless of price.
+///
+/// With `dip_ma_period` set, a due entry is held back until price closes below the moving
+/// average, so accumulation happens on dips.
+pub struct DcaAccumulator {
+    interval: Duration,
+    params: Value,
+    buy_interval_mins: usize,
+    dip_ma_period: Option<usize>,
+    dip_ma: Option<RollingWindow>,
+    last_buy_time: Option<u64>, // Close time of the kline of the last entry
+}
+
+impl DcaAccumulator {
+    /// Returns the JSON schema of the parameters accepted by the algorithm.
+    pub fn params_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "buy_interval_mins": { "type": "integer", "minimum": 1, "default": 1440 },
+                "dip_ma_period": { "type": "integer", "minimum": 1 }
+            },
+            "required": []
+        })
+    }
+
+    pub fn new(interval: Duration, params: Value) -> Result<Self, AlgorithmError> {
+        let buy_interval_mins =
+            parse_usize_from_value("buy_interval_mins", &params).unwrap_or(1440); // Default to buying daily
+        let dip_ma_period = parse_usize_from_value("dip_ma_period", &params).ok();
+
+        if buy_interval_mins == 0 {
+            return Err(AlgorithmError::InvalidParams(
+                "buy_interval_mins must be greater than 0".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            interval,
+            params,
+            buy_interval_mins,
+            dip_ma_period,
+            dip_ma: dip_ma_period.map(RollingWindow::new),
+            last_buy_time: None,
+        })
+    }
+
+    fn is_buy_due(&self, kline: &Kline) -> bool {
+        match self.last_buy_time {
+            Some(last_buy_time) => {
+                kline.close_time >= last_buy_time + self.buy_interval_mins as u64 * MIN_AS_MILI
+            }
+            None => true,
+        }
+    }
+}
+
+impl Algorithm for DcaAccumulator {
+    fn evaluate(
+        &mut self,
+        kline: Kline,
+        _data_points: &AlgorithmDataPointManager,
+    ) -> AlgorithmEvalResult {
+        // keep the moving average up to date even when no entry is due
+        let is_dip = match self.dip_ma.as_mut() {
+            Some(dip_ma) => match dip_ma.next(kline.close) {
+                Some(ma) => kline.close < ma,
+                None => false, // Not enough data
+            },
+            None => true,
+        };
+
+        if !self.is_buy_due(&kline) || !is_dip {
+            return AlgorithmEvalResult::Ignore;
+        }
+
+        self.last_buy_time = Some(kline.close_time);
+
+        AlgorithmEvalResult::Long(1.0)
+    }
+
+    fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    fn get_params(&self) -> &Value {
+        &self.params
+    }
+
+    fn set_params(&mut self, params: Value) -> Result<(), AlgorithmError> {
+        if let Ok(buy_interval_mins) = parse_usize_from_value("buy_interval_mins", &params) {
+            if buy_interval_mins == 0 {
+                return Err(AlgorithmError::InvalidParams(
+                    "buy_interval_mins must be greater than 0".to_string(),
+                ));
+            }
+            self.buy_interval_mins = buy_interval_mins;
+        }
+
+        let dip_ma_period = parse_usize_from_value("dip_ma_period", &params).ok();
+        if dip_ma_period != self.dip_ma_period {
+            self.dip_ma_period = dip_ma_period;
+            self.dip_ma = dip_ma_period.map(RollingWindow::new);
+        }
+
+        self.params = params;
+        Ok(())
+    }
+
+    fn max_period(&self) -> usize {
+        self.dip_ma_period.unwrap_or(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a one minute kline closing at the end of the given minute.
+    fn kline(minute: u64, close: f64) -> Kline {
+        Kline {
+            open_time: minute * MIN_AS_MILI,
+            close_time: (minute + 1) * MIN_AS_MILI - 1,
+            close,
+            ..Default::default()
+        }
+    }
+
+    /// Tests entries are due once the buy interval passed since the close of the last entry and
+    /// only taken below the dip moving average.
+    #[test]
+    fn test_dca_accumulator_entries() {
+        let mut dca = DcaAccumulator::new(
+            Duration::from_secs(60),
+            json!({"buy_interval_mins": 3, "dip_ma_period": 2}),
+        )
+        .unwrap();
+        let data_points = AlgorithmDataPointManager::new(1);
+
+        assert!(dca.is_buy_due(&kline(0, 10.0)));
+        // the moving average isn't ready yet
+        assert!(matches!(
+            dca.evaluate(kline(0, 10.0), &data_points),
+            AlgorithmEvalResult::Ignore
+        ));
+        // 9 is below the average of 9.5
+        assert!(matches!(
+            dca.evaluate(kline(1, 9.0), &data_points),
+            AlgorithmEvalResult::Long(confidence) if confidence == 1.0
+        ));
+        assert_eq!(dca.last_buy_time, Some(2 * MIN_AS_MILI - 1));
+
+        assert!(!dca.is_buy_due(&kline(3, 8.0)));
+        assert!(dca.is_buy_due(&kline(4, 8.0)));
+        // 10 is above the average of 9.5
+        assert!(matches!(
+            dca.evaluate(kline(4, 10.0), &data_points),
+            AlgorithmEvalResult::Ignore
+        ));
+    }
+}
@@ -0,0 +1,272 @@
+use crate::market::kline::Kline;
+use crate::strategy::{
+    algorithm::Algorithm,
+    data_points::AlgorithmDataPointManager,
+    types::{AlgorithmError, AlgorithmEvalResult},
+};
+use crate::utils::number::parse_usize_from_value;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+const DEFAULT_LEVELS: [f64; 3] = [0.382, 0.5, 0.618];
+
+/// Index, from the oldest retained k-line, and price of a swing high or low.
+type Swing = (usize, f64);
+
+/// Detects the latest swing high and swing low and trades pullbacks to Fibonacci retracement
+/// levels of the swing.
+///
+/// A swing high (low) is a kline whose high (low) is above (below) the highs (lows) of the
+/// `swing_strength` klines on each side, where the first of equal extremes is the swing. After an upswing, a kline wicking down to a level
+/// and closing back above it is a buy, with the stop loss placed below the swing low. Downswings
+/// are traded symmetrically.
+pub struct FibRetracement {
+    interval: Duration,
+    params: Value,
+    swing_strength: usize,
+    lookback: usize,
+    levels: Vec<f64>,
+    stop_buffer_pct: f64, // Distance of the stop loss beyond the swing, e.g. 0.001 for 0.1%
+    stop_loss: Option<f64>,
+}
+
+impl FibRetracement {
+    /// Returns the JSON schema of the parameters accepted by the algorithm.
+    pub fn params_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "swing_strength": { "type": "integer", "minimum": 1, "default": 5 },
+                "lookback": { "type": "integer", "minimum": 3, "default": 100 },
+                "levels": {
+                    "type": "array",
+                    "items": { "type": "number", "exclusiveMinimum": 0, "exclusiveMaximum": 1 },
+                    "default": DEFAULT_LEVELS
+                },
+                "stop_buffer_pct": { "type": "number", "minimum": 0, "default": 0.001 }
+            },
+            "required": []
+        })
+    }
+
+    pub fn new(interval: Duration, params: Value) -> Result<Self, AlgorithmError> {
+        let swing_strength = parse_usize_from_value("swing_strength", &params)
+            .unwrap_or(5)
+            .max(1);
+        let lookback = parse_usize_from_value("lookback", &params).unwrap_or(100);
+        let levels = Self::parse_levels(&params)?.unwrap_or_else(|| DEFAULT_LEVELS.to_vec());
+        let stop_buffer_pct = params
+            .get("stop_buffer_pct")
+            .and_then(Value::as_f64)
+            .unwrap_or(0.001)
+            .max(0.0);
+
+        Ok(Self {
+            interval,
+            params,
+            swing_strength,
+            lookback,
+            levels,
+            stop_buffer_pct,
+            stop_loss: None,
+        })
+    }
+
+    /// Parses the retracement levels, each must be a ratio between 0 and 1.
+    ///
+    /// # Returns
+    ///
+    /// The levels, `None` if the params don't set them, or an `AlgorithmError` if any level
+    /// is invalid.
+    fn parse_levels(params: &Value) -> Result<Option<Vec<f64>>, AlgorithmError> {
+        let levels = match params.get("levels").and_then(Value::as_array) {
+            Some(levels) => levels,
+            None => return Ok(None),
+        };
+
+        levels
+            .iter()
+            .map(|level| match level.as_f64() {
+                Some(level) if level > 0.0 && level < 1.0 => Ok(level),
+                _ => Err(AlgorithmError::InvalidParams(format!(
+                    "Invalid retracement level {level}, levels must be between 0 and 1"
+                ))),
+            })
+            .collect::<Result<Vec<f64>, AlgorithmError>>()
+            .map(Some)
+    }
+
+    /// Finds the latest confirmed swing high and swing low within the lookback.
+    ///
+    /// # Returns
+    ///
+    /// The latest swing high and swing low.
+    fn find_swings(
+        &self,
+        data_points: &AlgorithmDataPointManager,
+    ) -> (Option<Swing>, Option<Swing>) {
+        let n = self.swing_strength;
+        let len = data_points.len();
+        if len < 2 * n + 1 {
+            return (None, None);
+        }
+
+        let first = n.max(len.saturating_sub(self.lookback));
+        let last = len - 1 - n; // a swing needs n klines after it to be confirmed
+
+        let mut swing_high = None;
+        let mut swing_low = None;
+
+        for i in (first..=last).rev() {
+            let (before, after) = (i - n..i, i + 1..=i + n);
+
+            if swing_high.is_none()
+                && before
+                    .clone()
+                    .all(|j| data_points[j].high < data_points[i].high)
+                && after
+                    .clone()
+                    .all(|j| data_points[j].high <= data_points[i].high)
+            {
+                swing_high = Some((i, data_points[i].high));
+            }
+            if swing_low.is_none()
+                && before
+                    .clone()
+                    .all(|j| data_points[j].low > data_points[i].low)
+                && after
+                    .clone()
+                    .all(|j| data_points[j].low >= data_points[i].low)
+            {
+                swing_low = Some((i, data_points[i].low));
+            }
+            if swing_high.is_some() && swing_low.is_some() {
+                break;
+            }
+        }
+
+        (swing_high, swing_low)
+    }
+}
+
+impl Algorithm for FibRetracement {
+    fn evaluate(
+        &mut self,
+        kline: Kline,
+        data_points: &AlgorithmDataPointManager,
+    ) -> AlgorithmEvalResult {
+        self.stop_loss = None;
+
+        let (high_idx, high, low_idx, low) = match self.find_swings(data_points) {
+            (Some((high_idx, high)), Some((low_idx, low))) => (high_idx, high, low_idx, low),
+            _ => return AlgorithmEvalResult::Ignore, // No swing to retrace
+        };
+        let range = high - low;
+
+        if low_idx < high_idx {
+            // Upswing, buy a pullback holding above a retracement level
+            let touched = self
+                .levels
+                .iter()
+                .map(|level| high - range * level)
+                .any(|price| kline.low <= price && kline.close > price);
+            if touched {
+                self.stop_loss = Some(low * (1.0 - self.stop_buffer_pct));
+                return AlgorithmEvalResult::Buy;
+            }
+        } else {
+            // Downswing, sell a rally rejected below a retracement level
+            let touched = self
+                .levels
+                .iter()
+                .map(|level| low + range * level)
+                .any(|price| kline.high >= price && kline.close < price);
+            if touched {
+                self.stop_loss = Some(high * (1.0 + self.stop_buffer_pct));
+                return AlgorithmEvalResult::Sell;
+            }
+        }
+
+        AlgorithmEvalResult::Ignore
+    }
+
+    fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    fn get_params(&self) -> &Value {
+        &self.params
+    }
+
+    fn set_params(&mut self, params: Value) -> Result<(), AlgorithmError> {
+        if let Some(levels) = Self::parse_levels(&params)? {
+            self.levels = levels;
+        }
+        if let Ok(swing_strength) = parse_usize_from_value("swing_strength", &params) {
+            self.swing_strength = swing_strength.max(1);
+        }
+        if let Ok(lookback) = parse_usize_from_value("lookback", &params) {
+            self.lookback = lookback;
+        }
+        if let Some(stop_buffer_pct) = params.get("stop_buffer_pct").and_then(Value::as_f64) {
+            self.stop_buffer_pct = stop_buffer_pct.max(0.0);
+        }
+
+        self.params = params;
+        Ok(())
+    }
+
+    fn stop_loss(&self) -> Option<f64> {
+        self.stop_loss
+    }
+
+    fn max_period(&self) -> usize {
+        self.lookback
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests a pullback to the 50% retracement of an upswing from 90 to 110 is bought with the
+    /// stop loss below the swing low.
+    #[test]
+    fn test_fib_retracement_upswing() {
+        let mut fib = FibRetracement::new(
+            Duration::from_secs(60),
+            json!({"swing_strength": 1, "lookback": 10}),
+        )
+        .unwrap();
+        let kline = |high: f64, low: f64, close: f64| Kline {
+            high,
+            low,
+            close,
+            ..Default::default()
+        };
+
+        let mut data_points = AlgorithmDataPointManager::new(10);
+        for kline in [
+            kline(101.0, 99.0, 100.0),
+            kline(100.0, 90.0, 95.0),
+            kline(105.0, 95.0, 104.0),
+            kline(110.0, 100.0, 108.0),
+            kline(106.0, 101.0, 103.0),
+        ] {
+            data_points.push(kline);
+        }
+        // the 50% level at 100 is touched and held
+        let pullback = kline(102.0, 99.5, 101.0);
+        data_points.push(pullback.clone());
+
+        assert_eq!(
+            fib.find_swings(&data_points),
+            (Some((3, 110.0)), Some((1, 90.0)))
+        );
+        assert!(matches!(
+            fib.evaluate(pullback, &data_points),
+            AlgorithmEvalResult::Buy
+        ));
+        assert!((fib.stop_loss().unwrap() - 89.91).abs() < 1e-9);
+    }
+}
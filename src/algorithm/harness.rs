@@ -0,0 +1,399 @@
+use crate::{
+    market::{interval::Interval, kline::Kline},
+    strategy::{
+        algorithm::Algorithm,
+        data_points::{retention_for_period, AlgorithmDataPointManager},
+        types::AlgorithmEvalResult,
+    },
+    utils::time::MIN_AS_MILI,
+};
+
+/// Canned market scenarios used to validate algorithms against known signal sequences.
+#[derive(Debug, Clone, Copy)]
+pub enum Fixture {
+    /// Price doesn't move.
+    Flat,
+    /// Price rises by one on every kline.
+    TrendingUp,
+    /// Price falls by one on every kline.
+    TrendingDown,
+    /// Price oscillates around a fixed level.
+    Choppy,
+    /// Price is flat, gaps up by 20% half way through and stays flat.
+    Gap,
+    /// Price rises by one on every kline until half way through, then falls by one.
+    Reversal,
+    /// Price is flat until half way through, then rises by one on every kline.
+    Breakout,
+}
+
+impl Fixture {
+    /// Builds the fixture's klines.
+    ///
+    /// # Arguments
+    ///
+    /// * `len` - The number of klines to build.
+    ///
+    /// # Returns
+    ///
+    /// A vector of one minute `Kline` structs sorted by open time.
+    pub fn klines(&self, len: usize) -> Vec<Kline> {
+        let choppy = [0.0, 3.0, -2.0, 4.0, -3.0, 2.0];
+
+        let mut klines: Vec<Kline> = Vec::with_capacity(len);
+        let mut prev_close = 100.0;
+
+        for i in 0..len {
+            let close = match self {
+                Fixture::Flat => 100.0,
+                Fixture::TrendingUp => 100.0 + i as f64,
+                Fixture::TrendingDown => 100.0 - i as f64,
+                Fixture::Choppy => 100.0 + choppy[i % choppy.len()],
+                Fixture::Gap if i < len / 2 => 100.0,
+                Fixture::Gap => 120.0,
+                Fixture::Reversal if i < len / 2 => 100.0 + i as f64,
+                Fixture::Reversal => 100.0 + (2 * (len / 2)) as f64 - i as f64 - 2.0,
+                Fixture::Breakout if i < len / 2 => 100.0,
+                Fixture::Breakout => 100.0 + (i - len / 2 + 1) as f64,
+            };
+            let open = match self {
+                Fixture::Gap if i == len / 2 => close,
+                _ => prev_close,
+            };
+            let open_time = i as u64 * MIN_AS_MILI;
+
+            klines.push(Kline {
+                symbol: "BTCUSDT".to_string(),
+                interval: Interval::Min1,
+                open,
+                high: open.max(close) + 0.5,
+                low: open.min(close) - 0.5,
+                close,
+                volume: 10.0,
+                open_time,
+                close_time: open_time + MIN_AS_MILI - 1,
+            });
+
+            prev_close = close;
+        }
+
+        klines
+    }
+}
+
+/// Feeds klines through an algorithm and records the generated signals.
+///
+/// # Arguments
+///
+/// * `algorithm` - The algorithm to evaluate.
+/// * `klines` - The klines to evaluate in order.
+///
+/// # Returns
+///
+/// A string with one character per kline, `B` for buy, `S` for sell and `.` for ignore.
+pub fn run_signals(algorithm: &mut dyn Algorithm, klines: &[Kline]) -> String {
+    let mut data_points =
+        AlgorithmDataPointManager::new(retention_for_period(algorithm.max_period()));
+
+    klines
+        .iter()
+        .map(|kline| {
+            data_points.push(kline.clone());
+            algorithm.evaluate(kline.clone(), &data_points)
+        })
+        .map(|eval_result| match eval_result {
+            AlgorithmEvalResult::Buy | AlgorithmEvalResult::Long(_) => 'B',
+            AlgorithmEvalResult::Sell | AlgorithmEvalResult::Short(_) => 'S',
+            AlgorithmEvalResult::Ignore => '.',
+        })
+        .collect()
+}
+
+/// Asserts an algorithm generates the expected signal sequence for a fixture.
+///
+/// # Arguments
+///
+/// * `algorithm` - The algorithm to evaluate.
+/// * `fixture` - The market scenario to feed through the algorithm.
+/// * `expected` - The expected signals, see `run_signals`, its length sets the number of klines.
+///
+/// # Panics
+///
+/// Panics if the generated signals differ from the expected signals.
+pub fn assert_signals(algorithm: &mut dyn Algorithm, fixture: Fixture, expected: &str) {
+    let klines = fixture.klines(expected.len());
+    let signals = run_signals(algorithm, &klines);
+
+    assert_eq!(
+        signals, expected,
+        "Unexpected signals for fixture {fixture:?}"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::{
+        cci::Cci, chaikin_money_flow::ChaikinMoneyFlow, composite::Composite,
+        dca_accumulator::DcaAccumulator, fib_retracement::FibRetracement, hull_ma::HullMa,
+        ma_simple::SimpleMovingAverage, ma_three_crossover::ThreeMaCrossover, obv::Obv, rsi::Rsi,
+        trix::Trix, volume_profile::VolumeProfile, vwap_reversion::VwapReversion,
+    };
+    use serde_json::json;
+    use std::time::Duration;
+
+    const INTERVAL: Duration = Duration::from_secs(60);
+
+    /// Tests fixtures build the requested number of contiguous klines.
+    #[test]
+    fn test_fixture_klines() {
+        let klines = Fixture::Gap.klines(20);
+
+        assert_eq!(klines.len(), 20);
+        assert_eq!(klines[9].close, 100.0);
+        assert_eq!(klines[10].open, 120.0);
+
+        let klines = Fixture::Reversal.klines(20);
+        assert_eq!(klines[9].close, 109.0);
+        assert_eq!(klines[10].close, 108.0);
+        let klines = Fixture::Breakout.klines(20);
+        assert_eq!(klines[9].close, 100.0);
+        assert_eq!(klines[10].close, 101.0);
+        assert!(klines
+            .windows(2)
+            .all(|pair| pair[1].open_time == pair[0].close_time + 1));
+    }
+
+    /// Tests golden signals of `SimpleMovingAverage`.
+    #[test]
+    fn test_simple_moving_average_signals() {
+        let build = || SimpleMovingAverage::new(INTERVAL, json!({"sma_period": 5})).unwrap();
+
+        assert_signals(&mut build(), Fixture::Flat, "....SSSSSSSSSSSSSSSS");
+        assert_signals(&mut build(), Fixture::TrendingUp, "....BBBBBBBBBBBBBBBB");
+        assert_signals(&mut build(), Fixture::TrendingDown, "....SSSSSSSSSSSSSSSS");
+        assert_signals(&mut build(), Fixture::Choppy, "....SBSBSBSBSBSBSBSB");
+        assert_signals(&mut build(), Fixture::Gap, "....SSSSSSBBBBSSSSSS");
+        assert_signals(&mut build(), Fixture::Reversal, "....BBBBBBBSSSSSSSSS");
+        assert_signals(&mut build(), Fixture::Breakout, "....SSSSSSBBBBBBBBBB");
+    }
+
+    /// Tests golden signals of `Rsi`.
+    #[test]
+    fn test_rsi_signals() {
+        let build = || Rsi::new(INTERVAL, json!({"rsi_period": 5})).unwrap();
+
+        assert_signals(&mut build(), Fixture::Flat, "....................");
+        assert_signals(&mut build(), Fixture::TrendingUp, ".....SSSSSSSSSSSSSSS");
+        assert_signals(&mut build(), Fixture::TrendingDown, ".....BBBBBBBBBBBBBBB");
+        assert_signals(&mut build(), Fixture::Choppy, "....................");
+        assert_signals(&mut build(), Fixture::Gap, "..........SSSSSSSSSS");
+        assert_signals(&mut build(), Fixture::Reversal, ".....SSSSSS....BBBBB");
+        assert_signals(&mut build(), Fixture::Breakout, "..........SSSSSSSSSS");
+    }
+
+    /// Tests golden signals of `ThreeMaCrossover`.
+    #[test]
+    fn test_three_ma_crossover_signals() {
+        let build = || {
+            ThreeMaCrossover::new(
+                INTERVAL,
+                json!({"short_period": 3, "medium_period": 5, "long_period": 8}),
+            )
+            .unwrap()
+        };
+
+        assert_signals(&mut build(), Fixture::Flat, "....................");
+        assert_signals(&mut build(), Fixture::TrendingUp, ".......BBBBBBBBBBBBB");
+        assert_signals(&mut build(), Fixture::TrendingDown, ".......SSSSSSSSSSSSS");
+        assert_signals(&mut build(), Fixture::Choppy, ".......B.BSBSB.BSBSB");
+        assert_signals(&mut build(), Fixture::Gap, "..........BBBB......");
+        assert_signals(&mut build(), Fixture::Reversal, ".......BBBBB..SSSSSS");
+        assert_signals(&mut build(), Fixture::Breakout, "..........BBBBBBBBBB");
+    }
+
+    /// Tests golden signals of `Cci` and that a period of 0 is refused.
+    #[test]
+    fn test_cci_signals() {
+        let build = || Cci::new(INTERVAL, json!({"period": 5, "threshold": 100.0})).unwrap();
+
+        assert_signals(&mut build(), Fixture::Flat, "....................");
+        assert_signals(&mut build(), Fixture::TrendingUp, "....SSSSSSSSSSSSSSSS");
+        assert_signals(&mut build(), Fixture::TrendingDown, "....BBBBBBBBBBBBBBBB");
+        assert_signals(&mut build(), Fixture::Choppy, "....................");
+        assert_signals(&mut build(), Fixture::Gap, "..........S.........");
+        assert_signals(&mut build(), Fixture::Reversal, "....SSSSSS..BBBBBBBB");
+        assert_signals(&mut build(), Fixture::Breakout, "..........SSSSSSSSSS");
+
+        assert!(Cci::new(INTERVAL, json!({"period": 0})).is_err());
+        let mut cci = build();
+        assert!(cci.set_params(json!({"period": 0})).is_err());
+        assert_signals(&mut cci, Fixture::TrendingUp, "....SSSSSSSSSSSSSSSS");
+    }
+
+    /// Tests golden signals of `Obv`.
+    #[test]
+    fn test_obv_signals() {
+        let build = || Obv::new(INTERVAL, json!({"signal_period": 3})).unwrap();
+
+        assert_signals(&mut build(), Fixture::Flat, "....................");
+        assert_signals(&mut build(), Fixture::TrendingUp, "....................");
+        assert_signals(&mut build(), Fixture::TrendingDown, "....................");
+        assert_signals(&mut build(), Fixture::Choppy, "...BSBSBSBSBSBSBSBSB");
+        assert_signals(&mut build(), Fixture::Gap, "..........B.........");
+        assert_signals(&mut build(), Fixture::Reversal, "..........S.........");
+        assert_signals(&mut build(), Fixture::Breakout, "..........B.........");
+    }
+
+    /// Tests golden signals of `VwapReversion`.
+    #[test]
+    fn test_vwap_reversion_signals() {
+        let build = || {
+            VwapReversion::new(
+                INTERVAL,
+                json!({"std_dev_multiplier": 1.0, "min_session_klines": 5}),
+            )
+            .unwrap()
+        };
+
+        assert_signals(&mut build(), Fixture::Flat, "....................");
+        assert_signals(&mut build(), Fixture::TrendingUp, "....SSSSSSSSSSSSSSSS");
+        assert_signals(&mut build(), Fixture::TrendingDown, "....BBBBBBBBBBBBBBBB");
+        assert_signals(&mut build(), Fixture::Choppy, "....BS.SBSBS.SBSBS.S");
+        assert_signals(&mut build(), Fixture::Gap, "..........SSSSSSSSS.");
+        assert_signals(&mut build(), Fixture::Reversal, "....SSSSSSS.....BBBB");
+        assert_signals(&mut build(), Fixture::Breakout, "..........SSSSSSSSSS");
+    }
+
+    /// Tests golden signals of `DcaAccumulator`, with and without dip buying.
+    #[test]
+    fn test_dca_accumulator_signals() {
+        let build = || DcaAccumulator::new(INTERVAL, json!({"buy_interval_mins": 5})).unwrap();
+
+        assert_signals(&mut build(), Fixture::Flat, "B....B....B....B....");
+        assert_signals(&mut build(), Fixture::Choppy, "B....B....B....B....");
+
+        let build = || {
+            DcaAccumulator::new(
+                INTERVAL,
+                json!({"buy_interval_mins": 5, "dip_ma_period": 3}),
+            )
+            .unwrap()
+        };
+
+        assert_signals(&mut build(), Fixture::Flat, "....................");
+        assert_signals(&mut build(), Fixture::TrendingUp, "....................");
+        assert_signals(&mut build(), Fixture::TrendingDown, "..B....B....B....B..");
+        assert_signals(&mut build(), Fixture::Choppy, "..B.....B.....B.....");
+        assert_signals(&mut build(), Fixture::Reversal, "..........B....B....");
+        assert_signals(&mut build(), Fixture::Breakout, "....................");
+    }
+
+    /// Tests golden signals of `VolumeProfile`.
+    #[test]
+    fn test_volume_profile_signals() {
+        let build = || VolumeProfile::new(INTERVAL, json!({"period": 8, "bins": 6})).unwrap();
+
+        assert_signals(&mut build(), Fixture::Flat, "....................");
+        assert_signals(&mut build(), Fixture::TrendingUp, ".........BBBBBBBBBBB");
+        assert_signals(&mut build(), Fixture::TrendingDown, ".........SSSSSSSSSSS");
+        assert_signals(&mut build(), Fixture::Choppy, "...........B.S...B.S");
+        assert_signals(&mut build(), Fixture::Gap, "..........BBB.......");
+        assert_signals(&mut build(), Fixture::Reversal, ".........B....SS..SS");
+        assert_signals(&mut build(), Fixture::Breakout, "...........BBBBBB.BB");
+    }
+
+    /// Tests golden signals of `FibRetracement`.
+    #[test]
+    fn test_fib_retracement_signals() {
+        let build =
+            || FibRetracement::new(INTERVAL, json!({"swing_strength": 2, "lookback": 20})).unwrap();
+
+        assert_signals(&mut build(), Fixture::Flat, "....................");
+        assert_signals(&mut build(), Fixture::TrendingUp, "....................");
+        assert_signals(&mut build(), Fixture::TrendingDown, "....................");
+        assert_signals(&mut build(), Fixture::Choppy, "......S.S.SBS.S.SBS.");
+        assert_signals(&mut build(), Fixture::Gap, "....................");
+        assert_signals(&mut build(), Fixture::Reversal, "....................");
+        assert_signals(&mut build(), Fixture::Breakout, "....................");
+    }
+
+    /// Tests golden signals of `HullMa`.
+    #[test]
+    fn test_hull_ma_signals() {
+        let build = || HullMa::new(INTERVAL, json!({"fast_period": 3, "slow_period": 6})).unwrap();
+
+        assert_signals(&mut build(), Fixture::Flat, "....................");
+        assert_signals(&mut build(), Fixture::TrendingUp, "....................");
+        assert_signals(&mut build(), Fixture::TrendingDown, "....................");
+        assert_signals(&mut build(), Fixture::Choppy, "........SBSB..SBSB..");
+        assert_signals(&mut build(), Fixture::Gap, "..........B.S.......");
+        assert_signals(&mut build(), Fixture::Reversal, "..........S.........");
+        assert_signals(&mut build(), Fixture::Breakout, "..........B.........");
+    }
+
+    /// Tests golden signals of `Trix`.
+    #[test]
+    fn test_trix_signals() {
+        let build = || Trix::new(INTERVAL, json!({"period": 2, "signal_period": 2})).unwrap();
+
+        assert_signals(&mut build(), Fixture::Flat, "....................");
+        assert_signals(&mut build(), Fixture::TrendingUp, ".........S..........");
+        assert_signals(&mut build(), Fixture::TrendingDown, "....................");
+        assert_signals(&mut build(), Fixture::Choppy, "........SBSB..SBSB..");
+        assert_signals(&mut build(), Fixture::Gap, "..........B.S.......");
+        assert_signals(&mut build(), Fixture::Reversal, ".........S..........");
+        assert_signals(&mut build(), Fixture::Breakout, "..........B.......S.");
+    }
+
+    /// Tests golden signals of `ChaikinMoneyFlow`.
+    #[test]
+    fn test_chaikin_money_flow_signals() {
+        let build =
+            || ChaikinMoneyFlow::new(INTERVAL, json!({"period": 3, "ma_period": 5})).unwrap();
+
+        assert_signals(&mut build(), Fixture::Flat, "....................");
+        assert_signals(&mut build(), Fixture::TrendingUp, "....BBBBBBBBBBBBBBBB");
+        assert_signals(&mut build(), Fixture::TrendingDown, "....SSSSSSSSSSSSSSSS");
+        assert_signals(&mut build(), Fixture::Choppy, "....SBSBSBSBSBSBSBSB");
+        assert_signals(&mut build(), Fixture::Gap, "....................");
+        assert_signals(&mut build(), Fixture::Reversal, "....BBBBBBBSSSSSSSSS");
+        assert_signals(&mut build(), Fixture::Breakout, "..........BBBBBBBBBB");
+    }
+
+    /// Tests golden signals of `Composite`, buying fast/slow SMA crossovers unless the RSI is
+    /// overbought and selling crossunders or closes above the upper Bollinger Band.
+    #[test]
+    fn test_composite_signals() {
+        let build = || {
+            Composite::new(
+                INTERVAL,
+                json!({
+                    "indicators": {
+                        "fast": {"type": "Sma", "period": 3},
+                        "slow": {"type": "Sma", "period": 5},
+                        "rsi": {"type": "Rsi", "period": 5},
+                        "upper": {"type": "BollingerBands", "period": 5, "band": "upper"}
+                    },
+                    "buy": {"and": [
+                        {"compare": {"left": "fast", "op": "crosses_above", "right": "slow"}},
+                        {"not": {"compare": {"left": "rsi", "op": ">", "right": 70}}}
+                    ]},
+                    "sell": {"or": [
+                        {"compare": {"left": "fast", "op": "crosses_below", "right": "slow"}},
+                        {"compare": {"left": "close", "op": ">", "right": "upper"}}
+                    ]}
+                }),
+            )
+            .unwrap()
+        };
+
+        assert_signals(&mut build(), Fixture::Flat, "....................");
+        assert_signals(&mut build(), Fixture::TrendingUp, "....................");
+        assert_signals(&mut build(), Fixture::TrendingDown, "....................");
+        assert_signals(&mut build(), Fixture::Choppy, ".....BSB..SBSB..SBSB");
+        assert_signals(&mut build(), Fixture::Gap, "....................");
+        assert_signals(&mut build(), Fixture::Reversal, "............S.......");
+        assert_signals(&mut build(), Fixture::Breakout, "..........S.........");
+    }
+}
@@ -0,0 +1,153 @@
+use crate::algorithm::indicators::Hma;
+use crate::market::kline::Kline;
+use crate::strategy::{
+    algorithm::Algorithm,
+    data_points::AlgorithmDataPointManager,
+    types::{AlgorithmError, AlgorithmEvalResult},
+};
+use crate::utils::number::parse_usize_from_value;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+/// Fast and slow Hull moving average crossover.
+///
+/// The HMA reacts faster, with less lag, than the SMA and EMA used by the other crossover
+/// algorithms, so crossovers are signalled earlier in a new trend.
+pub struct HullMa {
+    interval: Duration,
+    params: Value,
+    fast_period: usize,
+    slow_period: usize,
+    fast_hma: Hma,
+    slow_hma: Hma,
+    prev_diff: Option<f64>, // Previous fast minus slow HMA, to detect crossovers
+}
+
+impl HullMa {
+    /// Returns the JSON schema of the parameters accepted by the algorithm.
+    pub fn params_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "fast_period": { "type": "integer", "minimum": 1, "default": 9 },
+                "slow_period": { "type": "integer", "minimum": 2, "default": 21 }
+            },
+            "required": []
+        })
+    }
+
+    pub fn new(interval: Duration, params: Value) -> Result<Self, AlgorithmError> {
+        let fast_period = parse_usize_from_value("fast_period", &params).unwrap_or(9);
+        let slow_period = parse_usize_from_value("slow_period", &params).unwrap_or(21);
+        Self::validate_periods(fast_period, slow_period)?;
+
+        Ok(Self {
+            interval,
+            params,
+            fast_period,
+            slow_period,
+            fast_hma: Hma::new(fast_period),
+            slow_hma: Hma::new(slow_period),
+            prev_diff: None,
+        })
+    }
+
+    fn validate_periods(fast_period: usize, slow_period: usize) -> Result<(), AlgorithmError> {
+        if fast_period == 0 || fast_period >= slow_period {
+            return Err(AlgorithmError::InvalidParams(format!(
+                "fast_period {fast_period} must be greater than 0 and less than slow_period {slow_period}"
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Algorithm for HullMa {
+    fn evaluate(
+        &mut self,
+        kline: Kline,
+        _data_points: &AlgorithmDataPointManager,
+    ) -> AlgorithmEvalResult {
+        let fast = self.fast_hma.next(kline.close);
+        let slow = self.slow_hma.next(kline.close);
+
+        let diff = match (fast, slow) {
+            (Some(fast), Some(slow)) => fast - slow,
+            _ => return AlgorithmEvalResult::Ignore, // Not enough data
+        };
+        let prev_diff = self.prev_diff.replace(diff);
+
+        match prev_diff {
+            // Fast HMA crosses above the slow HMA - potential buy signal
+            Some(prev_diff) if prev_diff <= 0.0 && diff > 0.0 => AlgorithmEvalResult::Buy,
+            // Fast HMA crosses below the slow HMA - potential sell signal
+            Some(prev_diff) if prev_diff >= 0.0 && diff < 0.0 => AlgorithmEvalResult::Sell,
+            _ => AlgorithmEvalResult::Ignore,
+        }
+    }
+
+    fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    fn get_params(&self) -> &Value {
+        &self.params
+    }
+
+    fn set_params(&mut self, params: Value) -> Result<(), AlgorithmError> {
+        let fast_period =
+            parse_usize_from_value("fast_period", &params).unwrap_or(self.fast_period);
+        let slow_period =
+            parse_usize_from_value("slow_period", &params).unwrap_or(self.slow_period);
+        Self::validate_periods(fast_period, slow_period)?;
+
+        self.fast_period = fast_period;
+        self.slow_period = slow_period;
+        self.fast_hma = Hma::new(fast_period);
+        self.slow_hma = Hma::new(slow_period);
+        self.prev_diff = None;
+        self.params = params;
+
+        Ok(())
+    }
+
+    fn max_period(&self) -> usize {
+        Hma::warmup_period(self.slow_period)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests crossovers of a fast HMA of 2, `(4 * close - previous close) / 3`, and a slow HMA
+    /// of 3 over hand-computed values.
+    #[test]
+    fn test_hull_ma_crossovers() {
+        let mut hull_ma = HullMa::new(
+            Duration::from_secs(60),
+            json!({"fast_period": 2, "slow_period": 3}),
+        )
+        .unwrap();
+        let data_points = AlgorithmDataPointManager::new(1);
+        let mut evaluate = |close: f64| {
+            hull_ma.evaluate(
+                Kline {
+                    close,
+                    ..Default::default()
+                },
+                &data_points,
+            )
+        };
+
+        for _ in 0..4 {
+            assert!(matches!(evaluate(10.0), AlgorithmEvalResult::Ignore));
+        }
+        // fast (64 - 10) / 3 = 18, slow (10 + 2 * 19) / 3 = 16
+        assert!(matches!(evaluate(16.0), AlgorithmEvalResult::Buy));
+        // fast (40 - 16) / 3 = 8, slow (19 + 2 * 8) / 3 = 35 / 3
+        assert!(matches!(evaluate(10.0), AlgorithmEvalResult::Sell));
+        let diff = hull_ma.prev_diff.unwrap();
+        assert!((diff - (8.0 - 35.0 / 3.0)).abs() < 1e-9);
+    }
+}
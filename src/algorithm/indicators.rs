@@ -0,0 +1,408 @@
+//! Incremental indicators shared by the built-in algorithms.
+//!
+//! Each indicator keeps running state so that updating it with a new value is O(1), instead of
+//! rescanning the historical data points on every k-line.
+
+use std::collections::{vec_deque::Iter, VecDeque};
+
+/// Rolling window over the last `period` values, maintaining a running sum and sum of squares.
+#[derive(Debug, Clone)]
+pub struct RollingWindow {
+    period: usize,
+    values: VecDeque<f64>,
+    sum: f64,
+    sum_sq: f64,
+}
+
+impl RollingWindow {
+    pub fn new(period: usize) -> Self {
+        let period = period.max(1);
+        Self {
+            period,
+            values: VecDeque::with_capacity(period + 1),
+            sum: 0.0,
+            sum_sq: 0.0,
+        }
+    }
+
+    /// Adds a value to the window, evicting the oldest value once the window is full.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value to add.
+    ///
+    /// # Returns
+    ///
+    /// The mean of the window once it holds `period` values, otherwise `None`.
+    pub fn next(&mut self, value: f64) -> Option<f64> {
+        self.values.push_back(value);
+        self.sum += value;
+        self.sum_sq += value * value;
+
+        if self.values.len() > self.period {
+            if let Some(evicted) = self.values.pop_front() {
+                self.sum -= evicted;
+                self.sum_sq -= evicted * evicted;
+            }
+        }
+
+        self.mean()
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.values.len() >= self.period
+    }
+
+    /// Iterates the values in the window from oldest to newest.
+    pub fn values(&self) -> Iter<'_, f64> {
+        self.values.iter()
+    }
+
+    /// Returns the simple moving average of the window once full.
+    pub fn mean(&self) -> Option<f64> {
+        if !self.is_ready() {
+            return None;
+        }
+        Some(self.sum / self.period as f64)
+    }
+
+    /// Returns the population standard deviation of the window once full.
+    pub fn std_dev(&self) -> Option<f64> {
+        let mean = self.mean()?;
+        // running sums can drift slightly negative for flat prices
+        let variance = (self.sum_sq / self.period as f64 - mean * mean).max(0.0);
+        Some(variance.sqrt())
+    }
+}
+
+/// Exponential moving average, seeded with the first value.
+#[derive(Debug, Clone)]
+pub struct Ema {
+    period: usize,
+    k: f64,
+    count: usize,
+    value: Option<f64>,
+}
+
+impl Ema {
+    pub fn new(period: usize) -> Self {
+        let period = period.max(1);
+        Self {
+            period,
+            k: 2.0 / (period as f64 + 1.0),
+            count: 0,
+            value: None,
+        }
+    }
+
+    /// Updates the EMA with a new value.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value to add.
+    ///
+    /// # Returns
+    ///
+    /// The updated EMA.
+    pub fn next(&mut self, value: f64) -> f64 {
+        let ema = match self.value {
+            Some(prev) => (value - prev) * self.k + prev,
+            None => value,
+        };
+        self.count += 1;
+        self.value = Some(ema);
+
+        ema
+    }
+
+    /// Whether the EMA has seen at least `period` values.
+    pub fn is_ready(&self) -> bool {
+        self.count >= self.period
+    }
+}
+
+/// Linearly weighted moving average, the newest value weighs `period` and the oldest weighs one.
+///
+/// Keeps a running sum and weighted sum, on every update each weight drops by one, which is the
+/// same as subtracting the plain sum before adding the newest value with the full weight.
+#[derive(Debug, Clone)]
+pub struct Wma {
+    period: usize,
+    values: VecDeque<f64>,
+    sum: f64,
+    weighted_sum: f64,
+}
+
+impl Wma {
+    pub fn new(period: usize) -> Self {
+        let period = period.max(1);
+        Self {
+            period,
+            values: VecDeque::with_capacity(period + 1),
+            sum: 0.0,
+            weighted_sum: 0.0,
+        }
+    }
+
+    /// Updates the WMA with a new value.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value to add.
+    ///
+    /// # Returns
+    ///
+    /// The WMA once `period` values have been seen, otherwise `None`.
+    pub fn next(&mut self, value: f64) -> Option<f64> {
+        if self.values.len() == self.period {
+            self.weighted_sum -= self.sum;
+            if let Some(evicted) = self.values.pop_front() {
+                self.sum -= evicted;
+            }
+        }
+
+        self.values.push_back(value);
+        self.sum += value;
+        self.weighted_sum += value * self.values.len() as f64;
+
+        if self.values.len() < self.period {
+            return None;
+        }
+
+        let weights = (self.period * (self.period + 1)) as f64 / 2.0;
+        Some(self.weighted_sum / weights)
+    }
+}
+
+/// Hull moving average, `WMA(2 * WMA(n / 2) - WMA(n))` smoothed over `sqrt(n)` values.
+#[derive(Debug, Clone)]
+pub struct Hma {
+    half_wma: Wma,
+    full_wma: Wma,
+    smoothing_wma: Wma,
+}
+
+impl Hma {
+    pub fn new(period: usize) -> Self {
+        Self {
+            half_wma: Wma::new(period / 2),
+            full_wma: Wma::new(period),
+            smoothing_wma: Wma::new((period as f64).sqrt().round() as usize),
+        }
+    }
+
+    /// Number of values needed before the HMA is available.
+    ///
+    /// # Arguments
+    ///
+    /// * `period` - The HMA period.
+    pub fn warmup_period(period: usize) -> usize {
+        period.max(1) + ((period as f64).sqrt().round() as usize).max(1) - 1
+    }
+
+    /// Updates the HMA with a new value.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value to add.
+    ///
+    /// # Returns
+    ///
+    /// The HMA once `warmup_period` values have been seen, otherwise `None`.
+    pub fn next(&mut self, value: f64) -> Option<f64> {
+        let half = self.half_wma.next(value);
+        let full = self.full_wma.next(value)?;
+
+        self.smoothing_wma.next(2.0 * half? - full)
+    }
+}
+
+/// MACD line, the difference between a short and a long EMA, together with its signal line.
+#[derive(Debug, Clone)]
+pub struct MacdLine {
+    short_ema: Ema,
+    long_ema: Ema,
+    signal_ema: Ema,
+}
+
+impl MacdLine {
+    pub fn new(short_period: usize, long_period: usize, signal_period: usize) -> Self {
+        Self {
+            short_ema: Ema::new(short_period),
+            long_ema: Ema::new(long_period),
+            signal_ema: Ema::new(signal_period),
+        }
+    }
+
+    /// Updates the MACD and signal lines with a new close price.
+    ///
+    /// # Arguments
+    ///
+    /// * `close` - The close price of the latest k-line.
+    ///
+    /// # Returns
+    ///
+    /// The `(macd, signal)` values once both EMAs and the signal line are warmed up,
+    /// otherwise `None`.
+    pub fn next(&mut self, close: f64) -> Option<(f64, f64)> {
+        let macd = self.short_ema.next(close) - self.long_ema.next(close);
+        if !self.short_ema.is_ready() || !self.long_ema.is_ready() {
+            return None;
+        }
+
+        let signal = self.signal_ema.next(macd);
+        if !self.signal_ema.is_ready() {
+            return None;
+        }
+
+        Some((macd, signal))
+    }
+}
+
+/// Relative strength index using Wilder's smoothing of average gains and losses.
+///
+/// The first averages are the simple averages of the first `period` price changes, later
+/// averages are smoothed as `(prev * (period - 1) + change) / period`.
+#[derive(Debug, Clone)]
+pub struct WilderRsi {
+    period: usize,
+    prev_close: Option<f64>,
+    count: usize,
+    avg_gain: f64,
+    avg_loss: f64,
+}
+
+impl WilderRsi {
+    pub fn new(period: usize) -> Self {
+        Self {
+            period: period.max(1),
+            prev_close: None,
+            count: 0,
+            avg_gain: 0.0,
+            avg_loss: 0.0,
+        }
+    }
+
+    /// Updates the RSI with a new close price.
+    ///
+    /// # Arguments
+    ///
+    /// * `close` - The close price of the latest k-line.
+    ///
+    /// # Returns
+    ///
+    /// The RSI once `period` price changes have been seen, otherwise `None`.
+    pub fn next(&mut self, close: f64) -> Option<f64> {
+        let prev_close = self.prev_close.replace(close)?;
+
+        let delta = close - prev_close;
+        let (gain, loss) = if delta > 0.0 {
+            (delta, 0.0)
+        } else {
+            (0.0, -delta)
+        };
+
+        let period = self.period as f64;
+        self.count += 1;
+
+        if self.count <= self.period {
+            // accumulate the simple average of the first period of changes
+            self.avg_gain += gain / period;
+            self.avg_loss += loss / period;
+        } else {
+            self.avg_gain = (self.avg_gain * (period - 1.0) + gain) / period;
+            self.avg_loss = (self.avg_loss * (period - 1.0) + loss) / period;
+        }
+
+        self.value()
+    }
+
+    /// Returns the current RSI once `period` price changes have been seen.
+    pub fn value(&self) -> Option<f64> {
+        if self.count < self.period {
+            return None;
+        }
+
+        if self.avg_loss == 0.0 {
+            // flat prices are neutral rather than overbought
+            if self.avg_gain == 0.0 {
+                return Some(50.0);
+            }
+            return Some(100.0); // Prevent division by zero
+        }
+
+        let rs = self.avg_gain / self.avg_loss;
+        Some(100.0 - (100.0 / (1.0 + rs)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests the rolling window mean and standard deviation only cover the last period.
+    #[test]
+    fn test_rolling_window() {
+        let mut window = RollingWindow::new(3);
+
+        assert_eq!(window.next(1.0), None);
+        assert_eq!(window.next(2.0), None);
+        assert_eq!(window.next(3.0), Some(2.0));
+        assert_eq!(window.next(10.0), Some(5.0));
+
+        let std_dev = window.std_dev().unwrap();
+        let expected = ((9.0 + 4.0 + 25.0) / 3.0_f64).sqrt();
+        assert!((std_dev - expected).abs() < 1e-9);
+    }
+
+    /// Tests the EMA is seeded with the first value and smoothed afterwards.
+    #[test]
+    fn test_ema() {
+        let mut ema = Ema::new(3);
+
+        assert_eq!(ema.next(10.0), 10.0);
+        assert_eq!(ema.next(20.0), 15.0);
+        assert!(!ema.is_ready());
+        assert_eq!(ema.next(15.0), 15.0);
+        assert!(ema.is_ready());
+    }
+
+    /// Tests the WMA weights the newest values the most once the window rolls.
+    #[test]
+    fn test_wma() {
+        let mut wma = Wma::new(3);
+
+        assert_eq!(wma.next(1.0), None);
+        assert_eq!(wma.next(2.0), None);
+        // (1 + 4 + 9) / 6
+        assert!((wma.next(3.0).unwrap() - 14.0 / 6.0).abs() < 1e-9);
+        // (2 + 6 + 30) / 6
+        assert!((wma.next(10.0).unwrap() - 38.0 / 6.0).abs() < 1e-9);
+    }
+
+    /// Tests the HMA tracks a linear trend without lag once warmed up.
+    #[test]
+    fn test_hma() {
+        let mut hma = Hma::new(4);
+        let values: Vec<Option<f64>> = (0..6).map(|i| hma.next(i as f64)).collect();
+
+        assert_eq!(Hma::warmup_period(4), 5);
+        assert!(values[..4].iter().all(Option::is_none));
+        assert!((values[4].unwrap() - 4.0).abs() < 1e-9);
+        assert!((values[5].unwrap() - 5.0).abs() < 1e-9);
+    }
+
+    /// Tests Wilder smoothing of the RSI averages.
+    #[test]
+    fn test_wilder_rsi() {
+        let mut rsi = WilderRsi::new(2);
+
+        assert_eq!(rsi.next(10.0), None);
+        assert_eq!(rsi.next(12.0), None);
+        // avg gain 1, avg loss 0.5
+        assert_eq!(rsi.next(11.0).map(|v| v.round()), Some(67.0));
+        // avg gain 0.5, avg loss 1.25
+        let value = rsi.next(9.0).unwrap();
+        assert!((value - (100.0 - 100.0 / (1.0 + 0.4))).abs() < 1e-9);
+    }
+}
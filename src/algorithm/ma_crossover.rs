@@ -2,6 +2,7 @@ use std::time::Duration;
 
 use crate::market::kline::Kline;
 
+use crate::strategy::data_points::AlgorithmDataPointManager;
 use crate::strategy::types::AlgorithmError;
 use crate::strategy::{algorithm::Algorithm, types::AlgorithmEvalResult};
 use crate::utils::number::parse_usize_from_value;
@@ -9,13 +10,12 @@ use ta::indicators::{ExponentialMovingAverage, SimpleMovingAverage};
 
 // use indicators::exponential_moving_average::ExponentialMovingAverage;
 // use indicators::simple_moving_average::SimpleMovingAverage;
-use serde_json::Value;
+use serde_json::{json, Value};
 use ta::Next;
 
 // Assume the existence of the Kline struct and other necessary dependencies
 
 pub struct EmaSmaCrossover {
-    data_points: Vec<Kline>,
     interval: Duration,
     ema_period: usize,
     sma_period: usize,
@@ -25,6 +25,18 @@ pub struct EmaSmaCrossover {
 }
 
 impl EmaSmaCrossover {
+    /// Returns the JSON schema of the parameters accepted by the algorithm.
+    pub fn params_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "ema_period": { "type": "integer", "minimum": 1 },
+                "sma_period": { "type": "integer", "minimum": 1 }
+            },
+            "required": ["ema_period", "sma_period"]
+        })
+    }
+
     pub fn new(interval: Duration, params: Value) -> Result<Self, AlgorithmError> {
         let ema_period = parse_usize_from_value("ema_period", &params)
             .or_else(|e| Err(AlgorithmError::InvalidParams(e.to_string())))?;
@@ -37,7 +49,6 @@ impl EmaSmaCrossover {
             .or_else(|e| Err(AlgorithmError::InvalidParams(e.to_string())))?;
 
         Ok(Self {
-            data_points: vec![],
             interval,
             ema_period,
             sma_period,
@@ -57,10 +68,12 @@ impl EmaSmaCrossover {
 }
 
 impl Algorithm for EmaSmaCrossover {
-    fn evaluate(&mut self, kline: Kline) -> AlgorithmEvalResult {
-        self.data_points.push(kline.clone());
-
-        let result = if self.data_points.len() >= self.sma_period {
+    fn evaluate(
+        &mut self,
+        kline: Kline,
+        data_points: &AlgorithmDataPointManager,
+    ) -> AlgorithmEvalResult {
+        if data_points.len() >= self.sma_period {
             let ema = self.calculate_ema(kline.clone());
             let sma = self.calculate_sma(kline.clone());
 
@@ -79,15 +92,7 @@ impl Algorithm for EmaSmaCrossover {
             result
         } else {
             AlgorithmEvalResult::Ignore
-        };
-
-        self.clean_data_points();
-
-        result
-    }
-
-    fn data_points(&self) -> Vec<Kline> {
-        self.data_points.clone()
+        }
     }
 
     fn interval(&self) -> Duration {
@@ -118,13 +123,7 @@ impl Algorithm for EmaSmaCrossover {
         Ok(())
     }
 
-    fn clean_data_points(&mut self) {
-        // TODO: Change length to be checked
-        // based on individual algorithm
-        let two_weeks_minutes = 10080 * 2;
-        if self.data_points.len() > two_weeks_minutes {
-            // reduce back to 1 week worth on data
-            self.data_points.drain(0..10080);
-        }
+    fn max_period(&self) -> usize {
+        self.ema_period.max(self.sma_period)
     }
 }
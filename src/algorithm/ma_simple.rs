@@ -1,56 +1,53 @@
 use std::time::Duration;
 
-use serde_json::Value;
+use serde_json::{json, Value};
 
+use crate::algorithm::indicators::RollingWindow;
 use crate::market::kline::Kline;
 
+use crate::strategy::data_points::AlgorithmDataPointManager;
 use crate::strategy::types::AlgorithmError;
 use crate::strategy::{algorithm::Algorithm, types::AlgorithmEvalResult};
 use crate::utils::number::parse_usize_from_value;
 
 pub struct SimpleMovingAverage {
-    data_points: Vec<Kline>,
     interval: Duration,
     period: usize,
+    sma: RollingWindow,
     params: Value,
 }
 
 impl SimpleMovingAverage {
+    /// Returns the JSON schema of the parameters accepted by the algorithm.
+    pub fn params_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "sma_period": { "type": "integer", "minimum": 1 }
+            },
+            "required": ["sma_period"]
+        })
+    }
+
     pub fn new(interval: Duration, params: Value) -> Result<Self, AlgorithmError> {
         let period = parse_usize_from_value("sma_period", &params.clone())
             .or_else(|e| Err(AlgorithmError::InvalidParams(e.to_string())))?;
         Ok(Self {
-            data_points: vec![],
             interval,
             period,
+            sma: RollingWindow::new(period),
             params,
         })
     }
-
-    fn calculate_sma(&self, period: usize) -> f64 {
-        if self.data_points.len() < period {
-            return 0.0;
-        }
-
-        let sum: f64 = self
-            .data_points
-            .iter()
-            .rev()
-            .take(period)
-            .map(|k| k.close)
-            .sum();
-
-        sum / period as f64
-    }
 }
 
 impl Algorithm for SimpleMovingAverage {
-    fn evaluate(&mut self, kline: Kline) -> AlgorithmEvalResult {
-        self.data_points.push(kline.clone());
-
-        let result = if self.data_points.len() >= self.period {
-            let sma = self.calculate_sma(self.period);
-
+    fn evaluate(
+        &mut self,
+        kline: Kline,
+        _data_points: &AlgorithmDataPointManager,
+    ) -> AlgorithmEvalResult {
+        if let Some(sma) = self.sma.next(kline.close) {
             // Placeholder logic for buy/sell decision based on SMA
             if kline.close > sma {
                 AlgorithmEvalResult::Buy
@@ -59,15 +56,7 @@ impl Algorithm for SimpleMovingAverage {
             }
         } else {
             AlgorithmEvalResult::Ignore
-        };
-
-        self.clean_data_points();
-
-        result
-    }
-
-    fn data_points(&self) -> Vec<Kline> {
-        self.data_points.clone()
+        }
     }
 
     fn interval(&self) -> Duration {
@@ -83,17 +72,42 @@ impl Algorithm for SimpleMovingAverage {
             .or_else(|e| Err(AlgorithmError::InvalidParams(e.to_string())))?;
 
         self.period = period;
+        self.sma = RollingWindow::new(period);
         self.params = params;
         Ok(())
     }
 
-    fn clean_data_points(&mut self) {
-        // TODO: Change length to be checked
-        // based on individual algorithm
-        let two_weeks_minutes = 10080 * 2;
-        if self.data_points.len() > two_weeks_minutes {
-            // reduce back to 1 week worth on data
-            self.data_points.drain(0..10080);
-        }
+    fn max_period(&self) -> usize {
+        self.period
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests closes above the moving average buy and closes at or below it sell.
+    #[test]
+    fn test_simple_moving_average() {
+        let mut sma =
+            SimpleMovingAverage::new(Duration::from_secs(60), json!({"sma_period": 3})).unwrap();
+        let data_points = AlgorithmDataPointManager::new(1);
+        let mut evaluate = |close: f64| {
+            sma.evaluate(
+                Kline {
+                    close,
+                    ..Default::default()
+                },
+                &data_points,
+            )
+        };
+
+        assert!(matches!(evaluate(1.0), AlgorithmEvalResult::Ignore));
+        assert!(matches!(evaluate(2.0), AlgorithmEvalResult::Ignore));
+        // 3 above the average of 2
+        assert!(matches!(evaluate(3.0), AlgorithmEvalResult::Buy));
+        // 0 below the average of 5 / 3
+        assert!(matches!(evaluate(0.0), AlgorithmEvalResult::Sell));
+        assert_eq!(sma.sma.mean(), Some(5.0 / 3.0));
     }
 }
@@ -1,23 +1,40 @@
 use std::time::Duration;
 
-use serde_json::Value;
+use serde_json::{json, Value};
 
+use crate::algorithm::indicators::RollingWindow;
 use crate::market::kline::Kline;
 
+use crate::strategy::data_points::AlgorithmDataPointManager;
 use crate::strategy::types::AlgorithmError;
 use crate::strategy::{algorithm::Algorithm, types::AlgorithmEvalResult};
 use crate::utils::number::parse_usize_from_value;
 
 pub struct ThreeMaCrossover {
-    data_points: Vec<Kline>,
     interval: Duration,
     short_period: usize,
     medium_period: usize,
     long_period: usize,
+    short_ma: RollingWindow,
+    medium_ma: RollingWindow,
+    long_ma: RollingWindow,
     params: Value,
 }
 
 impl ThreeMaCrossover {
+    /// Returns the JSON schema of the parameters accepted by the algorithm.
+    pub fn params_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "short_period": { "type": "integer", "minimum": 1 },
+                "medium_period": { "type": "integer", "minimum": 1 },
+                "long_period": { "type": "integer", "minimum": 1 }
+            },
+            "required": ["short_period", "medium_period", "long_period"]
+        })
+    }
+
     pub fn new(interval: Duration, params: Value) -> Result<Self, AlgorithmError> {
         let short_period = parse_usize_from_value("short_period", &params)
             .or_else(|e| Err(AlgorithmError::InvalidParams(e.to_string())))?;
@@ -27,53 +44,29 @@ impl ThreeMaCrossover {
             .or_else(|e| Err(AlgorithmError::InvalidParams(e.to_string())))?;
 
         Ok(Self {
-            data_points: vec![],
             interval,
             short_period,
             medium_period,
             long_period,
+            short_ma: RollingWindow::new(short_period),
+            medium_ma: RollingWindow::new(medium_period),
+            long_ma: RollingWindow::new(long_period),
             params,
         })
     }
-
-    fn calculate_ma(&self, period: usize) -> f64 {
-        if self.data_points.len() < period {
-            return 0.0;
-        }
-
-        let sum: f64 = self
-            .data_points
-            .iter()
-            .rev()
-            .take(period)
-            .map(|k| k.close)
-            .sum();
-
-        sum / period as f64
-    }
-
-    fn calculate_short_ma(&self) -> f64 {
-        self.calculate_ma(self.short_period)
-    }
-
-    fn calculate_medium_ma(&self) -> f64 {
-        self.calculate_ma(self.medium_period)
-    }
-
-    fn calculate_long_ma(&self) -> f64 {
-        self.calculate_ma(self.long_period)
-    }
 }
 
 impl Algorithm for ThreeMaCrossover {
-    fn evaluate(&mut self, kline: Kline) -> AlgorithmEvalResult {
-        self.data_points.push(kline.clone());
-
-        let result = if self.data_points.len() >= self.long_period {
-            let short_ma = self.calculate_short_ma();
-            let medium_ma = self.calculate_medium_ma();
-            let long_ma = self.calculate_long_ma();
-
+    fn evaluate(
+        &mut self,
+        kline: Kline,
+        _data_points: &AlgorithmDataPointManager,
+    ) -> AlgorithmEvalResult {
+        let short_ma = self.short_ma.next(kline.close);
+        let medium_ma = self.medium_ma.next(kline.close);
+        let long_ma = self.long_ma.next(kline.close);
+
+        if let (Some(short_ma), Some(medium_ma), Some(long_ma)) = (short_ma, medium_ma, long_ma) {
             // Placeholder logic for buy/sell decision based on MA crossovers
             if short_ma > medium_ma && medium_ma > long_ma {
                 AlgorithmEvalResult::Buy
@@ -84,15 +77,7 @@ impl Algorithm for ThreeMaCrossover {
             }
         } else {
             AlgorithmEvalResult::Ignore
-        };
-
-        self.clean_data_points();
-
-        result
-    }
-
-    fn data_points(&self) -> Vec<Kline> {
-        self.data_points.clone()
+        }
     }
 
     fn interval(&self) -> Duration {
@@ -115,17 +100,50 @@ impl Algorithm for ThreeMaCrossover {
         self.long_period = long_period;
         self.short_period = short_period;
         self.medium_period = medium_period;
+        self.short_ma = RollingWindow::new(short_period);
+        self.medium_ma = RollingWindow::new(medium_period);
+        self.long_ma = RollingWindow::new(long_period);
 
         Ok(())
     }
 
-    fn clean_data_points(&mut self) {
-        // TODO: Change length to be checked
-        // based on individual algorithm
-        let two_weeks_minutes = 10080 * 2;
-        if self.data_points.len() > two_weeks_minutes {
-            // reduce back to 1 week worth on data
-            self.data_points.drain(0..10080);
-        }
+    fn max_period(&self) -> usize {
+        self.short_period
+            .max(self.medium_period)
+            .max(self.long_period)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests the averages buy once stacked upwards and sell once stacked downwards.
+    #[test]
+    fn test_three_ma_crossover() {
+        let mut three_ma = ThreeMaCrossover::new(
+            Duration::from_secs(60),
+            json!({"short_period": 1, "medium_period": 2, "long_period": 3}),
+        )
+        .unwrap();
+        let data_points = AlgorithmDataPointManager::new(1);
+        let mut evaluate = |close: f64| {
+            three_ma.evaluate(
+                Kline {
+                    close,
+                    ..Default::default()
+                },
+                &data_points,
+            )
+        };
+
+        assert!(matches!(evaluate(1.0), AlgorithmEvalResult::Ignore));
+        assert!(matches!(evaluate(2.0), AlgorithmEvalResult::Ignore));
+        // 3 above 2.5 above 2
+        assert!(matches!(evaluate(3.0), AlgorithmEvalResult::Buy));
+        // 2 below 2.5, which is above 7 / 3, the averages aren't stacked
+        assert!(matches!(evaluate(2.0), AlgorithmEvalResult::Ignore));
+        // 0 below 1 below 5 / 3
+        assert!(matches!(evaluate(0.0), AlgorithmEvalResult::Sell));
     }
 }
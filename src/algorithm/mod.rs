@@ -1,9 +1,25 @@
 pub mod bollinger_bands;
+pub mod cci;
+pub mod chaikin_money_flow;
+pub mod composite;
+pub mod dca_accumulator;
+pub mod fib_retracement;
+#[cfg(any(test, feature = "testkit"))]
+#[allow(dead_code)] // only used by tests and the testkit
+pub mod harness;
+pub mod hull_ma;
+pub mod indicators;
 pub mod ma_crossover;
 pub mod ma_simple;
 pub mod ma_three_crossover;
 pub mod macd;
 pub mod macd_bollinger;
+pub mod obv;
+#[cfg(feature = "plugins")]
+pub mod plugin;
 pub mod rsi;
 pub mod rsi_ema_sma;
 pub mod template;
+pub mod trix;
+pub mod volume_profile;
+pub mod vwap_reversion;
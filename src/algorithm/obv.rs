@@ -0,0 +1,131 @@
+use crate::algorithm::indicators::Ema;
+use crate::market::kline::Kline;
+use crate::strategy::{
+    algorithm::Algorithm,
+    data_points::AlgorithmDataPointManager,
+    types::{AlgorithmError, AlgorithmEvalResult},
+};
+use crate::utils::number::parse_usize_from_value;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+pub struct Obv {
+    interval: Duration,
+    params: Value,
+    signal_period: usize,
+    obv: f64,                // Running on-balance volume
+    prev_close: Option<f64>, // Close of the previous kline, to tell up and down volume apart
+    signal: Ema,             // Signal line: EMA of the OBV series
+    prev_diff: Option<f64>,  // Previous OBV minus signal line, to detect crossovers
+}
+
+impl Obv {
+    /// Returns the JSON schema of the parameters accepted by the algorithm.
+    pub fn params_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "signal_period": { "type": "integer", "minimum": 1, "default": 20 }
+            },
+            "required": []
+        })
+    }
+
+    pub fn new(interval: Duration, params: Value) -> Result<Self, AlgorithmError> {
+        let signal_period = parse_usize_from_value("signal_period", &params).unwrap_or(20); // Default signal period is 20
+
+        Ok(Self {
+            interval,
+            params,
+            signal_period,
+            obv: 0.0,
+            prev_close: None,
+            signal: Ema::new(signal_period),
+            prev_diff: None,
+        })
+    }
+
+    fn update_obv(&mut self, kline: &Kline) -> f64 {
+        if let Some(prev_close) = self.prev_close {
+            if kline.close > prev_close {
+                self.obv += kline.volume;
+            } else if kline.close < prev_close {
+                self.obv -= kline.volume;
+            }
+        }
+        self.prev_close = Some(kline.close);
+
+        self.obv
+    }
+}
+
+impl Algorithm for Obv {
+    fn evaluate(
+        &mut self,
+        kline: Kline,
+        _data_points: &AlgorithmDataPointManager,
+    ) -> AlgorithmEvalResult {
+        let obv = self.update_obv(&kline);
+        let signal = self.signal.next(obv);
+
+        if !self.signal.is_ready() {
+            return AlgorithmEvalResult::Ignore; // Not enough data
+        }
+
+        let diff = obv - signal;
+        let prev_diff = self.prev_diff.replace(diff);
+
+        match prev_diff {
+            // OBV crosses above its signal line, volume is flowing in - potential buy signal
+            Some(prev_diff) if prev_diff <= 0.0 && diff > 0.0 => AlgorithmEvalResult::Buy,
+            // OBV crosses below its signal line, volume is flowing out - potential sell signal
+            Some(prev_diff) if prev_diff >= 0.0 && diff < 0.0 => AlgorithmEvalResult::Sell,
+            _ => AlgorithmEvalResult::Ignore,
+        }
+    }
+
+    fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    fn get_params(&self) -> &Value {
+        &self.params
+    }
+
+    fn set_params(&mut self, params: Value) -> Result<(), AlgorithmError> {
+        if let Ok(signal_period) = parse_usize_from_value("signal_period", &params) {
+            self.signal_period = signal_period;
+            self.signal = Ema::new(signal_period);
+            self.prev_diff = None;
+        }
+
+        self.params = params;
+        Ok(())
+    }
+
+    fn max_period(&self) -> usize {
+        self.signal_period
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests volume is added on up closes, subtracted on down closes and ignored otherwise.
+    #[test]
+    fn test_update_obv() {
+        let mut obv = Obv::new(Duration::from_secs(60), json!({"signal_period": 2})).unwrap();
+        let kline = |close: f64, volume: f64| Kline {
+            close,
+            volume,
+            ..Default::default()
+        };
+
+        assert_eq!(obv.update_obv(&kline(10.0, 5.0)), 0.0);
+        assert_eq!(obv.update_obv(&kline(12.0, 3.0)), 3.0);
+        assert_eq!(obv.update_obv(&kline(11.0, 2.0)), 1.0);
+        assert_eq!(obv.update_obv(&kline(11.0, 4.0)), 1.0);
+        assert_eq!(obv.update_obv(&kline(9.0, 6.0)), -5.0);
+    }
+}
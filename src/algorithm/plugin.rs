@@ -0,0 +1,370 @@
+use std::{
+    collections::HashMap,
+    ffi::{c_char, c_void, CStr, CString},
+    fs,
+    path::Path,
+    ptr::NonNull,
+    sync::{Arc, OnceLock, RwLock},
+    time::Duration,
+};
+
+use libloading::{Library, Symbol};
+use log::info;
+use serde_json::Value;
+
+use crate::{
+    market::kline::Kline,
+    strategy::{
+        algorithm::Algorithm,
+        data_points::AlgorithmDataPointManager,
+        types::{AlgorithmError, AlgorithmEvalResult},
+    },
+};
+
+/// Version of the plugin ABI, plugins built against a different version are rejected.
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+
+/// Name of the constructor symbol every algorithm plugin must export.
+///
+/// The symbol must be an `extern "C" fn() -> *const AlgorithmPluginDecl`.
+pub const PLUGIN_CONSTRUCTOR_SYMBOL: &[u8] = b"raderbot_algorithm_plugin";
+
+/// Kline data passed to plugins across the C ABI.
+#[repr(C)]
+pub struct PluginKline {
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+    pub open_time: u64,
+    pub close_time: u64,
+}
+
+impl From<&Kline> for PluginKline {
+    fn from(kline: &Kline) -> Self {
+        Self {
+            open: kline.open,
+            high: kline.high,
+            low: kline.low,
+            close: kline.close,
+            volume: kline.volume,
+            open_time: kline.open_time,
+            close_time: kline.close_time,
+        }
+    }
+}
+
+/// Evaluation result returned by plugins across the C ABI.
+///
+/// `side` is `1` for buy, `-1` for sell and `0` to ignore, `confidence` ranges between `0.0` and `1.0`.
+#[repr(C)]
+pub struct PluginEvalResult {
+    pub side: i32,
+    pub confidence: f64,
+}
+
+/// Declaration exported by an algorithm plugin through its constructor symbol.
+///
+/// Parameters are exchanged as null-terminated JSON strings. `create` and `set_params` return a
+/// null pointer and a non-zero value respectively when the parameters are invalid.
+#[repr(C)]
+pub struct AlgorithmPluginDecl {
+    pub abi_version: u32,
+    pub name: *const c_char,
+    pub create: extern "C" fn(params: *const c_char) -> *mut c_void,
+    pub evaluate:
+        extern "C" fn(instance: *mut c_void, kline: *const PluginKline) -> PluginEvalResult,
+    pub set_params: extern "C" fn(instance: *mut c_void, params: *const c_char) -> i32,
+    pub destroy: extern "C" fn(instance: *mut c_void),
+}
+
+/// An algorithm plugin loaded from a dynamic library.
+///
+/// The declaration points into the library, so the plugin holds the library alongside it and
+/// only lends the declaration for as long as the plugin is borrowed. Algorithms built from the
+/// plugin hold it in turn, keeping the library loaded while any of them is alive.
+pub struct AlgorithmPlugin {
+    pub name: String,
+    decl: NonNull<AlgorithmPluginDecl>,
+    _library: Arc<Library>,
+}
+
+// SAFETY: The declaration only holds function pointers and a name owned by the library, plugins
+// are required to be thread safe.
+unsafe impl Send for AlgorithmPlugin {}
+unsafe impl Sync for AlgorithmPlugin {}
+
+impl AlgorithmPlugin {
+    /// Loads an algorithm plugin from a dynamic library.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the dynamic library.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the loaded plugin, or an error message if loading fails.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        // SAFETY: Loading a library runs its initialisation code, only trusted plugins
+        // should be placed in the plugin directory.
+        let library = unsafe { Library::new(path) }.map_err(|e| e.to_string())?;
+
+        let decl = unsafe {
+            let constructor: Symbol<extern "C" fn() -> *const AlgorithmPluginDecl> = library
+                .get(PLUGIN_CONSTRUCTOR_SYMBOL)
+                .map_err(|e| e.to_string())?;
+            NonNull::new(constructor() as *mut AlgorithmPluginDecl)
+                .ok_or("Plugin constructor returned null")?
+        };
+        // SAFETY: The declaration lives in the library, which isn't unloaded before it's dropped.
+        let decl_ref = unsafe { decl.as_ref() };
+
+        if decl_ref.abi_version != PLUGIN_ABI_VERSION {
+            return Err(format!(
+                "Plugin ABI version {} is not supported, expected {PLUGIN_ABI_VERSION}",
+                decl_ref.abi_version
+            ));
+        }
+
+        if decl_ref.name.is_null() {
+            return Err("Plugin name is null".to_string());
+        }
+        let name = unsafe { CStr::from_ptr(decl_ref.name) }
+            .to_string_lossy()
+            .to_string();
+
+        Ok(Self {
+            name,
+            decl,
+            _library: Arc::new(library),
+        })
+    }
+
+    /// The declaration exported by the plugin, borrowed no longer than the plugin holds its
+    /// library.
+    fn decl(&self) -> &AlgorithmPluginDecl {
+        // SAFETY: The declaration points into the library, which outlives the borrow of self.
+        unsafe { self.decl.as_ref() }
+    }
+}
+
+/// An `Algorithm` implementation delegating evaluation to a loaded plugin.
+pub struct PluginAlgorithm {
+    plugin: Arc<AlgorithmPlugin>,
+    instance: *mut c_void,
+    interval: Duration,
+    params: Value,
+}
+
+// SAFETY: The plugin instance is only accessed through `&mut self`, plugins are required
+// to be thread safe.
+unsafe impl Send for PluginAlgorithm {}
+unsafe impl Sync for PluginAlgorithm {}
+
+impl PluginAlgorithm {
+    pub fn new(
+        plugin: Arc<AlgorithmPlugin>,
+        interval: Duration,
+        params: Value,
+    ) -> Result<Self, AlgorithmError> {
+        let c_params = to_c_params(&params)?;
+        let instance = (plugin.decl().create)(c_params.as_ptr());
+
+        if instance.is_null() {
+            return Err(AlgorithmError::InvalidParams(format!(
+                "Plugin {} rejected params {params}",
+                plugin.name
+            )));
+        }
+
+        Ok(Self {
+            plugin,
+            instance,
+            interval,
+            params,
+        })
+    }
+}
+
+impl Drop for PluginAlgorithm {
+    fn drop(&mut self) {
+        (self.plugin.decl().destroy)(self.instance);
+    }
+}
+
+impl Algorithm for PluginAlgorithm {
+    fn evaluate(
+        &mut self,
+        kline: Kline,
+        _data_points: &AlgorithmDataPointManager,
+    ) -> AlgorithmEvalResult {
+        let plugin_kline = PluginKline::from(&kline);
+        let result = (self.plugin.decl().evaluate)(self.instance, &plugin_kline);
+
+        match result.side {
+            1 => AlgorithmEvalResult::Long(result.confidence),
+            -1 => AlgorithmEvalResult::Short(result.confidence),
+            _ => AlgorithmEvalResult::Ignore,
+        }
+    }
+
+    fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    fn get_params(&self) -> &Value {
+        &self.params
+    }
+
+    fn set_params(&mut self, params: Value) -> Result<(), AlgorithmError> {
+        let c_params = to_c_params(&params)?;
+
+        if (self.plugin.decl().set_params)(self.instance, c_params.as_ptr()) != 0 {
+            return Err(AlgorithmError::InvalidParams(format!(
+                "Plugin {} rejected params {params}",
+                self.plugin.name
+            )));
+        }
+
+        self.params = params;
+
+        Ok(())
+    }
+
+    fn max_period(&self) -> usize {
+        // plugins keep their own history and only need the latest k-line
+        0
+    }
+}
+
+/// Registry of loaded algorithm plugins, keyed by algorithm name.
+fn registry() -> &'static RwLock<HashMap<String, Arc<AlgorithmPlugin>>> {
+    static REGISTRY: OnceLock<RwLock<HashMap<String, Arc<AlgorithmPlugin>>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+/// Registers a loaded plugin so `AlgorithmBuilder` can build algorithms from it.
+///
+/// # Arguments
+///
+/// * `plugin` - The loaded plugin, replacing any plugin already registered with the same name.
+pub fn register_plugin(plugin: AlgorithmPlugin) {
+    if let Ok(mut registry) = registry().write() {
+        registry.insert(plugin.name.clone(), Arc::new(plugin));
+    }
+}
+
+/// Finds a registered plugin by algorithm name.
+///
+/// # Arguments
+///
+/// * `name` - The algorithm name exported by the plugin.
+///
+/// # Returns
+///
+/// The plugin if registered, otherwise `None`.
+pub fn find_plugin(name: &str) -> Option<Arc<AlgorithmPlugin>> {
+    registry().read().ok()?.get(name).cloned()
+}
+
+/// Lists the algorithm names of all registered plugins.
+pub fn list_plugins() -> Vec<String> {
+    match registry().read() {
+        Ok(registry) => registry.keys().cloned().collect(),
+        Err(_) => vec![],
+    }
+}
+
+/// Loads and registers every dynamic library found in a directory.
+///
+/// # Arguments
+///
+/// * `dir` - The directory containing the plugin libraries.
+///
+/// # Returns
+///
+/// The names of the algorithms registered.
+pub fn load_plugins_from_dir(dir: &Path) -> Vec<String> {
+    let mut names = vec![];
+
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            info!("Unable to read plugin directory {}, e: {e}", dir.display());
+            return names;
+        }
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let is_library = path
+            .extension()
+            .map(|ext| ext == "so" || ext == "dylib" || ext == "dll")
+            .unwrap_or(false);
+        if !is_library {
+            continue;
+        }
+
+        match AlgorithmPlugin::load(&path) {
+            Ok(plugin) => {
+                info!(
+                    "Loaded algorithm plugin {} from {}",
+                    plugin.name,
+                    path.display()
+                );
+                names.push(plugin.name.clone());
+                register_plugin(plugin);
+            }
+            Err(e) => {
+                info!("Unable to load plugin {}, e: {e}", path.display());
+            }
+        }
+    }
+
+    names
+}
+
+fn to_c_params(params: &Value) -> Result<CString, AlgorithmError> {
+    CString::new(params.to_string()).map_err(|e| AlgorithmError::InvalidParams(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+
+    /// Tests that loading a missing or invalid library returns an error.
+    #[test]
+    fn test_load_invalid_plugin() {
+        let directory = std::env::temp_dir().join(format!("raderbot-plugins-{}", Uuid::new_v4()));
+        fs::create_dir_all(&directory).unwrap();
+
+        assert!(AlgorithmPlugin::load(&directory.join("missing.so")).is_err());
+
+        let invalid = directory.join("invalid.so");
+        fs::write(&invalid, b"not a library").unwrap();
+        assert!(AlgorithmPlugin::load(&invalid).is_err());
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    /// Tests that loading a directory skips files that aren't valid plugins and that a missing
+    /// directory loads nothing.
+    #[test]
+    fn test_load_plugins_from_dir() {
+        let directory = std::env::temp_dir().join(format!("raderbot-plugins-{}", Uuid::new_v4()));
+        assert!(load_plugins_from_dir(&directory).is_empty());
+
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join("readme.txt"), b"not a plugin").unwrap();
+        fs::write(directory.join("invalid.so"), b"not a library").unwrap();
+        assert!(load_plugins_from_dir(&directory).is_empty());
+
+        assert!(find_plugin("invalid").is_none());
+        assert!(!list_plugins().contains(&"invalid".to_string()));
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
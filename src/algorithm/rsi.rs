@@ -1,85 +1,60 @@
+use crate::algorithm::indicators::WilderRsi;
 use crate::market::kline::Kline;
+use crate::strategy::data_points::AlgorithmDataPointManager;
 use crate::strategy::types::AlgorithmError;
 use crate::strategy::{algorithm::Algorithm, types::AlgorithmEvalResult};
 use crate::utils::number::parse_usize_from_value;
-use serde_json::Value;
+use serde_json::{json, Value};
 use std::time::Duration;
 
 pub struct Rsi {
-    data_points: Vec<Kline>,
     interval: Duration,
     params: Value,
     rsi_period: usize,
-    rsi: f64, // Optional: Store the last calculated RSI value
+    rsi: WilderRsi,
 }
 
 impl Rsi {
+    /// Returns the JSON schema of the parameters accepted by the algorithm.
+    pub fn params_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "rsi_period": { "type": "integer", "minimum": 1, "default": 14 }
+            },
+            "required": []
+        })
+    }
+
     pub fn new(interval: Duration, params: Value) -> Result<Self, AlgorithmError> {
         let rsi_period = parse_usize_from_value("rsi_period", &params).unwrap_or(14); // Default to 14 if not specified
         Ok(Self {
-            data_points: vec![],
             interval,
             rsi_period,
-            rsi: 0.0,
+            rsi: WilderRsi::new(rsi_period),
             params,
         })
     }
-
-    fn calculate_rsi(&mut self) -> f64 {
-        if self.data_points.len() < self.rsi_period {
-            return 0.0; // Not enough data to calculate RSI
-        }
-
-        let mut gains = 0.0;
-        let mut losses = 0.0;
-
-        for i in 1..=self.rsi_period {
-            let delta = self.data_points[self.data_points.len() - i].close
-                - self.data_points[self.data_points.len() - i - 1].close;
-            if delta > 0.0 {
-                gains += delta;
-            } else {
-                losses -= delta; // Losses are positive numbers
-            }
-        }
-
-        let avg_gain = gains / self.rsi_period as f64;
-        let avg_loss = losses / self.rsi_period as f64;
-
-        if avg_loss == 0.0 {
-            return 100.0; // Prevent division by zero
-        }
-
-        let rs = avg_gain / avg_loss;
-        let rsi = 100.0 - (100.0 / (1.0 + rs));
-
-        self.rsi = rsi; // Store the calculated RSI value
-        rsi
-    }
 }
 
 impl Algorithm for Rsi {
-    fn evaluate(&mut self, kline: Kline) -> AlgorithmEvalResult {
-        self.data_points.push(kline);
-
-        let rsi = self.calculate_rsi();
+    fn evaluate(
+        &mut self,
+        kline: Kline,
+        _data_points: &AlgorithmDataPointManager,
+    ) -> AlgorithmEvalResult {
+        // Not enough data to calculate RSI, use neutral value
+        let rsi = self.rsi.next(kline.close).unwrap_or(50.0);
 
         // Example RSI logic: Buy if RSI < 30 (oversold), Sell if RSI > 70 (overbought), else Ignore
-        let result = if rsi < 30.0 {
-            AlgorithmEvalResult::Buy
+        // confidence grows from 0.5 at the threshold to 1.0 at the RSI extreme
+        if rsi < 30.0 {
+            AlgorithmEvalResult::Long(0.5 + 0.5 * (30.0 - rsi) / 30.0)
         } else if rsi > 70.0 {
-            AlgorithmEvalResult::Sell
+            AlgorithmEvalResult::Short(0.5 + 0.5 * (rsi - 70.0) / 30.0)
         } else {
             AlgorithmEvalResult::Ignore
-        };
-
-        self.clean_data_points();
-
-        result
-    }
-
-    fn data_points(&self) -> Vec<Kline> {
-        self.data_points.clone()
+        }
     }
 
     fn interval(&self) -> Duration {
@@ -94,18 +69,57 @@ impl Algorithm for Rsi {
         let rsi_period = parse_usize_from_value("rsi_period", &params).unwrap_or(self.rsi_period);
 
         self.rsi_period = rsi_period;
+        self.rsi = WilderRsi::new(rsi_period);
         self.params = params;
 
         Ok(())
     }
 
-    fn clean_data_points(&mut self) {
-        // TODO: Change length to be checked
-        // based on individual algorithm
-        let two_weeks_minutes = 10080 * 2;
-        if self.data_points.len() > two_weeks_minutes {
-            // reduce back to 1 week worth on data
-            self.data_points.drain(0..10080);
+    fn warm_up(&mut self, data_points: &AlgorithmDataPointManager) {
+        for kline in data_points.iter() {
+            self.rsi.next(kline.close);
         }
     }
+
+    fn max_period(&self) -> usize {
+        // one extra data point is needed to calculate the first price change
+        self.rsi_period + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::account::trade::OrderSide;
+
+    /// Tests the confidence of entries grows with the distance of the RSI beyond its thresholds.
+    #[test]
+    fn test_rsi_confidence() {
+        let mut rsi = Rsi::new(Duration::from_secs(60), json!({"rsi_period": 2})).unwrap();
+        let data_points = AlgorithmDataPointManager::new(1);
+        let mut evaluate = |close: f64| {
+            rsi.evaluate(
+                Kline {
+                    close,
+                    ..Default::default()
+                },
+                &data_points,
+            )
+            .order_side()
+        };
+
+        assert_eq!(evaluate(10.0), None);
+        assert_eq!(evaluate(12.0), None);
+        // average gain 1 and loss 0.5, an RSI of 200 / 3
+        assert_eq!(evaluate(11.0), None);
+        // average gain 0.5 and loss 1.25, an RSI of 200 / 7
+        let (side, confidence) = evaluate(9.0).unwrap();
+        assert_eq!(side, OrderSide::Buy);
+        assert!((confidence - (0.5 + 0.5 * (30.0 - 200.0 / 7.0) / 30.0)).abs() < 1e-9);
+        // average gain 3.25 and loss 0.625, an RSI of 100 - 100 / 6.2
+        let (side, confidence) = evaluate(15.0).unwrap();
+        assert_eq!(side, OrderSide::Sell);
+        let value = 100.0 - 100.0 / 6.2;
+        assert!((confidence - (0.5 + 0.5 * (value - 70.0) / 30.0)).abs() < 1e-9);
+    }
 }
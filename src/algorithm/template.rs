@@ -4,12 +4,12 @@ use serde_json::Value;
 
 use crate::market::kline::Kline;
 
+use crate::strategy::data_points::AlgorithmDataPointManager;
 use crate::strategy::types::AlgorithmError;
 use crate::strategy::{algorithm::Algorithm, types::AlgorithmEvalResult};
 use crate::utils::number::parse_usize_from_value;
 
 pub struct CustomAlgorithm {
-    data_points: Vec<Kline>,
     interval: Duration,
     custom_param: usize,
     params: Value,
@@ -20,7 +20,6 @@ impl CustomAlgorithm {
         let custom_param = parse_usize_from_value("custom_param", &params)
             .or_else(|e| Err(AlgorithmError::InvalidParams(e.to_string())))?;
         Ok(Self {
-            data_points: vec![],
             interval,
             custom_param,
             params,
@@ -37,14 +36,14 @@ impl CustomAlgorithm {
 }
 
 impl Algorithm for CustomAlgorithm {
-    fn evaluate(&mut self, kline: Kline) -> AlgorithmEvalResult {
-        self.data_points.push(kline.clone());
-
-        // Example logic using self.custom_param
+    fn evaluate(
+        &mut self,
+        _kline: Kline,
+        _data_points: &AlgorithmDataPointManager,
+    ) -> AlgorithmEvalResult {
+        // Example logic using self.custom_param and the historical data points
         // ...
 
-        self.clean_data_points();
-
         AlgorithmEvalResult::Ignore
     }
 
@@ -60,11 +59,8 @@ impl Algorithm for CustomAlgorithm {
         unimplemented!()
     }
 
-    fn data_points(&self) -> Vec<Kline> {
-        self.data_points.clone()
-    }
-    fn clean_data_points(&mut self) {
-        unimplemented!()
+    fn max_period(&self) -> usize {
+        self.custom_param
     }
 }
 
@@ -76,6 +72,8 @@ impl Algorithm for CustomAlgorithm {
 // enum AlgorithmEvalResult {
 //     Buy,
 //     Sell,
+//     Long(f64),  // Buy with confidence between 0.0 and 1.0
+//     Short(f64), // Sell with confidence between 0.0 and 1.0
 //     Ignore,
 // }
 
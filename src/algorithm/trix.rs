@@ -0,0 +1,180 @@
+use crate::algorithm::indicators::Ema;
+use crate::market::kline::Kline;
+use crate::strategy::{
+    algorithm::Algorithm,
+    data_points::AlgorithmDataPointManager,
+    types::{AlgorithmError, AlgorithmEvalResult},
+};
+use crate::utils::number::parse_usize_from_value;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+/// TRIX, the one k-line percentage rate of change of a triple smoothed EMA of the close price,
+/// traded on crossovers of its signal line.
+pub struct Trix {
+    interval: Duration,
+    params: Value,
+    period: usize,
+    signal_period: usize,
+    emas: [Ema; 3],
+    prev_triple_ema: Option<f64>,
+    signal: Ema,            // Signal line: EMA of the TRIX series
+    prev_diff: Option<f64>, // Previous TRIX minus signal line, to detect crossovers
+}
+
+impl Trix {
+    /// Returns the JSON schema of the parameters accepted by the algorithm.
+    pub fn params_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "period": { "type": "integer", "minimum": 1, "default": 15 },
+                "signal_period": { "type": "integer", "minimum": 1, "default": 9 }
+            },
+            "required": []
+        })
+    }
+
+    pub fn new(interval: Duration, params: Value) -> Result<Self, AlgorithmError> {
+        let period = parse_usize_from_value("period", &params).unwrap_or(15);
+        let signal_period = parse_usize_from_value("signal_period", &params).unwrap_or(9);
+
+        Ok(Self {
+            interval,
+            params,
+            period,
+            signal_period,
+            emas: Self::build_emas(period),
+            prev_triple_ema: None,
+            signal: Ema::new(signal_period),
+            prev_diff: None,
+        })
+    }
+
+    fn build_emas(period: usize) -> [Ema; 3] {
+        [Ema::new(period), Ema::new(period), Ema::new(period)]
+    }
+
+    /// Updates the triple smoothed EMA with a new close price, each EMA is only fed once the
+    /// previous one is warmed up.
+    ///
+    /// # Returns
+    ///
+    /// The TRIX value once all EMAs are warmed up, otherwise `None`.
+    fn update_trix(&mut self, close: f64) -> Option<f64> {
+        let mut value = close;
+        for ema in self.emas.iter_mut() {
+            value = ema.next(value);
+            if !ema.is_ready() {
+                return None;
+            }
+        }
+
+        let prev_triple_ema = self.prev_triple_ema.replace(value)?;
+        if prev_triple_ema == 0.0 {
+            return None;
+        }
+
+        Some((value - prev_triple_ema) / prev_triple_ema * 100.0)
+    }
+}
+
+impl Algorithm for Trix {
+    fn evaluate(
+        &mut self,
+        kline: Kline,
+        _data_points: &AlgorithmDataPointManager,
+    ) -> AlgorithmEvalResult {
+        let trix = match self.update_trix(kline.close) {
+            Some(trix) => trix,
+            None => return AlgorithmEvalResult::Ignore, // Not enough data
+        };
+
+        let signal = self.signal.next(trix);
+        if !self.signal.is_ready() {
+            return AlgorithmEvalResult::Ignore;
+        }
+
+        let diff = trix - signal;
+        let prev_diff = self.prev_diff.replace(diff);
+
+        match prev_diff {
+            // TRIX crosses above its signal line, momentum is turning up - potential buy signal
+            Some(prev_diff) if prev_diff <= 0.0 && diff > 0.0 => AlgorithmEvalResult::Buy,
+            // TRIX crosses below its signal line, momentum is turning down - potential sell signal
+            Some(prev_diff) if prev_diff >= 0.0 && diff < 0.0 => AlgorithmEvalResult::Sell,
+            _ => AlgorithmEvalResult::Ignore,
+        }
+    }
+
+    fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    fn get_params(&self) -> &Value {
+        &self.params
+    }
+
+    fn set_params(&mut self, params: Value) -> Result<(), AlgorithmError> {
+        let period = parse_usize_from_value("period", &params).unwrap_or(self.period);
+        let signal_period =
+            parse_usize_from_value("signal_period", &params).unwrap_or(self.signal_period);
+
+        self.period = period;
+        self.signal_period = signal_period;
+        self.emas = Self::build_emas(period);
+        self.prev_triple_ema = None;
+        self.signal = Ema::new(signal_period);
+        self.prev_diff = None;
+        self.params = params;
+
+        Ok(())
+    }
+
+    fn max_period(&self) -> usize {
+        // three chained EMAs, one rate of change and the signal line
+        3 * self.period + 1 + self.signal_period
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests TRIX is the rate of change of the triple EMA, which is the close for a period of
+    /// 1, and crossovers of its signal line.
+    #[test]
+    fn test_trix() {
+        let build = || {
+            Trix::new(
+                Duration::from_secs(60),
+                json!({"period": 1, "signal_period": 2}),
+            )
+            .unwrap()
+        };
+
+        let mut trix = build();
+        assert_eq!(trix.update_trix(100.0), None);
+        assert!((trix.update_trix(110.0).unwrap() - 10.0).abs() < 1e-9);
+        assert!((trix.update_trix(99.0).unwrap() - -10.0).abs() < 1e-9);
+
+        let mut trix = build();
+        let data_points = AlgorithmDataPointManager::new(1);
+        let mut evaluate = |close: f64| {
+            trix.evaluate(
+                Kline {
+                    close,
+                    ..Default::default()
+                },
+                &data_points,
+            )
+        };
+        assert!(matches!(evaluate(100.0), AlgorithmEvalResult::Ignore));
+        // TRIX 10, the signal line isn't ready
+        assert!(matches!(evaluate(110.0), AlgorithmEvalResult::Ignore));
+        // TRIX -10 below a signal of -10 / 3
+        assert!(matches!(evaluate(99.0), AlgorithmEvalResult::Ignore));
+        // TRIX 10 above a signal of 50 / 9
+        assert!(matches!(evaluate(108.9), AlgorithmEvalResult::Buy));
+    }
+}
@@ -0,0 +1,304 @@
+use crate::market::kline::Kline;
+use crate::strategy::{
+    algorithm::Algorithm,
+    data_points::AlgorithmDataPointManager,
+    types::{AlgorithmError, AlgorithmEvalResult},
+};
+use crate::utils::number::parse_usize_from_value;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+/// Price levels of a volume profile.
+#[derive(Debug, Clone, PartialEq)]
+struct ValueArea {
+    /// Point of control, the price level with the most traded volume.
+    poc: f64,
+    /// Value area low.
+    val: f64,
+    /// Value area high.
+    vah: f64,
+}
+
+/// Builds a volume profile over the previous `period` klines and trades reactions at its
+/// value area boundaries.
+///
+/// Rejection: a kline opening inside the value area or beyond the boundary it is rejected from,
+/// wicking through the boundary and closing back inside fades the move. Klines spanning the
+/// whole value area are ignored.
+/// Acceptance: a kline opening and closing beyond a boundary follows the breakout.
+pub struct VolumeProfile {
+    interval: Duration,
+    params: Value,
+    period: usize,
+    bins: usize,
+    value_area_pct: f64, // Share of the total volume inside the value area, typically 70%
+}
+
+impl VolumeProfile {
+    /// Returns the JSON schema of the parameters accepted by the algorithm.
+    pub fn params_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "period": { "type": "integer", "minimum": 1, "default": 50 },
+                "bins": { "type": "integer", "minimum": 1, "default": 24 },
+                "value_area_pct": { "type": "number", "minimum": 0, "maximum": 1, "default": 0.7 }
+            },
+            "required": []
+        })
+    }
+
+    pub fn new(interval: Duration, params: Value) -> Result<Self, AlgorithmError> {
+        let period = parse_usize_from_value("period", &params).unwrap_or(50);
+        let bins = parse_usize_from_value("bins", &params).unwrap_or(24).max(1);
+        let value_area_pct = params
+            .get("value_area_pct")
+            .and_then(Value::as_f64)
+            .unwrap_or(0.7)
+            .clamp(0.0, 1.0);
+
+        Ok(Self {
+            interval,
+            params,
+            period,
+            bins,
+            value_area_pct,
+        })
+    }
+
+    /// Builds the volume profile of the given klines, attributing each kline's volume to the
+    /// price bin of its typical price.
+    ///
+    /// # Returns
+    ///
+    /// The `ValueArea` of the profile, or `None` if the klines have no price range or volume.
+    fn build_value_area<'a>(
+        &self,
+        klines: impl Iterator<Item = &'a Kline> + Clone,
+    ) -> Option<ValueArea> {
+        let low = klines.clone().map(|k| k.low).fold(f64::INFINITY, f64::min);
+        let high = klines
+            .clone()
+            .map(|k| k.high)
+            .fold(f64::NEG_INFINITY, f64::max);
+        let bin_width = (high - low) / self.bins as f64;
+        if !bin_width.is_finite() || bin_width <= 0.0 {
+            return None;
+        }
+
+        let mut volumes = vec![0.0; self.bins];
+        for kline in klines {
+            let typical_price = (kline.high + kline.low + kline.close) / 3.0;
+            let bin = (((typical_price - low) / bin_width) as usize).min(self.bins - 1);
+            volumes[bin] += kline.volume;
+        }
+
+        let total_volume: f64 = volumes.iter().sum();
+        if total_volume <= 0.0 {
+            return None;
+        }
+
+        // of bins with the same volume, the point of control is the one closest to the middle
+        let middle = (self.bins - 1) as f64 / 2.0;
+        let poc_bin = (0..self.bins)
+            .max_by(|a, b| {
+                volumes[*a].total_cmp(&volumes[*b]).then_with(|| {
+                    (*b as f64 - middle)
+                        .abs()
+                        .total_cmp(&(*a as f64 - middle).abs())
+                })
+            })
+            .unwrap_or(0);
+
+        // expand from the point of control two bins at a time towards the side with more
+        // volume, alternating sides on ties so symmetric profiles get symmetric value areas
+        let (mut lower, mut upper) = (poc_bin, poc_bin);
+        let mut area_volume = volumes[poc_bin];
+        let mut up_on_tie = true;
+        while area_volume < total_volume * self.value_area_pct {
+            let below_bins = lower.saturating_sub(2)..lower;
+            let above_bins = upper + 1..(upper + 3).min(self.bins);
+            let below: f64 = volumes[below_bins.clone()].iter().sum();
+            let above: f64 = volumes[above_bins.clone()].iter().sum();
+
+            let up = match (below_bins.is_empty(), above_bins.is_empty()) {
+                (true, true) => break,
+                (true, false) => true,
+                (false, true) => false,
+                (false, false) if above == below => {
+                    up_on_tie = !up_on_tie;
+                    !up_on_tie
+                }
+                (false, false) => above > below,
+            };
+            if up {
+                upper = above_bins.end - 1;
+                area_volume += above;
+            } else {
+                lower = below_bins.start;
+                area_volume += below;
+            }
+        }
+
+        Some(ValueArea {
+            poc: low + (poc_bin as f64 + 0.5) * bin_width,
+            val: low + lower as f64 * bin_width,
+            vah: low + (upper + 1) as f64 * bin_width,
+        })
+    }
+}
+
+impl Algorithm for VolumeProfile {
+    fn evaluate(
+        &mut self,
+        kline: Kline,
+        data_points: &AlgorithmDataPointManager,
+    ) -> AlgorithmEvalResult {
+        // the profile is built from the klines before the one being evaluated
+        if data_points.len() <= self.period {
+            return AlgorithmEvalResult::Ignore; // Not enough data
+        }
+        let history = data_points.iter().rev().skip(1).take(self.period);
+
+        let value_area = match self.build_value_area(history) {
+            Some(value_area) => value_area,
+            None => return AlgorithmEvalResult::Ignore,
+        };
+
+        // a kline spanning the whole value area was rejected from neither side
+        if kline.low < value_area.val && kline.high > value_area.vah {
+            return AlgorithmEvalResult::Ignore;
+        }
+
+        if kline.open <= value_area.vah
+            && kline.low < value_area.val
+            && kline.close > value_area.val
+        {
+            // Sellers were rejected below the value area - potential buy signal
+            AlgorithmEvalResult::Buy
+        } else if kline.open >= value_area.val
+            && kline.high > value_area.vah
+            && kline.close < value_area.vah
+        {
+            // Buyers were rejected above the value area - potential sell signal
+            AlgorithmEvalResult::Sell
+        } else if kline.open > value_area.vah && kline.close > value_area.vah {
+            // Price is accepted above the value area - potential buy signal
+            AlgorithmEvalResult::Buy
+        } else if kline.open < value_area.val && kline.close < value_area.val {
+            // Price is accepted below the value area - potential sell signal
+            AlgorithmEvalResult::Sell
+        } else {
+            AlgorithmEvalResult::Ignore
+        }
+    }
+
+    fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    fn get_params(&self) -> &Value {
+        &self.params
+    }
+
+    fn set_params(&mut self, params: Value) -> Result<(), AlgorithmError> {
+        if let Ok(period) = parse_usize_from_value("period", &params) {
+            self.period = period;
+        }
+        if let Ok(bins) = parse_usize_from_value("bins", &params) {
+            self.bins = bins.max(1);
+        }
+        if let Some(value_area_pct) = params.get("value_area_pct").and_then(Value::as_f64) {
+            self.value_area_pct = value_area_pct.clamp(0.0, 1.0);
+        }
+
+        self.params = params;
+        Ok(())
+    }
+
+    fn max_period(&self) -> usize {
+        // one extra data point for the kline being evaluated
+        self.period + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::harness::run_signals;
+
+    /// Builds a kline of the given prices with the given volume.
+    fn kline(open: f64, high: f64, low: f64, close: f64, volume: f64) -> Kline {
+        Kline {
+            open,
+            high,
+            low,
+            close,
+            volume,
+            ..Default::default()
+        }
+    }
+
+    /// Klines with typical prices of 101 to 111 in steps of 2, one per bin of 2 from 100 to
+    /// 112, with volumes of 5, 10, 40, 20, 15 and 10.
+    fn history() -> Vec<Kline> {
+        [5.0, 10.0, 40.0, 20.0, 15.0, 10.0]
+            .into_iter()
+            .enumerate()
+            .map(|(i, volume)| {
+                let low = 100.0 + 2.0 * i as f64;
+                kline(low + 1.0, low + 2.0, low, low + 1.0, volume)
+            })
+            .collect()
+    }
+
+    fn build() -> VolumeProfile {
+        VolumeProfile::new(
+            Duration::from_secs(60),
+            json!({"period": 6, "bins": 6, "value_area_pct": 0.7}),
+        )
+        .unwrap()
+    }
+
+    /// Tests the point of control is the bin with the most volume and the value area grows from
+    /// it towards the side with more volume until it holds 70% of the volume.
+    #[test]
+    fn test_volume_profile_value_area() {
+        // 40 of the 100 volume trade in the bin 104-106, the bins above add 35 against 15 below
+        assert_eq!(
+            build().build_value_area(history().iter()),
+            Some(ValueArea {
+                poc: 105.0,
+                val: 104.0,
+                vah: 110.0,
+            })
+        );
+
+        let flat = vec![kline(100.0, 100.0, 100.0, 100.0, 10.0); 6];
+        assert_eq!(build().build_value_area(flat.iter()), None);
+    }
+
+    /// Tests rejections only count for klines opening inside the value area or beyond the
+    /// rejected boundary, and klines spanning the value area are ignored.
+    #[test]
+    fn test_volume_profile_reactions() {
+        let signal = |kline: Kline| {
+            let mut klines = history();
+            klines.push(kline);
+            run_signals(&mut build(), &klines).pop().unwrap()
+        };
+
+        // rejected below the value area low of 104
+        assert_eq!(signal(kline(105.0, 106.0, 103.0, 105.5, 10.0)), 'B');
+        assert_eq!(signal(kline(103.5, 105.0, 103.0, 104.5, 10.0)), 'B');
+        // rejected above the value area high of 110
+        assert_eq!(signal(kline(109.0, 111.0, 108.0, 108.5, 10.0)), 'S');
+        assert_eq!(signal(kline(110.5, 111.0, 109.0, 109.5, 10.0)), 'S');
+        // accepted beyond the value area
+        assert_eq!(signal(kline(111.0, 112.5, 110.5, 112.0, 10.0)), 'B');
+        assert_eq!(signal(kline(103.0, 103.5, 101.0, 101.5, 10.0)), 'S');
+        // spanning the value area or staying inside it
+        assert_eq!(signal(kline(105.0, 111.0, 103.0, 106.0, 10.0)), '.');
+        assert_eq!(signal(kline(106.0, 108.0, 105.0, 107.0, 10.0)), '.');
+    }
+}
@@ -0,0 +1,213 @@
+use crate::market::kline::Kline;
+use crate::strategy::{
+    algorithm::Algorithm,
+    data_points::AlgorithmDataPointManager,
+    types::{AlgorithmError, AlgorithmEvalResult},
+};
+use crate::utils::number::parse_usize_from_value;
+use crate::utils::time::{floor_mili_ts, HOUR_AS_MILI};
+use serde_json::{json, Value};
+use std::time::Duration;
+
+pub struct VwapReversion {
+    interval: Duration,
+    params: Value,
+    session_hours: usize,
+    std_dev_multiplier: f64,
+    min_session_klines: usize,
+    session_start: Option<u64>, // Open time of the current VWAP session
+    session_klines: usize,
+    volume_sum: f64,
+    price_volume_sum: f64,    // Sum of typical price * volume
+    price_sq_volume_sum: f64, // Sum of typical price^2 * volume, for the standard deviation
+}
+
+impl VwapReversion {
+    /// Returns the JSON schema of the parameters accepted by the algorithm.
+    pub fn params_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "session_hours": { "type": "integer", "minimum": 1, "default": 24 },
+                "std_dev_multiplier": { "type": "number", "minimum": 0, "default": 2.0 },
+                "min_session_klines": { "type": "integer", "minimum": 1, "default": 10 }
+            },
+            "required": []
+        })
+    }
+
+    pub fn new(interval: Duration, params: Value) -> Result<Self, AlgorithmError> {
+        let session_hours = parse_usize_from_value("session_hours", &params).unwrap_or(24); // Default to daily sessions
+        let std_dev_multiplier = params
+            .get("std_dev_multiplier")
+            .and_then(Value::as_f64)
+            .unwrap_or(2.0);
+        let min_session_klines =
+            parse_usize_from_value("min_session_klines", &params).unwrap_or(10);
+
+        if session_hours == 0 {
+            return Err(AlgorithmError::InvalidParams(
+                "session_hours must be greater than 0".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            interval,
+            params,
+            session_hours,
+            std_dev_multiplier,
+            min_session_klines,
+            session_start: None,
+            session_klines: 0,
+            volume_sum: 0.0,
+            price_volume_sum: 0.0,
+            price_sq_volume_sum: 0.0,
+        })
+    }
+
+    /// Adds a kline to the session VWAP, starting a new session when the kline falls outside
+    /// the current one.
+    ///
+    /// # Returns
+    ///
+    /// The session `(vwap, std_dev)`, or `None` if the session has no volume yet.
+    fn update_vwap(&mut self, kline: &Kline) -> Option<(f64, f64)> {
+        let session_start =
+            floor_mili_ts(kline.open_time, self.session_hours as u64 * HOUR_AS_MILI);
+        if self.session_start != Some(session_start) {
+            self.reset_session(Some(session_start));
+        }
+
+        let typical_price = (kline.high + kline.low + kline.close) / 3.0;
+        self.session_klines += 1;
+        self.volume_sum += kline.volume;
+        self.price_volume_sum += typical_price * kline.volume;
+        self.price_sq_volume_sum += typical_price * typical_price * kline.volume;
+
+        if self.volume_sum == 0.0 {
+            return None;
+        }
+
+        let vwap = self.price_volume_sum / self.volume_sum;
+        // running sums can drift slightly negative for flat prices
+        let variance = (self.price_sq_volume_sum / self.volume_sum - vwap * vwap).max(0.0);
+
+        Some((vwap, variance.sqrt()))
+    }
+
+    fn reset_session(&mut self, session_start: Option<u64>) {
+        self.session_start = session_start;
+        self.session_klines = 0;
+        self.volume_sum = 0.0;
+        self.price_volume_sum = 0.0;
+        self.price_sq_volume_sum = 0.0;
+    }
+}
+
+impl Algorithm for VwapReversion {
+    fn evaluate(
+        &mut self,
+        kline: Kline,
+        _data_points: &AlgorithmDataPointManager,
+    ) -> AlgorithmEvalResult {
+        let (vwap, std_dev) = match self.update_vwap(&kline) {
+            Some(vwap) => vwap,
+            None => return AlgorithmEvalResult::Ignore,
+        };
+
+        // Not enough data in the session yet, or price hasn't moved
+        if self.session_klines < self.min_session_klines || std_dev == 0.0 {
+            return AlgorithmEvalResult::Ignore;
+        }
+
+        // number of standard deviations price is away from VWAP
+        let deviation = (kline.close - vwap) / std_dev;
+        let threshold = self.std_dev_multiplier;
+
+        // confidence grows from 0.5 at the threshold to 1.0 at twice the threshold
+        let confidence = |deviation: f64| {
+            if threshold == 0.0 {
+                1.0
+            } else {
+                (0.5 + 0.5 * (deviation - threshold) / threshold).min(1.0)
+            }
+        };
+
+        if deviation < -threshold {
+            // Price is stretched below VWAP - expect reversion up
+            AlgorithmEvalResult::Long(confidence(-deviation))
+        } else if deviation > threshold {
+            // Price is stretched above VWAP - expect reversion down
+            AlgorithmEvalResult::Short(confidence(deviation))
+        } else {
+            AlgorithmEvalResult::Ignore
+        }
+    }
+
+    fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    fn get_params(&self) -> &Value {
+        &self.params
+    }
+
+    fn set_params(&mut self, params: Value) -> Result<(), AlgorithmError> {
+        if let Ok(session_hours) = parse_usize_from_value("session_hours", &params) {
+            if session_hours == 0 {
+                return Err(AlgorithmError::InvalidParams(
+                    "session_hours must be greater than 0".to_string(),
+                ));
+            }
+            self.session_hours = session_hours;
+            self.reset_session(None);
+        }
+        if let Some(std_dev_multiplier) = params.get("std_dev_multiplier").and_then(Value::as_f64) {
+            self.std_dev_multiplier = std_dev_multiplier;
+        }
+        if let Ok(min_session_klines) = parse_usize_from_value("min_session_klines", &params) {
+            self.min_session_klines = min_session_klines;
+        }
+
+        self.params = params;
+        Ok(())
+    }
+
+    fn max_period(&self) -> usize {
+        self.min_session_klines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests the VWAP and its standard deviation are weighted by volume and start over with
+    /// every session.
+    #[test]
+    fn test_update_vwap() {
+        let mut vwap =
+            VwapReversion::new(Duration::from_secs(60), json!({"session_hours": 24})).unwrap();
+        let kline = |open_time: u64, price: f64, volume: f64| Kline {
+            open_time,
+            high: price,
+            low: price,
+            close: price,
+            volume,
+            ..Default::default()
+        };
+
+        assert_eq!(vwap.update_vwap(&kline(0, 10.0, 1.0)), Some((10.0, 0.0)));
+        // (10 * 1 + 20 * 3) / 4, variance (100 * 1 + 400 * 3) / 4 - 17.5^2
+        let (price, std_dev) = vwap.update_vwap(&kline(60_000, 20.0, 3.0)).unwrap();
+        assert_eq!(price, 17.5);
+        assert!((std_dev - 18.75_f64.sqrt()).abs() < 1e-9);
+        assert_eq!(vwap.session_klines, 2);
+
+        assert_eq!(
+            vwap.update_vwap(&kline(24 * HOUR_AS_MILI, 30.0, 2.0)),
+            Some((30.0, 0.0))
+        );
+        assert_eq!(vwap.session_klines, 1);
+    }
+}
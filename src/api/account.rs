@@ -1,22 +1,29 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 
+use actix_web::post;
 use actix_web::{
     get,
     web::{self, scope, Json},
-    HttpResponse, Responder, Scope,
+    HttpResponse, Responder, ResponseError, Scope,
 };
-use actix_web::{post, HttpRequest};
 
 use log::info;
 use serde::Deserialize;
 use serde_json::json;
 
 use crate::{
-    account::trade::{OrderSide, Position, PositionId},
+    account::money::Price,
+    account::trade::{validate_exit_prices, OrderSide, Position, PositionId},
     exchange::mock::MockExchangeApi,
     strategy::strategy::StrategyId,
 };
-use crate::{app::AppState, exchange::api::ExchangeApi};
+use crate::{
+    api::{error::ApiErrorResponse, tenant::TenantScope},
+    app::AppState,
+    exchange::api::ExchangeApi,
+    market::symbol::canonical_symbol,
+};
 
 #[derive(Debug, Deserialize)]
 pub struct ClosePosParams {
@@ -25,44 +32,45 @@ pub struct ClosePosParams {
 #[post("/close-position")]
 async fn close_position(
     app_data: web::Data<AppState>,
+    scope: TenantScope,
     body: Json<ClosePosParams>,
 ) -> impl Responder {
-    let account = app_data.get_account().await;
+    let account = scope.account(&app_data).await;
     let market = app_data.get_market().await;
-    let market = market.lock().await;
     let mut account = account.lock().await;
 
     let pos = account.get_position(&body.position_id);
     if pos.is_none() {
-        let json_data =
-            json!({ "error": "Unable to find position", "position_id":body.position_id });
-        return HttpResponse::ExpectationFailed().json(json_data);
+        return ApiErrorResponse::not_found("Unable to find position")
+            .with_detail("position_id", body.position_id)
+            .error_response();
     };
 
     // SAFETY: None check above
     let position = pos.unwrap().clone();
 
     if let Some(last_price) = market.last_price(&position.symbol).await {
-        let res = account.close_position(position.id, last_price).await;
+        let res = account.close_position(position.id, last_price.into()).await;
 
         if let Some(trade) = res {
             let json_data = json!({ "success": "Position Closed", "trade": trade });
             HttpResponse::Ok().json(json_data)
         } else {
-            let json_data = json!({ "error": "Unable to close position" });
-            HttpResponse::ExpectationFailed().json(json_data)
+            ApiErrorResponse::exchange("Unable to close position")
+                .with_detail("position_id", position.id)
+                .error_response()
         }
     } else {
-        let json_data = json!({ "error": "Unable to close position, last price not found", "symbol": position.symbol });
-        HttpResponse::ExpectationFailed().json(json_data)
+        ApiErrorResponse::conflict("Unable to close position, last price not found")
+            .with_detail("symbol", &position.symbol)
+            .error_response()
     }
 }
 
 #[get("/close-all-positions")]
-async fn close_all_positions(app_data: web::Data<AppState>) -> impl Responder {
-    let account = app_data.get_account().await;
+async fn close_all_positions(app_data: web::Data<AppState>, scope: TenantScope) -> impl Responder {
+    let account = scope.account(&app_data).await;
     let market = app_data.get_market().await;
-    let market = market.lock().await;
     let mut account = account.lock().await;
 
     let mut trades = vec![];
@@ -71,12 +79,13 @@ async fn close_all_positions(app_data: web::Data<AppState>) -> impl Responder {
 
     for position in positions {
         if let Some(last_price) = market.last_price(&position.symbol).await {
-            if let Some(trade) = account.close_position(position.id, last_price).await {
+            if let Some(trade) = account.close_position(position.id, last_price.into()).await {
                 trades.push(trade.clone())
             }
         } else {
-            let json_data = json!({ "error": "Unable to close position, last price not found", "symbol": position.clone().symbol });
-            return HttpResponse::ExpectationFailed().json(json_data);
+            return ApiErrorResponse::conflict("Unable to close position, last price not found")
+                .with_detail("symbol", &position.symbol)
+                .error_response();
         }
     }
 
@@ -86,55 +95,120 @@ async fn close_all_positions(app_data: web::Data<AppState>) -> impl Responder {
 
 #[derive(Debug, Deserialize)]
 pub struct OpenPosParams {
+    #[serde(deserialize_with = "canonical_symbol")]
     symbol: String,
     margin: f64,
     leverage: u32,
     order_side: OrderSide,
-    stop_loss: Option<f64>,
+    stop_loss: Option<Price>,
+    take_profit: Option<Price>,
     strategy_id: Option<StrategyId>,
 }
 #[post("/open-position")]
-async fn open_position(app_data: web::Data<AppState>, body: Json<OpenPosParams>) -> impl Responder {
-    let account = app_data.get_account().await;
+async fn open_position(
+    app_data: web::Data<AppState>,
+    scope: TenantScope,
+    body: Json<OpenPosParams>,
+) -> impl Responder {
+    let account = scope.account(&app_data).await;
     let market = app_data.get_market().await;
 
-    let market = market.try_lock();
     let mut account = account.lock().await;
 
-    if let Some(market) = market {
-        if let Some(last_price) = market.last_price(&body.symbol).await {
-            let res = account
-                .open_position(
-                    &body.symbol,
-                    body.margin,
-                    body.leverage,
-                    body.order_side.clone(),
-                    last_price,
-                    body.strategy_id,
-                    body.stop_loss,
-                )
-                .await;
-
-            if let Some(res) = res {
-                let json_data = json!({ "success": "Position Opened", "position": res });
-                HttpResponse::Ok().json(json_data)
-            } else {
-                let json_data = json!({ "error": "Unable to open position" });
-                HttpResponse::ExpectationFailed().json(json_data)
-            }
+    if let Some(last_price) = market.last_price(&body.symbol).await {
+        let last_price = Price::from_f64(last_price);
+        if let Err(e) = validate_exit_prices(
+            body.order_side,
+            last_price,
+            body.stop_loss,
+            body.take_profit,
+        ) {
+            return ApiErrorResponse::bad_request(e).error_response();
+        }
+
+        let res = account
+            .open_position(
+                &body.symbol,
+                body.margin,
+                body.leverage,
+                body.order_side,
+                last_price,
+                body.strategy_id,
+                body.stop_loss,
+            )
+            .await
+            .map(|position| position.id);
+
+        // the take profit is set once opened, so it is persisted along with the position
+        let res = match (res, body.take_profit) {
+            (Some(position_id), Some(_)) => account
+                .set_position_exits(position_id, body.stop_loss, body.take_profit)
+                .await
+                .cloned(),
+            (Some(position_id), None) => account.get_position(&position_id).cloned(),
+            (None, _) => None,
+        };
+
+        if let Some(res) = res {
+            let json_data = json!({ "success": "Position Opened", "position": res });
+            HttpResponse::Ok().json(json_data)
         } else {
-            let json_data = json!({ "error": "Unable to open position" });
-            HttpResponse::ExpectationFailed().json(json_data)
+            ApiErrorResponse::exchange("Unable to open position").error_response()
         }
     } else {
-        let json_data = json!({ "error": "Unable to get market lock" });
-        HttpResponse::ExpectationFailed().json(json_data)
+        ApiErrorResponse::conflict("Unable to open position, last price not found")
+            .with_detail("symbol", &body.symbol)
+            .error_response()
     }
 }
 
+#[derive(Debug, Deserialize)]
+struct SetPositionExitsParams {
+    position_id: PositionId,
+    stop_loss: Option<Price>,
+    take_profit: Option<Price>,
+}
+#[post("/set-position-exits")]
+async fn set_position_exits(
+    app_data: web::Data<AppState>,
+    scope: TenantScope,
+    body: Json<SetPositionExitsParams>,
+) -> impl Responder {
+    let account = scope.account(&app_data).await;
+    let market = app_data.get_market().await;
+    let mut account = account.lock().await;
+
+    let Some(position) = account.get_position(&body.position_id).cloned() else {
+        return ApiErrorResponse::not_found("Unable to find position")
+            .with_detail("position_id", body.position_id)
+            .error_response();
+    };
+
+    // exits already reached would close the position right away
+    if let Some(last_price) = market.last_price(&position.symbol).await {
+        if let Err(e) = validate_exit_prices(
+            position.order_side,
+            last_price.into(),
+            body.stop_loss,
+            body.take_profit,
+        ) {
+            return ApiErrorResponse::bad_request(e).error_response();
+        }
+    }
+
+    let position = account
+        .set_position_exits(body.position_id, body.stop_loss, body.take_profit)
+        .await;
+    let json_data = json!({ "success": "Position Exits Set", "position": position });
+    HttpResponse::Ok().json(json_data)
+}
+
 #[get("/active-positions")]
-async fn list_active_positions(app_data: web::Data<AppState>, _req: HttpRequest) -> impl Responder {
-    let account = app_data.get_account().await;
+async fn list_active_positions(
+    app_data: web::Data<AppState>,
+    scope: TenantScope,
+) -> impl Responder {
+    let account = scope.account(&app_data).await;
     let mut positions = vec![];
 
     for position in account.lock().await.positions() {
@@ -147,8 +221,8 @@ async fn list_active_positions(app_data: web::Data<AppState>, _req: HttpRequest)
 }
 
 #[get("/trades")]
-async fn list_trades(app_data: web::Data<AppState>, _req: HttpRequest) -> impl Responder {
-    let account = app_data.get_account().await;
+async fn list_trades(app_data: web::Data<AppState>, scope: TenantScope) -> impl Responder {
+    let account = scope.account(&app_data).await;
     let mut trades = vec![];
 
     for trade in account.lock().await.trades() {
@@ -160,9 +234,29 @@ async fn list_trades(app_data: web::Data<AppState>, _req: HttpRequest) -> impl R
     HttpResponse::Ok().json(json_data)
 }
 
+#[get("/orders")]
+async fn list_orders(app_data: web::Data<AppState>, scope: TenantScope) -> impl Responder {
+    let account = scope.account(&app_data).await;
+    let orders = account.lock().await.orders().to_vec();
+
+    let json_data = json!({ "orders": orders });
+
+    HttpResponse::Ok().json(json_data)
+}
+
+#[get("/iceberg-orders")]
+async fn list_iceberg_orders(app_data: web::Data<AppState>, scope: TenantScope) -> impl Responder {
+    let account = scope.account(&app_data).await;
+    let iceberg_orders = account.lock().await.iceberg_orders().to_vec();
+
+    let json_data = json!({ "iceberg_orders": iceberg_orders });
+
+    HttpResponse::Ok().json(json_data)
+}
+
 #[get("/account-info")]
-async fn account_info(app_data: web::Data<AppState>, _req: HttpRequest) -> impl Responder {
-    let account = app_data.get_account().await;
+async fn account_info(app_data: web::Data<AppState>, scope: TenantScope) -> impl Responder {
+    let account = scope.account(&app_data).await;
     let info = account.lock().await.info().await;
 
     let json_data = json!({ "account_info": info });
@@ -170,6 +264,155 @@ async fn account_info(app_data: web::Data<AppState>, _req: HttpRequest) -> impl
     HttpResponse::Ok().json(json_data)
 }
 
+/// Lists the credential profiles with the open positions and equity of their accounts, the
+/// profile of the tenant only for tenants.
+#[get("/profiles")]
+async fn list_profiles(app_data: web::Data<AppState>, scope: TenantScope) -> impl Responder {
+    let mut profiles = app_data.bot.lock().await.list_profile_accounts().await;
+    if let TenantScope::Tenant(tenant) = &scope {
+        profiles.retain(|profile| profile.profile.name == tenant.profile);
+    }
+
+    let json_data = json!({ "profiles": profiles });
+
+    HttpResponse::Ok().json(json_data)
+}
+
+#[get("/equity")]
+async fn account_equity(app_data: web::Data<AppState>, scope: TenantScope) -> impl Responder {
+    let account = scope.account(&app_data).await;
+    let market = app_data.get_market().await;
+
+    let account = account.lock().await;
+    let balance = account.balance().await;
+    let equity = account.equity(market).await;
+
+    let json_data = json!({
+        "balance": balance,
+        "realized_profit": equity.realized_profit,
+        "unrealized_profit": equity.unrealized_profit,
+        "equity": equity.equity(),
+        "open_positions": equity.open_positions,
+    });
+
+    HttpResponse::Ok().json(json_data)
+}
+
+#[derive(Debug, Deserialize)]
+struct ListPositionsParams {
+    strategy_id: Option<StrategyId>,
+}
+#[get("/positions")]
+async fn list_positions(
+    app_data: web::Data<AppState>,
+    scope: TenantScope,
+    query: web::Query<ListPositionsParams>,
+) -> impl Responder {
+    let account = scope.account(&app_data).await;
+    let account = account.lock().await;
+
+    // positions opened manually are counted under "manual"
+    let mut counts_by_strategy: HashMap<String, usize> = HashMap::new();
+    for position in account.positions() {
+        let key = position
+            .strategy_id
+            .map_or("manual".to_string(), |strategy_id| strategy_id.to_string());
+        *counts_by_strategy.entry(key).or_default() += 1;
+    }
+
+    let positions: Vec<Position> = account
+        .positions()
+        .filter(|position| query.strategy_id.is_none() || position.strategy_id == query.strategy_id)
+        .cloned()
+        .collect();
+
+    let json_data = json!({
+        "positions": positions,
+        "total": account.positions().len(),
+        "by_strategy": counts_by_strategy,
+    });
+
+    HttpResponse::Ok().json(json_data)
+}
+
+#[derive(Debug, Deserialize)]
+struct TradeHistoryParams {
+    strategy_id: Option<StrategyId>,
+    #[serde(default)]
+    offset: usize,
+    limit: Option<usize>,
+}
+#[get("/trade-history")]
+async fn trade_history(
+    app_data: web::Data<AppState>,
+    scope: TenantScope,
+    query: web::Query<TradeHistoryParams>,
+) -> impl Responder {
+    let account = scope.account(&app_data).await;
+    let (trades, total) =
+        account
+            .lock()
+            .await
+            .trade_page(query.strategy_id, query.offset, query.limit);
+
+    // more trades follow the page if it ends before the total
+    let page_end = query.offset + trades.len();
+    let next_offset = (page_end < total).then_some(page_end);
+    let json_data = json!({
+        "trades": trades,
+        "total": total,
+        "offset": query.offset,
+        "limit": query.limit,
+        "next_offset": next_offset,
+    });
+
+    HttpResponse::Ok().json(json_data)
+}
+
+#[get("/unrealized-pnl")]
+async fn unrealized_pnl(app_data: web::Data<AppState>, scope: TenantScope) -> impl Responder {
+    let account = scope.account(&app_data).await;
+    let market = app_data.get_market().await;
+
+    let pnls = account.lock().await.position_pnls(market).await;
+    let total: f64 = pnls.iter().map(|pnl| pnl.unrealized_profit).sum();
+
+    let json_data = json!({ "positions": pnls, "total": total });
+
+    HttpResponse::Ok().json(json_data)
+}
+
+#[derive(Debug, Deserialize)]
+struct DailyReportsParams {
+    /// Returns the latest reports only.
+    limit: Option<usize>,
+}
+#[get("/daily-reports")]
+async fn list_daily_reports(
+    app_data: web::Data<AppState>,
+    scope: TenantScope,
+    query: web::Query<DailyReportsParams>,
+) -> impl Responder {
+    // reports cover every account of the bot
+    if let Err(e) = scope.require_operator() {
+        return e.error_response();
+    }
+
+    let reports = app_data.bot.lock().await.list_daily_reports().await;
+
+    match reports {
+        Ok(mut reports) => {
+            if let Some(limit) = query.limit {
+                reports.drain(..reports.len().saturating_sub(limit));
+            }
+            HttpResponse::Ok().json(json!({ "reports": reports }))
+        }
+        Err(e) => ApiErrorResponse::from(e)
+            .context("Unable to list daily reports")
+            .error_response(),
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct SetExchangeApiParams {
     exchange: String,
@@ -178,8 +421,13 @@ struct SetExchangeApiParams {
 #[post("/set-exchange-api")]
 async fn set_exchange_api(
     app_data: web::Data<AppState>,
+    scope: TenantScope,
     body: Json<SetExchangeApiParams>,
 ) -> impl Responder {
+    if let Err(e) = scope.require_operator() {
+        return e.error_response();
+    }
+
     let api = match body.exchange.as_str() {
         "Bing" => app_data.get_exchange_api().await,
         "Binance" => app_data.get_exchange_api().await,
@@ -188,8 +436,9 @@ async fn set_exchange_api(
             api
         }
         _ => {
-            let json_data = json!({ "error": "Unknown exchange API" });
-            return HttpResponse::Ok().json(json_data);
+            return ApiErrorResponse::bad_request("Unknown exchange API")
+                .with_detail("exchange", &body.exchange)
+                .error_response();
         }
     };
 
@@ -209,6 +458,15 @@ pub fn register_account_service() -> Scope {
         .service(open_position)
         .service(close_position)
         .service(close_all_positions)
+        .service(set_position_exits)
         .service(list_active_positions)
         .service(list_trades)
+        .service(list_orders)
+        .service(list_iceberg_orders)
+        .service(account_equity)
+        .service(list_positions)
+        .service(trade_history)
+        .service(unrealized_pnl)
+        .service(list_profiles)
+        .service(list_daily_reports)
 }
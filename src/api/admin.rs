@@ -0,0 +1,357 @@
+use std::time::Duration;
+
+use actix_web::web::Json;
+use actix_web::{
+    delete, get,
+    http::header,
+    post,
+    rt::{self, System},
+    web::{self, scope},
+    HttpRequest, HttpResponse, Responder, ResponseError, Scope,
+};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::api::error::ApiErrorResponse;
+use crate::app::AppState;
+use crate::audit::AuditQuery;
+use crate::scheduler::ScheduledJob;
+use crate::shutdown::ShutdownPolicy;
+use crate::tenant::AdminToken;
+
+/// Time given to the shutdown response to reach the client before the server stops.
+const SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(1);
+
+/// Checks the request carries the admin token set in `ADMIN_API_TOKEN` as a bearer token.
+/// Requests are refused while no token is set.
+fn authorize(req: &HttpRequest, admin_token: &AdminToken) -> Result<(), ApiErrorResponse> {
+    if !admin_token.is_set() {
+        return Err(ApiErrorResponse::unauthorized(
+            "ADMIN_API_TOKEN isn't set, admin routes are disabled",
+        ));
+    }
+
+    let token = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    match token {
+        Some(token) if admin_token.matches(token) => Ok(()),
+        _ => Err(ApiErrorResponse::unauthorized("Invalid admin token")),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ShutdownParams {
+    /// Keeps open positions for the strategies to resume on restart by default.
+    #[serde(default)]
+    policy: ShutdownPolicy,
+}
+#[post("/shutdown")]
+async fn shutdown(
+    app_data: web::Data<AppState>,
+    req: HttpRequest,
+    body: Json<ShutdownParams>,
+) -> impl Responder {
+    if let Err(e) = authorize(&req, &app_data.admin_token) {
+        return e.error_response();
+    }
+
+    let report = app_data.bot.lock().await.shutdown(body.policy).await;
+
+    rt::spawn(async {
+        rt::time::sleep(SHUTDOWN_GRACE_PERIOD).await;
+        System::current().stop();
+    });
+
+    let json_data = json!({ "success": "Shutting down", "report": report });
+    HttpResponse::Ok().json(json_data)
+}
+
+/// Pauses trading, cancels all working orders and closes every open position at market.
+#[post("/flatten-all")]
+async fn flatten_all(app_data: web::Data<AppState>, req: HttpRequest) -> impl Responder {
+    if let Err(e) = authorize(&req, &app_data.admin_token) {
+        return e.error_response();
+    }
+
+    let report = app_data.bot.lock().await.flatten_all().await;
+
+    let json_data = json!({ "success": "Flattened, trading is paused", "report": report });
+    HttpResponse::Ok().json(json_data)
+}
+
+#[post("/resume-trading")]
+async fn resume_trading(app_data: web::Data<AppState>, req: HttpRequest) -> impl Responder {
+    if let Err(e) = authorize(&req, &app_data.admin_token) {
+        return e.error_response();
+    }
+
+    app_data.bot.lock().await.resume_trading().await;
+
+    let json_data = json!({ "success": "Trading resumed" });
+    HttpResponse::Ok().json(json_data)
+}
+
+/// Lists the scheduled jobs with their next and last run.
+#[get("/schedule")]
+async fn list_scheduled_jobs(app_data: web::Data<AppState>, req: HttpRequest) -> impl Responder {
+    if let Err(e) = authorize(&req, &app_data.admin_token) {
+        return e.error_response();
+    }
+
+    let jobs = app_data.bot.lock().await.scheduled_jobs();
+
+    let json_data = json!({ "jobs": jobs });
+    HttpResponse::Ok().json(json_data)
+}
+
+/// Schedules a job, replacing a job of the same name. Changes are written to the schedule file.
+#[post("/schedule")]
+async fn schedule_job(
+    app_data: web::Data<AppState>,
+    req: HttpRequest,
+    body: Json<ScheduledJob>,
+) -> impl Responder {
+    if let Err(e) = authorize(&req, &app_data.admin_token) {
+        return e.error_response();
+    }
+
+    let job = body.into_inner();
+    let name = job.name.clone();
+    match app_data.bot.lock().await.schedule_job(job) {
+        Ok(()) => HttpResponse::Ok().json(json!({ "success": "Job scheduled", "name": name })),
+        Err(e) => ApiErrorResponse::bad_request(e).error_response(),
+    }
+}
+
+#[delete("/schedule/{name}")]
+async fn unschedule_job(
+    app_data: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<String>,
+) -> impl Responder {
+    if let Err(e) = authorize(&req, &app_data.admin_token) {
+        return e.error_response();
+    }
+
+    let name = path.into_inner();
+    match app_data.bot.lock().await.unschedule_job(&name) {
+        Ok(true) => HttpResponse::Ok().json(json!({ "success": "Job removed", "name": name })),
+        Ok(false) => ApiErrorResponse::not_found("Unable to find job")
+            .with_detail("name", name)
+            .error_response(),
+        Err(e) => ApiErrorResponse::internal(e).error_response(),
+    }
+}
+
+/// Runs a scheduled job now, whether it is due or not.
+#[post("/schedule/{name}/run")]
+async fn run_scheduled_job(
+    app_data: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<String>,
+) -> impl Responder {
+    if let Err(e) = authorize(&req, &app_data.admin_token) {
+        return e.error_response();
+    }
+
+    let name = path.into_inner();
+    match app_data.bot.lock().await.run_scheduled_job(&name).await {
+        Some(run) => HttpResponse::Ok().json(json!({ "name": name, "run": run })),
+        None => ApiErrorResponse::not_found("Unable to find job")
+            .with_detail("name", name)
+            .error_response(),
+    }
+}
+
+/// Reloads the configuration file, applying the changes safe to make while strategies run.
+#[post("/reload-config")]
+async fn reload_config(app_data: web::Data<AppState>, req: HttpRequest) -> impl Responder {
+    if let Err(e) = authorize(&req, &app_data.admin_token) {
+        return e.error_response();
+    }
+
+    match app_data.bot.lock().await.reload_config().await {
+        Ok(report) => {
+            HttpResponse::Ok().json(json!({ "success": "Config reloaded", "report": report }))
+        }
+        Err(e) => ApiErrorResponse::from(e).error_response(),
+    }
+}
+
+/// Checkpoints the bot state into a snapshot.
+#[post("/snapshots")]
+async fn create_snapshot(app_data: web::Data<AppState>, req: HttpRequest) -> impl Responder {
+    if let Err(e) = authorize(&req, &app_data.admin_token) {
+        return e.error_response();
+    }
+
+    match app_data.bot.lock().await.create_snapshot().await {
+        Ok(snapshot) => HttpResponse::Ok().json(snapshot),
+        Err(e) => ApiErrorResponse::from(e)
+            .context("Unable to create snapshot")
+            .error_response(),
+    }
+}
+
+#[get("/snapshots")]
+async fn list_snapshots(app_data: web::Data<AppState>, req: HttpRequest) -> impl Responder {
+    if let Err(e) = authorize(&req, &app_data.admin_token) {
+        return e.error_response();
+    }
+
+    match app_data.bot.lock().await.list_snapshots() {
+        Ok(snapshots) => HttpResponse::Ok().json(json!({ "snapshots": snapshots })),
+        Err(e) => ApiErrorResponse::from(e)
+            .context("Unable to list snapshots")
+            .error_response(),
+    }
+}
+
+/// Replaces the running strategies, positions and streams by those of a snapshot.
+#[post("/snapshots/{name}/restore")]
+async fn restore_snapshot(
+    app_data: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<String>,
+) -> impl Responder {
+    if let Err(e) = authorize(&req, &app_data.admin_token) {
+        return e.error_response();
+    }
+
+    let name = path.into_inner();
+    match app_data.bot.lock().await.restore_snapshot(&name).await {
+        Ok(report) => {
+            HttpResponse::Ok().json(json!({ "success": "Snapshot restored", "report": report }))
+        }
+        Err(e) => ApiErrorResponse::from(e)
+            .context("Unable to restore snapshot")
+            .with_detail("name", name)
+            .error_response(),
+    }
+}
+
+/// Creates a compressed backup of the stored data.
+#[post("/backup")]
+async fn create_backup(app_data: web::Data<AppState>, req: HttpRequest) -> impl Responder {
+    if let Err(e) = authorize(&req, &app_data.admin_token) {
+        return e.error_response();
+    }
+
+    match app_data.bot.lock().await.create_backup().await {
+        Ok(backup) => HttpResponse::Ok().json(backup),
+        Err(e) => ApiErrorResponse::from(e)
+            .context("Unable to create backup")
+            .error_response(),
+    }
+}
+
+#[get("/backups")]
+async fn list_backups(app_data: web::Data<AppState>, req: HttpRequest) -> impl Responder {
+    if let Err(e) = authorize(&req, &app_data.admin_token) {
+        return e.error_response();
+    }
+
+    match app_data.bot.lock().await.list_backups().await {
+        Ok(backups) => HttpResponse::Ok().json(json!({ "backups": backups })),
+        Err(e) => ApiErrorResponse::from(e)
+            .context("Unable to list backups")
+            .error_response(),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RestoreBackupParams {
+    name: String,
+}
+/// Replaces all stored data by that of a backup, refused while strategies are running.
+#[post("/restore-backup")]
+async fn restore_backup(
+    app_data: web::Data<AppState>,
+    req: HttpRequest,
+    body: Json<RestoreBackupParams>,
+) -> impl Responder {
+    if let Err(e) = authorize(&req, &app_data.admin_token) {
+        return e.error_response();
+    }
+
+    match app_data.bot.lock().await.restore_backup(&body.name).await {
+        Ok(()) => HttpResponse::Ok().json(json!({ "success": format!("Restored {}", body.name) })),
+        Err(e) => ApiErrorResponse::from(e)
+            .context("Unable to restore backup")
+            .with_detail("name", body.name.clone())
+            .error_response(),
+    }
+}
+
+/// Lists the audit log entries matching the query, oldest first.
+#[get("/audit-log")]
+async fn get_audit_log(
+    app_data: web::Data<AppState>,
+    req: HttpRequest,
+    query: web::Query<AuditQuery>,
+) -> impl Responder {
+    if let Err(e) = authorize(&req, &app_data.admin_token) {
+        return e.error_response();
+    }
+
+    match app_data
+        .bot
+        .lock()
+        .await
+        .get_audit_log(query.into_inner())
+        .await
+    {
+        Ok(entries) => HttpResponse::Ok().json(json!({ "entries": entries })),
+        Err(e) => ApiErrorResponse::from(e)
+            .context("Unable to read audit log")
+            .error_response(),
+    }
+}
+
+pub fn register_admin_service() -> Scope {
+    scope("/admin")
+        .service(shutdown)
+        .service(flatten_all)
+        .service(resume_trading)
+        .service(list_scheduled_jobs)
+        .service(schedule_job)
+        .service(unschedule_job)
+        .service(run_scheduled_job)
+        .service(reload_config)
+        .service(create_snapshot)
+        .service(list_snapshots)
+        .service(restore_snapshot)
+        .service(create_backup)
+        .service(list_backups)
+        .service(restore_backup)
+        .service(get_audit_log)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    use crate::utils::env::vars_lookup;
+
+    /// Tests admin requests need the bearer token set in `ADMIN_API_TOKEN`.
+    #[test]
+    fn test_authorize() {
+        let admin_token = AdminToken::from_lookup(vars_lookup(&[("ADMIN_API_TOKEN", "s3cret")]));
+
+        let req = TestRequest::default()
+            .insert_header((header::AUTHORIZATION, "Bearer s3cret"))
+            .to_http_request();
+        assert!(authorize(&req, &admin_token).is_ok());
+        assert!(authorize(&req, &AdminToken::default()).is_err());
+
+        let req = TestRequest::default()
+            .insert_header((header::AUTHORIZATION, "Bearer s3crex"))
+            .to_http_request();
+        assert!(authorize(&req, &admin_token).is_err());
+        assert!(authorize(&TestRequest::default().to_http_request(), &admin_token).is_err());
+    }
+}
@@ -0,0 +1,174 @@
+use actix_web::web::Json;
+use actix_web::{
+    get, post,
+    web::{self, scope},
+    HttpResponse, Responder, ResponseError, Scope,
+};
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::api::error::ApiErrorResponse;
+use crate::app::AppState;
+use crate::market::interval::Interval;
+use crate::market::regime::MarketRegime;
+use crate::strategy::backer::{BackTestMode, BackTestOptions};
+use crate::strategy::execution::ExecutionStyle;
+use crate::strategy::jobs::{JobId, JobStatus};
+use crate::strategy::strategy::{StrategyDirection, StrategySettings};
+use crate::utils::time::string_to_timestamp;
+
+#[derive(Debug, Deserialize)]
+pub struct SubmitBackTestParams {
+    symbol: String,
+    strategy_name: String,
+    algorithm_params: Value,
+    interval: Interval,
+    margin: Option<f64>,
+    leverage: Option<u32>,
+    min_volume_24h: Option<f64>,
+    min_atr: Option<f64>,
+    max_atr: Option<f64>,
+    min_candle_body: Option<f64>,
+    min_confidence: Option<f64>,
+    scale_by_confidence: Option<bool>,
+    direction: Option<StrategyDirection>,
+    max_position_duration: Option<u64>,
+    allowed_regimes: Option<Vec<MarketRegime>>,
+    #[serde(default)]
+    mode: BackTestMode,
+    #[serde(flatten)]
+    options: BackTestOptions,
+    from_ts: String,
+    to_ts: String,
+}
+impl SubmitBackTestParams {
+    fn settings(&self) -> StrategySettings {
+        StrategySettings {
+            max_open_orders: 2,
+            margin_usd: self.margin.unwrap_or(1000.0),
+            leverage: self.leverage.unwrap_or(10),
+            stop_loss: None,
+            take_profit: None,
+            min_volume_24h: self.min_volume_24h,
+            min_atr: self.min_atr,
+            max_atr: self.max_atr,
+            min_candle_body: self.min_candle_body,
+            min_confidence: self.min_confidence,
+            scale_by_confidence: self.scale_by_confidence.unwrap_or(false),
+            direction: self.direction.unwrap_or_default(),
+            max_position_duration: self.max_position_duration,
+            shadow: false,
+            allowed_regimes: self.allowed_regimes.clone(),
+            profile: None,
+            execution: ExecutionStyle::Market,
+            max_correlated_margin: None,
+        }
+    }
+}
+#[post("/submit")]
+async fn submit_back_test(
+    app_data: web::Data<AppState>,
+    body: Json<SubmitBackTestParams>,
+) -> impl Responder {
+    let settings = body.settings();
+
+    let (from_ts, to_ts) = match (
+        string_to_timestamp(&body.from_ts),
+        string_to_timestamp(&body.to_ts),
+    ) {
+        (Ok(from_ts), Ok(to_ts)) => (from_ts, to_ts),
+        _ => return ApiErrorResponse::bad_request("Unable to parse dates").error_response(),
+    };
+
+    let result = app_data
+        .bot
+        .lock()
+        .await
+        .start_back_test_job(
+            &body.strategy_name,
+            &body.symbol,
+            body.interval,
+            from_ts,
+            to_ts,
+            settings,
+            body.algorithm_params.clone(),
+            body.mode,
+            body.options.clone(),
+        )
+        .await;
+
+    match result {
+        Ok(job_id) => {
+            let json_data = json!({ "job_id": job_id });
+
+            HttpResponse::Ok().json(json_data)
+        }
+        Err(e) => ApiErrorResponse::bad_request(e.to_string()).error_response(),
+    }
+}
+
+#[get("/jobs")]
+async fn list_back_test_jobs(app_data: web::Data<AppState>) -> impl Responder {
+    let jobs = app_data.bot.lock().await.list_back_test_jobs().await;
+
+    let json_data = json!({ "jobs": jobs });
+
+    HttpResponse::Ok().json(json_data)
+}
+
+#[get("/{job_id}/status")]
+async fn back_test_status(app_data: web::Data<AppState>, path: web::Path<JobId>) -> impl Responder {
+    let job_id = path.into_inner();
+    let job = app_data.bot.lock().await.get_back_test_job(job_id).await;
+
+    match job {
+        Some((job, _)) => HttpResponse::Ok().json(json!({ "job": job })),
+        None => ApiErrorResponse::not_found("Unable to find job")
+            .with_detail("job_id", job_id)
+            .error_response(),
+    }
+}
+
+#[post("/{job_id}/cancel")]
+async fn cancel_back_test(app_data: web::Data<AppState>, path: web::Path<JobId>) -> impl Responder {
+    let job_id = path.into_inner();
+    let mut bot = app_data.bot.lock().await;
+    if bot.cancel_back_test_job(job_id).await {
+        let json_data = json!({ "success": "Job cancelled", "job_id": job_id });
+
+        return HttpResponse::Ok().json(json_data);
+    };
+
+    ApiErrorResponse::not_found("Unable to find running job")
+        .with_detail("job_id", job_id)
+        .error_response()
+}
+
+#[get("/{job_id}/result")]
+async fn back_test_result(app_data: web::Data<AppState>, path: web::Path<JobId>) -> impl Responder {
+    let job_id = path.into_inner();
+    let job = app_data.bot.lock().await.get_back_test_job(job_id).await;
+
+    match job {
+        Some((job, Some(result))) if job.status == JobStatus::Completed => {
+            HttpResponse::Ok().json(json!({ "job": job, "result": result }))
+        }
+        // running or cancelled jobs have no result, the status tells which
+        Some((job, _)) => ApiErrorResponse::conflict("Job has no result")
+            .with_detail("job", job)
+            .error_response(),
+        None => ApiErrorResponse::not_found("Unable to find job")
+            .with_detail("job_id", job_id)
+            .error_response(),
+    }
+}
+
+pub fn register_backtest_service() -> Scope {
+    scope("/backtest")
+        .service(submit_back_test)
+        .service(list_back_test_jobs)
+        .service(back_test_status)
+        .service(cancel_back_test)
+        .service(back_test_result)
+}
@@ -0,0 +1,249 @@
+use std::fmt;
+
+use actix_web::{
+    error::{JsonPayloadError, PathError, QueryPayloadError},
+    http::StatusCode,
+    HttpRequest, HttpResponse, ResponseError,
+};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::error::{RaderBotError, StorageError, StrategyError};
+use crate::exchange::types::ApiError;
+
+/// Machine readable kind of an API error, each answered with its own status code.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiErrorCode {
+    /// The request is malformed or has invalid values, 400.
+    InvalidRequest,
+    /// The request lacks valid credentials, 401.
+    Unauthorized,
+    /// The credentials don't grant access to the route, e.g. operator routes called by a
+    /// tenant, 403.
+    Forbidden,
+    /// The strategy, job or record asked for doesn't exist, 404.
+    NotFound,
+    /// The request conflicts with the current state, e.g. a job that already finished, 409.
+    Conflict,
+    /// The exchange failed or rejected the request, 502.
+    ExchangeError,
+    /// The bot failed to handle a valid request, e.g. storage failures, 500.
+    InternalError,
+}
+
+impl ApiErrorCode {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            ApiErrorCode::InvalidRequest => StatusCode::BAD_REQUEST,
+            ApiErrorCode::Unauthorized => StatusCode::UNAUTHORIZED,
+            ApiErrorCode::Forbidden => StatusCode::FORBIDDEN,
+            ApiErrorCode::NotFound => StatusCode::NOT_FOUND,
+            ApiErrorCode::Conflict => StatusCode::CONFLICT,
+            ApiErrorCode::ExchangeError => StatusCode::BAD_GATEWAY,
+            ApiErrorCode::InternalError => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// The body of every failed API request, e.g.
+/// `{"code": "not_found", "error": "Unable to find strategy", "strategy_id": "..."}`.
+///
+/// Details such as the id that wasn't found are added next to the message.
+#[derive(Serialize, Debug, Clone)]
+pub struct ApiErrorResponse {
+    pub code: ApiErrorCode,
+    pub error: String,
+    #[serde(flatten)]
+    pub details: Map<String, Value>,
+}
+
+impl ApiErrorResponse {
+    pub fn new(code: ApiErrorCode, error: impl Into<String>) -> Self {
+        Self {
+            code,
+            error: error.into(),
+            details: Map::new(),
+        }
+    }
+
+    pub fn bad_request(error: impl Into<String>) -> Self {
+        Self::new(ApiErrorCode::InvalidRequest, error)
+    }
+
+    pub fn unauthorized(error: impl Into<String>) -> Self {
+        Self::new(ApiErrorCode::Unauthorized, error)
+    }
+
+    pub fn forbidden(error: impl Into<String>) -> Self {
+        Self::new(ApiErrorCode::Forbidden, error)
+    }
+
+    pub fn not_found(error: impl Into<String>) -> Self {
+        Self::new(ApiErrorCode::NotFound, error)
+    }
+
+    pub fn conflict(error: impl Into<String>) -> Self {
+        Self::new(ApiErrorCode::Conflict, error)
+    }
+
+    pub fn exchange(error: impl Into<String>) -> Self {
+        Self::new(ApiErrorCode::ExchangeError, error)
+    }
+
+    pub fn internal(error: impl Into<String>) -> Self {
+        Self::new(ApiErrorCode::InternalError, error)
+    }
+
+    /// Prefixes the message with what failed, e.g. `Unable to create snapshot, e: ...`.
+    pub fn context(mut self, context: &str) -> Self {
+        self.error = format!("{context}, e: {}", self.error);
+        self
+    }
+
+    /// Adds a detail to the error, such as the id of a missing strategy.
+    pub fn with_detail(mut self, key: &str, value: impl Serialize) -> Self {
+        self.details.insert(
+            key.to_string(),
+            serde_json::to_value(value).unwrap_or_default(),
+        );
+        self
+    }
+}
+
+impl fmt::Display for ApiErrorResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.error)
+    }
+}
+
+impl ResponseError for ApiErrorResponse {
+    fn status_code(&self) -> StatusCode {
+        self.code.status_code()
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code()).json(self)
+    }
+}
+
+/// Answers the errors of the bot with the code of their kind, so every route reports e.g.
+/// records that aren't stored as `not_found` and failures of the exchange as `exchange_error`.
+impl From<RaderBotError> for ApiErrorResponse {
+    fn from(e: RaderBotError) -> Self {
+        let code = match &e {
+            RaderBotError::Exchange(_) => ApiErrorCode::ExchangeError,
+            RaderBotError::Storage(e) => match e {
+                StorageError::NotFound(_) => ApiErrorCode::NotFound,
+                StorageError::Invalid(_) => ApiErrorCode::InvalidRequest,
+                StorageError::Unsupported(_) => ApiErrorCode::Conflict,
+                _ => ApiErrorCode::InternalError,
+            },
+            RaderBotError::Strategy(e) => match e {
+                StrategyError::NotFound(_) => ApiErrorCode::NotFound,
+                StrategyError::Unavailable(_) => ApiErrorCode::Conflict,
+                _ => ApiErrorCode::InvalidRequest,
+            },
+            RaderBotError::Config(_) | RaderBotError::Io(_) => ApiErrorCode::InternalError,
+        };
+        Self::new(code, e.to_string())
+    }
+}
+
+impl From<ApiError> for ApiErrorResponse {
+    fn from(e: ApiError) -> Self {
+        RaderBotError::from(e).into()
+    }
+}
+
+impl From<StorageError> for ApiErrorResponse {
+    fn from(e: StorageError) -> Self {
+        RaderBotError::from(e).into()
+    }
+}
+
+impl From<StrategyError> for ApiErrorResponse {
+    fn from(e: StrategyError) -> Self {
+        RaderBotError::from(e).into()
+    }
+}
+
+/// Answers JSON bodies that can't be parsed with an `ApiErrorResponse`.
+pub fn json_error_handler(e: JsonPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    ApiErrorResponse::bad_request(format!("Invalid JSON body, e: {e}")).into()
+}
+
+/// Answers query strings that can't be parsed with an `ApiErrorResponse`.
+pub fn query_error_handler(e: QueryPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    ApiErrorResponse::bad_request(format!("Invalid query, e: {e}")).into()
+}
+
+/// Answers path parameters that can't be parsed, e.g. malformed ids, with an
+/// `ApiErrorResponse`.
+pub fn path_error_handler(e: PathError, _req: &HttpRequest) -> actix_web::Error {
+    ApiErrorResponse::bad_request(format!("Invalid path, e: {e}")).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::body::to_bytes;
+
+    /// Tests errors are answered with the status of their code and their details.
+    #[actix_web::test]
+    async fn test_api_error_response() {
+        let error = ApiErrorResponse::not_found("Unable to find strategy")
+            .with_detail("strategy_id", "abc");
+        let response = error.error_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let body = to_bytes(response.into_body()).await.unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["code"], "not_found");
+        assert_eq!(json["error"], "Unable to find strategy");
+        assert_eq!(json["strategy_id"], "abc");
+
+        assert_eq!(
+            ApiErrorResponse::exchange("timeout").status_code(),
+            StatusCode::BAD_GATEWAY
+        );
+        assert_eq!(
+            ApiErrorResponse::bad_request("bad").status_code(),
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    /// Tests errors of every layer are answered with the code of their kind.
+    #[test]
+    fn test_bot_error_codes() {
+        let code = |e: ApiErrorResponse| e.code;
+
+        assert_eq!(
+            code(ApiError::Network("timeout".to_string()).into()),
+            ApiErrorCode::ExchangeError
+        );
+        let not_found = ApiErrorResponse::from(StorageError::NotFound("Back test 1".to_string()));
+        assert_eq!(not_found.code, ApiErrorCode::NotFound);
+        assert_eq!(not_found.error, "Back test 1 not found");
+        assert_eq!(
+            code(StorageError::Unsupported("InfluxStorage").into()),
+            ApiErrorCode::Conflict
+        );
+        assert_eq!(
+            code(StorageError::Backend("connection reset".to_string()).into()),
+            ApiErrorCode::InternalError
+        );
+        assert_eq!(
+            code(StrategyError::InvalidSettings("unknown profile".to_string()).into()),
+            ApiErrorCode::InvalidRequest
+        );
+        assert_eq!(
+            code(RaderBotError::Config("invalid line 3".to_string()).into()),
+            ApiErrorCode::InternalError
+        );
+
+        let error = ApiErrorResponse::from(StorageError::Io(std::io::Error::other("disk full")))
+            .context("Unable to create snapshot");
+        assert_eq!(error.error, "Unable to create snapshot, e: disk full");
+    }
+}
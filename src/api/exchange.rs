@@ -2,34 +2,91 @@ use actix_web::HttpRequest;
 use actix_web::{
     get,
     web::{self, scope},
-    HttpResponse, Responder, Scope,
+    HttpResponse, Responder, ResponseError, Scope,
 };
+use serde::Deserialize;
+use serde_json::json;
 
+use crate::api::error::ApiErrorResponse;
 use crate::app::AppState;
 
 #[get("/account")]
 async fn account(app_data: web::Data<AppState>, _req: HttpRequest) -> impl Responder {
     let exchange = app_data.get_exchange_api().await;
 
-    let data = exchange
-        .get_account()
-        .await
-        .expect("Unable to get account info");
-
-    // Return the stream data as JSON
-    HttpResponse::Ok().json(data)
+    match exchange.get_account().await {
+        Ok(data) => HttpResponse::Ok().json(data),
+        Err(e) => ApiErrorResponse::from(e)
+            .context("Unable to get account info")
+            .error_response(),
+    }
 }
 
 #[get("/info")]
 async fn info(app_data: web::Data<AppState>, _req: HttpRequest) -> impl Responder {
     let exchange = app_data.get_exchange_api().await;
+    let cache = app_data.get_exchange_cache().await;
+
+    let result = cache.lock().await.info(&**exchange).await;
+    match result {
+        Ok(exchange_info) => HttpResponse::Ok().json(exchange_info),
+        Err(e) => ApiErrorResponse::from(e).error_response(),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SymbolsParams {
+    /// Only lists symbols quoted in this asset, e.g. `USDT`.
+    quote_asset: Option<String>,
+    /// Also lists symbols closed for trading.
+    #[serde(default)]
+    include_closed: bool,
+}
+#[get("/symbols")]
+async fn symbols(
+    app_data: web::Data<AppState>,
+    params: web::Query<SymbolsParams>,
+) -> impl Responder {
+    let exchange = app_data.get_exchange_api().await;
+    let cache = app_data.get_exchange_cache().await;
+
+    let result = cache.lock().await.info(&**exchange).await;
+    match result {
+        Ok(exchange_info) => {
+            let symbols: Vec<_> = exchange_info
+                .symbols
+                .into_iter()
+                .filter(|symbol| params.include_closed || symbol.trading)
+                .filter(|symbol| {
+                    params
+                        .quote_asset
+                        .as_ref()
+                        .is_none_or(|quote_asset| symbol.quote_asset == *quote_asset)
+                })
+                .collect();
 
-    let data = exchange.info().await.expect("Unable to get exchange info");
+            HttpResponse::Ok().json(json!({ "exchange": exchange_info.name, "symbols": symbols }))
+        }
+        Err(e) => ApiErrorResponse::from(e).error_response(),
+    }
+}
+
+#[get("/balance")]
+async fn balance(app_data: web::Data<AppState>) -> impl Responder {
+    let exchange = app_data.get_exchange_api().await;
+    let cache = app_data.get_exchange_cache().await;
 
-    // Return the stream data as JSON
-    HttpResponse::Ok().json(data)
+    let result = cache.lock().await.balance(&**exchange).await;
+    match result {
+        Ok(balance) => HttpResponse::Ok().json(json!({ "balance": balance })),
+        Err(e) => ApiErrorResponse::from(e).error_response(),
+    }
 }
 
 pub fn register_exchange_service() -> Scope {
-    scope("/exchange").service(info).service(account)
+    scope("/exchange")
+        .service(info)
+        .service(symbols)
+        .service(balance)
+        .service(account)
 }
@@ -0,0 +1,707 @@
+use actix_web::{
+    get, post,
+    web::{self, scope, Json},
+    HttpResponse, Responder, Scope,
+};
+use async_graphql::{
+    http::GraphiQLSource, Context, EmptyMutation, EmptySubscription, Enum, InputObject, Object,
+    Schema,
+};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::account::money::Price;
+use crate::account::trade::{OrderSide, Position, PositionId, TradeTx};
+use crate::app::AppState;
+use crate::market::interval::Interval;
+use crate::market::kline::Kline;
+use crate::market::symbol::Symbol;
+use crate::strategy::backer::BackTestRecord;
+use crate::strategy::metrics::PerformanceMetrics;
+use crate::strategy::strategy::{StrategyId, StrategyInfo, StrategySettings, StrategySummary};
+use crate::utils::time::string_to_timestamp;
+
+/// The schema of the GraphQL API, read only.
+pub type RaderBotSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+/// Builds the GraphQL schema, the `AppState` is passed along with each request.
+pub fn build_schema() -> RaderBotSchema {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription).finish()
+}
+
+/// Side of a position.
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+impl From<OrderSide> for Side {
+    fn from(order_side: OrderSide) -> Self {
+        match order_side {
+            OrderSide::Buy => Side::Buy,
+            OrderSide::Sell => Side::Sell,
+        }
+    }
+}
+
+/// Filters active strategies, every field is optional.
+#[derive(InputObject, Debug, Clone, Default)]
+pub struct StrategyFilter {
+    pub symbol: Option<String>,
+    /// The name of the algorithm.
+    pub name: Option<String>,
+    pub running: Option<bool>,
+}
+
+impl StrategyFilter {
+    fn matches(&self, info: &StrategyInfo) -> bool {
+        self.symbol
+            .as_ref()
+            .is_none_or(|symbol| Symbol::normalize(symbol) == info.symbol)
+            && self.name.as_ref().is_none_or(|name| *name == info.name)
+            && self.running.is_none_or(|running| running == info.running)
+    }
+}
+
+/// Filters positions and trades, every field is optional.
+#[derive(InputObject, Debug, Clone, Default)]
+pub struct PositionFilter {
+    pub symbol: Option<String>,
+    pub strategy_id: Option<StrategyId>,
+    pub side: Option<Side>,
+}
+
+impl PositionFilter {
+    fn matches(&self, position: &Position) -> bool {
+        self.symbol
+            .as_ref()
+            .is_none_or(|symbol| Symbol::normalize(symbol) == position.symbol)
+            && self
+                .strategy_id
+                .is_none_or(|strategy_id| position.strategy_id == Some(strategy_id))
+            && self
+                .side
+                .is_none_or(|side| side == Side::from(position.order_side))
+    }
+}
+
+/// Filters saved backtest runs, every field is optional.
+#[derive(InputObject, Debug, Clone, Default)]
+pub struct BackTestFilter {
+    pub symbol: Option<String>,
+    /// The name of the algorithm.
+    pub name: Option<String>,
+    pub min_profit: Option<f64>,
+}
+
+impl BackTestFilter {
+    fn matches(&self, record: &BackTestRecord) -> bool {
+        let info = &record.summary.info;
+        self.symbol
+            .as_ref()
+            .is_none_or(|symbol| Symbol::normalize(symbol) == info.symbol)
+            && self.name.as_ref().is_none_or(|name| *name == info.name)
+            && self
+                .min_profit
+                .is_none_or(|min_profit| record.summary.profit >= min_profit)
+    }
+}
+
+/// Keeps the last `limit` items, all if no limit is given.
+fn take_last<T>(mut items: Vec<T>, limit: Option<usize>) -> Vec<T> {
+    if let Some(limit) = limit {
+        items.drain(..items.len().saturating_sub(limit));
+    }
+    items
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// Active strategies.
+    async fn strategies(
+        &self,
+        ctx: &Context<'_>,
+        filter: Option<StrategyFilter>,
+    ) -> async_graphql::Result<Vec<StrategyObject>> {
+        let app_data = ctx.data::<web::Data<AppState>>()?;
+        let filter = filter.unwrap_or_default();
+        let mut bot = app_data.bot.lock().await;
+
+        let mut strategies = vec![];
+        for id in bot.get_active_strategy_ids().await {
+            if let Some(info) = bot.get_strategy_info(id).await {
+                if filter.matches(&info) {
+                    strategies.push(StrategyObject(info));
+                }
+            }
+        }
+
+        Ok(strategies)
+    }
+
+    /// An active strategy by id.
+    async fn strategy(
+        &self,
+        ctx: &Context<'_>,
+        id: StrategyId,
+    ) -> async_graphql::Result<Option<StrategyObject>> {
+        let app_data = ctx.data::<web::Data<AppState>>()?;
+        let info = app_data.bot.lock().await.get_strategy_info(id).await;
+
+        Ok(info.map(StrategyObject))
+    }
+
+    /// Open positions of the account.
+    async fn positions(
+        &self,
+        ctx: &Context<'_>,
+        filter: Option<PositionFilter>,
+    ) -> async_graphql::Result<Vec<PositionObject>> {
+        let app_data = ctx.data::<web::Data<AppState>>()?;
+        let filter = filter.unwrap_or_default();
+        let account = app_data.get_account().await;
+        let account = account.lock().await;
+
+        Ok(account
+            .positions()
+            .filter(|position| filter.matches(position))
+            .map(|position| PositionObject(position.clone()))
+            .collect())
+    }
+
+    /// Closed trades of the account, the last `limit` if given.
+    async fn trades(
+        &self,
+        ctx: &Context<'_>,
+        filter: Option<PositionFilter>,
+        limit: Option<usize>,
+    ) -> async_graphql::Result<Vec<TradeObject>> {
+        let app_data = ctx.data::<web::Data<AppState>>()?;
+        let filter = filter.unwrap_or_default();
+        let account = app_data.get_account().await;
+        let trades: Vec<TradeObject> = account
+            .lock()
+            .await
+            .trades()
+            .into_iter()
+            .filter(|trade| filter.matches(&trade.position))
+            .map(TradeObject)
+            .collect();
+
+        Ok(take_last(trades, limit))
+    }
+
+    /// K-lines of a symbol held by the market, the last `limit` if given. Dates are RFC 3339 or
+    /// timestamps like in the REST API.
+    async fn klines(
+        &self,
+        ctx: &Context<'_>,
+        symbol: String,
+        interval: String,
+        from: Option<String>,
+        to: Option<String>,
+        limit: Option<usize>,
+    ) -> async_graphql::Result<Vec<KlineObject>> {
+        let app_data = ctx.data::<web::Data<AppState>>()?;
+        let parse_ts = |ts: Option<String>| {
+            ts.as_deref()
+                .map(string_to_timestamp)
+                .transpose()
+                .map_err(|_| async_graphql::Error::new("Unable to parse dates"))
+        };
+        let (from_ts, to_ts) = (parse_ts(from)?, parse_ts(to)?);
+        let interval: Interval = interval.parse().map_err(async_graphql::Error::new)?;
+
+        let market = app_data.get_market().await;
+        let kline_data = market
+            .kline_data_range(&Symbol::normalize(&symbol), interval, from_ts, to_ts, limit)
+            .await;
+
+        Ok(kline_data
+            .map(|kline_data| kline_data.klines().into_iter().map(KlineObject).collect())
+            .unwrap_or_default())
+    }
+
+    /// Saved backtest runs, most recent first, the first `limit` if given.
+    async fn back_tests(
+        &self,
+        ctx: &Context<'_>,
+        filter: Option<BackTestFilter>,
+        limit: Option<usize>,
+    ) -> async_graphql::Result<Vec<BackTestObject>> {
+        let app_data = ctx.data::<web::Data<AppState>>()?;
+        let filter = filter.unwrap_or_default();
+        let records = app_data
+            .bot
+            .lock()
+            .await
+            .list_saved_back_tests()
+            .await
+            .ok_or("Unable to list back tests")?;
+
+        let mut records: Vec<BackTestRecord> = records
+            .into_iter()
+            .filter(|record| filter.matches(record))
+            .collect();
+        records.sort_by_key(|record| std::cmp::Reverse(record.created_at));
+        records.truncate(limit.unwrap_or(records.len()));
+
+        Ok(records.into_iter().map(BackTestObject).collect())
+    }
+
+    /// A saved backtest run by id.
+    async fn back_test(
+        &self,
+        ctx: &Context<'_>,
+        id: StrategyId,
+    ) -> async_graphql::Result<Option<BackTestObject>> {
+        let app_data = ctx.data::<web::Data<AppState>>()?;
+        let record = app_data.bot.lock().await.get_saved_back_test(id).await;
+
+        Ok(record.map(BackTestObject))
+    }
+}
+
+/// An active strategy, its positions, trades and k-lines are fetched when asked for.
+pub struct StrategyObject(StrategyInfo);
+
+#[Object(name = "Strategy")]
+impl StrategyObject {
+    async fn id(&self) -> StrategyId {
+        self.0.id
+    }
+
+    async fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    async fn symbol(&self) -> &str {
+        &self.0.symbol
+    }
+
+    async fn interval(&self) -> &str {
+        self.0.interval.as_str()
+    }
+
+    async fn running(&self) -> bool {
+        self.0.running
+    }
+
+    async fn start_time(&self) -> Option<&str> {
+        self.0.start_time.as_deref()
+    }
+
+    async fn end_time(&self) -> Option<&str> {
+        self.0.end_time.as_deref()
+    }
+
+    /// The algorithm params as JSON.
+    async fn params(&self) -> async_graphql::Json<&Value> {
+        async_graphql::Json(&self.0.params)
+    }
+
+    /// The risk and sizing settings as JSON.
+    async fn settings(&self) -> async_graphql::Json<&StrategySettings> {
+        async_graphql::Json(&self.0.settings)
+    }
+
+    /// Open positions of the strategy.
+    async fn positions(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<PositionObject>> {
+        let app_data = ctx.data::<web::Data<AppState>>()?;
+        let account = app_data
+            .bot
+            .lock()
+            .await
+            .get_strategy_account(self.0.id)
+            .await;
+        let account = account.lock().await;
+
+        Ok(account
+            .strategy_positions(self.0.id)
+            .into_iter()
+            .map(|position| PositionObject(position.clone()))
+            .collect())
+    }
+
+    /// Closed trades of the strategy, the last `limit` if given.
+    async fn trades(
+        &self,
+        ctx: &Context<'_>,
+        limit: Option<usize>,
+    ) -> async_graphql::Result<Vec<TradeObject>> {
+        let app_data = ctx.data::<web::Data<AppState>>()?;
+        let account = app_data
+            .bot
+            .lock()
+            .await
+            .get_strategy_account(self.0.id)
+            .await;
+        let trades: Vec<TradeObject> = account
+            .lock()
+            .await
+            .strategy_trades(self.0.id)
+            .into_iter()
+            .map(|trade| TradeObject(trade.clone()))
+            .collect();
+
+        Ok(take_last(trades, limit))
+    }
+
+    /// The results of the strategy so far.
+    async fn summary(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<SummaryObject>> {
+        let app_data = ctx.data::<web::Data<AppState>>()?;
+        let summary = app_data
+            .bot
+            .lock()
+            .await
+            .get_strategy_summary(self.0.id)
+            .await;
+
+        Ok(summary.map(SummaryObject))
+    }
+
+    /// The last `limit` k-lines of the symbol and interval of the strategy.
+    async fn klines(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default = 100)] limit: usize,
+    ) -> async_graphql::Result<Vec<KlineObject>> {
+        let app_data = ctx.data::<web::Data<AppState>>()?;
+        let market = app_data.get_market().await;
+        let kline_data = market
+            .kline_data_range(&self.0.symbol, self.0.interval, None, None, None)
+            .await;
+
+        let klines = kline_data
+            .map(|kline_data| kline_data.klines().into_iter().map(KlineObject).collect())
+            .unwrap_or_default();
+        Ok(take_last(klines, Some(limit)))
+    }
+}
+
+pub struct PositionObject(Position);
+
+#[Object(name = "Position")]
+impl PositionObject {
+    async fn id(&self) -> PositionId {
+        self.0.id
+    }
+
+    async fn symbol(&self) -> &str {
+        &self.0.symbol
+    }
+
+    async fn side(&self) -> Side {
+        self.0.order_side.into()
+    }
+
+    async fn open_time(&self) -> &str {
+        &self.0.open_time
+    }
+
+    async fn open_price(&self) -> f64 {
+        self.0.open_price.to_f64()
+    }
+
+    async fn quantity(&self) -> f64 {
+        self.0.quantity.to_f64()
+    }
+
+    async fn margin_usd(&self) -> f64 {
+        self.0.margin_usd
+    }
+
+    async fn leverage(&self) -> u32 {
+        self.0.leverage
+    }
+
+    async fn stop_loss(&self) -> Option<f64> {
+        self.0.stop_loss.map(Price::to_f64)
+    }
+
+    async fn take_profit(&self) -> Option<f64> {
+        self.0.take_profit.map(Price::to_f64)
+    }
+
+    async fn strategy_id(&self) -> Option<StrategyId> {
+        self.0.strategy_id
+    }
+
+    /// The strategy which opened the position, if it is still active.
+    async fn strategy(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<StrategyObject>> {
+        let Some(strategy_id) = self.0.strategy_id else {
+            return Ok(None);
+        };
+        let app_data = ctx.data::<web::Data<AppState>>()?;
+        let info = app_data
+            .bot
+            .lock()
+            .await
+            .get_strategy_info(strategy_id)
+            .await;
+
+        Ok(info.map(StrategyObject))
+    }
+}
+
+pub struct TradeObject(TradeTx);
+
+#[Object(name = "Trade")]
+impl TradeObject {
+    async fn id(&self) -> Uuid {
+        self.0.id
+    }
+
+    async fn close_time(&self) -> &str {
+        &self.0.close_time
+    }
+
+    async fn close_price(&self) -> f64 {
+        self.0.close_price.to_f64()
+    }
+
+    /// The profit of the trade, net of funding.
+    async fn profit(&self) -> f64 {
+        self.0.calc_profit()
+    }
+
+    /// The position the trade closed.
+    async fn position(&self) -> PositionObject {
+        PositionObject(self.0.position.clone())
+    }
+}
+
+pub struct KlineObject(Kline);
+
+#[Object(name = "Kline")]
+impl KlineObject {
+    async fn symbol(&self) -> &str {
+        &self.0.symbol
+    }
+
+    async fn interval(&self) -> &str {
+        self.0.interval.as_str()
+    }
+
+    async fn open(&self) -> f64 {
+        self.0.open
+    }
+
+    async fn high(&self) -> f64 {
+        self.0.high
+    }
+
+    async fn low(&self) -> f64 {
+        self.0.low
+    }
+
+    async fn close(&self) -> f64 {
+        self.0.close
+    }
+
+    async fn volume(&self) -> f64 {
+        self.0.volume
+    }
+
+    async fn open_time(&self) -> u64 {
+        self.0.open_time
+    }
+
+    async fn close_time(&self) -> u64 {
+        self.0.close_time
+    }
+}
+
+/// The results of a strategy, live or backtested.
+pub struct SummaryObject(StrategySummary);
+
+#[Object(name = "StrategySummary")]
+impl SummaryObject {
+    async fn strategy_id(&self) -> StrategyId {
+        self.0.info.id
+    }
+
+    /// The name of the algorithm.
+    async fn name(&self) -> &str {
+        &self.0.info.name
+    }
+
+    async fn symbol(&self) -> &str {
+        &self.0.info.symbol
+    }
+
+    async fn interval(&self) -> &str {
+        self.0.info.interval.as_str()
+    }
+
+    async fn profit(&self) -> f64 {
+        self.0.profit
+    }
+
+    async fn max_drawdown(&self) -> f64 {
+        self.0.max_drawdown
+    }
+
+    async fn max_profit(&self) -> f64 {
+        self.0.max_profit
+    }
+
+    async fn funding_paid(&self) -> f64 {
+        self.0.funding_paid
+    }
+
+    async fn long_trade_count(&self) -> usize {
+        self.0.long_trade_count
+    }
+
+    async fn short_trade_count(&self) -> usize {
+        self.0.short_trade_count
+    }
+
+    /// The performance metrics as JSON.
+    async fn metrics(&self) -> async_graphql::Json<&PerformanceMetrics> {
+        async_graphql::Json(&self.0.metrics)
+    }
+
+    async fn trades(&self, filter: Option<PositionFilter>) -> Vec<TradeObject> {
+        let filter = filter.unwrap_or_default();
+        self.0
+            .trades
+            .iter()
+            .filter(|trade| filter.matches(&trade.position))
+            .map(|trade| TradeObject(trade.clone()))
+            .collect()
+    }
+
+    async fn positions(&self) -> Vec<PositionObject> {
+        self.0
+            .positions
+            .iter()
+            .map(|position| PositionObject(position.clone()))
+            .collect()
+    }
+}
+
+pub struct BackTestObject(BackTestRecord);
+
+#[Object(name = "BackTest")]
+impl BackTestObject {
+    async fn id(&self) -> StrategyId {
+        self.0.id()
+    }
+
+    async fn from_ts(&self) -> u64 {
+        self.0.from_ts
+    }
+
+    async fn to_ts(&self) -> u64 {
+        self.0.to_ts
+    }
+
+    async fn created_at(&self) -> u64 {
+        self.0.created_at
+    }
+
+    /// The mode and options the run was backtested with as JSON.
+    async fn options(&self) -> async_graphql::Json<Value> {
+        async_graphql::Json(serde_json::json!({ "mode": self.0.mode, "options": self.0.options }))
+    }
+
+    async fn summary(&self) -> SummaryObject {
+        SummaryObject(self.0.summary.clone())
+    }
+}
+
+#[post("")]
+async fn graphql(
+    app_data: web::Data<AppState>,
+    schema: web::Data<RaderBotSchema>,
+    body: Json<async_graphql::Request>,
+) -> impl Responder {
+    let request = body.into_inner().data(app_data.clone());
+    let response = schema.execute(request).await;
+
+    HttpResponse::Ok().json(response)
+}
+
+#[get("")]
+async fn graphiql() -> impl Responder {
+    let source = GraphiQLSource::build().endpoint("/graphql").finish();
+
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(source)
+}
+
+pub fn register_graphql_service() -> Scope {
+    scope("/graphql").service(graphql).service(graphiql)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(symbol: &str, order_side: OrderSide, strategy_id: Option<StrategyId>) -> Position {
+        let mut position = Position::new(
+            symbol,
+            100.0.into(),
+            order_side,
+            10.0,
+            10,
+            None,
+            Default::default(),
+        );
+        position.strategy_id = strategy_id;
+        position
+    }
+
+    /// Tests filters match positions and backtests and the schema exposes nested types.
+    #[test]
+    fn test_graphql_filters() {
+        let strategy_id = Uuid::new_v4();
+        let filter = PositionFilter {
+            symbol: Some("BTCUSDT".to_string()),
+            strategy_id: Some(strategy_id),
+            side: Some(Side::Buy),
+        };
+        assert!(filter.matches(&position("BTCUSDT", OrderSide::Buy, Some(strategy_id))));
+        assert!(!filter.matches(&position("BTCUSDT", OrderSide::Sell, Some(strategy_id))));
+        assert!(!filter.matches(&position("BTCUSDT", OrderSide::Buy, None)));
+        assert!(PositionFilter::default().matches(&position("ETHUSDT", OrderSide::Sell, None)));
+
+        let mut summary = StrategySummary {
+            profit: 5.0,
+            ..Default::default()
+        };
+        summary.info.name = "rsi".to_string();
+        let record = BackTestRecord {
+            from_ts: 0,
+            to_ts: 0,
+            mode: Default::default(),
+            options: Default::default(),
+            created_at: 0,
+            summary,
+        };
+        let filter = BackTestFilter {
+            name: Some("rsi".to_string()),
+            min_profit: Some(1.0),
+            ..Default::default()
+        };
+        assert!(filter.matches(&record));
+        assert!(!BackTestFilter {
+            min_profit: Some(10.0),
+            ..Default::default()
+        }
+        .matches(&record));
+
+        assert_eq!(take_last(vec![1, 2, 3], Some(2)), vec![2, 3]);
+        assert_eq!(take_last(vec![1, 2, 3], None), vec![1, 2, 3]);
+
+        let sdl = build_schema().sdl();
+        assert!(sdl.contains("type Strategy"));
+        assert!(sdl.contains("backTests(filter: BackTestFilter, limit: Int)"));
+    }
+}
@@ -1,11 +1,21 @@
 use actix_files::NamedFile;
-use actix_web::{get, web::scope, Responder, Scope};
+use actix_web::{get, web::scope, HttpResponse, Responder, Scope};
+
+use crate::health;
+use crate::utils::time::generate_ts;
 
 #[get("/")]
 async fn home() -> impl Responder {
     NamedFile::open_async("./static/index.html").await.unwrap()
 }
 
+/// Reports the runtime health of the bot: channel backlogs, task liveness and latency, and lock
+/// wait times.
+#[get("/metrics")]
+async fn metrics() -> impl Responder {
+    HttpResponse::Ok().json(health::runtime().report(generate_ts()))
+}
+
 pub fn register_main_service() -> Scope {
-    scope("/api").service(home)
+    scope("/api").service(home).service(metrics)
 }
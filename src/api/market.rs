@@ -3,22 +3,37 @@ use actix_web::web::Json;
 use actix_web::{
     get,
     web::{self, scope},
-    HttpResponse, Responder, Scope,
+    HttpResponse, Responder, ResponseError, Scope,
 };
 
+use std::collections::HashSet;
+use std::time::Duration;
+
+use actix_web::web::Bytes;
 use serde::Deserialize;
 use serde_json::json;
+use tokio::sync::broadcast::error::RecvError;
 
+use crate::exchange::stream::build_stream_id;
 use crate::exchange::types::StreamType;
 
+use crate::api::error::ApiErrorResponse;
+use crate::api::tenant::TenantScope;
 use crate::app::AppState;
+use crate::market::correlation::CORRELATION_THRESHOLD;
+use crate::market::interval::Interval;
+use crate::market::kline_export::{KlineEncoder, KlineExportFormat};
+use crate::market::messages::MarketUpdate;
+use crate::market::symbol::{canonical_symbol, Symbol};
 use crate::market::volume::MarketTradeVolume;
-use crate::utils::time::string_to_timestamp;
+use crate::storage::kline_query::{KlineQuery, SortOrder};
+use crate::utils::time::{generate_ts, string_to_timestamp};
 
 #[derive(Debug, Deserialize)]
 pub struct GetKlineDataParams {
+    #[serde(deserialize_with = "canonical_symbol")]
     symbol: String,
-    interval: String,
+    interval: Interval,
 }
 #[post("/kline-data")]
 async fn get_kline_data(
@@ -27,20 +42,16 @@ async fn get_kline_data(
 ) -> impl Responder {
     let market = app_data.get_market().await;
 
-    let kline_data = market
-        .lock()
-        .await
-        .last_kline(&body.symbol, &body.interval)
-        .await;
+    let kline_data = market.last_kline(&body.symbol, body.interval).await;
 
     if let Some(kline_data) = kline_data {
         // Return the stream data as JSON
         let json_data = json!({ "last_kline": kline_data });
         HttpResponse::Ok().json(json_data)
     } else {
-        let json_data = json!({ "error": "Kline data not found" });
-        // Stream ID not found
-        HttpResponse::Ok().json(json_data)
+        ApiErrorResponse::not_found("Kline data not found")
+            .with_detail("symbol", &body.symbol)
+            .error_response()
     }
 }
 
@@ -51,27 +62,28 @@ async fn get_ticker_data(
 ) -> impl Responder {
     let market = app_data.get_market().await;
 
-    let ticker_data = market.lock().await.last_ticker(&body.symbol).await;
+    let ticker_data = market.last_ticker(&body.symbol).await;
 
     if let Some(ticker_data) = ticker_data {
         // Return the stream data as JSON
         let json_data = json!({ "ticker_data": ticker_data });
         HttpResponse::Ok().json(json_data)
     } else {
-        let json_data = json!({ "error": "Ticker data not found" });
-        // Stream ID not found
-        HttpResponse::Ok().json(json_data)
+        ApiErrorResponse::not_found("Ticker data not found")
+            .with_detail("symbol", &body.symbol)
+            .error_response()
     }
 }
 
 #[derive(Deserialize)]
 struct GetMarketTradesParams {
+    #[serde(deserialize_with = "canonical_symbol")]
     symbol: String,
     from_ts: Option<String>,
     to_ts: Option<String>,
     limit: Option<usize>,
     price_granularity: Option<usize>,
-    time_interval: Option<String>,
+    time_interval: Option<Interval>,
 }
 #[post("/trade-data")]
 async fn get_trade_data(
@@ -86,8 +98,7 @@ async fn get_trade_data(
     if let Some(ts) = &body.to_ts {
         let _ts = string_to_timestamp(ts);
         if _ts.is_err() {
-            let json_data = json!({ "error": "Unable to parse dates".to_string()});
-            return HttpResponse::ExpectationFailed().json(json_data);
+            return ApiErrorResponse::bad_request("Unable to parse dates").error_response();
         }
         let _ts = _ts.unwrap();
         to_ts = Some(_ts);
@@ -96,16 +107,13 @@ async fn get_trade_data(
     if let Some(ts) = &body.from_ts {
         let _ts = string_to_timestamp(ts);
         if _ts.is_err() {
-            let json_data = json!({ "error": "Unable to parse dates".to_string()});
-            return HttpResponse::ExpectationFailed().json(json_data);
+            return ApiErrorResponse::bad_request("Unable to parse dates").error_response();
         }
         let _ts = _ts.unwrap();
         from_ts = Some(_ts);
     };
 
     let trade_data = market
-        .lock()
-        .await
         .trade_data_range(&body.symbol, from_ts, to_ts, body.limit)
         .await;
 
@@ -115,9 +123,9 @@ async fn get_trade_data(
         let json_data = json!({ "trade_data": {"meta": meta, "trades": trades } } );
         HttpResponse::Ok().json(json_data)
     } else {
-        let json_data = json!({ "error": "Trade Data data not found" });
-        // Stream ID not found
-        HttpResponse::Ok().json(json_data)
+        ApiErrorResponse::not_found("Trade data not found")
+            .with_detail("symbol", &body.symbol)
+            .error_response()
     }
 }
 
@@ -134,8 +142,7 @@ async fn get_volume_data(
     if let Some(ts) = &body.to_ts {
         let _ts = string_to_timestamp(ts);
         if _ts.is_err() {
-            let json_data = json!({ "error": "Unable to parse dates".to_string()});
-            return HttpResponse::ExpectationFailed().json(json_data);
+            return ApiErrorResponse::bad_request("Unable to parse dates").error_response();
         }
         let _ts = _ts.unwrap();
         to_ts = Some(_ts);
@@ -144,45 +151,40 @@ async fn get_volume_data(
     if let Some(ts) = &body.from_ts {
         let _ts = string_to_timestamp(ts);
         if _ts.is_err() {
-            let json_data = json!({ "error": "Unable to parse dates".to_string()});
-            return HttpResponse::ExpectationFailed().json(json_data);
+            return ApiErrorResponse::bad_request("Unable to parse dates").error_response();
         }
         let _ts = _ts.unwrap();
         from_ts = Some(_ts);
     };
 
     let trade_data = market
-        .lock()
-        .await
         .trade_data_range(&body.symbol, from_ts, to_ts, body.limit)
         .await;
 
     if let Some(trade_data) = trade_data {
-        let time_interval = body
-            .time_interval
-            .clone()
-            .unwrap_or_else(|| "1h".to_string());
+        let time_interval = body.time_interval.unwrap_or(Interval::Hour1);
 
         let market_volume = MarketTradeVolume::new();
         let bucket_volume = market_volume.calc_volume_buckets(
             &trade_data.trades(),
             body.price_granularity.unwrap_or_else(|| 10),
-            &time_interval,
+            time_interval,
         );
         // Return the stream data as JSON
         let json_data = json!({ "volume_data": bucket_volume });
         HttpResponse::Ok().json(json_data)
     } else {
-        let json_data = json!({ "error": "Trade Data data not found" });
-        // Stream ID not found
-        HttpResponse::Ok().json(json_data)
+        ApiErrorResponse::not_found("Trade data not found")
+            .with_detail("symbol", &body.symbol)
+            .error_response()
     }
 }
 
 #[derive(Debug, Deserialize)]
 pub struct GetKlineDataRangeParams {
+    #[serde(deserialize_with = "canonical_symbol")]
     symbol: String,
-    interval: String,
+    interval: Interval,
     from_ts: Option<String>,
     to_ts: Option<String>,
     limit: Option<usize>,
@@ -200,8 +202,7 @@ async fn get_kline_data_range(
     if let Some(ts) = &body.to_ts {
         let _ts = string_to_timestamp(ts);
         if _ts.is_err() {
-            let json_data = json!({ "error": "Unable to parse dates".to_string()});
-            return HttpResponse::ExpectationFailed().json(json_data);
+            return ApiErrorResponse::bad_request("Unable to parse dates").error_response();
         }
         let _ts = _ts.unwrap();
         to_ts = Some(_ts);
@@ -210,17 +211,14 @@ async fn get_kline_data_range(
     if let Some(ts) = &body.from_ts {
         let _ts = string_to_timestamp(ts);
         if _ts.is_err() {
-            let json_data = json!({ "error": "Unable to parse dates".to_string()});
-            return HttpResponse::ExpectationFailed().json(json_data);
+            return ApiErrorResponse::bad_request("Unable to parse dates").error_response();
         }
         let _ts = _ts.unwrap();
         from_ts = Some(_ts);
     };
 
     let kline_data = market
-        .lock()
-        .await
-        .kline_data_range(&body.symbol, &body.interval, from_ts, to_ts, body.limit)
+        .kline_data_range(&body.symbol, body.interval, from_ts, to_ts, body.limit)
         .await;
 
     if let Some(kline_data) = kline_data {
@@ -228,14 +226,62 @@ async fn get_kline_data_range(
         let json_data = json!({ "kline_data": kline_data });
         HttpResponse::Ok().json(json_data)
     } else {
-        let json_data = json!({ "error": "Kline data not found" });
-        // Stream ID not found
-        HttpResponse::Ok().json(json_data)
+        ApiErrorResponse::not_found("Kline data not found")
+            .with_detail("symbol", &body.symbol)
+            .error_response()
     }
 }
 
+#[derive(Debug, Deserialize)]
+pub struct GetKlinePageParams {
+    #[serde(deserialize_with = "canonical_symbol")]
+    symbol: String,
+    interval: Interval,
+    from_ts: Option<String>,
+    to_ts: Option<String>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+    order: Option<SortOrder>,
+}
+#[post("/kline-page")]
+async fn get_kline_page(
+    app_data: web::Data<AppState>,
+    body: Json<GetKlinePageParams>,
+) -> impl Responder {
+    let parse_ts = |ts: &Option<String>| ts.as_deref().map(string_to_timestamp).transpose();
+    let (from_ts, to_ts) = match (parse_ts(&body.from_ts), parse_ts(&body.to_ts)) {
+        (Ok(from_ts), Ok(to_ts)) => (from_ts, to_ts),
+        _ => return ApiErrorResponse::bad_request("Unable to parse dates").error_response(),
+    };
+
+    let query = KlineQuery {
+        from_ts,
+        to_ts,
+        limit: body.limit,
+        offset: body.offset.unwrap_or(0),
+        order: body.order,
+    };
+    let market = app_data.get_market().await;
+    let klines = market.kline_page(&body.symbol, body.interval, &query).await;
+
+    // a full page may be followed by more klines
+    let next_offset = query
+        .limit
+        .filter(|limit| klines.len() == *limit)
+        .map(|limit| query.offset + limit);
+    let json_data = json!({
+        "klines": klines,
+        "offset": query.offset,
+        "limit": query.limit,
+        "order": query.sort_order(),
+        "next_offset": next_offset,
+    });
+    HttpResponse::Ok().json(json_data)
+}
+
 #[derive(Debug, Deserialize)]
 pub struct GetTickerDataParams {
+    #[serde(deserialize_with = "canonical_symbol")]
     symbol: String,
 }
 
@@ -246,16 +292,16 @@ async fn last_price(
 ) -> impl Responder {
     let market = app_data.get_market().await;
 
-    let last_price = market.lock().await.last_price(&body.symbol).await;
+    let last_price = market.last_price(&body.symbol).await;
 
     if let Some(last_price) = last_price {
         // Return the stream data as JSON
         let json_data = json!({ "last_price": last_price,"symbol":body.symbol });
         HttpResponse::Ok().json(json_data)
     } else {
-        let json_data = json!({ "error": "Last price not found","symbol":body.symbol });
-        // Stream ID not found
-        HttpResponse::Ok().json(json_data)
+        ApiErrorResponse::not_found("Last price not found")
+            .with_detail("symbol", &body.symbol)
+            .error_response()
     }
 }
 
@@ -263,15 +309,16 @@ async fn last_price(
 async fn market_info(app_data: web::Data<AppState>) -> impl Responder {
     let market = app_data.get_market().await;
 
-    let info = market.lock().await.info().await;
+    let info = market.info().await;
     let json_data = json!({ "market_info": info });
     HttpResponse::Ok().json(json_data)
 }
 
+/// Lists the active streams with their health and whether a running strategy needs them.
 #[get("/active-streams")]
 async fn active_streams(app_data: web::Data<AppState>) -> impl Responder {
     let market = app_data.get_market().await;
-    let active_streams = market.lock().await.active_streams().await;
+    let active_streams = market.stream_statuses().await;
     // Return the stream data as JSON
     let json_data = json!({ "active_streams": active_streams });
     HttpResponse::Ok().json(json_data)
@@ -284,23 +331,36 @@ pub struct CloseStreamParams {
 #[post("/close-stream")]
 async fn close_stream(
     app_data: web::Data<AppState>,
+    scope: TenantScope,
     body: Json<CloseStreamParams>,
 ) -> HttpResponse {
-    let market = app_data.get_market().await;
+    // streams are shared by the strategies of every tenant
+    if let Err(e) = scope.require_operator() {
+        return e.error_response();
+    }
 
-    let stream_meta = market.lock().await.close_stream(&body.stream_id).await;
+    let market = app_data.get_market().await;
+    let stream_meta = market.close_stream(&body.stream_id).await;
 
-    // TODO: handle error
     match stream_meta {
+        // streams needed by strategies are reopened by the stream monitor, which reconnects
+        // stale streams
+        Some(meta) if market.is_needed_stream(&meta.id).await => {
+            let json_data = json!({
+                "success": "Stream closed, it is reopened shortly as a strategy needs it",
+                "stream_meta": meta,
+                "reopened": true,
+            });
+            HttpResponse::Ok().json(json_data)
+        }
         Some(meta) => {
             let json_data = json!({ "success": "Stream closed successfully","stream_meta":meta });
             HttpResponse::Ok().json(json_data)
         }
         None => {
-            let json_data =
-                json!({ "error": format!("Stream width ID {} not found", &body.stream_id) });
-            // Stream ID not found
-            HttpResponse::Ok().json(json_data)
+            ApiErrorResponse::not_found(format!("Stream with ID {} not found", &body.stream_id))
+                .with_detail("stream_id", &body.stream_id)
+                .error_response()
         }
     }
 }
@@ -308,55 +368,325 @@ async fn close_stream(
 #[derive(Debug, Deserialize)]
 pub struct OpenStreamParams {
     stream_type: StreamType,
+    #[serde(deserialize_with = "canonical_symbol")]
     symbol: String,
-    interval: Option<String>,
+    interval: Option<Interval>,
 }
 #[post("/open-stream")]
 async fn open_stream(
     app_data: web::Data<AppState>,
+    scope: TenantScope,
     body: Json<OpenStreamParams>,
 ) -> impl Responder {
-    let stream_type = body.stream_type.clone();
+    // streams are shared by the strategies of every tenant
+    if let Err(e) = scope.require_operator() {
+        return e.error_response();
+    }
+
+    let stream_type = body.stream_type;
     let market = app_data.get_market().await;
 
-    // TODO: handle errors
     let symbol = body.symbol.to_string();
 
-    let stream_id = match stream_type {
-        StreamType::Kline => {
-            let interval = body.interval.clone().unwrap().to_string();
-            market
-                .lock()
-                .await
-                .open_stream(stream_type, &symbol, Some(&interval))
-                .await
-        }
-        StreamType::Ticker => {
-            market
-                .lock()
-                .await
-                .open_stream(stream_type, &symbol, None)
-                .await
+    // only kline streams have an interval
+    let interval = match stream_type {
+        StreamType::Kline => match body.interval {
+            Some(interval) => Some(interval),
+            None => {
+                return ApiErrorResponse::bad_request("Kline streams require an interval")
+                    .error_response()
+            }
+        },
+        StreamType::Ticker | StreamType::Trade => None,
+    };
+
+    let stream_id = build_stream_id(&symbol, stream_type, interval);
+    let is_open = market
+        .active_streams()
+        .await
+        .iter()
+        .any(|meta| meta.id == stream_id);
+    if is_open {
+        return ApiErrorResponse::conflict("Stream is already open")
+            .with_detail("stream_id", stream_id)
+            .error_response();
+    }
+
+    let stream_id = market.open_stream(stream_type, &symbol, interval).await;
+
+    match stream_id {
+        Ok(stream_id) => {
+            let json_data = json!({ "success": "Stream created","stream_id":stream_id });
+            HttpResponse::Ok().json(json_data)
         }
-        StreamType::Trade => {
-            market
-                .lock()
-                .await
-                .open_stream(stream_type, &symbol, None)
-                .await
+        Err(e) => ApiErrorResponse::exchange("Unable to open stream")
+            .with_detail("msg", e.to_string())
+            .error_response(),
+    }
+}
+
+/// Interval of the heartbeat comments keeping idle event streams open through proxies.
+const SSE_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Deserialize)]
+pub struct LiveUpdatesParams {
+    /// Symbols to receive updates of, separated by commas, e.g. `BTCUSDT,ETHUSDT`.
+    symbols: String,
+    /// Update types to receive, `ticker` and/or `kline` separated by commas, all if not given.
+    types: Option<String>,
+    /// Only receive k-lines of this interval.
+    interval: Option<Interval>,
+}
+#[get("/live")]
+async fn live_updates(
+    app_data: web::Data<AppState>,
+    params: web::Query<LiveUpdatesParams>,
+) -> impl Responder {
+    let split = |values: &str| -> HashSet<String> {
+        values
+            .split(',')
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .collect()
+    };
+    let symbols: HashSet<String> = split(&params.symbols)
+        .iter()
+        .map(|symbol| Symbol::normalize(symbol))
+        .collect();
+    if symbols.is_empty() {
+        return ApiErrorResponse::bad_request("No symbols given").error_response();
+    }
+    let types = params.types.as_deref().map(split);
+    let interval = params.interval;
+
+    let is_wanted = move |update: &MarketUpdate| {
+        symbols.contains(update.symbol())
+            && types
+                .as_ref()
+                .is_none_or(|types| types.contains(update.kind()))
+            && match (update, interval) {
+                (MarketUpdate::Kline(kline), Some(interval)) => kline.interval == interval,
+                _ => true,
+            }
+    };
+
+    let market = app_data.get_market().await;
+    let mut updates = market.subscribe_updates();
+
+    let stream = async_stream::stream! {
+        let mut heartbeat = tokio::time::interval(SSE_HEARTBEAT_INTERVAL);
+        loop {
+            tokio::select! {
+                update = updates.recv() => match update {
+                    Ok(update) if is_wanted(&update) => {
+                        let data = serde_json::to_string(&update).unwrap_or_default();
+                        let event = format!("event: {}\ndata: {data}\n\n", update.kind());
+                        yield Ok::<_, actix_web::Error>(Bytes::from(event));
+                    }
+                    Ok(_) => {}
+                    // slow clients miss the oldest updates rather than blocking the market
+                    Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => break,
+                },
+                _ = heartbeat.tick() => {
+                    yield Ok(Bytes::from_static(b": heartbeat\n\n"));
+                }
+            }
         }
     };
 
-    let data = match stream_id {
-        Ok(stream_id) => {
-            json!({ "success": "Stream created","stream_id":stream_id })
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(stream)
+}
+
+/// Returns correlations calculated on demand may span at most.
+const MAX_CORRELATION_WINDOW: usize = 10_000;
+
+#[derive(Debug, Deserialize)]
+pub struct CorrelationsParams {
+    /// Only the correlations of this interval, of every tracked interval if not given.
+    interval: Option<Interval>,
+    /// Symbols to correlate instead of the tracked symbols, separated by commas.
+    symbols: Option<String>,
+    /// The number of returns the symbols are correlated over.
+    window: Option<usize>,
+}
+#[get("/correlations")]
+async fn correlations(
+    app_data: web::Data<AppState>,
+    params: web::Query<CorrelationsParams>,
+) -> impl Responder {
+    let market = app_data.get_market().await;
+
+    let correlations = match (&params.symbols, params.interval) {
+        (Some(symbols), Some(interval)) => {
+            let mut symbols: Vec<String> = symbols
+                .split(',')
+                .map(|symbol| Symbol::normalize(symbol.trim()))
+                .filter(|symbol| !symbol.is_empty())
+                .collect();
+            let mut seen = HashSet::new();
+            symbols.retain(|symbol| seen.insert(symbol.clone()));
+            if symbols.is_empty() {
+                return ApiErrorResponse::bad_request("No symbols given").error_response();
+            }
+            if params
+                .window
+                .is_some_and(|window| window == 0 || window > MAX_CORRELATION_WINDOW)
+            {
+                let error = format!("window must be between 1 and {MAX_CORRELATION_WINDOW}");
+                return ApiErrorResponse::bad_request(error).error_response();
+            }
+            let matrix = market
+                .correlation_matrix(&symbols, interval, params.window, generate_ts())
+                .await;
+            vec![matrix]
+        }
+        (Some(_), None) => {
+            return ApiErrorResponse::bad_request("An interval is needed to correlate symbols")
+                .error_response();
         }
-        Err(e) => {
-            json!({ "error": "Unable to open stream","msg":e.to_string() })
+        (None, Some(interval)) => market.correlations(interval).await.into_iter().collect(),
+        (None, None) => market.all_correlations().await,
+    };
+
+    let json_data = json!({ "correlations": correlations });
+
+    HttpResponse::Ok().json(json_data)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CorrelatedPairsParams {
+    interval: Interval,
+    /// The absolute correlation from which symbols pair, `CORRELATION_THRESHOLD` if not given.
+    min_correlation: Option<f64>,
+}
+#[get("/correlated-pairs")]
+async fn correlated_pairs(
+    app_data: web::Data<AppState>,
+    params: web::Query<CorrelatedPairsParams>,
+) -> impl Responder {
+    let market = app_data.get_market().await;
+    let min_correlation = params.min_correlation.unwrap_or(CORRELATION_THRESHOLD);
+    let pairs = market
+        .correlations(params.interval)
+        .await
+        .map(|matrix| matrix.pairs(min_correlation))
+        .unwrap_or_default();
+
+    let json_data = json!({ "pairs": pairs });
+
+    HttpResponse::Ok().json(json_data)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExternalDataParams {
+    /// The series to list the values of, the latest value of every series if not given.
+    series: Option<String>,
+    from: Option<String>,
+    to: Option<String>,
+}
+#[get("/external-data")]
+async fn external_data(
+    app_data: web::Data<AppState>,
+    params: web::Query<ExternalDataParams>,
+) -> impl Responder {
+    let market = app_data.get_market().await;
+
+    let Some(series) = &params.series else {
+        let values = market.external_values(generate_ts()).await;
+        let json_data = json!({ "series": market.external_series_names().await, "values": values });
+        return HttpResponse::Ok().json(json_data);
+    };
+
+    let parse = |date: &Option<String>| date.as_deref().map(string_to_timestamp).transpose();
+    let (from_ts, to_ts) = match (parse(&params.from), parse(&params.to)) {
+        (Ok(from_ts), Ok(to_ts)) => (from_ts, to_ts),
+        _ => return ApiErrorResponse::bad_request("Unable to parse dates").error_response(),
+    };
+    let points = market.external_series(series, from_ts, to_ts).await;
+
+    let json_data = json!({ "series": series, "points": points });
+
+    HttpResponse::Ok().json(json_data)
+}
+
+/// Number of k-lines encoded into each chunk of an export.
+const EXPORT_BATCH_SIZE: usize = 1000;
+
+#[derive(Debug, Deserialize)]
+pub struct ExportKlinesParams {
+    #[serde(deserialize_with = "canonical_symbol")]
+    symbol: String,
+    interval: Interval,
+    from: String,
+    /// The end of the range, now if not given.
+    to: Option<String>,
+    #[serde(default)]
+    format: KlineExportFormat,
+}
+#[get("/klines/export")]
+async fn export_klines(
+    app_data: web::Data<AppState>,
+    params: web::Query<ExportKlinesParams>,
+) -> impl Responder {
+    let to_ts = match &params.to {
+        Some(to) => string_to_timestamp(to),
+        None => Ok(generate_ts()),
+    };
+    let (from_ts, to_ts) = match (string_to_timestamp(&params.from), to_ts) {
+        (Ok(from_ts), Ok(to_ts)) => (from_ts, to_ts),
+        _ => return ApiErrorResponse::bad_request("Unable to parse dates").error_response(),
+    };
+
+    let market = app_data.get_market().await;
+    let mut klines = market
+        .kline_stream(&params.symbol, params.interval, from_ts, to_ts)
+        .await;
+
+    let format = params.format;
+    let mut encoder = KlineEncoder::new(format);
+    let stream = async_stream::stream! {
+        let mut batch = Vec::with_capacity(EXPORT_BATCH_SIZE);
+        loop {
+            let kline = klines.next().await;
+            let is_last = kline.is_none();
+            batch.extend(kline);
+
+            if batch.len() == EXPORT_BATCH_SIZE || is_last {
+                match encoder.encode(&batch) {
+                    Ok(bytes) => yield Ok::<_, actix_web::Error>(Bytes::from(bytes)),
+                    Err(e) => {
+                        // the response is cut short, the client sees an incomplete download
+                        yield Err(actix_web::error::ErrorInternalServerError(e.to_string()));
+                        break;
+                    }
+                }
+                batch.clear();
+            }
+
+            if is_last {
+                yield Ok(Bytes::from(encoder.finish()));
+                break;
+            }
         }
     };
 
-    HttpResponse::Ok().json(data)
+    let filename = format!(
+        "{}-{}-klines.{}",
+        params.symbol,
+        params.interval,
+        format.extension()
+    );
+    HttpResponse::Ok()
+        .content_type(format.content_type())
+        .insert_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"{filename}\""),
+        ))
+        .streaming(stream)
 }
 
 pub fn register_market_service() -> Scope {
@@ -366,9 +696,15 @@ pub fn register_market_service() -> Scope {
         .service(open_stream)
         .service(get_kline_data)
         .service(get_kline_data_range)
+        .service(get_kline_page)
         .service(market_info)
         .service(active_streams)
         .service(get_ticker_data)
         .service(get_trade_data)
         .service(get_volume_data)
+        .service(live_updates)
+        .service(export_klines)
+        .service(correlations)
+        .service(correlated_pairs)
+        .service(external_data)
 }
@@ -1,6 +1,14 @@
 pub mod account;
+pub mod admin;
+pub mod backtest;
+pub mod error;
 pub mod exchange;
+#[cfg(feature = "graphql")]
+pub mod graphql;
 pub mod main;
 pub mod market;
 pub mod strategy;
+pub mod tenant;
 pub mod utils;
+pub mod webhook;
+pub mod ws;
@@ -1,40 +1,91 @@
+use std::collections::BTreeMap;
+
 use actix_web::web::Json;
 use actix_web::{
     get, post,
     web::{self, scope},
-    HttpResponse, Responder, Scope,
+    HttpResponse, Responder, ResponseError, Scope,
 };
 
 use serde::{Deserialize, Serialize};
-use serde_json::{json, Value};
+use serde_json::{json, Map, Value};
 
 use crate::account::trade::Position;
+use crate::api::error::ApiErrorResponse;
+use crate::api::tenant::TenantScope;
 use crate::app::AppState;
-use crate::strategy::strategy::{StrategyId, StrategySettings};
+use crate::market::interval::Interval;
+use crate::market::regime::MarketRegime;
+use crate::market::symbol::canonical_symbol;
+use crate::storage::summary_query::StrategySummaryQuery;
+use crate::strategy::algorithm::AlgorithmBuilder;
+use crate::strategy::backer::BatchBackTest;
+use crate::strategy::execution::ExecutionStyle;
+use crate::strategy::export::trades_to_csv;
+use crate::strategy::optimizer::{OptimizerSettings, ParamRange};
+use crate::strategy::portfolio::PortfolioStrategy;
+use crate::strategy::report::ReportFormat;
+use crate::strategy::signal::SignalHistoryQuery;
+use crate::strategy::strategy::{
+    StrategyDefinition, StrategyDirection, StrategyId, StrategySettings, StrategySettingsUpdate,
+};
+use crate::strategy::walk_forward::{OptimizationObjective, WalkForwardSettings};
 use crate::utils::time::string_to_timestamp;
 
 #[derive(Debug, Deserialize)]
 pub struct NewStrategyParams {
+    #[serde(deserialize_with = "canonical_symbol")]
     symbol: String,
     strategy_name: String,
     algorithm_params: Value,
-    interval: String,
+    interval: Interval,
     margin: Option<f64>,
     leverage: Option<u32>,
+    min_volume_24h: Option<f64>,
+    min_atr: Option<f64>,
+    max_atr: Option<f64>,
+    min_candle_body: Option<f64>,
+    min_confidence: Option<f64>,
+    scale_by_confidence: Option<bool>,
+    direction: Option<StrategyDirection>,
+    max_position_duration: Option<u64>,
+    shadow: Option<bool>,
+    allowed_regimes: Option<Vec<MarketRegime>>,
+    profile: Option<String>,
+    execution: Option<ExecutionStyle>,
+    max_correlated_margin: Option<f64>,
 }
 #[post("/new-strategy")]
 async fn new_strategy(
     app_data: web::Data<AppState>,
+    scope: TenantScope,
     body: web::Json<NewStrategyParams>,
 ) -> impl Responder {
     let bot = app_data.bot.clone();
 
-    let settings = StrategySettings {
+    let mut settings = StrategySettings {
         max_open_orders: 2,
         margin_usd: body.margin.unwrap_or(1000.0),
         leverage: body.leverage.unwrap_or(10),
         stop_loss: None,
+        take_profit: None,
+        min_volume_24h: body.min_volume_24h,
+        min_atr: body.min_atr,
+        max_atr: body.max_atr,
+        min_candle_body: body.min_candle_body,
+        min_confidence: body.min_confidence,
+        scale_by_confidence: body.scale_by_confidence.unwrap_or(false),
+        direction: body.direction.unwrap_or_default(),
+        max_position_duration: body.max_position_duration,
+        shadow: body.shadow.unwrap_or(false),
+        allowed_regimes: body.allowed_regimes.clone(),
+        profile: body.profile.clone(),
+        execution: body.execution.unwrap_or_default(),
+        max_correlated_margin: body.max_correlated_margin,
     };
+    if let Err(e) = scope.scope_settings(&mut settings) {
+        return e.error_response();
+    }
 
     let info = bot
         .lock()
@@ -42,7 +93,7 @@ async fn new_strategy(
         .start_strategy(
             &body.strategy_name,
             &body.symbol,
-            &body.interval,
+            body.interval,
             settings,
             body.algorithm_params.clone(),
         )
@@ -54,9 +105,71 @@ async fn new_strategy(
 
             HttpResponse::Ok().json(json_data)
         }
-        Err(e) => {
-            let json_data = json!({ "error": e.to_string()});
-            HttpResponse::ExpectationFailed().json(json_data)
+        Err(e) => ApiErrorResponse::from(e).error_response(),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NewStrategiesParams {
+    strategies: Vec<StrategyDefinition>,
+}
+#[post("/new-strategies")]
+async fn new_strategies(
+    app_data: web::Data<AppState>,
+    scope: TenantScope,
+    body: web::Json<NewStrategiesParams>,
+) -> impl Responder {
+    let mut definitions = body.into_inner().strategies;
+    if definitions.is_empty() {
+        return ApiErrorResponse::bad_request("No strategies given").error_response();
+    }
+    for definition in &mut definitions {
+        if let Err(e) = scope.scope_settings(&mut definition.settings) {
+            return e
+                .with_detail("strategy_name", &definition.strategy_name)
+                .error_response();
+        }
+    }
+
+    let names: Vec<(String, String)> = definitions
+        .iter()
+        .map(|definition| (definition.strategy_name.clone(), definition.symbol.clone()))
+        .collect();
+
+    let result = app_data
+        .bot
+        .lock()
+        .await
+        .start_strategies(definitions)
+        .await;
+
+    match result {
+        Ok(infos) => {
+            let results: Vec<Value> = infos
+                .iter()
+                .map(|info| json!({ "started": true, "strategy_info": info }))
+                .collect();
+            let json_data = json!({ "success": "Strategies started", "results": results });
+
+            HttpResponse::Ok().json(json_data)
+        }
+        Err(errors) => {
+            // nothing was started, valid definitions are reported without an error
+            let results: Vec<Value> = names
+                .iter()
+                .zip(errors)
+                .map(|((strategy_name, symbol), error)| {
+                    json!({
+                        "started": false,
+                        "strategy_name": strategy_name,
+                        "symbol": symbol,
+                        "error": error.map(|e| e.to_string()),
+                    })
+                })
+                .collect();
+            ApiErrorResponse::bad_request("No strategy started")
+                .with_detail("results", results)
+                .error_response()
         }
     }
 }
@@ -69,17 +182,17 @@ pub struct GetStrategyParams {
 #[post("/stop-strategy")]
 async fn stop_strategy(
     app_data: web::Data<AppState>,
+    scope: TenantScope,
     body: web::Json<GetStrategyParams>,
 ) -> impl Responder {
-    let bot = app_data.bot.clone();
+    let mut bot = app_data.bot.lock().await;
+    if let Err(e) = scope.check_strategy(&mut bot, body.strategy_id).await {
+        return e.error_response();
+    }
 
     let close_positions = body.close_positions.unwrap_or(true);
 
-    let summary = bot
-        .lock()
-        .await
-        .stop_strategy(body.strategy_id, close_positions)
-        .await;
+    let summary = bot.stop_strategy(body.strategy_id, close_positions).await;
 
     match summary {
         Some(summary) => {
@@ -87,20 +200,24 @@ async fn stop_strategy(
 
             HttpResponse::Ok().json(json_data)
         }
-        None => {
-            let json_data = json!({ "error": "Strategy not found","strategy_id":body.strategy_id });
-
-            HttpResponse::BadRequest().json(json_data)
-        }
+        None => ApiErrorResponse::not_found("Strategy not found")
+            .with_detail("strategy_id", body.strategy_id)
+            .error_response(),
     }
 }
 
 #[post("/list-positions")]
 async fn list_strategy_positions(
     app_data: web::Data<AppState>,
+    scope: TenantScope,
     body: web::Json<GetStrategyParams>,
 ) -> impl Responder {
-    let account = app_data.get_account().await;
+    let mut bot = app_data.bot.lock().await;
+    if let Err(e) = scope.check_strategy(&mut bot, body.strategy_id).await {
+        return e.error_response();
+    }
+    let account = bot.get_strategy_account(body.strategy_id).await;
+    drop(bot);
 
     let positions: Vec<Position> = account
         .lock()
@@ -118,9 +235,13 @@ async fn list_strategy_positions(
 #[post("/summary")]
 async fn active_strategy_summary(
     app_data: web::Data<AppState>,
+    scope: TenantScope,
     body: web::Json<GetStrategyParams>,
 ) -> impl Responder {
     let mut bot = app_data.bot.lock().await;
+    if let Err(e) = scope.check_strategy(&mut bot, body.strategy_id).await {
+        return e.error_response();
+    }
 
     if let Some(summary) = bot.get_strategy_summary(body.strategy_id).await {
         let json_data = json!({ "strategy_summary": summary });
@@ -128,30 +249,37 @@ async fn active_strategy_summary(
         return HttpResponse::Ok().json(json_data);
     };
 
-    let json_data = json!({ "error": "Unable to find strategy", "strategy_id": body.strategy_id });
-
-    HttpResponse::ExpectationFailed().json(json_data)
+    ApiErrorResponse::not_found("Unable to find strategy")
+        .with_detail("strategy_id", body.strategy_id)
+        .error_response()
 }
 
 #[post("/info")]
 async fn strategy_info(
     app_data: web::Data<AppState>,
+    scope: TenantScope,
     body: web::Json<GetStrategyParams>,
 ) -> impl Responder {
     let mut bot = app_data.bot.lock().await;
+    if let Err(e) = scope.check_strategy(&mut bot, body.strategy_id).await {
+        return e.error_response();
+    }
     if let Some(info) = bot.get_strategy_info(body.strategy_id).await {
         let json_data = json!({ "strategy_info": info });
 
         return HttpResponse::Ok().json(json_data);
     };
 
-    let json_data = json!({ "error": "Unable to find strategy" });
-
-    HttpResponse::ExpectationFailed().json(json_data)
+    ApiErrorResponse::not_found("Unable to find strategy")
+        .with_detail("strategy_id", body.strategy_id)
+        .error_response()
 }
 
 #[get("/active-strategies")]
-async fn list_active_strategies(app_data: web::Data<AppState>) -> impl Responder {
+async fn list_active_strategies(
+    app_data: web::Data<AppState>,
+    scope: TenantScope,
+) -> impl Responder {
     let bot = app_data.bot.clone();
 
     let strategy_ids = bot.lock().await.get_active_strategy_ids().await;
@@ -160,7 +288,9 @@ async fn list_active_strategies(app_data: web::Data<AppState>) -> impl Responder
 
     for id in strategy_ids {
         if let Some(info) = bot.lock().await.get_strategy_info(id).await {
-            infos.push(info)
+            if scope.allows(&info.settings) {
+                infos.push(info)
+            }
         }
     }
 
@@ -169,37 +299,146 @@ async fn list_active_strategies(app_data: web::Data<AppState>) -> impl Responder
     HttpResponse::Ok().json(json_data)
 }
 
-#[get("/historical-strategies")]
-async fn list_historical_strategies(app_data: web::Data<AppState>) -> impl Responder {
-    let bot = app_data.bot.clone();
+/// Lists the strategies whose task failed, with their restarts and whether they were stopped.
+#[get("/restarts")]
+async fn list_strategy_restarts(
+    app_data: web::Data<AppState>,
+    scope: TenantScope,
+) -> impl Responder {
+    if let Err(e) = scope.require_operator() {
+        return e.error_response();
+    }
+
+    let restarts = app_data.bot.lock().await.strategy_restarts();
+
+    let json_data = json!({ "restarts": restarts });
+    HttpResponse::Ok().json(json_data)
+}
+
+#[get("/funding-opportunities")]
+async fn list_funding_opportunities(
+    app_data: web::Data<AppState>,
+    scope: TenantScope,
+) -> impl Responder {
+    if let Err(e) = scope.require_operator() {
+        return e.error_response();
+    }
 
-    let summaries = bot.lock().await.list_historical_strategies().await;
+    let opportunities = app_data.bot.lock().await.funding_opportunities().await;
+
+    let json_data = json!({ "opportunities": opportunities });
+    HttpResponse::Ok().json(json_data)
+}
 
-    let json_data = json!({ "strategy_infos": summaries });
+#[get("/algorithms")]
+async fn list_algorithms() -> impl Responder {
+    let algorithms = AlgorithmBuilder::list_algorithms();
+
+    let json_data = json!({ "algorithms": algorithms });
 
     HttpResponse::Ok().json(json_data)
 }
 
+#[get("/historical-strategies")]
+async fn list_historical_strategies(
+    app_data: web::Data<AppState>,
+    scope: TenantScope,
+    query: web::Query<StrategySummaryQuery>,
+) -> impl Responder {
+    let mut query = query.into_inner();
+    scope.scope_summary_query(&mut query);
+
+    let bot = app_data.bot.lock().await;
+    match bot.list_historical_strategies(&query).await {
+        Ok((strategies, total)) => {
+            // more strategies follow the page if it ends before the total
+            let page_end = query.offset + strategies.len();
+            let next_offset = (page_end < total).then_some(page_end);
+            let json_data = json!({
+                "strategies": strategies,
+                "total": total,
+                "offset": query.offset,
+                "limit": query.limit,
+                "next_offset": next_offset,
+            });
+            HttpResponse::Ok().json(json_data)
+        }
+        Err(e) => ApiErrorResponse::from(e).error_response(),
+    }
+}
+
+#[get("/historical-strategies/{strategy_id}")]
+async fn historical_strategy_detail(
+    app_data: web::Data<AppState>,
+    scope: TenantScope,
+    strategy_id: web::Path<StrategyId>,
+) -> impl Responder {
+    let strategy_id = strategy_id.into_inner();
+    let mut bot = app_data.bot.lock().await;
+    if let Err(e) = scope.check_strategy(&mut bot, strategy_id).await {
+        return e.error_response();
+    }
+    let summary = bot.get_historical_strategy_summary(strategy_id).await;
+
+    match summary {
+        Some(summary) => HttpResponse::Ok().json(json!({ "strategy_summary": summary })),
+        None => ApiErrorResponse::not_found("Historical data not found")
+            .with_detail("strategy_id", strategy_id)
+            .error_response(),
+    }
+}
+
+#[post("/historical-summaries")]
+async fn historical_strategy_summaries(
+    app_data: web::Data<AppState>,
+    scope: TenantScope,
+    body: Json<StrategySummaryQuery>,
+) -> impl Responder {
+    let mut query = body.into_inner();
+    scope.scope_summary_query(&mut query);
+
+    let bot = app_data.bot.lock().await;
+    match bot.list_historical_strategy_summaries(&query).await {
+        Ok(summaries) => HttpResponse::Ok().json(json!({ "strategy_summaries": summaries })),
+        Err(e) => ApiErrorResponse::from(e).error_response(),
+    }
+}
+
+#[post("/historical-stats")]
+async fn historical_strategy_stats(
+    app_data: web::Data<AppState>,
+    scope: TenantScope,
+    body: Json<StrategySummaryQuery>,
+) -> impl Responder {
+    let mut query = body.into_inner();
+    scope.scope_summary_query(&mut query);
+
+    let bot = app_data.bot.lock().await;
+    match bot.get_historical_strategy_stats(&query).await {
+        Ok(stats) => HttpResponse::Ok().json(json!({ "algorithm_stats": stats })),
+        Err(e) => ApiErrorResponse::from(e).error_response(),
+    }
+}
+
 #[post("/historical-summary")]
 async fn historical_strategy_summary(
     app_data: web::Data<AppState>,
+    scope: TenantScope,
     body: Json<GetStrategyParams>,
 ) -> impl Responder {
-    if let Some(summary) = app_data
-        .bot
-        .lock()
-        .await
-        .get_historical_strategy_summary(body.strategy_id)
-        .await
-    {
+    let mut bot = app_data.bot.lock().await;
+    if let Err(e) = scope.check_strategy(&mut bot, body.strategy_id).await {
+        return e.error_response();
+    }
+
+    if let Some(summary) = bot.get_historical_strategy_summary(body.strategy_id).await {
         let json_data = json!({ "strategy_summary": summary });
 
         HttpResponse::Ok().json(json_data)
     } else {
-        let json_data =
-            json!({ "error": "Historical data not found", "strategy_id": body.strategy_id });
-
-        HttpResponse::Ok().json(json_data)
+        ApiErrorResponse::not_found("Historical data not found")
+            .with_detail("strategy_id", body.strategy_id)
+            .error_response()
     }
 }
 
@@ -210,11 +449,23 @@ struct StopAllStrategiesParams {
 #[post("/stop-all-strategies")]
 async fn stop_all_strategies(
     app_data: web::Data<AppState>,
+    scope: TenantScope,
     body: Json<StopAllStrategiesParams>,
 ) -> impl Responder {
     let bot = app_data.bot.clone();
 
-    let strategies = bot.lock().await.get_active_strategy_ids().await;
+    // tenants stop their own strategies only
+    let active_strategies = bot.lock().await.get_active_strategy_ids().await;
+    let mut strategies = vec![];
+    for id in active_strategies {
+        if scope
+            .check_strategy(&mut *bot.lock().await, id)
+            .await
+            .is_ok()
+        {
+            strategies.push(id);
+        }
+    }
 
     let close_positions = body.close_positions.unwrap_or(true);
 
@@ -231,25 +482,76 @@ async fn stop_all_strategies(
 pub struct SetStrategyParams {
     strategy_id: StrategyId,
     params: Value,
+    changed_by: Option<String>,
 }
 #[post("/set-params")]
 async fn set_strategy_params(
     app_data: web::Data<AppState>,
+    scope: TenantScope,
     body: Json<SetStrategyParams>,
 ) -> impl Responder {
     let bot = app_data.bot.clone();
     let mut bot = bot.lock().await;
-    // if let Some(strategy) = app_data.bot.lock().await.get_strategy(body.strategy_id) {
-    if let Err(err) = bot
-        .set_strategy_params(body.strategy_id, body.params.clone())
+    if let Err(e) = scope.check_strategy(&mut bot, body.strategy_id).await {
+        return e.error_response();
+    }
+    let changed_by = body.changed_by.clone().unwrap_or("api".to_string());
+    match bot
+        .set_strategy_params(body.strategy_id, body.params.clone(), &changed_by)
         .await
     {
-        let json_data = json!({ "error": err.to_string() });
-        HttpResponse::Ok().json(json_data)
-    } else {
-        let updated_params = bot.get_strategy_params(body.strategy_id).await;
-        let json_data = json!({ "success": { "updated_params": updated_params } });
-        HttpResponse::Ok().json(json_data)
+        Ok(Some(updated_params)) => {
+            let json_data = json!({ "success": { "updated_params": updated_params } });
+            HttpResponse::Ok().json(json_data)
+        }
+        Ok(None) => ApiErrorResponse::not_found("Unable to find strategy")
+            .with_detail("strategy_id", body.strategy_id)
+            .error_response(),
+        Err(err) => ApiErrorResponse::bad_request(err.to_string()).error_response(),
+    }
+}
+
+#[post("/params-history")]
+async fn strategy_params_history(
+    app_data: web::Data<AppState>,
+    scope: TenantScope,
+    body: Json<GetStrategyParams>,
+) -> impl Responder {
+    let mut bot = app_data.bot.lock().await;
+    if let Err(e) = scope.check_strategy(&mut bot, body.strategy_id).await {
+        return e.error_response();
+    }
+    if let Some(history) = bot.get_strategy_params_history(body.strategy_id).await {
+        let json_data = json!({ "params_history": history });
+
+        return HttpResponse::Ok().json(json_data);
+    };
+
+    ApiErrorResponse::not_found("Unable to get params history")
+        .with_detail("strategy_id", body.strategy_id)
+        .error_response()
+}
+
+#[post("/signal-history")]
+async fn signal_history(
+    app_data: web::Data<AppState>,
+    scope: TenantScope,
+    body: Json<SignalHistoryQuery>,
+) -> impl Responder {
+    let mut bot = app_data.bot.lock().await;
+    // tenants see the signals of one of their strategies at a time
+    if matches!(scope, TenantScope::Tenant(_)) {
+        let Some(strategy_id) = body.strategy_id else {
+            return ApiErrorResponse::bad_request("strategy_id is required for tenants")
+                .error_response();
+        };
+        if let Err(e) = scope.check_strategy(&mut bot, strategy_id).await {
+            return e.error_response();
+        }
+    }
+    match bot.get_signal_history(body.into_inner()).await {
+        Ok(signals) => HttpResponse::Ok().json(json!({ "signals": signals })),
+        Err(e) => ApiErrorResponse::from(e).error_response(),
     }
 }
 
@@ -261,52 +563,417 @@ pub struct ChangeSettingsParams {
 #[post("/change-settings")]
 async fn change_strategy_settings(
     app_data: web::Data<AppState>,
+    scope: TenantScope,
     body: Json<ChangeSettingsParams>,
 ) -> impl Responder {
     let bot = app_data.bot.clone();
     let mut bot = bot.lock().await;
+    if let Err(e) = scope.check_strategy(&mut bot, body.strategy_id).await {
+        return e.error_response();
+    }
+    let mut settings = body.settings.clone();
+    if let Err(e) = scope.scope_settings(&mut settings) {
+        return e.error_response();
+    }
+
     if let Some(info) = bot
-        .change_strategy_settings(body.strategy_id, body.settings.clone())
+        .change_strategy_settings(body.strategy_id, settings)
         .await
     {
         let json_data = json!({ "success": { "updated_info": info } });
 
         HttpResponse::Ok().json(json_data)
     } else {
-        let json_data = json!({ "error": "Unable to find strategy" });
-        HttpResponse::Ok().json(json_data)
+        ApiErrorResponse::not_found("Unable to find strategy")
+            .with_detail("strategy_id", body.strategy_id)
+            .error_response()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateSettingsParams {
+    strategy_id: StrategyId,
+    #[serde(flatten)]
+    update: StrategySettingsUpdate,
+}
+#[post("/update-settings")]
+async fn update_strategy_settings(
+    app_data: web::Data<AppState>,
+    scope: TenantScope,
+    body: Json<UpdateSettingsParams>,
+) -> impl Responder {
+    let mut bot = app_data.bot.lock().await;
+    if let Err(e) = scope.check_strategy(&mut bot, body.strategy_id).await {
+        return e.error_response();
+    }
+    match bot
+        .update_strategy_settings(body.strategy_id, &body.update)
+        .await
+    {
+        Ok(info) => {
+            let json_data = json!({ "success": { "updated_info": info } });
+
+            HttpResponse::Ok().json(json_data)
+        }
+        Err(e) => ApiErrorResponse::from(e)
+            .with_detail("strategy_id", body.strategy_id)
+            .error_response(),
+    }
+}
+
+#[get("/back-tests")]
+async fn list_back_tests(app_data: web::Data<AppState>, scope: TenantScope) -> impl Responder {
+    // back tests are shared by every tenant, they are run by the operator
+    if let Err(e) = scope.require_operator() {
+        return e.error_response();
+    }
+
+    let records = app_data.bot.lock().await.list_saved_back_tests().await;
+
+    if let Some(records) = records {
+        // leave out trades and equity curves, fetch a single run for the full result
+        let back_tests: Vec<Value> = records
+            .iter()
+            .map(|record| {
+                json!({
+                    "id": record.id(),
+                    "info": record.summary.info,
+                    "from_ts": record.from_ts,
+                    "to_ts": record.to_ts,
+                    "mode": record.mode,
+                    "created_at": record.created_at,
+                    "profit": record.summary.profit,
+                    "metrics": record.summary.metrics,
+                })
+            })
+            .collect();
+        let json_data = json!({ "back_tests": back_tests });
+
+        return HttpResponse::Ok().json(json_data);
+    }
+
+    ApiErrorResponse::internal("Unable to list back tests").error_response()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GetBackTestParams {
+    id: StrategyId,
+}
+#[post("/back-test")]
+async fn back_test(
+    app_data: web::Data<AppState>,
+    scope: TenantScope,
+    body: Json<GetBackTestParams>,
+) -> impl Responder {
+    if let Err(e) = scope.require_operator() {
+        return e.error_response();
+    }
+
+    let mut bot = app_data.bot.lock().await;
+    if let Some(record) = bot.get_saved_back_test(body.id).await {
+        let json_data = json!({ "back_test": record });
+
+        return HttpResponse::Ok().json(json_data);
+    };
+
+    ApiErrorResponse::not_found("Unable to find back test")
+        .with_detail("id", body.id)
+        .error_response()
+}
+
+#[post("/back-test-trades")]
+async fn back_test_trades(
+    app_data: web::Data<AppState>,
+    scope: TenantScope,
+    body: Json<GetBackTestParams>,
+) -> impl Responder {
+    if let Err(e) = scope.require_operator() {
+        return e.error_response();
+    }
+
+    let mut bot = app_data.bot.lock().await;
+    let record = match bot.get_saved_back_test(body.id).await {
+        Some(record) => record,
+        None => {
+            return ApiErrorResponse::not_found("Unable to find back test")
+                .with_detail("id", body.id)
+                .error_response();
+        }
+    };
+
+    let fee_rate = record.options.fee_rate.unwrap_or(0.0);
+    match trades_to_csv(&record.summary.trades, fee_rate) {
+        Ok(csv) => HttpResponse::Ok()
+            .content_type("text/csv")
+            .insert_header((
+                "Content-Disposition",
+                format!("attachment; filename=\"back-test-{}-trades.csv\"", body.id),
+            ))
+            .body(csv),
+        Err(e) => ApiErrorResponse::internal(e.to_string()).error_response(),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BackTestReportParams {
+    id: StrategyId,
+    #[serde(default)]
+    format: ReportFormat,
+}
+#[post("/generate-back-test-report")]
+async fn generate_back_test_report(
+    app_data: web::Data<AppState>,
+    scope: TenantScope,
+    body: Json<BackTestReportParams>,
+) -> impl Responder {
+    if let Err(e) = scope.require_operator() {
+        return e.error_response();
+    }
+
+    let mut bot = app_data.bot.lock().await;
+    if bot.get_saved_back_test(body.id).await.is_none() {
+        return ApiErrorResponse::not_found("Unable to find back test")
+            .with_detail("id", body.id)
+            .error_response();
+    }
+
+    match bot.generate_back_test_report(body.id, body.format).await {
+        Ok(report) => {
+            // leave out the content, download the report for it
+            let json_data = json!({
+                "report": {
+                    "id": report.id,
+                    "format": report.format,
+                    "created_at": report.created_at,
+                    "filename": report.filename(),
+                }
+            });
+            HttpResponse::Ok().json(json_data)
+        }
+        Err(e) => ApiErrorResponse::from(e)
+            .with_detail("id", body.id)
+            .error_response(),
+    }
+}
+
+#[post("/back-test-report")]
+async fn back_test_report(
+    app_data: web::Data<AppState>,
+    scope: TenantScope,
+    body: Json<BackTestReportParams>,
+) -> impl Responder {
+    if let Err(e) = scope.require_operator() {
+        return e.error_response();
+    }
+
+    let mut bot = app_data.bot.lock().await;
+    if let Some(report) = bot.get_back_test_report(body.id, body.format).await {
+        return HttpResponse::Ok()
+            .content_type(report.format.content_type())
+            .insert_header((
+                "Content-Disposition",
+                format!("attachment; filename=\"{}\"", report.filename()),
+            ))
+            .body(report.content);
+    };
+
+    ApiErrorResponse::not_found("Unable to find back test report")
+        .with_detail("id", body.id)
+        .error_response()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CompareBackTestsParams {
+    ids: Vec<StrategyId>,
+}
+#[post("/compare-back-tests")]
+async fn compare_back_tests(
+    app_data: web::Data<AppState>,
+    scope: TenantScope,
+    body: Json<CompareBackTestsParams>,
+) -> impl Responder {
+    if let Err(e) = scope.require_operator() {
+        return e.error_response();
+    }
+
+    if body.ids.len() < 2 {
+        return ApiErrorResponse::bad_request("At least two back tests are required")
+            .error_response();
+    }
+
+    let mut bot = app_data.bot.lock().await;
+    if let Some(comparison) = bot.compare_back_tests(&body.ids).await {
+        let json_data = json!({ "comparison": comparison });
+
+        return HttpResponse::Ok().json(json_data);
+    };
+
+    ApiErrorResponse::not_found("Unable to find back tests")
+        .with_detail("ids", &body.ids)
+        .error_response()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RunPortfolioBackTestParams {
+    strategies: Vec<PortfolioStrategy>,
+    capital: Option<f64>,
+    from_ts: String,
+    to_ts: String,
+}
+#[post("/run-portfolio-back-test")]
+async fn run_portfolio_back_test(
+    app_data: web::Data<AppState>,
+    scope: TenantScope,
+    body: Json<RunPortfolioBackTestParams>,
+) -> impl Responder {
+    if let Err(e) = scope.require_operator() {
+        return e.error_response();
+    }
+
+    let bot = app_data.bot.clone();
+
+    let from_ts = string_to_timestamp(&body.from_ts);
+    let to_ts = string_to_timestamp(&body.to_ts);
+    if from_ts.is_err() || to_ts.is_err() {
+        return ApiErrorResponse::bad_request("Unable to parse dates").error_response();
+    }
+
+    // SAFETY: Error check above
+    let from_ts = from_ts.unwrap();
+    let to_ts = to_ts.unwrap();
+
+    let result = bot
+        .lock()
+        .await
+        .run_portfolio_back_test(
+            body.strategies.clone(),
+            from_ts,
+            to_ts,
+            body.capital.unwrap_or(10_000.0),
+        )
+        .await;
+
+    match result {
+        Ok(result) => {
+            let json_data = json!({ "result": result });
+
+            HttpResponse::Ok().json(json_data)
+        }
+        Err(e) => ApiErrorResponse::bad_request(e.to_string()).error_response(),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RunBatchBackTestParams {
+    back_tests: Vec<BatchBackTest>,
+    from_ts: String,
+    to_ts: String,
+}
+#[post("/run-batch-back-test")]
+async fn run_batch_back_test(
+    app_data: web::Data<AppState>,
+    scope: TenantScope,
+    body: Json<RunBatchBackTestParams>,
+) -> impl Responder {
+    if let Err(e) = scope.require_operator() {
+        return e.error_response();
+    }
+
+    let bot = app_data.bot.clone();
+
+    let from_ts = string_to_timestamp(&body.from_ts);
+    let to_ts = string_to_timestamp(&body.to_ts);
+    if from_ts.is_err() || to_ts.is_err() {
+        return ApiErrorResponse::bad_request("Unable to parse dates").error_response();
+    }
+
+    // SAFETY: Error check above
+    let from_ts = from_ts.unwrap();
+    let to_ts = to_ts.unwrap();
+
+    let result = bot
+        .lock()
+        .await
+        .run_batch_back_test(body.back_tests.clone(), from_ts, to_ts)
+        .await;
+
+    match result {
+        Ok(result) => {
+            let json_data = json!({ "result": result });
+
+            HttpResponse::Ok().json(json_data)
+        }
+        Err(e) => ApiErrorResponse::bad_request(e.to_string()).error_response(),
     }
 }
 
 #[derive(Debug, Deserialize)]
-pub struct RunBackTestParams {
+pub struct RunWalkForwardParams {
+    #[serde(deserialize_with = "canonical_symbol")]
     symbol: String,
     strategy_name: String,
     algorithm_params: Value,
-    interval: String,
+    #[serde(default)]
+    param_grid: Map<String, Value>,
+    interval: Interval,
     margin: Option<f64>,
     leverage: Option<u32>,
+    min_volume_24h: Option<f64>,
+    min_atr: Option<f64>,
+    max_atr: Option<f64>,
+    min_candle_body: Option<f64>,
+    min_confidence: Option<f64>,
+    scale_by_confidence: Option<bool>,
+    direction: Option<StrategyDirection>,
+    max_position_duration: Option<u64>,
+    allowed_regimes: Option<Vec<MarketRegime>>,
+    in_sample_klines: usize,
+    out_of_sample_klines: usize,
+    objective: Option<OptimizationObjective>,
     from_ts: String,
     to_ts: String,
 }
-#[post("/run-back-test")]
-async fn run_back_test(
+#[post("/run-walk-forward")]
+async fn run_walk_forward(
     app_data: web::Data<AppState>,
-    body: Json<RunBackTestParams>,
+    scope: TenantScope,
+    body: Json<RunWalkForwardParams>,
 ) -> impl Responder {
+    if let Err(e) = scope.require_operator() {
+        return e.error_response();
+    }
+
     let bot = app_data.bot.clone();
     let settings = StrategySettings {
         max_open_orders: 2,
-        margin_usd: body.margin.unwrap_or_else(|| 1000.0),
-        leverage: body.leverage.unwrap_or_else(|| 10),
+        margin_usd: body.margin.unwrap_or(1000.0),
+        leverage: body.leverage.unwrap_or(10),
         stop_loss: None,
+        take_profit: None,
+        min_volume_24h: body.min_volume_24h,
+        min_atr: body.min_atr,
+        max_atr: body.max_atr,
+        min_candle_body: body.min_candle_body,
+        min_confidence: body.min_confidence,
+        scale_by_confidence: body.scale_by_confidence.unwrap_or(false),
+        direction: body.direction.unwrap_or_default(),
+        max_position_duration: body.max_position_duration,
+        shadow: false,
+        allowed_regimes: body.allowed_regimes.clone(),
+        profile: None,
+        execution: ExecutionStyle::Market,
+        max_correlated_margin: None,
+    };
+    let walk_forward_settings = WalkForwardSettings {
+        in_sample_klines: body.in_sample_klines,
+        out_of_sample_klines: body.out_of_sample_klines,
+        objective: body.objective.unwrap_or_default(),
     };
 
     let from_ts = string_to_timestamp(&body.from_ts);
     let to_ts = string_to_timestamp(&body.to_ts);
     if from_ts.is_err() || to_ts.is_err() {
-        let json_data = json!({ "error": "Unable to parse dates".to_string()});
-        return HttpResponse::ExpectationFailed().json(json_data);
+        return ApiErrorResponse::bad_request("Unable to parse dates").error_response();
     }
 
     // SAFETY: Error check above
@@ -316,14 +983,16 @@ async fn run_back_test(
     let result = bot
         .lock()
         .await
-        .run_back_test(
+        .run_walk_forward(
             &body.strategy_name,
             &body.symbol,
-            &body.interval,
+            body.interval,
             from_ts,
             to_ts,
             settings,
             body.algorithm_params.clone(),
+            &body.param_grid,
+            walk_forward_settings,
         )
         .await;
 
@@ -333,25 +1002,133 @@ async fn run_back_test(
 
             HttpResponse::Ok().json(json_data)
         }
-        Err(e) => {
-            let json_data = json!({ "error": e.to_string()});
-            HttpResponse::ExpectationFailed().json(json_data)
+        Err(e) => ApiErrorResponse::bad_request(e.to_string()).error_response(),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RunOptimizerParams {
+    #[serde(deserialize_with = "canonical_symbol")]
+    symbol: String,
+    strategy_name: String,
+    algorithm_params: Value,
+    param_space: BTreeMap<String, ParamRange>,
+    interval: Interval,
+    margin: Option<f64>,
+    leverage: Option<u32>,
+    min_volume_24h: Option<f64>,
+    min_atr: Option<f64>,
+    max_atr: Option<f64>,
+    min_candle_body: Option<f64>,
+    min_confidence: Option<f64>,
+    scale_by_confidence: Option<bool>,
+    direction: Option<StrategyDirection>,
+    max_position_duration: Option<u64>,
+    allowed_regimes: Option<Vec<MarketRegime>>,
+    #[serde(default)]
+    optimizer: OptimizerSettings,
+    from_ts: String,
+    to_ts: String,
+}
+#[post("/optimize")]
+async fn run_optimizer(
+    app_data: web::Data<AppState>,
+    scope: TenantScope,
+    body: Json<RunOptimizerParams>,
+) -> impl Responder {
+    if let Err(e) = scope.require_operator() {
+        return e.error_response();
+    }
+
+    let bot = app_data.bot.clone();
+    let settings = StrategySettings {
+        max_open_orders: 2,
+        margin_usd: body.margin.unwrap_or(1000.0),
+        leverage: body.leverage.unwrap_or(10),
+        stop_loss: None,
+        take_profit: None,
+        min_volume_24h: body.min_volume_24h,
+        min_atr: body.min_atr,
+        max_atr: body.max_atr,
+        min_candle_body: body.min_candle_body,
+        min_confidence: body.min_confidence,
+        scale_by_confidence: body.scale_by_confidence.unwrap_or(false),
+        direction: body.direction.unwrap_or_default(),
+        max_position_duration: body.max_position_duration,
+        shadow: false,
+        allowed_regimes: body.allowed_regimes.clone(),
+        profile: None,
+        execution: ExecutionStyle::Market,
+        max_correlated_margin: None,
+    };
+
+    let from_ts = string_to_timestamp(&body.from_ts);
+    let to_ts = string_to_timestamp(&body.to_ts);
+    if from_ts.is_err() || to_ts.is_err() {
+        return ApiErrorResponse::bad_request("Unable to parse dates").error_response();
+    }
+
+    // SAFETY: Error check above
+    let from_ts = from_ts.unwrap();
+    let to_ts = to_ts.unwrap();
+
+    let result = bot
+        .lock()
+        .await
+        .run_optimizer(
+            &body.strategy_name,
+            &body.symbol,
+            body.interval,
+            from_ts,
+            to_ts,
+            settings,
+            body.algorithm_params.clone(),
+            &body.param_space,
+            body.optimizer.clone(),
+        )
+        .await;
+
+    match result {
+        Ok(result) => {
+            let json_data = json!({ "result": result });
+
+            HttpResponse::Ok().json(json_data)
         }
+        Err(e) => ApiErrorResponse::bad_request(e.to_string()).error_response(),
     }
 }
 
 pub fn register_strategy_service() -> Scope {
     scope("/strategy")
         .service(new_strategy)
+        .service(new_strategies)
         .service(stop_strategy)
         .service(stop_all_strategies)
         .service(set_strategy_params)
+        .service(strategy_params_history)
+        .service(signal_history)
         .service(change_strategy_settings)
+        .service(update_strategy_settings)
         .service(list_active_strategies)
+        .service(list_strategy_restarts)
+        .service(list_funding_opportunities)
+        .service(list_algorithms)
         .service(strategy_info)
         .service(list_strategy_positions)
         .service(active_strategy_summary)
         .service(list_historical_strategies)
+        .service(historical_strategy_detail)
         .service(historical_strategy_summary)
-        .service(run_back_test)
+        .service(historical_strategy_summaries)
+        .service(historical_strategy_stats)
+        .service(list_back_tests)
+        .service(back_test)
+        .service(back_test_trades)
+        .service(generate_back_test_report)
+        .service(back_test_report)
+        .service(compare_back_tests)
+        .service(run_portfolio_back_test)
+        .service(run_batch_back_test)
+        .service(run_walk_forward)
+        .service(run_optimizer)
 }
@@ -0,0 +1,162 @@
+//! # Tenant Scoping
+//!
+//! While the bot has tenants, every API request is authenticated by a bearer token: the token
+//! of a tenant scopes the request to the tenant, the admin token set in `ADMIN_API_TOKEN` grants
+//! the operator access to everything. Tenants may only call the account, strategy and market
+//! routes, where they see and manage their own account and strategies only.
+//!
+//! Without tenants the API is unchanged and every request is made by the operator.
+
+use std::future::{ready, Ready};
+
+use actix_web::{
+    body::MessageBody,
+    dev::{Payload, ServiceRequest, ServiceResponse},
+    http::header,
+    middleware::Next,
+    web, Error, FromRequest, HttpMessage, HttpRequest,
+};
+
+use crate::account::account::Account;
+use crate::api::error::ApiErrorResponse;
+use crate::app::AppState;
+use crate::bot::RaderBot;
+use crate::market::types::ArcMutex;
+use crate::storage::summary_query::StrategySummaryQuery;
+use crate::strategy::strategy::{StrategyId, StrategySettings};
+use crate::tenant::Tenant;
+
+/// Routes tenants may call, every other route is reserved to the operator.
+const TENANT_SCOPES: [&str; 3] = ["/account", "/strategy", "/market"];
+
+/// Routes checking credentials of their own.
+const UNSCOPED_ROUTES: [&str; 3] = ["/static", "/webhook", "/admin"];
+
+/// Who a request is made by, extracted by handlers to scope what they serve.
+#[derive(Debug, Clone)]
+pub enum TenantScope {
+    /// The operator of the bot, who may see and manage everything.
+    Operator,
+    Tenant(Tenant),
+}
+
+impl FromRequest for TenantScope {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        // the scope is set by `authenticate_tenant`, requests are the operator's without tenants
+        let scope = req.extensions().get::<TenantScope>().cloned();
+        ready(Ok(scope.unwrap_or(TenantScope::Operator)))
+    }
+}
+
+impl TenantScope {
+    /// The account the request trades on: the account of the tenant, or the live account.
+    pub async fn account(&self, app_data: &AppState) -> ArcMutex<Account> {
+        match self {
+            Self::Operator => app_data.get_account().await,
+            Self::Tenant(tenant) => tenant.account.clone(),
+        }
+    }
+
+    /// Refuses tenants access to routes acting on the bot as a whole.
+    pub fn require_operator(&self) -> Result<(), ApiErrorResponse> {
+        match self {
+            Self::Operator => Ok(()),
+            Self::Tenant(tenant) => Err(ApiErrorResponse::forbidden(
+                "The route is reserved to the operator",
+            )
+            .with_detail("tenant", &tenant.name)),
+        }
+    }
+
+    /// Checks whether a strategy with the settings is visible to the request.
+    pub fn allows(&self, settings: &StrategySettings) -> bool {
+        match self {
+            Self::Operator => true,
+            Self::Tenant(tenant) => tenant.owns(settings),
+        }
+    }
+
+    /// Makes the settings of a strategy started or changed by a tenant trade on its account.
+    pub fn scope_settings(&self, settings: &mut StrategySettings) -> Result<(), ApiErrorResponse> {
+        match self {
+            Self::Operator => Ok(()),
+            Self::Tenant(tenant) => tenant
+                .scope_settings(settings)
+                .map_err(ApiErrorResponse::forbidden),
+        }
+    }
+
+    /// Restricts a query of saved strategy summaries to the strategies of the tenant.
+    pub fn scope_summary_query(&self, query: &mut StrategySummaryQuery) {
+        if let Self::Tenant(tenant) = self {
+            query.profile = Some(tenant.profile.clone());
+        }
+    }
+
+    /// Checks the request may see an active or saved strategy. Strategies of other tenants are
+    /// reported as not found, so their ids can't be probed.
+    pub async fn check_strategy(
+        &self,
+        bot: &mut RaderBot,
+        strategy_id: StrategyId,
+    ) -> Result<(), ApiErrorResponse> {
+        let Self::Tenant(tenant) = self else {
+            return Ok(());
+        };
+
+        let settings = match bot.get_strategy_info(strategy_id).await {
+            Some(info) => Some(info.settings),
+            None => bot
+                .get_historical_strategy_summary(strategy_id)
+                .await
+                .map(|summary| summary.info.settings),
+        };
+        match settings {
+            Some(settings) if tenant.owns(&settings) => Ok(()),
+            _ => Err(ApiErrorResponse::not_found("Unable to find strategy")
+                .with_detail("strategy_id", strategy_id)),
+        }
+    }
+}
+
+/// Authenticates requests while the bot has tenants and sets their `TenantScope`, see the
+/// module documentation.
+pub async fn authenticate_tenant(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let Some(app_data) = req.app_data::<web::Data<AppState>>() else {
+        return next.call(req).await;
+    };
+    let path = req.path();
+    if app_data.tenants.is_empty() || UNSCOPED_ROUTES.iter().any(|route| path.starts_with(route)) {
+        return next.call(req).await;
+    }
+
+    let token = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .unwrap_or_default();
+    let scope = if app_data.admin_token.matches(token) {
+        TenantScope::Operator
+    } else if let Some(tenant) = app_data.tenants.authenticate(token) {
+        if !TENANT_SCOPES.iter().any(|route| path.starts_with(route)) {
+            return Err(
+                ApiErrorResponse::forbidden("The route is reserved to the operator")
+                    .with_detail("tenant", &tenant.name)
+                    .into(),
+            );
+        }
+        TenantScope::Tenant(tenant.clone())
+    } else {
+        return Err(ApiErrorResponse::unauthorized("Invalid API token").into());
+    };
+
+    req.extensions_mut().insert(scope);
+    next.call(req).await
+}
@@ -4,21 +4,29 @@ use actix_web::web::Json;
 use actix_web::{
     get,
     web::{self, scope},
-    HttpResponse, Responder, Scope,
+    HttpResponse, Responder, ResponseError, Scope,
 };
 use actix_web::{post, HttpRequest};
+use chrono::FixedOffset;
 use directories::UserDirs;
 use log::info;
 use serde::Deserialize;
 use serde_json::json;
 
+use crate::api::error::ApiErrorResponse;
 use crate::app::AppState;
+use crate::error::StorageError;
+use crate::market::interval::Interval;
 use crate::utils::crypt::sign_hmac;
 use crate::utils::kline::{
     build_kline_filename, build_kline_key, interval_symbol_from_binance_filename,
-    load_binance_klines, save_klines,
+    load_binance_klines, save_klines, SyntheticKlines, SYNTHETIC_SYMBOL_PREFIX,
+};
+use crate::utils::time::{
+    day_start_ts, day_start_ts_in, get_time_difference, next_funding_ts, parse_utc_offset,
+    string_to_timestamp, string_to_timestamp_in, timestamp_to_string, timestamp_to_string_in,
+    FUNDING_INTERVAL_HOURS, HOUR_AS_MILI,
 };
-use crate::utils::time::{calculate_kline_open_time, get_time_difference};
 use crate::utils::time::{generate_ts, year_month_day_to_ts};
 use crate::utils::trade::{build_market_trade_key, load_binance_agg_trades, save_trades};
 
@@ -42,21 +50,20 @@ async fn date_to_timestamp(body: Json<DateToTsParams>) -> impl Responder {
 
     let timestamp = year_month_day_to_ts(year, month, day);
 
-    let json_data = match timestamp {
+    match timestamp {
         Some(timestamp) => {
-            json!({ "timestamp": timestamp })
+            let json_data = json!({ "timestamp": timestamp });
+            HttpResponse::Ok().json(json_data)
         }
-        None => json!({ "error": "Unable to create timestamp" }),
-    };
-
-    HttpResponse::Ok().json(json_data)
+        None => ApiErrorResponse::bad_request("Unable to create timestamp").error_response(),
+    }
 }
 
 #[derive(Debug, Deserialize)]
 struct BootstrapKlinesParams {
     filename: String,
     symbol: String,
-    interval: String,
+    interval: Interval,
 }
 #[post("/bootstrap-historical-klines")]
 async fn load_klines(
@@ -81,11 +88,17 @@ async fn load_klines(
                 .to_string_lossy()
                 .into_owned();
 
-            let (symbol, interval) = interval_symbol_from_binance_filename(&file_name);
+            let (symbol, interval) = match interval_symbol_from_binance_filename(&file_name) {
+                Ok(symbol_interval) => symbol_interval,
+                Err(e) => {
+                    info!("Skipping kline file: {e}");
+                    continue;
+                }
+            };
 
-            let kline_key = build_kline_key(&symbol, &interval);
+            let kline_key = build_kline_key(&symbol, interval);
 
-            let klines = load_binance_klines(entry.path(), &symbol, &interval);
+            let klines = load_binance_klines(entry.path(), &symbol, interval);
 
             if let Err(e) = storage_manager.save_klines(&klines, &kline_key, true).await {
                 info!("Unable to save klines: {e}");
@@ -159,6 +172,42 @@ async fn get_sign_hmac(_app_data: web::Data<AppState>, _req: HttpRequest) -> imp
     HttpResponse::Ok().json(json_data)
 }
 
+#[derive(Debug, Deserialize)]
+struct ConvertTimeParams {
+    /// A timestamp or a date in any format the API takes, e.g. `2024-01-01 08:00`.
+    date: String,
+    /// The UTC offset of the timezone, e.g. `+08:00`, dates without an offset are read in it.
+    tz: Option<String>,
+}
+#[post("/convert-time")]
+async fn convert_time(body: Json<ConvertTimeParams>) -> impl Responder {
+    let offset = match body.tz.as_deref().map(parse_utc_offset) {
+        Some(Some(offset)) => offset,
+        Some(None) => {
+            return ApiErrorResponse::bad_request("Unable to parse timezone")
+                .with_detail("tz", body.tz.as_deref().unwrap_or_default())
+                .error_response()
+        }
+        None => FixedOffset::east_opt(0).unwrap(),
+    };
+    let Ok(ts) = string_to_timestamp_in(&body.date, offset) else {
+        return ApiErrorResponse::bad_request("Unable to parse date")
+            .with_detail("date", &body.date)
+            .error_response();
+    };
+
+    let funding_interval = FUNDING_INTERVAL_HOURS * HOUR_AS_MILI;
+    let json_data = json!({
+        "timestamp": ts,
+        "utc": timestamp_to_string(ts),
+        "local": timestamp_to_string_in(ts, offset),
+        "exchange_day_start": day_start_ts(ts),
+        "local_day_start": day_start_ts_in(ts, offset),
+        "next_funding": next_funding_ts(ts, funding_interval),
+    });
+    HttpResponse::Ok().json(json_data)
+}
+
 #[derive(Debug, Deserialize)]
 struct TimeDifParams {
     from_ts: String,
@@ -169,10 +218,14 @@ async fn time_difference(
     _app_data: web::Data<AppState>,
     body: Json<TimeDifParams>,
 ) -> impl Responder {
-    let difference = get_time_difference(
-        body.from_ts.parse::<u64>().unwrap(),
-        body.to_ts.parse::<u64>().unwrap(),
-    );
+    let (from_ts, to_ts) = match (
+        string_to_timestamp(&body.from_ts),
+        string_to_timestamp(&body.to_ts),
+    ) {
+        (Ok(from_ts), Ok(to_ts)) => (from_ts, to_ts),
+        _ => return ApiErrorResponse::bad_request("Unable to parse timestamps").error_response(),
+    };
+    let difference = get_time_difference(from_ts, to_ts);
 
     // Return the stream data as JSON
     let json_data = json!({ "difference": difference });
@@ -181,30 +234,109 @@ async fn time_difference(
 #[derive(Debug, Deserialize)]
 struct CalculateOpenTimeParams {
     close_time: String,
-    interval: String,
+    interval: Interval,
 }
 #[post("/calculate-open-time")]
 async fn calculate_open_time(
     _app_data: web::Data<AppState>,
     body: Json<CalculateOpenTimeParams>,
 ) -> impl Responder {
-    // let params = web::Query::<CalculateOpenTimeParams>::from_query(req.query_string()).unwrap();
-
-    let open_time =
-        calculate_kline_open_time(body.close_time.parse::<u64>().unwrap(), &body.interval);
+    let Ok(close_time) = body.close_time.parse::<u64>() else {
+        return ApiErrorResponse::bad_request("Unable to parse close time").error_response();
+    };
+    let open_time = body.interval.open_time(close_time);
 
     // Return the stream data as JSON
     let json_data = json!({ "open_time": open_time });
     HttpResponse::Ok().json(json_data)
 }
 
+#[post("/compact-klines")]
+async fn compact_klines(app_data: web::Data<AppState>) -> impl Responder {
+    let storage_manager = app_data.get_storage_manager().await;
+
+    match storage_manager.compact_klines().await {
+        Ok(report) => HttpResponse::Ok().json(report),
+        Err(e) => ApiErrorResponse::from(e)
+            .context("Unable to compact klines")
+            .error_response(),
+    }
+}
+
+/// Largest number of klines a synthetic series is generated with.
+const MAX_SYNTHETIC_KLINES: usize = 100_000;
+
+#[derive(Debug, Deserialize)]
+struct SyntheticKlinesParams {
+    #[serde(flatten)]
+    series: SyntheticKlines,
+    /// Stores the klines under the symbol of the series, so strategies can be back tested on it.
+    /// Only symbols starting with `SYNTH` the exchange doesn't list can be stored.
+    #[serde(default)]
+    save: bool,
+}
+#[post("/synthetic-klines")]
+async fn synthetic_klines(
+    app_data: web::Data<AppState>,
+    body: Json<SyntheticKlinesParams>,
+) -> impl Responder {
+    let series = &body.series;
+    if series.count > MAX_SYNTHETIC_KLINES {
+        return ApiErrorResponse::bad_request(format!(
+            "At most {MAX_SYNTHETIC_KLINES} klines can be generated"
+        ))
+        .error_response();
+    }
+
+    if body.save {
+        if !series.has_synthetic_symbol() {
+            return ApiErrorResponse::bad_request(format!(
+                "Synthetic klines can only be saved under a symbol starting with {SYNTHETIC_SYMBOL_PREFIX}"
+            ))
+            .error_response();
+        }
+
+        let exchange = app_data.get_exchange_api().await;
+        let cache = app_data.get_exchange_cache().await;
+        // an unreachable exchange doesn't block saving, the prefix already reserves the symbol
+        let listed = match cache.lock().await.info(&**exchange).await {
+            Ok(info) => info.symbols.iter().any(|info| info.symbol == series.symbol),
+            Err(_) => false,
+        };
+        if listed {
+            return ApiErrorResponse::bad_request(format!(
+                "{} is listed by the exchange, synthetic klines can't overwrite its klines",
+                series.symbol
+            ))
+            .error_response();
+        }
+    }
+
+    let klines = series.generate();
+    if body.save {
+        let storage_manager = app_data.get_storage_manager().await;
+        let kline_key = build_kline_key(&series.symbol, series.interval);
+        if let Err(e) = storage_manager.save_klines(&klines, &kline_key, true).await {
+            return ApiErrorResponse::from(StorageError::from(e))
+                .context("Unable to save synthetic klines")
+                .error_response();
+        }
+    }
+
+    let json_data = json!({ "klines": klines });
+    HttpResponse::Ok().json(json_data)
+}
+
 pub fn register_utils_service() -> Scope {
     scope("/utils")
         .service(get_ts)
         .service(calculate_open_time)
         .service(time_difference)
+        .service(convert_time)
         .service(load_klines)
         .service(date_to_timestamp)
         .service(get_sign_hmac)
         .service(bootstrap_historical_trades)
+        .service(compact_klines)
+        .service(synthetic_klines)
 }
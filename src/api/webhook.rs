@@ -0,0 +1,93 @@
+use actix_web::{
+    post,
+    web::{self, scope, Json},
+    HttpResponse, Responder, ResponseError, Scope,
+};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::api::error::ApiErrorResponse;
+use crate::app::AppState;
+use crate::market::external::{feed_webhook_secret, is_series_name, ExternalDataPoint};
+use crate::strategy::webhook::TradingViewAlert;
+use crate::utils::crypt::constant_time_eq;
+use crate::utils::env::env_lookup;
+use crate::utils::time::generate_ts;
+
+/// Trades a TradingView alert. TradingView posts alert messages as plain text unless they are
+/// valid JSON, so the body is parsed whatever its content type.
+#[post("/tradingview")]
+async fn tradingview_alert(app_data: web::Data<AppState>, body: web::Bytes) -> impl Responder {
+    let alert: TradingViewAlert = match serde_json::from_slice(&body) {
+        Ok(alert) => alert,
+        Err(e) => {
+            return ApiErrorResponse::bad_request(format!("Invalid alert, e: {e}")).error_response()
+        }
+    };
+
+    let mut bot = app_data.bot.lock().await;
+    let Some(config) = bot.webhook_config() else {
+        return ApiErrorResponse::not_found("TradingView webhook is disabled").error_response();
+    };
+    if !config.is_authorized(&alert) {
+        return ApiErrorResponse::unauthorized("Invalid webhook secret").error_response();
+    }
+
+    match bot.handle_webhook_alert(&alert).await {
+        Ok((signal, action)) => {
+            let json_data = json!({ "signal": signal, "action": action });
+            HttpResponse::Ok().json(json_data)
+        }
+        Err(e) => ApiErrorResponse::from(e).error_response(),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FeedValueParams {
+    secret: String,
+    value: f64,
+    /// The time the value was published in milliseconds, now if not given.
+    timestamp: Option<u64>,
+}
+/// Adds a value to an external series, e.g. a sentiment score computed by another service,
+/// which algorithms read alongside klines.
+#[post("/feeds/{series}")]
+async fn feed_value(
+    app_data: web::Data<AppState>,
+    path: web::Path<String>,
+    body: Json<FeedValueParams>,
+) -> impl Responder {
+    let Some(secret) = feed_webhook_secret(env_lookup) else {
+        return ApiErrorResponse::not_found("Feed webhook is disabled").error_response();
+    };
+    if !constant_time_eq(&body.secret, &secret) {
+        return ApiErrorResponse::unauthorized("Invalid webhook secret").error_response();
+    }
+
+    let series = path.into_inner();
+    if !is_series_name(&series) {
+        return ApiErrorResponse::bad_request("Invalid series name").error_response();
+    }
+    if !body.value.is_finite() {
+        return ApiErrorResponse::bad_request("value must be a finite number").error_response();
+    }
+
+    let point = ExternalDataPoint {
+        timestamp: body.timestamp.unwrap_or_else(generate_ts),
+        value: body.value,
+    };
+    app_data
+        .get_market()
+        .await
+        .insert_external_data(&series, &[point])
+        .await;
+
+    let json_data = json!({ "series": series, "point": point });
+    HttpResponse::Ok().json(json_data)
+}
+
+pub fn register_webhook_service() -> Scope {
+    scope("/webhook")
+        .service(tradingview_alert)
+        .service(feed_value)
+}
@@ -0,0 +1,130 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+use actix::{Actor, ActorContext, AsyncContext, StreamHandler};
+use actix_web::{
+    get,
+    web::{self, scope},
+    Error, HttpRequest, HttpResponse, Scope,
+};
+use actix_web_actors::ws;
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+
+use crate::app::AppState;
+use crate::events::{BotEvent, EventTopic};
+
+/// Interval of pings keeping idle connections open through proxies.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Messages clients send to choose the topics they receive, e.g.
+/// `{"action": "subscribe", "topics": ["signals", "positions"]}`.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+enum ClientMessage {
+    Subscribe { topics: Vec<EventTopic> },
+    Unsubscribe { topics: Vec<EventTopic> },
+}
+
+/// A WebSocket connection pushing the bot events of the subscribed topics as JSON.
+struct PushSession {
+    topics: HashSet<EventTopic>,
+    events: Option<Receiver<BotEvent>>,
+}
+
+impl Actor for PushSession {
+    type Context = ws::WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        if let Some(mut events) = self.events.take() {
+            ctx.add_stream(async_stream::stream! {
+                loop {
+                    match events.recv().await {
+                        Ok(event) => yield event,
+                        // slow clients miss the oldest events rather than blocking the bot
+                        Err(RecvError::Lagged(_)) => continue,
+                        Err(RecvError::Closed) => break,
+                    }
+                }
+            });
+        }
+
+        ctx.run_interval(HEARTBEAT_INTERVAL, |_, ctx| ctx.ping(b""));
+    }
+}
+
+impl StreamHandler<BotEvent> for PushSession {
+    fn handle(&mut self, event: BotEvent, ctx: &mut Self::Context) {
+        if !self.topics.contains(&event.topic()) {
+            return;
+        }
+
+        let json_data = json!({ "topic": event.topic(), "event": event });
+        ctx.text(json_data.to_string());
+    }
+}
+
+impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for PushSession {
+    fn handle(&mut self, message: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
+        match message {
+            Ok(ws::Message::Ping(bytes)) => ctx.pong(&bytes),
+            Ok(ws::Message::Text(text)) => {
+                match serde_json::from_str::<ClientMessage>(&text) {
+                    Ok(ClientMessage::Subscribe { topics }) => self.topics.extend(topics),
+                    Ok(ClientMessage::Unsubscribe { topics }) => {
+                        for topic in topics {
+                            self.topics.remove(&topic);
+                        }
+                    }
+                    Err(e) => {
+                        let json_error = json!({ "error": format!("Invalid message, e: {e}") });
+                        ctx.text(json_error.to_string());
+                        return;
+                    }
+                }
+
+                ctx.text(json!({ "topics": self.topics }).to_string());
+            }
+            Ok(ws::Message::Close(reason)) => {
+                ctx.close(reason);
+                ctx.stop();
+            }
+            Err(_) => ctx.stop(),
+            _ => {}
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct PushParams {
+    /// Topics subscribed on connect, separated by commas, e.g. `signals,positions`.
+    topics: Option<String>,
+}
+
+#[get("")]
+async fn push_events(
+    app_data: web::Data<AppState>,
+    req: HttpRequest,
+    params: web::Query<PushParams>,
+    stream: web::Payload,
+) -> Result<HttpResponse, Error> {
+    let mut topics = HashSet::new();
+    for topic in params.topics.iter().flat_map(|topics| topics.split(',')) {
+        let topic = serde_json::from_value(json!(topic.trim()))
+            .map_err(|e| actix_web::error::ErrorBadRequest(format!("Invalid topic, e: {e}")))?;
+        topics.insert(topic);
+    }
+
+    let events = app_data.bot.lock().await.subscribe_events();
+    let session = PushSession {
+        topics,
+        events: Some(events),
+    };
+
+    ws::start(session, &req, stream)
+}
+
+pub fn register_ws_service() -> Scope {
+    scope("/ws").service(push_events)
+}
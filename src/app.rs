@@ -5,9 +5,14 @@ use actix_web::web::Data;
 use crate::{
     account::account::Account,
     bot::RaderBot,
-    exchange::api::ExchangeApi,
+    config::spawn_config_watcher,
+    error::RaderBotResult,
+    exchange::{api::ExchangeApi, cache::ExchangeCache},
     market::{market::Market, types::ArcMutex},
+    scheduler::spawn_scheduler,
     storage::manager::StorageManager,
+    strategy::supervisor::spawn_supervisor,
+    tenant::{AdminToken, Tenants},
 };
 
 /// Represents the shared state of the application.
@@ -18,6 +23,10 @@ use crate::{
 pub struct AppState {
     /// A thread-safe, mutable reference to the `RaderBot` instance.
     pub bot: ArcMutex<RaderBot>,
+    /// The tenants requests are scoped to, read once at startup.
+    pub tenants: Tenants,
+    /// The token of the admin routes, shared with the bot which replaces it on config reloads.
+    pub admin_token: AdminToken,
 }
 
 impl AppState {
@@ -40,8 +49,8 @@ impl AppState {
     ///
     /// # Returns
     ///
-    /// An `ArcMutex<Market>` allowing safe, concurrent access to market data.
-    pub async fn get_market(&self) -> ArcMutex<Market> {
+    /// An `Arc<Market>` allowing safe, concurrent access to market data.
+    pub async fn get_market(&self) -> Arc<Market> {
         self.bot.lock().await.market.clone()
     }
 
@@ -61,19 +70,34 @@ impl AppState {
     pub async fn get_exchange_api(&self) -> Arc<Box<dyn ExchangeApi>> {
         self.bot.lock().await.exchange_api.clone()
     }
+
+    /// Retrieves the cache of exchange info and balance fetched through the `ExchangeApi`.
+    pub async fn get_exchange_cache(&self) -> ArcMutex<ExchangeCache> {
+        self.bot.lock().await.exchange_cache.clone()
+    }
 }
 
 /// Creates and initializes a new application state.
 ///
-/// This function constructs a new `RaderBot` instance and wraps it in a shared state object,
-/// making it accessible throughout the application.
+/// This function constructs a new `RaderBot` instance, starts running its scheduled jobs and wraps
+/// it in a shared state object, making it accessible throughout the application.
 ///
 /// # Returns
 ///
 /// A `Data<AppState>` wrapper around the initialized application state, ready for integration
-/// into an Actix web application.
-pub async fn new_app_state() -> Data<AppState> {
-    let bot = ArcMutex::new(RaderBot::new().await);
+/// into an Actix web application, or an error if the bot can't be created.
+pub async fn new_app_state() -> RaderBotResult<Data<AppState>> {
+    let bot = RaderBot::new().await?;
+    let tenants = bot.tenants().clone();
+    let admin_token = bot.admin_token().clone();
+    let bot = ArcMutex::new(bot);
+    spawn_scheduler(bot.clone());
+    spawn_config_watcher(bot.clone());
+    spawn_supervisor(bot.clone());
 
-    Data::new(AppState { bot })
+    Ok(Data::new(AppState {
+        bot,
+        tenants,
+        admin_token,
+    }))
 }
@@ -0,0 +1,249 @@
+//! # Audit Log
+//!
+//! An append-only record of every consequential action of the bot: handled signals, filled
+//! orders, opened and closed positions, started and stopped strategies, changed settings and
+//! params, errors and manual interventions through the admin API. Entries are built from the
+//! events published on the event bus, written to storage in the order they were published and
+//! never changed afterwards, so the log tells what the bot did and why after the fact.
+//!
+//! Entries are recorded by the `EventPublisher` itself rather than by a subscriber, so none is
+//! lost when subscribers lag behind. Storage backends without an audit log, such as MongoDB and
+//! InfluxDB, write it to the `audit_log.jsonl` file of the default `FsStorage` instead.
+
+use std::sync::Arc;
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::mpsc::{self, UnboundedSender};
+use uuid::Uuid;
+
+use crate::error::{StorageError, StorageResult};
+use crate::events::BotEvent;
+use crate::storage::{fs::FsStorage, manager::StorageManager};
+use crate::utils::time::generate_ts;
+
+/// An action of the bot, see the module documentation.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AuditEntry {
+    pub id: Uuid,
+    /// When the action was published, in milliseconds.
+    pub timestamp: u64,
+    /// The type of the event, e.g. `order_filled` or `manual_action`.
+    pub action: String,
+    /// What the action applies to: the account, strategy id or source of an error.
+    pub subject: Option<String>,
+    /// The data of the event.
+    pub data: Value,
+}
+
+impl AuditEntry {
+    /// Creates the entry of an event, `None` for events that aren't actions, such as equity
+    /// samples, stream changes and summaries.
+    pub fn from_event(event: &BotEvent, timestamp: u64) -> Option<Self> {
+        let subject = match event {
+            BotEvent::Signal(record) => Some(record.signal.strategy_id.to_string()),
+            BotEvent::PositionOpened { account, .. }
+            | BotEvent::PositionClosed { account, .. }
+            | BotEvent::OrderFilled { account, .. } => Some(account.clone()),
+            BotEvent::StrategyStarted(info) | BotEvent::StrategyStopped(info) => {
+                Some(info.id.to_string())
+            }
+            BotEvent::SettingsChanged { strategy_id, .. } => Some(strategy_id.to_string()),
+            BotEvent::ParamsChanged(change) => Some(change.strategy_id.to_string()),
+            BotEvent::Error { source, .. } => Some(source.clone()),
+            BotEvent::ManualAction { action, .. } => Some(action.clone()),
+            BotEvent::Equity { .. }
+            | BotEvent::StreamOpened(_)
+            | BotEvent::StreamClosed(_)
+            | BotEvent::StreamError { .. }
+            | BotEvent::DailySummary { .. }
+            | BotEvent::DailyReport(_) => return None,
+        };
+
+        let mut json = serde_json::to_value(event).ok()?;
+        let action = json["type"].as_str()?.to_string();
+
+        Some(Self {
+            id: Uuid::new_v4(),
+            timestamp,
+            action,
+            subject,
+            data: json["data"].take(),
+        })
+    }
+}
+
+/// Filters the audit log by time, action and subject.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct AuditQuery {
+    pub from_ts: Option<u64>,
+    pub to_ts: Option<u64>,
+    pub action: Option<String>,
+    pub subject: Option<String>,
+    /// Returns only the latest entries.
+    pub limit: Option<usize>,
+}
+
+impl AuditQuery {
+    /// Checks whether an entry matches the time range, action and subject of the query.
+    pub fn matches(&self, entry: &AuditEntry) -> bool {
+        self.from_ts
+            .is_none_or(|from_ts| entry.timestamp >= from_ts)
+            && self.to_ts.is_none_or(|to_ts| entry.timestamp <= to_ts)
+            && self
+                .action
+                .as_ref()
+                .is_none_or(|action| &entry.action == action)
+            && self
+                .subject
+                .as_ref()
+                .is_none_or(|subject| entry.subject.as_ref() == Some(subject))
+    }
+
+    /// Keeps the entries matching the query, sorted by time and cut to the latest `limit`
+    /// entries.
+    pub fn apply(&self, entries: Vec<AuditEntry>) -> Vec<AuditEntry> {
+        let mut entries: Vec<AuditEntry> = entries
+            .into_iter()
+            .filter(|entry| self.matches(entry))
+            .collect();
+        entries.sort_by_key(|entry| entry.timestamp);
+
+        if let Some(limit) = self.limit {
+            let skip = entries.len().saturating_sub(limit);
+            entries.drain(..skip);
+        }
+        entries
+    }
+}
+
+/// Writes the entries of published events to storage. Clones write to the same log.
+#[derive(Debug, Clone)]
+pub struct AuditLog {
+    sender: UnboundedSender<AuditEntry>,
+}
+
+impl AuditLog {
+    /// Spawns the task appending entries to storage one at a time, in the order they were
+    /// recorded. The task ends once every clone of the log is dropped.
+    pub fn spawn(storage_manager: Arc<Box<dyn StorageManager>>) -> Self {
+        let (sender, mut receiver) = mpsc::unbounded_channel::<AuditEntry>();
+
+        tokio::spawn(async move {
+            let mut fallback: Option<FsStorage> = None;
+            while let Some(entry) = receiver.recv().await {
+                let result = match &fallback {
+                    Some(fallback) => fallback.append_audit_entry(entry.clone()).await,
+                    None => match storage_manager.append_audit_entry(entry.clone()).await {
+                        Err(StorageError::Unsupported(backend)) => {
+                            warn!("{backend} has no audit log, appending to the file system");
+                            let fallback = fallback.insert(FsStorage::default());
+                            fallback.append_audit_entry(entry.clone()).await
+                        }
+                        result => result,
+                    },
+                };
+                if let Err(e) = result {
+                    warn!(
+                        "Unable to append {} audit entry {}, e: {e}",
+                        entry.action, entry.id
+                    );
+                }
+            }
+        });
+
+        Self { sender }
+    }
+
+    /// Lists the entries matching a query, oldest first, from the file system on storage
+    /// backends without an audit log.
+    ///
+    /// # Arguments
+    ///
+    /// * `storage_manager` - The storage the log is written to.
+    /// * `query` - The filters of the entries.
+    ///
+    /// # Returns
+    ///
+    /// The matching entries, or an error if the log can't be read.
+    pub async fn list(
+        storage_manager: &Arc<Box<dyn StorageManager>>,
+        query: AuditQuery,
+    ) -> StorageResult<Vec<AuditEntry>> {
+        match storage_manager.list_audit_entries(query.clone()).await {
+            Err(StorageError::Unsupported(_)) => {
+                FsStorage::default().list_audit_entries(query).await
+            }
+            result => result,
+        }
+    }
+
+    /// Records an event if it is an action.
+    pub fn record(&self, event: &BotEvent) {
+        if let Some(entry) = AuditEntry::from_event(event, generate_ts()) {
+            let _ = self.sender.send(entry);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    use crate::strategy::strategy::StrategyInfo;
+
+    /// Tests actions become entries tagged by event type and subject, which queries filter on.
+    #[test]
+    fn test_audit_entries() {
+        let info = StrategyInfo::default();
+        let started =
+            AuditEntry::from_event(&BotEvent::StrategyStarted(info.clone()), 1_000).unwrap();
+        assert_eq!(started.action, "strategy_started");
+        assert_eq!(started.subject, Some(info.id.to_string()));
+        assert_eq!(started.data["id"], info.id.to_string());
+
+        let flatten = BotEvent::ManualAction {
+            action: "flatten_all".to_string(),
+            detail: json!({ "closed_positions": 2 }),
+        };
+        let flatten = AuditEntry::from_event(&flatten, 3_000).unwrap();
+        assert_eq!(flatten.action, "manual_action");
+        assert_eq!(flatten.data["detail"]["closed_positions"], 2);
+
+        let error = BotEvent::Error {
+            source: "live".to_string(),
+            message: "Order rejected".to_string(),
+        };
+        let error = AuditEntry::from_event(&error, 2_000).unwrap();
+        let stream_error = BotEvent::StreamError {
+            stream_id: "BTCUSDT@kline_1m".to_string(),
+            message: "Connection reset".to_string(),
+        };
+        assert!(AuditEntry::from_event(&stream_error, 2_000).is_none());
+
+        let entries = vec![flatten.clone(), started.clone(), error.clone()];
+        let all = AuditQuery::default().apply(entries.clone());
+        assert_eq!(all, vec![started.clone(), error.clone(), flatten.clone()]);
+
+        let query = AuditQuery {
+            subject: Some("live".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(query.apply(entries.clone()), vec![error.clone()]);
+
+        let query = AuditQuery {
+            from_ts: Some(1_500),
+            limit: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(query.apply(entries.clone()), vec![flatten.clone()]);
+
+        let query = AuditQuery {
+            action: Some("strategy_stopped".to_string()),
+            ..Default::default()
+        };
+        assert!(query.apply(entries).is_empty());
+    }
+}
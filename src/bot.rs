@@ -1,62 +1,241 @@
-use dotenv_codegen::dotenv;
+use log::{info, warn};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
 
-use log::info;
-use serde_json::Value;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    sync::Arc,
+    time::Duration,
+};
 
-use std::{collections::HashMap, sync::Arc};
+use tokio::sync::broadcast::Receiver;
 
+#[cfg(feature = "plugins")]
+use crate::algorithm::plugin::load_plugins_from_dir;
+#[cfg(feature = "binance")]
+use crate::exchange::binance::BinanceApi;
+#[cfg(feature = "mongo")]
+use crate::storage::mongo::MongoDbStorage;
+#[cfg(feature = "object-store")]
+use crate::storage::object_store::ObjectStoreStorage;
+#[cfg(feature = "postgres")]
+use crate::storage::postgres::PostgresStorage;
+#[cfg(feature = "influx")]
+use crate::storage::{influx::InfluxStorage, mirror::MirroredStorage};
 use crate::{
-    account::account::Account,
-    exchange::{api::ExchangeApi, binance::BinanceApi, bingx::BingXApi, mock::MockExchangeApi},
+    account::{
+        account::Account,
+        daily_report::{DailyReport, NotableEvents, StrategyActivity},
+        router::{AccountRouter, ProfileAccount},
+        trade::Position,
+    },
+    audit::{AuditEntry, AuditLog, AuditQuery},
+    config::{reload_log_filter, ConfigFile, ConfigReloadReport},
+    error::{RaderBotError, RaderBotResult, StorageError, StrategyError, StrategyResult},
+    events::{BotEvent, EventPublisher},
+    exchange::{
+        api::ExchangeApi, cache::ExchangeCache, mock::MockExchangeApi, profile::CredentialProfile,
+        types::StreamType,
+    },
+    health::{self, timed_lock, timed_read},
     market::{
+        interval::Interval,
+        kline::Kline,
         market::Market,
         messages::MarketMessage,
-        types::{ArcMutex, ArcReceiver, ArcSender},
+        types::{ArcMutex, ArcReceiver, ArcRwLock, ArcSender},
+    },
+    notify::{
+        discord::{DiscordChannel, DiscordNotifier},
+        webhook::{WebhookEndpoint, WebhookNotifier},
+    },
+    scheduler::{JobKind, JobRun, JobScheduler, ScheduledJob, ScheduledJobInfo},
+    secrets::{provider_from_env, read_secret, EnvSecrets},
+    shutdown::{FlattenReport, FlattenedPosition, ShutdownPolicy, ShutdownReport},
+    snapshot::{
+        AccountSnapshot, BotSnapshot, MarketSnapshot, SignalSnapshot, SnapshotInfo,
+        SnapshotRestoreReport, SnapshotStore,
     },
     storage::{
-        fs::FsStorage, influx::InfluxStorage, manager::StorageManager, mongo::MongoDbStorage,
+        backup::BackupInfo,
+        compression::FileCompression,
+        fs::{FsStorage, KlineFileFormat},
+        manager::StorageManager,
+        retention::RetentionPolicy,
+        summary_query::{
+            summarize_by_algorithm, AlgorithmStats, StrategySummaryItem, StrategySummaryQuery,
+        },
     },
     strategy::{
-        backer::BackTest,
-        signal::SignalManager,
-        strategy::{Strategy, StrategyId, StrategyInfo, StrategySettings, StrategySummary},
+        backer::{
+            BackTest, BackTestMode, BackTestOptions, BackTestRecord, BackTestRunner, BatchBackTest,
+        },
+        comparison::{compare_back_tests, BackTestComparison},
+        funding::{
+            FundingArbitrageAction, FundingArbitrageSettings, FundingArbitrageTracker,
+            FundingOpportunity, FUNDING_ARBITRAGE_ID,
+        },
+        jobs::{BackTestJobInfo, BackTestJobManager, JobId},
+        optimizer::{GeneticOptimizer, OptimizationResult, OptimizerSettings, ParamRange},
+        portfolio::{PortfolioBackTest, PortfolioStrategy, PortfolioSummary},
+        report::{BackTestReport, ReportFormat},
+        signal::{SignalAction, SignalHistoryQuery, SignalManager, SignalRecord},
+        strategy::{
+            Strategy, StrategyDefinition, StrategyId, StrategyInfo, StrategyParamsChange,
+            StrategySettings, StrategySettingsUpdate, StrategySummary,
+        },
+        supervisor::{
+            panic_message, RestartDecision, RestartPolicy, StrategyRestarts, StrategySupervisor,
+        },
         types::{AlgorithmError, SignalMessage},
+        walk_forward::{WalkForward, WalkForwardResult, WalkForwardSettings},
+        webhook::{TradingViewAlert, WebhookConfig, WEBHOOK_STRATEGY_ID},
+    },
+    tenant::{AdminToken, Tenants},
+    utils::{
+        channel::{
+            build_arc_channel, channel_capacity, OverflowPolicy, DEFAULT_MARKET_CHANNEL_CAPACITY,
+            DEFAULT_SIGNAL_CHANNEL_CAPACITY,
+        },
+        env::env_lookup,
+        pool::run_parallel,
+        time::{generate_ts, DAY_AS_MILI},
     },
-    utils::channel::build_arc_channel,
 };
 
 use tokio::task::JoinHandle;
 
+/// Interval in seconds between checks for positions exceeding their max position duration.
+const POSITION_MONITOR_INTERVAL_SECS: u64 = 10;
+
+/// Interval in seconds between samples of account equity written to the time-series sink.
+const EQUITY_SAMPLE_INTERVAL_SECS: u64 = 60;
+
+/// Interval in seconds between polls of the funding rates of the traded symbols.
+const FUNDING_MONITOR_INTERVAL_SECS: u64 = 300;
+
+/// Connection pool size of the Postgres storage when `POSTGRES_MAX_CONNECTIONS` isn't set.
+#[cfg(feature = "postgres")]
+const DEFAULT_POSTGRES_MAX_CONNECTIONS: u32 = 10;
+
 pub struct RaderBot {
-    pub market: ArcMutex<Market>,
+    pub market: Arc<Market>,
     pub account: ArcMutex<Account>,
-    strategy_manager: ArcMutex<StrategyManager>,
+    pub shadow_account: ArcMutex<Account>,
+    /// Accounts of the credential profiles by profile name.
+    profile_accounts: BTreeMap<String, ArcMutex<Account>>,
+    profiles: Vec<CredentialProfile>,
+    /// Isolated users trading on credential profiles of their own, see `tenant::Tenants`.
+    tenants: Tenants,
+    admin_token: AdminToken,
+    strategy_manager: ArcRwLock<StrategyManager>,
     pub exchange_api: Arc<Box<dyn ExchangeApi>>,
+    /// Exchange info and balance served to the API without hitting the exchange on every call.
+    pub exchange_cache: ArcMutex<ExchangeCache>,
     pub storage_manager: Arc<Box<dyn StorageManager>>,
     strategy_tx: ArcSender<SignalMessage>,
     strategy_rx: ArcReceiver<SignalMessage>,
+    back_test_jobs: ArcMutex<BackTestJobManager>,
+    #[cfg(feature = "influx")]
+    metrics_sink: Option<Arc<InfluxStorage>>,
+    events: EventPublisher,
+    /// Set while TradingView alerts are accepted, see `handle_webhook_alert`.
+    webhook: Option<WebhookConfig>,
+    /// The report of the last shutdown, `None` while the bot runs.
+    shutdown_report: Option<ShutdownReport>,
+    /// Jobs run at fixed times, see `scheduler::spawn_scheduler`.
+    scheduler: JobScheduler,
+    /// Checkpoints of the bot state, see `snapshot::BotSnapshot`.
+    snapshots: SnapshotStore,
+    /// Restarts strategies whose task failed, see `supervise_strategies`.
+    supervisor: StrategySupervisor,
+    /// The configuration last applied, see `reload_config`.
+    config_file: ConfigFile,
+    /// Tasks posting events to Discord and webhooks, respawned when their settings change.
+    notifiers: Vec<JoinHandle<()>>,
+    /// Notable events of the last day for the daily report.
+    notable_events: NotableEvents,
+    /// Funding-rate arbitrage opportunities of the traded symbols.
+    funding_arbitrage: ArcMutex<FundingArbitrageTracker>,
 }
 
 impl RaderBot {
-    pub async fn new() -> Self {
+    /// Creates the bot from the environment, restoring the account state and resuming the
+    /// active strategies.
+    ///
+    /// # Returns
+    ///
+    /// The bot, or an error if its configuration can't be run, e.g. trading live on a storage
+    /// that can't persist positions.
+    pub async fn new() -> RaderBotResult<Self> {
+        // keys and credentials are read at runtime, never compiled into the binary
+        let secrets = provider_from_env().unwrap_or_else(|e| {
+            warn!(
+                "Unable to create secrets provider, reading secrets from the environment, e: {e}"
+            );
+            Box::new(EnvSecrets)
+        });
+        info!("Reading secrets from the {} provider", secrets.name());
+
         // create new Arc of exchange API
-        let api_key = dotenv!("BINGX_API_KEY");
-        let secret_key = dotenv!("BINGX_SECRET_KEY");
-        let dry_run = dotenv!("DRY_RUN");
-        let mongo_uri = dotenv!("MONGO_URI");
-        let influx_uri = dotenv!("INFLUX_DB_HOST");
-        let influx_token = dotenv!("INFLUX_TOKEN");
-        let storage_type = dotenv!("STORAGE_TYPE");
+        #[cfg(feature = "binance")]
+        let api_key = read_secret(secrets.as_ref(), "BINGX_API_KEY").await;
+        #[cfg(feature = "binance")]
+        let secret_key = read_secret(secrets.as_ref(), "BINGX_SECRET_KEY").await;
+        // orders are only sent to the exchange when dry run is turned off explicitly
+        let dry_run = env_lookup("DRY_RUN").unwrap_or_else(|| "True".to_string());
+        #[cfg(feature = "mongo")]
+        let mongo_uri = read_secret(secrets.as_ref(), "MONGO_URI").await;
+        #[cfg(feature = "influx")]
+        let influx_uri = env_lookup("INFLUX_DB_HOST").unwrap_or_default();
+        #[cfg(feature = "influx")]
+        let influx_token = read_secret(secrets.as_ref(), "INFLUX_TOKEN").await;
+        let storage_type = env_lookup("STORAGE_TYPE").unwrap_or_default();
+        #[cfg(feature = "influx")]
+        let influx_org = env_lookup("INFLUX_ORG").unwrap_or_else(|| "raderbot".to_string());
+        #[cfg(feature = "influx")]
+        let influx_bucket = env_lookup("INFLUX_BUCKET").unwrap_or_else(|| "trade_data".to_string());
+
+        // load algorithm plugins before any strategy is resumed
+        #[cfg(feature = "plugins")]
+        if let Some(plugin_dir) = env_lookup("ALGORITHM_PLUGIN_DIR") {
+            load_plugins_from_dir(std::path::Path::new(&plugin_dir));
+        }
+        #[cfg(not(feature = "plugins"))]
+        if env_lookup("ALGORITHM_PLUGIN_DIR").is_some() {
+            warn!("Built without the plugins feature, ALGORITHM_PLUGIN_DIR is ignored");
+        }
 
         // create new channel for stream handler and market to communicate
-        let (market_tx, market_rx) = build_arc_channel::<MarketMessage>();
+        // market data is superseded by the next update, so a full channel drops the oldest
+        let (market_tx, market_rx) = build_arc_channel::<MarketMessage>(
+            channel_capacity(
+                env_lookup,
+                "MARKET_CHANNEL_CAPACITY",
+                DEFAULT_MARKET_CHANNEL_CAPACITY,
+            ),
+            OverflowPolicy::DropOldest,
+        );
 
+        #[cfg(feature = "binance")]
         let exchange_api: Arc<Box<dyn ExchangeApi>> = Arc::new(Box::new(BinanceApi::new(
-            api_key,
-            secret_key,
+            &api_key,
+            &secret_key,
             market_tx.clone(),
             false,
         )));
+        // a live account would trade on mock fills, so only dry runs start without an exchange
+        #[cfg(not(feature = "binance"))]
+        let exchange_api: Arc<Box<dyn ExchangeApi>> = {
+            if dry_run != "True" {
+                return Err(RaderBotError::Config(
+                    "Unable to trade live without an exchange adapter, build with the binance feature"
+                        .to_string(),
+                ));
+            }
+            warn!("Built without the binance feature, orders are filled by the mock exchange");
+            Arc::new(Box::new(MockExchangeApi {}))
+        };
         // let exchange_api: Arc<Box<dyn ExchangeApi>> = Arc::new(Box::new(BingXApi::new(
         //     api_key,
         //     secret_key,
@@ -65,12 +244,15 @@ impl RaderBot {
 
         // create new storage manager
 
-        let storage_manager: Arc<Box<dyn StorageManager>> = match storage_type {
+        let storage_manager: Arc<Box<dyn StorageManager>> = match storage_type.as_str() {
+            #[cfg(feature = "influx")]
             "INFLUX" => {
                 info!("Using InfluxStorage as storage backend");
                 let manager: Arc<Box<dyn StorageManager>> =
-                    match InfluxStorage::new(influx_uri, influx_token).await {
-                        Ok(manager) => Arc::new(Box::new(manager)),
+                    match InfluxStorage::new(&influx_uri, &influx_token).await {
+                        Ok(manager) => {
+                            Arc::new(Box::new(manager.with_bucket(&influx_org, &influx_bucket)))
+                        }
                         Err(e) => {
                             info!("There was an error instantiating InfluxDB: {e}");
                             Arc::new(Box::new(FsStorage::default()))
@@ -78,10 +260,11 @@ impl RaderBot {
                     };
                 manager
             }
+            #[cfg(feature = "mongo")]
             "MONGO" => {
                 info!("Using MongoDbStorage as storage backend");
                 let manager: Arc<Box<dyn StorageManager>> =
-                    match MongoDbStorage::new(mongo_uri).await {
+                    match MongoDbStorage::new(&mongo_uri).await {
                         Ok(manager) => Arc::new(Box::new(manager)),
                         Err(e) => {
                             info!("There was an error instantiating MongoDB: {e}");
@@ -90,23 +273,124 @@ impl RaderBot {
                     };
                 manager
             }
+            #[cfg(feature = "postgres")]
+            "POSTGRES" => {
+                info!("Using PostgresStorage as storage backend");
+                // read at runtime, so .env files without Postgres settings still compile
+                let postgres_uri = env_lookup("POSTGRES_URI").unwrap_or_default();
+                let max_connections = env_lookup("POSTGRES_MAX_CONNECTIONS")
+                    .and_then(|max_connections| max_connections.parse().ok())
+                    .unwrap_or(DEFAULT_POSTGRES_MAX_CONNECTIONS);
+
+                let manager: Arc<Box<dyn StorageManager>> =
+                    match PostgresStorage::new(&postgres_uri, max_connections).await {
+                        Ok(manager) => Arc::new(Box::new(manager)),
+                        Err(e) => {
+                            info!("There was an error instantiating PostgreSQL: {e}");
+                            Arc::new(Box::new(FsStorage::default()))
+                        }
+                    };
+                manager
+            }
+            #[cfg(feature = "object-store")]
+            "OBJECT_STORE" => {
+                info!("Using ObjectStoreStorage as storage backend");
+                let object_store_url = env_lookup("OBJECT_STORE_URL").unwrap_or_default();
+
+                let manager: Arc<Box<dyn StorageManager>> =
+                    match ObjectStoreStorage::new(&object_store_url) {
+                        Ok(manager) => Arc::new(Box::new(manager)),
+                        Err(e) => {
+                            info!("There was an error instantiating the object store: {e}");
+                            Arc::new(Box::new(FsStorage::default()))
+                        }
+                    };
+                manager
+            }
             _ => {
+                if !matches!(storage_type.as_str(), "FS" | "") {
+                    warn!("Storage {storage_type} isn't enabled in this build, see the cargo features");
+                }
                 info!("Using FsStorage as storage backend");
+                let kline_format = match env_lookup("KLINE_FILE_FORMAT").as_deref() {
+                    #[cfg(feature = "parquet")]
+                    Some("PARQUET") => KlineFileFormat::Parquet,
+                    #[cfg(not(feature = "parquet"))]
+                    Some("PARQUET") => {
+                        warn!("Built without the parquet feature, k-lines are stored as CSV");
+                        KlineFileFormat::Csv
+                    }
+                    _ => KlineFileFormat::Csv,
+                };
+                let kline_compression = match env_lookup("KLINE_COMPRESSION").as_deref() {
+                    Some("ZSTD") => FileCompression::Zstd,
+                    Some("GZIP") => FileCompression::Gzip,
+                    _ => FileCompression::None,
+                };
 
-                Arc::new(Box::new(FsStorage::default()))
+                let mut storage = FsStorage::default()
+                    .with_kline_format(kline_format)
+                    .with_kline_compression(kline_compression);
+
+                // batch streamed klines in memory, writing them every flush interval
+                if let Some(secs) = env_lookup("KLINE_FLUSH_INTERVAL_SECS")
+                    .and_then(|secs| secs.parse::<u64>().ok())
+                    .filter(|secs| *secs > 0)
+                {
+                    storage = storage.with_kline_write_buffer();
+                    storage.start_kline_flush(Duration::from_secs(secs));
+                }
+
+                // compress CSV klines once they are no longer written to
+                if let Some(days) = env_lookup("KLINE_COMPRESS_AFTER_DAYS")
+                    .and_then(|days| days.parse::<u64>().ok())
+                {
+                    storage.start_kline_compression(Duration::from_millis(days * DAY_AS_MILI));
+                }
+
+                Arc::new(Box::new(storage))
+            }
+        };
+
+        // mirror market data and account equity into a time-series database for dashboards
+        #[cfg(feature = "influx")]
+        let metrics_sink = match env_lookup("TSDB_SINK").as_deref() {
+            Some("INFLUX") => match InfluxStorage::new(&influx_uri, &influx_token).await {
+                Ok(sink) => Some(Arc::new(sink.with_bucket(&influx_org, &influx_bucket))),
+                Err(e) => {
+                    info!("There was an error instantiating the InfluxDB sink: {e}");
+                    None
+                }
+            },
+            _ => None,
+        };
+        #[cfg(feature = "influx")]
+        let storage_manager: Arc<Box<dyn StorageManager>> = match &metrics_sink {
+            // market data of InfluxStorage is already in InfluxDB
+            Some(sink) if storage_type != "INFLUX" => {
+                info!("Mirroring market data to InfluxDB");
+                Arc::new(Box::new(MirroredStorage::new(
+                    storage_manager,
+                    sink.clone(),
+                )))
             }
+            _ => storage_manager,
         };
 
+        let events = EventPublisher::with_audit_log(AuditLog::spawn(storage_manager.clone()));
+        let notable_events = NotableEvents::spawn(events.subscribe());
+
         // create new market to hold market data
         let market = Market::new(
             market_rx.clone(),
             exchange_api.clone(),
             storage_manager.clone(),
+            events.clone(),
             true,
         )
         .await;
 
-        let market = ArcMutex::new(market);
+        let market = Arc::new(market);
 
         // Account can use different API from market exchange API
         // that is to allow for retrieving market data from separate source
@@ -120,41 +404,172 @@ impl RaderBot {
             (exchange_api.clone(), false)
         };
 
-        let account = Account::new(account_exchange_api, true, dry_run).await;
+        let mut account = Account::new(account_exchange_api, true, dry_run).await;
+        // restore positions, trades and orders from before a restart
+        if let Err(e) = account
+            .set_storage_manager(storage_manager.clone(), "live")
+            .await
+        {
+            refuse_live_trading(&e, dry_run)?;
+            info!("Unable to restore account history, e: {e}");
+        }
+        account.set_event_publisher(events.clone());
 
         let account = ArcMutex::new(account);
 
-        let (strategy_tx, strategy_rx) = build_arc_channel::<SignalMessage>();
+        // shadow strategies always trade on a mock account, regardless of dry run
+        let shadow_exchange_api: Arc<Box<dyn ExchangeApi>> = Arc::new(Box::new(MockExchangeApi {}));
+        let mut shadow_account = Account::new(shadow_exchange_api, true, true).await;
+        if let Err(e) = shadow_account
+            .set_storage_manager(storage_manager.clone(), "shadow")
+            .await
+        {
+            info!("Unable to restore shadow account history, e: {e}");
+        }
+        shadow_account.set_event_publisher(events.clone());
+        let shadow_account = ArcMutex::new(shadow_account);
+
+        // every credential profile trades on an account of its own, stored under its name
+        let profiles = CredentialProfile::from_env(secrets.as_ref())
+            .await
+            .unwrap_or_else(|e| {
+                info!("Unable to read credential profiles, e: {e}");
+                vec![]
+            });
+        let mut profile_accounts = BTreeMap::new();
+        for profile in &profiles {
+            let api = profile.build_api(market_tx.clone());
+            let mut profile_account = Account::new(api, true, profile.dry_run).await;
+            if let Err(e) = profile_account
+                .set_storage_manager(storage_manager.clone(), &profile.name)
+                .await
+            {
+                refuse_live_trading(&e, profile.dry_run)?;
+                info!("Unable to restore {} account history, e: {e}", profile.name);
+            }
+            profile_account.set_event_publisher(events.clone());
+            profile_accounts.insert(profile.name.clone(), ArcMutex::new(profile_account));
+        }
+
+        // without its tenants the API wouldn't be scoped, so the bot doesn't start without them
+        let tenants = Tenants::from_env(secrets.as_ref(), &profile_accounts)
+            .await
+            .map_err(|e| RaderBotError::Config(format!("Unable to read tenants, e: {e}")))?;
+        if !tenants.is_empty() {
+            info!("Serving {} tenants", tenants.list().len());
+        }
+
+        // signals must not be lost, so strategies wait for room in a full channel
+        let (strategy_tx, strategy_rx) = build_arc_channel::<SignalMessage>(
+            channel_capacity(
+                env_lookup,
+                "SIGNAL_CHANNEL_CAPACITY",
+                DEFAULT_SIGNAL_CHANNEL_CAPACITY,
+            ),
+            OverflowPolicy::Block,
+        );
+
+        let mut strategy_manager = StrategyManager::new();
+        // record every live signal with the action taken on it
+        strategy_manager
+            .get_signal_manager_mut()
+            .set_storage_manager(storage_manager.clone());
+        strategy_manager
+            .get_signal_manager_mut()
+            .set_event_publisher(events.clone());
 
-        let strategy_manager = StrategyManager::new();
+        // alerts trade as a pseudo-strategy which stays active while the webhook is enabled
+        let webhook = match WebhookConfig::from_env() {
+            Ok(webhook) => webhook,
+            Err(e) => {
+                info!("TradingView webhook disabled, e: {e}");
+                None
+            }
+        };
+        if let Some(webhook) = &webhook {
+            strategy_manager
+                .get_signal_manager_mut()
+                .add_strategy_settings(&WEBHOOK_STRATEGY_ID, webhook.settings.clone());
+        }
+
+        let scheduler = JobScheduler::from_env().unwrap_or_else(|e| {
+            info!("Unable to read scheduled jobs, e: {e}");
+            JobScheduler::default()
+        });
 
         let mut _self = Self {
             market,
             account,
+            shadow_account,
+            profile_accounts,
+            profiles,
+            tenants,
+            admin_token: AdminToken::from_lookup(env_lookup),
             exchange_api: exchange_api.clone(),
-            strategy_manager: ArcMutex::new(strategy_manager),
+            exchange_cache: ArcMutex::new(ExchangeCache::new()),
+            strategy_manager: ArcRwLock::new(strategy_manager),
             strategy_tx,
             strategy_rx,
             storage_manager,
+            back_test_jobs: ArcMutex::new(BackTestJobManager::new()),
+            #[cfg(feature = "influx")]
+            metrics_sink,
+            events,
+            webhook,
+            shutdown_report: None,
+            scheduler,
+            snapshots: SnapshotStore::from_env(),
+            supervisor: StrategySupervisor::new(RestartPolicy::from_env()),
+            config_file: ConfigFile::from_env(),
+            notifiers: vec![],
+            notable_events,
+            funding_arbitrage: ArcMutex::new(FundingArbitrageTracker::new(
+                FundingArbitrageSettings::from_env(),
+            )),
         };
 
         _self.init().await;
+        {
+            let accounts = _self.accounts();
+            let mut funding_arbitrage = _self.funding_arbitrage.lock().await;
+            funding_arbitrage
+                .check_accounts(&accounts)
+                .await
+                .map_err(|e| {
+                    RaderBotError::Config(format!("Unable to trade funding legs, e: {e}"))
+                })?;
+            funding_arbitrage.restore(&accounts).await;
+        }
+        _self.resume_strategies().await;
 
-        _self
+        Ok(_self)
     }
 
     pub async fn start_strategy(
         &mut self,
         strategy_name: &str,
         symbol: &str,
-        interval: &str,
+        interval: Interval,
         settings: StrategySettings,
         algorithm_params: Value,
-    ) -> Result<StrategyInfo, AlgorithmError> {
+    ) -> StrategyResult<StrategyInfo> {
+        let accounts = self.accounts();
+        accounts
+            .check_profile(&settings)
+            .map_err(StrategyError::InvalidSettings)?;
+        settings
+            .execution
+            .validate()
+            .map_err(StrategyError::InvalidSettings)?;
+        accounts
+            .check_execution(&settings)
+            .await
+            .map_err(StrategyError::InvalidSettings)?;
+
         let market = self.market.clone();
         let strategy_tx = self.strategy_tx.clone();
 
-        let mut strategy = Strategy::new(
+        let strategy = Strategy::new(
             strategy_name,
             symbol,
             interval,
@@ -164,17 +579,69 @@ impl RaderBot {
             algorithm_params,
         )?;
 
-        let handle = strategy.start().await;
+        Ok(self.launch_strategy(strategy).await)
+    }
 
-        let strategy_info = strategy.info().await;
+    /// Starts several strategies at once, either all of them or none. Every strategy is built
+    /// before any is launched, so a single unknown algorithm or invalid params keeps the whole
+    /// batch from starting.
+    ///
+    /// # Arguments
+    ///
+    /// * `definitions` - The strategies to start.
+    ///
+    /// # Returns
+    ///
+    /// The `StrategyInfo` of each started strategy in input order, or, if any strategy can't be
+    /// built, the `StrategyError` of each definition in input order, `None` for valid ones.
+    pub async fn start_strategies(
+        &mut self,
+        definitions: Vec<StrategyDefinition>,
+    ) -> Result<Vec<StrategyInfo>, Vec<Option<StrategyError>>> {
+        let mut strategies = vec![];
+        let mut errors = vec![];
+        let accounts = self.accounts();
+        for definition in definitions {
+            if let Err(e) = accounts.check_profile(&definition.settings) {
+                errors.push(Some(StrategyError::InvalidSettings(e)));
+                continue;
+            }
+            if let Err(e) = definition.settings.execution.validate() {
+                errors.push(Some(StrategyError::InvalidSettings(e)));
+                continue;
+            }
+            if let Err(e) = accounts.check_execution(&definition.settings).await {
+                errors.push(Some(StrategyError::InvalidSettings(e)));
+                continue;
+            }
+            let strategy = Strategy::new(
+                &definition.strategy_name,
+                &definition.symbol,
+                definition.interval,
+                self.strategy_tx.clone(),
+                self.market.clone(),
+                definition.settings,
+                definition.algorithm_params,
+            );
+            match strategy {
+                Ok(strategy) => {
+                    strategies.push(strategy);
+                    errors.push(None);
+                }
+                Err(e) => errors.push(Some(e.into())),
+            }
+        }
 
-        self.strategy_manager
-            .clone()
-            .lock()
-            .await
-            .insert(strategy, handle);
+        if errors.iter().any(Option::is_some) {
+            return Err(errors);
+        }
+
+        let mut infos = vec![];
+        for strategy in strategies {
+            infos.push(self.launch_strategy(strategy).await);
+        }
 
-        Ok(strategy_info)
+        Ok(infos)
     }
 
     pub async fn stop_strategy(
@@ -183,14 +650,17 @@ impl RaderBot {
         close_positions: bool,
     ) -> Option<StrategySummary> {
         let mut summary: Option<StrategySummary> = None;
-        let account = self.account.clone();
         let strategy_manager = self.strategy_manager.clone();
 
         // Remove strategy handles
-        if let Some((handle, strategy)) = strategy_manager.lock().await.get(&strategy_id) {
+        if let Some((handle, strategy)) = strategy_manager.write().await.get_mut(&strategy_id) {
             handle.abort();
 
+            let account = self.account_for(&strategy.settings());
+
             let _summary = strategy.stop(account.clone(), close_positions).await;
+            self.events
+                .publish(BotEvent::StrategyStopped(_summary.info.clone()));
 
             // Save summary
             self.storage_manager
@@ -201,20 +671,207 @@ impl RaderBot {
             summary = Some(_summary);
         };
 
+        if let Err(e) = self
+            .storage_manager
+            .remove_active_strategy(strategy_id)
+            .await
+        {
+            info!("Unable to remove active strategy {strategy_id}, e: {e}");
+        }
+
         // Remove all handles and settings from signal_manager
-        strategy_manager.lock().await.remove(&strategy_id);
+        strategy_manager.write().await.remove(&strategy_id);
 
         summary
     }
 
+    /// Shuts the bot down, stopping its strategies, writing the open positions of every account
+    /// and the market data held in memory to storage and closing all streams. Shutdowns requested
+    /// through the API and by the OS both take this path.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - Whether open positions are kept for the strategies to resume on restart or
+    ///   closed.
+    ///
+    /// # Returns
+    ///
+    /// The `ShutdownReport` of the stopped strategies, closed positions and failures.
+    pub async fn shutdown(&mut self, policy: ShutdownPolicy) -> ShutdownReport {
+        let mut report = ShutdownReport {
+            policy,
+            ..Default::default()
+        };
+        let open_positions = self.count_open_positions().await;
+
+        match policy {
+            ShutdownPolicy::KeepPositions => {
+                // strategies stay active in storage, so they resume on restart
+                report.stopped_strategies = self.strategy_manager.write().await.abort_all();
+            }
+            ShutdownPolicy::ClosePositions => {
+                for strategy_id in self.get_active_strategy_ids().await {
+                    if self.stop_strategy(strategy_id, true).await.is_some() {
+                        report.stopped_strategies += 1;
+                    }
+                }
+
+                // positions opened manually have no strategy closing them
+                for (_name, account) in self.accounts().named() {
+                    account
+                        .lock()
+                        .await
+                        .close_all_positions(self.market.clone())
+                        .await;
+                }
+            }
+        }
+
+        report.open_positions = self.count_open_positions().await;
+        report.closed_positions = open_positions.saturating_sub(report.open_positions);
+
+        for (_name, account) in self.accounts().named() {
+            if let Err(e) = account.lock().await.persist_positions().await {
+                report.errors.push(e.to_string());
+            }
+        }
+
+        let market = &self.market;
+        for stream_id in market.close_all_streams().await {
+            report
+                .errors
+                .push(format!("Unable to close stream {stream_id}"));
+        }
+        if let Err(e) = market.flush_data().await {
+            report.errors.push(e.to_string());
+        }
+
+        info!(
+            "Shut down with {policy:?}, {} strategies stopped, {} positions closed, {} positions open",
+            report.stopped_strategies, report.closed_positions, report.open_positions
+        );
+        for error in &report.errors {
+            warn!("Shutdown error: {error}");
+        }
+        self.shutdown_report = Some(report.clone());
+        self.publish_action("shutdown", &report);
+
+        report
+    }
+
+    /// The report of the last shutdown, `None` if the bot hasn't been shut down.
+    pub fn shutdown_report(&self) -> Option<&ShutdownReport> {
+        self.shutdown_report.as_ref()
+    }
+
+    /// Flattens the bot: pauses trading, cancels the working orders of every traded symbol and
+    /// closes every open position of both accounts at the last market price. Strategies keep
+    /// running, but their signals are ignored until trading is resumed.
+    ///
+    /// # Returns
+    ///
+    /// The `FlattenReport` of the paused strategies, cancelled orders and closed positions.
+    pub async fn flatten_all(&mut self) -> FlattenReport {
+        let mut report = FlattenReport::default();
+
+        // signals are handled under the manager lock, so none opens a position while flattening
+        let strategy_manager = self.strategy_manager.clone();
+        let mut strategy_manager = strategy_manager.write().await;
+        strategy_manager.get_signal_manager_mut().set_paused(true);
+        report.paused_strategies = strategy_manager.list_ids();
+
+        let mut symbols = BTreeSet::new();
+        for strategy_id in &report.paused_strategies {
+            if let Some((_handle, strategy)) = strategy_manager.get(strategy_id) {
+                symbols.insert(strategy.symbol.clone());
+            }
+        }
+
+        for (name, account) in self.accounts().named() {
+            let name = name.as_str();
+            let mut account = account.lock().await;
+            let mut account_symbols = symbols.clone();
+            account_symbols.extend(account.positions().map(|position| position.symbol.clone()));
+
+            for symbol in account_symbols {
+                match account.cancel_all_orders(&symbol).await {
+                    Ok(_) => report.cancelled_orders.push((name.to_string(), symbol)),
+                    Err(e) => report.errors.push(format!(
+                        "Unable to cancel {name} orders of {symbol}, e: {e}"
+                    )),
+                }
+            }
+
+            for trade in account.close_all_positions(self.market.clone()).await {
+                report
+                    .closed_positions
+                    .push(FlattenedPosition::new(name, &trade));
+            }
+            // positions the exchange didn't close stay in the book
+            for position in account.positions() {
+                report.errors.push(format!(
+                    "Unable to close {name} position {} of {}",
+                    position.id, position.symbol
+                ));
+            }
+            report.open_positions += account.positions().len();
+        }
+
+        info!(
+            "Flattened, {} strategies paused, {} positions closed, {} positions open",
+            report.paused_strategies.len(),
+            report.closed_positions.len(),
+            report.open_positions
+        );
+        self.publish_action("flatten_all", &report);
+
+        report
+    }
+
+    /// Resumes trading paused by `flatten_all`.
+    pub async fn resume_trading(&mut self) {
+        self.strategy_manager
+            .write()
+            .await
+            .get_signal_manager_mut()
+            .set_paused(false);
+        info!("Trading resumed");
+        self.publish_action("resume_trading", json!({}));
+    }
+
     pub async fn get_active_strategy_ids(&mut self) -> Vec<StrategyId> {
         let strategy_manager = self.strategy_manager.clone();
-        let strategy_manger = strategy_manager.lock().await;
+        let strategy_manger = strategy_manager.read().await;
         strategy_manger.list_ids()
     }
 
-    pub async fn list_historical_strategies(&mut self) -> Option<Vec<StrategyInfo>> {
-        self.storage_manager.list_saved_strategies().await.ok()
+    /// Lists a page of the saved strategies matching the query as lightweight items.
+    ///
+    /// # Returns
+    ///
+    /// The items of the page and the number of saved strategies matching the filters of the
+    /// query.
+    pub async fn list_historical_strategies(
+        &self,
+        query: &StrategySummaryQuery,
+    ) -> RaderBotResult<(Vec<StrategySummaryItem>, usize)> {
+        let filter_query = StrategySummaryQuery {
+            limit: None,
+            offset: 0,
+            ..query.clone()
+        };
+        let summaries = self
+            .storage_manager
+            .list_strategy_summaries(&filter_query)
+            .await?;
+        let total = summaries.len();
+        let items = query
+            .page(summaries)
+            .iter()
+            .map(StrategySummaryItem::from)
+            .collect();
+
+        Ok((items, total))
     }
 
     pub async fn get_historical_strategy_summary(
@@ -227,48 +884,320 @@ impl RaderBot {
             .ok()
     }
 
-    pub async fn run_back_test(
+    /// Lists the saved strategy summaries matching the query.
+    pub async fn list_historical_strategy_summaries(
+        &self,
+        query: &StrategySummaryQuery,
+    ) -> RaderBotResult<Vec<StrategySummary>> {
+        Ok(self.storage_manager.list_strategy_summaries(query).await?)
+    }
+
+    /// Aggregates the results of all saved strategy summaries matching the filters of the
+    /// query by algorithm, the paging of the query is ignored.
+    pub async fn get_historical_strategy_stats(
+        &self,
+        query: &StrategySummaryQuery,
+    ) -> RaderBotResult<Vec<AlgorithmStats>> {
+        let query = StrategySummaryQuery {
+            limit: None,
+            offset: 0,
+            ..query.clone()
+        };
+        let summaries = self.storage_manager.list_strategy_summaries(&query).await?;
+
+        Ok(summarize_by_algorithm(&summaries))
+    }
+
+    /// Starts a backtest as a background task, see `BackTestJobManager`.
+    ///
+    /// # Returns
+    ///
+    /// The id of the job, or an `AlgorithmError` if the strategy can't be built.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn start_back_test_job(
         &mut self,
         strategy_name: &str,
         symbol: &str,
-        interval: &str,
+        interval: Interval,
         from_ts: u64,
         to_ts: u64,
         settings: StrategySettings,
         algorithm_params: Value,
-    ) -> Result<StrategySummary, AlgorithmError> {
-        let strategy_tx = self.strategy_tx.clone();
+        mode: BackTestMode,
+        options: BackTestOptions,
+    ) -> Result<JobId, AlgorithmError> {
         let strategy = Strategy::new(
             strategy_name,
             symbol,
             interval,
-            strategy_tx,
+            self.strategy_tx.clone(),
             self.market.clone(),
             settings,
             algorithm_params,
         )?;
 
-        // TODO: Get initial_balance from params
-        let initial_balance = Some(10_000.0);
-        let mut back_test = BackTest::new(strategy, self.market.clone(), initial_balance).await;
+        let (job_id, progress) =
+            self.back_test_jobs
+                .lock()
+                .await
+                .add_job(strategy_name, symbol, interval);
 
-        if let Some(kline_data) = self
-            .market
-            .clone()
-            .lock()
-            .await
-            .kline_data_range(&symbol, &interval, Some(from_ts), Some(to_ts), None)
+        let market = self.market.clone();
+        let back_test_jobs = self.back_test_jobs.clone();
+        let storage_manager = self.storage_manager.clone();
+        let back_test_task = tokio::spawn(async move {
+            let mut back_test = BackTest::new(strategy, market.clone(), Some(10_000.0)).await;
+            back_test.set_progress(progress.clone());
+            back_test.set_options(options.clone());
+            back_test.run_range(market, from_ts, to_ts, mode).await;
+
+            let result = back_test.result().await;
+            if !progress.is_cancelled() {
+                Self::save_back_test(
+                    storage_manager,
+                    BackTestRecord::new(from_ts, to_ts, mode, options, result.clone()),
+                )
+                .await;
+            }
+            result
+        });
+        tokio::spawn(async move {
+            let result = back_test_task.await;
+            let mut back_test_jobs = back_test_jobs.lock().await;
+            match result {
+                Ok(result) => back_test_jobs.finish_job(job_id, result),
+                Err(e) if e.is_panic() => {
+                    back_test_jobs.fail_job(job_id, &panic_message(e.into_panic().as_ref()))
+                }
+                Err(e) => back_test_jobs.fail_job(job_id, &e.to_string()),
+            }
+        });
+
+        Ok(job_id)
+    }
+
+    pub async fn cancel_back_test_job(&mut self, job_id: JobId) -> bool {
+        self.back_test_jobs.lock().await.cancel_job(job_id)
+    }
+
+    pub async fn get_back_test_job(
+        &mut self,
+        job_id: JobId,
+    ) -> Option<(BackTestJobInfo, Option<StrategySummary>)> {
+        self.back_test_jobs.lock().await.get_job(job_id)
+    }
+
+    pub async fn list_back_test_jobs(&mut self) -> Vec<BackTestJobInfo> {
+        self.back_test_jobs.lock().await.list_jobs()
+    }
+
+    pub async fn list_saved_back_tests(&mut self) -> Option<Vec<BackTestRecord>> {
+        self.storage_manager.list_back_tests().await.ok()
+    }
+
+    pub async fn get_saved_back_test(&mut self, id: StrategyId) -> Option<BackTestRecord> {
+        self.storage_manager.get_back_test(id).await.ok()
+    }
+
+    /// Renders the report of a saved backtest run and saves it, replacing a previous report of
+    /// the run in the same format.
+    ///
+    /// # Returns
+    ///
+    /// The saved `BackTestReport`, or an error if the run can't be found or the report saved.
+    pub async fn generate_back_test_report(
+        &mut self,
+        id: StrategyId,
+        format: ReportFormat,
+    ) -> RaderBotResult<BackTestReport> {
+        let record = self.storage_manager.get_back_test(id).await?;
+        let report = BackTestReport::new(&record, format);
+        self.storage_manager
+            .save_back_test_report(report.clone())
+            .await?;
+
+        Ok(report)
+    }
+
+    pub async fn get_back_test_report(
+        &mut self,
+        id: StrategyId,
+        format: ReportFormat,
+    ) -> Option<BackTestReport> {
+        self.storage_manager
+            .get_back_test_report(id, format)
             .await
-        {
-            back_test.run(kline_data).await;
-        };
+            .ok()
+    }
+
+    /// Compares saved backtest runs side by side, see `compare_back_tests`.
+    ///
+    /// # Returns
+    ///
+    /// The `BackTestComparison`, or `None` if any of the runs can't be found.
+    pub async fn compare_back_tests(&mut self, ids: &[StrategyId]) -> Option<BackTestComparison> {
+        let mut records = vec![];
+        for id in ids {
+            records.push(self.storage_manager.get_back_test(*id).await.ok()?);
+        }
+
+        compare_back_tests(&records)
+    }
+
+    /// Backtests several strategy configurations over the same range in parallel, see
+    /// `run_parallel`. The k-lines of every symbol and interval are loaded once and shared by
+    /// all backtests using them. Every run is saved like a single backtest.
+    ///
+    /// # Returns
+    ///
+    /// The `StrategySummary` of every backtest in input order, or the first `AlgorithmError`.
+    pub async fn run_batch_back_test(
+        &mut self,
+        back_tests: Vec<BatchBackTest>,
+        from_ts: u64,
+        to_ts: u64,
+    ) -> Result<Vec<StrategySummary>, AlgorithmError> {
+        let mut kline_cache: HashMap<(String, Interval), Arc<[Kline]>> = HashMap::new();
+        let mut futures = vec![];
+
+        for back_test in back_tests {
+            let key = (back_test.symbol.clone(), back_test.interval);
+            let klines = match kline_cache.get(&key) {
+                Some(klines) => klines.clone(),
+                None => {
+                    let klines: Arc<[Kline]> = Arc::from(
+                        self.klines_range(&back_test.symbol, back_test.interval, from_ts, to_ts)
+                            .await,
+                    );
+                    kline_cache.insert(key, klines.clone());
+                    klines
+                }
+            };
+
+            let runner = self.back_test_runner(
+                &back_test.strategy_name,
+                &back_test.symbol,
+                back_test.interval,
+                back_test.settings,
+            );
+            let algorithm_params = back_test.algorithm_params;
+            futures.push(async move { runner.run(algorithm_params, &klines).await });
+        }
+
+        let mut summaries = vec![];
+        for result in run_parallel(futures).await {
+            let summary = result.map_err(|e| AlgorithmError::InvalidParams(e.to_string()))??;
+            summaries.push(summary);
+        }
+
+        for summary in summaries.iter() {
+            Self::save_back_test(
+                self.storage_manager.clone(),
+                BackTestRecord::new(
+                    from_ts,
+                    to_ts,
+                    BackTestMode::Klines,
+                    BackTestOptions::default(),
+                    summary.clone(),
+                ),
+            )
+            .await;
+        }
+
+        Ok(summaries)
+    }
+
+    /// Backtests several strategies against a shared account, see `PortfolioBackTest`.
+    pub async fn run_portfolio_back_test(
+        &mut self,
+        strategies: Vec<PortfolioStrategy>,
+        from_ts: u64,
+        to_ts: u64,
+        capital: f64,
+    ) -> Result<PortfolioSummary, AlgorithmError> {
+        let mut portfolio_strategies = vec![];
+        let mut streams = vec![];
+        let mut routes: Vec<(String, Interval)> = vec![];
+        for params in strategies {
+            let strategy = Strategy::new(
+                &params.strategy_name,
+                &params.symbol,
+                params.interval,
+                self.strategy_tx.clone(),
+                self.market.clone(),
+                params.settings,
+                params.algorithm_params,
+            )?;
+            portfolio_strategies.push(strategy);
+
+            // strategies trading the same symbol and interval share a stream
+            let route = (params.symbol, params.interval);
+            if routes.contains(&route) {
+                continue;
+            }
+            streams.push(
+                self.market
+                    .kline_stream(&route.0, route.1, from_ts, to_ts)
+                    .await,
+            );
+            routes.push(route);
+        }
+
+        let mut back_test = PortfolioBackTest::new(portfolio_strategies, capital).await;
+        back_test.set_external_data(self.market.external_data_until(to_ts).await);
+        back_test.run(streams).await;
 
         Ok(back_test.result().await)
     }
 
+    /// Runs a walk-forward analysis of a strategy over a historical range, see `WalkForward`.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn run_walk_forward(
+        &mut self,
+        strategy_name: &str,
+        symbol: &str,
+        interval: Interval,
+        from_ts: u64,
+        to_ts: u64,
+        settings: StrategySettings,
+        algorithm_params: Value,
+        param_grid: &Map<String, Value>,
+        walk_forward_settings: WalkForwardSettings,
+    ) -> Result<WalkForwardResult, AlgorithmError> {
+        let klines = self.klines_range(symbol, interval, from_ts, to_ts).await;
+        let runner = self.back_test_runner(strategy_name, symbol, interval, settings);
+
+        WalkForward::new(runner, walk_forward_settings)
+            .run(&klines, algorithm_params, param_grid)
+            .await
+    }
+
+    /// Optimizes the algorithm parameters of a strategy over a historical range, see
+    /// `GeneticOptimizer`.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn run_optimizer(
+        &mut self,
+        strategy_name: &str,
+        symbol: &str,
+        interval: Interval,
+        from_ts: u64,
+        to_ts: u64,
+        settings: StrategySettings,
+        algorithm_params: Value,
+        param_space: &BTreeMap<String, ParamRange>,
+        optimizer_settings: OptimizerSettings,
+    ) -> Result<OptimizationResult, AlgorithmError> {
+        let klines = self.klines_range(symbol, interval, from_ts, to_ts).await;
+        let runner = self.back_test_runner(strategy_name, symbol, interval, settings);
+
+        GeneticOptimizer::new(runner, optimizer_settings)
+            .run(&klines, algorithm_params, param_space)
+            .await
+    }
+
     pub async fn get_strategy_info(&mut self, strategy_id: StrategyId) -> Option<StrategyInfo> {
         let manager = self.strategy_manager.clone();
-        let mut manager = manager.lock().await;
+        let manager = manager.read().await;
         if let Some((_handle, strategy)) = manager.get(&strategy_id) {
             return Some(strategy.info().await.clone());
         }
@@ -280,9 +1209,9 @@ impl RaderBot {
         strategy_id: StrategyId,
     ) -> Option<StrategySummary> {
         let manager = self.strategy_manager.clone();
-        let account = self.account.clone();
-        let mut manager = manager.lock().await;
+        let manager = manager.read().await;
         if let Some((_handle, strategy)) = manager.get(&strategy_id) {
+            let account = self.account_for(&strategy.settings());
             return Some(strategy.summary(account).await.clone());
         }
         None
@@ -294,57 +1223,1259 @@ impl RaderBot {
         settings: StrategySettings,
     ) -> Option<StrategyInfo> {
         let manager = self.strategy_manager.clone();
-        let mut manager = manager.lock().await;
-        if let Some((_handle, strategy)) = manager.get(&strategy_id) {
-            strategy.change_settings(settings);
-            return Some(strategy.info().await);
-        }
-        None
+        let mut manager = manager.write().await;
+        let strategy = manager.change_settings(&strategy_id, settings)?;
+        let info = strategy.info().await;
+        self.save_active_strategy(info.clone()).await;
+        self.events.publish(BotEvent::SettingsChanged {
+            strategy_id,
+            settings: info.settings.clone(),
+        });
+
+        Some(info)
     }
 
+    /// Updates the risk settings of a running strategy. The strategy and the `SignalManager`
+    /// change together, so the next signal of the strategy uses the new settings.
+    ///
+    /// # Returns
+    ///
+    /// The updated `StrategyInfo`, or an error if the strategy isn't running or the update has
+    /// invalid values.
+    pub async fn update_strategy_settings(
+        &mut self,
+        strategy_id: StrategyId,
+        update: &StrategySettingsUpdate,
+    ) -> StrategyResult<StrategyInfo> {
+        let manager = self.strategy_manager.clone();
+        let mut manager = manager.write().await;
+        let settings = match manager.get(&strategy_id) {
+            Some((_handle, strategy)) => update
+                .apply(&strategy.settings())
+                .map_err(StrategyError::InvalidSettings)?,
+            None => return Err(StrategyError::NotFound(strategy_id)),
+        };
+        self.accounts()
+            .check_execution(&settings)
+            .await
+            .map_err(StrategyError::InvalidSettings)?;
+
+        // SAFETY: the strategy was found above while holding the lock
+        let strategy = manager.change_settings(&strategy_id, settings).unwrap();
+        let info = strategy.info().await;
+        self.save_active_strategy(info.clone()).await;
+        self.events.publish(BotEvent::SettingsChanged {
+            strategy_id,
+            settings: info.settings.clone(),
+        });
+
+        Ok(info)
+    }
+
+    /// Changes the algorithm params of an active strategy and records the change.
+    ///
+    /// # Returns
+    ///
+    /// The updated params, `None` if the strategy isn't active, or an error if the params are
+    /// invalid.
     pub async fn set_strategy_params(
         &mut self,
         strategy_id: StrategyId,
         params: Value,
-    ) -> Result<(), AlgorithmError> {
+        changed_by: &str,
+    ) -> Result<Option<Value>, AlgorithmError> {
         let manager = self.strategy_manager.clone();
-        let mut manager = manager.lock().await;
+        let manager = manager.read().await;
         if let Some((_handle, strategy)) = manager.get(&strategy_id) {
-            strategy.set_algorithm_params(params).await?
+            let old_params = strategy.get_algorithm_params().await;
+            strategy.set_algorithm_params(params).await?;
+            let new_params = strategy.get_algorithm_params().await;
+
+            let change = StrategyParamsChange {
+                strategy_id,
+                timestamp: generate_ts(),
+                old_params,
+                new_params: new_params.clone(),
+                changed_by: changed_by.to_string(),
+            };
+            self.events.publish(BotEvent::ParamsChanged(change.clone()));
+            if let Err(e) = self
+                .storage_manager
+                .save_strategy_params_change(change)
+                .await
+            {
+                info!("Unable to save params change for strategy {strategy_id}, e: {e}");
+            }
+
+            let info = strategy.info().await;
+            self.save_active_strategy(info).await;
+
+            return Ok(Some(new_params));
         }
+        Ok(None)
+    }
+
+    pub async fn get_strategy_params_history(
+        &mut self,
+        strategy_id: StrategyId,
+    ) -> Option<Vec<StrategyParamsChange>> {
+        self.storage_manager
+            .list_strategy_params_changes(strategy_id)
+            .await
+            .ok()
+    }
+
+    /// Subscribes to the events published by the bot from now on, such as handled signals and
+    /// opened positions.
+    pub fn webhook_config(&self) -> Option<&WebhookConfig> {
+        self.webhook.as_ref()
+    }
+
+    /// Handles a TradingView alert as a live signal of the webhook pseudo-strategy, which goes
+    /// through the same settings and risk checks as the signals of running strategies.
+    ///
+    /// # Returns
+    ///
+    /// The signal and the action taken on it, or an error if the webhook is disabled or the
+    /// alert can't be converted into a signal.
+    pub async fn handle_webhook_alert(
+        &mut self,
+        alert: &TradingViewAlert,
+    ) -> StrategyResult<(SignalMessage, SignalAction)> {
+        if self.webhook.is_none() {
+            return Err(StrategyError::Unavailable(
+                "TradingView webhook is disabled".to_string(),
+            ));
+        }
+        let signal = alert
+            .to_signal(generate_ts())
+            .map_err(StrategyError::InvalidSignal)?;
+
+        let strategy_manager = self.strategy_manager.read().await;
+        let signal_manager = strategy_manager.get_signal_manager();
+        let account = self
+            .accounts()
+            .account_for(signal_manager.strategy_settings(&signal.strategy_id));
+        let action = signal_manager
+            .handle_signal(signal.clone(), self.market.clone(), account)
+            .await;
+
+        Ok((signal, action))
+    }
+
+    pub fn subscribe_events(&self) -> Receiver<BotEvent> {
+        self.events.subscribe()
+    }
+
+    /// Lists the recorded signals matching the query with the action taken on each of them.
+    pub async fn get_signal_history(
+        &self,
+        query: SignalHistoryQuery,
+    ) -> RaderBotResult<Vec<SignalRecord>> {
+        Ok(self.storage_manager.list_signal_records(query).await?)
+    }
+
+    /// Creates a backup archive of the stored data, see `StorageManager::create_backup`.
+    pub async fn create_backup(&self) -> RaderBotResult<BackupInfo> {
+        let backup = self.storage_manager.create_backup().await?;
+        info!("Created backup {} of {} bytes", backup.name, backup.size);
+
+        Ok(backup)
+    }
+
+    /// Lists the backup archives, most recent first.
+    pub async fn list_backups(&self) -> RaderBotResult<Vec<BackupInfo>> {
+        Ok(self.storage_manager.list_backups().await?)
+    }
+
+    /// Restores the stored data from a backup archive and reloads the account state from it.
+    /// The active strategies of the backup are resumed on the next start.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the backup.
+    ///
+    /// # Returns
+    ///
+    /// An error if strategies are running, they would keep writing to the replaced data, or if
+    /// the backup can't be restored.
+    pub async fn restore_backup(&self, name: &str) -> RaderBotResult<()> {
+        let running = self.strategy_manager.read().await.list_ids().len();
+        if running > 0 {
+            return Err(StrategyError::Unavailable(format!(
+                "{running} strategies are running, stop them before restoring a backup"
+            ))
+            .into());
+        }
+
+        self.storage_manager.restore_backup(name).await?;
+        info!("Restored backup {name}");
+        self.publish_action("restore_backup", json!({ "name": name }));
+
+        for (name, account) in self.accounts().named() {
+            account
+                .lock()
+                .await
+                .set_storage_manager(self.storage_manager.clone(), &name)
+                .await?;
+        }
+
         Ok(())
     }
-    pub async fn get_strategy_params(&mut self, strategy_id: StrategyId) -> Option<Value> {
+
+    /// Detects strategies whose task ended, e.g. because their algorithm panicked, publishes an
+    /// error event for each and restarts them once their backoff passed. Strategies failing too
+    /// often are stopped, keeping their positions.
+    ///
+    /// # Returns
+    ///
+    /// The number of restarted strategies.
+    pub async fn supervise_strategies(&mut self) -> usize {
+        let now = generate_ts();
+        let finished = self.strategy_manager.read().await.finished();
+
+        for (strategy_id, failure) in finished {
+            if self.supervisor.is_pending(&strategy_id) {
+                continue;
+            }
+
+            let failure = failure.unwrap_or_else(|| "task ended".to_string());
+            let message = match self.supervisor.record_failure(strategy_id, &failure, now) {
+                RestartDecision::Restart { at, attempt } => format!(
+                    "Strategy {strategy_id} failed: {failure}, restart {attempt} in {}s",
+                    at.saturating_sub(now) / 1000
+                ),
+                RestartDecision::GiveUp { restarts } => {
+                    self.stop_strategy(strategy_id, false).await;
+                    format!(
+                        "Strategy {strategy_id} failed: {failure}, stopped after {restarts} restarts"
+                    )
+                }
+            };
+            warn!("{message}");
+            self.events.publish(BotEvent::Error {
+                source: "strategy_supervisor".to_string(),
+                message,
+            });
+        }
+
+        let mut restarted = 0;
+        for strategy_id in self.supervisor.due(now) {
+            if self
+                .strategy_manager
+                .write()
+                .await
+                .restart(&strategy_id)
+                .await
+            {
+                info!("Restarted strategy {strategy_id}");
+                self.supervisor.record_restart(&strategy_id, now);
+                restarted += 1;
+            } else {
+                // stopped while waiting for its restart
+                self.supervisor.forget(&strategy_id);
+            }
+        }
+
+        restarted
+    }
+
+    /// Lists the strategies that failed with their restarts.
+    pub fn strategy_restarts(&self) -> Vec<StrategyRestarts> {
+        self.supervisor.list()
+    }
+
+    /// Lists the funding-rate arbitrage opportunities of the traded symbols, as of their last
+    /// polled funding rate.
+    pub async fn funding_opportunities(&self) -> Vec<FundingOpportunity> {
+        self.funding_arbitrage.lock().await.opportunities()
+    }
+
+    pub fn config_file(&self) -> &ConfigFile {
+        &self.config_file
+    }
+
+    /// Reloads the configuration file and applies the changes safe to make at runtime, see
+    /// `config`. Strategies keep running and streams stay open.
+    ///
+    /// # Returns
+    ///
+    /// The `ConfigReloadReport` of the changes, or an error if the file can't be read.
+    pub async fn reload_config(&mut self) -> RaderBotResult<ConfigReloadReport> {
+        let mut report = self.config_file.reload().map_err(RaderBotError::Config)?;
+        let changed = |prefix: &str| report.applied.iter().any(|key| key.starts_with(prefix));
+
+        let reload_logger = changed("RUST_LOG");
+        let reload_notifiers = changed("DISCORD_") || changed("NOTIFY_WEBHOOK");
+        let reload_webhook = changed("TRADINGVIEW_");
+        let reload_snapshots = changed("SNAPSHOT_");
+        let reload_admin_token = changed("ADMIN_API_TOKEN");
+
+        if reload_logger {
+            reload_log_filter();
+        }
+        if reload_notifiers {
+            // invalid settings keep the notifiers running with the previous ones
+            match DiscordChannel::from_env().and_then(|_| WebhookEndpoint::from_env()) {
+                Ok(_) => {
+                    for handle in self.notifiers.drain(..) {
+                        handle.abort();
+                    }
+                    report.errors.extend(self.spawn_notifiers());
+                }
+                Err(e) => report
+                    .errors
+                    .push(format!("Notifications unchanged, e: {e}")),
+            }
+        }
+        if reload_webhook {
+            match WebhookConfig::from_env() {
+                Ok(webhook) => {
+                    let mut strategy_manager = self.strategy_manager.write().await;
+                    let signal_manager = strategy_manager.get_signal_manager_mut();
+                    match &webhook {
+                        Some(webhook) => signal_manager
+                            .add_strategy_settings(&WEBHOOK_STRATEGY_ID, webhook.settings.clone()),
+                        None => signal_manager.remove_strategy_settings(&WEBHOOK_STRATEGY_ID),
+                    }
+                    self.webhook = webhook;
+                }
+                Err(e) => report
+                    .errors
+                    .push(format!("TradingView webhook unchanged, e: {e}")),
+            }
+        }
+        if reload_snapshots {
+            self.snapshots = SnapshotStore::from_env();
+        }
+        if reload_admin_token {
+            self.admin_token.reload(env_lookup);
+        }
+
+        info!(
+            "Reloaded {}, applied {} changes",
+            self.config_file.path().display(),
+            report.applied.len()
+        );
+        self.publish_action("reload_config", &report);
+
+        Ok(report)
+    }
+
+    /// Checkpoints the active strategies, the open positions of every account, the needed
+    /// streams and the signal manager state into a snapshot, then removes the oldest snapshots
+    /// beyond the ones kept.
+    pub async fn create_snapshot(&mut self) -> RaderBotResult<SnapshotInfo> {
+        let mut strategies = vec![];
+        for strategy_id in self.get_active_strategy_ids().await {
+            if let Some(info) = self.get_strategy_info(strategy_id).await {
+                strategies.push(info);
+            }
+        }
+        strategies.sort_by_key(|info| info.id);
+
+        let mut accounts = vec![];
+        for (name, account) in self.accounts().named() {
+            let mut positions: Vec<Position> = account.lock().await.positions().cloned().collect();
+            positions.sort_by_key(|position| position.id);
+            accounts.push(AccountSnapshot { name, positions });
+        }
+
+        let market = &self.market;
+        let market_snapshot = MarketSnapshot {
+            needed_streams: market.needed_streams().await,
+            active_streams: market
+                .active_streams()
+                .await
+                .into_iter()
+                .map(|meta| meta.id)
+                .collect(),
+        };
+
+        let strategy_manager = self.strategy_manager.read().await;
+        let signal_manager = strategy_manager.get_signal_manager();
+        let signals = SignalSnapshot {
+            paused: signal_manager.is_paused(),
+            strategy_settings: signal_manager.all_strategy_settings().clone(),
+        };
+        drop(strategy_manager);
+
+        let snapshot = BotSnapshot::new(strategies, accounts, market_snapshot, signals);
+        let info = self.snapshots.save(&snapshot)?;
+        let pruned = self.snapshots.prune()?;
+        info!(
+            "Created snapshot {} of {} strategies, removed {pruned} old snapshots",
+            info.name,
+            snapshot.strategies.len()
+        );
+
+        Ok(info)
+    }
+
+    /// Lists the snapshots, most recent first.
+    pub fn list_snapshots(&self) -> RaderBotResult<Vec<SnapshotInfo>> {
+        Ok(self.snapshots.list()?)
+    }
+
+    /// Replaces the running state by a snapshot. Running strategies are aborted without closing
+    /// their positions, the open positions of every account in the snapshot are replaced by its
+    /// positions, and the strategies of the snapshot are warmed up and relaunched under their
+    /// ids. The TradingView alerts keep the settings they are configured with.
+    ///
+    /// Positions of the snapshot are first reconciled with the positions held on the exchange,
+    /// positions closed since the snapshot aren't restored. Accounts whose exchange positions
+    /// can't be listed keep their current positions.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the snapshot.
+    ///
+    /// # Returns
+    ///
+    /// The `SnapshotRestoreReport`, or an error if the snapshot or the active strategies can't
+    /// be read, in which case the running state is left untouched, or if the strategies of the
+    /// snapshot can't be saved, in which case the aborted strategies are relaunched.
+    pub async fn restore_snapshot(&mut self, name: &str) -> RaderBotResult<SnapshotRestoreReport> {
+        let snapshot = self.snapshots.load(name)?;
+        let mut report = SnapshotRestoreReport {
+            name: name.to_string(),
+            ..Default::default()
+        };
+
+        let accounts: HashMap<String, ArcMutex<Account>> =
+            self.accounts().named().into_iter().collect();
+        let mut restored_positions = vec![];
+        for account_snapshot in snapshot.accounts {
+            let Some(account) = accounts.get(&account_snapshot.name) else {
+                report.errors.push(format!(
+                    "Account {} isn't configured, its {} positions weren't restored",
+                    account_snapshot.name,
+                    account_snapshot.positions.len()
+                ));
+                continue;
+            };
+            let reconciled = account
+                .lock()
+                .await
+                .reconcile_positions(account_snapshot.positions)
+                .await;
+            match reconciled {
+                Ok((held, closed)) => {
+                    for position in closed {
+                        report.errors.push(format!(
+                            "Position {} {} {} of {} was closed since the snapshot, it wasn't restored",
+                            position.id, position.order_side, position.symbol, account_snapshot.name
+                        ));
+                    }
+                    restored_positions.push((account.clone(), held));
+                }
+                Err(e) => report.errors.push(format!(
+                    "Unable to list the exchange positions of {}, its positions weren't restored, e: {e}",
+                    account_snapshot.name
+                )),
+            }
+        }
+        let previous_strategies = self.storage_manager.list_active_strategies().await?;
+
+        let mut strategy_manager = self.strategy_manager.write().await;
+        let aborted = strategy_manager.list_ids();
+        strategy_manager.abort_all();
+        drop(strategy_manager);
+        for strategy_id in aborted {
+            health::runtime().remove_task(&format!("strategy:{strategy_id}"));
+        }
+
+        if let Err(e) = self.replace_active_strategies(&snapshot.strategies).await {
+            // relaunch the aborted strategies rather than leave none running
+            if let Err(e) = self.replace_active_strategies(&previous_strategies).await {
+                warn!("Unable to restore the active strategies, e: {e}");
+            }
+            self.resume_strategies().await;
+            return Err(e);
+        }
+
+        for (account, positions) in restored_positions {
+            if let Err(e) = account.lock().await.replace_positions(positions).await {
+                report.errors.push(e.to_string());
+            }
+        }
+
+        let mut strategy_manager = self.strategy_manager.write().await;
+        let signal_manager = strategy_manager.get_signal_manager_mut();
+        signal_manager.set_paused(snapshot.signals.paused);
+        signal_manager.replace_strategy_settings(snapshot.signals.strategy_settings);
+        if let Some(webhook) = &self.webhook {
+            signal_manager.add_strategy_settings(&WEBHOOK_STRATEGY_ID, webhook.settings.clone());
+        }
+        drop(strategy_manager);
+
+        self.market
+            .replace_needed_streams(snapshot.market.needed_streams)
+            .await;
+
+        self.resume_strategies().await;
+
+        let resumed = self.get_active_strategy_ids().await;
+        for info in &snapshot.strategies {
+            if !resumed.contains(&info.id) {
+                report.errors.push(format!(
+                    "Strategy {} {} {} {} didn't resume",
+                    info.id, info.name, info.symbol, info.interval
+                ));
+            }
+        }
+        report.resumed_strategies = resumed.len();
+        report.open_positions = self.count_open_positions().await;
+
+        info!(
+            "Restored snapshot {name} taken by raderbot {}, {} strategies resumed, {} positions open",
+            snapshot.bot_version, report.resumed_strategies, report.open_positions
+        );
+        for error in &report.errors {
+            warn!("Snapshot restore error: {error}");
+        }
+        self.publish_action("restore_snapshot", &report);
+
+        Ok(report)
+    }
+
+    /// Returns the account a strategy trades on, the shadow account for strategies in
+    /// shadow mode or the main account otherwise.
+    pub async fn get_strategy_account(&mut self, strategy_id: StrategyId) -> ArcMutex<Account> {
         let manager = self.strategy_manager.clone();
-        let mut manager = manager.lock().await;
+        let manager = manager.read().await;
         if let Some((_handle, strategy)) = manager.get(&strategy_id) {
-            return Some(strategy.get_algorithm_params().await);
+            return self.account_for(&strategy.settings());
         }
-        None
+        self.account.clone()
+    }
+
+    /// The accounts strategies trade on, see `AccountRouter`.
+    pub fn accounts(&self) -> AccountRouter {
+        AccountRouter {
+            live: self.account.clone(),
+            shadow: self.shadow_account.clone(),
+            profiles: self.profile_accounts.clone(),
+        }
+    }
+
+    /// Lists the configured credential profiles with the open positions and equity of their
+    /// accounts.
+    pub async fn list_profile_accounts(&self) -> Vec<ProfileAccount> {
+        let mut profile_accounts = vec![];
+        for profile in &self.profiles {
+            let Some(account) = self.profile_accounts.get(&profile.name) else {
+                continue;
+            };
+            let account = account.lock().await;
+            profile_accounts.push(ProfileAccount {
+                profile: profile.clone(),
+                open_positions: account.positions().len(),
+                equity: account.equity(self.market.clone()).await,
+            });
+        }
+
+        profile_accounts
+    }
+
+    /// The tenants of the bot, empty when it isn't multi-tenant.
+    pub fn tenants(&self) -> &Tenants {
+        &self.tenants
+    }
+
+    /// The token of the admin routes, replaced when a config reload changes `ADMIN_API_TOKEN`.
+    pub fn admin_token(&self) -> &AdminToken {
+        &self.admin_token
+    }
+
+    pub fn scheduler(&self) -> &JobScheduler {
+        &self.scheduler
+    }
+
+    /// Lists the scheduled jobs with their next and last run.
+    pub fn scheduled_jobs(&self) -> Vec<ScheduledJobInfo> {
+        self.scheduler.list(generate_ts())
+    }
+
+    /// Schedules a job, replacing a job of the same name, see `JobScheduler::add`.
+    pub fn schedule_job(&mut self, job: ScheduledJob) -> Result<(), String> {
+        let detail = json!(job);
+        self.scheduler.add(job)?;
+        self.publish_action("schedule_job", detail);
+
+        Ok(())
+    }
+
+    /// Removes a scheduled job, see `JobScheduler::remove`.
+    pub fn unschedule_job(&mut self, name: &str) -> Result<bool, String> {
+        let removed = self.scheduler.remove(name)?;
+        if removed {
+            self.publish_action("unschedule_job", json!({ "name": name }));
+        }
+
+        Ok(removed)
+    }
+
+    /// Runs a scheduled job now, whether it is due or not. Failures are published as error
+    /// events.
+    ///
+    /// # Returns
+    ///
+    /// The `JobRun` of the job, or `None` if no job of the name is scheduled.
+    pub async fn run_scheduled_job(&mut self, name: &str) -> Option<JobRun> {
+        let job = self.scheduler.get(name)?.job.clone();
+
+        let started_at = generate_ts();
+        let result = self.run_job(job).await;
+        if let Err(e) = &result {
+            self.events.publish(BotEvent::Error {
+                source: "scheduler".to_string(),
+                message: format!("Job {name} failed: {e}"),
+            });
+        }
+
+        let run = JobRun {
+            started_at,
+            finished_at: generate_ts(),
+            success: result.is_ok(),
+            message: result.unwrap_or_else(|e| e),
+        };
+        self.scheduler.record_run(name, run.clone());
+
+        Some(run)
+    }
+
+    /// Publishes the trading activity of every account over the past day.
+    pub async fn publish_daily_summaries(&self) {
+        let to_ts = generate_ts();
+        for (name, account) in self.accounts().named() {
+            let summary = account
+                .lock()
+                .await
+                .trading_summary(self.market.clone(), to_ts - DAY_AS_MILI, to_ts)
+                .await;
+            self.events.publish(BotEvent::DailySummary {
+                account: name,
+                summary,
+            });
+        }
+    }
+
+    /// Creates the report of the profit and activity of every strategy over the past day. Fees
+    /// are estimated with the share of the value of every fill set in `TRADING_FEE_RATE`.
+    pub async fn daily_report(&mut self) -> DailyReport {
+        let to_ts = generate_ts();
+        let from_ts = to_ts - DAY_AS_MILI;
+        let fee_rate = env_lookup("TRADING_FEE_RATE")
+            .and_then(|rate| rate.parse::<f64>().ok())
+            .unwrap_or(0.0);
+
+        let mut strategies = vec![];
+        for (name, account) in self.accounts().named() {
+            let account = account.lock().await;
+            let positions: Vec<Position> = account.positions().cloned().collect();
+            let unrealized = account
+                .position_pnls(self.market.clone())
+                .await
+                .into_iter()
+                .map(|pnl| (pnl.position_id, pnl.unrealized_profit))
+                .collect();
+            strategies.extend(StrategyActivity::collect(
+                &name,
+                &account.trades(),
+                &positions,
+                &unrealized,
+                (from_ts, to_ts),
+                fee_rate,
+            ));
+        }
+
+        let mut names = HashMap::new();
+        for strategy_id in self.get_active_strategy_ids().await {
+            if let Some(info) = self.get_strategy_info(strategy_id).await {
+                names.insert(strategy_id, info.name);
+            }
+        }
+        for strategy in &mut strategies {
+            strategy.strategy_name = strategy
+                .strategy_id
+                .and_then(|strategy_id| names.get(&strategy_id).cloned());
+        }
+
+        let events = self.notable_events.between(from_ts, to_ts);
+        DailyReport::new(from_ts, to_ts, strategies, events)
+    }
+
+    /// Lists the saved daily reports, oldest first.
+    pub async fn list_daily_reports(&self) -> RaderBotResult<Vec<DailyReport>> {
+        Ok(self.storage_manager.list_daily_reports().await?)
+    }
+
+    /// Lists the audit log entries matching a query, oldest first.
+    pub async fn get_audit_log(&self, query: AuditQuery) -> RaderBotResult<Vec<AuditEntry>> {
+        Ok(AuditLog::list(&self.storage_manager, query).await?)
     }
 
     // ---
     // Private Methods
     // ---
 
+    /// Publishes an intervention of an operator, which the audit log records.
+    fn publish_action(&self, action: &str, detail: impl Serialize) {
+        self.events.publish(BotEvent::ManualAction {
+            action: action.to_string(),
+            detail: serde_json::to_value(detail).unwrap_or_default(),
+        });
+    }
+
     async fn init(&mut self) {
         let strategy_manager = self.strategy_manager.clone();
         let strategy_rx = self.strategy_rx.clone();
-        let account = self.account.clone();
+        let accounts = self.accounts();
         let market = self.market.clone();
 
         tokio::spawn(async move {
-            while let Some(signal) = strategy_rx.lock().await.recv().await {
-                let strategy_manager = strategy_manager.lock().await;
+            health::runtime().register_task("signal_handler", None);
+            loop {
+                let (signal, stats) = {
+                    let mut receiver = strategy_rx.lock().await;
+                    let Some(signal) = receiver.recv().await else {
+                        break;
+                    };
+                    (signal, receiver.stats())
+                };
+                health::runtime().record_channel_stats("strategy_tx", stats);
+                let _iteration = health::time_iteration("signal_handler");
+
+                let strategy_manager = timed_read("strategy_manager", &strategy_manager).await;
                 let signal_manager = strategy_manager.get_signal_manager();
+                let account =
+                    accounts.account_for(signal_manager.strategy_settings(&signal.strategy_id));
                 signal_manager
-                    .handle_signal(signal, market.clone(), account.clone())
+                    .handle_signal(signal, market.clone(), account)
                     .await;
             }
         });
+
+        let strategy_manager = self.strategy_manager.clone();
+        let accounts = self.accounts().named();
+        let market = self.market.clone();
+
+        // monitor positions for time-based, stop loss and take profit exits
+        tokio::spawn(async move {
+            let interval = Duration::from_secs(POSITION_MONITOR_INTERVAL_SECS);
+            health::runtime().register_task("position_monitor", Some(interval));
+            loop {
+                tokio::time::sleep(interval).await;
+                let _iteration = health::time_iteration("position_monitor");
+
+                let strategy_manager = timed_read("strategy_manager", &strategy_manager).await;
+                let signal_manager = strategy_manager.get_signal_manager();
+                for (name, account) in &accounts {
+                    signal_manager
+                        .close_expired_positions(market.clone(), account.clone())
+                        .await;
+                    timed_lock(&format!("account:{name}"), account)
+                        .await
+                        .close_exit_hit_positions(market.clone())
+                        .await;
+                }
+            }
+        });
+
+        // sample account equity into the time-series sink
+        #[cfg(feature = "influx")]
+        if let Some(sink) = self.metrics_sink.clone() {
+            let accounts = self.accounts().named();
+            let market = self.market.clone();
+
+            tokio::spawn(async move {
+                loop {
+                    tokio::time::sleep(Duration::from_secs(EQUITY_SAMPLE_INTERVAL_SECS)).await;
+
+                    for (name, account) in &accounts {
+                        let equity = account.lock().await.equity(market.clone()).await;
+                        if let Err(e) = sink.save_account_equity(name, &equity).await {
+                            info!("Unable to write {name} account equity to InfluxDB, e: {e}");
+                        }
+                    }
+                }
+            });
+        }
+
+        let strategy_manager = self.strategy_manager.clone();
+        let exchange_api = self.exchange_api.clone();
+        let funding_arbitrage = self.funding_arbitrage.clone();
+        let accounts = self.accounts();
+        let market = self.market.clone();
+
+        // poll funding rates of the traded and hedged symbols for funding-rate arbitrage
+        tokio::spawn(async move {
+            let interval = Duration::from_secs(FUNDING_MONITOR_INTERVAL_SECS);
+            health::runtime().register_task("funding_monitor", Some(interval));
+            loop {
+                tokio::time::sleep(interval).await;
+                let _iteration = health::time_iteration("funding_monitor");
+
+                let mut symbols = timed_read("strategy_manager", &strategy_manager)
+                    .await
+                    .symbols();
+                funding_arbitrage.lock().await.retain_symbols(&symbols);
+                for symbol in funding_arbitrage.lock().await.hedged_symbols() {
+                    if !symbols.contains(&symbol) {
+                        symbols.push(symbol);
+                    }
+                }
+                for symbol in symbols {
+                    let funding_rate = match exchange_api.get_funding_rate(&symbol).await {
+                        Ok(funding_rate) => funding_rate,
+                        Err(e) => {
+                            info!("Unable to get funding rate of {symbol}, e: {e}");
+                            continue;
+                        }
+                    };
+                    let mut funding_arbitrage = funding_arbitrage.lock().await;
+                    let action = funding_arbitrage.update(funding_rate);
+                    if action == FundingArbitrageAction::Hold {
+                        continue;
+                    }
+                    info!("Funding arbitrage on {symbol}: {action:?}");
+
+                    let price = match market.last_price(&symbol).await {
+                        Some(price) => Some(price),
+                        None => exchange_api
+                            .get_ticker(&symbol)
+                            .await
+                            .ok()
+                            .map(|ticker| ticker.last_price),
+                    };
+                    if let Err(e) = funding_arbitrage
+                        .execute(&symbol, action, price, &accounts)
+                        .await
+                    {
+                        info!("Unable to trade funding arbitrage legs of {symbol}, e: {e}");
+                    }
+                }
+            }
+        });
+
+        for error in self.spawn_notifiers() {
+            info!("{error}");
+        }
+
+        // publish account equity while anyone is subscribed
+        {
+            let events = self.events.clone();
+            let accounts = self.accounts().named();
+            let market = self.market.clone();
+
+            tokio::spawn(async move {
+                loop {
+                    tokio::time::sleep(Duration::from_secs(EQUITY_SAMPLE_INTERVAL_SECS)).await;
+                    if !events.has_subscribers() {
+                        continue;
+                    }
+
+                    for (name, account) in &accounts {
+                        let equity = account.lock().await.equity(market.clone()).await;
+                        events.publish(BotEvent::Equity {
+                            account: name.clone(),
+                            equity,
+                        });
+                    }
+                }
+            });
+        }
+
+        // compact stored klines, removing duplicates and reporting anomalies
+        if let Some(hours) = env_lookup("KLINE_COMPACTION_INTERVAL_HOURS")
+            .and_then(|hours| hours.parse::<u64>().ok())
+            .filter(|hours| *hours > 0)
+        {
+            let storage_manager = self.storage_manager.clone();
+
+            tokio::spawn(async move {
+                loop {
+                    match storage_manager.compact_klines().await {
+                        Ok(report) => {
+                            info!(
+                                "Compacted {} of {} kline files, removed {} duplicates",
+                                report.files_compacted,
+                                report.files_checked,
+                                report.duplicates_removed
+                            );
+                            for anomaly in report.anomalies {
+                                info!("Kline anomaly in {}: {:?}", anomaly.file, anomaly.anomaly);
+                            }
+                        }
+                        Err(e) => info!("Unable to compact klines, e: {e}"),
+                    }
+
+                    tokio::time::sleep(Duration::from_secs(hours * 60 * 60)).await;
+                }
+            });
+        }
+
+        // remove market data older than the configured retention
+        match RetentionPolicy::from_env() {
+            Ok(policy) if !policy.is_empty() => {
+                let storage_manager = self.storage_manager.clone();
+                let hours = env_lookup("RETENTION_INTERVAL_HOURS")
+                    .and_then(|hours| hours.parse::<u64>().ok())
+                    .filter(|hours| *hours > 0)
+                    .unwrap_or(24);
+
+                tokio::spawn(async move {
+                    loop {
+                        match storage_manager.apply_retention(&policy).await {
+                            Ok(report) => info!(
+                                "Retention removed {} klines and {} trades, {} files removed, {} rewritten",
+                                report.klines_removed,
+                                report.trades_removed,
+                                report.files_removed,
+                                report.files_rewritten
+                            ),
+                            Err(e) => info!("Unable to apply retention, e: {e}"),
+                        }
+
+                        tokio::time::sleep(Duration::from_secs(hours * 60 * 60)).await;
+                    }
+                });
+            }
+            Ok(_) => {}
+            Err(e) => info!("Retention disabled, e: {e}"),
+        }
+    }
+
+    /// Does the work of a scheduled job.
+    ///
+    /// # Returns
+    ///
+    /// What the job did, or why it failed.
+    async fn run_job(&mut self, job: JobKind) -> Result<String, String> {
+        match job {
+            JobKind::CompactKlines => {
+                let report = self
+                    .storage_manager
+                    .compact_klines()
+                    .await
+                    .map_err(|e| e.to_string())?;
+                Ok(format!(
+                    "Compacted {} of {} kline files, removed {} duplicates",
+                    report.files_compacted, report.files_checked, report.duplicates_removed
+                ))
+            }
+            JobKind::ApplyRetention => {
+                let policy = RetentionPolicy::from_env()?;
+                if policy.is_empty() {
+                    return Err("No retention is configured".to_string());
+                }
+                let report = self
+                    .storage_manager
+                    .apply_retention(&policy)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                Ok(format!(
+                    "Removed {} klines and {} trades",
+                    report.klines_removed, report.trades_removed
+                ))
+            }
+            JobKind::DailySummary => {
+                self.publish_daily_summaries().await;
+                Ok("Published daily summaries".to_string())
+            }
+            JobKind::DailyReport => {
+                let report = self.daily_report().await;
+                self.events.publish(BotEvent::DailyReport(report.clone()));
+                self.storage_manager
+                    .save_daily_report(report.clone())
+                    .await
+                    .map_err(|e| format!("Unable to save daily report, e: {e}"))?;
+                Ok(format!(
+                    "Reported {} trades of {} strategies",
+                    report.total.trades,
+                    report.strategies.len()
+                ))
+            }
+            JobKind::SyncBalance => {
+                let balance = self
+                    .exchange_cache
+                    .lock()
+                    .await
+                    .refresh_balance(&**self.exchange_api)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                Ok(format!("Synced balance of {balance:.2}"))
+            }
+            JobKind::Backup => {
+                let backup = self.create_backup().await.map_err(|e| e.to_string())?;
+                Ok(format!("Created backup {}", backup.name))
+            }
+            JobKind::Snapshot => {
+                let snapshot = self.create_snapshot().await.map_err(|e| e.to_string())?;
+                Ok(format!("Created snapshot {}", snapshot.name))
+            }
+            JobKind::FlushMarketData => {
+                self.market.flush_data().await.map_err(|e| e.to_string())?;
+                Ok("Flushed market data".to_string())
+            }
+            JobKind::StartStrategy(definition) => {
+                let info = self
+                    .start_strategy(
+                        &definition.strategy_name,
+                        &definition.symbol,
+                        definition.interval,
+                        definition.settings,
+                        definition.algorithm_params,
+                    )
+                    .await
+                    .map_err(|e| e.to_string())?;
+                Ok(format!(
+                    "Started strategy {} on {} {} ({})",
+                    info.name, info.symbol, info.interval, info.id
+                ))
+            }
+            JobKind::StopStrategies {
+                strategy_name,
+                symbol,
+                close_positions,
+            } => {
+                let mut stopped = 0;
+                for strategy_id in self.get_active_strategy_ids().await {
+                    let Some(info) = self.get_strategy_info(strategy_id).await else {
+                        continue;
+                    };
+                    if strategy_name
+                        .as_ref()
+                        .is_some_and(|name| *name != info.name)
+                        || symbol.as_ref().is_some_and(|symbol| *symbol != info.symbol)
+                    {
+                        continue;
+                    }
+                    if self
+                        .stop_strategy(strategy_id, close_positions)
+                        .await
+                        .is_some()
+                    {
+                        stopped += 1;
+                    }
+                }
+                Ok(format!("Stopped {stopped} strategies"))
+            }
+        }
+    }
+
+    /// Starts a strategy, registers it with the strategy manager and persists its
+    /// definition so it can be resumed after a restart.
+    async fn launch_strategy(&mut self, mut strategy: Strategy) -> StrategyInfo {
+        let handle = strategy.start().await;
+
+        let strategy_info = strategy.info().await;
+
+        self.strategy_manager.write().await.insert(strategy, handle);
+
+        self.save_active_strategy(strategy_info.clone()).await;
+        self.events
+            .publish(BotEvent::StrategyStarted(strategy_info.clone()));
+
+        strategy_info
+    }
+
+    fn account_for(&self, settings: &StrategySettings) -> ArcMutex<Account> {
+        self.accounts().account_for(Some(settings))
+    }
+
+    /// Fetches the k-lines of a historical range, empty if none are available.
+    async fn klines_range(
+        &self,
+        symbol: &str,
+        interval: Interval,
+        from_ts: u64,
+        to_ts: u64,
+    ) -> Vec<Kline> {
+        self.market
+            .kline_data_range(symbol, interval, Some(from_ts), Some(to_ts), None)
+            .await
+            .map(|kline_data| kline_data.klines())
+            .unwrap_or_default()
+    }
+
+    fn back_test_runner(
+        &self,
+        strategy_name: &str,
+        symbol: &str,
+        interval: Interval,
+        settings: StrategySettings,
+    ) -> BackTestRunner {
+        BackTestRunner::new(
+            strategy_name,
+            symbol,
+            interval,
+            self.strategy_tx.clone(),
+            self.market.clone(),
+            settings,
+        )
+    }
+
+    async fn save_back_test(storage_manager: Arc<Box<dyn StorageManager>>, record: BackTestRecord) {
+        if let Err(e) = storage_manager.save_back_test(record).await {
+            info!("Unable to save back test: {e}");
+        }
+    }
+
+    async fn save_active_strategy(&self, info: StrategyInfo) {
+        let strategy_id = info.id;
+        if let Err(e) = self.storage_manager.save_active_strategy(info).await {
+            info!("Unable to persist active strategy {strategy_id}, e: {e}");
+        }
+    }
+
+    /// Replaces the stored active strategies, the strategies relaunched by `resume_strategies`.
+    async fn replace_active_strategies(&self, strategies: &[StrategyInfo]) -> RaderBotResult<()> {
+        for info in self.storage_manager.list_active_strategies().await? {
+            self.storage_manager.remove_active_strategy(info.id).await?;
+        }
+        for info in strategies {
+            self.storage_manager
+                .save_active_strategy(info.clone())
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Counts the open positions of every account.
+    async fn count_open_positions(&self) -> usize {
+        let mut open_positions = 0;
+        for (_name, account) in self.accounts().named() {
+            open_positions += account.lock().await.positions().len();
+        }
+
+        open_positions
+    }
+
+    /// Posts events to the configured Discord channels and webhook endpoints.
+    ///
+    /// # Returns
+    ///
+    /// The reasons notifications are disabled, if any.
+    fn spawn_notifiers(&mut self) -> Vec<String> {
+        let mut errors = vec![];
+
+        match DiscordChannel::from_env() {
+            Ok(channels) if !channels.is_empty() => {
+                info!("Posting events to {} Discord channels", channels.len());
+                self.notifiers
+                    .push(DiscordNotifier::new(channels).spawn(self.events.subscribe()));
+            }
+            Ok(_) => {}
+            Err(e) => errors.push(format!("Discord notifications disabled, e: {e}")),
+        }
+
+        match WebhookEndpoint::from_env() {
+            Ok(endpoints) if !endpoints.is_empty() => {
+                info!("Posting events to {} webhooks", endpoints.len());
+                self.notifiers
+                    .push(WebhookNotifier::new(endpoints).spawn(self.events.subscribe()));
+            }
+            Ok(_) => {}
+            Err(e) => errors.push(format!("Webhook notifications disabled, e: {e}")),
+        }
+
+        errors
+    }
+
+    /// Relaunches every strategy that was still running when the bot last stopped. Each strategy
+    /// is warmed up with its stored k-lines before it starts, and the streams of the resumed
+    /// strategies and of the open positions restored by the accounts are reopened, so open
+    /// positions are managed again after a crash or deploy.
+    async fn resume_strategies(&mut self) {
+        let strategies = match self.storage_manager.list_active_strategies().await {
+            Ok(strategies) => strategies,
+            Err(e) => {
+                info!("Unable to list active strategies, e: {e}");
+                return;
+            }
+        };
+
+        let accounts = self.accounts();
+        let mut resumed = BTreeSet::new();
+        let mut kline_streams = BTreeSet::new();
+        let mut ticker_streams = BTreeSet::new();
+        for info in strategies {
+            let strategy_id = info.id;
+            if let Err(e) = accounts.check_profile(&info.settings) {
+                info!("Unable to resume strategy {strategy_id}, e: {e}");
+                continue;
+            }
+            if let Err(e) = accounts.check_execution(&info.settings).await {
+                info!("Unable to resume strategy {strategy_id}, e: {e}");
+                continue;
+            }
+            match Strategy::from_info(info, self.strategy_tx.clone(), self.market.clone()) {
+                Ok(strategy) => {
+                    let warm_up_klines = strategy.warm_up().await;
+                    let info = self.launch_strategy(strategy).await;
+                    info!(
+                        "Resumed strategy {} {} {} {}, warmed up with {warm_up_klines} klines",
+                        info.id, info.name, info.symbol, info.interval
+                    );
+                    resumed.insert(info.id);
+                    kline_streams.insert((info.symbol.clone(), info.interval));
+                    ticker_streams.insert(info.symbol);
+                }
+                Err(e) => {
+                    info!("Unable to resume strategy {strategy_id}, e: {e}");
+                }
+            }
+        }
+
+        // open positions need prices for their exits, whether or not their strategy resumed
+        for (name, account) in accounts.named() {
+            for position in account.lock().await.positions() {
+                ticker_streams.insert(position.symbol.clone());
+                if let Some(strategy_id) = position.strategy_id {
+                    // funding arbitrage legs are managed by the funding monitor
+                    if !resumed.contains(&strategy_id) && strategy_id != FUNDING_ARBITRAGE_ID {
+                        warn!(
+                            "Position {} on the {name} account belongs to strategy {strategy_id} which didn't resume, only its exits are managed",
+                            position.id
+                        );
+                    }
+                }
+            }
+        }
+
+        let market = &self.market;
+        for (symbol, interval) in &kline_streams {
+            market
+                .add_needed_stream(symbol, StreamType::Kline, Some(*interval))
+                .await;
+        }
+        for symbol in &ticker_streams {
+            market
+                .add_needed_stream(symbol, StreamType::Ticker, None)
+                .await;
+        }
     }
 }
 
+/// Stops the bot from trading live on a storage that can't persist positions, positions opened
+/// before a restart would be left on the exchange without being managed.
+///
+/// # Arguments
+///
+/// * `error` - The error restoring the account history.
+/// * `dry_run` - Whether the account only simulates its orders.
+fn refuse_live_trading(error: &StorageError, dry_run: bool) -> RaderBotResult<()> {
+    if !dry_run && matches!(error, StorageError::Unsupported(_)) {
+        return Err(RaderBotError::Config(format!(
+            "Unable to trade live without persisting positions, e: {error}"
+        )));
+    }
+
+    Ok(())
+}
+
 /// Manages multiple trading strategies by storing their handles, settings, and providing methods for insertion, removal, and retrieval.
 pub struct StrategyManager {
     /// A mapping of strategy IDs to their corresponding join handles for managing strategy execution.
@@ -400,6 +2531,27 @@ impl StrategyManager {
         self.signal_manager.remove_strategy_settings(strategy_id);
     }
 
+    /// Changes the settings of a strategy and the settings the signal manager trades it with.
+    /// The shadow mode is kept, as the positions of a strategy live on a single account.
+    ///
+    /// # Returns
+    ///
+    /// The updated strategy, if found.
+    pub fn change_settings(
+        &mut self,
+        strategy_id: &StrategyId,
+        mut settings: StrategySettings,
+    ) -> Option<&mut Strategy> {
+        let strategy = self.strategies.get_mut(strategy_id)?;
+        settings.shadow = strategy.settings().shadow;
+
+        self.signal_manager
+            .add_strategy_settings(strategy_id, settings.clone());
+        strategy.change_settings(settings);
+
+        Some(strategy)
+    }
+
     /// Retrieves the join handle and mutable reference to a strategy with the specified ID, if present.
     ///
     /// # Arguments
@@ -408,8 +2560,22 @@ impl StrategyManager {
     ///
     /// # Returns
     ///
-    /// A tuple containing the join handle and mutable reference to the strategy, if found.
-    pub fn get(&mut self, strategy_id: &StrategyId) -> Option<(&JoinHandle<()>, &mut Strategy)> {
+    /// A tuple containing the join handle and a reference to the strategy, if found.
+    pub fn get(&self, strategy_id: &StrategyId) -> Option<(&JoinHandle<()>, &Strategy)> {
+        if let (Some(handle), Some(strategy)) = (
+            self.strategy_handles.get(strategy_id),
+            self.strategies.get(strategy_id),
+        ) {
+            return Some((handle, strategy));
+        }
+        None
+    }
+
+    /// Retrieves a strategy to change it, e.g. to stop it, see `get`.
+    pub fn get_mut(
+        &mut self,
+        strategy_id: &StrategyId,
+    ) -> Option<(&JoinHandle<()>, &mut Strategy)> {
         if let (Some(handle), Some(strategy)) = (
             self.strategy_handles.get(strategy_id),
             self.strategies.get_mut(strategy_id),
@@ -419,6 +2585,54 @@ impl StrategyManager {
         None
     }
 
+    /// Lists the strategies whose task ended while they were running, with the reason if their
+    /// algorithm panicked.
+    pub fn finished(&self) -> Vec<(StrategyId, Option<String>)> {
+        self.strategy_handles
+            .iter()
+            .filter(|(_strategy_id, handle)| handle.is_finished())
+            .filter_map(|(strategy_id, _handle)| {
+                let strategy = self.strategies.get(strategy_id)?;
+                Some((*strategy_id, strategy.failure()))
+            })
+            .collect()
+    }
+
+    /// Starts the task of a strategy again, see `Strategy::start`.
+    ///
+    /// # Returns
+    ///
+    /// Whether the strategy is managed.
+    pub async fn restart(&mut self, strategy_id: &StrategyId) -> bool {
+        let Some(strategy) = self.strategies.get_mut(strategy_id) else {
+            return false;
+        };
+
+        let handle = strategy.start().await;
+        self.strategy_handles.insert(*strategy_id, handle);
+
+        true
+    }
+
+    /// Aborts the tasks of all strategies and removes them from the manager, without stopping
+    /// them, so their positions are left as they are.
+    ///
+    /// # Returns
+    ///
+    /// The number of aborted strategies.
+    pub fn abort_all(&mut self) -> usize {
+        for handle in self.strategy_handles.values() {
+            handle.abort();
+        }
+
+        let strategy_ids = self.list_ids();
+        for strategy_id in &strategy_ids {
+            self.remove(strategy_id);
+        }
+
+        strategy_ids.len()
+    }
+
     /// Retrieves a list of strategy IDs currently managed by the manager.
     ///
     /// # Returns
@@ -433,6 +2647,19 @@ impl StrategyManager {
         strategies
     }
 
+    /// Lists the symbols traded by the managed strategies, sorted and without duplicates.
+    pub fn symbols(&self) -> Vec<String> {
+        let mut symbols: Vec<String> = self
+            .strategies
+            .values()
+            .map(|strategy| strategy.symbol.clone())
+            .collect();
+        symbols.sort();
+        symbols.dedup();
+
+        symbols
+    }
+
     /// Retrieves a reference to the signal manager associated with this strategy manager.
     ///
     /// # Returns
@@ -441,4 +2668,9 @@ impl StrategyManager {
     pub fn get_signal_manager(&self) -> &SignalManager {
         &self.signal_manager
     }
+
+    /// Retrieves a mutable reference to the signal manager associated with this strategy manager.
+    pub fn get_signal_manager_mut(&mut self) -> &mut SignalManager {
+        &mut self.signal_manager
+    }
 }
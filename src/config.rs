@@ -0,0 +1,305 @@
+//! # Configuration Reload
+//!
+//! The bot is configured by the variables of its `.env` file. Changes to the variables listed in
+//! `RELOADABLE_PREFIXES` are applied at runtime by `POST /admin/reload-config`, or when the file
+//! changes if `CONFIG_WATCH_INTERVAL_SECS` is set, without restarting strategies or dropping
+//! streams: the log level, the Discord and webhook notifications, the settings of TradingView
+//! alerts, the admin token, the fee rate of the daily report and the snapshot settings. Changes
+//! to any other variable are reported and take effect on the next start.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::{OnceLock, RwLock};
+use std::time::{Duration, SystemTime};
+
+use log::{info, warn, Log, Metadata, Record};
+use serde::Serialize;
+use tokio::task::JoinHandle;
+
+use crate::bot::RaderBot;
+use crate::health;
+use crate::market::types::ArcMutex;
+use crate::utils::env::env_lookup;
+
+/// Prefixes of the variables applied by a reload.
+const RELOADABLE_PREFIXES: [&str; 7] = [
+    "RUST_LOG",
+    "ADMIN_API_TOKEN",
+    "TRADING_FEE_RATE",
+    "DISCORD_",
+    "NOTIFY_WEBHOOK",
+    "TRADINGVIEW_",
+    "SNAPSHOT_",
+];
+
+/// Checks whether a change to a variable is applied by a reload.
+pub fn is_reloadable(key: &str) -> bool {
+    RELOADABLE_PREFIXES
+        .iter()
+        .any(|prefix| key.starts_with(prefix))
+}
+
+/// The outcome of a reload. Only variable names are reported, as values may be secrets.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct ConfigReloadReport {
+    /// Changed variables that were applied.
+    pub applied: Vec<String>,
+    /// Changed variables that take effect on the next start.
+    pub requires_restart: Vec<String>,
+    /// Applied changes that were invalid, the previous configuration is kept for those.
+    pub errors: Vec<String>,
+}
+
+/// The variables of the configuration file as last applied.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigFile {
+    path: PathBuf,
+    values: BTreeMap<String, String>,
+}
+
+impl ConfigFile {
+    /// Reads the file in `CONFIG_FILE`, `.env` in the working directory by default.
+    pub fn from_env() -> Self {
+        let path = env_lookup("CONFIG_FILE").unwrap_or_else(|| ".env".to_string());
+        Self::new(PathBuf::from(path))
+    }
+
+    /// Reads a configuration file, a missing or invalid file has no variables.
+    pub fn new(path: PathBuf) -> Self {
+        let values = read_values(&path).unwrap_or_default();
+        Self { path, values }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The time the file was last modified, `None` if it doesn't exist.
+    pub fn modified(&self) -> Option<SystemTime> {
+        std::fs::metadata(&self.path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    }
+
+    /// Reads the file again and sets the reloadable variables that changed in the environment.
+    ///
+    /// # Returns
+    ///
+    /// The `ConfigReloadReport` of the changes, or an error if the file can't be read.
+    pub fn reload(&mut self) -> Result<ConfigReloadReport, String> {
+        let values = read_values(&self.path)?;
+
+        Ok(self.apply(values, |key, value| match value {
+            Some(value) => std::env::set_var(key, value),
+            None => std::env::remove_var(key),
+        }))
+    }
+
+    /// Applies the reloadable variables that differ from the last applied values, see `reload`.
+    ///
+    /// # Arguments
+    ///
+    /// * `values` - The variables of the file.
+    /// * `set_var` - Sets a variable, or removes it if its value is `None`.
+    pub fn apply(
+        &mut self,
+        values: BTreeMap<String, String>,
+        mut set_var: impl FnMut(&str, Option<&str>),
+    ) -> ConfigReloadReport {
+        let keys: BTreeSet<String> = self.values.keys().chain(values.keys()).cloned().collect();
+
+        let mut report = ConfigReloadReport::default();
+        for key in keys {
+            let value = values.get(&key);
+            if self.values.get(&key) == value {
+                continue;
+            }
+            // other changes are reported again until the bot restarts with them
+            if !is_reloadable(&key) {
+                report.requires_restart.push(key);
+                continue;
+            }
+
+            set_var(&key, value.map(String::as_str));
+            match value {
+                Some(value) => self.values.insert(key.clone(), value.clone()),
+                None => self.values.remove(&key),
+            };
+            report.applied.push(key);
+        }
+
+        report
+    }
+}
+
+/// Initializes logging with the filter in `RUST_LOG`, which `reload_log_filter` replaces at
+/// runtime.
+pub fn init_logger() {
+    let logger = LOGGER.get_or_init(|| ReloadableLogger {
+        inner: RwLock::new(env_logger::Builder::from_default_env().build()),
+    });
+
+    if log::set_logger(logger).is_ok() {
+        log::set_max_level(logger.inner().filter());
+    }
+}
+
+/// Replaces the log filter by the one now in `RUST_LOG`.
+pub fn reload_log_filter() {
+    let Some(logger) = LOGGER.get() else {
+        return;
+    };
+
+    let new_logger = env_logger::Builder::from_default_env().build();
+    log::set_max_level(new_logger.filter());
+    *logger.inner.write().unwrap_or_else(|e| e.into_inner()) = new_logger;
+}
+
+/// Reloads the configuration of a bot whenever its file changes, checking every
+/// `CONFIG_WATCH_INTERVAL_SECS` seconds.
+///
+/// # Returns
+///
+/// The handle of the task watching the file, `None` if `CONFIG_WATCH_INTERVAL_SECS` isn't set.
+pub fn spawn_config_watcher(bot: ArcMutex<RaderBot>) -> Option<JoinHandle<()>> {
+    let interval = env_lookup("CONFIG_WATCH_INTERVAL_SECS")
+        .and_then(|secs| secs.parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .map(Duration::from_secs)?;
+
+    Some(tokio::spawn(async move {
+        health::runtime().register_task("config_watcher", Some(interval));
+
+        let mut modified = bot.lock().await.config_file().modified();
+        loop {
+            tokio::time::sleep(interval).await;
+            let _iteration = health::time_iteration("config_watcher");
+
+            let mut bot = bot.lock().await;
+            let last_modified = bot.config_file().modified();
+            if last_modified == modified {
+                continue;
+            }
+            modified = last_modified;
+
+            match bot.reload_config().await {
+                Ok(report) => {
+                    if !report.applied.is_empty() {
+                        info!("Applied changed config {}", report.applied.join(", "));
+                    }
+                    if !report.requires_restart.is_empty() {
+                        info!(
+                            "Changed config {} takes effect on restart",
+                            report.requires_restart.join(", ")
+                        );
+                    }
+                    for error in &report.errors {
+                        warn!("Config reload error: {error}");
+                    }
+                }
+                Err(e) => warn!("Unable to reload config, e: {e}"),
+            }
+        }
+    }))
+}
+
+// ---
+// Private Methods
+// ---
+
+static LOGGER: OnceLock<ReloadableLogger> = OnceLock::new();
+
+/// A logger whose filter can be replaced while the bot runs.
+struct ReloadableLogger {
+    inner: RwLock<env_logger::Logger>,
+}
+
+impl ReloadableLogger {
+    fn inner(&self) -> std::sync::RwLockReadGuard<'_, env_logger::Logger> {
+        self.inner.read().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Log for ReloadableLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.inner().enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        self.inner().log(record)
+    }
+
+    fn flush(&self) {
+        self.inner().flush()
+    }
+}
+
+fn read_values(path: &Path) -> Result<BTreeMap<String, String>, String> {
+    dotenvy::from_path_iter(path)
+        .map_err(|e| format!("Unable to read {}, e: {e}", path.display()))?
+        .map(|item| item.map_err(|e| format!("Invalid line in {}, e: {e}", path.display())))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(vars: &[(&str, &str)]) -> BTreeMap<String, String> {
+        vars.iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    /// Tests only changed reloadable variables are applied and other changes are reported until
+    /// they are reverted.
+    #[test]
+    fn test_config_reload() {
+        let mut config = ConfigFile {
+            path: PathBuf::from(".env"),
+            values: values(&[
+                ("RUST_LOG", "info"),
+                ("STORAGE_TYPE", "FS"),
+                ("DISCORD_CHANNELS", "ops"),
+            ]),
+        };
+
+        let mut set = vec![];
+        let report = config.apply(
+            values(&[
+                ("RUST_LOG", "debug"),
+                ("STORAGE_TYPE", "POSTGRES"),
+                ("TRADINGVIEW_LEVERAGE", "2"),
+            ]),
+            |key, value| set.push((key.to_string(), value.map(str::to_string))),
+        );
+        assert_eq!(
+            report.applied,
+            vec!["DISCORD_CHANNELS", "RUST_LOG", "TRADINGVIEW_LEVERAGE"]
+        );
+        assert_eq!(report.requires_restart, vec!["STORAGE_TYPE"]);
+        assert_eq!(set[0], ("DISCORD_CHANNELS".to_string(), None));
+        assert_eq!(set[1], ("RUST_LOG".to_string(), Some("debug".to_string())));
+
+        let report = config.apply(
+            values(&[
+                ("RUST_LOG", "debug"),
+                ("STORAGE_TYPE", "POSTGRES"),
+                ("TRADINGVIEW_LEVERAGE", "2"),
+            ]),
+            |_, _| panic!("unchanged variables are not set again"),
+        );
+        assert!(report.applied.is_empty());
+        assert_eq!(report.requires_restart, vec!["STORAGE_TYPE"]);
+
+        let report = config.apply(
+            values(&[
+                ("RUST_LOG", "debug"),
+                ("STORAGE_TYPE", "FS"),
+                ("TRADINGVIEW_LEVERAGE", "2"),
+            ]),
+            |_, _| {},
+        );
+        assert_eq!(report, ConfigReloadReport::default());
+    }
+}
@@ -0,0 +1,163 @@
+//! # Errors
+//!
+//! The errors of the bot, one type per layer, all of which convert into `RaderBotError`:
+//!
+//! - `ApiError` of the exchange adapters, see `exchange::types`.
+//! - `StorageError` of the storage backends.
+//! - `StrategyError` of starting, changing and running strategies.
+//!
+//! Handlers of the HTTP API answer each of them with the same status code, see
+//! `api::error::ApiErrorResponse`.
+
+use std::io;
+
+use thiserror::Error;
+
+use crate::exchange::types::ApiError;
+use crate::strategy::strategy::StrategyId;
+use crate::strategy::types::AlgorithmError;
+
+/// Any error of the bot.
+#[derive(Debug, Error)]
+pub enum RaderBotError {
+    #[error(transparent)]
+    Exchange(#[from] ApiError),
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+    #[error(transparent)]
+    Strategy(#[from] StrategyError),
+    /// The configuration of the bot is invalid, e.g. a config file that can't be parsed.
+    #[error("Invalid configuration: {0}")]
+    Config(String),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+pub type RaderBotResult<T> = Result<T, RaderBotError>;
+
+/// An error of a storage backend.
+#[derive(Debug, Error)]
+pub enum StorageError {
+    /// The record asked for isn't stored, e.g. an unknown back test id.
+    #[error("{0} not found")]
+    NotFound(String),
+    /// The backend doesn't store this kind of record, e.g. strategy summaries on InfluxDB.
+    #[error("Not supported by {0}")]
+    Unsupported(&'static str),
+    /// The record or the request for it is invalid, e.g. a snapshot of a newer format.
+    #[error("{0}")]
+    Invalid(String),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("Serialization error: {0}")]
+    Serialization(String),
+    /// The database or object store failed or rejected the request.
+    #[error("Backend error: {0}")]
+    Backend(String),
+}
+
+pub type StorageResult<T> = Result<T, StorageError>;
+
+impl From<serde_json::Error> for StorageError {
+    fn from(e: serde_json::Error) -> Self {
+        StorageError::Serialization(e.to_string())
+    }
+}
+
+impl From<bincode::Error> for StorageError {
+    fn from(e: bincode::Error) -> Self {
+        StorageError::Serialization(e.to_string())
+    }
+}
+
+impl From<csv::Error> for StorageError {
+    fn from(e: csv::Error) -> Self {
+        StorageError::Serialization(e.to_string())
+    }
+}
+
+/// Blocking storage work run on its own thread failed to complete, e.g. it panicked.
+impl From<tokio::task::JoinError> for StorageError {
+    fn from(e: tokio::task::JoinError) -> Self {
+        StorageError::Backend(e.to_string())
+    }
+}
+
+#[cfg(feature = "mongo")]
+impl From<mongodb::error::Error> for StorageError {
+    fn from(e: mongodb::error::Error) -> Self {
+        StorageError::Backend(e.to_string())
+    }
+}
+
+#[cfg(feature = "mongo")]
+impl From<mongodb::bson::ser::Error> for StorageError {
+    fn from(e: mongodb::bson::ser::Error) -> Self {
+        StorageError::Serialization(e.to_string())
+    }
+}
+
+#[cfg(feature = "mongo")]
+impl From<mongodb::bson::de::Error> for StorageError {
+    fn from(e: mongodb::bson::de::Error) -> Self {
+        StorageError::Serialization(e.to_string())
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl From<sqlx::Error> for StorageError {
+    fn from(e: sqlx::Error) -> Self {
+        StorageError::Backend(e.to_string())
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl From<sqlx::migrate::MigrateError> for StorageError {
+    fn from(e: sqlx::migrate::MigrateError) -> Self {
+        StorageError::Backend(e.to_string())
+    }
+}
+
+#[cfg(feature = "object-store")]
+impl From<object_store::Error> for StorageError {
+    fn from(e: object_store::Error) -> Self {
+        match e {
+            object_store::Error::NotFound { path, .. } => StorageError::NotFound(path),
+            e => StorageError::Backend(e.to_string()),
+        }
+    }
+}
+
+#[cfg(feature = "object-store")]
+impl From<object_store::path::Error> for StorageError {
+    fn from(e: object_store::path::Error) -> Self {
+        StorageError::Backend(e.to_string())
+    }
+}
+
+#[cfg(feature = "influx")]
+impl From<reqwest::Error> for StorageError {
+    fn from(e: reqwest::Error) -> Self {
+        StorageError::Backend(e.to_string())
+    }
+}
+
+/// An error of starting, changing or running a strategy.
+#[derive(Debug, Error)]
+pub enum StrategyError {
+    #[error(transparent)]
+    Algorithm(#[from] AlgorithmError),
+    /// The settings of the strategy are invalid, e.g. an unknown credential profile.
+    #[error("Invalid settings: {0}")]
+    InvalidSettings(String),
+    /// A signal, e.g. of a TradingView alert, can't be handled as given.
+    #[error("Invalid signal: {0}")]
+    InvalidSignal(String),
+    #[error("Strategy {0} not found")]
+    NotFound(StrategyId),
+    /// The strategy can't handle the request in its current state, e.g. a disabled webhook.
+    #[error("{0}")]
+    Unavailable(String),
+}
+
+pub type StrategyResult<T> = Result<T, StrategyError>;
@@ -0,0 +1,235 @@
+//! # Bot Events
+//!
+//! The event bus of the bot. Accounts publish opened and closed positions and rejected orders,
+//! the `SignalManager` handled signals, the `Market` opened and closed streams and the errors the
+//! stream layer reports, and the bot equity samples, daily summaries and reports and strategy
+//! status changes. Subscribers such as the WebSocket push API and the notifiers receive every event
+//! published after they subscribed and pick the topics they handle. Actions among the events are
+//! also recorded in the audit log, see `audit`.
+
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::broadcast::{self, Receiver, Sender};
+
+use crate::{
+    account::{
+        account::{AccountEquity, TradingSummary},
+        daily_report::DailyReport,
+        trade::{OrderRecord, Position, TradeTx},
+    },
+    audit::AuditLog,
+    exchange::stream::StreamMeta,
+    strategy::{
+        signal::SignalRecord,
+        strategy::{StrategyId, StrategyInfo, StrategyParamsChange, StrategySettings},
+    },
+};
+
+/// Number of events buffered per subscriber, subscribers lagging further behind miss the
+/// oldest events.
+const EVENT_CAPACITY: usize = 1024;
+
+/// Topic events are grouped by, subscribers choose the topics they receive.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum EventTopic {
+    Signals,
+    Positions,
+    Equity,
+    Strategies,
+    Errors,
+    Summaries,
+    Streams,
+    Orders,
+    Actions,
+}
+
+impl FromStr for EventTopic {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "signals" => Ok(Self::Signals),
+            "positions" => Ok(Self::Positions),
+            "equity" => Ok(Self::Equity),
+            "strategies" => Ok(Self::Strategies),
+            "errors" => Ok(Self::Errors),
+            "summaries" => Ok(Self::Summaries),
+            "streams" => Ok(Self::Streams),
+            "orders" => Ok(Self::Orders),
+            "actions" => Ok(Self::Actions),
+            _ => Err(format!("Unknown event topic: {s}")),
+        }
+    }
+}
+
+/// An event published by the bot.
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum BotEvent {
+    /// A live signal was handled.
+    Signal(SignalRecord),
+    PositionOpened {
+        account: String,
+        position: Position,
+    },
+    PositionClosed {
+        account: String,
+        trade: TradeTx,
+    },
+    /// An order opening or closing a position was filled.
+    OrderFilled {
+        account: String,
+        order: OrderRecord,
+    },
+    Equity {
+        account: String,
+        equity: AccountEquity,
+    },
+    StrategyStarted(StrategyInfo),
+    StrategyStopped(StrategyInfo),
+    /// The risk settings of a running strategy changed.
+    SettingsChanged {
+        strategy_id: StrategyId,
+        settings: StrategySettings,
+    },
+    /// The algorithm params of a running strategy changed.
+    ParamsChanged(StrategyParamsChange),
+    /// An error the bot couldn't recover from on its own, such as an order the exchange
+    /// rejected.
+    Error {
+        /// Where the error occurred, e.g. the name of an account.
+        source: String,
+        message: String,
+    },
+    StreamOpened(StreamMeta),
+    StreamClosed(StreamMeta),
+    /// A stream failed to receive data, e.g. because its connection dropped. Streams the bot
+    /// needs are reopened by the market.
+    StreamError {
+        stream_id: String,
+        message: String,
+    },
+    /// The trading activity of an account over the last day.
+    DailySummary {
+        account: String,
+        summary: TradingSummary,
+    },
+    /// The profit and activity of every strategy over the last day.
+    DailyReport(DailyReport),
+    /// An operator intervened through the admin API, e.g. flattening all positions.
+    ManualAction {
+        /// What was done, e.g. `flatten_all`.
+        action: String,
+        /// The arguments and outcome of the action.
+        detail: Value,
+    },
+}
+
+impl BotEvent {
+    pub fn topic(&self) -> EventTopic {
+        match self {
+            BotEvent::Signal(_) => EventTopic::Signals,
+            BotEvent::PositionOpened { .. } | BotEvent::PositionClosed { .. } => {
+                EventTopic::Positions
+            }
+            BotEvent::Equity { .. } => EventTopic::Equity,
+            BotEvent::OrderFilled { .. } => EventTopic::Orders,
+            BotEvent::StrategyStarted(_)
+            | BotEvent::StrategyStopped(_)
+            | BotEvent::SettingsChanged { .. }
+            | BotEvent::ParamsChanged(_) => EventTopic::Strategies,
+            BotEvent::Error { .. } => EventTopic::Errors,
+            BotEvent::DailySummary { .. } | BotEvent::DailyReport(_) => EventTopic::Summaries,
+            BotEvent::StreamOpened(_)
+            | BotEvent::StreamClosed(_)
+            | BotEvent::StreamError { .. } => EventTopic::Streams,
+            BotEvent::ManualAction { .. } => EventTopic::Actions,
+        }
+    }
+}
+
+/// Publishes bot events to every subscriber. Clones publish to the same subscribers.
+#[derive(Debug, Clone)]
+pub struct EventPublisher {
+    sender: Sender<BotEvent>,
+    audit_log: Option<AuditLog>,
+}
+
+impl EventPublisher {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_CAPACITY);
+        Self {
+            sender,
+            audit_log: None,
+        }
+    }
+
+    /// Creates a publisher recording the actions among its events in an audit log.
+    pub fn with_audit_log(audit_log: AuditLog) -> Self {
+        Self {
+            audit_log: Some(audit_log),
+            ..Self::new()
+        }
+    }
+
+    /// Publishes an event, events published without subscribers are dropped. Actions are
+    /// recorded in the audit log either way.
+    pub fn publish(&self, event: BotEvent) {
+        if let Some(audit_log) = &self.audit_log {
+            audit_log.record(&event);
+        }
+        let _ = self.sender.send(event);
+    }
+
+    /// Subscribes to the events published from now on.
+    pub fn subscribe(&self) -> Receiver<BotEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Checks whether anyone receives published events, so events that are costly to build
+    /// can be skipped.
+    pub fn has_subscribers(&self) -> bool {
+        self.sender.receiver_count() > 0
+    }
+}
+
+impl Default for EventPublisher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests subscribers receive events published after they subscribed, tagged by type.
+    #[tokio::test]
+    async fn test_event_publisher() {
+        let events = EventPublisher::new();
+        let info = StrategyInfo::default();
+        events.publish(BotEvent::StrategyStarted(info.clone()));
+        assert!(!events.has_subscribers());
+
+        let mut receiver = events.subscribe();
+        assert!(events.has_subscribers());
+        events.publish(BotEvent::StrategyStopped(info.clone()));
+
+        let event = receiver.recv().await.unwrap();
+        assert_eq!(event.topic(), EventTopic::Strategies);
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], "strategy_stopped");
+        assert_eq!(json["data"]["id"], info.id.to_string());
+        assert!(receiver.try_recv().is_err());
+
+        let error = BotEvent::StreamError {
+            stream_id: "BTCUSDT@kline_1m".to_string(),
+            message: "Connection reset".to_string(),
+        };
+        assert_eq!(error.topic(), "streams".parse().unwrap());
+        assert!("stream".parse::<EventTopic>().is_err());
+    }
+}
@@ -4,15 +4,21 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use std::{error::Error, fmt};
+use url::form_urlencoded::byte_serialize;
 
 use crate::{
-    account::trade::{OrderSide, Position, TradeTx},
-    market::{kline::Kline, ticker::Ticker, types::ArcMutex},
+    account::{
+        money::{Price, SymbolPrecision},
+        trade::{OrderSide, Position, TradeTx},
+    },
+    market::{interval::Interval, kline::Kline, ticker::Ticker, types::ArcMutex},
+    strategy::funding::FundingRate,
+    utils::{crypt::sign_hmac, time::generate_ts},
 };
 
 use super::{
     stream::{StreamManager, StreamMeta},
-    types::{ApiResult, StreamType},
+    types::{self, ApiResult, StreamType},
 };
 
 /// Represents an error encountered within the API operations.
@@ -64,6 +70,7 @@ pub trait ExchangeApi: Send + Sync {
     /// * `leverage` - The leverage to apply to the position.
     /// * `order_side` - The side of the order (`OrderSide::Buy` or `OrderSide::Sell`).
     /// * `open_price` - The price at which to open the position.
+    /// * `precision` - The precision of the symbol, the quantity ordered is rounded down to it.
     ///
     /// # Returns
     ///
@@ -75,7 +82,8 @@ pub trait ExchangeApi: Send + Sync {
         margin_usd: f64,
         leverage: u32,
         order_side: OrderSide,
-        open_price: f64,
+        open_price: Price,
+        precision: SymbolPrecision,
     ) -> ApiResult<Position>;
 
     /// Closes an existing position at the specified price.
@@ -89,7 +97,7 @@ pub trait ExchangeApi: Send + Sync {
     ///
     /// A `Result` containing the trade transaction as `TradeTx` if successful, or an `ApiError` otherwise.
 
-    async fn close_position(&self, position: Position, close_price: f64) -> ApiResult<TradeTx>;
+    async fn close_position(&self, position: Position, close_price: Price) -> ApiResult<TradeTx>;
 
     /// Retrieves all orders for the account.
     ///
@@ -107,6 +115,26 @@ pub trait ExchangeApi: Send + Sync {
 
     async fn list_open_orders(&self) -> ApiResult<Value>;
 
+    /// Cancels all working orders of a symbol.
+    ///
+    /// # Arguments
+    ///
+    /// * `symbol` - A string slice representing the trading pair.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the response of the exchange as `Value` if successful, or an `ApiError` otherwise.
+    async fn cancel_all_orders(&self, symbol: &str) -> ApiResult<Value>;
+
+    /// Lists the positions held on the exchange.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing every non-empty position as `ExchangePosition` if successful, or an `ApiError` otherwise.
+    async fn open_positions(&self) -> ApiResult<Vec<ExchangePosition>> {
+        Err(types::ApiError::Unsupported("Open positions".to_string()))
+    }
+
     /// Retrieves the stream manager instance.
     ///
     /// # Returns
@@ -136,13 +164,13 @@ pub trait ExchangeApi: Send + Sync {
     /// # Arguments
     ///
     /// * `symbol` - A string slice representing the trading pair.
-    /// * `interval` - A string slice representing the k-line interval.
+    /// * `interval` - The k-line interval.
     ///
     /// # Returns
     ///
     /// A `Result` containing the k-line as `Kline` if successful, or an `ApiError` otherwise.
 
-    async fn get_kline(&self, symbol: &str, interval: &str) -> ApiResult<Kline>;
+    async fn get_kline(&self, symbol: &str, interval: Interval) -> ApiResult<Kline>;
 
     /// Retrieves the ticker information for a specific symbol.
     ///
@@ -156,6 +184,21 @@ pub trait ExchangeApi: Send + Sync {
 
     async fn get_ticker(&self, symbol: &str) -> ApiResult<Ticker>;
 
+    /// Retrieves the latest funding rate of a perpetual futures symbol.
+    ///
+    /// # Arguments
+    ///
+    /// * `symbol` - A string slice representing the trading pair.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `FundingRate` if successful, or an `ApiError` otherwise.
+    async fn get_funding_rate(&self, symbol: &str) -> ApiResult<FundingRate> {
+        Err(types::ApiError::Unsupported(format!(
+            "Funding rate of {symbol}"
+        )))
+    }
+
     /// Retrieves information about the exchange.
     ///
     /// # Returns
@@ -170,7 +213,7 @@ pub trait ExchangeApi: Send + Sync {
     ///
     /// * `symbol` - A string slice representing the trading pair.
     /// * `stream_type` - The type of stream to subscribe to.
-    /// * `interval` - An optional string slice representing the interval for k-line streams.
+    /// * `interval` - The interval for k-line streams.
     ///
     /// # Returns
     ///
@@ -180,63 +223,179 @@ pub trait ExchangeApi: Send + Sync {
         &self,
         symbol: &str,
         stream_type: StreamType,
-        interval: Option<&str>,
+        interval: Option<Interval>,
     ) -> String;
 }
 
-/// A utility for constructing query strings from key-value pairs.
+/// Builds the query strings of requests to the exchanges, signed for private endpoints.
 ///
-/// This struct is used to assemble a query string for HTTP requests by accepting a vector of key-value pairs (`&str`). The `ToString` trait implementation concatenates these pairs into a well-formed query string.
-
-pub struct QueryStr<'a> {
-    params: Vec<(&'a str, &'a str)>,
+/// Values are URL-encoded, so parameters with reserved characters such as `&`, `=` or spaces
+/// are sent and signed as the exchange reads them. Parameters keep the order they are added in,
+/// unless sorted by key for exchanges that expect it.
+///
+/// # Examples
+///
+/// ```ignore
+/// let query_str = SignedRequestBuilder::new()
+///     .param("symbol", "BTCUSDT")
+///     .timestamp()
+///     .sign(secret_key);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SignedRequestBuilder {
+    params: Vec<(String, String)>,
+    sort_params: bool,
+    sign_raw: bool,
 }
 
-impl<'a> QueryStr<'a> {
-    /// Creates a new instance of `QueryStr` with the given parameters.
-    ///
-    /// # Arguments
-    ///
-    /// * `params` - A vector of tuples, where each tuple consists of a reference to a string slice representing the key and a reference to a string slice representing the value.
-    ///
-    /// # Returns
-    ///
-    /// Returns a `QueryStr` instance containing the provided parameters for query string construction.
+impl SignedRequestBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-    pub fn new(params: Vec<(&'a str, &'a str)>) -> Self {
-        Self { params }
+    /// Adds a parameter, replacing an earlier one with the same key.
+    pub fn param(mut self, key: &str, value: impl ToString) -> Self {
+        let value = value.to_string();
+        match self.params.iter_mut().find(|(k, _)| k == key) {
+            Some((_, v)) => *v = value,
+            None => self.params.push((key.to_string(), value)),
+        }
+        self
     }
-}
 
-/// Converts the stored key-value pairs into a single query string.
-///
-/// This method concatenates all key-value pairs into a query string format, separating keys from values with `=` and pairs from each other with `&`. The final string does not end with `&`.
-///
-/// # Returns
-///
-/// Returns a `String` representing the assembled query string.
+    /// Adds the current time in milliseconds as the `timestamp` parameter.
+    pub fn timestamp(self) -> Self {
+        self.param("timestamp", generate_ts())
+    }
 
-impl<'a> ToString for QueryStr<'a> {
-    fn to_string(&self) -> String {
-        let str_vec: Vec<String> = self
-            .params
-            .iter()
-            .map(|(key, val)| format!("{key}={val}&"))
-            .collect();
+    /// Sorts the parameters by key, e.g. for BingX, which signs the parameters in key order.
+    pub fn sort_params(mut self) -> Self {
+        self.sort_params = true;
+        self
+    }
+
+    /// Signs the parameters before they are URL-encoded, e.g. for BingX. Binance signs the query
+    /// string as it is sent.
+    pub fn sign_raw(mut self) -> Self {
+        self.sign_raw = true;
+        self
+    }
+
+    /// The URL-encoded query string, without a signature.
+    pub fn query_str(&self) -> String {
+        self.join(true)
+    }
 
-        let mut query_str = str_vec.join("");
+    /// The URL-encoded query string, followed by its HMAC SHA256 `signature` with the secret key.
+    pub fn sign(&self, secret_key: &str) -> String {
+        let query_str = self.query_str();
+        let signature = sign_hmac(secret_key, &self.join(!self.sign_raw));
+        if query_str.is_empty() {
+            return format!("signature={signature}");
+        }
+        format!("{query_str}&signature={signature}")
+    }
 
-        // remove last & from query_str
-        query_str.pop();
-        query_str
+    // ---
+    // Private Methods
+    // ---
+
+    fn join(&self, encode: bool) -> String {
+        let mut params: Vec<&(String, String)> = self.params.iter().collect();
+        if self.sort_params {
+            params.sort_by(|(a, _), (b, _)| a.cmp(b));
+        }
+
+        let encode = |value: &str| -> String {
+            if encode {
+                byte_serialize(value.as_bytes()).collect()
+            } else {
+                value.to_string()
+            }
+        };
+        params
+            .iter()
+            .map(|(key, value)| format!("{}={}", encode(key), encode(value)))
+            .collect::<Vec<_>>()
+            .join("&")
     }
 }
 
 /// Represents exchange-specific information.
 ///
-/// This structure stores metadata about an exchange, such as its name. It is intended for serialization and deserialization of data related to exchange information.
-
-#[derive(Serialize, Deserialize)]
+/// This structure stores metadata about an exchange, such as its name and the symbols it lists. It is intended for serialization and deserialization of data related to exchange information.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct ExchangeInfo {
     pub name: String,
+    #[serde(default)]
+    pub symbols: Vec<SymbolInfo>,
+}
+
+/// Trading rules of a symbol listed on an exchange.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SymbolInfo {
+    pub symbol: String,
+    pub base_asset: String,
+    pub quote_asset: String,
+    /// Whether the symbol is currently open for trading.
+    pub trading: bool,
+    pub price_precision: u32,
+    pub quantity_precision: u32,
+    pub min_quantity: Option<f64>,
+    /// The highest leverage the exchange allows, `None` if unknown.
+    pub max_leverage: Option<u32>,
+}
+
+/// A position held on an exchange, the net quantity of a symbol on one side.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ExchangePosition {
+    pub symbol: String,
+    pub order_side: OrderSide,
+    pub quantity: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests parameters are encoded, ordered and signed over the string each exchange expects.
+    #[test]
+    fn test_signed_request_builder() {
+        let builder = SignedRequestBuilder::new()
+            .param("symbol", "BTCUSDT")
+            .param("side", "BUY")
+            .param("clientOrderId", "a&b=c d");
+        assert_eq!(
+            builder.query_str(),
+            "symbol=BTCUSDT&side=BUY&clientOrderId=a%26b%3Dc+d"
+        );
+
+        let signed = builder.sign("secret");
+        let query_str = builder.query_str();
+        assert_eq!(
+            signed,
+            format!("{query_str}&signature={}", sign_hmac("secret", &query_str))
+        );
+
+        let sorted = builder.clone().sort_params().sign_raw();
+        assert_eq!(
+            sorted.query_str(),
+            "clientOrderId=a%26b%3Dc+d&side=BUY&symbol=BTCUSDT"
+        );
+        let raw = "clientOrderId=a&b=c d&side=BUY&symbol=BTCUSDT";
+        assert!(sorted
+            .sign("secret")
+            .ends_with(&format!("&signature={}", sign_hmac("secret", raw))));
+
+        let replaced = SignedRequestBuilder::new()
+            .param("limit", 1)
+            .param("limit", 2);
+        assert_eq!(replaced.query_str(), "limit=2");
+
+        let timestamped = SignedRequestBuilder::new().timestamp();
+        assert!(timestamped.query_str().starts_with("timestamp="));
+        assert!(SignedRequestBuilder::new()
+            .sign("secret")
+            .starts_with("signature="));
+    }
 }
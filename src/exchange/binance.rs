@@ -14,23 +14,27 @@ use uuid::Uuid;
 use tokio_tungstenite::connect_async;
 use tokio_tungstenite::tungstenite::Message;
 
-use hmac::{Hmac, Mac};
-use sha2::Sha256;
-
+use crate::account::money::{Price, Qty, SymbolPrecision};
 use crate::account::trade::{OrderSide, Position, TradeTx};
-use crate::exchange::api::{ExchangeApi, QueryStr};
+use crate::exchange::api::{ExchangeApi, SignedRequestBuilder};
 use crate::exchange::types::ArcEsStreamSync;
+use crate::market::interval::Interval;
 use crate::market::messages::MarketMessage;
+use crate::market::symbol::Symbol;
 use crate::market::trade::Trade;
 use crate::market::types::{ArcMutex, ArcSender};
 use crate::market::{kline::Kline, ticker::Ticker};
+use crate::strategy::funding::FundingRate;
 use crate::utils::number::{parse_f64_from_lookup, parse_f64_from_value, parse_usize_from_value};
 use crate::utils::time::generate_ts;
 
-use super::api::ExchangeInfo;
+use super::api::{ExchangeInfo, ExchangePosition, SymbolInfo};
 
 use super::stream::{StreamManager, StreamMeta};
-use super::types::{ApiResult, StreamType};
+use super::types::{ApiError, ApiResult, StreamType};
+
+/// The endpoint of futures orders, positions are opened and closed with it.
+const BINANCE_ORDER_ENDPOINT: &str = "/fapi/v1/order";
 
 /// Represents the Binance API client for interacting with the Binance exchange.
 ///
@@ -132,7 +136,6 @@ impl BinanceApi {
         endpoint: &str,
         query_str: Option<&str>,
     ) -> Result<Response, reqwest::Error> {
-        // let signature = self.sign_query_str(query_str);
         let url = match query_str {
             Some(qs) => format!("{}{}?{}", self.host, endpoint, qs),
             None => format!("{}{}", self.host, endpoint),
@@ -168,6 +171,26 @@ impl BinanceApi {
             .await
     }
 
+    /// Performs an HTTP DELETE request to the specified endpoint.
+    ///
+    /// # Arguments
+    ///
+    /// * `endpoint` - A string slice specifying the endpoint for the DELETE request.
+    /// * `query_str` - A string slice containing the signed query of the request.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` with the response `Response` object if the request is successful, or an error of type `reqwest::Error` otherwise.
+    async fn delete(&self, endpoint: &str, query_str: &str) -> Result<Response, reqwest::Error> {
+        let url = format!("{}{}?{}", self.host, endpoint, query_str);
+
+        self.client
+            .delete(&url)
+            .headers(self.build_headers(true))
+            .send()
+            .await
+    }
+
     /// Processes the HTTP response, extracting the relevant data based on the content type.
     ///
     /// This method checks the content type of the response and accordingly parses the response body as either plain text or JSON. It is designed to handle different response formats gracefully, ensuring that the data is correctly extracted from various API endpoints.
@@ -195,39 +218,56 @@ impl BinanceApi {
         Ok(data)
     }
 
-    /// Signs a query string using the API secret key.
-    ///
-    /// This method is used to generate a signature for secured endpoints. The signature is generated using HMAC SHA256, based on the query string and the secret key.
-    ///
-    /// # Arguments
-    ///
-    /// * `query_str` - A string slice containing the query string to be signed.
+    /// Performs a signed HTTP GET request to the specified endpoint.
     ///
     /// # Returns
     ///
-    /// Returns a string representing the hexadecimal value of the signature.
-
-    fn sign_query_str(&self, query_str: &str) -> String {
-        // Create a new HMAC instance with SHA256
-        let mut hmac =
-            Hmac::<Sha256>::new_from_slice(self.secret_key.as_bytes()).expect("Invalid key length");
+    /// Returns an `ApiResult<Value>` with the parsed response.
+    async fn signed_get(&self, endpoint: &str) -> ApiResult<Value> {
+        let query_str = SignedRequestBuilder::new()
+            .timestamp()
+            .sign(&self.secret_key);
 
-        // Update the HMAC with the data
-        hmac.update(query_str.as_bytes());
+        let res = self.get(endpoint, Some(&query_str)).await?;
 
-        // Get the resulting HMAC value
-        let result = hmac.finalize();
+        self.handle_response(res).await
+    }
 
-        // Convert the HMAC value to a string
-        hex::encode(result.into_bytes())
+    /// Fetches the highest leverage of the first bracket of each symbol.
+    ///
+    /// # Returns
+    ///
+    /// Returns the maximum leverage by symbol.
+    async fn max_leverages(&self) -> ApiResult<HashMap<String, u32>> {
+        let data = self.signed_get("/fapi/v1/leverageBracket").await?;
+
+        let symbols = data
+            .as_array()
+            .ok_or_else(|| ApiError::Parsing(format!("Unexpected response: {data}")))?;
+
+        Ok(symbols
+            .iter()
+            .filter_map(|symbol| {
+                let max_leverage = symbol["brackets"]
+                    .as_array()?
+                    .iter()
+                    .filter_map(|bracket| bracket["initialLeverage"].as_u64())
+                    .max()?;
+                Some((symbol["symbol"].as_str()?.to_string(), max_leverage as u32))
+            })
+            .collect())
     }
 
     fn format_binance_symbol(symbol: &str, lower_case: bool) -> String {
+        let symbol = symbol
+            .parse::<Symbol>()
+            .map_or_else(|_| symbol.to_string(), |symbol| symbol.to_binance());
+
         if lower_case {
             return symbol.to_lowercase();
         }
 
-        symbol.to_string()
+        symbol
     }
 }
 
@@ -242,12 +282,23 @@ impl ExchangeApi for BinanceApi {
     /// An `ApiResult<f64>` representing the successful retrieval of the account balance as a floating-point number. In case of an error, it returns an appropriate error encapsulated within `ApiResult`.
 
     async fn get_account_balance(&self) -> ApiResult<f64> {
-        unimplemented!()
+        let endpoint = "/fapi/v2/balance";
+        let data = self.signed_get(endpoint).await?;
+
+        // the USDT wallet margins all positions
+        let balance = data
+            .as_array()
+            .and_then(|assets| assets.iter().find(|asset| asset["asset"] == "USDT"))
+            .ok_or_else(|| ApiError::Parsing(format!("No USDT balance in response: {data}")))?;
+
+        parse_f64_from_value("balance", balance)
     }
 
     /// Opens a new trading position on the exchange with specified parameters.
     ///
-    /// This method places an order to open a new trading position based on the symbol, margin used, leverage, order side (buy/sell), and the specified opening price. It constructs the request, signs it, and sends it to the exchange.
+    /// Places a futures market order of the position quantity, the margin times the leverage
+    /// at the open price rounded down to the quantity steps of the symbol. Closing the position
+    /// orders the same quantity.
     ///
     /// # Arguments
     ///
@@ -255,7 +306,7 @@ impl ExchangeApi for BinanceApi {
     /// * `margin_usd` - The amount of margin in USD to be used for this position.
     /// * `leverage` - The leverage to apply to the position.
     /// * `order_side` - The side of the order, either `OrderSide::Buy` or `OrderSide::Sell`.
-    /// * `open_price` - The price at which to attempt to open the position.
+    /// * `open_price` - The expected open price, used if the response has no fill price.
     ///
     /// # Returns
     ///
@@ -267,63 +318,54 @@ impl ExchangeApi for BinanceApi {
         margin_usd: f64,
         leverage: u32,
         order_side: OrderSide,
-        open_price: f64,
+        open_price: Price,
+        precision: SymbolPrecision,
     ) -> ApiResult<Position> {
-        let endpoint = "/api/v3/order";
-        let quantity = (margin_usd * leverage as f64) / open_price;
-
-        // format qty to 8 decimals
-        let _qty = format!("{:.1$}", quantity, 8);
-
-        let ts = &generate_ts().to_string();
-        let side = &order_side.to_string();
-        let quote_qty = 50.to_string();
-
-        let request_body = QueryStr::new(vec![
-            ("symbol", symbol),
-            ("quoteOrderQty", &quote_qty),
-            // ("quantity", &qty),
-            ("type", "MARKET"),
-            ("side", side),
-            ("timestamp", ts),
-        ]);
-
-        let signature = self.sign_query_str(&request_body.to_string());
-
-        let query_str = format!("{}&signature={signature}", request_body.to_string());
+        let mut position = Position::new(
+            symbol, open_price, order_side, margin_usd, leverage, None, precision,
+        );
 
-        println!("qry_str: {query_str}");
+        let (endpoint, request) = open_order(&position);
+        let query_str = request.timestamp().sign(&self.secret_key);
 
         let res = self.post(endpoint, &query_str).await?;
+        let data = self.handle_response(res).await?;
 
-        match self.handle_response(res).await {
-            Ok(_res) => {
-                // parse response
-                // build position from response
-                Ok(Position::new(
-                    symbol, open_price, order_side, margin_usd, leverage, None,
-                ))
-            }
-            Err(e) => Err(e),
-        }
+        // the ordered quantity is held at the price it traded at
+        let fill_price = parse_binance_fill_price(&data)?;
+        position.open_price = fill_price
+            .map(Price::from_f64)
+            .unwrap_or(position.open_price);
+
+        Ok(position)
     }
 
     /// Closes an existing trading position on the exchange.
     ///
-    /// This method sends a request to the exchange to close a specific trading position at the specified price. It handles the necessary calculations to close the position based on its current state.
+    /// Places a reduce-only market order of the position quantity on the opposite side, so it
+    /// can't open a position in the other direction if the position is already closed.
     ///
     /// # Arguments
     ///
     /// * `position` - The `Position` object representing the trading position to close.
-    /// * `close_price` - The price at which the position should be closed.
+    /// * `close_price` - The expected close price, used if the response has no fill price.
     ///
     /// # Returns
     ///
-    /// Returns an `ApiResult<TradeTx>` representing the transaction details of the closed position, or an error if the operation fails.
+    /// Returns an `ApiResult<TradeTx>` representing the transaction details of the closed position, or an error if the order is rejected.
+    async fn close_position(&self, position: Position, close_price: Price) -> ApiResult<TradeTx> {
+        let (endpoint, request) = close_order(&position);
+        let query_str = request.timestamp().sign(&self.secret_key);
 
-    async fn close_position(&self, position: Position, close_price: f64) -> ApiResult<TradeTx> {
-        // TODO: make api request to close position
-        Ok(TradeTx::new(close_price, generate_ts(), position))
+        let res = self.post(endpoint, &query_str).await?;
+        let data = self.handle_response(res).await?;
+
+        let fill_price = parse_binance_fill_price(&data)?;
+        Ok(TradeTx::new(
+            fill_price.map(Price::from_f64).unwrap_or(close_price),
+            generate_ts(),
+            position,
+        ))
     }
 
     /// Retrieves the account information from the exchange.
@@ -335,16 +377,7 @@ impl ExchangeApi for BinanceApi {
     /// Returns an `ApiResult<Value>`, where `Value` is a JSON representation of the account information. In case of an error, it returns an appropriate error encapsulated within `ApiResult`.
 
     async fn get_account(&self) -> ApiResult<Value> {
-        let endpoint = "/api/v3/account";
-        let ts = generate_ts();
-
-        let query_str = format!("timestamp={ts}");
-        let signature = self.sign_query_str(&query_str);
-        let query_str = format!("{}&signature={signature}", query_str);
-
-        let res = self.get(endpoint, Some(&query_str)).await?;
-
-        self.handle_response(res).await
+        self.signed_get("/api/v3/account").await
     }
 
     /// Fetches the latest k-line (candlestick) data for a specified symbol and interval.
@@ -360,12 +393,14 @@ impl ExchangeApi for BinanceApi {
     ///
     /// Returns an `ApiResult<Kline>`, encapsulating the latest k-line data. In case of an error, it returns an appropriate error encapsulated within `ApiResult`.
 
-    async fn get_kline(&self, symbol: &str, interval: &str) -> ApiResult<Kline> {
-        let format_symbol = BinanceApi::format_binance_symbol(symbol, false);
-        let endpoint =
-            format!("/fapi/v1/klines?symbol={format_symbol}&interval={interval}&limit=1");
+    async fn get_kline(&self, symbol: &str, interval: Interval) -> ApiResult<Kline> {
+        let query_str = SignedRequestBuilder::new()
+            .param("symbol", BinanceApi::format_binance_symbol(symbol, false))
+            .param("interval", interval)
+            .param("limit", 1)
+            .query_str();
 
-        let res = self.get(&endpoint, None).await?;
+        let res = self.get("/fapi/v1/klines", Some(&query_str)).await?;
 
         let data = self.handle_response(res).await?;
 
@@ -397,7 +432,7 @@ impl ExchangeApi for BinanceApi {
         let close_time = arr[0][6].as_u64().unwrap();
 
         Ok(Kline {
-            interval: interval.to_string(),
+            interval,
             symbol: symbol.to_string(),
             open_time,
             open,
@@ -422,10 +457,11 @@ impl ExchangeApi for BinanceApi {
     /// Returns an `ApiResult<Ticker>`, providing the current market ticker data. If the operation fails, it returns an error within `ApiResult`.
 
     async fn get_ticker(&self, symbol: &str) -> ApiResult<Ticker> {
-        let format_symbol = BinanceApi::format_binance_symbol(symbol, false);
-        let endpoint = format!("/fapi/v1/ticker/24hr?symbol={format_symbol}");
+        let query_str = SignedRequestBuilder::new()
+            .param("symbol", BinanceApi::format_binance_symbol(symbol, false))
+            .query_str();
 
-        let res = self.get(&endpoint, None).await?;
+        let res = self.get("/fapi/v1/ticker/24hr", Some(&query_str)).await?;
 
         let data = self.handle_response(res).await?;
 
@@ -451,6 +487,18 @@ impl ExchangeApi for BinanceApi {
         // Ok(Ticker::default())
     }
 
+    /// Retrieves the latest funding rate of a perpetual futures symbol from its premium index.
+    async fn get_funding_rate(&self, symbol: &str) -> ApiResult<FundingRate> {
+        let query_str = SignedRequestBuilder::new()
+            .param("symbol", BinanceApi::format_binance_symbol(symbol, false))
+            .query_str();
+
+        let res = self.get("/fapi/v1/premiumIndex", Some(&query_str)).await?;
+        let data = self.handle_response(res).await?;
+
+        FundingRate::from_premium_index(symbol, &data)
+    }
+
     /// Lists all orders associated with the account, including historical orders.
     ///
     /// This asynchronous method sends a request to the exchange to retrieve a comprehensive list of all orders placed by the account, allowing for a complete audit trail of trading activity.
@@ -460,16 +508,7 @@ impl ExchangeApi for BinanceApi {
     /// Returns an `ApiResult<Value>`, where `Value` is a JSON array of orders. In case of an error, it returns an appropriate error encapsulated within `ApiResult`.
 
     async fn all_orders(&self) -> ApiResult<Value> {
-        let endpoint = "/api/v3/allOrderList";
-        let ts = generate_ts();
-
-        let query_str = format!("timestamp={ts}");
-        let signature = self.sign_query_str(&query_str);
-        let query_str = format!("{}&signature={signature}", query_str);
-
-        let res = self.get(endpoint, Some(&query_str)).await?;
-
-        self.handle_response(res).await
+        self.signed_get("/api/v3/allOrderList").await
     }
 
     /// Retrieves a list of all open (active) orders for the account.
@@ -481,18 +520,33 @@ impl ExchangeApi for BinanceApi {
     /// An `ApiResult<Value>` that contains a JSON array of open orders. In case of an error, it returns an appropriate error encapsulated within `ApiResult`.
 
     async fn list_open_orders(&self) -> ApiResult<Value> {
-        let endpoint = "/api/v3/openOrderList";
-        let ts = generate_ts();
+        self.signed_get("/api/v3/openOrderList").await
+    }
 
-        let query_str = format!("timestamp={ts}");
-        let signature = self.sign_query_str(&query_str);
-        let query_str = format!("{}&signature={signature}", query_str);
+    /// Cancels all working orders of a symbol on the futures exchange.
+    ///
+    /// # Returns
+    ///
+    /// An `ApiResult<Value>` with the response of the exchange. In case of an error, it returns an appropriate error encapsulated within `ApiResult`.
+    async fn cancel_all_orders(&self, symbol: &str) -> ApiResult<Value> {
+        let endpoint = "/fapi/v1/allOpenOrders";
 
-        let res = self.get(endpoint, Some(&query_str)).await?;
+        let query_str = SignedRequestBuilder::new()
+            .param("symbol", Self::format_binance_symbol(symbol, false))
+            .timestamp()
+            .sign(&self.secret_key);
+
+        let res = self.delete(endpoint, &query_str).await?;
 
         self.handle_response(res).await
     }
 
+    /// Lists the futures positions of the account from their position risk.
+    async fn open_positions(&self) -> ApiResult<Vec<ExchangePosition>> {
+        let data = self.signed_get("/fapi/v2/positionRisk").await?;
+        parse_binance_positions(&data)
+    }
+
     // ---
     // Exchange Methods
     // ---
@@ -506,14 +560,26 @@ impl ExchangeApi for BinanceApi {
     /// Returns an `ApiResult<ExchangeInfo>`, encapsulating various pieces of information about the exchange. In case of an error, it returns an appropriate error encapsulated within `ApiResult`.
 
     async fn info(&self) -> ApiResult<ExchangeInfo> {
-        let endpoint = "/api/v3/exchangeInfo";
+        let endpoint = "/fapi/v1/exchangeInfo";
 
-        let _res = self.get(endpoint, None).await?;
+        let res = self.get(endpoint, None).await?;
+        let data = self.handle_response(res).await?;
 
-        // self.handle_response(res).await
+        let mut symbols = parse_binance_symbols(&data);
+
+        // leverage brackets need a signed request, without valid keys symbols have no limit
+        match self.max_leverages().await {
+            Ok(max_leverages) => {
+                for symbol in &mut symbols {
+                    symbol.max_leverage = max_leverages.get(&symbol.symbol).copied();
+                }
+            }
+            Err(e) => info!("Unable to get leverage brackets, e: {e}"),
+        }
 
         Ok(ExchangeInfo {
             name: "Binance".to_string(),
+            symbols,
         })
     }
 
@@ -529,7 +595,7 @@ impl ExchangeApi for BinanceApi {
         &self,
         symbol: &str,
         stream_type: StreamType,
-        interval: Option<&str>,
+        interval: Option<Interval>,
     ) -> String {
         let url = match stream_type {
             StreamType::Kline => {
@@ -659,7 +725,8 @@ impl StreamManager for BinanceStreamManager {
 
                                         if let Ok(kline) = Kline::from_binance_lookup(lookup) {
                                             let _ = market_sender
-                                                .send(MarketMessage::UpdateKline(kline));
+                                                .send(MarketMessage::UpdateKline(kline))
+                                                .await;
                                         }
                                     }
                                     StreamType::Ticker => {
@@ -668,7 +735,8 @@ impl StreamManager for BinanceStreamManager {
 
                                         if let Ok(ticker) = Ticker::from_binance_lookup(lookup) {
                                             let _ = market_sender
-                                                .send(MarketMessage::UpdateTicker(ticker));
+                                                .send(MarketMessage::UpdateTicker(ticker))
+                                                .await;
                                         }
                                     }
                                     StreamType::Trade => {
@@ -677,7 +745,8 @@ impl StreamManager for BinanceStreamManager {
 
                                         if let Ok(trade) = Trade::from_binance_lookup(lookup) {
                                             let _ = market_sender
-                                                .send(MarketMessage::UpdateMarketTrade(trade));
+                                                .send(MarketMessage::UpdateMarketTrade(trade))
+                                                .await;
                                         }
                                     }
                                 }
@@ -699,8 +768,13 @@ impl StreamManager for BinanceStreamManager {
                         }
                     },
                     Err(e) => {
-                        // Handle error
-                        eprintln!("Error receiving message: {:?}", e);
+                        // the market logs the error and publishes it
+                        let _ = market_sender
+                            .send(MarketMessage::StreamError {
+                                stream_id: thread_stream_id.clone(),
+                                error: e.to_string(),
+                            })
+                            .await;
                     }
                 }
             }
@@ -742,11 +816,237 @@ impl StreamManager for BinanceStreamManager {
     }
 }
 
+/// Builds the futures market order opening a position, of the position quantity on its side.
+///
+/// # Returns
+///
+/// The order endpoint and the unsigned request.
+fn open_order(position: &Position) -> (&'static str, SignedRequestBuilder) {
+    let request = market_order(&position.symbol, position.order_side, position.quantity);
+    (BINANCE_ORDER_ENDPOINT, request)
+}
+
+/// Builds the reduce-only futures market order closing a position, of the position quantity on
+/// the opposite side, so it can't open a position in the other direction.
+///
+/// # Returns
+///
+/// The order endpoint and the unsigned request.
+fn close_order(position: &Position) -> (&'static str, SignedRequestBuilder) {
+    let side = match position.order_side {
+        OrderSide::Buy => OrderSide::Sell,
+        OrderSide::Sell => OrderSide::Buy,
+    };
+    let request =
+        market_order(&position.symbol, side, position.quantity).param("reduceOnly", "true");
+    (BINANCE_ORDER_ENDPOINT, request)
+}
+
+/// Builds a futures market order of a quantity.
+fn market_order(symbol: &str, order_side: OrderSide, quantity: Qty) -> SignedRequestBuilder {
+    let side = match order_side {
+        OrderSide::Buy => "BUY",
+        OrderSide::Sell => "SELL",
+    };
+    SignedRequestBuilder::new()
+        .param("symbol", BinanceApi::format_binance_symbol(symbol, false))
+        .param("side", side)
+        .param("type", "MARKET")
+        .param("quantity", quantity)
+}
+
+/// Parses the symbols of a futures exchange info response.
+///
+/// # Returns
+///
+/// The `SymbolInfo` of each symbol, symbols missing a field are left out.
+fn parse_binance_symbols(data: &Value) -> Vec<SymbolInfo> {
+    let Some(symbols) = data["symbols"].as_array() else {
+        return vec![];
+    };
+
+    symbols
+        .iter()
+        .filter_map(|symbol| {
+            let min_quantity = symbol["filters"]
+                .as_array()
+                .and_then(|filters| {
+                    filters
+                        .iter()
+                        .find(|filter| filter["filterType"] == "LOT_SIZE")
+                })
+                .and_then(|lot_size| parse_f64_from_value("minQty", lot_size).ok());
+
+            Some(SymbolInfo {
+                symbol: symbol["symbol"].as_str()?.to_string(),
+                base_asset: symbol["baseAsset"].as_str()?.to_string(),
+                quote_asset: symbol["quoteAsset"].as_str()?.to_string(),
+                trading: symbol["status"] == "TRADING",
+                price_precision: symbol["pricePrecision"].as_u64()? as u32,
+                quantity_precision: symbol["quantityPrecision"].as_u64()? as u32,
+                min_quantity,
+                max_leverage: None,
+            })
+        })
+        .collect()
+}
+
+/// Parses the non-empty positions of a position risk response, the sign of a position amount
+/// is its side.
+///
+/// # Returns
+///
+/// The positions, or an error if the response isn't a list of positions.
+fn parse_binance_positions(data: &Value) -> ApiResult<Vec<ExchangePosition>> {
+    let positions = data
+        .as_array()
+        .ok_or_else(|| ApiError::Parsing(format!("Unexpected response: {data}")))?;
+
+    Ok(positions
+        .iter()
+        .filter_map(|position| {
+            let amount = parse_f64_from_value("positionAmt", position).ok()?;
+            if amount == 0.0 {
+                return None;
+            }
+            Some(ExchangePosition {
+                symbol: position["symbol"].as_str()?.to_string(),
+                order_side: if amount > 0.0 {
+                    OrderSide::Buy
+                } else {
+                    OrderSide::Sell
+                },
+                quantity: amount.abs(),
+            })
+        })
+        .collect())
+}
+
+/// Parses the average fill price of a futures order response.
+///
+/// # Returns
+///
+/// The fill price, `None` if the order isn't filled yet, or an error if the order was rejected.
+fn parse_binance_fill_price(data: &Value) -> ApiResult<Option<f64>> {
+    if data["orderId"].is_null() {
+        return Err(ApiError::Rejected(data.to_string()));
+    }
+
+    Ok(parse_f64_from_value("avgPrice", data)
+        .ok()
+        .filter(|price| *price > 0.0))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use tokio::test;
 
+    #[test]
+    async fn test_parse_binance_positions() {
+        let data = json!([
+            { "symbol": "BTCUSDT", "positionAmt": "0.010", "positionSide": "BOTH" },
+            { "symbol": "ETHUSDT", "positionAmt": "-1.5", "positionSide": "BOTH" },
+            { "symbol": "SOLUSDT", "positionAmt": "0.0", "positionSide": "BOTH" }
+        ]);
+
+        let positions = parse_binance_positions(&data).unwrap();
+        assert_eq!(
+            positions,
+            vec![
+                ExchangePosition {
+                    symbol: "BTCUSDT".to_string(),
+                    order_side: OrderSide::Buy,
+                    quantity: 0.01,
+                },
+                ExchangePosition {
+                    symbol: "ETHUSDT".to_string(),
+                    order_side: OrderSide::Sell,
+                    quantity: 1.5,
+                },
+            ]
+        );
+        assert!(parse_binance_positions(&json!({ "code": -2015 })).is_err());
+    }
+
+    #[test]
+    async fn test_parse_binance_fill_price() {
+        let data = json!({ "orderId": 1, "status": "FILLED", "avgPrice": "30000.10" });
+        assert_eq!(parse_binance_fill_price(&data).unwrap(), Some(30000.1));
+
+        let data = json!({ "orderId": 1, "status": "NEW", "avgPrice": "0.00" });
+        assert_eq!(parse_binance_fill_price(&data).unwrap(), None);
+
+        let data = json!({ "code": -2022, "msg": "ReduceOnly Order is rejected." });
+        assert!(matches!(
+            parse_binance_fill_price(&data),
+            Err(ApiError::Rejected(_))
+        ));
+    }
+
+    #[test]
+    async fn test_parse_binance_symbols() {
+        let data = json!({
+            "symbols": [
+                {
+                    "symbol": "BTCUSDT",
+                    "baseAsset": "BTC",
+                    "quoteAsset": "USDT",
+                    "status": "TRADING",
+                    "pricePrecision": 2,
+                    "quantityPrecision": 3,
+                    "filters": [
+                        { "filterType": "PRICE_FILTER", "tickSize": "0.10" },
+                        { "filterType": "LOT_SIZE", "minQty": "0.001", "stepSize": "0.001" }
+                    ]
+                },
+                { "symbol": "ETHUSDT", "status": "SETTLING" }
+            ]
+        });
+
+        let symbols = parse_binance_symbols(&data);
+        assert_eq!(symbols.len(), 1);
+        assert_eq!(symbols[0].symbol, "BTCUSDT");
+        assert_eq!(symbols[0].quote_asset, "USDT");
+        assert!(symbols[0].trading);
+        assert_eq!(symbols[0].quantity_precision, 3);
+        assert_eq!(symbols[0].min_quantity, Some(0.001));
+        assert!(parse_binance_symbols(&json!({})).is_empty());
+    }
+
+    /// Tests positions are opened and closed on the futures order endpoint with the same
+    /// quantity, rounded down to the quantity steps of the symbol.
+    #[test]
+    async fn test_position_orders() {
+        let precision = SymbolPrecision {
+            price: 2,
+            quantity: 3,
+        };
+        let position = Position::new(
+            "BTCUSDT",
+            Price::from_f64(30_000.0),
+            OrderSide::Buy,
+            100.0,
+            10,
+            None,
+            precision,
+        );
+
+        let (open_endpoint, open_request) = open_order(&position);
+        let (close_endpoint, close_request) = close_order(&position);
+        assert_eq!(open_endpoint, "/fapi/v1/order");
+        assert_eq!(open_endpoint, close_endpoint);
+        // 1000 USD at 30000 is 0.0333.. BTC, rounded down to 0.001 steps
+        assert_eq!(
+            open_request.query_str(),
+            "symbol=BTCUSDT&side=BUY&type=MARKET&quantity=0.033"
+        );
+        assert_eq!(
+            close_request.query_str(),
+            "symbol=BTCUSDT&side=SELL&type=MARKET&quantity=0.033&reduceOnly=true"
+        );
+    }
+
     #[test]
     async fn test_format_binance_symbol() {
         let symbol = "BTC-USDT";
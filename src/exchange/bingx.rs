@@ -13,27 +13,31 @@ use std::collections::HashMap;
 use std::time::Duration;
 use tokio::task::JoinHandle;
 
-use hmac::{Hmac, Mac};
-use sha2::Sha256;
-
+use crate::account::money::{Price, Qty, SymbolPrecision};
 use crate::account::trade::{OrderSide, Position, TradeTx};
-use crate::exchange::api::{ExchangeApi, QueryStr};
+use crate::exchange::api::{ExchangeApi, SignedRequestBuilder};
 
+use crate::market::interval::Interval;
 use crate::market::messages::MarketMessage;
+use crate::market::symbol::Symbol;
 use crate::market::trade::Trade;
 use crate::market::types::{ArcMutex, ArcSender};
 use crate::market::{kline::Kline, ticker::Ticker};
+use crate::strategy::funding::FundingRate;
 
+use crate::utils::number::parse_f64_from_value;
 use crate::utils::time::generate_ts;
 
-use super::api::ExchangeInfo;
+use super::api::{ExchangeInfo, ExchangePosition, SymbolInfo};
 
 use super::stream::{StreamManager, StreamMeta};
-use super::types::{ApiResult, StreamType};
+use super::types::{ApiError, ApiResult, StreamType};
 
 const BING_X_WS_HOST_URL: &str = "wss://open-api-swap.bingx.com/swap-market";
 const BING_X_HOST_URL: &str = "https://open-api.bingx.com";
 const API_VERSION: &str = "v3";
+/// The endpoint of swap orders, positions are opened and closed with it.
+const BING_X_ORDER_ENDPOINT: &str = "/openApi/swap/v2/trade/order";
 
 pub struct BingXApi {
     ws_host: String,
@@ -106,7 +110,6 @@ impl BingXApi {
         query_str: Option<&str>,
         body: Option<String>,
     ) -> Result<Response, reqwest::Error> {
-        // let signature = self.sign_query_str(query_str);
         let url = match query_str {
             Some(qs) => format!("{}{}?{}", self.host, endpoint, qs),
             None => format!("{}{}", self.host, endpoint),
@@ -148,6 +151,26 @@ impl BingXApi {
             .await
     }
 
+    /// Performs an HTTP DELETE request to the specified endpoint.
+    ///
+    /// # Arguments
+    ///
+    /// * `endpoint` - A string slice specifying the endpoint for the DELETE request.
+    /// * `query_str` - A string slice containing the signed query of the request.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` with the response `Response` object if the request is successful, or an error of type `reqwest::Error` otherwise.
+    async fn delete(&self, endpoint: &str, query_str: &str) -> Result<Response, reqwest::Error> {
+        let url = format!("{}{}?{}", self.host, endpoint, query_str);
+
+        self.client
+            .delete(&url)
+            .headers(self.build_headers(true))
+            .send()
+            .await
+    }
+
     /// Processes the HTTP response, extracting the relevant data based on the content type.
     ///
     /// This method checks the content type of the response and accordingly parses the response body as either plain text or JSON. It is designed to handle different response formats gracefully, ensuring that the data is correctly extracted from various API endpoints.
@@ -175,35 +198,16 @@ impl BingXApi {
         Ok(data)
     }
 
-    /// Signs a query string using the API secret key.
-    ///
-    /// This method is used to generate a signature for secured endpoints. The signature is generated using HMAC SHA256, based on the query string and the secret key.
-    ///
-    /// # Arguments
-    ///
-    /// * `query_str` - A string slice containing the query string to be signed.
-    ///
-    /// # Returns
-    ///
-    /// Returns a string representing the hexadecimal value of the signature.
-
-    fn sign_query_str(&self, query_str: &str) -> String {
-        // Create a new HMAC instance with SHA256
-        let mut hmac =
-            Hmac::<Sha256>::new_from_slice(self.secret_key.as_bytes()).expect("Invalid key length");
-
-        // Update the HMAC with the data
-        hmac.update(query_str.as_bytes());
-
-        // Get the resulting HMAC value
-        let result = hmac.finalize();
-
-        // Convert the HMAC value to a string
-        hex::encode(result.into_bytes())
+    /// Starts a request, whose parameters BingX expects sorted by key and signed before they are
+    /// URL-encoded.
+    fn request() -> SignedRequestBuilder {
+        SignedRequestBuilder::new().sort_params().sign_raw()
     }
 
     fn format_bingx_symbol(symbol: &str, lower_case: bool) -> String {
-        let symbol: String = symbol.replace("USDT", "-USDT");
+        let symbol = symbol
+            .parse::<Symbol>()
+            .map_or_else(|_| symbol.to_string(), |symbol| symbol.to_bingx());
 
         if lower_case {
             return symbol.to_lowercase();
@@ -224,7 +228,13 @@ impl ExchangeApi for BingXApi {
     /// An `ApiResult<f64>` representing the successful retrieval of the account balance as a floating-point number. In case of an error, it returns an appropriate error encapsulated within `ApiResult`.
 
     async fn get_account_balance(&self) -> ApiResult<f64> {
-        unimplemented!()
+        let data = self.get_account().await?;
+
+        // Response
+        // { "code": 0, "data": { "balance": { "asset": "USDT", "balance": "1000.00", ... } } }
+        let balance = &data["data"]["balance"];
+        parse_f64_from_value("balance", balance)
+            .map_err(|_| ApiError::Parsing(format!("No balance in response: {data}")))
     }
 
     /// Fetches the latest k-line (candlestick) data for a specified symbol and interval.
@@ -240,7 +250,7 @@ impl ExchangeApi for BingXApi {
     ///
     /// Returns an `ApiResult<Kline>`, encapsulating the latest k-line data. In case of an error, it returns an appropriate error encapsulated within `ApiResult`.
 
-    async fn get_kline(&self, symbol: &str, interval: &str) -> ApiResult<Kline> {
+    async fn get_kline(&self, symbol: &str, interval: Interval) -> ApiResult<Kline> {
         get_bingx_kline(symbol, interval).await
     }
 
@@ -260,9 +270,28 @@ impl ExchangeApi for BingXApi {
         get_bingx_ticker(symbol).await
     }
 
+    /// Retrieves the latest funding rate of a perpetual swap from its premium index.
+    async fn get_funding_rate(&self, symbol: &str) -> ApiResult<FundingRate> {
+        let query_str = BingXApi::request()
+            .param("symbol", BingXApi::format_bingx_symbol(symbol, false))
+            .timestamp()
+            .query_str();
+        let url = format!(
+            "{}/openApi/swap/v2/quote/premiumIndex?{}",
+            BING_X_HOST_URL, query_str
+        );
+
+        let res = reqwest::Client::new().get(url).send().await?;
+        let lookup = res.json::<Value>().await?;
+
+        FundingRate::from_premium_index(symbol, &lookup["data"])
+    }
+
     /// Opens a new trading position on the exchange with specified parameters.
     ///
-    /// This method places an order to open a new trading position based on the symbol, margin used, leverage, order side (buy/sell), and the specified opening price. It constructs the request, signs it, and sends it to the exchange.
+    /// Places a swap market order of the position quantity, the margin times the leverage at
+    /// the open price rounded down to the quantity steps of the symbol. Closing the position
+    /// orders the same quantity.
     ///
     /// # Arguments
     ///
@@ -270,7 +299,7 @@ impl ExchangeApi for BingXApi {
     /// * `margin_usd` - The amount of margin in USD to be used for this position.
     /// * `leverage` - The leverage to apply to the position.
     /// * `order_side` - The side of the order, either `OrderSide::Buy` or `OrderSide::Sell`.
-    /// * `open_price` - The price at which to attempt to open the position.
+    /// * `open_price` - The expected open price, used if the response has no fill price.
     ///
     /// # Returns
     ///
@@ -282,62 +311,54 @@ impl ExchangeApi for BingXApi {
         margin_usd: f64,
         leverage: u32,
         order_side: OrderSide,
-        open_price: f64,
+        open_price: Price,
+        precision: SymbolPrecision,
     ) -> ApiResult<Position> {
-        let quantity = (margin_usd * leverage as f64) / open_price;
-
-        let endpoint = "/api/v3/order";
-
-        // format qty to 8 decimals
-        let _qty = format!("{:.1$}", quantity, 8);
-
-        let ts = &generate_ts().to_string();
-        let side = &order_side.to_string();
-        let quote_qty = quantity.to_string();
-
-        let request_body = QueryStr::new(vec![
-            ("symbol", symbol),
-            ("quoteOrderQty", &quote_qty),
-            // ("quantity", &qty),
-            ("type", "MARKET"),
-            ("side", side),
-            ("timestamp", ts),
-        ]);
-
-        let signature = self.sign_query_str(&request_body.to_string());
+        let mut position = Position::new(
+            symbol, open_price, order_side, margin_usd, leverage, None, precision,
+        );
 
-        let query_str = format!("{}&signature={signature}", request_body.to_string());
+        let (endpoint, request) = open_order(&position);
+        let query_str = request.timestamp().sign(&self.secret_key);
 
         let res = self.post(endpoint, &query_str).await?;
+        let data = self.handle_response(res).await?;
 
-        match self.handle_response(res).await {
-            Ok(_res) => {
-                // parse response
-                // build position from response
-                Ok(Position::new(
-                    symbol, open_price, order_side, margin_usd, leverage, None,
-                ))
-            }
-            Err(e) => Err(e),
-        }
+        // the ordered quantity is held at the price it traded at
+        let fill_price = parse_bingx_fill_price(&data)?;
+        position.open_price = fill_price
+            .map(Price::from_f64)
+            .unwrap_or(position.open_price);
+
+        Ok(position)
     }
 
     /// Closes an existing trading position on the exchange.
     ///
-    /// This method sends a request to the exchange to close a specific trading position at the specified price. It handles the necessary calculations to close the position based on its current state.
+    /// Places a reduce-only market order of the position quantity on the opposite side, so it
+    /// can't open a position in the other direction if the position is already closed.
     ///
     /// # Arguments
     ///
     /// * `position` - The `Position` object representing the trading position to close.
-    /// * `close_price` - The price at which the position should be closed.
+    /// * `close_price` - The expected close price, used if the response has no fill price.
     ///
     /// # Returns
     ///
-    /// Returns an `ApiResult<TradeTx>` representing the transaction details of the closed position, or an error if the operation fails.
+    /// Returns an `ApiResult<TradeTx>` representing the transaction details of the closed position, or an error if the order is rejected.
+    async fn close_position(&self, position: Position, close_price: Price) -> ApiResult<TradeTx> {
+        let (endpoint, request) = close_order(&position);
+        let query_str = request.timestamp().sign(&self.secret_key);
 
-    async fn close_position(&self, position: Position, close_price: f64) -> ApiResult<TradeTx> {
-        // TODO: make api request to close position
-        Ok(TradeTx::new(close_price, generate_ts(), position))
+        let res = self.post(endpoint, &query_str).await?;
+        let data = self.handle_response(res).await?;
+
+        let fill_price = parse_bingx_fill_price(&data)?;
+        Ok(TradeTx::new(
+            fill_price.map(Price::from_f64).unwrap_or(close_price),
+            generate_ts(),
+            position,
+        ))
     }
 
     /// Retrieves the account information from the exchange.
@@ -351,22 +372,14 @@ impl ExchangeApi for BingXApi {
     async fn get_account(&self) -> ApiResult<Value> {
         let endpoint = "/openApi/swap/v2/user/balance";
         // let endpoint = "/openApi/spot/v1/account/balance";
-        let ts = generate_ts().to_string();
-
-        let query_str = QueryStr::new(vec![("timestamp", &ts)]);
-
-        let signature = self.sign_query_str(&query_str.to_string());
-
-        let query_str = QueryStr::new(vec![("timestamp", &ts), ("signature", &signature)]);
+        let query_str = Self::request().timestamp().sign(&self.secret_key);
 
         // let body = json!({
         //     "timestamp": &ts,
         //     "signature": &signature
         // });
 
-        let res = self
-            .get(endpoint, Some(&query_str.to_string()), None)
-            .await?;
+        let res = self.get(endpoint, Some(&query_str), None).await?;
 
         self.handle_response(res).await
     }
@@ -381,11 +394,7 @@ impl ExchangeApi for BingXApi {
 
     async fn all_orders(&self) -> ApiResult<Value> {
         let endpoint = "/api/v3/allOrderList";
-        let ts = generate_ts();
-
-        let query_str = format!("timestamp={ts}");
-        let signature = self.sign_query_str(&query_str);
-        let query_str = format!("{}&signature={signature}", query_str);
+        let query_str = Self::request().timestamp().sign(&self.secret_key);
 
         let res = self.get(endpoint, Some(&query_str), None).await?;
 
@@ -402,17 +411,41 @@ impl ExchangeApi for BingXApi {
 
     async fn list_open_orders(&self) -> ApiResult<Value> {
         let endpoint = "/api/v3/openOrderList";
-        let ts = generate_ts();
-
-        let query_str = format!("timestamp={ts}");
-        let signature = self.sign_query_str(&query_str);
-        let query_str = format!("{}&signature={signature}", query_str);
+        let query_str = Self::request().timestamp().sign(&self.secret_key);
 
         let res = self.get(endpoint, Some(&query_str), None).await?;
 
         self.handle_response(res).await
     }
 
+    /// Cancels all working orders of a symbol on the perpetual swap exchange.
+    ///
+    /// # Returns
+    ///
+    /// An `ApiResult<Value>` with the response of the exchange. In case of an error, it returns an appropriate error encapsulated within `ApiResult`.
+    async fn cancel_all_orders(&self, symbol: &str) -> ApiResult<Value> {
+        let endpoint = "/openApi/swap/v2/trade/allOpenOrders";
+        let query_str = Self::request()
+            .param("symbol", Self::format_bingx_symbol(symbol, false))
+            .timestamp()
+            .sign(&self.secret_key);
+
+        let res = self.delete(endpoint, &query_str).await?;
+
+        self.handle_response(res).await
+    }
+
+    /// Lists the perpetual swap positions of the account.
+    async fn open_positions(&self) -> ApiResult<Vec<ExchangePosition>> {
+        let endpoint = "/openApi/swap/v2/user/positions";
+        let query_str = Self::request().timestamp().sign(&self.secret_key);
+
+        let res = self.get(endpoint, Some(&query_str), None).await?;
+        let data = self.handle_response(res).await?;
+
+        parse_bingx_positions(&data)
+    }
+
     // ---
     // Exchange Methods
     // ---
@@ -426,14 +459,14 @@ impl ExchangeApi for BingXApi {
     /// Returns an `ApiResult<ExchangeInfo>`, encapsulating various pieces of information about the exchange. In case of an error, it returns an appropriate error encapsulated within `ApiResult`.
 
     async fn info(&self) -> ApiResult<ExchangeInfo> {
-        let endpoint = "/api/v3/exchangeInfo";
+        let endpoint = "/openApi/swap/v2/quote/contracts";
 
-        let _res = self.get(endpoint, None, None).await?;
-
-        // self.handle_response(res).await
+        let res = self.get(endpoint, None, None).await?;
+        let data = self.handle_response(res).await?;
 
         Ok(ExchangeInfo {
             name: "BingX".to_string(),
+            symbols: parse_bingx_symbols(&data),
         })
     }
     // ---
@@ -448,7 +481,7 @@ impl ExchangeApi for BingXApi {
         &self,
         _symbol: &str,
         _stream_type: StreamType,
-        _interval: Option<&str>,
+        _interval: Option<Interval>,
     ) -> String {
         self.ws_host.to_string()
     }
@@ -524,15 +557,35 @@ impl StreamManager for BingXStreamManager {
         match stream_meta.stream_type {
             StreamType::Ticker => {
                 let market_sender = self.market_sender.clone();
+                let stream_metas = stream_metas.clone();
+                let stream_id = stream_meta.id.clone();
 
                 let thread_handle = tokio::spawn(async move {
+                    let mut failing = false;
                     loop {
                         let ticker = get_bingx_ticker(&stream_meta.symbol).await;
 
-                        if let Ok(ticker) = ticker {
-                            let _ = market_sender.send(MarketMessage::UpdateTicker(ticker));
-                        } else {
-                            warn!("Unable to get ticker from BingX API");
+                        match ticker {
+                            Ok(ticker) => {
+                                failing = false;
+                                touch_stream(&stream_metas, &stream_id).await;
+                                let _ = market_sender
+                                    .send(MarketMessage::UpdateTicker(ticker))
+                                    .await;
+                            }
+                            Err(e) => {
+                                warn!("Unable to get ticker from BingX API");
+                                // report the first failure only, polls repeat every second
+                                if !failing {
+                                    failing = true;
+                                    let _ = market_sender
+                                        .send(MarketMessage::StreamError {
+                                            stream_id: stream_id.clone(),
+                                            error: e.to_string(),
+                                        })
+                                        .await;
+                                }
+                            }
                         }
 
                         tokio::time::sleep(Duration::from_secs(1)).await;
@@ -544,23 +597,37 @@ impl StreamManager for BingXStreamManager {
             }
             StreamType::Kline => {
                 let market_sender = self.market_sender.clone();
+                let stream_metas = stream_metas.clone();
+                let stream_id = stream_meta.id.clone();
+                let interval = stream_meta
+                    .interval
+                    .ok_or_else(|| "Kline streams require an interval".to_string())?;
 
                 let thread_handle = tokio::spawn(async move {
+                    let mut failing = false;
                     loop {
-                        let kline = get_bingx_kline(
-                            &stream_meta.symbol,
-                            &stream_meta
-                                .interval
-                                .clone()
-                                .unwrap_or_else(|| "UNKNOWN".to_string()),
-                        )
-                        .await;
-
-                        if let Ok(kline) = kline {
-                            // let ticker = BingXApi::parse_ticker(&ticker_str);
-                            let _ = market_sender.send(MarketMessage::UpdateKline(kline));
-                        } else {
-                            warn!("Unable to get kline from BingX API");
+                        let kline = get_bingx_kline(&stream_meta.symbol, interval).await;
+
+                        match kline {
+                            Ok(kline) => {
+                                failing = false;
+                                touch_stream(&stream_metas, &stream_id).await;
+                                // let ticker = BingXApi::parse_ticker(&ticker_str);
+                                let _ = market_sender.send(MarketMessage::UpdateKline(kline)).await;
+                            }
+                            Err(e) => {
+                                warn!("Unable to get kline from BingX API");
+                                // report the first failure only, polls repeat every second
+                                if !failing {
+                                    failing = true;
+                                    let _ = market_sender
+                                        .send(MarketMessage::StreamError {
+                                            stream_id: stream_id.clone(),
+                                            error: e.to_string(),
+                                        })
+                                        .await;
+                                }
+                            }
                         }
 
                         tokio::time::sleep(Duration::from_secs(1)).await;
@@ -577,7 +644,9 @@ impl StreamManager for BingXStreamManager {
                     loop {
                         // TODO: Implement get market trade
                         let trade = Trade::default();
-                        let _ = market_sender.send(MarketMessage::UpdateMarketTrade(trade));
+                        let _ = market_sender
+                            .send(MarketMessage::UpdateMarketTrade(trade))
+                            .await;
 
                         tokio::time::sleep(Duration::from_secs(1)).await;
                     }
@@ -638,43 +707,29 @@ impl StreamManager for BingXStreamManager {
 
 /// Fetches the latest Kline data for a given symbol and interval from BingX's open API.
 ///
-/// This function adjusts the interval format to match BingX API requirements, constructs the query string, and sends a GET request to the BingX kline endpoint.
+/// This function constructs the query string and sends a GET request to the BingX kline endpoint.
 ///
 /// # Arguments
 ///
-/// * `symbol` - A string slice representing the trading symbol (e.g., "BTC-USDT").
-/// * `interval` - A string slice representing the candlestick chart interval (e.g., "1min", "5min").
+/// * `symbol` - A string slice representing the trading symbol in any spelling (e.g., "BTCUSDT").
+/// * `interval` - The candlestick chart interval, which BingX names like raderbot does (e.g., "1m", "5m").
 ///
 /// # Returns
 ///
 /// Returns an `ApiResult<Kline>`, which is either the latest Kline data for the symbol and interval if successful, or an error message if the request fails or data is incomplete.
 
-pub async fn get_bingx_kline(symbol: &str, interval: &str) -> ApiResult<Kline> {
-    let symbol = BingXApi::format_bingx_symbol(symbol, false);
-    // remove last two letters from interval if interval is {number}min
-    // api accepts interval as {number}m
-    let _interval = if interval.ends_with('n') {
-        let mut interval_copy = interval.to_string();
-        interval_copy.pop();
-        interval_copy.pop();
-        interval_copy
-    } else {
-        interval.to_string()
-    };
-    let ts = generate_ts().to_string();
-
+pub async fn get_bingx_kline(symbol: &str, interval: Interval) -> ApiResult<Kline> {
     let client = reqwest::Client::new();
-    let query_str = QueryStr::new(vec![
-        ("symbol", &symbol),
-        ("interval", &_interval),
-        ("timestamp", &ts),
-        ("limit", "1"),
-    ]);
+    let query_str = BingXApi::request()
+        .param("symbol", BingXApi::format_bingx_symbol(symbol, false))
+        .param("interval", interval)
+        .param("limit", 1)
+        .timestamp()
+        .query_str();
 
     let url: String = format!(
         "{}/openApi/swap/v3/quote/klines?{}",
-        BING_X_HOST_URL,
-        query_str.to_string()
+        BING_X_HOST_URL, query_str
     );
 
     let res = client.get(url).send().await?;
@@ -693,7 +748,7 @@ pub async fn get_bingx_kline(symbol: &str, interval: &str) -> ApiResult<Kline> {
     let data = data[0].clone();
     let data: HashMap<String, Value> = serde_json::from_value(data.to_owned())?;
 
-    let kline = Kline::from_bingx_lookup(data, &symbol, interval)?;
+    let kline = Kline::from_bingx_lookup(data, &Symbol::normalize(symbol), interval)?;
 
     Ok(kline)
 }
@@ -704,7 +759,7 @@ pub async fn get_bingx_kline(symbol: &str, interval: &str) -> ApiResult<Kline> {
 ///
 /// # Arguments
 ///
-/// * `symbol` - A string slice representing the trading symbol (e.g., "BTC-USDT").
+/// * `symbol` - A string slice representing the trading symbol in any spelling (e.g., "BTCUSDT").
 ///
 /// # Returns
 ///
@@ -712,13 +767,13 @@ pub async fn get_bingx_kline(symbol: &str, interval: &str) -> ApiResult<Kline> {
 
 pub async fn get_bingx_ticker(symbol: &str) -> ApiResult<Ticker> {
     let client = reqwest::Client::new();
-    let ts = generate_ts().to_string();
-    let symbol = BingXApi::format_bingx_symbol(symbol, false);
-    let query_str = QueryStr::new(vec![("symbol", &symbol), ("timestamp", &ts)]);
+    let query_str = BingXApi::request()
+        .param("symbol", BingXApi::format_bingx_symbol(symbol, false))
+        .timestamp()
+        .query_str();
     let url = format!(
         "{}/openApi/swap/v2/quote/ticker?{}",
-        BING_X_HOST_URL,
-        query_str.to_string()
+        BING_X_HOST_URL, query_str
     );
 
     let res = client.get(url).send().await?;
@@ -737,3 +792,259 @@ pub async fn get_bingx_ticker(symbol: &str) -> ApiResult<Ticker> {
 
     Ok(ticker)
 }
+
+/// Records an update of a polled stream, so its health reflects the last successful poll.
+async fn touch_stream(stream_metas: &ArcMutex<HashMap<String, StreamMeta>>, stream_id: &str) {
+    if let Some(stream_meta) = stream_metas.lock().await.get_mut(stream_id) {
+        stream_meta.last_update = generate_ts();
+    }
+}
+
+/// Parses the contracts of a perpetual swap contracts response.
+///
+/// # Returns
+///
+/// The `SymbolInfo` of each contract, contracts missing a field are left out.
+fn parse_bingx_symbols(data: &Value) -> Vec<SymbolInfo> {
+    let Some(contracts) = data["data"].as_array() else {
+        return vec![];
+    };
+
+    contracts
+        .iter()
+        .filter_map(|contract| {
+            let max_leverage = ["maxLongLeverage", "maxShortLeverage"]
+                .iter()
+                .filter_map(|key| contract[key].as_u64())
+                .max();
+
+            Some(SymbolInfo {
+                symbol: Symbol::normalize(contract["symbol"].as_str()?),
+                base_asset: contract["asset"].as_str()?.to_string(),
+                quote_asset: contract["currency"].as_str()?.to_string(),
+                trading: contract["status"] == 1,
+                price_precision: contract["pricePrecision"].as_u64()? as u32,
+                quantity_precision: contract["quantityPrecision"].as_u64()? as u32,
+                min_quantity: contract["tradeMinQuantity"].as_f64(),
+                max_leverage: max_leverage.map(|max_leverage| max_leverage as u32),
+            })
+        })
+        .collect()
+}
+
+/// Parses the non-empty positions of a swap positions response. Positions are on the side of
+/// their position side, or of the sign of their amount in the one-way position mode.
+///
+/// # Returns
+///
+/// The positions, or an error if the request was rejected.
+fn parse_bingx_positions(data: &Value) -> ApiResult<Vec<ExchangePosition>> {
+    let positions = match data["data"].as_array() {
+        Some(positions) if data["code"] == 0 => positions,
+        _ => return Err(ApiError::Rejected(data.to_string())),
+    };
+
+    Ok(positions
+        .iter()
+        .filter_map(|position| {
+            let amount = parse_f64_from_value("positionAmt", position).ok()?;
+            if amount == 0.0 {
+                return None;
+            }
+            let order_side = match position["positionSide"].as_str()? {
+                "LONG" => OrderSide::Buy,
+                "SHORT" => OrderSide::Sell,
+                _ if amount < 0.0 => OrderSide::Sell,
+                _ => OrderSide::Buy,
+            };
+            Some(ExchangePosition {
+                symbol: Symbol::normalize(position["symbol"].as_str()?),
+                order_side,
+                quantity: amount.abs(),
+            })
+        })
+        .collect())
+}
+
+/// Builds the swap market order opening a position, of the position quantity on its side.
+///
+/// # Returns
+///
+/// The order endpoint and the unsigned request.
+fn open_order(position: &Position) -> (&'static str, SignedRequestBuilder) {
+    let request = market_order(&position.symbol, position.order_side, position.quantity);
+    (BING_X_ORDER_ENDPOINT, request)
+}
+
+/// Builds the reduce-only swap market order closing a position, of the position quantity on
+/// the opposite side, so it can't open a position in the other direction.
+///
+/// # Returns
+///
+/// The order endpoint and the unsigned request.
+fn close_order(position: &Position) -> (&'static str, SignedRequestBuilder) {
+    let side = match position.order_side {
+        OrderSide::Buy => OrderSide::Sell,
+        OrderSide::Sell => OrderSide::Buy,
+    };
+    let request =
+        market_order(&position.symbol, side, position.quantity).param("reduceOnly", "true");
+    (BING_X_ORDER_ENDPOINT, request)
+}
+
+/// Builds a swap market order of a quantity. Reduce-only orders need the one-way position
+/// mode, where positions are on side BOTH, so every order is placed on it.
+fn market_order(symbol: &str, order_side: OrderSide, quantity: Qty) -> SignedRequestBuilder {
+    let side = match order_side {
+        OrderSide::Buy => "BUY",
+        OrderSide::Sell => "SELL",
+    };
+    BingXApi::request()
+        .param("symbol", BingXApi::format_bingx_symbol(symbol, false))
+        .param("side", side)
+        .param("positionSide", "BOTH")
+        .param("type", "MARKET")
+        .param("quantity", quantity)
+}
+
+/// Parses the average fill price of a swap order response.
+///
+/// # Returns
+///
+/// The fill price, `None` if the order isn't filled yet, or an error if the order was rejected.
+fn parse_bingx_fill_price(data: &Value) -> ApiResult<Option<f64>> {
+    if data["code"] != 0 {
+        return Err(ApiError::Rejected(data.to_string()));
+    }
+
+    let order = &data["data"]["order"];
+    let fill_price = match &order["avgPrice"] {
+        Value::String(price) => price.parse::<f64>().ok(),
+        price => price.as_f64(),
+    };
+    Ok(fill_price.filter(|price| *price > 0.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests positions are parsed on the side of their position side and empty ones left out.
+    #[test]
+    fn test_parse_bingx_positions() {
+        let data = json!({
+            "code": 0,
+            "data": [
+                { "symbol": "BTC-USDT", "positionSide": "LONG", "positionAmt": "0.01" },
+                { "symbol": "ETH-USDT", "positionSide": "SHORT", "positionAmt": "1.5" },
+                { "symbol": "SOL-USDT", "positionSide": "BOTH", "positionAmt": "-2" },
+                { "symbol": "XRP-USDT", "positionSide": "LONG", "positionAmt": "0" }
+            ]
+        });
+
+        let positions = parse_bingx_positions(&data).unwrap();
+        let sides: Vec<(&str, OrderSide, f64)> = positions
+            .iter()
+            .map(|position| {
+                (
+                    position.symbol.as_str(),
+                    position.order_side,
+                    position.quantity,
+                )
+            })
+            .collect();
+        assert_eq!(
+            sides,
+            vec![
+                ("BTCUSDT", OrderSide::Buy, 0.01),
+                ("ETHUSDT", OrderSide::Sell, 1.5),
+                ("SOLUSDT", OrderSide::Sell, 2.0),
+            ]
+        );
+        assert!(
+            parse_bingx_positions(&json!({ "code": 100_001, "msg": "bad signature" })).is_err()
+        );
+    }
+
+    /// Tests the fill price of a close order is parsed and rejected orders are errors.
+    #[test]
+    fn test_parse_bingx_fill_price() {
+        let data = json!({
+            "code": 0,
+            "msg": "",
+            "data": { "order": { "orderId": 1, "avgPrice": "30000.1" } }
+        });
+        assert_eq!(parse_bingx_fill_price(&data).unwrap(), Some(30000.1));
+
+        let data = json!({ "code": 0, "data": { "order": { "orderId": 1, "avgPrice": 0 } } });
+        assert_eq!(parse_bingx_fill_price(&data).unwrap(), None);
+
+        let data = json!({ "code": 101_400, "msg": "no position to close" });
+        assert!(matches!(
+            parse_bingx_fill_price(&data),
+            Err(ApiError::Rejected(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_bingx_symbols() {
+        let data = json!({
+            "code": 0,
+            "data": [
+                {
+                    "symbol": "BTC-USDT",
+                    "asset": "BTC",
+                    "currency": "USDT",
+                    "status": 1,
+                    "pricePrecision": 1,
+                    "quantityPrecision": 4,
+                    "tradeMinQuantity": 0.0001,
+                    "maxLongLeverage": 125,
+                    "maxShortLeverage": 100
+                },
+                { "symbol": "ETH-USDT", "status": 0 }
+            ]
+        });
+
+        let symbols = parse_bingx_symbols(&data);
+        assert_eq!(symbols.len(), 1);
+        assert_eq!(symbols[0].symbol, "BTCUSDT");
+        assert_eq!(symbols[0].base_asset, "BTC");
+        assert!(symbols[0].trading);
+        assert_eq!(symbols[0].min_quantity, Some(0.0001));
+        assert_eq!(symbols[0].max_leverage, Some(125));
+    }
+
+    /// Tests positions are opened and closed on the swap order endpoint with the same quantity,
+    /// rounded down to the quantity steps of the symbol.
+    #[test]
+    fn test_position_orders() {
+        let precision = SymbolPrecision {
+            price: 2,
+            quantity: 3,
+        };
+        let position = Position::new(
+            "BTCUSDT",
+            Price::from_f64(30_000.0),
+            OrderSide::Sell,
+            100.0,
+            10,
+            None,
+            precision,
+        );
+
+        let (open_endpoint, open_request) = open_order(&position);
+        let (close_endpoint, close_request) = close_order(&position);
+        assert_eq!(open_endpoint, "/openApi/swap/v2/trade/order");
+        assert_eq!(open_endpoint, close_endpoint);
+        // 1000 USD at 30000 is 0.0333.. BTC, rounded down to 0.001 steps
+        assert_eq!(
+            open_request.query_str(),
+            "positionSide=BOTH&quantity=0.033&side=SELL&symbol=BTC-USDT&type=MARKET"
+        );
+        assert_eq!(
+            close_request.query_str(),
+            "positionSide=BOTH&quantity=0.033&reduceOnly=true&side=BUY&symbol=BTC-USDT&type=MARKET"
+        );
+    }
+}
@@ -0,0 +1,113 @@
+use crate::utils::time::{generate_ts, HOUR_AS_MILI, SEC_AS_MILI};
+
+use super::api::{ExchangeApi, ExchangeInfo};
+use super::types::ApiResult;
+
+/// How long fetched exchange info is served before it's fetched again, symbols and their
+/// trading rules rarely change.
+const INFO_TTL: u64 = HOUR_AS_MILI;
+/// How long a fetched account balance is served before it's fetched again.
+const BALANCE_TTL: u64 = 10 * SEC_AS_MILI;
+
+/// A value fetched from the exchange with the time it was fetched at.
+#[derive(Debug, Clone)]
+struct Cached<T> {
+    value: T,
+    fetched_at: u64,
+}
+
+impl<T: Clone> Cached<T> {
+    fn fresh(&self, ttl: u64, now: u64) -> Option<T> {
+        (now.saturating_sub(self.fetched_at) < ttl).then(|| self.value.clone())
+    }
+}
+
+/// Exchange info and account balance fetched through an `ExchangeApi`, kept for a while so
+/// front-ends polling them don't run into the rate limits of the exchange.
+#[derive(Debug, Default)]
+pub struct ExchangeCache {
+    info: Option<Cached<ExchangeInfo>>,
+    balance: Option<Cached<f64>>,
+}
+
+impl ExchangeCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Gets the exchange info, fetched at most once an hour.
+    pub async fn info(&mut self, exchange_api: &dyn ExchangeApi) -> ApiResult<ExchangeInfo> {
+        let now = generate_ts();
+        if let Some(info) = self
+            .info
+            .as_ref()
+            .and_then(|info| info.fresh(INFO_TTL, now))
+        {
+            return Ok(info);
+        }
+
+        let info = exchange_api.info().await?;
+        self.info = Some(Cached {
+            value: info.clone(),
+            fetched_at: now,
+        });
+
+        Ok(info)
+    }
+
+    /// Gets the account balance, fetched at most every few seconds.
+    pub async fn balance(&mut self, exchange_api: &dyn ExchangeApi) -> ApiResult<f64> {
+        let now = generate_ts();
+        if let Some(balance) = self
+            .balance
+            .as_ref()
+            .and_then(|balance| balance.fresh(BALANCE_TTL, now))
+        {
+            return Ok(balance);
+        }
+
+        self.refresh_balance(exchange_api).await
+    }
+
+    /// Fetches the account balance from the exchange, replacing the cached balance.
+    pub async fn refresh_balance(&mut self, exchange_api: &dyn ExchangeApi) -> ApiResult<f64> {
+        let balance = exchange_api.get_account_balance().await?;
+        self.balance = Some(Cached {
+            value: balance,
+            fetched_at: generate_ts(),
+        });
+
+        Ok(balance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchange::mock::MockExchangeApi;
+
+    /// Tests values are fetched once and served from the cache until they expire.
+    #[tokio::test]
+    async fn test_exchange_cache() {
+        let exchange_api = MockExchangeApi::default();
+        let mut cache = ExchangeCache::new();
+
+        let info = cache.info(&exchange_api).await.unwrap();
+        assert_eq!(info.name, "Mock");
+        assert!(cache.info.is_some());
+
+        // the mock exchange can't fetch balances, a fresh balance is served from the cache
+        cache.balance = Some(Cached {
+            value: 1_000.0,
+            fetched_at: generate_ts(),
+        });
+        assert_eq!(cache.balance(&exchange_api).await.unwrap(), 1_000.0);
+
+        let cached = Cached {
+            value: 1_000.0,
+            fetched_at: 0,
+        };
+        assert_eq!(cached.fresh(BALANCE_TTL, BALANCE_TTL - 1), Some(1_000.0));
+        assert_eq!(cached.fresh(BALANCE_TTL, BALANCE_TTL), None);
+    }
+}
@@ -1,7 +1,9 @@
+use crate::account::money::{Price, SymbolPrecision};
 use crate::account::trade::{OrderSide, Position, TradeTx};
 use crate::exchange::api::ExchangeApi;
 use crate::exchange::stream::StreamManager;
-use crate::exchange::types::{ApiResult, StreamType};
+use crate::exchange::types::{ApiError, ApiResult, StreamType};
+use crate::market::interval::Interval;
 use crate::market::kline::Kline;
 use crate::market::ticker::Ticker;
 use crate::market::types::ArcMutex;
@@ -27,7 +29,8 @@ impl ExchangeApi for MockExchangeApi {
     /// * `margin_usd` - A floating-point number representing the amount of margin used for the position in USD.
     /// * `leverage` - An unsigned 32-bit integer representing the leverage applied to the position.
     /// * `order_side` - An `OrderSide` enum value indicating whether the position is a buy or sell.
-    /// * `open_price` - A floating-point number representing the price at which the position is opened.
+    /// * `open_price` - The price at which the position is opened.
+    /// * `precision` - The precision of the symbol the position is sized with.
     ///
     /// # Returns
     ///
@@ -40,9 +43,12 @@ impl ExchangeApi for MockExchangeApi {
         margin_usd: f64,
         leverage: u32,
         order_side: OrderSide,
-        open_price: f64,
+        open_price: Price,
+        precision: SymbolPrecision,
     ) -> ApiResult<Position> {
-        let position = Position::new(symbol, open_price, order_side, margin_usd, leverage, None);
+        let position = Position::new(
+            symbol, open_price, order_side, margin_usd, leverage, None, precision,
+        );
         Ok(position)
     }
 
@@ -55,7 +61,7 @@ impl ExchangeApi for MockExchangeApi {
     /// # Arguments
     ///
     /// * `position` - A `Position` object representing the position to be closed.
-    /// * `close_price` - A floating-point number representing the price at which the position is closed.
+    /// * `close_price` - The price at which the position is closed.
     ///
     /// # Returns
     ///
@@ -63,7 +69,7 @@ impl ExchangeApi for MockExchangeApi {
     /// success, it contains a `TradeTx` object representing the trade transaction details. On failure,
     /// it contains an error.
 
-    async fn close_position(&self, position: Position, close_price: f64) -> ApiResult<TradeTx> {
+    async fn close_position(&self, position: Position, close_price: Price) -> ApiResult<TradeTx> {
         let trade_tx = TradeTx::new(close_price, generate_ts(), position);
         Ok(trade_tx)
     }
@@ -77,7 +83,7 @@ impl ExchangeApi for MockExchangeApi {
     /// # Arguments
     ///
     /// * `position` - A `Position` object representing the position to be closed.
-    /// * `close_price` - A floating-point number representing the price at which the position is closed.
+    /// * `close_price` - The price at which the position is closed.
     ///
     /// # Returns
     ///
@@ -88,9 +94,20 @@ impl ExchangeApi for MockExchangeApi {
     async fn info(&self) -> ApiResult<ExchangeInfo> {
         Ok(ExchangeInfo {
             name: "Mock".to_string(),
+            symbols: vec![],
         })
     }
 
+    /// Simulates cancelling the working orders of a symbol, the mock never has any.
+    async fn cancel_all_orders(&self, _symbol: &str) -> ApiResult<Value> {
+        Ok(Value::Array(vec![]))
+    }
+
+    /// Simulates fetching the ticker of a symbol, the mock has no market prices.
+    async fn get_ticker(&self, symbol: &str) -> ApiResult<Ticker> {
+        Err(ApiError::Unsupported(format!("no ticker for {symbol}")))
+    }
+
     // ---
     // All Other methods not used on this mock MockExchangeApi
     // Will fail if called
@@ -110,10 +127,7 @@ impl ExchangeApi for MockExchangeApi {
     fn get_stream_manager(&self) -> ArcMutex<Box<dyn StreamManager>> {
         unimplemented!()
     }
-    async fn get_kline(&self, _symbol: &str, _interval: &str) -> ApiResult<Kline> {
-        unimplemented!()
-    }
-    async fn get_ticker(&self, _symbol: &str) -> ApiResult<Ticker> {
+    async fn get_kline(&self, _symbol: &str, _interval: Interval) -> ApiResult<Kline> {
         unimplemented!()
     }
 
@@ -121,7 +135,7 @@ impl ExchangeApi for MockExchangeApi {
         &self,
         _symbol: &str,
         _stream_type: StreamType,
-        _interval: Option<&str>,
+        _interval: Option<Interval>,
     ) -> String {
         todo!()
     }
@@ -147,10 +161,13 @@ mod test {
         let margin_usd = 1000.0;
         let leverage = 10;
         let order_side = OrderSide::Buy;
-        let open_price = 50000.0;
+        let open_price = Price::from_f64(50000.0);
+        let precision = SymbolPrecision::default();
 
         let result = api
-            .open_position(symbol, margin_usd, leverage, order_side, open_price)
+            .open_position(
+                symbol, margin_usd, leverage, order_side, open_price, precision,
+            )
             .await;
 
         assert!(result.is_ok());
@@ -170,11 +187,14 @@ mod test {
         let margin_usd = 1000.0;
         let leverage = 10;
         let order_side = OrderSide::Buy;
-        let open_price = 50000.0;
+        let open_price = Price::from_f64(50000.0);
+        let precision = SymbolPrecision::default();
 
-        let position = Position::new(symbol, open_price, order_side, margin_usd, leverage, None);
+        let position = Position::new(
+            symbol, open_price, order_side, margin_usd, leverage, None, precision,
+        );
 
-        let close_price = 55000.0;
+        let close_price = Price::from_f64(55000.0);
 
         let result = api.close_position(position.clone(), close_price).await;
 
@@ -1,6 +1,10 @@
 pub mod api;
+#[cfg(feature = "binance")]
 pub mod binance;
+#[cfg(feature = "bingx")]
 pub mod bingx;
+pub mod cache;
 pub mod mock;
+pub mod profile;
 pub mod stream;
 pub mod types;
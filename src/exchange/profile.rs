@@ -0,0 +1,258 @@
+//! # Credential Profiles
+//!
+//! Named exchange credential sets, such as `bingx-main`, `bingx-sub1` or `binance-test`, each
+//! trading on its own account. Strategies reference a profile by name in their settings, which
+//! isolates sub-accounts from each other and lets live and testnet accounts trade side by side.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use serde::Serialize;
+
+#[cfg(feature = "binance")]
+use crate::exchange::binance::BinanceApi;
+#[cfg(feature = "bingx")]
+use crate::exchange::bingx::BingXApi;
+use crate::exchange::{api::ExchangeApi, mock::MockExchangeApi};
+use crate::market::{messages::MarketMessage, types::ArcSender};
+use crate::secrets::SecretsProvider;
+use crate::utils::env::{env_lookup, Lookup};
+
+/// Account names reserved for the accounts every bot has.
+const RESERVED_NAMES: [&str; 2] = ["live", "shadow"];
+
+/// The exchange a credential profile trades on.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExchangeKind {
+    Binance,
+    #[serde(rename = "bingx")]
+    BingX,
+    /// Fills every order locally, for trying out profiles without credentials.
+    Mock,
+}
+
+impl FromStr for ExchangeKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_uppercase().as_str() {
+            "BINANCE" => Ok(Self::Binance),
+            "BINGX" => Ok(Self::BingX),
+            "MOCK" => Ok(Self::Mock),
+            _ => Err(format!("Unknown exchange: {s}")),
+        }
+    }
+}
+
+impl ExchangeKind {
+    /// Whether the adapter of the exchange is compiled into this build, see the cargo features.
+    pub fn is_enabled(&self) -> bool {
+        match self {
+            Self::Binance => cfg!(feature = "binance"),
+            Self::BingX => cfg!(feature = "bingx"),
+            Self::Mock => true,
+        }
+    }
+}
+
+/// A named set of exchange credentials. The keys are never serialized.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CredentialProfile {
+    pub name: String,
+    pub exchange: ExchangeKind,
+    #[serde(skip)]
+    pub api_key: String,
+    #[serde(skip)]
+    pub secret_key: String,
+    /// Trades on the testnet of the exchange.
+    pub testnet: bool,
+    /// Fills orders locally instead of sending them to the exchange.
+    pub dry_run: bool,
+}
+
+impl CredentialProfile {
+    /// Reads the profiles listed in `EXCHANGE_PROFILES`, separated by commas.
+    ///
+    /// Each profile is configured by variables prefixed with its name in upper case, dashes
+    /// replaced by underscores, e.g. for `bingx-sub1`:
+    /// `PROFILE_BINGX_SUB1_EXCHANGE`, `PROFILE_BINGX_SUB1_API_KEY`,
+    /// `PROFILE_BINGX_SUB1_SECRET_KEY` and optionally `PROFILE_BINGX_SUB1_TESTNET` and
+    /// `PROFILE_BINGX_SUB1_DRY_RUN`. The keys are read from the secrets provider, under the same
+    /// names.
+    ///
+    /// # Returns
+    ///
+    /// The profiles in the order they are listed, none if `EXCHANGE_PROFILES` isn't set, or an
+    /// error if a profile is incomplete or invalid or its keys can't be read.
+    pub async fn from_env(secrets: &dyn SecretsProvider) -> Result<Vec<Self>, String> {
+        let mut keys = HashMap::new();
+        let names = env_lookup("EXCHANGE_PROFILES").unwrap_or_default();
+        for name in names.split(',').map(str::trim) {
+            for key in ["API_KEY", "SECRET_KEY"] {
+                let key = format!("{}{key}", var_prefix(name));
+                if let Some(secret) = secrets.get(&key).await? {
+                    keys.insert(key, secret);
+                }
+            }
+        }
+
+        // keys never fall back to the environment of providers other than `env`
+        Self::from_lookup(|key| {
+            if key.ends_with("_API_KEY") || key.ends_with("_SECRET_KEY") {
+                keys.get(key).cloned()
+            } else {
+                env_lookup(key)
+            }
+        })
+    }
+
+    /// Reads the exchange, keys and testnet and dry run flags of every listed profile through
+    /// `lookup`, see `from_env`.
+    pub fn from_lookup(lookup: impl Lookup) -> Result<Vec<Self>, String> {
+        let Some(names) = lookup("EXCHANGE_PROFILES") else {
+            return Ok(vec![]);
+        };
+
+        let mut profiles: Vec<Self> = vec![];
+        for name in names
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+        {
+            if RESERVED_NAMES.contains(&name) {
+                return Err(format!("Profile name {name} is reserved"));
+            }
+            if profiles.iter().any(|profile| profile.name == name) {
+                return Err(format!("Profile {name} is listed twice"));
+            }
+
+            let prefix = var_prefix(name);
+            let var = |key: &str| lookup(&format!("{prefix}{key}"));
+            let required = |key: &str| {
+                var(key).ok_or_else(|| format!("Profile {name} is missing {prefix}{key}"))
+            };
+            let flag = |key: &str| var(key).is_some_and(|value| value.trim() == "true");
+
+            let exchange: ExchangeKind = required("EXCHANGE")?.parse()?;
+            if !exchange.is_enabled() {
+                return Err(format!(
+                    "Profile {name}: {exchange:?} isn't enabled in this build"
+                ));
+            }
+            let (api_key, secret_key) = match exchange {
+                ExchangeKind::Mock => (String::new(), String::new()),
+                _ => (required("API_KEY")?, required("SECRET_KEY")?),
+            };
+            let testnet = flag("TESTNET");
+            if testnet && exchange == ExchangeKind::BingX {
+                return Err(format!("Profile {name}: BingX has no testnet"));
+            }
+
+            profiles.push(Self {
+                name: name.to_string(),
+                exchange,
+                api_key,
+                secret_key,
+                testnet,
+                dry_run: flag("DRY_RUN") || exchange == ExchangeKind::Mock,
+            });
+        }
+
+        Ok(profiles)
+    }
+
+    /// Builds the exchange API orders of the profile are sent to.
+    ///
+    /// # Arguments
+    ///
+    /// * `market_sender` - Receives the market data of streams opened through the API.
+    #[cfg_attr(
+        not(any(feature = "binance", feature = "bingx")),
+        allow(unused_variables)
+    )]
+    pub fn build_api(&self, market_sender: ArcSender<MarketMessage>) -> Arc<Box<dyn ExchangeApi>> {
+        if self.dry_run {
+            return Arc::new(Box::new(MockExchangeApi {}));
+        }
+
+        match self.exchange {
+            #[cfg(feature = "binance")]
+            ExchangeKind::Binance => Arc::new(Box::new(BinanceApi::new(
+                &self.api_key,
+                &self.secret_key,
+                market_sender,
+                self.testnet,
+            ))),
+            #[cfg(feature = "bingx")]
+            ExchangeKind::BingX => Arc::new(Box::new(BingXApi::new(
+                &self.api_key,
+                &self.secret_key,
+                market_sender,
+            ))),
+            // profiles of exchanges left out of the build are refused when read
+            _ => Arc::new(Box::new(MockExchangeApi {})),
+        }
+    }
+}
+
+/// The prefix of the variables of a profile, e.g. `PROFILE_BINGX_SUB1_` for `bingx-sub1`.
+fn var_prefix(name: &str) -> String {
+    format!(
+        "PROFILE_{}_",
+        name.to_uppercase()
+            .replace(|c: char| !c.is_ascii_alphanumeric(), "_")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::env::vars_lookup;
+
+    #[cfg(all(feature = "binance", feature = "bingx"))]
+    fn profiles(vars: &[(&str, &str)]) -> Result<Vec<CredentialProfile>, String> {
+        CredentialProfile::from_lookup(vars_lookup(vars))
+    }
+
+    /// Tests profiles are read by name and incomplete or reserved profiles are refused.
+    #[cfg(all(feature = "binance", feature = "bingx"))]
+    #[test]
+    fn test_credential_profiles() {
+        assert!(profiles(&[]).unwrap().is_empty());
+
+        let vars = [
+            ("EXCHANGE_PROFILES", "bingx-sub1, binance-test,paper"),
+            ("PROFILE_BINGX_SUB1_EXCHANGE", "bingx"),
+            ("PROFILE_BINGX_SUB1_API_KEY", "key"),
+            ("PROFILE_BINGX_SUB1_SECRET_KEY", "secret"),
+            ("PROFILE_BINANCE_TEST_EXCHANGE", "BINANCE"),
+            ("PROFILE_BINANCE_TEST_API_KEY", "test-key"),
+            ("PROFILE_BINANCE_TEST_SECRET_KEY", "test-secret"),
+            ("PROFILE_BINANCE_TEST_TESTNET", "true"),
+            ("PROFILE_PAPER_EXCHANGE", "mock"),
+        ];
+        let loaded = profiles(&vars).unwrap();
+        assert_eq!(loaded.len(), 3);
+        assert_eq!(loaded[0].name, "bingx-sub1");
+        assert_eq!(loaded[0].exchange, ExchangeKind::BingX);
+        assert_eq!(loaded[0].api_key, "key");
+        assert!(!loaded[0].testnet && !loaded[0].dry_run);
+        assert!(loaded[1].testnet);
+        assert!(loaded[2].dry_run);
+
+        let json = serde_json::to_value(&loaded[0]).unwrap();
+        assert_eq!(json["exchange"], "bingx");
+        assert!(json.get("api_key").is_none() && json.get("secret_key").is_none());
+
+        let missing_key = &vars[..5];
+        assert!(profiles(missing_key).is_err());
+        assert!(profiles(&[("EXCHANGE_PROFILES", "live")]).is_err());
+        assert!(profiles(&[
+            ("EXCHANGE_PROFILES", "paper,paper"),
+            ("PROFILE_PAPER_EXCHANGE", "mock"),
+        ])
+        .is_err());
+    }
+}
@@ -1,13 +1,21 @@
 use std::collections::HashMap;
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use async_trait::async_trait;
 
-use crate::{exchange::types::StreamType, market::types::ArcMutex, utils::time::generate_ts};
+use crate::{
+    exchange::types::StreamType,
+    market::{interval::Interval, types::ArcMutex},
+    utils::time::generate_ts,
+};
 
 use super::types::ApiResult;
 
+/// Time without an update after which a stream is reported as stale, streams update at least
+/// every few seconds while the exchange connection is alive.
+pub const STREAM_STALE_AFTER_MS: u64 = 60_000;
+
 /// Provides an interface for managing data streams in a concurrent environment.
 ///
 /// This trait defines the essential functionalities for opening and closing streams, as well as
@@ -70,7 +78,7 @@ pub trait StreamManager: Send + Sync {
 }
 
 /// A struct representing metadata for a stream.
-#[derive(Serialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct StreamMeta {
     /// The ID of the stream.
     pub id: String,
@@ -85,7 +93,7 @@ pub struct StreamMeta {
     /// The symbol associated with the stream.
     pub symbol: String,
     /// The interval of the stream, if applicable.
-    pub interval: Option<String>,
+    pub interval: Option<Interval>,
 }
 
 impl StreamMeta {
@@ -107,7 +115,7 @@ impl StreamMeta {
         url: &str,
         symbol: &str,
         stream_type: StreamType,
-        interval: Option<String>,
+        interval: Option<Interval>,
     ) -> Self {
         Self {
             id: id.to_string(),
@@ -121,6 +129,52 @@ impl StreamMeta {
     }
 }
 
+/// Whether a stream still receives updates.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum StreamHealth {
+    Healthy,
+    /// No update for `STREAM_STALE_AFTER_MS`, the connection is likely lost.
+    Stale,
+}
+
+/// An active stream with its health.
+#[derive(Serialize, Debug, Clone)]
+pub struct StreamStatus {
+    #[serde(flatten)]
+    pub meta: StreamMeta,
+    pub health: StreamHealth,
+    /// Milliseconds since the last update of the stream.
+    pub idle_ms: u64,
+    /// Whether a running strategy needs the stream, needed streams are reopened when closed.
+    pub needed: bool,
+}
+
+impl StreamStatus {
+    /// Builds the status of a stream at a given time.
+    ///
+    /// # Arguments
+    ///
+    /// * `meta` - The metadata of the stream.
+    /// * `needed` - Whether a running strategy needs the stream.
+    /// * `now` - The current timestamp.
+    pub fn new(meta: StreamMeta, needed: bool, now: u64) -> Self {
+        let idle_ms = now.saturating_sub(meta.last_update);
+        let health = if idle_ms > STREAM_STALE_AFTER_MS {
+            StreamHealth::Stale
+        } else {
+            StreamHealth::Healthy
+        };
+
+        Self {
+            meta,
+            health,
+            idle_ms,
+            needed,
+        }
+    }
+}
+
 impl Default for StreamMeta {
     fn default() -> Self {
         Self {
@@ -145,7 +199,11 @@ impl Default for StreamMeta {
 /// # Returns
 ///
 /// Returns the ID of the stream.
-pub fn build_stream_id(symbol: &str, stream_type: StreamType, interval: Option<&str>) -> String {
+pub fn build_stream_id(
+    symbol: &str,
+    stream_type: StreamType,
+    interval: Option<Interval>,
+) -> String {
     match stream_type {
         StreamType::Kline => {
             if let Some(interval) = interval {
@@ -162,3 +220,28 @@ pub fn build_stream_id(symbol: &str, stream_type: StreamType, interval: Option<&
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests streams without an update for longer than the threshold are reported as stale.
+    #[test]
+    fn test_stream_status() {
+        let meta = StreamMeta {
+            last_update: 1_000,
+            ..Default::default()
+        };
+
+        let status = StreamStatus::new(meta.clone(), true, 1_000 + STREAM_STALE_AFTER_MS);
+        assert_eq!(status.health, StreamHealth::Healthy);
+        assert_eq!(status.idle_ms, STREAM_STALE_AFTER_MS);
+
+        let status = StreamStatus::new(meta, false, 2_000 + STREAM_STALE_AFTER_MS);
+        assert_eq!(status.health, StreamHealth::Stale);
+        let json = serde_json::to_value(&status).unwrap();
+        assert_eq!(json["health"], "stale");
+        assert_eq!(json["symbol"], "unknown");
+        assert_eq!(json["needed"], false);
+    }
+}
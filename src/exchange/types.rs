@@ -1,7 +1,7 @@
 use futures_util::stream::SplitSink;
 use serde::{Deserialize, Serialize};
-use std::error::Error;
 use std::fmt::{self, Display, Formatter};
+use thiserror::Error;
 use tokio::net::TcpStream;
 use tokio_tungstenite::tungstenite::Message;
 use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
@@ -9,41 +9,27 @@ use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
 use crate::market::types::ArcMutex;
 /// Custom error type for API-related errors.
 ///
-/// This enum represents various types of errors that can occur during API operations.
-#[derive(Debug)]
+/// This enum represents various types of errors that can occur during API operations. It is
+/// the exchange layer of `RaderBotError`.
+#[derive(Debug, Error)]
 pub enum ApiError {
     /// Represents a network-related error with a descriptive message.
+    #[error("Network error: {0}")]
     Network(String),
     /// Represents a parsing-related error with a descriptive message.
+    #[error("Parsing error: {0}")]
     Parsing(String),
     /// Represents a Reqwest error with a descriptive message.
+    #[error("Reqwest error: {0}")]
     Reqwest(String),
+    /// The exchange doesn't offer the request, e.g. funding rates on a mock exchange.
+    #[error("Not supported: {0}")]
+    Unsupported(String),
+    /// The exchange rejected the request, e.g. an order failing its checks.
+    #[error("Rejected: {0}")]
+    Rejected(String),
 }
 
-/// Implementation of the `Display` trait for `ApiError`.
-///
-/// This implementation formats an `ApiError` for display purposes.
-impl fmt::Display for ApiError {
-    /// Formats the error for display.
-    ///
-    /// # Arguments
-    ///
-    /// * `f` - A mutable reference to a formatter.
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        // Match on the enum variant to format the error message accordingly
-        match self {
-            ApiError::Network(msg) => write!(f, "Network error: {}", msg),
-            ApiError::Parsing(msg) => write!(f, "Parsing error: {}", msg),
-            ApiError::Reqwest(msg) => write!(f, "Reqwest error: {}", msg),
-        }
-    }
-}
-
-/// Implementation of the `Error` trait for `ApiError`.
-///
-/// This implementation allows `ApiError` to be used as an error type in the `Result` type.
-impl Error for ApiError {}
-
 /// Custom result type used for API operations.
 ///
 /// This type aliases a `Result` with the `ApiError` enum as the error type.
@@ -0,0 +1,389 @@
+//! # Runtime Health
+//!
+//! Metrics of the bot's own runtime: the backlog of its channels, the liveness and iteration
+//! latency of its long running tasks and the time spent waiting for its shared locks. Operators
+//! read them from `GET /api/metrics` to spot a stalled strategy task or a backed-up market channel
+//! before it costs money.
+//!
+//! Tasks record into the process wide registry returned by `runtime`, so instrumenting a task
+//! doesn't require threading a handle through its constructor.
+
+use std::collections::BTreeMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use futures_util::lock::MutexGuard;
+use serde::Serialize;
+use tokio::sync::RwLockReadGuard;
+
+use crate::market::types::{ArcMutex, ArcRwLock};
+use crate::utils::channel::{ChannelStats, OverflowPolicy};
+use crate::utils::time::generate_ts;
+
+/// Tasks with an expected interval are stalled once they haven't iterated for this many
+/// intervals.
+const STALL_INTERVALS: u32 = 3;
+
+/// Grace period added to the stall threshold of a task, covering tasks that wait for the next
+/// minute before their first iteration.
+const STALL_GRACE: Duration = Duration::from_secs(60);
+
+/// Backlog of a channel from which on it counts as backed up, bounded channels also count as
+/// backed up once full.
+const CHANNEL_BACKLOG_LIMIT: usize = 1000;
+
+/// Returns the registry every task of the process records its metrics to.
+pub fn runtime() -> &'static RuntimeHealth {
+    static RUNTIME: OnceLock<RuntimeHealth> = OnceLock::new();
+    RUNTIME.get_or_init(RuntimeHealth::default)
+}
+
+/// Locks a mutex, recording the time waited for it under the name of the lock.
+///
+/// # Arguments
+///
+/// * `name` - The name the wait time is recorded under, e.g. `market`.
+/// * `mutex` - The mutex to lock.
+pub async fn timed_lock<'a, T>(name: &str, mutex: &'a ArcMutex<T>) -> MutexGuard<'a, T> {
+    let started = Instant::now();
+    let guard = mutex.lock().await;
+    runtime().record_lock_wait(name, started.elapsed());
+
+    guard
+}
+
+/// Takes a read lock, recording the time waited for it under the name of the lock, see
+/// `timed_lock`.
+pub async fn timed_read<'a, T>(name: &str, lock: &'a ArcRwLock<T>) -> RwLockReadGuard<'a, T> {
+    let started = Instant::now();
+    let guard = lock.read().await;
+    runtime().record_lock_wait(name, started.elapsed());
+
+    guard
+}
+
+/// Starts timing an iteration of a task, recorded when the returned timer is dropped, so
+/// iterations ending early are recorded too.
+pub fn time_iteration(task: &str) -> IterationTimer {
+    IterationTimer {
+        task: task.to_string(),
+        started: Instant::now(),
+    }
+}
+
+/// Records an iteration of a task when dropped, see `time_iteration`.
+pub struct IterationTimer {
+    task: String,
+    started: Instant,
+}
+
+impl Drop for IterationTimer {
+    fn drop(&mut self) {
+        runtime().record_iteration(&self.task, self.started.elapsed());
+    }
+}
+
+#[derive(Debug, Default)]
+struct ChannelState {
+    depth: usize,
+    max_depth: usize,
+    updated_at: u64,
+    capacity: Option<usize>,
+    policy: Option<OverflowPolicy>,
+    dropped: u64,
+}
+
+#[derive(Debug, Default)]
+struct TaskState {
+    expected_interval: Option<Duration>,
+    started_at: u64,
+    iterations: u64,
+    last_iteration_at: Option<u64>,
+    last_latency: Duration,
+    max_latency: Duration,
+    total_latency: Duration,
+}
+
+#[derive(Debug, Default)]
+struct LockState {
+    acquisitions: u64,
+    last_wait: Duration,
+    max_wait: Duration,
+    total_wait: Duration,
+}
+
+#[derive(Debug, Default)]
+struct HealthState {
+    channels: BTreeMap<String, ChannelState>,
+    tasks: BTreeMap<String, TaskState>,
+    locks: BTreeMap<String, LockState>,
+}
+
+/// Records runtime metrics of channels, tasks and locks, see the module documentation.
+#[derive(Debug, Default)]
+pub struct RuntimeHealth {
+    state: Mutex<HealthState>,
+}
+
+impl RuntimeHealth {
+    /// Records the number of messages waiting in a channel, sampled by its receiver.
+    pub fn record_channel_depth(&self, channel: &str, depth: usize) {
+        let mut state = self.state();
+        let channel = state.channels.entry(channel.to_string()).or_default();
+        channel.depth = depth;
+        channel.max_depth = channel.max_depth.max(depth);
+        channel.updated_at = generate_ts();
+    }
+
+    /// Records the backlog of a bounded channel, along with its capacity and the messages it
+    /// dropped, sampled by its receiver.
+    pub fn record_channel_stats(&self, channel: &str, stats: ChannelStats) {
+        self.record_channel_depth(channel, stats.depth);
+
+        let mut state = self.state();
+        let channel = state.channels.entry(channel.to_string()).or_default();
+        channel.capacity = Some(stats.capacity);
+        channel.policy = Some(stats.policy);
+        channel.dropped = stats.dropped;
+    }
+
+    /// Registers a long running task, replacing its earlier metrics.
+    ///
+    /// # Arguments
+    ///
+    /// * `task` - The name of the task, e.g. `position_monitor`.
+    /// * `expected_interval` - The time between iterations of tasks running periodically, tasks
+    ///   waiting for messages have none and are never considered stalled.
+    pub fn register_task(&self, task: &str, expected_interval: Option<Duration>) {
+        self.state().tasks.insert(
+            task.to_string(),
+            TaskState {
+                expected_interval,
+                started_at: generate_ts(),
+                ..Default::default()
+            },
+        );
+    }
+
+    /// Records an iteration of a task and the time it took, tasks which aren't registered,
+    /// such as removed tasks, are skipped.
+    pub fn record_iteration(&self, task: &str, latency: Duration) {
+        let mut state = self.state();
+        let Some(task) = state.tasks.get_mut(task) else {
+            return;
+        };
+        task.iterations += 1;
+        task.last_iteration_at = Some(generate_ts());
+        task.last_latency = latency;
+        task.max_latency = task.max_latency.max(latency);
+        task.total_latency += latency;
+    }
+
+    /// Forgets a task which ended on purpose, such as a stopped strategy.
+    pub fn remove_task(&self, task: &str) {
+        self.state().tasks.remove(task);
+    }
+
+    /// Records the time waited for a lock.
+    pub fn record_lock_wait(&self, lock: &str, wait: Duration) {
+        let mut state = self.state();
+        let lock = state.locks.entry(lock.to_string()).or_default();
+        lock.acquisitions += 1;
+        lock.last_wait = wait;
+        lock.max_wait = lock.max_wait.max(wait);
+        lock.total_wait += wait;
+    }
+
+    /// Reports the recorded metrics.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - The current time in milliseconds, idle times are measured up to it.
+    pub fn report(&self, now: u64) -> HealthReport {
+        let state = self.state();
+
+        let channels = state
+            .channels
+            .iter()
+            .map(|(name, channel)| ChannelHealth {
+                name: name.clone(),
+                depth: channel.depth,
+                max_depth: channel.max_depth,
+                capacity: channel.capacity,
+                policy: channel.policy,
+                dropped: channel.dropped,
+                backed_up: channel.depth >= CHANNEL_BACKLOG_LIMIT
+                    || channel
+                        .capacity
+                        .is_some_and(|capacity| channel.depth >= capacity),
+                sampled_ms_ago: now.saturating_sub(channel.updated_at),
+            })
+            .collect();
+
+        let tasks: Vec<TaskHealth> = state
+            .tasks
+            .iter()
+            .map(|(name, task)| {
+                let idle_ms = now.saturating_sub(task.last_iteration_at.unwrap_or(task.started_at));
+                let stalled = task.expected_interval.is_some_and(|interval| {
+                    let threshold = interval * STALL_INTERVALS + STALL_GRACE;
+                    idle_ms > threshold.as_millis() as u64
+                });
+
+                TaskHealth {
+                    name: name.clone(),
+                    iterations: task.iterations,
+                    idle_ms,
+                    stalled,
+                    last_latency_us: task.last_latency.as_micros() as u64,
+                    max_latency_us: task.max_latency.as_micros() as u64,
+                    avg_latency_us: average_us(task.total_latency, task.iterations),
+                }
+            })
+            .collect();
+
+        let locks = state
+            .locks
+            .iter()
+            .map(|(name, lock)| LockHealth {
+                name: name.clone(),
+                acquisitions: lock.acquisitions,
+                last_wait_us: lock.last_wait.as_micros() as u64,
+                max_wait_us: lock.max_wait.as_micros() as u64,
+                avg_wait_us: average_us(lock.total_wait, lock.acquisitions),
+            })
+            .collect();
+
+        HealthReport {
+            timestamp: now,
+            stalled_tasks: tasks
+                .iter()
+                .filter(|task| task.stalled)
+                .map(|task| task.name.clone())
+                .collect(),
+            channels,
+            tasks,
+            locks,
+        }
+    }
+
+    // ---
+    // Private Methods
+    // ---
+
+    fn state(&self) -> std::sync::MutexGuard<'_, HealthState> {
+        // metrics stay readable even if a task panicked while recording
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// The backlog of a channel.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ChannelHealth {
+    pub name: String,
+    /// Messages waiting when the receiver last took one.
+    pub depth: usize,
+    pub max_depth: usize,
+    /// The number of messages a bounded channel holds.
+    pub capacity: Option<usize>,
+    pub policy: Option<OverflowPolicy>,
+    /// Messages a bounded channel dropped because it was full.
+    pub dropped: u64,
+    pub backed_up: bool,
+    pub sampled_ms_ago: u64,
+}
+
+/// The liveness and iteration latency of a task.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TaskHealth {
+    pub name: String,
+    pub iterations: u64,
+    /// Time since the last iteration, or since the task started if it never iterated.
+    pub idle_ms: u64,
+    /// Whether a periodic task missed several of its intervals, e.g. because it died.
+    pub stalled: bool,
+    pub last_latency_us: u64,
+    pub max_latency_us: u64,
+    pub avg_latency_us: u64,
+}
+
+/// The time spent waiting for a lock.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct LockHealth {
+    pub name: String,
+    pub acquisitions: u64,
+    pub last_wait_us: u64,
+    pub max_wait_us: u64,
+    pub avg_wait_us: u64,
+}
+
+/// The runtime metrics of the bot.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct HealthReport {
+    pub timestamp: u64,
+    /// Names of the stalled tasks, empty while the bot is healthy.
+    pub stalled_tasks: Vec<String>,
+    pub channels: Vec<ChannelHealth>,
+    pub tasks: Vec<TaskHealth>,
+    pub locks: Vec<LockHealth>,
+}
+
+fn average_us(total: Duration, count: u64) -> u64 {
+    if count == 0 {
+        return 0;
+    }
+    total.as_micros() as u64 / count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests periodic tasks are reported stalled once they miss their intervals and channels
+    /// once their backlog grows.
+    #[test]
+    fn test_runtime_health() {
+        let health = RuntimeHealth::default();
+        health.register_task("monitor", Some(Duration::from_secs(10)));
+        health.register_task("receiver", None);
+        health.record_iteration("monitor", Duration::from_micros(300));
+        health.record_iteration("monitor", Duration::from_micros(100));
+        health.record_channel_depth("market_tx", 1200);
+        health.record_channel_depth("market_tx", 5);
+        health.record_lock_wait("market", Duration::from_micros(50));
+
+        let report = health.report(generate_ts());
+        assert!(report.stalled_tasks.is_empty());
+        let monitor = &report.tasks[0];
+        assert_eq!(monitor.name, "monitor");
+        assert_eq!(monitor.iterations, 2);
+        assert_eq!(monitor.max_latency_us, 300);
+        assert_eq!(monitor.avg_latency_us, 200);
+        assert_eq!(report.channels[0].depth, 5);
+        assert_eq!(report.channels[0].max_depth, 1200);
+        assert!(!report.channels[0].backed_up);
+        assert_eq!(report.locks[0].acquisitions, 1);
+
+        health.record_channel_stats(
+            "strategy_tx",
+            ChannelStats {
+                depth: 10,
+                capacity: 10,
+                policy: OverflowPolicy::Block,
+                dropped: 0,
+            },
+        );
+        let report = health.report(generate_ts());
+        assert_eq!(report.channels[1].capacity, Some(10));
+        assert!(report.channels[1].backed_up);
+
+        let later = generate_ts() + 120_000;
+        let report = health.report(later);
+        assert_eq!(report.stalled_tasks, vec!["monitor".to_string()]);
+
+        health.remove_task("monitor");
+        assert_eq!(health.report(later).tasks.len(), 1);
+    }
+}
@@ -9,17 +9,25 @@
 
 use app::new_app_state;
 use dotenv::dotenv;
-use log::info;
+use log::{error, info};
+use server::ServerConfig;
+use shutdown::{shutdown_signal, ShutdownPolicy};
 use std::io;
 
 use actix_files::Files;
-use actix_web::middleware::Logger;
-use actix_web::{App, HttpServer};
+use actix_web::middleware::{from_fn, Condition, Logger};
+use actix_web::{web, App, HttpServer};
 
+use api::error::{json_error_handler, path_error_handler, query_error_handler};
+#[cfg(feature = "graphql")]
+use api::graphql::{build_schema, register_graphql_service};
+use api::tenant::authenticate_tenant;
 use api::{
-    account::register_account_service, exchange::register_exchange_service,
+    account::register_account_service, admin::register_admin_service,
+    backtest::register_backtest_service, exchange::register_exchange_service,
     main::register_main_service, market::register_market_service,
     strategy::register_strategy_service, utils::register_utils_service,
+    webhook::register_webhook_service, ws::register_ws_service,
 };
 
 #[allow(unused_must_use)]
@@ -27,24 +35,35 @@ mod account;
 mod algorithm;
 mod api;
 mod app;
+mod audit;
 mod bot;
+mod config;
+mod error;
+mod events;
 mod exchange;
+mod health;
 mod market;
+mod notify;
+mod scheduler;
+mod secrets;
+mod server;
+mod shutdown;
+mod snapshot;
 mod storage;
 mod strategy;
+mod tenant;
 mod utils;
 
-/// Server host configuration (IP address and port).
-const SERVER_HOST: (&str, u16) = ("127.0.0.1", 3000);
-
 /// The main function serves as the entry point of the application.
 /// It performs initial setup, including loading environment variables, initializing logging,
 /// creating application state, and starting the HTTP server with all the configured services.
 ///
 /// # Errors
 ///
-/// This function will return an `io::Error` if there's an issue binding the server to the specified address
-/// or if any other issue occurs while starting the server.
+/// This function will return an `io::Error` if the server or bot configuration is invalid, if
+/// there's an issue binding the server to the configured address or if any other issue occurs
+/// while starting the server. The process exits with code 1 if a shutdown, requested by SIGTERM, SIGINT or the
+/// admin API, couldn't persist all state.
 ///
 /// # Examples
 ///
@@ -60,20 +79,47 @@ const SERVER_HOST: (&str, u16) = ("127.0.0.1", 3000);
 
 async fn main() -> io::Result<()> {
     dotenv().ok();
-    env_logger::init();
+    config::init_logger();
+
+    let config =
+        ServerConfig::from_env().map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let tls_acceptor = config.tls_acceptor()?;
+    let scheme = if tls_acceptor.is_some() {
+        "https"
+    } else {
+        "http"
+    };
 
     info!(
-        "Server listening at {:}:{:}...",
-        SERVER_HOST.0, SERVER_HOST.1
+        "Server listening at {scheme}://{}:{}...",
+        config.host, config.port
     );
 
-    let app_state = new_app_state().await;
+    let app_state = new_app_state().await.map_err(|e| {
+        error!("Unable to start the bot, e: {e}");
+        io::Error::new(io::ErrorKind::InvalidInput, e)
+    })?;
+    let bot = app_state.bot.clone();
+    let signal_bot = bot.clone();
+    #[cfg(feature = "graphql")]
+    let graphql_schema = web::Data::new(build_schema());
 
     // Make new HTTP server
-    HttpServer::new(move || {
+    let server_config = config.clone();
+    let server = HttpServer::new(move || {
         App::new()
+            // requests are scoped to tenants after CORS preflights are answered
+            .wrap(from_fn(authenticate_tenant))
+            .wrap(Condition::new(
+                server_config.has_cors(),
+                server_config.cors(),
+            ))
             .wrap(Logger::default())
             .app_data(app_state.clone())
+            // malformed requests are answered like every other API error
+            .app_data(web::JsonConfig::default().error_handler(json_error_handler))
+            .app_data(web::QueryConfig::default().error_handler(query_error_handler))
+            .app_data(web::PathConfig::default().error_handler(path_error_handler))
             .service(Files::new("/static", "./static"))
             .service(register_market_service())
             .service(register_exchange_service())
@@ -81,9 +127,54 @@ async fn main() -> io::Result<()> {
             .service(register_utils_service())
             .service(register_account_service())
             .service(register_strategy_service())
-    })
-    // .listen(listener)?
-    .bind(SERVER_HOST)?
-    .run()
-    .await
+            .service(register_backtest_service())
+            .service(register_ws_service())
+            .service(register_admin_service())
+            .configure(|_cfg| {
+                #[cfg(feature = "graphql")]
+                _cfg.app_data(graphql_schema.clone())
+                    .service(register_graphql_service());
+            })
+            .service(register_webhook_service())
+    });
+    let server = match tls_acceptor {
+        Some(tls_acceptor) => server.bind_openssl(config.address(), tls_acceptor)?,
+        None => server.bind(config.address())?,
+    }
+    // signals shut the bot down before the server stops, see below
+    .disable_signals()
+    .run();
+
+    let server_handle = server.handle();
+    actix_web::rt::spawn(async move {
+        let signal = match shutdown_signal().await {
+            Ok(signal) => signal,
+            Err(e) => {
+                info!("Unable to listen for shutdown signals, e: {e}");
+                return;
+            }
+        };
+
+        info!("Received {signal}, shutting down...");
+        let policy = ShutdownPolicy::from_env().unwrap_or_else(|e| {
+            info!("{e}, keeping positions open");
+            ShutdownPolicy::default()
+        });
+        signal_bot.lock().await.shutdown(policy).await;
+        server_handle.stop(true).await;
+    });
+
+    server.await?;
+
+    if let Some(report) = bot.lock().await.shutdown_report() {
+        if !report.errors.is_empty() {
+            error!(
+                "Shut down with {} errors, state may be lost",
+                report.errors.len()
+            );
+            std::process::exit(1);
+        }
+    }
+
+    Ok(())
 }
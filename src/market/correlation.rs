@@ -0,0 +1,292 @@
+//! # Correlations
+//!
+//! Rolling correlations between the returns of the symbols the bot tracks, the symbols of its
+//! needed kline streams. Returns are the log change of the close price from one kline to the
+//! next, paired across symbols by open time, and correlated over the last `window` pairs.
+//!
+//! The market keeps a matrix per kline interval up to date, which the `SignalManager` reads to
+//! cap the margin of positions correlated with a new one (`max_correlated_margin`), and which
+//! pairs-trading strategies can read through `CorrelationMatrix::pairs` to select their legs.
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    market::{interval::Interval, kline::Kline},
+    utils::{env::Lookup, time::generate_ts},
+};
+
+/// Returns the correlations are calculated over unless `CORRELATION_WINDOW` is set.
+pub const DEFAULT_CORRELATION_WINDOW: usize = 100;
+
+/// Correlation from which positions count as correlated exposure, see
+/// `StrategySettings::max_correlated_margin`.
+pub const CORRELATION_THRESHOLD: f64 = 0.7;
+
+/// Return pairs below which the correlation of two symbols isn't calculated.
+const MIN_CORRELATION_SAMPLES: usize = 10;
+
+/// The correlations between the returns of a set of symbols.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CorrelationMatrix {
+    pub interval: Interval,
+    /// The number of returns the correlations are calculated over.
+    pub window: usize,
+    pub symbols: Vec<String>,
+    /// The correlation of each pair of symbols, in the order of `symbols`. `None` where the
+    /// symbols have too few returns in common.
+    pub correlations: Vec<Vec<Option<f64>>>,
+    /// The time the matrix was calculated.
+    pub timestamp: u64,
+}
+
+/// Two symbols and the correlation of their returns.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SymbolPair {
+    pub first: String,
+    pub second: String,
+    pub correlation: f64,
+}
+
+impl CorrelationMatrix {
+    /// Calculates the correlations of the symbols of the given klines.
+    ///
+    /// # Arguments
+    ///
+    /// * `interval` - The interval of the klines.
+    /// * `window` - The number of latest returns the correlations are calculated over.
+    /// * `klines` - The klines of each symbol, sorted by open time.
+    pub fn from_klines(interval: Interval, window: usize, klines: &[(String, Vec<Kline>)]) -> Self {
+        let returns: Vec<BTreeMap<u64, f64>> = klines
+            .iter()
+            .map(|(_, klines)| calc_returns(klines))
+            .collect();
+
+        let correlations = (0..returns.len())
+            .map(|row| {
+                (0..returns.len())
+                    .map(|column| {
+                        if row == column && !returns[row].is_empty() {
+                            Some(1.0)
+                        } else {
+                            correlate(&returns[row], &returns[column], window)
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            interval,
+            window,
+            symbols: klines.iter().map(|(symbol, _)| symbol.clone()).collect(),
+            correlations,
+            timestamp: generate_ts(),
+        }
+    }
+
+    /// Retrieves the correlation of two symbols, `None` if either isn't in the matrix or they
+    /// have too few returns in common.
+    pub fn get(&self, first: &str, second: &str) -> Option<f64> {
+        let row = self.symbols.iter().position(|symbol| symbol == first)?;
+        let column = self.symbols.iter().position(|symbol| symbol == second)?;
+        self.correlations[row][column]
+    }
+
+    /// Lists the pairs of distinct symbols whose correlation is at least `min_correlation` in
+    /// absolute terms, strongest first, e.g. to select the legs of a pairs trade.
+    pub fn pairs(&self, min_correlation: f64) -> Vec<SymbolPair> {
+        let mut pairs = vec![];
+        for (row, first) in self.symbols.iter().enumerate() {
+            for (column, second) in self.symbols.iter().enumerate().skip(row + 1) {
+                if let Some(correlation) = self.correlations[row][column] {
+                    if correlation.abs() >= min_correlation {
+                        pairs.push(SymbolPair {
+                            first: first.clone(),
+                            second: second.clone(),
+                            correlation,
+                        });
+                    }
+                }
+            }
+        }
+        pairs.sort_by(|a, b| b.correlation.abs().total_cmp(&a.correlation.abs()));
+        pairs
+    }
+}
+
+/// Calculates the log returns of klines from one close price to the next.
+///
+/// # Returns
+///
+/// The return of each kline but the first, by the open time of the kline.
+pub fn calc_returns(klines: &[Kline]) -> BTreeMap<u64, f64> {
+    klines
+        .windows(2)
+        .filter(|pair| pair[0].close > 0.0 && pair[1].close > 0.0)
+        .map(|pair| (pair[1].open_time, (pair[1].close / pair[0].close).ln()))
+        .collect()
+}
+
+/// Calculates the Pearson correlation of two series of the same length.
+///
+/// # Returns
+///
+/// The correlation from `-1` to `1`, or `None` for fewer than two values or a series without
+/// variance.
+pub fn calc_correlation(first: &[f64], second: &[f64]) -> Option<f64> {
+    let len = first.len().min(second.len());
+    if len < 2 {
+        return None;
+    }
+    let (first, second) = (&first[..len], &second[..len]);
+    let mean = |values: &[f64]| values.iter().sum::<f64>() / len as f64;
+    let (first_mean, second_mean) = (mean(first), mean(second));
+
+    let (mut covariance, mut first_variance, mut second_variance) = (0.0, 0.0, 0.0);
+    for (a, b) in first.iter().zip(second) {
+        let (a, b) = (a - first_mean, b - second_mean);
+        covariance += a * b;
+        first_variance += a * a;
+        second_variance += b * b;
+    }
+    if first_variance == 0.0 || second_variance == 0.0 {
+        return None;
+    }
+
+    Some((covariance / (first_variance * second_variance).sqrt()).clamp(-1.0, 1.0))
+}
+
+/// Reads the number of returns correlations are calculated over from `CORRELATION_WINDOW`
+/// through `lookup`.
+pub fn correlation_window(lookup: impl Lookup) -> usize {
+    lookup("CORRELATION_WINDOW")
+        .and_then(|window| window.parse().ok())
+        .filter(|window| *window >= MIN_CORRELATION_SAMPLES)
+        .unwrap_or(DEFAULT_CORRELATION_WINDOW)
+}
+
+/// Groups the tracked symbols by kline interval.
+pub fn symbols_by_interval(
+    streams: impl IntoIterator<Item = (String, Interval)>,
+) -> HashMap<Interval, Vec<String>> {
+    let mut symbols: HashMap<Interval, Vec<String>> = HashMap::new();
+    for (symbol, interval) in streams {
+        let interval_symbols = symbols.entry(interval).or_default();
+        if !interval_symbols.contains(&symbol) {
+            interval_symbols.push(symbol);
+        }
+    }
+    symbols.values_mut().for_each(|symbols| symbols.sort());
+    symbols
+}
+
+// ---
+// Private Methods
+// ---
+
+/// Correlates the latest `window` returns two symbols have in common.
+fn correlate(
+    first: &BTreeMap<u64, f64>,
+    second: &BTreeMap<u64, f64>,
+    window: usize,
+) -> Option<f64> {
+    let (first, second): (Vec<f64>, Vec<f64>) = first
+        .iter()
+        .rev()
+        .filter_map(|(open_time, a)| Some((*a, *second.get(open_time)?)))
+        .take(window)
+        .unzip();
+    if first.len() < MIN_CORRELATION_SAMPLES {
+        return None;
+    }
+    calc_correlation(&first, &second)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::env::vars_lookup;
+
+    /// Tests correlations of symbols moving together, against each other and independently,
+    /// and the pairs they form.
+    #[test]
+    fn test_correlation_matrix() {
+        assert_eq!(
+            calc_correlation(&[1.0, 2.0, 3.0], &[2.0, 4.0, 6.0]),
+            Some(1.0)
+        );
+        assert_eq!(
+            calc_correlation(&[1.0, 2.0, 3.0], &[3.0, 2.0, 1.0]),
+            Some(-1.0)
+        );
+        assert_eq!(calc_correlation(&[1.0, 1.0, 1.0], &[1.0, 2.0, 3.0]), None);
+        assert_eq!(calc_correlation(&[1.0], &[1.0]), None);
+
+        let klines = |symbol: &str, closes: &[f64]| {
+            let klines = closes
+                .iter()
+                .enumerate()
+                .map(|(i, close)| Kline {
+                    symbol: symbol.to_string(),
+                    open_time: i as u64 * 60_000,
+                    close: *close,
+                    ..Default::default()
+                })
+                .collect();
+            (symbol.to_string(), klines)
+        };
+        let zigzag: Vec<f64> = (0..30)
+            .map(|i| 100.0 + if i % 2 == 0 { i as f64 } else { -(i as f64) })
+            .collect();
+        let mirrored: Vec<f64> = zigzag.iter().map(|close| 10_000.0 / close).collect();
+        let scaled: Vec<f64> = zigzag.iter().map(|close| close * 3.0).collect();
+        let short: Vec<f64> = zigzag[..5].to_vec();
+
+        let matrix = CorrelationMatrix::from_klines(
+            Interval::Min1,
+            20,
+            &[
+                klines("BTCUSDT", &zigzag),
+                klines("ETHUSDT", &scaled),
+                klines("SOLUSDT", &mirrored),
+                klines("XRPUSDT", &short),
+            ],
+        );
+        assert_eq!(matrix.get("BTCUSDT", "BTCUSDT"), Some(1.0));
+        assert!(matrix.get("BTCUSDT", "ETHUSDT").unwrap() > 0.99);
+        assert!(matrix.get("ETHUSDT", "SOLUSDT").unwrap() < -0.99);
+        assert_eq!(matrix.get("BTCUSDT", "XRPUSDT"), None);
+        assert_eq!(matrix.get("BTCUSDT", "DOGEUSDT"), None);
+
+        let pairs = matrix.pairs(0.9);
+        assert_eq!(pairs.len(), 3);
+        assert!(pairs.iter().all(|pair| pair.first < pair.second));
+        assert!(matrix.pairs(1.1).is_empty());
+
+        let symbols = symbols_by_interval([
+            ("ETHUSDT".to_string(), Interval::Min1),
+            ("BTCUSDT".to_string(), Interval::Min1),
+            ("BTCUSDT".to_string(), Interval::Min1),
+            ("BTCUSDT".to_string(), Interval::Hour1),
+        ]);
+        assert_eq!(symbols[&Interval::Min1], vec!["BTCUSDT", "ETHUSDT"]);
+        assert_eq!(symbols[&Interval::Hour1], vec!["BTCUSDT"]);
+    }
+
+    /// Tests the window is read from `CORRELATION_WINDOW`, ignoring windows too small.
+    #[test]
+    fn test_correlation_window() {
+        let window =
+            |value: &str| correlation_window(vars_lookup(&[("CORRELATION_WINDOW", value)]));
+
+        assert_eq!(window("50"), 50);
+        assert_eq!(window("5"), DEFAULT_CORRELATION_WINDOW);
+        assert_eq!(window("many"), DEFAULT_CORRELATION_WINDOW);
+        assert_eq!(
+            correlation_window(vars_lookup(&[])),
+            DEFAULT_CORRELATION_WINDOW
+        );
+    }
+}
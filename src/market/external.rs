@@ -0,0 +1,528 @@
+//! # External Data
+//!
+//! Series of non-price data, such as news sentiment scores or the fear and greed index, which
+//! algorithms read alongside klines. Each series is a set of values by timestamp, kept by the
+//! market in an `ExternalDataStore`.
+//!
+//! Series are filled by feeds the market polls, any `ExternalFeed`, and by values posted to the
+//! `/webhook/feeds/{series}` endpoint. Algorithms see the value each series had when the kline
+//! they evaluate closed, `AlgorithmDataPointManager::external`, so back tests never read values
+//! published after the kline.
+//!
+//! Every value is saved to storage, the market reloads the retained values on start and back
+//! tests load the values of their range. Storage backends without external data, such as
+//! MongoDB and InfluxDB, save them to the default `FsStorage` instead.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::{StorageError, StorageResult};
+use crate::storage::{fs::FsStorage, manager::StorageManager};
+use crate::utils::env::{env_lookup, Lookup};
+use crate::utils::time::{generate_ts, SEC_AS_MILI};
+
+/// Values retained per series, the oldest are dropped beyond it.
+pub const EXTERNAL_DATA_RETENTION: usize = 10_000;
+
+/// Interval between polls of feeds configured without one.
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(300);
+
+/// The fear and greed index of alternative.me, updated daily.
+const FEAR_GREED_URL: &str = "https://api.alternative.me/fng/?limit=10";
+
+/// A value of an external series.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ExternalDataPoint {
+    /// The time the value was published in milliseconds.
+    pub timestamp: u64,
+    pub value: f64,
+}
+
+/// Series of external values by name, aligned by time to the klines they are read with.
+#[derive(Debug, Clone)]
+pub struct ExternalDataStore {
+    series: HashMap<String, BTreeMap<u64, f64>>,
+    retention: usize,
+}
+
+impl ExternalDataStore {
+    /// Creates an empty store.
+    ///
+    /// # Arguments
+    ///
+    /// * `retention` - The maximum number of values retained per series.
+    pub fn new(retention: usize) -> Self {
+        Self {
+            series: HashMap::new(),
+            retention: retention.max(1),
+        }
+    }
+
+    /// Adds values to a series, replacing values of the same timestamp and dropping the oldest
+    /// values beyond the retention.
+    ///
+    /// # Returns
+    ///
+    /// The values that weren't in the series yet, e.g. the new values of a feed polled again.
+    pub fn insert(&mut self, series: &str, points: &[ExternalDataPoint]) -> Vec<ExternalDataPoint> {
+        let values = self.series.entry(series.to_string()).or_default();
+        let mut changed = vec![];
+        for point in points {
+            if values.insert(point.timestamp, point.value) != Some(point.value) {
+                changed.push(*point);
+            }
+        }
+        while values.len() > self.retention {
+            values.pop_first();
+        }
+        changed
+    }
+
+    /// Retrieves the value a series had at a time, its latest value published at or before it.
+    pub fn value_at(&self, series: &str, ts: u64) -> Option<f64> {
+        let (_, value) = self.series.get(series)?.range(..=ts).next_back()?;
+        Some(*value)
+    }
+
+    /// Retrieves the value every series had at a time, series without a value yet are left out.
+    pub fn values_at(&self, ts: u64) -> HashMap<String, f64> {
+        self.series
+            .keys()
+            .filter_map(|series| Some((series.clone(), self.value_at(series, ts)?)))
+            .collect()
+    }
+
+    /// Retrieves the values of a series published in a time range, oldest first.
+    pub fn points(
+        &self,
+        series: &str,
+        from_ts: Option<u64>,
+        to_ts: Option<u64>,
+    ) -> Vec<ExternalDataPoint> {
+        let Some(values) = self.series.get(series) else {
+            return vec![];
+        };
+        values
+            .range(from_ts.unwrap_or(0)..=to_ts.unwrap_or(u64::MAX))
+            .map(|(timestamp, value)| ExternalDataPoint {
+                timestamp: *timestamp,
+                value: *value,
+            })
+            .collect()
+    }
+
+    /// Lists the names of the series, sorted.
+    pub fn series_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.series.keys().cloned().collect();
+        names.sort();
+        names
+    }
+}
+
+/// A source of an external series polled by the market.
+#[async_trait]
+pub trait ExternalFeed: Send + Sync {
+    /// The name of the series the feed fills.
+    fn name(&self) -> &str;
+
+    /// The interval between polls.
+    fn poll_interval(&self) -> Duration;
+
+    /// Fetches the latest values of the series.
+    async fn fetch(&self, client: &Client) -> Result<Vec<ExternalDataPoint>, String>;
+}
+
+/// The crypto fear and greed index of alternative.me, from `0`, extreme fear, to `100`,
+/// extreme greed.
+pub struct FearGreedFeed {
+    name: String,
+    poll_interval: Duration,
+}
+
+impl FearGreedFeed {
+    pub fn new(name: &str, poll_interval: Duration) -> Self {
+        Self {
+            name: name.to_string(),
+            poll_interval,
+        }
+    }
+}
+
+#[async_trait]
+impl ExternalFeed for FearGreedFeed {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn poll_interval(&self) -> Duration {
+        self.poll_interval
+    }
+
+    async fn fetch(&self, client: &Client) -> Result<Vec<ExternalDataPoint>, String> {
+        let body = fetch_json(client, FEAR_GREED_URL).await?;
+        let data = body
+            .get("data")
+            .and_then(Value::as_array)
+            .ok_or("Fear and greed index without data")?;
+
+        data.iter()
+            .map(|entry| {
+                let value = entry.get("value").and_then(parse_number);
+                let timestamp = entry.get("timestamp").and_then(parse_number);
+                match (value, timestamp) {
+                    (Some(value), Some(timestamp)) => Ok(ExternalDataPoint {
+                        timestamp: to_millis(timestamp),
+                        value,
+                    }),
+                    _ => Err(format!("Invalid fear and greed entry: {entry}")),
+                }
+            })
+            .collect()
+    }
+}
+
+/// A JSON endpoint returning the latest value of a series, e.g. a news sentiment score.
+pub struct JsonFeed {
+    name: String,
+    url: String,
+    /// JSON pointer to the value, e.g. `/data/score`.
+    value_pointer: String,
+    /// JSON pointer to the timestamp of the value in seconds or milliseconds, the time of the
+    /// poll if not set.
+    timestamp_pointer: Option<String>,
+    poll_interval: Duration,
+}
+
+impl JsonFeed {
+    pub fn new(
+        name: &str,
+        url: &str,
+        value_pointer: &str,
+        timestamp_pointer: Option<String>,
+        poll_interval: Duration,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            url: url.to_string(),
+            value_pointer: value_pointer.to_string(),
+            timestamp_pointer,
+            poll_interval,
+        }
+    }
+}
+
+#[async_trait]
+impl ExternalFeed for JsonFeed {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn poll_interval(&self) -> Duration {
+        self.poll_interval
+    }
+
+    async fn fetch(&self, client: &Client) -> Result<Vec<ExternalDataPoint>, String> {
+        let body = fetch_json(client, &self.url).await?;
+        let point = read_point(
+            &body,
+            &self.value_pointer,
+            self.timestamp_pointer.as_deref(),
+        )?;
+
+        Ok(vec![point])
+    }
+}
+
+/// Reads the feeds listed in `EXTERNAL_FEEDS`, separated by commas.
+///
+/// Each feed is configured by variables prefixed with its name in upper case, dashes replaced
+/// by underscores, e.g. for `news-sentiment`: `EXTERNAL_FEED_NEWS_SENTIMENT_KIND`, either
+/// `fear_greed` or `json`, and optionally `EXTERNAL_FEED_NEWS_SENTIMENT_INTERVAL_SECS`. `json`
+/// feeds also need `..._URL` and `..._VALUE_POINTER`, and may set `..._TIMESTAMP_POINTER`.
+///
+/// # Returns
+///
+/// The feeds in the order they are listed, none if `EXTERNAL_FEEDS` isn't set, or an error if
+/// a feed is incomplete or invalid.
+pub fn external_feeds_from_env() -> Result<Vec<Box<dyn ExternalFeed>>, String> {
+    external_feeds_from_lookup(env_lookup)
+}
+
+/// Reads the kind, source and poll interval of every listed feed through `lookup`, see
+/// `external_feeds_from_env`.
+pub fn external_feeds_from_lookup(
+    lookup: impl Lookup,
+) -> Result<Vec<Box<dyn ExternalFeed>>, String> {
+    let Some(names) = lookup("EXTERNAL_FEEDS") else {
+        return Ok(vec![]);
+    };
+
+    let mut feeds: Vec<Box<dyn ExternalFeed>> = vec![];
+    for name in names
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+    {
+        if !is_series_name(name) {
+            return Err(format!("Invalid external feed name: {name}"));
+        }
+        if feeds.iter().any(|feed| feed.name() == name) {
+            return Err(format!("External feed {name} is listed twice"));
+        }
+
+        let prefix = format!(
+            "EXTERNAL_FEED_{}_",
+            name.to_uppercase()
+                .replace(|c: char| !c.is_ascii_alphanumeric(), "_")
+        );
+        let required = |key: &str| {
+            lookup(&format!("{prefix}{key}"))
+                .filter(|value| !value.is_empty())
+                .ok_or_else(|| format!("External feed {name} is missing {prefix}{key}"))
+        };
+
+        let poll_interval = match lookup(&format!("{prefix}INTERVAL_SECS")) {
+            Some(secs) => match secs.trim().parse::<u64>() {
+                Ok(secs) if secs > 0 => Duration::from_secs(secs),
+                _ => return Err(format!("Invalid {prefix}INTERVAL_SECS: {secs}")),
+            },
+            None => DEFAULT_POLL_INTERVAL,
+        };
+
+        let feed: Box<dyn ExternalFeed> = match required("KIND")?.trim() {
+            "fear_greed" => Box::new(FearGreedFeed::new(name, poll_interval)),
+            "json" => Box::new(JsonFeed::new(
+                name,
+                &required("URL")?,
+                &required("VALUE_POINTER")?,
+                lookup(&format!("{prefix}TIMESTAMP_POINTER")).filter(|ptr| !ptr.is_empty()),
+                poll_interval,
+            )),
+            kind => return Err(format!("External feed {name} has an invalid kind: {kind}")),
+        };
+        feeds.push(feed);
+    }
+
+    Ok(feeds)
+}
+
+/// Reads the secret values posted to the feed webhook must send from `EXTERNAL_FEED_SECRET`
+/// through `lookup`, `None` disables the webhook.
+pub fn feed_webhook_secret(lookup: impl Lookup) -> Option<String> {
+    lookup("EXTERNAL_FEED_SECRET").filter(|secret| !secret.is_empty())
+}
+
+/// Checks a series name is up to 64 letters, digits, dashes and underscores.
+pub fn is_series_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Saves values of a series to storage, or to the default `FsStorage` on backends without
+/// external data.
+pub async fn save_external_data(
+    storage_manager: &Arc<Box<dyn StorageManager>>,
+    series: &str,
+    points: &[ExternalDataPoint],
+) -> StorageResult<()> {
+    if points.is_empty() {
+        return Ok(());
+    }
+    match storage_manager.save_external_data(series, points).await {
+        Err(StorageError::Unsupported(_)) => {
+            FsStorage::default()
+                .save_external_data(series, points)
+                .await
+        }
+        result => result,
+    }
+}
+
+/// Loads the values saved by `save_external_data` published in a time range into a store.
+///
+/// # Arguments
+///
+/// * `storage_manager` - The storage the values were saved to.
+/// * `from_ts` - The start of the range, from the first value if `None`.
+/// * `to_ts` - The end of the range, up to the last value if `None`.
+/// * `retention` - The maximum number of values retained per series, the latest are kept.
+///
+/// # Returns
+///
+/// The store of the values, or an error if they can't be read.
+pub async fn load_external_data(
+    storage_manager: &Arc<Box<dyn StorageManager>>,
+    from_ts: Option<u64>,
+    to_ts: Option<u64>,
+    retention: usize,
+) -> StorageResult<ExternalDataStore> {
+    let series = match storage_manager.list_external_data(from_ts, to_ts).await {
+        Err(StorageError::Unsupported(_)) => {
+            FsStorage::default()
+                .list_external_data(from_ts, to_ts)
+                .await
+        }
+        result => result,
+    }?;
+
+    let mut store = ExternalDataStore::new(retention);
+    for (name, points) in series {
+        store.insert(&name, &points);
+    }
+    Ok(store)
+}
+
+/// Reads a value and its timestamp from a JSON document.
+///
+/// # Arguments
+///
+/// * `body` - The document.
+/// * `value_pointer` - JSON pointer to the value, a number or a numeric string.
+/// * `timestamp_pointer` - JSON pointer to the timestamp in seconds or milliseconds, the current
+///   time if `None`.
+pub fn read_point(
+    body: &Value,
+    value_pointer: &str,
+    timestamp_pointer: Option<&str>,
+) -> Result<ExternalDataPoint, String> {
+    let value = body
+        .pointer(value_pointer)
+        .and_then(parse_number)
+        .ok_or_else(|| format!("No numeric value at {value_pointer}"))?;
+
+    let timestamp = match timestamp_pointer {
+        Some(pointer) => body
+            .pointer(pointer)
+            .and_then(parse_number)
+            .map(to_millis)
+            .ok_or_else(|| format!("No timestamp at {pointer}"))?,
+        None => generate_ts(),
+    };
+
+    Ok(ExternalDataPoint { timestamp, value })
+}
+
+// ---
+// Private Methods
+// ---
+
+async fn fetch_json(client: &Client, url: &str) -> Result<Value, String> {
+    client
+        .get(url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("Request to {url} failed, e: {e}"))?
+        .json::<Value>()
+        .await
+        .map_err(|e| format!("Invalid JSON from {url}, e: {e}"))
+}
+
+/// Reads a number given as a JSON number or a numeric string, as APIs often quote them.
+fn parse_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(number) => number.trim().parse().ok(),
+        _ => None,
+    }
+    .filter(|number| number.is_finite())
+}
+
+/// Converts a timestamp in seconds or milliseconds to milliseconds, timestamps below `1e12` are
+/// read as seconds.
+fn to_millis(timestamp: f64) -> u64 {
+    let timestamp = timestamp.max(0.0) as u64;
+    if timestamp < 1_000_000_000_000 {
+        timestamp * SEC_AS_MILI
+    } else {
+        timestamp
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::env::vars_lookup;
+    use serde_json::json;
+
+    /// Tests values are read as of a time without looking ahead, and the oldest values are
+    /// dropped beyond the retention.
+    #[test]
+    fn test_external_data_store() {
+        let point = |timestamp: u64, value: f64| ExternalDataPoint { timestamp, value };
+        let mut store = ExternalDataStore::new(3);
+        store.insert(
+            "fear_greed",
+            &[point(1_000, 20.0), point(2_000, 40.0), point(3_000, 60.0)],
+        );
+        let changed = store.insert("sentiment", &[point(2_500, -0.5)]);
+        assert_eq!(changed, vec![point(2_500, -0.5)]);
+        // a feed polled again only adds its new values
+        let changed = store.insert("sentiment", &[point(2_500, -0.5), point(2_600, 0.5)]);
+        assert_eq!(changed, vec![point(2_600, 0.5)]);
+
+        assert_eq!(store.value_at("fear_greed", 999), None);
+        assert_eq!(store.value_at("fear_greed", 1_000), Some(20.0));
+        assert_eq!(store.value_at("fear_greed", 2_999), Some(40.0));
+        assert_eq!(store.value_at("unknown", 2_999), None);
+
+        let values = store.values_at(2_000);
+        assert_eq!(values.get("fear_greed"), Some(&40.0));
+        assert_eq!(values.get("sentiment"), None);
+
+        store.insert("fear_greed", &[point(4_000, 80.0), point(3_000, 65.0)]);
+        assert_eq!(store.value_at("fear_greed", 1_500), None);
+        assert_eq!(
+            store.points("fear_greed", Some(2_500), None),
+            vec![point(3_000, 65.0), point(4_000, 80.0)]
+        );
+        assert_eq!(store.series_names(), vec!["fear_greed", "sentiment"]);
+    }
+
+    /// Tests feeds are configured from the environment and values are read from JSON.
+    #[test]
+    fn test_external_feeds() {
+        let feeds = external_feeds_from_lookup(vars_lookup(&[
+            ("EXTERNAL_FEEDS", "fear_greed, news-sentiment"),
+            ("EXTERNAL_FEED_FEAR_GREED_KIND", "fear_greed"),
+            ("EXTERNAL_FEED_NEWS_SENTIMENT_KIND", "json"),
+            (
+                "EXTERNAL_FEED_NEWS_SENTIMENT_URL",
+                "https://example.com/sentiment",
+            ),
+            ("EXTERNAL_FEED_NEWS_SENTIMENT_VALUE_POINTER", "/data/score"),
+            ("EXTERNAL_FEED_NEWS_SENTIMENT_INTERVAL_SECS", "60"),
+        ]))
+        .unwrap();
+        assert_eq!(feeds.len(), 2);
+        assert_eq!(feeds[0].poll_interval(), DEFAULT_POLL_INTERVAL);
+        assert_eq!(feeds[1].name(), "news-sentiment");
+        assert_eq!(feeds[1].poll_interval(), Duration::from_secs(60));
+
+        let incomplete = vars_lookup(&[
+            ("EXTERNAL_FEEDS", "news"),
+            ("EXTERNAL_FEED_NEWS_KIND", "json"),
+        ]);
+        assert!(external_feeds_from_lookup(incomplete).is_err());
+        assert!(!is_series_name("news sentiment"));
+
+        let body = json!({ "data": { "score": "0.25", "time": 1_700_000_000 } });
+        assert_eq!(
+            read_point(&body, "/data/score", Some("/data/time")),
+            Ok(ExternalDataPoint {
+                timestamp: 1_700_000_000_000,
+                value: 0.25
+            })
+        );
+        assert!(read_point(&body, "/data/missing", None).is_err());
+    }
+}
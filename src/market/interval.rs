@@ -0,0 +1,224 @@
+//! # Kline Intervals
+//!
+//! The intervals k-lines are traded and stored at. Intervals are named like Binance and BingX
+//! name them, e.g. `1m`, `4h` or `1M`, which is also how they are serialized, written into kline
+//! keys and passed to the exchanges, so stored data and API requests read the same as before.
+//! Parsing also takes the `5min` style of older BingX configs.
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::utils::time::{floor_mili_ts, floor_month_ts, DAY_AS_MILI, HOUR_AS_MILI, MIN_AS_MILI};
+
+/// Weekly k-lines open on Mondays, the first of which was the 4th day after the epoch.
+const FIRST_MONDAY_AS_MILI: u64 = 4 * DAY_AS_MILI;
+
+/// The interval between the open times of two k-lines.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Interval {
+    #[default]
+    Min1,
+    Min3,
+    Min5,
+    Min15,
+    Min30,
+    Hour1,
+    Hour2,
+    Hour4,
+    Hour6,
+    Hour8,
+    Hour12,
+    Day1,
+    Day3,
+    Week1,
+    /// A calendar month, whose k-lines open on the first day of the month.
+    Month1,
+}
+
+impl Interval {
+    pub const ALL: [Interval; 15] = [
+        Self::Min1,
+        Self::Min3,
+        Self::Min5,
+        Self::Min15,
+        Self::Min30,
+        Self::Hour1,
+        Self::Hour2,
+        Self::Hour4,
+        Self::Hour6,
+        Self::Hour8,
+        Self::Hour12,
+        Self::Day1,
+        Self::Day3,
+        Self::Week1,
+        Self::Month1,
+    ];
+
+    /// The name of the interval, e.g. `15m`, as used by the exchanges and in kline keys.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Min1 => "1m",
+            Self::Min3 => "3m",
+            Self::Min5 => "5m",
+            Self::Min15 => "15m",
+            Self::Min30 => "30m",
+            Self::Hour1 => "1h",
+            Self::Hour2 => "2h",
+            Self::Hour4 => "4h",
+            Self::Hour6 => "6h",
+            Self::Hour8 => "8h",
+            Self::Hour12 => "12h",
+            Self::Day1 => "1d",
+            Self::Day3 => "3d",
+            Self::Week1 => "1w",
+            Self::Month1 => "1M",
+        }
+    }
+
+    /// The length of the interval in milliseconds, a month counts 30 days.
+    pub fn to_millis(self) -> u64 {
+        match self {
+            Self::Min1 => MIN_AS_MILI,
+            Self::Min3 => 3 * MIN_AS_MILI,
+            Self::Min5 => 5 * MIN_AS_MILI,
+            Self::Min15 => 15 * MIN_AS_MILI,
+            Self::Min30 => 30 * MIN_AS_MILI,
+            Self::Hour1 => HOUR_AS_MILI,
+            Self::Hour2 => 2 * HOUR_AS_MILI,
+            Self::Hour4 => 4 * HOUR_AS_MILI,
+            Self::Hour6 => 6 * HOUR_AS_MILI,
+            Self::Hour8 => 8 * HOUR_AS_MILI,
+            Self::Hour12 => 12 * HOUR_AS_MILI,
+            Self::Day1 => DAY_AS_MILI,
+            Self::Day3 => 3 * DAY_AS_MILI,
+            Self::Week1 => 7 * DAY_AS_MILI,
+            Self::Month1 => 30 * DAY_AS_MILI,
+        }
+    }
+
+    pub fn duration(self) -> Duration {
+        Duration::from_millis(self.to_millis())
+    }
+
+    /// The interval lasting a number of minutes, if there is one.
+    pub fn from_minutes(minutes: u64) -> Option<Self> {
+        Self::ALL.into_iter().find(|interval| {
+            *interval != Self::Month1 && interval.to_millis() == minutes * MIN_AS_MILI
+        })
+    }
+
+    /// Calculates the open time of the k-line closing at a time.
+    ///
+    /// # Arguments
+    ///
+    /// * `close_time` - The close time of the k-line in milliseconds, the last millisecond of
+    ///   the interval as the exchanges report it.
+    pub fn open_time(self, close_time: u64) -> u64 {
+        match self {
+            Self::Month1 => floor_month_ts(close_time),
+            interval => (close_time + 1).saturating_sub(interval.to_millis()),
+        }
+    }
+
+    /// Floors a timestamp in milliseconds to the open time of the k-line it falls in.
+    pub fn floor(self, timestamp: u64) -> u64 {
+        match self {
+            Self::Month1 => floor_month_ts(timestamp),
+            Self::Week1 => {
+                let since_monday = timestamp.saturating_sub(FIRST_MONDAY_AS_MILI);
+                floor_mili_ts(since_monday, self.to_millis()) + FIRST_MONDAY_AS_MILI
+            }
+            interval => floor_mili_ts(timestamp, interval.to_millis()),
+        }
+    }
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Interval {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let unit_start = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let (count, unit) = s.split_at(unit_start);
+        let unknown = || format!("Unknown interval: {s}");
+        let count: u64 = count.parse().map_err(|_| unknown())?;
+
+        let minutes = match unit {
+            // `M` is a month, never a minute
+            "M" if count == 1 => return Ok(Self::Month1),
+            "m" | "min" => count,
+            "h" | "hour" => count * 60,
+            "d" | "day" => count * 60 * 24,
+            "w" | "week" => count * 60 * 24 * 7,
+            _ => return Err(unknown()),
+        };
+        Self::from_minutes(minutes).ok_or_else(unknown)
+    }
+}
+
+impl Serialize for Interval {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Interval {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let interval = String::deserialize(deserializer)?;
+        interval.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests intervals are parsed in every supported style and read and written by name.
+    #[test]
+    fn test_interval() {
+        for interval in Interval::ALL {
+            assert_eq!(interval.as_str().parse::<Interval>(), Ok(interval));
+        }
+        assert_eq!("5min".parse::<Interval>(), Ok(Interval::Min5));
+        assert_eq!("60m".parse::<Interval>(), Ok(Interval::Hour1));
+        assert_eq!(" 1d".parse::<Interval>(), Ok(Interval::Day1));
+        assert!("7m".parse::<Interval>().is_err());
+        assert!("2M".parse::<Interval>().is_err());
+        assert!("m".parse::<Interval>().is_err());
+        assert!("".parse::<Interval>().is_err());
+
+        assert_eq!(Interval::Min15.duration(), Duration::from_secs(900));
+        assert_eq!(Interval::from_minutes(240), Some(Interval::Hour4));
+        assert_eq!(
+            Interval::Min1.open_time(1_700_000_059_999),
+            1_700_000_000_000
+        );
+        assert_eq!(
+            Interval::Month1.open_time(1_706_745_599_999),
+            1_704_067_200_000
+        );
+
+        // Wednesday 2024-01-03T10:20:00Z
+        let ts = 1_704_277_200_000;
+        assert_eq!(Interval::Hour4.floor(ts), 1_704_268_800_000);
+        assert_eq!(Interval::Week1.floor(ts), 1_704_067_200_000);
+        assert_eq!(Interval::Month1.floor(ts), 1_704_067_200_000);
+
+        let json = serde_json::to_string(&Interval::Month1).unwrap();
+        assert_eq!(json, "\"1M\"");
+        assert_eq!(
+            serde_json::from_str::<Interval>("\"1h\"").unwrap(),
+            Interval::Hour1
+        );
+        assert!(serde_json::from_str::<Interval>("\"1y\"").is_err());
+    }
+}
@@ -2,10 +2,6 @@
 use std::collections::{BTreeMap, HashMap};
 
 use log::info;
-use mongodb::{
-    bson::{self, doc, to_document},
-    IndexModel,
-};
 use serde::{ser::SerializeMap, Deserialize, Serialize, Serializer};
 use serde_json::Value;
 
@@ -13,11 +9,8 @@ use uuid::Uuid;
 
 use crate::{
     exchange::types::ApiResult,
-    market::market::MarketDataSymbol,
-    utils::{
-        number::parse_f64_from_lookup,
-        time::{calculate_kline_open_time, generate_ts, timestamp_to_string},
-    },
+    market::{interval::Interval, market::MarketDataSymbol, symbol::Symbol},
+    utils::{number::parse_f64_from_lookup, time::generate_ts},
 };
 
 /// Represents metadata for a series of klines, including the symbol, interval, length, and last update timestamp.
@@ -28,16 +21,16 @@ use crate::{
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct KlineMeta {
     pub symbol: String,
-    pub interval: String,
+    pub interval: Interval,
     pub len: usize,
     pub last_update: u64,
 }
 
 impl KlineMeta {
-    pub fn new(symbol: &str, interval: &str) -> Self {
+    pub fn new(symbol: &str, interval: Interval) -> Self {
         Self {
             symbol: symbol.to_string(),
-            interval: interval.to_string(),
+            interval,
             len: 0,
             last_update: generate_ts(),
         }
@@ -60,7 +53,7 @@ impl KlineData {
     ///
     /// This method initializes a `KlineData` object with empty kline data and associated metadata.
 
-    pub fn new(symbol: &str, interval: &str) -> Self {
+    pub fn new(symbol: &str, interval: Interval) -> Self {
         Self {
             meta: KlineMeta::new(symbol, interval),
             klines: BTreeMap::new(),
@@ -81,6 +74,17 @@ impl KlineData {
         self.klines.values().cloned().collect()
     }
 
+    /// Returns the klines opening within a range, both ends inclusive, in open time order.
+    pub fn klines_in_range(&self, from_ts: u64, to_ts: u64) -> Vec<Kline> {
+        if from_ts > to_ts {
+            return vec![];
+        }
+        self.klines
+            .range(from_ts..=to_ts)
+            .map(|(_, kline)| kline.clone())
+            .collect()
+    }
+
     pub fn drain_klines(&mut self, before_ts: u64) -> Vec<Kline> {
         // info!(
         //     "Removing all klines before {} ...",
@@ -106,7 +110,7 @@ impl KlineData {
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Kline {
     pub symbol: String,
-    pub interval: String,
+    pub interval: Interval,
     pub open: f64,
     pub high: f64,
     pub low: f64,
@@ -119,7 +123,7 @@ pub struct Kline {
 impl Default for Kline {
     fn default() -> Self {
         Self {
-            interval: "UNkown".to_string(),
+            interval: Interval::default(),
             symbol: "Unknown".to_string(),
             open_time: 42,
             open: 42.2,
@@ -154,7 +158,8 @@ impl Kline {
             .ok_or_else(|| {
                 // Create an error message or construct an error type
                 "Unable to 'as_str' from 'i' key in data kline lookup".to_string()
-            })?;
+            })?
+            .parse::<Interval>()?;
 
         let symbol = lookup
             .get("s")
@@ -201,7 +206,7 @@ impl Kline {
         let volume = parse_f64_from_lookup("v", &_kline)?;
 
         Ok(Self {
-            interval: interval.to_string(),
+            interval,
             symbol: symbol.to_string(),
             open_time,
             open,
@@ -220,7 +225,7 @@ impl Kline {
     pub fn from_bingx_lookup(
         data: HashMap<String, Value>,
         symbol: &str,
-        interval: &str,
+        interval: Interval,
     ) -> ApiResult<Self> {
         // {
         //     "open": "float64",
@@ -243,7 +248,7 @@ impl Kline {
                 "Unable to parse as u64".to_string()
             })?;
 
-        let open_time = calculate_kline_open_time(close_time, interval);
+        let open_time = interval.open_time(close_time);
 
         let open = parse_f64_from_lookup("open", &data)?;
         let close = parse_f64_from_lookup("close", &data)?;
@@ -254,7 +259,7 @@ impl Kline {
         let volume = parse_f64_from_lookup("volume", &data)?;
 
         Ok(Self {
-            interval: interval.to_string(),
+            interval,
             symbol: symbol.to_string(),
             open_time,
             open,
@@ -289,13 +294,13 @@ impl Kline {
 
         let data_type = data.get("dataType").unwrap().as_str().unwrap();
         // BTC-USDT@kline_1m
-        let split = data_type.split('_');
-        let interval = split.last().unwrap().to_string();
+        let mut split = data_type.split('_');
+        let interval: Interval = split.next_back().unwrap().parse()?;
         let symbol = lookup.get("s").unwrap().as_str().unwrap();
 
         let close_time = data.get("T").unwrap().as_u64().unwrap();
 
-        let open_time = calculate_kline_open_time(close_time, &interval);
+        let open_time = interval.open_time(close_time);
 
         let open = parse_f64_from_lookup("o", &data)?;
         let close = parse_f64_from_lookup("c", &data)?;
@@ -307,7 +312,7 @@ impl Kline {
 
         Ok(Self {
             interval,
-            symbol: symbol.to_string(),
+            symbol: Symbol::normalize(symbol),
             open_time,
             open,
             high,
@@ -348,3 +353,20 @@ pub struct BinanceKline {
     pub taker_buy_quote_volume: f64,
     pub ignore: u8,
 }
+
+/// Builds a BTCUSDT k-line for tests, with a fixed open, high, low and volume and the close
+/// time at the end of the interval.
+#[cfg(test)]
+pub fn test_kline(interval: Interval, open_time: u64, close: f64) -> Kline {
+    Kline {
+        symbol: "BTCUSDT".to_string(),
+        interval,
+        open: 1.0,
+        high: 2.0,
+        low: 0.5,
+        close,
+        volume: 10.0,
+        open_time,
+        close_time: open_time + interval.to_millis() - 1,
+    }
+}
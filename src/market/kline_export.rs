@@ -0,0 +1,135 @@
+use std::error::Error;
+
+use serde::Deserialize;
+
+use super::kline::Kline;
+
+/// File format k-lines are exported in.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum KlineExportFormat {
+    /// CSV with a header row, one k-line per row.
+    #[default]
+    Csv,
+    /// A JSON array of k-lines.
+    Json,
+}
+
+impl KlineExportFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            KlineExportFormat::Csv => "text/csv",
+            KlineExportFormat::Json => "application/json",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            KlineExportFormat::Csv => "csv",
+            KlineExportFormat::Json => "json",
+        }
+    }
+}
+
+/// Encodes k-lines batch by batch, so an export can be streamed without holding all k-lines
+/// in memory. The batches concatenated with `finish` form a single document.
+#[derive(Debug)]
+pub struct KlineEncoder {
+    format: KlineExportFormat,
+    encoded: usize,
+}
+
+impl KlineEncoder {
+    pub fn new(format: KlineExportFormat) -> Self {
+        Self { format, encoded: 0 }
+    }
+
+    /// Encodes a batch of k-lines following the batches encoded before.
+    pub fn encode(&mut self, klines: &[Kline]) -> Result<Vec<u8>, Box<dyn Error>> {
+        let bytes = match self.format {
+            KlineExportFormat::Csv => {
+                // the header row goes before the first k-line only
+                let mut writer = csv::WriterBuilder::new()
+                    .has_headers(self.encoded == 0)
+                    .from_writer(vec![]);
+                for kline in klines {
+                    writer.serialize(kline)?;
+                }
+                writer.into_inner().map_err(|e| e.to_string())?
+            }
+            KlineExportFormat::Json => {
+                let mut bytes = vec![];
+                for (i, kline) in klines.iter().enumerate() {
+                    bytes.push(if self.encoded + i == 0 { b'[' } else { b',' });
+                    serde_json::to_writer(&mut bytes, kline)?;
+                }
+                bytes
+            }
+        };
+        self.encoded += klines.len();
+
+        Ok(bytes)
+    }
+
+    /// Ends the document after the last batch.
+    pub fn finish(&self) -> Vec<u8> {
+        match (self.format, self.encoded) {
+            (KlineExportFormat::Csv, _) => vec![],
+            (KlineExportFormat::Json, 0) => b"[]".to_vec(),
+            (KlineExportFormat::Json, _) => b"]".to_vec(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::interval::Interval;
+    use crate::market::kline::test_kline;
+
+    fn export(format: KlineExportFormat, batches: &[Vec<Kline>]) -> String {
+        let mut encoder = KlineEncoder::new(format);
+        let mut bytes = vec![];
+        for batch in batches {
+            bytes.extend(encoder.encode(batch).unwrap());
+        }
+        bytes.extend(encoder.finish());
+        String::from_utf8(bytes).unwrap()
+    }
+
+    /// Tests batches encode to a single CSV or JSON document.
+    #[test]
+    fn test_kline_encoder() {
+        let batches = vec![
+            vec![
+                test_kline(Interval::Min1, 0, 1.5),
+                test_kline(Interval::Min1, 60_000, 1.5),
+            ],
+            vec![],
+            vec![test_kline(Interval::Min1, 120_000, 1.5)],
+        ];
+
+        let csv = export(KlineExportFormat::Csv, &batches);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(
+            lines[0],
+            "symbol,interval,open,high,low,close,volume,open_time,close_time"
+        );
+        assert_eq!(lines[3], "BTCUSDT,1m,1.0,2.0,0.5,1.5,10.0,120000,179999");
+
+        let json = export(KlineExportFormat::Json, &batches);
+        let klines: Vec<Kline> = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            klines,
+            vec![
+                test_kline(Interval::Min1, 0, 1.5),
+                test_kline(Interval::Min1, 60_000, 1.5),
+                test_kline(Interval::Min1, 120_000, 1.5)
+            ]
+        );
+
+        assert_eq!(export(KlineExportFormat::Json, &[]), "[]");
+        assert_eq!(export(KlineExportFormat::Csv, &[vec![]]), "");
+    }
+}
@@ -0,0 +1,130 @@
+use std::{collections::VecDeque, sync::Arc};
+
+use crate::{
+    market::{interval::Interval, kline::Kline},
+    storage::manager::StorageManager,
+    utils::time::{add_month_to_timestamp, floor_month_ts},
+};
+
+/// Iterates the stored k-lines of a symbol and interval over a time range in open time order,
+/// loading a single month of k-lines at a time.
+///
+/// Stored k-lines are followed by the in-memory k-lines not yet backed up. K-lines opening at or
+/// before an already returned one are skipped, so months overlapping the in-memory k-lines
+/// don't produce duplicates.
+pub struct KlineStream {
+    storage_manager: Arc<Box<dyn StorageManager>>,
+    symbol: String,
+    interval: Interval,
+    from_ts: u64,
+    cursor: u64,
+    to_ts: u64,
+    buffer: VecDeque<Kline>,
+    in_mem_klines: Option<Vec<Kline>>,
+    last_open_time: Option<u64>,
+}
+
+impl KlineStream {
+    /// Creates a new `KlineStream`.
+    ///
+    /// # Arguments
+    ///
+    /// * `storage_manager` - The storage the k-lines are read from.
+    /// * `symbol` - The symbol of the k-lines.
+    /// * `interval` - The interval of the k-lines.
+    /// * `from_ts` - The earliest open time, inclusive.
+    /// * `to_ts` - The latest open time, inclusive.
+    /// * `in_mem_klines` - K-lines not yet backed up to storage, returned after stored ones.
+    ///
+    /// # Returns
+    ///
+    /// Returns a new instance of `KlineStream`.
+    pub fn new(
+        storage_manager: Arc<Box<dyn StorageManager>>,
+        symbol: &str,
+        interval: Interval,
+        from_ts: u64,
+        to_ts: u64,
+        in_mem_klines: Vec<Kline>,
+    ) -> Self {
+        let mut in_mem_klines: Vec<Kline> = in_mem_klines
+            .into_iter()
+            .filter(|kline| kline.open_time >= from_ts && kline.open_time <= to_ts)
+            .collect();
+        in_mem_klines.sort_by_key(|kline| kline.open_time);
+
+        Self {
+            storage_manager,
+            symbol: symbol.to_string(),
+            interval,
+            from_ts,
+            cursor: from_ts,
+            to_ts,
+            buffer: VecDeque::new(),
+            in_mem_klines: Some(in_mem_klines),
+            last_open_time: None,
+        }
+    }
+
+    /// Estimates the number of k-lines in the range from its length, assuming no gaps.
+    pub fn estimated_len(&self) -> usize {
+        (self.to_ts.saturating_sub(self.from_ts) / self.interval.to_millis() + 1) as usize
+    }
+
+    /// Returns the next k-line, `None` once the range is exhausted.
+    pub async fn next(&mut self) -> Option<Kline> {
+        self.fill_buffer().await;
+        let kline = self.buffer.pop_front()?;
+        self.last_open_time = Some(kline.open_time);
+        Some(kline)
+    }
+
+    /// Returns the next k-line without consuming it.
+    pub async fn peek(&mut self) -> Option<&Kline> {
+        self.fill_buffer().await;
+        self.buffer.front()
+    }
+
+    // ---
+    // Private Methods
+    // ---
+
+    /// Loads months until the buffer holds a k-line or the range is exhausted.
+    async fn fill_buffer(&mut self) {
+        while self.buffer.is_empty() {
+            let mut klines = match self.next_chunk().await {
+                Some(klines) => klines,
+                None => return,
+            };
+            klines.sort_by_key(|kline| kline.open_time);
+            klines.dedup_by_key(|kline| kline.open_time);
+
+            let last_open_time = self.last_open_time;
+            self.buffer.extend(klines.into_iter().filter(|kline| {
+                last_open_time.is_none_or(|last_open_time| kline.open_time > last_open_time)
+            }));
+        }
+    }
+
+    /// Loads the k-lines of the month at the cursor and advances it, then the in-memory
+    /// k-lines once all months are loaded.
+    async fn next_chunk(&mut self) -> Option<Vec<Kline>> {
+        if self.cursor > self.to_ts {
+            return self.in_mem_klines.take();
+        }
+
+        let from_ts = self.cursor;
+        let next_month = add_month_to_timestamp(floor_month_ts(from_ts) as i64) as u64;
+        let to_ts = (next_month - 1).min(self.to_ts);
+        self.cursor = next_month;
+
+        let klines = self
+            .storage_manager
+            .get_klines(&self.symbol, self.interval, Some(from_ts), Some(to_ts))
+            .await
+            .into_iter()
+            .filter(|kline| kline.open_time >= from_ts && kline.open_time <= to_ts)
+            .collect();
+        Some(klines)
+    }
+}
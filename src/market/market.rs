@@ -1,47 +1,86 @@
 use futures::StreamExt;
 
-use log::info;
+use log::{info, warn};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use std::collections::BTreeMap;
 use std::time::{Duration, SystemTime};
 use std::{collections::HashMap, sync::Arc};
+use tokio::sync::broadcast::{self, Receiver, Sender};
 
 // use tokio::time::{self, Duration};
 
+use crate::error::{StorageError, StorageResult};
+use crate::events::{BotEvent, EventPublisher};
 use crate::exchange::api::ExchangeInfo;
 use crate::exchange::stream::build_stream_id;
 use crate::exchange::types::{ApiResult, StreamType};
+use crate::health;
+use crate::utils::env::env_lookup;
 use crate::utils::kline::{build_kline_key, build_ticker_key};
-use crate::utils::time::{floor_mili_ts, interval_to_millis, MIN_AS_MILI, SEC_AS_MILI};
+use crate::utils::time::{MIN_AS_MILI, SEC_AS_MILI};
 use crate::utils::trade::build_market_trade_key;
 use crate::{
     exchange::{
         api::ExchangeApi,
-        stream::{StreamManager, StreamMeta},
+        stream::{StreamMeta, StreamStatus},
     },
     market::{
+        correlation::{correlation_window, symbols_by_interval, CorrelationMatrix},
+        external::{
+            external_feeds_from_env, load_external_data, save_external_data, ExternalDataPoint,
+            ExternalDataStore, ExternalFeed, EXTERNAL_DATA_RETENTION,
+        },
+        interval::Interval,
         kline::{Kline, KlineData, KlineMeta},
-        messages::MarketMessage,
+        kline_stream::KlineStream,
+        messages::{MarketMessage, MarketUpdate},
+        regime::{detect_regime, MarketRegime, REGIME_PERIOD},
         ticker::{Ticker, TickerData, TickerMeta},
         types::ArcReceiver,
     },
-    storage::manager::StorageManager,
+    storage::{kline_query::KlineQuery, manager::StorageManager},
     utils::time::generate_ts,
 };
 
 use super::trade::{Trade, TradeData, TradeDataMeta};
-use super::types::ArcMutex;
+use super::types::{ArcMutex, ArcRwLock};
 use super::volume::MarketTradeVolume;
 
+/// Number of live updates buffered per subscriber, subscribers lagging further behind miss the
+/// oldest updates.
+const MARKET_UPDATE_CAPACITY: usize = 1024;
+
+/// Interval between checks reopening needed streams which aren't active.
+const STREAM_MONITOR_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Interval between updates of the correlations of the tracked symbols.
+const CORRELATION_UPDATE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Interval between updates of the market regimes of the tracked symbols.
+const REGIME_UPDATE_INTERVAL: Duration = Duration::from_secs(60);
+
 /// Represents the main market data structure for a trading application, managing market data streams, and integrating with exchange APIs.
 
 pub struct Market {
     market_receiver: ArcReceiver<MarketMessage>,
-    data: ArcMutex<MarketData>,
+    data: ArcRwLock<MarketData>,
     exchange_api: Arc<Box<dyn ExchangeApi>>,
     needed_streams: ArcMutex<Vec<StreamMeta>>,
+    updates: Sender<MarketUpdate>,
+    /// Publishes opened and closed streams and the errors streams report.
+    events: EventPublisher,
+    /// The correlations of the symbols of the needed kline streams, by kline interval.
+    correlations: ArcRwLock<HashMap<Interval, CorrelationMatrix>>,
+    /// The number of returns correlations are calculated over.
+    correlation_window: usize,
+    /// The market regimes of the symbols of the needed kline streams, by symbol and interval.
+    regimes: ArcRwLock<HashMap<(String, Interval), MarketRegime>>,
+    /// Non-price series read by algorithms alongside klines, e.g. news sentiment.
+    external_data: ArcRwLock<ExternalDataStore>,
+    /// The storage external series are saved to.
+    storage_manager: Arc<Box<dyn StorageManager>>,
 }
 
 impl Market {
@@ -58,6 +97,7 @@ impl Market {
     ///   interacting with the exchange.
     /// - `storage_manager`: Manages the persistence of market data, ensuring data is saved and can be
     ///   retrieved for analysis.
+    /// - `events`: The event bus stream events are published to.
     /// - `init_workers`: Indicates whether to initialize background tasks for processing market data
     ///   and managing streams upon creation of the market structure.
     ///
@@ -69,13 +109,21 @@ impl Market {
         market_receiver: ArcReceiver<MarketMessage>,
         exchange_api: Arc<Box<dyn ExchangeApi>>,
         storage_manager: Arc<Box<dyn StorageManager>>,
+        events: EventPublisher,
         init_workers: bool,
     ) -> Self {
         let mut _self = Self {
-            data: ArcMutex::new(MarketData::new(storage_manager)),
+            data: ArcRwLock::new(MarketData::new(storage_manager.clone())),
             market_receiver,
             exchange_api,
             needed_streams: ArcMutex::new(vec![]),
+            updates: broadcast::channel(MARKET_UPDATE_CAPACITY).0,
+            events,
+            correlations: ArcRwLock::new(HashMap::new()),
+            correlation_window: correlation_window(env_lookup),
+            regimes: ArcRwLock::new(HashMap::new()),
+            external_data: ArcRwLock::new(ExternalDataStore::new(EXTERNAL_DATA_RETENTION)),
+            storage_manager,
         };
 
         if init_workers {
@@ -122,16 +170,17 @@ impl Market {
     ///
     /// An `Option<Kline>` containing the most recent kline data if available; otherwise, `None`.
 
-    pub async fn last_kline(&self, symbol: &str, interval: &str) -> Option<Kline> {
-        let last_open_time = generate_ts() - interval_to_millis(interval);
+    pub async fn last_kline(&self, symbol: &str, interval: Interval) -> Option<Kline> {
+        let last_open_time = generate_ts() - interval.to_millis();
 
-        let kline = match self
+        // the lock is released before falling back to the exchange, which may take a while
+        let kline_data = self
             .data
-            .lock()
+            .read()
             .await
             .kline_data(symbol, interval, Some(last_open_time), None, None)
-            .await
-        {
+            .await;
+        let kline = match kline_data {
             Some(kline_data) => {
                 // info!("Getting Kline from kline_data on on Market");
                 kline_data.klines().last().cloned()
@@ -165,7 +214,8 @@ impl Market {
     pub async fn last_ticker(&self, symbol: &str) -> Option<Ticker> {
         // must be within the last second
         let last_sec = generate_ts() - SEC_AS_MILI;
-        let ticker = match self.data.lock().await.ticker_data(symbol, last_sec) {
+        let ticker_data = self.data.read().await.ticker_data(symbol, last_sec);
+        let ticker = match ticker_data {
             Some(ticker_data) => {
                 // info!("Getting Ticker from ticker_data on on Market");
 
@@ -190,10 +240,10 @@ impl Market {
     /// # Parameters
     ///
     /// - `symbol`: A `&str` representing the trading pair or market symbol for which Kline data is requested.
-    /// - `interval`: A `&str` indicating the time interval between each Kline.
+    /// - `interval`: An `Interval` indicating the time interval between each Kline.
     /// - `from_ts`: An `Option<u64>` specifying the start timestamp for filtering Kline data. If `None`, no start filter is applied.
     /// - `to_ts`: An `Option<u64>` specifying the end timestamp for filtering Kline data. If `None`, no end filter is applied.
-    /// - `limit`: An `Option<usize>` limiting the number of Kline data points returned. If `None`, all matching Klines are returned.
+    /// - `limit`: An `Option<usize>` limiting the number of Kline data points returned, the most recent ones without `from_ts`. If `None`, all matching Klines are returned.
     ///
     /// # Returns
     ///
@@ -202,18 +252,200 @@ impl Market {
     pub async fn kline_data_range(
         &self,
         symbol: &str,
-        interval: &str,
+        interval: Interval,
         from_ts: Option<u64>,
         to_ts: Option<u64>,
         limit: Option<usize>,
     ) -> Option<KlineData> {
         self.data
-            .lock()
+            .read()
             .await
             .kline_data(symbol, interval, from_ts, to_ts, limit)
             .await
     }
 
+    /// Retrieves a page of klines, see `KlineQuery`.
+    ///
+    /// # Parameters
+    ///
+    /// - `symbol`: The trading symbol of the klines.
+    /// - `interval`: The interval of the klines.
+    /// - `query`: The range, limit, offset and order of the page.
+    ///
+    /// # Returns
+    ///
+    /// The klines of the page in the order of the query.
+    pub async fn kline_page(
+        &self,
+        symbol: &str,
+        interval: Interval,
+        query: &KlineQuery,
+    ) -> Vec<Kline> {
+        self.data
+            .read()
+            .await
+            .kline_page(symbol, interval, query)
+            .await
+    }
+
+    /// Creates a stream over the klines of a range, loading stored klines a month at a time
+    /// instead of the whole range at once, see `KlineStream`.
+    ///
+    /// # Parameters
+    ///
+    /// - `symbol`: The trading symbol of the klines.
+    /// - `interval`: The interval of the klines.
+    /// - `from_ts`: The earliest open time, inclusive.
+    /// - `to_ts`: The latest open time, inclusive.
+    ///
+    /// # Returns
+    ///
+    /// A `KlineStream` over the range.
+    pub async fn kline_stream(
+        &self,
+        symbol: &str,
+        interval: Interval,
+        from_ts: u64,
+        to_ts: u64,
+    ) -> KlineStream {
+        self.data
+            .read()
+            .await
+            .kline_stream(symbol, interval, from_ts, to_ts)
+    }
+
+    /// Retrieves the market regime of a symbol, as last updated by the regime tracker.
+    ///
+    /// # Parameters
+    ///
+    /// - `symbol`: The trading symbol.
+    /// - `interval`: The interval of the klines used for the classification.
+    ///
+    /// # Returns
+    ///
+    /// The `MarketRegime`, or `None` if the kline stream isn't needed or there isn't enough
+    /// kline data.
+    pub async fn regime(&self, symbol: &str, interval: Interval) -> Option<MarketRegime> {
+        self.regimes
+            .read()
+            .await
+            .get(&(symbol.to_string(), interval))
+            .copied()
+    }
+
+    /// Classifies the market regime of a symbol from the klines closed up to a given time, e.g.
+    /// the signal time for back tests.
+    ///
+    /// # Parameters
+    ///
+    /// - `symbol`: The trading symbol to classify.
+    /// - `interval`: The interval of the klines used for the classification.
+    /// - `to_ts`: The timestamp of the last kline considered.
+    ///
+    /// # Returns
+    ///
+    /// The `MarketRegime`, or `None` if there isn't enough kline data.
+    pub async fn regime_at(
+        &self,
+        symbol: &str,
+        interval: Interval,
+        to_ts: u64,
+    ) -> Option<MarketRegime> {
+        Self::calc_regime(&self.data, symbol, interval, to_ts).await
+    }
+
+    /// Retrieves the correlations of the symbols tracked at a kline interval, as last updated
+    /// by the correlation tracker.
+    ///
+    /// # Returns
+    ///
+    /// The `CorrelationMatrix`, or `None` if no kline stream of the interval is needed.
+    pub async fn correlations(&self, interval: Interval) -> Option<CorrelationMatrix> {
+        self.correlations.read().await.get(&interval).cloned()
+    }
+
+    /// Retrieves the correlations of the tracked symbols at every interval.
+    pub async fn all_correlations(&self) -> Vec<CorrelationMatrix> {
+        let mut correlations: Vec<CorrelationMatrix> =
+            self.correlations.read().await.values().cloned().collect();
+        correlations.sort_by_key(|matrix| matrix.interval);
+        correlations
+    }
+
+    /// Retrieves the value every external series had at a time, e.g. the close time of a kline,
+    /// so algorithms never read values published after it.
+    pub async fn external_values(&self, ts: u64) -> HashMap<String, f64> {
+        self.external_data.read().await.values_at(ts)
+    }
+
+    /// Retrieves the values of an external series published in a time range, oldest first.
+    pub async fn external_series(
+        &self,
+        series: &str,
+        from_ts: Option<u64>,
+        to_ts: Option<u64>,
+    ) -> Vec<ExternalDataPoint> {
+        self.external_data
+            .read()
+            .await
+            .points(series, from_ts, to_ts)
+    }
+
+    /// Lists the names of the external series holding values.
+    pub async fn external_series_names(&self) -> Vec<String> {
+        self.external_data.read().await.series_names()
+    }
+
+    /// Adds values to an external series and saves them, e.g. values posted to the feed
+    /// webhook.
+    pub async fn insert_external_data(&self, series: &str, points: &[ExternalDataPoint]) {
+        let changed = self.external_data.write().await.insert(series, points);
+        if let Err(e) = save_external_data(&self.storage_manager, series, &changed).await {
+            warn!("Unable to save external series {series}, e: {e}");
+        }
+    }
+
+    /// Loads every value of the external series published up to a time from storage, so back
+    /// tests read the values of their own time rather than the values retained by the market.
+    ///
+    /// # Arguments
+    ///
+    /// * `to_ts` - The end of the back test, values before its start are loaded as well so its
+    ///   first klines read the latest value published before them.
+    ///
+    /// # Returns
+    ///
+    /// The values, or the values retained by the market if storage can't be read.
+    pub async fn external_data_until(&self, to_ts: u64) -> ExternalDataStore {
+        match load_external_data(&self.storage_manager, None, Some(to_ts), usize::MAX).await {
+            Ok(store) => store,
+            Err(e) => {
+                warn!("Unable to load external data, using the retained values, e: {e}");
+                self.external_data.read().await.clone()
+            }
+        }
+    }
+
+    /// Calculates the correlations of any symbols from their klines closed up to a given time.
+    ///
+    /// # Parameters
+    ///
+    /// - `symbols`: The symbols to correlate.
+    /// - `interval`: The interval of the klines the returns are calculated from.
+    /// - `window`: The number of returns the correlations are calculated over, the window of the
+    ///   tracker if `None`.
+    /// - `to_ts`: The timestamp of the last kline considered.
+    pub async fn correlation_matrix(
+        &self,
+        symbols: &[String],
+        interval: Interval,
+        window: Option<usize>,
+        to_ts: u64,
+    ) -> CorrelationMatrix {
+        let window = window.unwrap_or(self.correlation_window);
+        Self::calc_correlation_matrix(&self.data, symbols, interval, window, to_ts).await
+    }
+
     // TODO: docs
     pub async fn trade_data_range(
         &self,
@@ -223,21 +455,20 @@ impl Market {
         limit: Option<usize>,
     ) -> Option<TradeData> {
         self.data
-            .lock()
+            .read()
             .await
             .trade_data(symbol, from_ts, to_ts, limit)
             .await
     }
 
-    /// Provides a shared, thread-safe reference to the market data.
-    ///
-    /// This method grants access to the current state of market data, including Klines and tickers, managed within the Market instance.
+    /// Provides a shared, thread-safe reference to the market data, e.g. for tests to feed
+    /// tickers.
     ///
     /// # Returns
     ///
-    /// An `ArcMutex<MarketData>` encapsulating the market data, allowing for concurrent reads and writes.
-
-    pub async fn market_data(&self) -> ArcMutex<MarketData> {
+    /// An `ArcRwLock<MarketData>` encapsulating the market data, allowing for concurrent reads and writes.
+    #[cfg(test)]
+    pub async fn market_data(&self) -> ArcRwLock<MarketData> {
         self.data.clone()
     }
 
@@ -257,6 +488,33 @@ impl Market {
         self.exchange_api.active_streams().await
     }
 
+    /// Retrieves the active streams with their health and whether a running strategy needs them.
+    ///
+    /// # Returns
+    ///
+    /// A `Vec<StreamStatus>` of the active streams, sorted by stream id.
+    pub async fn stream_statuses(&self) -> Vec<StreamStatus> {
+        let now = generate_ts();
+        let mut statuses = vec![];
+        for meta in self.active_streams().await {
+            let needed = self.is_needed_stream(&meta.id).await;
+            statuses.push(StreamStatus::new(meta, needed, now));
+        }
+        statuses.sort_by(|a, b| a.meta.id.cmp(&b.meta.id));
+
+        statuses
+    }
+
+    /// Checks whether a running strategy needs a stream, needed streams are reopened by the
+    /// stream monitor when they are closed.
+    pub async fn is_needed_stream(&self, stream_id: &str) -> bool {
+        self.needed_streams
+            .lock()
+            .await
+            .iter()
+            .any(|meta| meta.id == stream_id)
+    }
+
     /// Initiates a new stream based on the specified parameters and adds it to the list of active streams.
     ///
     /// This method constructs a new stream URL and metadata for a given symbol, stream type, and optionally an interval, then requests the stream manager to open and monitor this stream.
@@ -265,7 +523,7 @@ impl Market {
     ///
     /// - `stream_type`: The `StreamType` indicating the nature of the stream to be opened (e.g., Ticker, Kline).
     /// - `symbol`: A `&str` representing the trading pair or market symbol for which the stream is to be opened.
-    /// - `interval`: An optional `&str` specifying the interval for Kline streams. Ignored for Ticker streams.
+    /// - `interval`: An optional `Interval` specifying the interval for Kline streams. Ignored for Ticker streams.
     ///
     /// # Returns
     ///
@@ -275,28 +533,40 @@ impl Market {
         &self,
         stream_type: StreamType,
         symbol: &str,
-        interval: Option<&str>,
+        interval: Option<Interval>,
     ) -> ApiResult<String> {
         let url = self
             .exchange_api
             .build_stream_url(symbol, stream_type.clone(), interval);
         let stream_id = build_stream_id(symbol, stream_type, interval);
 
-        let interval = interval.map(|s| s.to_owned());
-
         // create new StreamMeta
         let open_stream_meta = StreamMeta::new(&stream_id, &url, symbol, stream_type, interval);
-        self.exchange_api
+        let result = self
+            .exchange_api
             .get_stream_manager()
             .lock()
             .await
-            .open_stream(open_stream_meta)
-            .await
+            .open_stream(open_stream_meta.clone())
+            .await;
+
+        match &result {
+            Ok(_) => self
+                .events
+                .publish(BotEvent::StreamOpened(open_stream_meta)),
+            Err(e) => self.events.publish(BotEvent::StreamError {
+                stream_id,
+                message: e.to_string(),
+            }),
+        }
+
+        result
     }
 
     /// Closes an active stream identified by its unique identifier.
     ///
     /// This method requests the stream manager to terminate a specific stream and remove it from the list of active streams, ceasing data flow and interactions with that stream.
+    /// The stream is no longer needed either, so the stream monitor doesn't reopen it.
     ///
     /// # Parameters
     ///
@@ -307,12 +577,56 @@ impl Market {
     /// An `Option<StreamMeta>` containing the metadata of the closed stream if successful, or `None` if the stream could not be found or closed.
 
     pub async fn close_stream(&self, stream_id: &str) -> Option<StreamMeta> {
-        self.exchange_api
+        let meta = self
+            .exchange_api
             .get_stream_manager()
             .lock()
             .await
             .close_stream(stream_id)
+            .await;
+
+        if let Some(meta) = &meta {
+            self.remove_needed_stream(&meta.symbol, meta.stream_type, meta.interval)
+                .await;
+            self.events.publish(BotEvent::StreamClosed(meta.clone()));
+        }
+
+        meta
+    }
+
+    /// Closes every active stream for the bot to shut down. Needed streams are forgotten first,
+    /// so the stream monitor doesn't reopen them.
+    ///
+    /// # Returns
+    ///
+    /// The ids of the streams still active afterwards.
+    pub async fn close_all_streams(&self) -> Vec<String> {
+        self.needed_streams.lock().await.clear();
+
+        for meta in self.active_streams().await {
+            self.close_stream(&meta.id).await;
+        }
+
+        self.active_streams()
             .await
+            .into_iter()
+            .map(|meta| meta.id)
+            .collect()
+    }
+
+    /// Writes all k-lines and trades held in memory to storage, along with anything the storage
+    /// buffers, so no market data is lost when the bot shuts down.
+    ///
+    /// # Returns
+    ///
+    /// An error if market data couldn't be written.
+    pub async fn flush_data(&self) -> StorageResult<()> {
+        self.data.write().await.flush().await
+    }
+
+    /// Subscribes to the live ticker and kline updates received from now on.
+    pub fn subscribe_updates(&self) -> Receiver<MarketUpdate> {
+        self.updates.subscribe()
     }
 
     // ---
@@ -332,33 +646,74 @@ impl Market {
             .await;
         self.add_needed_stream("BTCUSDT", StreamType::Trade, None)
             .await;
-        self.add_needed_stream("BTCUSDT", StreamType::Kline, Some("1m"))
+        self.add_needed_stream("BTCUSDT", StreamType::Kline, Some(Interval::Min1))
             .await;
 
         self.init_market_receivers().await;
         self.init_active_stream_monitor().await;
+        self.init_correlation_tracker().await;
+        self.init_regime_tracker().await;
+
+        // values saved before a restart
+        match load_external_data(&self.storage_manager, None, None, EXTERNAL_DATA_RETENTION).await {
+            Ok(store) => *self.external_data.write().await = store,
+            Err(e) => warn!("Unable to load external data, e: {e}"),
+        }
+        match external_feeds_from_env() {
+            Ok(feeds) => feeds
+                .into_iter()
+                .for_each(|feed| self.init_external_feed(feed)),
+            Err(e) => warn!("External feeds are disabled, e: {e}"),
+        }
     }
 
     async fn init_market_receivers(&self) {
         let market_receiver = self.market_receiver.clone();
         let market_data = self.data.clone();
+        let updates = self.updates.clone();
+        let events = self.events.clone();
 
         // let active_streams = self.active_streams.clone();
 
         // spawn thread to handle stream_manager messages
         tokio::spawn(async move {
-            while let Some(message) = market_receiver.lock().await.recv().await {
-                // println!("{message:?}");
+            health::runtime().register_task("market_receiver", None);
+            loop {
+                let (message, stats) = {
+                    let mut receiver = market_receiver.lock().await;
+                    let Some(message) = receiver.recv().await else {
+                        break;
+                    };
+                    (message, receiver.stats())
+                };
+                health::runtime().record_channel_stats("market_tx", stats);
+                let _iteration = health::time_iteration("market_receiver");
+
+                // updates are only cloned while anyone is subscribed
+                let has_subscribers = updates.receiver_count() > 0;
 
                 match message {
                     MarketMessage::UpdateKline(kline) => {
-                        market_data.lock().await.update_kline(kline).await;
+                        if has_subscribers {
+                            let _ = updates.send(MarketUpdate::Kline(kline.clone()));
+                        }
+                        market_data.write().await.update_kline(kline).await;
                     }
                     MarketMessage::UpdateTicker(ticker) => {
-                        market_data.lock().await.update_ticker(ticker).await;
+                        if has_subscribers {
+                            let _ = updates.send(MarketUpdate::Ticker(ticker.clone()));
+                        }
+                        market_data.write().await.update_ticker(ticker).await;
                     }
                     MarketMessage::UpdateMarketTrade(mut trade) => {
-                        market_data.lock().await.update_trade(&mut trade).await;
+                        market_data.write().await.update_trade(&mut trade).await;
+                    }
+                    MarketMessage::StreamError { stream_id, error } => {
+                        info!("Stream {stream_id} failed, e: {error}");
+                        events.publish(BotEvent::StreamError {
+                            stream_id,
+                            message: error,
+                        });
                     }
                 }
             }
@@ -369,15 +724,18 @@ impl Market {
         let stream_manager = self.exchange_api.get_stream_manager();
         let exchange_api = self.exchange_api.clone();
         let needed_streams = self.needed_streams.clone();
+        let events = self.events.clone();
 
         tokio::spawn(async move {
+            health::runtime().register_task("stream_monitor", Some(STREAM_MONITOR_INTERVAL));
             loop {
-                tokio::time::sleep(Duration::from_secs(3)).await;
+                tokio::time::sleep(STREAM_MONITOR_INTERVAL).await;
+                let _iteration = health::time_iteration("stream_monitor");
                 let active_streams = stream_manager.lock().await.active_streams().await;
                 for needed_stream_meta in needed_streams.lock().await.iter() {
                     let active_stream_meta = active_streams
                         .iter()
-                        .find(|&meta| meta.symbol == needed_stream_meta.symbol);
+                        .find(|&meta| meta.id == needed_stream_meta.id);
 
                     match active_stream_meta {
                         Some(_meta) => {
@@ -386,12 +744,19 @@ impl Market {
                         None => {
                             let need_stream = needed_stream_meta.clone();
 
-                            let _ = exchange_api
+                            let result = exchange_api
                                 .get_stream_manager()
                                 .lock()
                                 .await
-                                .open_stream(need_stream)
+                                .open_stream(need_stream.clone())
                                 .await;
+                            match result {
+                                Ok(_) => events.publish(BotEvent::StreamOpened(need_stream)),
+                                Err(e) => events.publish(BotEvent::StreamError {
+                                    stream_id: need_stream.id,
+                                    message: e.to_string(),
+                                }),
+                            }
                         }
                     }
                 }
@@ -399,6 +764,158 @@ impl Market {
         });
     }
 
+    async fn init_correlation_tracker(&self) {
+        let market_data = self.data.clone();
+        let needed_streams = self.needed_streams.clone();
+        let correlations = self.correlations.clone();
+        let window = self.correlation_window;
+
+        tokio::spawn(async move {
+            health::runtime()
+                .register_task("correlation_tracker", Some(CORRELATION_UPDATE_INTERVAL));
+            loop {
+                tokio::time::sleep(CORRELATION_UPDATE_INTERVAL).await;
+                let _iteration = health::time_iteration("correlation_tracker");
+
+                let streams: Vec<(String, Interval)> = needed_streams
+                    .lock()
+                    .await
+                    .iter()
+                    .filter(|meta| matches!(meta.stream_type, StreamType::Kline))
+                    .filter_map(|meta| Some((meta.symbol.clone(), meta.interval?)))
+                    .collect();
+
+                let now = generate_ts();
+                let mut updated = HashMap::new();
+                for (interval, symbols) in symbols_by_interval(streams) {
+                    let matrix = Self::calc_correlation_matrix(
+                        &market_data,
+                        &symbols,
+                        interval,
+                        window,
+                        now,
+                    )
+                    .await;
+                    updated.insert(interval, matrix);
+                }
+                *correlations.write().await = updated;
+            }
+        });
+    }
+
+    async fn init_regime_tracker(&self) {
+        let market_data = self.data.clone();
+        let needed_streams = self.needed_streams.clone();
+        let regimes = self.regimes.clone();
+
+        tokio::spawn(async move {
+            health::runtime().register_task("regime_tracker", Some(REGIME_UPDATE_INTERVAL));
+            loop {
+                tokio::time::sleep(REGIME_UPDATE_INTERVAL).await;
+                let _iteration = health::time_iteration("regime_tracker");
+
+                let streams: Vec<(String, Interval)> = needed_streams
+                    .lock()
+                    .await
+                    .iter()
+                    .filter(|meta| matches!(meta.stream_type, StreamType::Kline))
+                    .filter_map(|meta| Some((meta.symbol.clone(), meta.interval?)))
+                    .collect();
+
+                let now = generate_ts();
+                let mut updated = HashMap::new();
+                for (symbol, interval) in streams {
+                    if let Some(regime) =
+                        Self::calc_regime(&market_data, &symbol, interval, now).await
+                    {
+                        updated.insert((symbol, interval), regime);
+                    }
+                }
+                *regimes.write().await = updated;
+            }
+        });
+    }
+
+    /// Polls an external feed into the external data store at the interval of the feed.
+    fn init_external_feed(&self, feed: Box<dyn ExternalFeed>) {
+        let external_data = self.external_data.clone();
+        let storage_manager = self.storage_manager.clone();
+        let client = reqwest::Client::new();
+
+        tokio::spawn(async move {
+            let task = format!("external_feed:{}", feed.name());
+            let poll_interval = feed.poll_interval();
+            health::runtime().register_task(&task, Some(poll_interval));
+            loop {
+                {
+                    let _iteration = health::time_iteration(&task);
+                    match feed.fetch(&client).await {
+                        Ok(points) => {
+                            // polls return values already saved, only the new ones are saved
+                            let changed = external_data.write().await.insert(feed.name(), &points);
+                            let saved =
+                                save_external_data(&storage_manager, feed.name(), &changed).await;
+                            if let Err(e) = saved {
+                                warn!("Unable to save external feed {}, e: {e}", feed.name());
+                            }
+                        }
+                        Err(e) => warn!("External feed {} failed, e: {e}", feed.name()),
+                    }
+                }
+                tokio::time::sleep(poll_interval).await;
+            }
+        });
+    }
+
+    /// Calculates the correlations of symbols from the klines of the market data.
+    async fn calc_correlation_matrix(
+        market_data: &ArcRwLock<MarketData>,
+        symbols: &[String],
+        interval: Interval,
+        window: usize,
+        to_ts: u64,
+    ) -> CorrelationMatrix {
+        let lookback = interval.to_millis() * (window as u64 + 1);
+        let from_ts = to_ts.saturating_sub(lookback);
+
+        let mut klines = vec![];
+        for symbol in symbols {
+            let symbol_klines = market_data
+                .read()
+                .await
+                .kline_data(symbol, interval, Some(from_ts), Some(to_ts), None)
+                .await
+                .map(|kline_data| kline_data.klines())
+                .unwrap_or_default();
+            klines.push((symbol.clone(), symbol_klines));
+        }
+
+        CorrelationMatrix::from_klines(interval, window, &klines)
+    }
+
+    /// Classifies the market regime of a symbol from the klines of the market data.
+    async fn calc_regime(
+        market_data: &ArcRwLock<MarketData>,
+        symbol: &str,
+        interval: Interval,
+        to_ts: u64,
+    ) -> Option<MarketRegime> {
+        let lookback = interval.to_millis() * (REGIME_PERIOD as u64 + 1);
+        let kline_data = market_data
+            .read()
+            .await
+            .kline_data(
+                symbol,
+                interval,
+                Some(to_ts.saturating_sub(lookback)),
+                Some(to_ts),
+                None,
+            )
+            .await?;
+
+        detect_regime(&kline_data.klines())
+    }
+
     /// Adds a specified stream to the list of necessary streams to be monitored or interacted with.
     ///
     /// This method queues a stream for opening based on the specified parameters. It constructs
@@ -409,24 +926,37 @@ impl Market {
     ///
     /// - `symbol`: A `&str` specifying the trading pair or market symbol the stream is associated with.
     /// - `stream_type`: A `StreamType` indicating the type of stream to be opened (e.g., Ticker, Kline).
-    /// - `interval`: An `Option<&str>` specifying the interval for Kline streams. This parameter is ignored for Ticker streams.
-
+    /// - `interval`: An `Option<Interval>` specifying the interval for Kline streams. This parameter is ignored for Ticker streams.
     pub async fn add_needed_stream(
         &self,
         symbol: &str,
         stream_type: StreamType,
-        interval: Option<&str>,
+        interval: Option<Interval>,
     ) {
         let mut needed_streams = self.needed_streams.lock().await;
         let url = self
             .exchange_api
             .build_stream_url(symbol, stream_type, interval);
         let stream_id = build_stream_id(symbol, stream_type, interval);
-        let stream_meta = StreamMeta::new(&stream_id, &url, symbol, stream_type, None);
+        if needed_streams.iter().any(|meta| meta.id == stream_id) {
+            return;
+        }
+        let stream_meta = StreamMeta::new(&stream_id, &url, symbol, stream_type, interval);
 
         needed_streams.push(stream_meta);
     }
 
+    /// Lists the streams reopened by the stream monitor whenever they close.
+    pub async fn needed_streams(&self) -> Vec<StreamMeta> {
+        self.needed_streams.lock().await.clone()
+    }
+
+    /// Replaces the needed streams, e.g. by those of a snapshot. Streams no longer needed stay
+    /// open until they close.
+    pub async fn replace_needed_streams(&self, streams: Vec<StreamMeta>) {
+        *self.needed_streams.lock().await = streams;
+    }
+
     /// Removes a specified stream from the list of necessary streams.
     ///
     /// This method deletes the stream metadata based on the specified parameters from the internal list of streams that need to be monitored or interacted with. It ensures that no further actions or data processing occur for the removed stream.
@@ -435,13 +965,12 @@ impl Market {
     ///
     /// - `symbol`: A `&str` specifying the trading pair or market symbol the stream is associated with.
     /// - `stream_type`: A `StreamType` indicating the type of stream to be removed. This parameter is currently not used but reserved for future functionality.
-    /// - `interval`: An `Option<&str>` specifying the interval for Kline streams. This parameter helps identify the correct stream to remove and is ignored for Ticker streams.
-
+    /// - `interval`: An `Option<Interval>` specifying the interval for Kline streams. This parameter helps identify the correct stream to remove and is ignored for Ticker streams.
     pub async fn remove_needed_stream(
         &self,
         symbol: &str,
         stream_type: StreamType,
-        interval: Option<&str>,
+        interval: Option<Interval>,
     ) {
         let mut needed_streams = self.needed_streams.lock().await;
         let stream_id = build_stream_id(symbol, stream_type, interval);
@@ -535,13 +1064,13 @@ impl MarketData {
     ///
     pub async fn update_kline(&mut self, kline: Kline) {
         // get kline key eg. BTCUSDT@kline_1m
-        let kline_key = build_kline_key(&kline.symbol, &kline.interval);
+        let kline_key = build_kline_key(&kline.symbol, kline.interval);
 
         // add new kline to data if key found for kline symbol
         if let Some(kline_data) = self.all_klines.get_mut(&kline_key) {
             kline_data.add_kline(kline);
         } else {
-            let mut new_kline_data = KlineData::new(&kline.symbol, &kline.interval);
+            let mut new_kline_data = KlineData::new(&kline.symbol, kline.interval);
             new_kline_data.add_kline(kline);
             self.all_klines
                 .insert(kline_key.to_string(), new_kline_data);
@@ -604,43 +1133,23 @@ impl MarketData {
     ///
     /// Returns an Option<KlineData> containing the requested kline data, or None if no data is available.
     pub async fn kline_data(
-        &mut self,
+        &self,
         symbol: &str,
-        interval: &str,
+        interval: Interval,
         from_ts: Option<u64>,
         to_ts: Option<u64>,
         limit: Option<usize>,
     ) -> Option<KlineData> {
-        let kline_key = build_kline_key(symbol, interval);
-
         let mut kline_data = KlineData::new(symbol, interval);
 
-        let in_mem_kline = match self.all_klines.get(&kline_key) {
-            Some(kline_data) => kline_data.klines(),
-            None => vec![],
+        // without from_ts the limit applies to the most recent klines
+        let query = KlineQuery {
+            from_ts,
+            to_ts,
+            limit,
+            ..Default::default()
         };
-
-        let mut filtered_klines = self
-            .storage_manager
-            .get_klines(symbol, interval, from_ts, to_ts)
-            .await;
-        filtered_klines.extend_from_slice(&in_mem_kline);
-
-        // filtered by from_ts and to_ts
-        if let Some(from_ts) = from_ts {
-            filtered_klines.retain(|kline| kline.open_time >= from_ts);
-            if let Some(to_ts) = to_ts {
-                filtered_klines.retain(|kline| kline.open_time <= to_ts);
-            }
-        }
-
-        // Sort the klines by open_time in descending order
-        filtered_klines.sort_by(|a, b| a.open_time.cmp(&b.open_time));
-
-        // Limit the number of data points returned
-        if let Some(limit) = limit {
-            filtered_klines = filtered_klines[..limit].to_vec();
-        }
+        let filtered_klines = self.kline_page(symbol, interval, &query).await;
 
         // Create a new KlineData object to hold the filtered klines
 
@@ -655,6 +1164,81 @@ impl MarketData {
         }
     }
 
+    /// Retrieves a page of klines from persistent storage merged with the in-memory klines not
+    /// saved yet.
+    ///
+    /// # Parameters
+    ///
+    /// - symbol: The market symbol of the klines.
+    /// - interval: The interval of the klines.
+    /// - query: The range and page of the klines.
+    ///
+    /// # Returns
+    ///
+    /// Returns the klines of the page in the order of the query.
+    pub async fn kline_page(
+        &self,
+        symbol: &str,
+        interval: Interval,
+        query: &KlineQuery,
+    ) -> Vec<Kline> {
+        let kline_key = build_kline_key(symbol, interval);
+        let in_mem_klines = match self.all_klines.get(&kline_key) {
+            Some(kline_data) => kline_data.klines(),
+            None => vec![],
+        };
+
+        // the page is cut from the stored klines up to its end merged with the in-memory ones,
+        // which replace stored klines of the same open time
+        let stored_query = KlineQuery {
+            limit: query.required_len(),
+            offset: 0,
+            ..query.clone()
+        };
+        let mut klines = self
+            .storage_manager
+            .query_klines(symbol, interval, &stored_query)
+            .await;
+        klines.extend(in_mem_klines);
+
+        query.apply(klines)
+    }
+
+    /// Creates a stream over the stored and in-memory klines of a range, see `KlineStream`.
+    ///
+    /// # Parameters
+    ///
+    /// - symbol: The market symbol of the klines.
+    /// - interval: The interval of the klines.
+    /// - from_ts: The earliest open time, inclusive.
+    /// - to_ts: The latest open time, inclusive.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `KlineStream` over the range.
+    pub fn kline_stream(
+        &self,
+        symbol: &str,
+        interval: Interval,
+        from_ts: u64,
+        to_ts: u64,
+    ) -> KlineStream {
+        let kline_key = build_kline_key(symbol, interval);
+        let in_mem_klines = match self.all_klines.get(&kline_key) {
+            Some(kline_data) => kline_data.klines_in_range(from_ts, to_ts),
+            None => vec![],
+        };
+
+        KlineStream::new(
+            self.storage_manager.clone(),
+            symbol,
+            interval,
+            from_ts,
+            to_ts,
+            in_mem_klines,
+        )
+    }
+
     /// Provides a snapshot of the latest ticker data for a given symbol. This method retrieves the most recent ticker information, offering insights into current market conditions such as the latest price, volume, and price changes.
     ///
     /// # Parameters
@@ -737,6 +1321,48 @@ impl MarketData {
     // Private methods
     // ---
 
+    /// Writes all k-lines and trades held in memory to storage and flushes the storage.
+    ///
+    /// # Returns
+    ///
+    /// An error naming the keys whose market data couldn't be written.
+    pub async fn flush(&mut self) -> StorageResult<()> {
+        let mut errors = vec![];
+        for (key, kline_data) in self.all_klines.iter_mut() {
+            let klines = kline_data.drain_klines(u64::MAX);
+            if klines.is_empty() {
+                continue;
+            }
+            if let Err(e) = self.storage_manager.save_klines(&klines, key, false).await {
+                errors.push(format!("{key}: {e}"));
+            }
+        }
+
+        for (key, trade_data) in self.all_trades.iter_mut() {
+            let trades = trade_data.drain_trades(u64::MAX);
+            if trades.is_empty() {
+                continue;
+            }
+            if let Err(e) = self.storage_manager.save_trades(&trades, key, false).await {
+                errors.push(format!("{key}: {e}"));
+            }
+        }
+        self.last_backup = generate_ts();
+
+        if let Err(e) = self.storage_manager.flush().await {
+            errors.push(e.to_string());
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(StorageError::Backend(format!(
+                "Unable to flush market data, {}",
+                errors.join(", ")
+            )))
+        }
+    }
+
     async fn handle_data_backup(&mut self) {
         let now = generate_ts();
 
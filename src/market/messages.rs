@@ -1,3 +1,5 @@
+use serde::Serialize;
+
 use crate::market::{kline::Kline, ticker::Ticker};
 
 use super::trade::Trade;
@@ -9,10 +11,38 @@ use super::trade::Trade;
 /// - UpdateTicker(Ticker): Carries a Ticker instance representing the latest ticker information to be updated in the market data.
 ///
 /// - UpdateKline(Kline): Contains a Kline instance representing a new or updated kline data point to be incorporated into the market data.
-
+///
+/// - StreamError: Reports a stream that failed to receive data, such as a dropped connection.
 #[derive(Debug)]
 pub enum MarketMessage {
     UpdateTicker(Ticker),
     UpdateKline(Kline),
     UpdateMarketTrade(Trade),
+    StreamError { stream_id: String, error: String },
+}
+
+/// A live ticker or kline update the market received, broadcast to subscribers such as the
+/// server-sent events stream.
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum MarketUpdate {
+    Ticker(Ticker),
+    Kline(Kline),
+}
+
+impl MarketUpdate {
+    pub fn symbol(&self) -> &str {
+        match self {
+            MarketUpdate::Ticker(ticker) => &ticker.symbol,
+            MarketUpdate::Kline(kline) => &kline.symbol,
+        }
+    }
+
+    /// The name of the update type, e.g. `ticker`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            MarketUpdate::Ticker(_) => "ticker",
+            MarketUpdate::Kline(_) => "kline",
+        }
+    }
 }
@@ -1,6 +1,13 @@
+pub mod correlation;
+pub mod external;
+pub mod interval;
 pub mod kline;
+pub mod kline_export;
+pub mod kline_stream;
 pub mod market;
 pub mod messages;
+pub mod regime;
+pub mod symbol;
 pub mod ticker;
 pub mod trade;
 pub mod types;
@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{market::kline::Kline, utils::kline::calc_atr};
+
+/// Number of klines used to classify the market regime.
+pub const REGIME_PERIOD: usize = 20;
+
+/// ATR, as a percentage of the last close price, above which the market is highly volatile.
+pub const HIGH_VOLATILITY_ATR_PCT: f64 = 3.0;
+
+/// Efficiency ratio above which the market is trending, see `detect_regime`.
+pub const TRENDING_EFFICIENCY_RATIO: f64 = 0.3;
+
+/// Volatility and trend classification of a symbol's recent klines.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MarketRegime {
+    TrendingUp,
+    TrendingDown,
+    Ranging,
+    HighVolatility,
+}
+
+/// Classifies the market regime of the last `REGIME_PERIOD` klines.
+///
+/// High volatility takes precedence, it is detected when the ATR exceeds
+/// `HIGH_VOLATILITY_ATR_PCT` of the last close price. Otherwise the trend is measured by the
+/// efficiency ratio, the net close price change divided by the sum of the absolute close price
+/// changes, `1` for a straight line and close to `0` when price goes back and forth.
+///
+/// # Arguments
+///
+/// * `klines` - A slice of `Kline` structs sorted by open time.
+///
+/// # Returns
+///
+/// The `MarketRegime`, or `None` if there are fewer than `REGIME_PERIOD + 1` klines.
+pub fn detect_regime(klines: &[Kline]) -> Option<MarketRegime> {
+    let atr = calc_atr(klines, REGIME_PERIOD)?;
+    let window = &klines[klines.len() - REGIME_PERIOD - 1..];
+
+    let last_close = window[REGIME_PERIOD].close;
+    if last_close > 0.0 && atr / last_close * 100.0 > HIGH_VOLATILITY_ATR_PCT {
+        return Some(MarketRegime::HighVolatility);
+    }
+
+    let net_change = last_close - window[0].close;
+    let path: f64 = window
+        .windows(2)
+        .map(|pair| (pair[1].close - pair[0].close).abs())
+        .sum();
+    if path == 0.0 {
+        return Some(MarketRegime::Ranging);
+    }
+
+    let efficiency_ratio = net_change / path;
+    let regime = if efficiency_ratio > TRENDING_EFFICIENCY_RATIO {
+        MarketRegime::TrendingUp
+    } else if efficiency_ratio < -TRENDING_EFFICIENCY_RATIO {
+        MarketRegime::TrendingDown
+    } else {
+        MarketRegime::Ranging
+    };
+
+    Some(regime)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::harness::Fixture;
+
+    /// Tests trends, ranges and volatile markets are told apart.
+    #[test]
+    fn test_detect_regime() {
+        let len = REGIME_PERIOD + 1;
+
+        assert_eq!(detect_regime(&Fixture::Flat.klines(len - 1)), None);
+        assert_eq!(
+            detect_regime(&Fixture::Flat.klines(len)),
+            Some(MarketRegime::Ranging)
+        );
+        assert_eq!(
+            detect_regime(&Fixture::TrendingUp.klines(len)),
+            Some(MarketRegime::TrendingUp)
+        );
+        assert_eq!(
+            detect_regime(&Fixture::TrendingDown.klines(len)),
+            Some(MarketRegime::TrendingDown)
+        );
+        assert_eq!(
+            detect_regime(&Fixture::Choppy.klines(len)),
+            Some(MarketRegime::HighVolatility)
+        );
+    }
+}
@@ -0,0 +1,176 @@
+//! # Symbols
+//!
+//! The markets the bot trades, identified by their base and quote assets. Exchanges spell the
+//! same market differently, Binance `BTCUSDT` and BingX `BTC-USDT`, so symbols are parsed from
+//! any of the spellings and written in the exchange's own only when talking to it. Everywhere
+//! else, in kline keys, stored data, strategies and the API, a symbol reads as its canonical
+//! name, e.g. `BTCUSDT`. Canonical names are used in file names, so they never contain a `/`.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Quote assets symbols written without a separator, e.g. `BTCUSDT`, are split at. Longer assets
+/// come first so `FDUSD` isn't read as `USD`.
+const QUOTE_ASSETS: [&str; 10] = [
+    "FDUSD", "USDT", "USDC", "BUSD", "TUSD", "USD", "BTC", "ETH", "BNB", "EUR",
+];
+
+/// Suffix of the canonical names of spot markets, e.g. `BTCUSDT.SPOT`.
+const SPOT_SUFFIX: &str = ".SPOT";
+
+/// The kind of market a symbol is traded on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum MarketKind {
+    /// A perpetual swap, which is what both exchange adapters trade.
+    #[default]
+    Perpetual,
+    Spot,
+}
+
+/// A market, e.g. the `BTC` perpetual swap quoted in `USDT`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Symbol {
+    pub base: String,
+    pub quote: String,
+    pub kind: MarketKind,
+}
+
+impl Symbol {
+    pub fn new(base: &str, quote: &str, kind: MarketKind) -> Self {
+        Self {
+            base: base.to_uppercase(),
+            quote: quote.to_uppercase(),
+            kind,
+        }
+    }
+
+    /// The symbol as Binance names it, e.g. `BTCUSDT`.
+    pub fn to_binance(&self) -> String {
+        format!("{}{}", self.base, self.quote)
+    }
+
+    /// The symbol as BingX names it, e.g. `BTC-USDT`.
+    pub fn to_bingx(&self) -> String {
+        format!("{}-{}", self.base, self.quote)
+    }
+
+    /// Canonicalizes a symbol in any spelling, leaving names that don't parse as they are, e.g.
+    /// symbols of tests or of exchange data with an unknown quote asset.
+    pub fn normalize(symbol: &str) -> String {
+        symbol
+            .parse::<Symbol>()
+            .map_or_else(|_| symbol.to_string(), |symbol| symbol.to_string())
+    }
+}
+
+/// The canonical name stored data is keyed by: `BTCUSDT` for perpetual swaps and `BTCUSDT.SPOT`
+/// for spot markets.
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            MarketKind::Perpetual => write!(f, "{}{}", self.base, self.quote),
+            MarketKind::Spot => write!(f, "{}{}{SPOT_SUFFIX}", self.base, self.quote),
+        }
+    }
+}
+
+/// Parses a symbol of any exchange, e.g. `BTCUSDT`, `btcusdt`, `BTC-USDT` or TradingView's
+/// `BTCUSDT.P`. `BTC/USDT` and the canonical `BTCUSDT.SPOT` are the spot market, `BTC/USDT:USDT`
+/// the perpetual swap.
+impl FromStr for Symbol {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid symbol: {s}");
+        let symbol = s.trim().to_uppercase();
+        let symbol = symbol.strip_suffix(".P").unwrap_or(&symbol);
+        if let Some(pair) = symbol.strip_suffix(SPOT_SUFFIX) {
+            let symbol: Symbol = pair.parse()?;
+            return Ok(Self::new(&symbol.base, &symbol.quote, MarketKind::Spot));
+        }
+
+        let (pair, kind) = match symbol.split_once(':') {
+            Some((pair, _settle)) => (pair, MarketKind::Perpetual),
+            None if symbol.contains('/') => (symbol, MarketKind::Spot),
+            None => (symbol, MarketKind::Perpetual),
+        };
+
+        let (base, quote) = match pair.split_once(['-', '_', '/']) {
+            Some(assets) => assets,
+            None => QUOTE_ASSETS
+                .iter()
+                .find_map(|quote| Some((pair.strip_suffix(quote)?, *quote)))
+                .ok_or_else(invalid)?,
+        };
+
+        let is_asset =
+            |asset: &str| !asset.is_empty() && asset.chars().all(|c| c.is_ascii_alphanumeric());
+        if !is_asset(base) || !is_asset(quote) {
+            return Err(invalid());
+        }
+        Ok(Self::new(base, quote, kind))
+    }
+}
+
+impl Serialize for Symbol {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Symbol {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let symbol = String::deserialize(deserializer)?;
+        symbol.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Deserializes a symbol in any spelling into its canonical name, for symbols given to the API
+/// by users, e.g. `#[serde(deserialize_with = "canonical_symbol")]`.
+pub fn canonical_symbol<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    Symbol::deserialize(deserializer).map(|symbol| symbol.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests the spellings of every exchange parse to the same symbol, which is written back in
+    /// each exchange's own.
+    #[test]
+    fn test_symbol() {
+        let btc = Symbol::new("BTC", "USDT", MarketKind::Perpetual);
+        for spelling in [
+            "BTCUSDT",
+            "btcusdt",
+            "BTC-USDT",
+            "BTC_USDT",
+            "BTCUSDT.P",
+            "BTC/USDT:USDT",
+        ] {
+            assert_eq!(spelling.parse::<Symbol>(), Ok(btc.clone()), "{spelling}");
+        }
+        assert_eq!(btc.to_string(), "BTCUSDT");
+        assert_eq!(btc.to_binance(), "BTCUSDT");
+        assert_eq!(btc.to_bingx(), "BTC-USDT");
+
+        let spot: Symbol = "eth/btc".parse().unwrap();
+        assert_eq!(spot, Symbol::new("ETH", "BTC", MarketKind::Spot));
+        assert_eq!(spot.to_string(), "ETHBTC.SPOT");
+        assert_eq!("ETHBTC.SPOT".parse::<Symbol>(), Ok(spot));
+
+        assert_eq!("1000PEPEFDUSD".parse::<Symbol>().unwrap().base, "1000PEPE");
+        assert!("USDT".parse::<Symbol>().is_err());
+        assert!("BTCXYZ".parse::<Symbol>().is_err());
+        assert!("BTC-".parse::<Symbol>().is_err());
+
+        assert_eq!(Symbol::normalize("BTC-USDT"), "BTCUSDT");
+        assert_eq!(Symbol::normalize("default"), "default");
+
+        let json = serde_json::to_string(&btc).unwrap();
+        assert_eq!(json, "\"BTCUSDT\"");
+        assert_eq!(serde_json::from_str::<Symbol>("\"BTC-USDT\"").unwrap(), btc);
+    }
+}
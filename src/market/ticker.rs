@@ -6,7 +6,7 @@ use serde_json::Value;
 
 use crate::{
     exchange::types::ApiResult,
-    market::market::MarketDataSymbol,
+    market::{market::MarketDataSymbol, symbol::Symbol},
     utils::{
         number::{generate_random_id, parse_f64_from_lookup},
         time::{generate_ts, timestamp_to_string},
@@ -301,7 +301,7 @@ impl Ticker {
 
         Ok(Self {
             time,
-            symbol: symbol.to_string(),
+            symbol: Symbol::normalize(symbol),
             last_price,
             open_price,
             high,
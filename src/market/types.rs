@@ -6,17 +6,19 @@ use futures_util::lock::Mutex;
 
 use serde::Serialize;
 
-use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+use tokio::sync::RwLock;
+
+use crate::utils::channel::{ChannelReceiver, ChannelSender};
 
 /// Defines types for thread-safe asynchronous communication channels in Rust.
 ///
-/// Provides wrappers around the standard `UnboundedSender` and `UnboundedReceiver` for use in asynchronous contexts.
-pub type ArcSender<T> = Arc<UnboundedSender<T>>;
+/// Provides wrappers around the bounded `ChannelSender` and `ChannelReceiver` for use in asynchronous contexts.
+pub type ArcSender<T> = Arc<ChannelSender<T>>;
 
-/// Represents a thread-safe, asynchronously accessible sender part of an unbounded channel.
+/// Represents a thread-safe, asynchronously accessible receiver part of a bounded channel.
 ///
-/// This type is an `Arc` wrapper around `tokio::sync::mpsc::UnboundedSender`, allowing it to be shared across threads and tasks safely.
-pub type ArcReceiver<T> = Arc<Mutex<UnboundedReceiver<T>>>;
+/// This type is an `Arc` and `Mutex` wrapper around `ChannelReceiver`, allowing it to be shared across threads and tasks safely.
+pub type ArcReceiver<T> = Arc<Mutex<ChannelReceiver<T>>>;
 
 /// A thread-safe, asynchronously lockable wrapper around a shared resource.
 ///
@@ -70,3 +72,33 @@ impl<T> Clone for ArcMutex<T> {
         Self(Arc::clone(&self.0))
     }
 }
+
+/// A thread-safe, asynchronously lockable wrapper around a shared resource that is read far more
+/// often than it is written, e.g. market data read by every strategy.
+///
+/// Any number of readers hold the lock at once, while a writer waits for them and holds it alone.
+#[derive(Debug)]
+pub struct ArcRwLock<T>(Arc<RwLock<T>>);
+
+impl<T> ArcRwLock<T> {
+    /// Creates a new `ArcRwLock` instance, wrapping the provided value with `Arc` and `RwLock` for safe shared access in an asynchronous environment.
+    pub fn new(inner: T) -> Self {
+        Self(Arc::new(RwLock::new(inner)))
+    }
+}
+
+/// Implements the `Deref` trait, allowing direct access to the `RwLock` wrapped by the `ArcRwLock`.
+impl<T> Deref for ArcRwLock<T> {
+    type Target = RwLock<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// Implements the `Clone` trait for `ArcRwLock`, creating a new reference to the same lock.
+impl<T> Clone for ArcRwLock<T> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
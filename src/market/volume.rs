@@ -1,9 +1,7 @@
 use crate::{
     account::trade::OrderSide,
-    utils::time::{
-        floor_mili_ts, generate_ts, timestamp_to_datetime, timestamp_to_string, HOUR_AS_MILI,
-        MIN_AS_MILI,
-    },
+    market::interval::Interval,
+    utils::time::{generate_ts, timestamp_to_string},
 };
 
 use super::trade::{Trade, TradeData};
@@ -29,7 +27,7 @@ impl MarketTradeVolume {
         &self,
         trades: &[Trade],
         price_granularity: usize,
-        time_interval: &str,
+        time_interval: Interval,
     ) -> BucketedVolumeData {
         let (start_time, end_time) = self.calc_start_end_time(trades);
         let (min_price, max_price) = self.calc_min_max(trades);
@@ -121,18 +119,12 @@ impl MarketTradeVolume {
     pub fn calc_time_buckets(
         &self,
         trades: &[Trade],
-        time_interval: &str,
+        time_interval: Interval,
     ) -> BTreeMap<String, BucketVolume> {
         let mut volume_by_time_bucket = BTreeMap::new();
 
         for trade in trades {
-            let timestamp = match time_interval {
-                "1m" => floor_mili_ts(trade.timestamp, 1 * MIN_AS_MILI),
-                "5m" => floor_mili_ts(trade.timestamp, 5 * MIN_AS_MILI),
-                "15m" => floor_mili_ts(trade.timestamp, 15 * MIN_AS_MILI),
-                "1h" => floor_mili_ts(trade.timestamp, HOUR_AS_MILI),
-                _ => floor_mili_ts(trade.timestamp, HOUR_AS_MILI),
-            };
+            let timestamp = time_interval.floor(trade.timestamp);
             let bucket_key_str = timestamp_to_string(timestamp);
 
             let volume_entry = volume_by_time_bucket
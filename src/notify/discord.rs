@@ -0,0 +1,377 @@
+//! # Discord Notifications
+//!
+//! Bot events are posted to Discord channels through their webhooks, so teams monitoring a
+//! shared bot are alerted where they already collaborate. Every channel receives the event
+//! topics it is configured for.
+
+use log::{info, warn};
+use reqwest::{Client, StatusCode};
+use serde_json::json;
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+use tokio::task::JoinHandle;
+
+use crate::events::{BotEvent, EventTopic};
+use crate::strategy::signal::SignalAction;
+use crate::utils::env::{env_lookup, Lookup};
+
+/// Topics of channels configured without any, equity samples are published every minute and
+/// only sent to channels asking for them.
+const DEFAULT_TOPICS: [EventTopic; 6] = [
+    EventTopic::Signals,
+    EventTopic::Positions,
+    EventTopic::Strategies,
+    EventTopic::Errors,
+    EventTopic::Summaries,
+    EventTopic::Actions,
+];
+
+/// Longest message Discord accepts.
+const MAX_MESSAGE_LENGTH: usize = 2000;
+
+/// A Discord channel events are posted to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiscordChannel {
+    pub name: String,
+    /// The webhook URL of the channel, created in its integration settings.
+    pub webhook_url: String,
+    /// Topics of the events posted to the channel.
+    pub topics: Vec<EventTopic>,
+}
+
+impl DiscordChannel {
+    /// Reads the channels listed in `DISCORD_CHANNELS`, separated by commas.
+    ///
+    /// Each channel is configured by variables prefixed with its name in upper case, dashes
+    /// replaced by underscores, e.g. for `trading-desk`: `DISCORD_TRADING_DESK_WEBHOOK_URL` and
+    /// optionally `DISCORD_TRADING_DESK_TOPICS`, a comma separated list of `signals`,
+    /// `positions`, `equity`, `strategies`, `errors`, `summaries` and `streams`.
+    ///
+    /// # Returns
+    ///
+    /// The channels in the order they are listed, none if `DISCORD_CHANNELS` isn't set, or an
+    /// error if a channel is incomplete or invalid.
+    pub fn from_env() -> Result<Vec<Self>, String> {
+        Self::from_lookup(env_lookup)
+    }
+
+    /// Reads the webhook URL and topics of every listed channel through `lookup`, see
+    /// `from_env`.
+    pub fn from_lookup(lookup: impl Lookup) -> Result<Vec<Self>, String> {
+        let Some(names) = lookup("DISCORD_CHANNELS") else {
+            return Ok(vec![]);
+        };
+
+        let mut channels: Vec<Self> = vec![];
+        for name in names
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+        {
+            if channels.iter().any(|channel| channel.name == name) {
+                return Err(format!("Discord channel {name} is listed twice"));
+            }
+
+            let prefix = format!(
+                "DISCORD_{}_",
+                name.to_uppercase()
+                    .replace(|c: char| !c.is_ascii_alphanumeric(), "_")
+            );
+            let webhook_url = lookup(&format!("{prefix}WEBHOOK_URL"))
+                .ok_or_else(|| format!("Discord channel {name} is missing {prefix}WEBHOOK_URL"))?;
+            if !webhook_url.starts_with("https://") {
+                return Err(format!(
+                    "Discord channel {name}: webhook URL must use https"
+                ));
+            }
+
+            let topics = match lookup(&format!("{prefix}TOPICS")) {
+                Some(topics) => topics
+                    .split(',')
+                    .filter(|topic| !topic.trim().is_empty())
+                    .map(str::parse)
+                    .collect::<Result<Vec<EventTopic>, String>>()?,
+                None => DEFAULT_TOPICS.to_vec(),
+            };
+
+            channels.push(Self {
+                name: name.to_string(),
+                webhook_url,
+                topics,
+            });
+        }
+
+        Ok(channels)
+    }
+
+    /// Checks whether the channel receives the topic of an event.
+    pub fn receives(&self, event: &BotEvent) -> bool {
+        self.topics.contains(&event.topic())
+    }
+}
+
+/// Posts bot events to Discord channels.
+pub struct DiscordNotifier {
+    channels: Vec<DiscordChannel>,
+    client: Client,
+}
+
+impl DiscordNotifier {
+    pub fn new(channels: Vec<DiscordChannel>) -> Self {
+        Self {
+            channels,
+            client: Client::new(),
+        }
+    }
+
+    /// Posts the events received until the publisher is dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `events` - A subscription to the bot events.
+    ///
+    /// # Returns
+    ///
+    /// The handle of the task posting the events.
+    pub fn spawn(self, mut events: Receiver<BotEvent>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(event) => self.notify(&event).await,
+                    Err(RecvError::Lagged(missed)) => {
+                        warn!("Discord notifications missed {missed} events")
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+
+    /// Posts an event to every channel receiving its topic. Failed posts are logged, the
+    /// bot keeps trading whether Discord is reachable or not.
+    pub async fn notify(&self, event: &BotEvent) {
+        let channels: Vec<&DiscordChannel> = self
+            .channels
+            .iter()
+            .filter(|channel| channel.receives(event))
+            .collect();
+        if channels.is_empty() {
+            return;
+        }
+
+        let content = format_event(event);
+        for channel in channels {
+            let result = self
+                .client
+                .post(&channel.webhook_url)
+                .json(&json!({ "content": content }))
+                .send()
+                .await;
+            match result {
+                Ok(response) if response.status().is_success() => {}
+                Ok(response) if response.status() == StatusCode::TOO_MANY_REQUESTS => {
+                    warn!("Discord rate limited channel {}", channel.name)
+                }
+                Ok(response) => info!(
+                    "Unable to notify Discord channel {}, status: {}",
+                    channel.name,
+                    response.status()
+                ),
+                Err(e) => info!("Unable to notify Discord channel {}, e: {e}", channel.name),
+            }
+        }
+    }
+}
+
+/// Formats an event as a Discord message.
+pub fn format_event(event: &BotEvent) -> String {
+    let message = match event {
+        BotEvent::Signal(record) => {
+            let signal = &record.signal;
+            let action = match &record.action {
+                SignalAction::Opened { position_id } => format!("opened position {position_id}"),
+                SignalAction::Closed { position_ids } => {
+                    format!("closed {} positions", position_ids.len())
+                }
+                SignalAction::Scheduled { child_orders } => {
+                    format!("split into {child_orders} child orders")
+                }
+                SignalAction::IcebergPlaced { order_id } => {
+                    format!("placed iceberg order {order_id}")
+                }
+                SignalAction::Ignored { reason } => format!("ignored, {reason}"),
+            };
+            format!(
+                "**Signal** {} {} {} at {} (confidence {:.2}): {action}",
+                signal.order_side,
+                signal.symbol,
+                signal.interval,
+                signal.price,
+                signal.confidence
+            )
+        }
+        BotEvent::PositionOpened { account, position } => format!(
+            "**Position opened** on {account}: {} {} {} at {} with {}x leverage",
+            position.order_side,
+            position.quantity,
+            position.symbol,
+            position.open_price,
+            position.leverage
+        ),
+        BotEvent::PositionClosed { account, trade } => format!(
+            "**Position closed** on {account}: {} {} {} from {} to {}, profit {:.2} USD",
+            trade.position.order_side,
+            trade.position.quantity,
+            trade.position.symbol,
+            trade.position.open_price,
+            trade.close_price,
+            trade.calc_profit()
+        ),
+        BotEvent::OrderFilled { account, order } => format!(
+            "**Order filled** on {account}: {:?} {} {} {} at {}",
+            order.action, order.order_side, order.quantity, order.symbol, order.price
+        ),
+        BotEvent::Equity { account, equity } => format!(
+            "**Equity** of {account}: {:.2} USD, {:.2} realized, {:.2} unrealized, {} open positions",
+            equity.equity(),
+            equity.realized_profit,
+            equity.unrealized_profit,
+            equity.open_positions
+        ),
+        BotEvent::StrategyStarted(info) => format!(
+            "**Strategy started** {} on {} {} ({})",
+            info.name, info.symbol, info.interval, info.id
+        ),
+        BotEvent::StrategyStopped(info) => format!(
+            "**Strategy stopped** {} on {} {} ({})",
+            info.name, info.symbol, info.interval, info.id
+        ),
+        BotEvent::SettingsChanged {
+            strategy_id,
+            settings,
+        } => format!(
+            "**Settings changed** of {strategy_id}: {} USD margin, {}x leverage, {} max open orders",
+            settings.margin_usd, settings.leverage, settings.max_open_orders
+        ),
+        BotEvent::ParamsChanged(change) => format!(
+            "**Params changed** of {} by {}: {}",
+            change.strategy_id, change.changed_by, change.new_params
+        ),
+        BotEvent::Error { source, message } => format!("**Error** on {source}: {message}"),
+        BotEvent::StreamOpened(meta) => format!("**Stream opened** {}", meta.id),
+        BotEvent::StreamClosed(meta) => format!("**Stream closed** {}", meta.id),
+        BotEvent::StreamError { stream_id, message } => {
+            format!("**Stream error** {stream_id}: {message}")
+        }
+        BotEvent::DailySummary { account, summary } => format!(
+            "**Daily summary** of {account}: {} trades, {} won, {:.2} USD realized, {:.2} USD unrealized, {} open positions",
+            summary.trades,
+            summary.winning_trades,
+            summary.realized_profit,
+            summary.unrealized_profit,
+            summary.open_positions
+        ),
+        BotEvent::DailyReport(report) => {
+            let total = &report.total;
+            let mut message = format!(
+                "**Daily report**: {} trades, {:.0}% won, {:.2} USD realized, {:.2} USD unrealized, {:.2} USD fees, {} open positions",
+                total.trades,
+                total.win_rate * 100.0,
+                total.realized_profit,
+                total.unrealized_profit,
+                total.fees,
+                total.open_positions
+            );
+            for strategy in &report.strategies {
+                let name = match (&strategy.strategy_name, strategy.strategy_id) {
+                    (Some(name), _) => name.clone(),
+                    (None, Some(strategy_id)) => strategy_id.to_string(),
+                    (None, None) => "manual".to_string(),
+                };
+                message.push_str(&format!(
+                    "\n- {name} on {}: {} trades, {:.2} USD realized, {:.2} USD unrealized",
+                    strategy.account,
+                    strategy.activity.trades,
+                    strategy.activity.realized_profit,
+                    strategy.activity.unrealized_profit
+                ));
+            }
+            for event in &report.events {
+                message.push_str(&format!("\n- {}", event.message));
+            }
+            message
+        }
+        BotEvent::ManualAction { action, detail } => {
+            format!("**Manual action** {action}: {detail}")
+        }
+    };
+
+    message.chars().take(MAX_MESSAGE_LENGTH).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::market::interval::Interval;
+    use crate::strategy::strategy::StrategyInfo;
+    use crate::utils::env::vars_lookup;
+
+    fn channels(vars: &[(&str, &str)]) -> Result<Vec<DiscordChannel>, String> {
+        DiscordChannel::from_lookup(vars_lookup(vars))
+    }
+
+    /// Tests channels are read by name with their topics and events are formatted as messages.
+    #[test]
+    fn test_discord_channels() {
+        assert!(channels(&[]).unwrap().is_empty());
+
+        let loaded = channels(&[
+            ("DISCORD_CHANNELS", "trading-desk, ops"),
+            (
+                "DISCORD_TRADING_DESK_WEBHOOK_URL",
+                "https://discord.com/api/webhooks/1/a",
+            ),
+            (
+                "DISCORD_OPS_WEBHOOK_URL",
+                "https://discord.com/api/webhooks/2/b",
+            ),
+            ("DISCORD_OPS_TOPICS", "strategies, equity"),
+        ])
+        .unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded[0].name, "trading-desk");
+        assert_eq!(loaded[0].topics, DEFAULT_TOPICS.to_vec());
+        assert_eq!(
+            loaded[1].topics,
+            vec![EventTopic::Strategies, EventTopic::Equity]
+        );
+
+        let event = BotEvent::StrategyStarted(StrategyInfo {
+            name: "ema_cross".to_string(),
+            symbol: "BTCUSDT".to_string(),
+            interval: Interval::Hour1,
+            ..Default::default()
+        });
+        assert!(loaded[0].receives(&event) && loaded[1].receives(&event));
+        assert!(format_event(&event).starts_with("**Strategy started** ema_cross on BTCUSDT 1h"));
+
+        assert!(channels(&[("DISCORD_CHANNELS", "ops")]).is_err());
+        assert!(channels(&[
+            ("DISCORD_CHANNELS", "ops"),
+            (
+                "DISCORD_OPS_WEBHOOK_URL",
+                "http://discord.com/api/webhooks/2/b"
+            ),
+        ])
+        .is_err());
+        assert!(channels(&[
+            ("DISCORD_CHANNELS", "ops"),
+            (
+                "DISCORD_OPS_WEBHOOK_URL",
+                "https://discord.com/api/webhooks/2/b"
+            ),
+            ("DISCORD_OPS_TOPICS", "trades"),
+        ])
+        .is_err());
+    }
+}
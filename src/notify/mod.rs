@@ -0,0 +1,2 @@
+pub mod discord;
+pub mod webhook;
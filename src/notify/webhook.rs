@@ -0,0 +1,318 @@
+//! # Outbound Webhooks
+//!
+//! Bot events are posted as JSON to user configured HTTPS endpoints, so raderbot integrates with
+//! custom ops tooling. Every request is signed with the secret of its endpoint and retried with
+//! a backoff while the endpoint is unreachable or failing.
+//!
+//! Requests carry the headers `X-Raderbot-Delivery`, the id of the event which stays the same
+//! across retries, `X-Raderbot-Timestamp`, the time of the request in milliseconds, and
+//! `X-Raderbot-Signature`, `sha256=` followed by the hex encoded HMAC-SHA256 of
+//! `<timestamp>.<body>` keyed with the secret.
+
+use std::time::Duration;
+
+use log::{info, warn};
+use reqwest::Client;
+use serde_json::json;
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+use crate::events::{BotEvent, EventTopic};
+use crate::utils::crypt::sign_hmac;
+use crate::utils::env::{env_lookup, Lookup};
+use crate::utils::time::generate_ts;
+
+/// Topics of endpoints configured without any: signals, fills, errors and daily summaries.
+const DEFAULT_TOPICS: [EventTopic; 4] = [
+    EventTopic::Signals,
+    EventTopic::Positions,
+    EventTopic::Errors,
+    EventTopic::Summaries,
+];
+
+/// Attempts to deliver an event before it is dropped.
+const MAX_ATTEMPTS: u32 = 4;
+
+/// Delay before the first retry, doubled on every further retry.
+const RETRY_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Time an endpoint has to answer a request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// An endpoint events are posted to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WebhookEndpoint {
+    pub name: String,
+    pub url: String,
+    /// Key of the request signatures, shared with the receiver.
+    pub secret: String,
+    /// Topics of the events posted to the endpoint.
+    pub topics: Vec<EventTopic>,
+}
+
+impl WebhookEndpoint {
+    /// Reads the endpoints listed in `NOTIFY_WEBHOOKS`, separated by commas.
+    ///
+    /// Each endpoint is configured by variables prefixed with its name in upper case, dashes
+    /// replaced by underscores, e.g. for `ops-tool`: `NOTIFY_WEBHOOK_OPS_TOOL_URL`,
+    /// `NOTIFY_WEBHOOK_OPS_TOOL_SECRET` and optionally `NOTIFY_WEBHOOK_OPS_TOOL_TOPICS`, a comma
+    /// separated list of `signals`, `positions`, `equity`, `strategies`, `errors`, `summaries`
+    /// and `streams`.
+    ///
+    /// # Returns
+    ///
+    /// The endpoints in the order they are listed, none if `NOTIFY_WEBHOOKS` isn't set, or an
+    /// error if an endpoint is incomplete or invalid.
+    pub fn from_env() -> Result<Vec<Self>, String> {
+        Self::from_lookup(env_lookup)
+    }
+
+    /// Reads the URL, signing secret and topics of every listed endpoint through `lookup`, see
+    /// `from_env`.
+    pub fn from_lookup(lookup: impl Lookup) -> Result<Vec<Self>, String> {
+        let Some(names) = lookup("NOTIFY_WEBHOOKS") else {
+            return Ok(vec![]);
+        };
+
+        let mut endpoints: Vec<Self> = vec![];
+        for name in names
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+        {
+            if endpoints.iter().any(|endpoint| endpoint.name == name) {
+                return Err(format!("Webhook {name} is listed twice"));
+            }
+
+            let prefix = format!(
+                "NOTIFY_WEBHOOK_{}_",
+                name.to_uppercase()
+                    .replace(|c: char| !c.is_ascii_alphanumeric(), "_")
+            );
+            let required = |key: &str| {
+                lookup(&format!("{prefix}{key}"))
+                    .filter(|value| !value.is_empty())
+                    .ok_or_else(|| format!("Webhook {name} is missing {prefix}{key}"))
+            };
+
+            let url = required("URL")?;
+            if !url.starts_with("https://") {
+                return Err(format!("Webhook {name}: URL must use https"));
+            }
+            let secret = required("SECRET")?;
+
+            let topics = match lookup(&format!("{prefix}TOPICS")) {
+                Some(topics) => topics
+                    .split(',')
+                    .filter(|topic| !topic.trim().is_empty())
+                    .map(str::parse)
+                    .collect::<Result<Vec<EventTopic>, String>>()?,
+                None => DEFAULT_TOPICS.to_vec(),
+            };
+
+            endpoints.push(Self {
+                name: name.to_string(),
+                url,
+                secret,
+                topics,
+            });
+        }
+
+        Ok(endpoints)
+    }
+
+    /// Checks whether the endpoint receives the topic of an event.
+    pub fn receives(&self, event: &BotEvent) -> bool {
+        self.topics.contains(&event.topic())
+    }
+}
+
+/// Posts bot events to webhook endpoints.
+pub struct WebhookNotifier {
+    endpoints: Vec<WebhookEndpoint>,
+    client: Client,
+}
+
+impl WebhookNotifier {
+    pub fn new(endpoints: Vec<WebhookEndpoint>) -> Self {
+        let client = Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+
+        Self { endpoints, client }
+    }
+
+    /// Posts the events received until the publisher is dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `events` - A subscription to the bot events.
+    ///
+    /// # Returns
+    ///
+    /// The handle of the task posting the events.
+    pub fn spawn(self, mut events: Receiver<BotEvent>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(event) => self.notify(&event),
+                    Err(RecvError::Lagged(missed)) => {
+                        warn!("Webhook notifications missed {missed} events")
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+
+    /// Delivers an event to every endpoint receiving its topic. Each delivery is retried in a
+    /// task of its own, so a failing endpoint delays neither other endpoints nor later events.
+    pub fn notify(&self, event: &BotEvent) {
+        let endpoints: Vec<&WebhookEndpoint> = self
+            .endpoints
+            .iter()
+            .filter(|endpoint| endpoint.receives(event))
+            .collect();
+        if endpoints.is_empty() {
+            return;
+        }
+
+        let delivery_id = Uuid::new_v4();
+        let body = json!({
+            "id": delivery_id,
+            "timestamp": generate_ts(),
+            "topic": event.topic(),
+            "event": event,
+        })
+        .to_string();
+
+        for endpoint in endpoints {
+            let client = self.client.clone();
+            let endpoint = endpoint.clone();
+            let body = body.clone();
+            tokio::spawn(async move { deliver(client, endpoint, delivery_id, body).await });
+        }
+    }
+}
+
+/// Signs a request body as described in the module documentation.
+///
+/// # Returns
+///
+/// The value of the `X-Raderbot-Signature` header.
+pub fn sign_payload(secret: &str, timestamp: u64, body: &str) -> String {
+    format!(
+        "sha256={}",
+        sign_hmac(secret, &format!("{timestamp}.{body}"))
+    )
+}
+
+// ---
+// Private Methods
+// ---
+
+/// Posts a body to an endpoint, retrying network errors, rate limits and server errors.
+async fn deliver(client: Client, endpoint: WebhookEndpoint, delivery_id: Uuid, body: String) {
+    let mut backoff = RETRY_BACKOFF;
+    for attempt in 1..=MAX_ATTEMPTS {
+        let timestamp = generate_ts();
+        let result = client
+            .post(&endpoint.url)
+            .header("Content-Type", "application/json")
+            .header("X-Raderbot-Delivery", delivery_id.to_string())
+            .header("X-Raderbot-Timestamp", timestamp.to_string())
+            .header(
+                "X-Raderbot-Signature",
+                sign_payload(&endpoint.secret, timestamp, &body),
+            )
+            .body(body.clone())
+            .send()
+            .await;
+
+        let error = match result {
+            Ok(response) if response.status().is_success() => return,
+            Ok(response)
+                if response.status().is_client_error() && response.status().as_u16() != 429 =>
+            {
+                info!(
+                    "Webhook {} refused delivery {delivery_id}, status: {}",
+                    endpoint.name,
+                    response.status()
+                );
+                return;
+            }
+            Ok(response) => format!("status: {}", response.status()),
+            Err(e) => format!("e: {e}"),
+        };
+
+        if attempt == MAX_ATTEMPTS {
+            warn!(
+                "Unable to deliver {delivery_id} to webhook {} after {MAX_ATTEMPTS} attempts, {error}",
+                endpoint.name
+            );
+            return;
+        }
+        tokio::time::sleep(backoff).await;
+        backoff *= 2;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::env::vars_lookup;
+
+    fn endpoints(vars: &[(&str, &str)]) -> Result<Vec<WebhookEndpoint>, String> {
+        WebhookEndpoint::from_lookup(vars_lookup(vars))
+    }
+
+    /// Tests endpoints are read by name with their secret and payloads are signed with it.
+    #[test]
+    fn test_webhook_endpoints() {
+        assert!(endpoints(&[]).unwrap().is_empty());
+
+        let loaded = endpoints(&[
+            ("NOTIFY_WEBHOOKS", "ops-tool,pager"),
+            (
+                "NOTIFY_WEBHOOK_OPS_TOOL_URL",
+                "https://ops.example.com/raderbot",
+            ),
+            ("NOTIFY_WEBHOOK_OPS_TOOL_SECRET", "s3cret"),
+            ("NOTIFY_WEBHOOK_PAGER_URL", "https://pager.example.com/hook"),
+            ("NOTIFY_WEBHOOK_PAGER_SECRET", "other"),
+            ("NOTIFY_WEBHOOK_PAGER_TOPICS", "errors"),
+        ])
+        .unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded[0].name, "ops-tool");
+        assert_eq!(loaded[0].topics, DEFAULT_TOPICS.to_vec());
+        assert_eq!(loaded[1].topics, vec![EventTopic::Errors]);
+
+        let error = BotEvent::Error {
+            source: "live".to_string(),
+            message: "Unable to open position".to_string(),
+        };
+        assert!(loaded[0].receives(&error) && loaded[1].receives(&error));
+
+        let signature = sign_payload("s3cret", 1_700_000_000_000, "{}");
+        assert_eq!(
+            signature,
+            format!("sha256={}", sign_hmac("s3cret", "1700000000000.{}"))
+        );
+        assert_ne!(signature, sign_payload("other", 1_700_000_000_000, "{}"));
+
+        assert!(endpoints(&[
+            ("NOTIFY_WEBHOOKS", "ops"),
+            ("NOTIFY_WEBHOOK_OPS_URL", "https://ops.example.com"),
+        ])
+        .is_err());
+        assert!(endpoints(&[
+            ("NOTIFY_WEBHOOKS", "ops"),
+            ("NOTIFY_WEBHOOK_OPS_URL", "http://ops.example.com"),
+            ("NOTIFY_WEBHOOK_OPS_SECRET", "s3cret"),
+        ])
+        .is_err());
+    }
+}
@@ -0,0 +1,557 @@
+//! # Job Scheduler
+//!
+//! Runs maintenance and trading jobs at fixed times given by cron expressions, such as a daily
+//! report at midnight, a nightly kline compaction or starting a strategy at the open of a
+//! session. Jobs are read from the JSON file set in `SCHEDULE_FILE` and managed through the admin
+//! API, which writes changes back to the file.
+//!
+//! Cron expressions have the five fields `minute hour day-of-month month day-of-week`, evaluated
+//! in UTC. Fields take `*`, values, ranges such as `1-5`, steps such as `*/15` or `0-30/10` and
+//! comma separated lists of them, `@hourly`, `@daily`, `@weekly` and `@monthly` are shorthands.
+//! Like cron, a job restricting both day fields runs on days matching either of them.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use chrono::Datelike;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+
+use crate::bot::RaderBot;
+use crate::health;
+use crate::market::types::ArcMutex;
+use crate::strategy::strategy::StrategyDefinition;
+use crate::utils::env::{env_lookup, Lookup};
+use crate::utils::time::{
+    floor_mili_ts, generate_ts, timestamp_to_datetime, DAY_AS_MILI, HOUR_AS_MILI, MIN_AS_MILI,
+};
+
+/// Days searched for the next run of a schedule, long enough to reach the next February 29th.
+const MAX_SEARCH_DAYS: u64 = 8 * 366;
+
+/// A cron expression, see the module documentation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct CronSchedule {
+    expression: String,
+    /// Bit masks of the values matched by each field.
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// Whether the day of month field is `*`.
+    any_day: bool,
+    /// Whether the day of week field is `*`.
+    any_weekday: bool,
+}
+
+impl CronSchedule {
+    /// Finds the first minute matching the schedule after a timestamp.
+    ///
+    /// # Returns
+    ///
+    /// The timestamp of the minute in milliseconds, or `None` if the schedule never matches,
+    /// e.g. on February 30th.
+    pub fn next_after(&self, ts: u64) -> Option<u64> {
+        let first = floor_mili_ts(ts, MIN_AS_MILI) + MIN_AS_MILI;
+        let first_day = floor_mili_ts(first, DAY_AS_MILI);
+
+        for day in 0..MAX_SEARCH_DAYS {
+            let day_ts = first_day + day * DAY_AS_MILI;
+            if !self.matches_day(day_ts) {
+                continue;
+            }
+
+            for hour in (0..24).filter(|hour| self.hours & (1 << hour) != 0) {
+                for minute in (0..60).filter(|minute| self.minutes & (1 << minute) != 0) {
+                    let ts = day_ts + hour * HOUR_AS_MILI + minute * MIN_AS_MILI;
+                    if ts >= first {
+                        return Some(ts);
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    // ---
+    // Private Methods
+    // ---
+
+    fn matches_day(&self, day_ts: u64) -> bool {
+        let date = timestamp_to_datetime(day_ts);
+        if self.months & (1 << date.month()) == 0 {
+            return false;
+        }
+
+        let day = self.days & (1 << date.day()) != 0;
+        let weekday = self.weekdays & (1 << date.weekday().num_days_from_sunday()) != 0;
+        if self.any_day || self.any_weekday {
+            day && weekday
+        } else {
+            day || weekday
+        }
+    }
+}
+
+impl FromStr for CronSchedule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let expression = s.trim();
+        let expanded = match expression {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            _ => expression,
+        };
+
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!("Cron expression {expression} must have 5 fields"));
+        };
+        let invalid = |e: String| format!("Invalid cron expression {expression}, {e}");
+
+        // Sunday is both 0 and 7
+        let mut weekdays = parse_field(weekday, 0, 7).map_err(invalid)?;
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays | 1) & !(1 << 7);
+        }
+
+        Ok(Self {
+            expression: expression.to_string(),
+            minutes: parse_field(minute, 0, 59).map_err(invalid)?,
+            hours: parse_field(hour, 0, 23).map_err(invalid)?,
+            days: parse_field(day, 1, 31).map_err(invalid)?,
+            months: parse_field(month, 1, 12).map_err(invalid)?,
+            weekdays,
+            any_day: day == "*",
+            any_weekday: weekday == "*",
+        })
+    }
+}
+
+impl TryFrom<String> for CronSchedule {
+    type Error = String;
+
+    fn try_from(expression: String) -> Result<Self, Self::Error> {
+        expression.parse()
+    }
+}
+
+impl From<CronSchedule> for String {
+    fn from(schedule: CronSchedule) -> Self {
+        schedule.expression
+    }
+}
+
+/// The work a scheduled job does.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum JobKind {
+    /// Compacts the stored klines, see `StorageManager::compact_klines`.
+    CompactKlines,
+    /// Removes market data older than the retention configured in the environment.
+    ApplyRetention,
+    /// Publishes the trading summary of every account over the past day.
+    DailySummary,
+    /// Saves and publishes the report of every strategy over the past day, see `DailyReport`.
+    DailyReport,
+    /// Fetches the balance of the exchange account, refreshing the cached balance.
+    SyncBalance,
+    /// Creates a backup archive of the stored data.
+    Backup,
+    /// Writes the market data held in memory to storage.
+    FlushMarketData,
+    /// Checkpoints the bot state, see `snapshot::BotSnapshot`.
+    Snapshot,
+    /// Starts a strategy.
+    StartStrategy(Box<StrategyDefinition>),
+    /// Stops the running strategies of an algorithm and/or symbol, all of them if neither is
+    /// given.
+    StopStrategies {
+        strategy_name: Option<String>,
+        symbol: Option<String>,
+        #[serde(default)]
+        close_positions: bool,
+    },
+}
+
+/// A job run on a schedule.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ScheduledJob {
+    pub name: String,
+    pub cron: CronSchedule,
+    pub job: JobKind,
+}
+
+/// The outcome of a run of a job.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct JobRun {
+    pub started_at: u64,
+    pub finished_at: u64,
+    pub success: bool,
+    /// What the job did, or why it failed.
+    pub message: String,
+}
+
+/// A scheduled job with its next and last run.
+#[derive(Serialize, Debug, Clone)]
+pub struct ScheduledJobInfo {
+    #[serde(flatten)]
+    pub job: ScheduledJob,
+    /// `None` if the schedule never matches.
+    pub next_run: Option<u64>,
+    pub last_run: Option<JobRun>,
+}
+
+/// The scheduled jobs of the bot with the outcome of their last runs.
+#[derive(Debug, Default)]
+pub struct JobScheduler {
+    jobs: Vec<ScheduledJob>,
+    last_runs: HashMap<String, JobRun>,
+    /// The file the jobs are read from and changes are written to.
+    path: Option<PathBuf>,
+}
+
+impl JobScheduler {
+    /// Reads the jobs from the JSON file set in `SCHEDULE_FILE`, a list of objects with a
+    /// `name`, a `cron` expression and the `job`, e.g.
+    /// `{"name": "nightly-compaction", "cron": "30 2 * * *", "job": {"kind": "compact_klines"}}`.
+    /// A file which doesn't exist yet is created once a job is added through the API.
+    ///
+    /// # Returns
+    ///
+    /// The scheduler, with a daily report at midnight if `SCHEDULE_FILE` isn't set, or an error
+    /// if the file can't be read or a job is invalid.
+    pub fn from_env() -> Result<Self, String> {
+        Self::from_lookup(env_lookup)
+    }
+
+    /// Reads the jobs from the file set in `SCHEDULE_FILE` through `lookup`, see `from_env`.
+    pub fn from_lookup(lookup: impl Lookup) -> Result<Self, String> {
+        let Some(path) = lookup("SCHEDULE_FILE") else {
+            return Ok(Self {
+                jobs: Self::default_jobs(),
+                ..Default::default()
+            });
+        };
+
+        let path = PathBuf::from(path);
+        let jobs = match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|e| format!("Unable to parse {}, e: {e}", path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => vec![],
+            Err(e) => return Err(format!("Unable to read {}, e: {e}", path.display())),
+        };
+
+        Self::from_jobs(jobs, Some(path))
+    }
+
+    /// Creates a scheduler of jobs, refusing duplicate names and schedules which never match.
+    ///
+    /// # Arguments
+    ///
+    /// * `jobs` - The scheduled jobs.
+    /// * `path` - The file changes to the jobs are written to, if any.
+    pub fn from_jobs(jobs: Vec<ScheduledJob>, path: Option<PathBuf>) -> Result<Self, String> {
+        let mut scheduler = Self {
+            path,
+            ..Default::default()
+        };
+        for job in jobs {
+            Self::validate(&job)?;
+            if scheduler.get(&job.name).is_some() {
+                return Err(format!("Job {} is scheduled twice", job.name));
+            }
+            scheduler.jobs.push(job);
+        }
+
+        Ok(scheduler)
+    }
+
+    /// The jobs scheduled while no schedule file is configured.
+    pub fn default_jobs() -> Vec<ScheduledJob> {
+        vec![
+            ScheduledJob {
+                name: "daily-report".to_string(),
+                cron: CronSchedule::from_str("@daily").expect("valid cron expression"),
+                job: JobKind::DailyReport,
+            },
+            ScheduledJob {
+                name: "snapshot".to_string(),
+                cron: CronSchedule::from_str("@hourly").expect("valid cron expression"),
+                job: JobKind::Snapshot,
+            },
+        ]
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ScheduledJob> {
+        self.jobs.iter().find(|job| job.name == name)
+    }
+
+    /// Lists the jobs with their next run after a timestamp and their last run.
+    pub fn list(&self, now: u64) -> Vec<ScheduledJobInfo> {
+        self.jobs
+            .iter()
+            .map(|job| ScheduledJobInfo {
+                job: job.clone(),
+                next_run: job.cron.next_after(now),
+                last_run: self.last_runs.get(&job.name).cloned(),
+            })
+            .collect()
+    }
+
+    /// Schedules a job, replacing a job of the same name, and writes the jobs to the schedule
+    /// file.
+    pub fn add(&mut self, job: ScheduledJob) -> Result<(), String> {
+        Self::validate(&job)?;
+
+        match self.jobs.iter_mut().find(|other| other.name == job.name) {
+            Some(other) => *other = job,
+            None => self.jobs.push(job),
+        }
+
+        self.save()
+    }
+
+    /// Removes a job and writes the remaining jobs to the schedule file.
+    ///
+    /// # Returns
+    ///
+    /// Whether the job was scheduled, or an error if the file can't be written.
+    pub fn remove(&mut self, name: &str) -> Result<bool, String> {
+        let count = self.jobs.len();
+        self.jobs.retain(|job| job.name != name);
+        if self.jobs.len() == count {
+            return Ok(false);
+        }
+        self.last_runs.remove(name);
+
+        self.save().map(|_| true)
+    }
+
+    /// Finds the jobs due to run between two timestamps.
+    ///
+    /// # Arguments
+    ///
+    /// * `from_ts` - The end of the previous check, jobs due at it already ran.
+    /// * `to_ts` - The current time.
+    pub fn due(&self, from_ts: u64, to_ts: u64) -> Vec<ScheduledJob> {
+        self.jobs
+            .iter()
+            .filter(|job| {
+                job.cron
+                    .next_after(from_ts)
+                    .is_some_and(|next| next <= to_ts)
+            })
+            .cloned()
+            .collect()
+    }
+
+    pub fn record_run(&mut self, name: &str, run: JobRun) {
+        self.last_runs.insert(name.to_string(), run);
+    }
+
+    // ---
+    // Private Methods
+    // ---
+
+    fn validate(job: &ScheduledJob) -> Result<(), String> {
+        if job.name.trim().is_empty() {
+            return Err("Job name can't be empty".to_string());
+        }
+        if job.cron.next_after(generate_ts()).is_none() {
+            return Err(format!("Job {} is never due", job.name));
+        }
+
+        Ok(())
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let content = serde_json::to_string_pretty(&self.jobs).map_err(|e| e.to_string())?;
+        std::fs::write(path, content)
+            .map_err(|e| format!("Unable to write {}, e: {e}", path.display()))
+    }
+}
+
+/// Runs the scheduled jobs of a bot when they are due, checking right after every minute.
+///
+/// # Returns
+///
+/// The handle of the task running the jobs.
+pub fn spawn_scheduler(bot: ArcMutex<RaderBot>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let interval = Duration::from_millis(MIN_AS_MILI);
+        health::runtime().register_task("scheduler", Some(interval));
+
+        let mut checked_to = generate_ts();
+        loop {
+            let wait = MIN_AS_MILI - generate_ts() % MIN_AS_MILI;
+            tokio::time::sleep(Duration::from_millis(wait)).await;
+            let _iteration = health::time_iteration("scheduler");
+
+            let now = generate_ts();
+            let mut bot = bot.lock().await;
+            let due = bot.scheduler().due(checked_to, now);
+            // jobs due while these run are picked up by the next check
+            checked_to = now;
+
+            for job in due {
+                match bot.run_scheduled_job(&job.name).await {
+                    Some(run) if run.success => info!("Ran job {}: {}", job.name, run.message),
+                    Some(run) => warn!("Job {} failed: {}", job.name, run.message),
+                    None => {}
+                }
+            }
+        }
+    })
+}
+
+// ---
+// Private Methods
+// ---
+
+/// Parses a field of a cron expression into a bit mask of the values it matches.
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut mask = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step = step
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .ok_or_else(|| format!("invalid step in {part}"))?;
+                (range, step)
+            }
+            None => (part, 1),
+        };
+
+        let parse = |value: &str| {
+            value
+                .parse::<u32>()
+                .map_err(|_| format!("invalid value {value}"))
+        };
+        let (from, to) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((from, to)) => (parse(from)?, parse(to)?),
+            // a step from a single value runs up to the end of the field, like 5/15
+            None if part.contains('/') => (parse(range)?, max),
+            None => (parse(range)?, parse(range)?),
+        };
+        if from < min || to > max || from > to {
+            return Err(format!("{part} is out of range {min}-{max}"));
+        }
+
+        for value in (from..=to).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+
+    Ok(mask)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::time::year_month_day_to_ts;
+
+    /// Tests cron expressions are parsed and their next runs are found.
+    #[test]
+    fn test_cron_schedule() {
+        // Monday, January 1st 2024
+        let monday = year_month_day_to_ts(2024, 1, 1).unwrap();
+
+        let daily: CronSchedule = "@daily".parse().unwrap();
+        assert_eq!(daily.next_after(monday), Some(monday + DAY_AS_MILI));
+
+        let quarter: CronSchedule = "*/15 9-17 * * 1-5".parse().unwrap();
+        assert_eq!(quarter.next_after(monday), Some(monday + 9 * HOUR_AS_MILI));
+        assert_eq!(
+            quarter.next_after(monday + 9 * HOUR_AS_MILI),
+            Some(monday + 9 * HOUR_AS_MILI + 15 * MIN_AS_MILI)
+        );
+        // Friday evening runs on Monday morning
+        let friday_evening = monday + 4 * DAY_AS_MILI + 18 * HOUR_AS_MILI;
+        assert_eq!(
+            quarter.next_after(friday_evening),
+            Some(monday + 7 * DAY_AS_MILI + 9 * HOUR_AS_MILI)
+        );
+
+        // Sunday as 7, or the 15th
+        let either: CronSchedule = "30 2 15 * 7".parse().unwrap();
+        assert_eq!(
+            either.next_after(monday),
+            Some(monday + 6 * DAY_AS_MILI + 2 * HOUR_AS_MILI + 30 * MIN_AS_MILI)
+        );
+
+        let leap: CronSchedule = "0 0 29 2 *".parse().unwrap();
+        assert_eq!(leap.next_after(monday), year_month_day_to_ts(2024, 2, 29));
+        let never: CronSchedule = "0 0 30 2 *".parse().unwrap();
+        assert_eq!(never.next_after(monday), None);
+
+        assert!("* * * *".parse::<CronSchedule>().is_err());
+        assert!("60 * * * *".parse::<CronSchedule>().is_err());
+        assert!("*/0 * * * *".parse::<CronSchedule>().is_err());
+        assert!("5-1 * * * *".parse::<CronSchedule>().is_err());
+    }
+
+    /// Tests jobs are read from JSON, found due once per run and refused when invalid.
+    #[test]
+    fn test_job_scheduler() {
+        let jobs: Vec<ScheduledJob> = serde_json::from_str(
+            r#"[
+                {"name": "compaction", "cron": "30 2 * * *", "job": {"kind": "compact_klines"}},
+                {"name": "close", "cron": "0 21 * * 5", "job": {
+                    "kind": "stop_strategies", "symbol": "BTCUSDT", "close_positions": true
+                }}
+            ]"#,
+        )
+        .unwrap();
+        let mut scheduler = JobScheduler::from_jobs(jobs, None).unwrap();
+
+        let monday = year_month_day_to_ts(2024, 1, 1).unwrap();
+        let compaction_at = monday + 2 * HOUR_AS_MILI + 30 * MIN_AS_MILI;
+        let due = scheduler.due(monday, compaction_at + 5_000);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].name, "compaction");
+        assert!(scheduler
+            .due(compaction_at + 5_000, monday + DAY_AS_MILI)
+            .is_empty());
+
+        let info = scheduler.list(monday);
+        assert_eq!(
+            info[1].next_run,
+            Some(monday + 4 * DAY_AS_MILI + 21 * HOUR_AS_MILI)
+        );
+        let json = serde_json::to_value(&info[1]).unwrap();
+        assert_eq!(json["cron"], "0 21 * * 5");
+        assert_eq!(json["job"]["kind"], "stop_strategies");
+
+        let never = ScheduledJob {
+            name: "never".to_string(),
+            cron: "0 0 30 2 *".parse().unwrap(),
+            job: JobKind::Backup,
+        };
+        assert!(scheduler.add(never).is_err());
+        assert!(scheduler.remove("compaction").unwrap());
+        assert!(!scheduler.remove("compaction").unwrap());
+
+        let twice = JobScheduler::default_jobs()
+            .into_iter()
+            .chain(JobScheduler::default_jobs())
+            .collect();
+        assert!(JobScheduler::from_jobs(twice, None).is_err());
+    }
+}
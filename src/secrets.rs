@@ -0,0 +1,464 @@
+//! # Secrets
+//!
+//! Exchange API keys and storage credentials are read at runtime from the provider chosen by
+//! `SECRETS_PROVIDER`, so keys are never compiled into the binary and can be rotated by
+//! restarting the bot:
+//!
+//! - `env`, the default: environment variables, including those of the `.env` file.
+//! - `file`: one file per secret in `SECRETS_DIR`, named after the secret, as mounted by Docker
+//!   and Kubernetes secrets.
+//! - `vault`: a HashiCorp Vault KV v2 secret at `VAULT_SECRET_PATH`, e.g.
+//!   `secret/data/raderbot`, read from `VAULT_ADDR` with `VAULT_TOKEN`.
+//! - `aws`: an AWS Secrets Manager secret `AWS_SECRET_ID` holding a JSON object, read in
+//!   `AWS_REGION` with the `AWS_ACCESS_KEY_ID` and `AWS_SECRET_ACCESS_KEY` credentials.
+//!
+//! Secrets keep the names of their variables, e.g. `BINGX_API_KEY` or
+//! `PROFILE_BINGX_SUB1_SECRET_KEY`, whichever the provider.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use log::warn;
+use reqwest::Client;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use tokio::sync::OnceCell;
+
+use crate::utils::env::{env_lookup, Lookup};
+
+/// Reads secrets by name.
+#[async_trait]
+pub trait SecretsProvider: Send + Sync + Debug {
+    /// The name of the provider, as set in `SECRETS_PROVIDER`.
+    fn name(&self) -> &str;
+
+    /// Reads a secret.
+    ///
+    /// # Returns
+    ///
+    /// The secret, `None` if the provider has no secret of the name, or an error if the
+    /// provider can't be reached.
+    async fn get(&self, key: &str) -> Result<Option<String>, String>;
+}
+
+/// Creates the provider chosen by `SECRETS_PROVIDER`, see the module documentation.
+///
+/// # Returns
+///
+/// The provider, or an error if it is unknown or its settings are missing.
+pub fn provider_from_env() -> Result<Box<dyn SecretsProvider>, String> {
+    provider_from_lookup(env_lookup)
+}
+
+/// Creates the provider named in `SECRETS_PROVIDER` with the settings it requires, read through
+/// `lookup`, see `provider_from_env`.
+pub fn provider_from_lookup(lookup: impl Lookup) -> Result<Box<dyn SecretsProvider>, String> {
+    let required = |key: &str| {
+        lookup(key)
+            .filter(|value| !value.is_empty())
+            .ok_or_else(|| format!("{key} is required by the secrets provider"))
+    };
+
+    let provider = lookup("SECRETS_PROVIDER").unwrap_or_default();
+    match provider.trim().to_lowercase().as_str() {
+        "" | "env" => Ok(Box::new(EnvSecrets)),
+        "file" => Ok(Box::new(FileSecrets::new(PathBuf::from(required(
+            "SECRETS_DIR",
+        )?)))),
+        "vault" => Ok(Box::new(VaultSecrets::new(
+            &required("VAULT_ADDR")?,
+            &required("VAULT_TOKEN")?,
+            &required("VAULT_SECRET_PATH")?,
+        ))),
+        "aws" => Ok(Box::new(AwsSecrets {
+            region: required("AWS_REGION")?,
+            secret_id: required("AWS_SECRET_ID")?,
+            access_key_id: required("AWS_ACCESS_KEY_ID")?,
+            secret_access_key: required("AWS_SECRET_ACCESS_KEY")?,
+            session_token: lookup("AWS_SESSION_TOKEN").filter(|token| !token.is_empty()),
+            client: Client::new(),
+            secrets: OnceCell::new(),
+        })),
+        _ => Err(format!("Unknown secrets provider: {provider}")),
+    }
+}
+
+/// Reads secrets from environment variables.
+#[derive(Debug, Clone, Default)]
+pub struct EnvSecrets;
+
+#[async_trait]
+impl SecretsProvider for EnvSecrets {
+    fn name(&self) -> &str {
+        "env"
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<String>, String> {
+        Ok(env_lookup(key))
+    }
+}
+
+/// Reads secrets from the files of a directory, one file per secret.
+#[derive(Debug, Clone)]
+pub struct FileSecrets {
+    directory: PathBuf,
+}
+
+impl FileSecrets {
+    pub fn new(directory: PathBuf) -> Self {
+        Self { directory }
+    }
+}
+
+#[async_trait]
+impl SecretsProvider for FileSecrets {
+    fn name(&self) -> &str {
+        "file"
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<String>, String> {
+        // keys are variable names, they must not point outside the directory
+        if key.contains(['/', '\\']) || key.starts_with('.') {
+            return Err(format!("Invalid secret name {key}"));
+        }
+
+        let path = self.directory.join(key);
+        match tokio::fs::read_to_string(&path).await {
+            Ok(secret) => Ok(Some(secret.trim_end_matches(['\r', '\n']).to_string())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!("Unable to read {}, e: {e}", path.display())),
+        }
+    }
+}
+
+/// Reads secrets from a HashiCorp Vault KV v2 secret. The secret is fetched once and its
+/// values are kept for the lifetime of the provider.
+pub struct VaultSecrets {
+    url: String,
+    token: String,
+    client: Client,
+    secrets: OnceCell<HashMap<String, String>>,
+}
+
+impl Debug for VaultSecrets {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VaultSecrets")
+            .field("url", &self.url)
+            .finish_non_exhaustive()
+    }
+}
+
+impl VaultSecrets {
+    /// # Arguments
+    ///
+    /// * `addr` - The address of Vault, e.g. `https://vault.example.com:8200`.
+    /// * `token` - The token the secret is read with.
+    /// * `path` - The API path of the secret, e.g. `secret/data/raderbot`.
+    pub fn new(addr: &str, token: &str, path: &str) -> Self {
+        Self {
+            url: format!(
+                "{}/v1/{}",
+                addr.trim_end_matches('/'),
+                path.trim_start_matches('/')
+            ),
+            token: token.to_string(),
+            client: Client::new(),
+            secrets: OnceCell::new(),
+        }
+    }
+
+    async fn fetch(&self) -> Result<HashMap<String, String>, String> {
+        let response = self
+            .client
+            .get(&self.url)
+            .header("X-Vault-Token", &self.token)
+            .send()
+            .await
+            .map_err(|e| format!("Unable to reach Vault, e: {e}"))?;
+        if !response.status().is_success() {
+            return Err(format!(
+                "Vault refused to read {}, status: {}",
+                self.url,
+                response.status()
+            ));
+        }
+
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|e| format!("Invalid Vault response, e: {e}"))?;
+        parse_secret_map(&body["data"]["data"])
+    }
+}
+
+#[async_trait]
+impl SecretsProvider for VaultSecrets {
+    fn name(&self) -> &str {
+        "vault"
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<String>, String> {
+        let secrets = self.secrets.get_or_try_init(|| self.fetch()).await?;
+        Ok(secrets.get(key).cloned())
+    }
+}
+
+/// Reads secrets from an AWS Secrets Manager secret holding a JSON object. The secret is
+/// fetched once and its values are kept for the lifetime of the provider.
+pub struct AwsSecrets {
+    region: String,
+    secret_id: String,
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: Option<String>,
+    client: Client,
+    secrets: OnceCell<HashMap<String, String>>,
+}
+
+impl Debug for AwsSecrets {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AwsSecrets")
+            .field("region", &self.region)
+            .field("secret_id", &self.secret_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl AwsSecrets {
+    async fn fetch(&self) -> Result<HashMap<String, String>, String> {
+        let host = format!("secretsmanager.{}.amazonaws.com", self.region);
+        let body = json!({ "SecretId": self.secret_id }).to_string();
+        let amz_date = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+
+        let mut headers = vec![
+            ("content-type", "application/x-amz-json-1.1".to_string()),
+            ("host", host.clone()),
+            ("x-amz-date", amz_date.clone()),
+            ("x-amz-target", "secretsmanager.GetSecretValue".to_string()),
+        ];
+        if let Some(token) = &self.session_token {
+            headers.push(("x-amz-security-token", token.clone()));
+        }
+        headers.sort();
+        let authorization = sign_aws_request(
+            &self.access_key_id,
+            &self.secret_access_key,
+            &self.region,
+            "secretsmanager",
+            &headers,
+            &body,
+            &amz_date,
+        );
+
+        let mut request = self
+            .client
+            .post(format!("https://{host}/"))
+            .header("Authorization", authorization)
+            .body(body);
+        for (name, value) in headers.iter().filter(|(name, _)| *name != "host") {
+            request = request.header(*name, value);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("Unable to reach AWS Secrets Manager, e: {e}"))?;
+        if !response.status().is_success() {
+            return Err(format!(
+                "AWS Secrets Manager refused to read {}, status: {}",
+                self.secret_id,
+                response.status()
+            ));
+        }
+
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|e| format!("Invalid AWS Secrets Manager response, e: {e}"))?;
+        let secret: Value = body["SecretString"]
+            .as_str()
+            .ok_or_else(|| format!("Secret {} has no SecretString", self.secret_id))
+            .and_then(|secret| {
+                serde_json::from_str(secret)
+                    .map_err(|e| format!("Secret {} isn't a JSON object, e: {e}", self.secret_id))
+            })?;
+        parse_secret_map(&secret)
+    }
+}
+
+#[async_trait]
+impl SecretsProvider for AwsSecrets {
+    fn name(&self) -> &str {
+        "aws"
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<String>, String> {
+        let secrets = self.secrets.get_or_try_init(|| self.fetch()).await?;
+        Ok(secrets.get(key).cloned())
+    }
+}
+
+/// Reads a secret, an empty string if the provider has none or fails, which is logged.
+pub async fn read_secret(provider: &dyn SecretsProvider, key: &str) -> String {
+    match provider.get(key).await {
+        Ok(Some(secret)) => secret,
+        Ok(None) => {
+            warn!("Secret {key} isn't set in the {} provider", provider.name());
+            String::new()
+        }
+        Err(e) => {
+            warn!("Unable to read secret {key}, e: {e}");
+            String::new()
+        }
+    }
+}
+
+// ---
+// Private Methods
+// ---
+
+/// Reads the string values of a JSON object, other values are skipped.
+fn parse_secret_map(value: &Value) -> Result<HashMap<String, String>, String> {
+    let object = value
+        .as_object()
+        .ok_or_else(|| "Secret isn't a JSON object".to_string())?;
+
+    Ok(object
+        .iter()
+        .filter_map(|(key, value)| Some((key.clone(), value.as_str()?.to_string())))
+        .collect())
+}
+
+fn hmac_sha256(key: &[u8], message: &str) -> Vec<u8> {
+    let mut hmac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    hmac.update(message.as_bytes());
+    hmac.finalize().into_bytes().to_vec()
+}
+
+/// Signs a `POST /` request with AWS Signature Version 4.
+///
+/// # Arguments
+///
+/// * `headers` - The signed headers, lower case and sorted by name.
+///
+/// # Returns
+///
+/// The value of the `Authorization` header.
+fn sign_aws_request(
+    access_key_id: &str,
+    secret_access_key: &str,
+    region: &str,
+    service: &str,
+    headers: &[(&str, String)],
+    body: &str,
+    amz_date: &str,
+) -> String {
+    let date = &amz_date[..8];
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{name}:{}\n", value.trim()))
+        .collect();
+    let signed_headers = headers
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(";");
+    let canonical_request = format!(
+        "POST\n/\n\n{canonical_headers}\n{signed_headers}\n{}",
+        hex::encode(Sha256::digest(body.as_bytes()))
+    );
+
+    let scope = format!("{date}/{region}/{service}/aws4_request");
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let key = hmac_sha256(format!("AWS4{secret_access_key}").as_bytes(), date);
+    let key = hmac_sha256(&key, region);
+    let key = hmac_sha256(&key, service);
+    let key = hmac_sha256(&key, "aws4_request");
+    let signature = hex::encode(hmac_sha256(&key, &string_to_sign));
+
+    format!(
+        "AWS4-HMAC-SHA256 Credential={access_key_id}/{scope}, SignedHeaders={signed_headers}, Signature={signature}"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::env::vars_lookup;
+    use crate::utils::time::generate_ts;
+
+    /// Tests providers are chosen by name and file secrets are read without their trailing
+    /// newline.
+    #[tokio::test]
+    async fn test_secrets_providers() {
+        let error = provider_from_lookup(vars_lookup(&[
+            ("SECRETS_PROVIDER", "vault"),
+            ("VAULT_ADDR", "https://vault.example.com:8200/"),
+            ("VAULT_TOKEN", "token"),
+        ]))
+        .unwrap_err();
+        assert!(error.contains("VAULT_SECRET_PATH"));
+        assert_eq!(provider_from_lookup(|_| None).unwrap().name(), "env");
+        assert!(provider_from_lookup(|_| Some("keychain".to_string())).is_err());
+
+        let vault = VaultSecrets::new("https://vault.example.com:8200/", "t", "/secret/data/bot");
+        assert_eq!(
+            vault.url,
+            "https://vault.example.com:8200/v1/secret/data/bot"
+        );
+
+        let directory = std::env::temp_dir().join(format!("raderbot-secrets-{}", generate_ts()));
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join("BINGX_API_KEY"), "key\n").unwrap();
+        let secrets_dir = directory.display().to_string();
+        let files = provider_from_lookup(vars_lookup(&[
+            ("SECRETS_PROVIDER", "file"),
+            ("SECRETS_DIR", &secrets_dir),
+        ]))
+        .unwrap();
+        assert_eq!(
+            files.get("BINGX_API_KEY").await.unwrap(),
+            Some("key".to_string())
+        );
+        assert_eq!(files.get("BINGX_SECRET_KEY").await.unwrap(), None);
+        assert!(files.get("../BINGX_API_KEY").await.is_err());
+        std::fs::remove_dir_all(directory).unwrap();
+
+        let secrets = parse_secret_map(&json!({ "API_KEY": "key", "LIMIT": 3 })).unwrap();
+        assert_eq!(secrets.len(), 1);
+        assert!(parse_secret_map(&json!("key")).is_err());
+    }
+
+    /// Tests requests are signed as in the `post-vanilla` case of the AWS Signature Version 4
+    /// test suite.
+    #[test]
+    fn test_sign_aws_request() {
+        let headers = vec![
+            ("host", "example.amazonaws.com".to_string()),
+            ("x-amz-date", "20150830T123600Z".to_string()),
+        ];
+        let authorization = sign_aws_request(
+            "AKIDEXAMPLE",
+            "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+            "us-east-1",
+            "service",
+            &headers,
+            "",
+            "20150830T123600Z",
+        );
+
+        assert_eq!(
+            authorization,
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+             SignedHeaders=host;x-amz-date, \
+             Signature=5da7c1a2acd57cee7505fc6676e4e544621c30862966e37dddb68e92efbe5d6b"
+        );
+    }
+}
@@ -0,0 +1,193 @@
+//! # Server Configuration
+//!
+//! The address the API server binds to, the TLS certificate it serves and the origins allowed
+//! to call it cross-origin, read from the environment so the dashboard can be hosted apart
+//! from the bot.
+
+use std::io;
+
+use actix_cors::Cors;
+use openssl::ssl::{SslAcceptor, SslAcceptorBuilder, SslFiletype, SslMethod};
+
+use crate::utils::env::{env_lookup, Lookup};
+
+const DEFAULT_HOST: &str = "127.0.0.1";
+const DEFAULT_PORT: u16 = 3000;
+
+/// Seconds browsers may cache the result of a CORS preflight request.
+const CORS_MAX_AGE: usize = 3600;
+
+/// Certificate chain and private key files of a TLS listener, both PEM encoded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TlsConfig {
+    pub cert_file: String,
+    pub key_file: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerConfig {
+    pub host: String,
+    pub port: u16,
+    /// Serves HTTPS instead of HTTP when set.
+    pub tls: Option<TlsConfig>,
+    /// Origins allowed to call the API from a browser, `*` allows any. Cross-origin requests
+    /// are left to the browser's same-origin policy when empty.
+    pub cors_allowed_origins: Vec<String>,
+}
+
+impl ServerConfig {
+    /// Reads the configuration from `SERVER_HOST`, `SERVER_PORT`, `TLS_CERT_FILE`,
+    /// `TLS_KEY_FILE` and `CORS_ALLOWED_ORIGINS`, a comma separated list of origins.
+    pub fn from_env() -> Result<Self, String> {
+        Self::from_lookup(env_lookup)
+    }
+
+    /// Reads the binding, TLS files and allowed origins through `lookup`, see `from_env`.
+    pub fn from_lookup(lookup: impl Lookup) -> Result<Self, String> {
+        let host = lookup("SERVER_HOST").unwrap_or_else(|| DEFAULT_HOST.to_string());
+        let port = match lookup("SERVER_PORT") {
+            Some(port) => port
+                .trim()
+                .parse()
+                .map_err(|_| format!("Invalid SERVER_PORT: {port}"))?,
+            None => DEFAULT_PORT,
+        };
+
+        let tls = match (lookup("TLS_CERT_FILE"), lookup("TLS_KEY_FILE")) {
+            (Some(cert_file), Some(key_file)) => Some(TlsConfig {
+                cert_file,
+                key_file,
+            }),
+            (None, None) => None,
+            _ => return Err("TLS_CERT_FILE and TLS_KEY_FILE must be set together".to_string()),
+        };
+
+        let cors_allowed_origins = lookup("CORS_ALLOWED_ORIGINS")
+            .map(|origins| {
+                origins
+                    .split(',')
+                    .map(|origin| origin.trim().trim_end_matches('/').to_string())
+                    .filter(|origin| !origin.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(Self {
+            host,
+            port,
+            tls,
+            cors_allowed_origins,
+        })
+    }
+
+    pub fn address(&self) -> (&str, u16) {
+        (&self.host, self.port)
+    }
+
+    pub fn has_cors(&self) -> bool {
+        !self.cors_allowed_origins.is_empty()
+    }
+
+    /// Builds the CORS middleware allowing the configured origins.
+    pub fn cors(&self) -> Cors {
+        let mut cors = Cors::default()
+            .allowed_methods(vec!["GET", "POST", "DELETE", "OPTIONS"])
+            .allow_any_header()
+            .max_age(CORS_MAX_AGE);
+
+        for origin in &self.cors_allowed_origins {
+            cors = if origin == "*" {
+                cors.allow_any_origin()
+            } else {
+                cors.allowed_origin(origin)
+            };
+        }
+
+        cors
+    }
+
+    /// Loads the certificate and key of the TLS listener.
+    ///
+    /// # Returns
+    ///
+    /// The acceptor of TLS connections, `None` when TLS isn't configured, or an error if the
+    /// files can't be loaded.
+    pub fn tls_acceptor(&self) -> io::Result<Option<SslAcceptorBuilder>> {
+        let Some(tls) = &self.tls else {
+            return Ok(None);
+        };
+
+        let mut acceptor =
+            SslAcceptor::mozilla_intermediate(SslMethod::tls()).map_err(io::Error::other)?;
+        acceptor
+            .set_private_key_file(&tls.key_file, SslFiletype::PEM)
+            .map_err(|e| io::Error::other(format!("Unable to load {}, e: {e}", tls.key_file)))?;
+        acceptor
+            .set_certificate_chain_file(&tls.cert_file)
+            .map_err(|e| io::Error::other(format!("Unable to load {}, e: {e}", tls.cert_file)))?;
+
+        Ok(Some(acceptor))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::env::vars_lookup;
+    use actix_web::{
+        http::{header, Method},
+        test::{call_service, init_service, TestRequest},
+        App,
+    };
+
+    fn config(vars: &[(&str, &str)]) -> Result<ServerConfig, String> {
+        ServerConfig::from_lookup(vars_lookup(vars))
+    }
+
+    /// Tests the configuration defaults to localhost and parses origins and TLS files.
+    #[test]
+    fn test_server_config() {
+        let default = config(&[]).unwrap();
+        assert_eq!(default.address(), ("127.0.0.1", 3000));
+        assert!(default.tls.is_none());
+        assert!(!default.has_cors());
+
+        let custom = config(&[
+            ("SERVER_HOST", "0.0.0.0"),
+            ("SERVER_PORT", "8443"),
+            ("TLS_CERT_FILE", "cert.pem"),
+            ("TLS_KEY_FILE", "key.pem"),
+            (
+                "CORS_ALLOWED_ORIGINS",
+                "https://dash.example.com/, http://localhost:5173,",
+            ),
+        ])
+        .unwrap();
+        assert_eq!(custom.address(), ("0.0.0.0", 8443));
+        assert_eq!(custom.tls.unwrap().key_file, "key.pem");
+        assert_eq!(
+            custom.cors_allowed_origins,
+            vec!["https://dash.example.com", "http://localhost:5173"]
+        );
+
+        assert!(config(&[("SERVER_PORT", "http")]).is_err());
+        assert!(config(&[("TLS_CERT_FILE", "cert.pem")]).is_err());
+    }
+
+    /// Tests browsers may send every method the API serves from an allowed origin.
+    #[actix_web::test]
+    async fn test_cors_preflight() {
+        let config = config(&[("CORS_ALLOWED_ORIGINS", "https://dash.example.com")]).unwrap();
+        let app = init_service(App::new().wrap(config.cors())).await;
+
+        for method in ["GET", "POST", "DELETE"] {
+            let req = TestRequest::default()
+                .method(Method::OPTIONS)
+                .insert_header((header::ORIGIN, "https://dash.example.com"))
+                .insert_header((header::ACCESS_CONTROL_REQUEST_METHOD, method))
+                .to_request();
+            let res = call_service(&app, req).await;
+            assert!(res.status().is_success(), "{method}");
+        }
+    }
+}
@@ -0,0 +1,167 @@
+//! # Graceful Shutdown
+//!
+//! Shutting the bot down stops its strategies, keeps or closes their open positions as the
+//! `ShutdownPolicy` asks and writes the market data held in memory to storage before the
+//! process exits. A shutdown is requested through the API or by the OS, see `shutdown_signal`.
+//!
+//! Flattening is the panic button short of a shutdown: trading is paused, working orders are
+//! cancelled and every open position is closed at market while the bot keeps running.
+
+use std::io;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use tokio::signal::unix::{signal, SignalKind};
+
+use crate::account::money::{Price, Qty};
+use crate::account::trade::{OrderSide, PositionId, TradeTx};
+use crate::strategy::strategy::StrategyId;
+use crate::utils::env::{env_lookup, Lookup};
+
+/// What happens to open positions when the bot shuts down.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ShutdownPolicy {
+    /// Strategies are halted but stay active, so they resume managing their open positions once
+    /// the bot is restarted.
+    #[default]
+    KeepPositions,
+    /// Strategies are stopped and every open position is closed at the last price.
+    ClosePositions,
+}
+
+impl ShutdownPolicy {
+    /// Reads the policy of shutdowns requested by the OS from `SHUTDOWN_POLICY`, keeping
+    /// positions open if it isn't set.
+    pub fn from_env() -> Result<Self, String> {
+        Self::from_lookup(env_lookup)
+    }
+
+    /// Reads the policy from `SHUTDOWN_POLICY` through `lookup`, see `from_env`.
+    pub fn from_lookup(lookup: impl Lookup) -> Result<Self, String> {
+        match lookup("SHUTDOWN_POLICY") {
+            Some(policy) => policy.parse(),
+            None => Ok(Self::default()),
+        }
+    }
+}
+
+impl FromStr for ShutdownPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "keep_positions" => Ok(Self::KeepPositions),
+            "close_positions" => Ok(Self::ClosePositions),
+            _ => Err(format!("Unknown shutdown policy: {s}")),
+        }
+    }
+}
+
+/// The outcome of a shutdown.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct ShutdownReport {
+    pub policy: ShutdownPolicy,
+    /// Strategies stopped or halted.
+    pub stopped_strategies: usize,
+    pub closed_positions: usize,
+    /// Positions left open, either kept by the policy or lacking a price to close them at.
+    pub open_positions: usize,
+    /// Errors persisting state, such as market data that couldn't be written.
+    pub errors: Vec<String>,
+}
+
+/// A position closed by flattening.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct FlattenedPosition {
+    /// The account the position was held on, `live` or `shadow`.
+    pub account: String,
+    pub position_id: PositionId,
+    pub strategy_id: Option<StrategyId>,
+    pub symbol: String,
+    pub order_side: OrderSide,
+    pub quantity: Qty,
+    pub open_price: Price,
+    pub close_price: Price,
+    pub profit: f64,
+}
+
+impl FlattenedPosition {
+    pub fn new(account: &str, trade: &TradeTx) -> Self {
+        Self {
+            account: account.to_string(),
+            position_id: trade.position.id,
+            strategy_id: trade.position.strategy_id,
+            symbol: trade.position.symbol.clone(),
+            order_side: trade.position.order_side,
+            quantity: trade.position.quantity,
+            open_price: trade.position.open_price,
+            close_price: trade.close_price,
+            profit: trade.calc_profit(),
+        }
+    }
+}
+
+/// The outcome of flattening.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct FlattenReport {
+    /// Strategies left running with trading paused.
+    pub paused_strategies: Vec<StrategyId>,
+    /// Symbols whose working orders were cancelled, by account.
+    pub cancelled_orders: Vec<(String, String)>,
+    pub closed_positions: Vec<FlattenedPosition>,
+    /// Positions left open, lacking a price or rejected by the exchange.
+    pub open_positions: usize,
+    /// Orders or positions that couldn't be cancelled or closed.
+    pub errors: Vec<String>,
+}
+
+/// Waits for the OS to ask the process to stop.
+///
+/// # Returns
+///
+/// The name of the received signal, `SIGTERM` or `SIGINT`, or an error if the signals can't
+/// be listened to.
+pub async fn shutdown_signal() -> io::Result<&'static str> {
+    let mut terminate = signal(SignalKind::terminate())?;
+
+    tokio::select! {
+        _ = terminate.recv() => Ok("SIGTERM"),
+        interrupt = tokio::signal::ctrl_c() => interrupt.map(|_| "SIGINT"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::env::vars_lookup;
+
+    /// Tests policies parse from their snake case names.
+    #[test]
+    fn test_shutdown_policy() {
+        assert_eq!(
+            "keep_positions".parse::<ShutdownPolicy>(),
+            Ok(ShutdownPolicy::KeepPositions)
+        );
+        assert_eq!(
+            " close_positions".parse::<ShutdownPolicy>(),
+            Ok(ShutdownPolicy::ClosePositions)
+        );
+        assert!("close".parse::<ShutdownPolicy>().is_err());
+        assert_eq!(ShutdownPolicy::default(), ShutdownPolicy::KeepPositions);
+    }
+
+    /// Tests the policy is read from `SHUTDOWN_POLICY`, keeping positions if it isn't set.
+    #[test]
+    fn test_shutdown_policy_from_lookup() {
+        let lookup = vars_lookup(&[("SHUTDOWN_POLICY", "close_positions")]);
+        assert_eq!(
+            ShutdownPolicy::from_lookup(lookup),
+            Ok(ShutdownPolicy::ClosePositions)
+        );
+        assert_eq!(
+            ShutdownPolicy::from_lookup(vars_lookup(&[])),
+            Ok(ShutdownPolicy::KeepPositions)
+        );
+    }
+}
@@ -0,0 +1,320 @@
+//! # Bot Snapshots
+//!
+//! A snapshot is a checkpoint of the whole bot state in a single versioned JSON file: the active
+//! strategies with their settings and params, the open positions of every account, the streams
+//! the market keeps open and the state of the signal manager. Restoring a snapshot replaces the
+//! running state by it, so an upgrade can be rolled back to the state from before it, or
+//! forward onto a new deploy, without replaying anything.
+//!
+//! Snapshots carry the `SNAPSHOT_VERSION` of their format. Snapshots of a newer format than the
+//! running bot understands are refused rather than restored partially.
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use directories::UserDirs;
+use serde::{Deserialize, Serialize};
+
+use crate::account::trade::Position;
+use crate::error::{StorageError, StorageResult};
+use crate::exchange::stream::StreamMeta;
+use crate::strategy::strategy::{StrategyId, StrategyInfo, StrategySettings};
+use crate::utils::env::{env_lookup, Lookup};
+use crate::utils::time::generate_ts;
+
+/// Version of the snapshot format, increased whenever a change keeps older bots from reading
+/// it. Older snapshots are migrated when read.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Snapshots kept when `SNAPSHOT_KEEP` isn't set.
+const DEFAULT_SNAPSHOT_KEEP: usize = 24;
+
+/// The open positions of an account.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AccountSnapshot {
+    pub name: String,
+    pub positions: Vec<Position>,
+}
+
+/// The streams the market keeps open.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct MarketSnapshot {
+    /// Streams reopened by the stream monitor whenever they close.
+    pub needed_streams: Vec<StreamMeta>,
+    /// Ids of the streams open when the snapshot was taken.
+    pub active_streams: Vec<String>,
+}
+
+/// The state of the signal manager.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SignalSnapshot {
+    /// Whether live signals are ignored, e.g. after flattening all positions.
+    pub paused: bool,
+    /// The settings of every strategy signals are handled for, including TradingView alerts.
+    pub strategy_settings: HashMap<StrategyId, StrategySettings>,
+}
+
+/// A checkpoint of the bot state, see the module documentation.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BotSnapshot {
+    /// The format of the snapshot, see `SNAPSHOT_VERSION`.
+    pub version: u32,
+    /// The version of raderbot which took the snapshot.
+    pub bot_version: String,
+    pub created_at: u64,
+    pub strategies: Vec<StrategyInfo>,
+    pub accounts: Vec<AccountSnapshot>,
+    pub market: MarketSnapshot,
+    pub signals: SignalSnapshot,
+}
+
+impl BotSnapshot {
+    pub fn new(
+        strategies: Vec<StrategyInfo>,
+        accounts: Vec<AccountSnapshot>,
+        market: MarketSnapshot,
+        signals: SignalSnapshot,
+    ) -> Self {
+        Self {
+            version: SNAPSHOT_VERSION,
+            bot_version: env!("CARGO_PKG_VERSION").to_string(),
+            created_at: generate_ts(),
+            strategies,
+            accounts,
+            market,
+            signals,
+        }
+    }
+
+    /// Parses a snapshot, migrating snapshots of older formats.
+    ///
+    /// # Returns
+    ///
+    /// The `BotSnapshot`, or an error if it is invalid or of a newer format.
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let header: SnapshotHeader =
+            serde_json::from_str(content).map_err(|e| format!("Invalid snapshot, e: {e}"))?;
+
+        match header.version {
+            SNAPSHOT_VERSION => {
+                serde_json::from_str(content).map_err(|e| format!("Invalid snapshot, e: {e}"))
+            }
+            version if version > SNAPSHOT_VERSION => Err(format!(
+                "Snapshot of format {version} was taken by raderbot {}, this bot reads format {SNAPSHOT_VERSION} and older",
+                header.bot_version
+            )),
+            version => Err(format!("Unknown snapshot format {version}")),
+        }
+    }
+}
+
+/// The fields every snapshot format starts with.
+#[derive(Deserialize, Debug)]
+struct SnapshotHeader {
+    version: u32,
+    bot_version: String,
+    created_at: u64,
+}
+
+/// A snapshot file.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SnapshotInfo {
+    pub name: String,
+    pub version: u32,
+    pub bot_version: String,
+    pub created_at: u64,
+    pub size: u64,
+}
+
+/// The outcome of restoring a snapshot.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct SnapshotRestoreReport {
+    pub name: String,
+    /// Strategies of the snapshot that resumed.
+    pub resumed_strategies: usize,
+    pub open_positions: usize,
+    /// Parts of the snapshot that couldn't be restored, such as accounts of removed profiles.
+    pub errors: Vec<String>,
+}
+
+/// The directory snapshots are written to.
+#[derive(Debug, Clone)]
+pub struct SnapshotStore {
+    directory: PathBuf,
+    /// Snapshots kept by `prune`.
+    keep: usize,
+}
+
+impl SnapshotStore {
+    /// Creates a store of the snapshots in `SNAPSHOT_DIR`, `~/.raderbot/snapshots` by default,
+    /// keeping the latest `SNAPSHOT_KEEP` snapshots, 24 by default.
+    pub fn from_env() -> Self {
+        Self::from_lookup(env_lookup)
+    }
+
+    /// Creates a store from `SNAPSHOT_DIR` and `SNAPSHOT_KEEP` read through `lookup`, see
+    /// `from_env`.
+    pub fn from_lookup(lookup: impl Lookup) -> Self {
+        let directory = match lookup("SNAPSHOT_DIR") {
+            Some(directory) => PathBuf::from(directory),
+            None => UserDirs::new()
+                .map(|dirs| dirs.home_dir().join(".raderbot"))
+                .unwrap_or_else(|| PathBuf::from(".raderbot"))
+                .join("snapshots"),
+        };
+        let keep = lookup("SNAPSHOT_KEEP")
+            .and_then(|keep| keep.parse().ok())
+            .unwrap_or(DEFAULT_SNAPSHOT_KEEP);
+
+        Self::new(directory, keep)
+    }
+
+    pub fn new(directory: PathBuf, keep: usize) -> Self {
+        Self { directory, keep }
+    }
+
+    /// Writes a snapshot to a file named after its creation time. The file is written under a
+    /// temporary name first, so a crash never leaves a partial snapshot behind.
+    pub fn save(&self, snapshot: &BotSnapshot) -> StorageResult<SnapshotInfo> {
+        fs::create_dir_all(&self.directory)?;
+
+        let name = format!("snapshot-{}.json", snapshot.created_at);
+        let content = serde_json::to_string_pretty(snapshot)?;
+        let temp_path = self.directory.join(format!("{name}.tmp"));
+        fs::write(&temp_path, &content)?;
+        fs::rename(&temp_path, self.directory.join(&name))?;
+
+        Ok(SnapshotInfo {
+            name,
+            version: snapshot.version,
+            bot_version: snapshot.bot_version.clone(),
+            created_at: snapshot.created_at,
+            size: content.len() as u64,
+        })
+    }
+
+    /// Lists the snapshots, most recent first. Files which aren't snapshots are skipped.
+    pub fn list(&self) -> StorageResult<Vec<SnapshotInfo>> {
+        if !self.directory.exists() {
+            return Ok(vec![]);
+        }
+
+        let mut snapshots = vec![];
+        for entry in fs::read_dir(&self.directory)? {
+            let path = entry?.path();
+            let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            if !name.starts_with("snapshot-") || !name.ends_with(".json") {
+                continue;
+            }
+
+            let content = fs::read_to_string(&path)?;
+            let Ok(header) = serde_json::from_str::<SnapshotHeader>(&content) else {
+                continue;
+            };
+            snapshots.push(SnapshotInfo {
+                name: name.to_string(),
+                version: header.version,
+                bot_version: header.bot_version,
+                created_at: header.created_at,
+                size: content.len() as u64,
+            });
+        }
+        snapshots.sort_by_key(|snapshot| Reverse(snapshot.created_at));
+
+        Ok(snapshots)
+    }
+
+    /// Reads a snapshot by name, see `BotSnapshot::parse`.
+    pub fn load(&self, name: &str) -> StorageResult<BotSnapshot> {
+        // names come from the API, they must not point outside the directory
+        if name.contains(['/', '\\']) || name.starts_with('.') {
+            return Err(StorageError::Invalid(format!(
+                "Invalid snapshot name {name}"
+            )));
+        }
+
+        let path = self.directory.join(name);
+        if !path.exists() {
+            return Err(StorageError::NotFound(format!("Snapshot {name}")));
+        }
+
+        BotSnapshot::parse(&fs::read_to_string(path)?).map_err(StorageError::Invalid)
+    }
+
+    /// Removes all but the most recent snapshots.
+    ///
+    /// # Returns
+    ///
+    /// The number of removed snapshots.
+    pub fn prune(&self) -> StorageResult<usize> {
+        let snapshots = self.list()?;
+        let mut removed = 0;
+        for snapshot in snapshots.iter().skip(self.keep) {
+            fs::remove_file(self.directory.join(&snapshot.name))?;
+            removed += 1;
+        }
+
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    use crate::market::interval::Interval;
+
+    /// Tests snapshots round trip through files, are pruned and refused when of a newer format.
+    #[test]
+    fn test_snapshot_store() {
+        let directory = std::env::temp_dir().join(format!("raderbot-snapshots-{}", generate_ts()));
+        let store = SnapshotStore::new(directory.clone(), 2);
+
+        let info = StrategyInfo {
+            name: "ema_cross".to_string(),
+            symbol: "BTCUSDT".to_string(),
+            interval: Interval::Hour1,
+            ..Default::default()
+        };
+        let mut snapshot = BotSnapshot::new(
+            vec![info.clone()],
+            vec![AccountSnapshot {
+                name: "live".to_string(),
+                positions: vec![],
+            }],
+            MarketSnapshot::default(),
+            SignalSnapshot {
+                paused: true,
+                strategy_settings: HashMap::from([(info.id, info.settings.clone())]),
+            },
+        );
+        for created_at in [1_000, 2_000, 3_000] {
+            snapshot.created_at = created_at;
+            store.save(&snapshot).unwrap();
+        }
+
+        let snapshots = store.list().unwrap();
+        assert_eq!(snapshots.len(), 3);
+        assert_eq!(snapshots[0].name, "snapshot-3000.json");
+        assert_eq!(store.prune().unwrap(), 1);
+        assert_eq!(store.list().unwrap().len(), 2);
+
+        let loaded = store.load("snapshot-3000.json").unwrap();
+        assert_eq!(loaded.version, SNAPSHOT_VERSION);
+        assert_eq!(loaded.strategies[0].id, info.id);
+        assert!(loaded.signals.paused);
+        assert!(store.load("snapshot-1000.json").is_err());
+        assert!(store.load("../snapshot-3000.json").is_err());
+
+        let mut newer = serde_json::to_value(&snapshot).unwrap();
+        newer["version"] = json!(SNAPSHOT_VERSION + 1);
+        assert!(BotSnapshot::parse(&newer.to_string()).is_err());
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,152 @@
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::Serialize;
+use std::io::{self, Read, Write};
+use std::path::{Component, Path};
+use tar::{Archive, Builder, EntryType, Header};
+
+/// Prefix of the names of backup archives.
+const BACKUP_PREFIX: &str = "raderbot-backup-";
+
+/// Extension of backup archives, gzip compressed tar archives.
+const BACKUP_EXTENSION: &str = ".tar.gz";
+
+/// A backup archive of the stored data, such as k-lines, strategy summaries and account state.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct BackupInfo {
+    /// The name the backup is restored by.
+    pub name: String,
+    pub created_at: u64,
+    /// The size of the archive in bytes.
+    pub size: u64,
+}
+
+/// Builds the name of a backup archive, e.g. `raderbot-backup-1700006400000.tar.gz`.
+pub fn build_backup_name(created_at: u64) -> String {
+    format!("{BACKUP_PREFIX}{created_at}{BACKUP_EXTENSION}")
+}
+
+/// Reads the creation time of a backup archive name, `None` if it isn't a backup name. Only
+/// backup names are accepted, so a name can't point outside of the backup directory.
+pub fn backup_created_at(name: &str) -> Option<u64> {
+    name.strip_prefix(BACKUP_PREFIX)?
+        .strip_suffix(BACKUP_EXTENSION)?
+        .parse()
+        .ok()
+}
+
+/// Writes a compressed archive of the contents of a directory.
+///
+/// # Arguments
+///
+/// * `dir` - The directory to archive.
+/// * `writer` - Where the archive is written to.
+///
+/// # Returns
+///
+/// The writer, or an error if the directory can't be read or the archive written.
+pub fn archive_directory<W: Write>(dir: &Path, writer: W) -> io::Result<W> {
+    let mut builder = Builder::new(GzEncoder::new(writer, Compression::default()));
+    builder.append_dir_all(".", dir)?;
+
+    builder.into_inner()?.finish()
+}
+
+/// Extracts a compressed archive into a directory.
+///
+/// # Arguments
+///
+/// * `reader` - The archive.
+/// * `dir` - The directory to extract into, entries pointing outside of it are skipped.
+pub fn extract_archive<R: Read>(reader: R, dir: &Path) -> io::Result<()> {
+    Archive::new(GzDecoder::new(reader)).unpack(dir)
+}
+
+/// Builds a compressed archive of files held in memory.
+///
+/// # Arguments
+///
+/// * `files` - The relative paths and contents of the files.
+///
+/// # Returns
+///
+/// The archive, or an error if it can't be written.
+pub fn archive_files(files: &[(String, Vec<u8>)]) -> io::Result<Vec<u8>> {
+    let mut builder = Builder::new(GzEncoder::new(vec![], Compression::default()));
+    for (path, bytes) in files {
+        let mut header = Header::new_gnu();
+        header.set_size(bytes.len() as u64);
+        header.set_mode(0o644);
+        builder.append_data(&mut header, path, bytes.as_slice())?;
+    }
+
+    builder.into_inner()?.finish()
+}
+
+/// Reads the files of a compressed archive into memory.
+///
+/// # Arguments
+///
+/// * `bytes` - The archive.
+///
+/// # Returns
+///
+/// The relative paths and contents of the files, or an error if the archive is invalid or
+/// holds a path pointing outside of it.
+pub fn read_archive_files(bytes: &[u8]) -> io::Result<Vec<(String, Vec<u8>)>> {
+    let mut archive = Archive::new(GzDecoder::new(bytes));
+
+    let mut files = vec![];
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if entry.header().entry_type() != EntryType::Regular {
+            continue;
+        }
+
+        let path = entry.path()?.into_owned();
+        let mut parts = vec![];
+        for component in path.components() {
+            match component {
+                Component::Normal(part) => parts.push(part.to_string_lossy().to_string()),
+                Component::CurDir => {}
+                _ => {
+                    let message = format!("Invalid path in backup: {}", path.display());
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+                }
+            }
+        }
+
+        let mut contents = vec![];
+        entry.read_to_end(&mut contents)?;
+        files.push((parts.join("/"), contents));
+    }
+
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests archived files round trip and only backup names are accepted.
+    #[test]
+    fn test_backup_archive() {
+        let files = vec![
+            (
+                "strategies/summaries/a.json".to_string(),
+                b"{\"id\":1}".to_vec(),
+            ),
+            (
+                "market/klines/BTCUSDT@kline_1h-2023-11.csv".to_string(),
+                b"1,2,3\n".to_vec(),
+            ),
+        ];
+        let archive = archive_files(&files).unwrap();
+        assert_eq!(read_archive_files(&archive).unwrap(), files);
+
+        let name = build_backup_name(1_700_006_400_000);
+        assert_eq!(name, "raderbot-backup-1700006400000.tar.gz");
+        assert_eq!(backup_created_at(&name), Some(1_700_006_400_000));
+        assert_eq!(backup_created_at("../raderbot-backup-1.tar.gz/x"), None);
+        assert_eq!(backup_created_at("raderbot-backup-../1.tar.gz"), None);
+    }
+}
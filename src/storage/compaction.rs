@@ -0,0 +1,237 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::{market::kline::Kline, utils::kline::build_kline_month_string};
+
+/// An irregularity found in stored k-lines while compacting them.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum KlineAnomaly {
+    /// K-lines of the same open time with different values, the last stored one is kept.
+    ConflictingDuplicate { open_time: u64 },
+    /// K-lines missing between two stored k-lines.
+    Gap {
+        from_open_time: u64,
+        to_open_time: u64,
+    },
+    /// A k-line with a high below or a low above its open and close, or a negative volume.
+    InvalidPrices { open_time: u64 },
+    /// A k-line stored in the file of another month, it's left in place.
+    OutsideMonth { open_time: u64 },
+    /// A file that can't be parsed, it's left untouched.
+    Unreadable { error: String },
+}
+
+/// An anomaly and the file or object it was found in.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct KlineFileAnomaly {
+    pub file: String,
+    #[serde(flatten)]
+    pub anomaly: KlineAnomaly,
+}
+
+/// Outcome of a k-line compaction run.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct KlineCompactionReport {
+    pub files_checked: usize,
+    /// Files rewritten because they had duplicates or weren't sorted.
+    pub files_compacted: usize,
+    pub duplicates_removed: usize,
+    pub anomalies: Vec<KlineFileAnomaly>,
+}
+
+impl KlineCompactionReport {
+    /// Adds the outcome of compacting a file to the report.
+    pub fn add_file(&mut self, file: &str, compacted: &CompactedKlines) {
+        self.files_checked += 1;
+        if compacted.changed {
+            self.files_compacted += 1;
+        }
+        self.duplicates_removed += compacted.duplicates_removed;
+        for anomaly in &compacted.anomalies {
+            self.add_anomaly(file, anomaly.clone());
+        }
+    }
+
+    pub fn add_anomaly(&mut self, file: &str, anomaly: KlineAnomaly) {
+        self.anomalies.push(KlineFileAnomaly {
+            file: file.to_string(),
+            anomaly,
+        });
+    }
+}
+
+/// K-lines of a file after compaction.
+#[derive(Debug, Clone)]
+pub struct CompactedKlines {
+    /// The k-lines sorted by open time without duplicates.
+    pub klines: Vec<Kline>,
+    pub duplicates_removed: usize,
+    /// Whether the k-lines differ from the stored ones and the file needs rewriting.
+    pub changed: bool,
+    pub anomalies: Vec<KlineAnomaly>,
+}
+
+/// Compacts the k-lines of a monthly k-line file, removing duplicate open times and sorting
+/// them by open time.
+///
+/// K-lines appended later win over earlier ones of the same open time, like a save replaces
+/// stored k-lines.
+///
+/// # Arguments
+///
+/// * `klines` - The k-lines in the order they are stored.
+/// * `month` - The month of the file, `YYYY-MM`, k-lines of other months are reported.
+///
+/// # Returns
+///
+/// The `CompactedKlines` and the anomalies found in them.
+pub fn compact_klines(klines: Vec<Kline>, month: Option<&str>) -> CompactedKlines {
+    let stored_len = klines.len();
+    let was_sorted = klines
+        .windows(2)
+        .all(|pair| pair[0].open_time < pair[1].open_time);
+
+    let mut anomalies = vec![];
+    let mut by_open_time: BTreeMap<u64, Kline> = BTreeMap::new();
+    for kline in klines {
+        if let Some(previous) = by_open_time.get(&kline.open_time) {
+            if *previous != kline {
+                anomalies.push(KlineAnomaly::ConflictingDuplicate {
+                    open_time: kline.open_time,
+                });
+            }
+        }
+        by_open_time.insert(kline.open_time, kline);
+    }
+    let klines: Vec<Kline> = by_open_time.into_values().collect();
+
+    for kline in &klines {
+        if kline.high < kline.open.max(kline.close)
+            || kline.low > kline.open.min(kline.close)
+            || kline.volume < 0.0
+        {
+            anomalies.push(KlineAnomaly::InvalidPrices {
+                open_time: kline.open_time,
+            });
+        }
+        if month.is_some_and(|month| build_kline_month_string(kline.open_time) != month) {
+            anomalies.push(KlineAnomaly::OutsideMonth {
+                open_time: kline.open_time,
+            });
+        }
+    }
+
+    // a k-line closes 1ms before the next one opens
+    for pair in klines.windows(2) {
+        if pair[1].open_time > pair[0].close_time + 1 {
+            anomalies.push(KlineAnomaly::Gap {
+                from_open_time: pair[0].open_time,
+                to_open_time: pair[1].open_time,
+            });
+        }
+    }
+
+    let duplicates_removed = stored_len - klines.len();
+    CompactedKlines {
+        changed: duplicates_removed > 0 || !was_sorted,
+        klines,
+        duplicates_removed,
+        anomalies,
+    }
+}
+
+/// Reads the month of a monthly k-line file name, e.g. `2024-01` of
+/// `BTCUSDT@kline_1m-2024-01.csv`.
+pub fn kline_file_month(filename: &str) -> Option<&str> {
+    let stem = filename.strip_suffix(".csv")?;
+    let month = stem.get(stem.len().checked_sub(7)?..)?;
+    let (year, month_of_year) = month.split_once('-')?;
+
+    let is_month = year.len() == 4
+        && month_of_year.len() == 2
+        && year
+            .chars()
+            .chain(month_of_year.chars())
+            .all(|c| c.is_ascii_digit());
+    is_month.then_some(month)
+}
+
+/// Reads the kline key of a monthly k-line file name, e.g. `BTCUSDT@kline_1m` of
+/// `BTCUSDT@kline_1m-2024-01.csv`.
+pub fn kline_file_key(filename: &str) -> Option<&str> {
+    let month = kline_file_month(filename)?;
+    filename
+        .strip_suffix(".csv")?
+        .strip_suffix(month)?
+        .strip_suffix('-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::interval::Interval;
+    use crate::market::kline::test_kline;
+
+    /// Tests duplicates are removed keeping the last one, k-lines are sorted and anomalies
+    /// reported.
+    #[test]
+    fn test_compact_klines() {
+        // 2023-11-15T00:00:00Z
+        let day_ts = 1_700_006_400_000;
+        let hour = 3_600_000;
+
+        let mut invalid = test_kline(Interval::Hour1, day_ts + 5 * hour, 1.5);
+        invalid.high = 1.2;
+        let stored = vec![
+            test_kline(Interval::Hour1, day_ts + hour, 1.0),
+            test_kline(Interval::Hour1, day_ts, 1.0),
+            test_kline(Interval::Hour1, day_ts + hour, 1.0),
+            test_kline(Interval::Hour1, day_ts + 2 * hour, 1.0),
+            test_kline(Interval::Hour1, day_ts + 2 * hour, 1.5),
+            invalid.clone(),
+        ];
+
+        let compacted = compact_klines(stored, Some("2023-11"));
+        assert!(compacted.changed);
+        assert_eq!(compacted.duplicates_removed, 2);
+        assert_eq!(
+            compacted.klines,
+            vec![
+                test_kline(Interval::Hour1, day_ts, 1.0),
+                test_kline(Interval::Hour1, day_ts + hour, 1.0),
+                test_kline(Interval::Hour1, day_ts + 2 * hour, 1.5),
+                invalid,
+            ]
+        );
+        assert_eq!(
+            compacted.anomalies,
+            vec![
+                KlineAnomaly::ConflictingDuplicate {
+                    open_time: day_ts + 2 * hour
+                },
+                KlineAnomaly::InvalidPrices {
+                    open_time: day_ts + 5 * hour
+                },
+                KlineAnomaly::Gap {
+                    from_open_time: day_ts + 2 * hour,
+                    to_open_time: day_ts + 5 * hour
+                },
+            ]
+        );
+
+        let compacted = compact_klines(compacted.klines, Some("2023-12"));
+        assert!(!compacted.changed);
+        assert_eq!(compacted.anomalies.len(), 6);
+
+        assert_eq!(
+            kline_file_month("BTCUSDT@kline_1m-2024-01.csv"),
+            Some("2024-01")
+        );
+        assert_eq!(kline_file_month("BTCUSDT@kline_1m.csv"), None);
+        assert_eq!(
+            kline_file_key("BTCUSDT@kline_1m-2024-01.csv"),
+            Some("BTCUSDT@kline_1m")
+        );
+    }
+}
@@ -0,0 +1,214 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use flate2::{read::GzDecoder, write::GzEncoder};
+use serde::{Deserialize, Serialize};
+
+/// Compression of files written by `FsStorage`.
+///
+/// Compressed files are stored next to where the plain file would be, with the extension of the
+/// compression appended, e.g. `BTCUSDT@kline_1m-2024-01.csv.zst`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub enum FileCompression {
+    #[default]
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl FileCompression {
+    /// Every compression, in the order files are looked up in.
+    const ALL: [FileCompression; 3] = [
+        FileCompression::Zstd,
+        FileCompression::Gzip,
+        FileCompression::None,
+    ];
+
+    /// Builds the path of a file in this compression from the path of the plain file.
+    pub fn path(&self, plain_path: &Path) -> PathBuf {
+        let mut path = plain_path.as_os_str().to_owned();
+        match self {
+            FileCompression::None => {}
+            FileCompression::Gzip => path.push(".gz"),
+            FileCompression::Zstd => path.push(".zst"),
+        }
+        PathBuf::from(path)
+    }
+
+    /// Creates a file in this compression, truncating an existing one.
+    ///
+    /// # Arguments
+    ///
+    /// * `plain_path` - The path of the plain file, see `path`.
+    ///
+    /// # Returns
+    ///
+    /// A `CompressedWriter`, which must be finished once written.
+    pub fn create(&self, plain_path: &Path) -> io::Result<CompressedWriter> {
+        let file = File::create(self.path(plain_path))?;
+        let writer = match self {
+            FileCompression::None => CompressedWriter::Plain(BufWriter::new(file)),
+            FileCompression::Gzip => {
+                CompressedWriter::Gzip(GzEncoder::new(file, flate2::Compression::default()))
+            }
+            FileCompression::Zstd => CompressedWriter::Zstd(zstd::Encoder::new(file, 0)?),
+        };
+        Ok(writer)
+    }
+}
+
+/// Writer compressing into a file, see `FileCompression::create`.
+pub enum CompressedWriter {
+    Plain(BufWriter<File>),
+    Gzip(GzEncoder<File>),
+    Zstd(zstd::Encoder<'static, File>),
+}
+
+impl CompressedWriter {
+    /// Writes the end of the compressed stream and flushes the file.
+    pub fn finish(self) -> io::Result<()> {
+        let mut file = match self {
+            CompressedWriter::Plain(writer) => writer.into_inner().map_err(|e| e.into_error())?,
+            CompressedWriter::Gzip(encoder) => encoder.finish()?,
+            CompressedWriter::Zstd(encoder) => encoder.finish()?,
+        };
+        file.flush()
+    }
+}
+
+impl Write for CompressedWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            CompressedWriter::Plain(writer) => writer.write(buf),
+            CompressedWriter::Gzip(encoder) => encoder.write(buf),
+            CompressedWriter::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            CompressedWriter::Plain(writer) => writer.flush(),
+            CompressedWriter::Gzip(encoder) => encoder.flush(),
+            CompressedWriter::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
+/// Finds the stored file of a plain path in any compression.
+///
+/// # Returns
+///
+/// The path of the file and its compression, or `None` if no file exists.
+pub fn find_file(plain_path: &Path) -> Option<(PathBuf, FileCompression)> {
+    FileCompression::ALL.iter().find_map(|compression| {
+        let path = compression.path(plain_path);
+        path.exists().then_some((path, *compression))
+    })
+}
+
+/// Opens the stored file of a plain path for reading, decompressing it if needed.
+///
+/// # Returns
+///
+/// A reader of the plain content, `None` if no file exists.
+pub fn open_file(plain_path: &Path) -> io::Result<Option<Box<dyn Read>>> {
+    let (path, compression) = match find_file(plain_path) {
+        Some(file) => file,
+        None => return Ok(None),
+    };
+
+    let file = BufReader::new(File::open(path)?);
+    let reader: Box<dyn Read> = match compression {
+        FileCompression::None => Box::new(file),
+        FileCompression::Gzip => Box::new(GzDecoder::new(file)),
+        FileCompression::Zstd => Box::new(zstd::Decoder::with_buffer(file)?),
+    };
+    Ok(Some(reader))
+}
+
+/// Removes the files of a plain path in every compression except one, e.g. after rewriting a
+/// file in another compression.
+pub fn remove_other_files(plain_path: &Path, keep: FileCompression) -> io::Result<()> {
+    for compression in FileCompression::ALL {
+        let path = compression.path(plain_path);
+        if compression != keep && path.exists() {
+            fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Removes the files of a plain path in every compression.
+pub fn remove_files(plain_path: &Path) -> io::Result<()> {
+    for compression in FileCompression::ALL {
+        let path = compression.path(plain_path);
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Compresses a plain file and removes it once the compressed file is written.
+///
+/// # Arguments
+///
+/// * `plain_path` - The path of the plain file.
+/// * `compression` - The compression to write.
+pub fn compress_file(plain_path: &Path, compression: FileCompression) -> io::Result<()> {
+    if compression == FileCompression::None {
+        return Ok(());
+    }
+
+    let mut reader = BufReader::new(File::open(plain_path)?);
+    let mut writer = compression.create(plain_path)?;
+    io::copy(&mut reader, &mut writer)?;
+    writer.finish()?;
+
+    fs::remove_file(plain_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    /// Tests files are read back in every compression and only one of them is kept.
+    #[test]
+    fn test_compressed_files() {
+        let dir = std::env::temp_dir().join(format!("raderbot-compression-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let plain_path = dir.join("klines.csv");
+        let read = || {
+            let mut content = String::new();
+            open_file(&plain_path)
+                .unwrap()
+                .unwrap()
+                .read_to_string(&mut content)
+                .unwrap();
+            content
+        };
+
+        assert!(open_file(&plain_path).unwrap().is_none());
+
+        fs::write(&plain_path, "1,2,3\n").unwrap();
+        assert_eq!(read(), "1,2,3\n");
+
+        compress_file(&plain_path, FileCompression::Gzip).unwrap();
+        assert!(!plain_path.exists());
+        assert_eq!(find_file(&plain_path).unwrap().1, FileCompression::Gzip);
+        assert_eq!(read(), "1,2,3\n");
+
+        let mut writer = FileCompression::Zstd.create(&plain_path).unwrap();
+        writer.write_all(b"4,5,6\n").unwrap();
+        writer.finish().unwrap();
+        remove_other_files(&plain_path, FileCompression::Zstd).unwrap();
+        assert!(!dir.join("klines.csv.gz").exists());
+        assert_eq!(read(), "4,5,6\n");
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,20 +1,32 @@
+use crate::error::StorageResult;
 use async_trait::async_trait;
 use csv::ReaderBuilder;
 use directories::UserDirs;
-use log::info;
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::error::Error;
+use log::{info, warn};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, HashMap};
 use std::fs;
 use std::fs::{File, OpenOptions};
 use std::io::Read;
 use std::io::Write;
 use std::io::{self};
 use std::path::{Path, PathBuf};
-
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, UNIX_EPOCH};
+use tokio::task::JoinHandle;
+
+use crate::account::daily_report::DailyReport;
+use crate::account::trade::{OrderRecord, Position, PositionId, TradeTx};
+use crate::audit::{AuditEntry, AuditQuery};
+use crate::market::external::ExternalDataPoint;
+use crate::market::interval::Interval;
 use crate::market::kline::Kline;
 use crate::market::trade::Trade;
-use crate::strategy::strategy::{StrategyId, StrategyInfo, StrategySummary};
+use crate::strategy::backer::BackTestRecord;
+use crate::strategy::report::{BackTestReport, ReportFormat};
+use crate::strategy::signal::{SignalHistoryQuery, SignalRecord};
+use crate::strategy::strategy::{StrategyId, StrategyInfo, StrategyParamsChange, StrategySummary};
 use crate::utils::kline::{
     build_kline_filename, build_kline_key, generate_kline_filenames_in_range, get_min_max_open_time,
 };
@@ -23,7 +35,45 @@ use crate::utils::trade::{
     build_market_trade_filename, build_market_trade_key, generate_trade_filenames_in_range,
 };
 
-use super::manager::StorageManager;
+use super::{
+    backup::{
+        archive_directory, backup_created_at, build_backup_name, extract_archive, BackupInfo,
+    },
+    compaction::{
+        compact_klines, kline_file_key, kline_file_month, KlineAnomaly, KlineCompactionReport,
+    },
+    compression::{
+        compress_file, find_file, open_file, remove_files, remove_other_files, FileCompression,
+    },
+    kline_query::KlineQuery,
+    manager::StorageManager,
+    manifest::{KlineFileEntry, KlineManifest},
+    retention::{kline_key_interval, trade_file_day_ts, RetentionPolicy, RetentionReport},
+    summary_query::StrategySummaryQuery,
+    write_buffer::KlineWriteBuffer,
+};
+#[cfg(feature = "parquet")]
+use crate::storage::parquet::ParquetKlineStore;
+
+/// Interval in seconds between runs of the kline compression job.
+const KLINE_COMPRESSION_INTERVAL_SECS: u64 = 60 * 60;
+
+/// Serializes writes of k-line files and their manifests, so concurrent saves of a kline key
+/// don't drop each other's manifest entries.
+static KLINE_WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+/// File format k-lines are stored in by `FsStorage`.
+///
+/// `Csv` stores a file per symbol, interval and month, `Parquet` a compressed columnar file per
+/// symbol, interval and day, see `ParquetKlineStore`, and needs the `parquet` feature. Stored
+/// k-lines aren't converted when switching formats.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub enum KlineFileFormat {
+    #[default]
+    Csv,
+    #[cfg(feature = "parquet")]
+    Parquet,
+}
 
 /// Represents a file system-based storage manager for managing klines and strategy summaries.
 
@@ -31,6 +81,12 @@ use super::manager::StorageManager;
 pub struct FsStorage {
     app_directory: PathBuf,
     data_directory: PathBuf,
+    #[serde(default)]
+    kline_format: KlineFileFormat,
+    #[serde(default)]
+    kline_compression: FileCompression,
+    #[serde(skip)]
+    kline_buffer: Option<Arc<KlineWriteBuffer>>,
 }
 
 impl FsStorage {
@@ -51,7 +107,453 @@ impl FsStorage {
         Self {
             app_directory,
             data_directory,
+            kline_format: KlineFileFormat::default(),
+            kline_compression: FileCompression::default(),
+            kline_buffer: None,
+        }
+    }
+
+    /// Sets the file format k-lines are saved and loaded in.
+    ///
+    /// # Arguments
+    ///
+    /// * `kline_format` - The `KlineFileFormat` to use.
+    pub fn with_kline_format(mut self, kline_format: KlineFileFormat) -> Self {
+        self.kline_format = kline_format;
+        self
+    }
+
+    /// Sets the compression CSV k-line files are written in. Files are read in any compression,
+    /// so existing plain files stay readable and are rewritten compressed once saved to.
+    ///
+    /// # Arguments
+    ///
+    /// * `kline_compression` - The `FileCompression` to write.
+    pub fn with_kline_compression(mut self, kline_compression: FileCompression) -> Self {
+        self.kline_compression = kline_compression;
+        self
+    }
+
+    /// Compresses plain CSV k-line files not modified within a given age, leaving recent files
+    /// that are still being written to plain.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_age` - The age after which files are compressed.
+    ///
+    /// # Returns
+    ///
+    /// The number of compressed files, or an error if the directory can't be read.
+    pub fn compress_old_klines(&self, max_age: Duration) -> io::Result<usize> {
+        // compress with zstd unless configured otherwise, writing plain files is the default
+        let compression = match self.kline_compression {
+            FileCompression::None => FileCompression::Zstd,
+            compression => compression,
+        };
+
+        let market_dir = self.data_directory.join("market").join("klines");
+        if !market_dir.is_dir() {
+            return Ok(0);
+        }
+
+        let mut compressed = 0;
+        for entry in fs::read_dir(market_dir)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|extension| extension != "csv") {
+                continue;
+            }
+
+            let age = fs::metadata(&path)?
+                .modified()?
+                .elapsed()
+                .unwrap_or_default();
+            if age < max_age {
+                continue;
+            }
+
+            match compress_file(&path, compression) {
+                Ok(()) => compressed += 1,
+                Err(e) => info!("Unable to compress {}, e: {e}", path.display()),
+            }
+        }
+
+        Ok(compressed)
+    }
+
+    /// Starts a background job compressing old k-line files every hour, see
+    /// `compress_old_klines`.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_age` - The age after which files are compressed.
+    pub fn start_kline_compression(&self, max_age: Duration) -> JoinHandle<()> {
+        let storage = self.clone();
+
+        tokio::spawn(async move {
+            loop {
+                let job_storage = storage.clone();
+                match tokio::task::spawn_blocking(move || job_storage.compress_old_klines(max_age))
+                    .await
+                {
+                    Ok(Ok(0)) => {}
+                    Ok(Ok(compressed)) => info!("Compressed {compressed} kline files"),
+                    Ok(Err(e)) => info!("Unable to compress kline files, e: {e}"),
+                    Err(e) => info!("Kline compression job failed, e: {e}"),
+                }
+
+                tokio::time::sleep(Duration::from_secs(KLINE_COMPRESSION_INTERVAL_SECS)).await;
+            }
+        })
+    }
+
+    /// Buffers streamed CSV k-lines in memory, so they are written in batches by
+    /// `flush_kline_buffer` rather than on every save. Bootstrapped k-lines are still written
+    /// right away and loading k-lines flushes the buffered ones of their kline key first.
+    pub fn with_kline_write_buffer(mut self) -> Self {
+        self.kline_buffer = Some(Arc::default());
+        self
+    }
+
+    /// Writes the buffered k-lines to their files.
+    ///
+    /// # Returns
+    ///
+    /// The number of k-lines written, or an error if a file can't be written. The k-lines of a
+    /// failed kline key stay buffered for the next flush.
+    pub fn flush_kline_buffer(&self) -> io::Result<usize> {
+        let buffer = match &self.kline_buffer {
+            Some(buffer) => buffer,
+            None => return Ok(0),
+        };
+
+        let mut written = 0;
+        let mut error = None;
+        for (kline_key, klines) in buffer.take_all() {
+            // after a failure the remaining k-lines stay buffered for the next flush
+            if error.is_none() {
+                match self.write_klines(&klines, &kline_key) {
+                    Ok(()) => {
+                        written += klines.len();
+                        continue;
+                    }
+                    Err(e) => error = Some(e),
+                }
+            }
+            buffer.push(&kline_key, &klines);
+        }
+
+        match error {
+            Some(e) => Err(e),
+            None => Ok(written),
+        }
+    }
+
+    /// Starts a background job flushing the buffered k-lines, see `flush_kline_buffer`.
+    ///
+    /// # Arguments
+    ///
+    /// * `interval` - The time between flushes.
+    pub fn start_kline_flush(&self, interval: Duration) -> JoinHandle<()> {
+        let storage = self.clone();
+
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+
+                let job_storage = storage.clone();
+                match tokio::task::spawn_blocking(move || job_storage.flush_kline_buffer()).await {
+                    Ok(Ok(_)) => {}
+                    Ok(Err(e)) => info!("Unable to flush buffered klines, e: {e}"),
+                    Err(e) => info!("Kline flush job failed, e: {e}"),
+                }
+            }
+        })
+    }
+
+    /// Compacts the CSV k-line files, removing duplicate open times and sorting k-lines by open
+    /// time. Files are rewritten in the compression they are stored in, only if they changed.
+    ///
+    /// Parquet k-lines are merged by open time on save and never need compacting.
+    ///
+    /// # Returns
+    ///
+    /// The `KlineCompactionReport`, or an error if the directory can't be read.
+    pub fn compact_kline_files(&self) -> io::Result<KlineCompactionReport> {
+        self.flush_kline_buffer()?;
+        let mut report = KlineCompactionReport::default();
+        let _write_guard = KLINE_WRITE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        // every file is read anyway, so the manifests are rebuilt from scratch
+        let mut manifests: HashMap<String, KlineManifest> = HashMap::new();
+
+        let market_dir = self.data_directory.join("market").join("klines");
+        if !market_dir.is_dir() {
+            return Ok(report);
+        }
+
+        // compressed files are found through the path of their plain file
+        let mut filenames = BTreeSet::new();
+        for entry in fs::read_dir(&market_dir)? {
+            let filename = entry?.file_name().to_string_lossy().into_owned();
+            let filename = filename
+                .strip_suffix(".gz")
+                .or_else(|| filename.strip_suffix(".zst"))
+                .unwrap_or(&filename);
+            if filename.ends_with(".csv") {
+                filenames.insert(filename.to_string());
+            }
+        }
+
+        for filename in filenames {
+            let file_path = market_dir.join(&filename);
+            let (_, compression) = match find_file(&file_path) {
+                Some(file) => file,
+                None => continue,
+            };
+
+            let klines = open_file(&file_path).and_then(|file| {
+                let file = file.ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+                let mut reader = csv::ReaderBuilder::new()
+                    .has_headers(false)
+                    .from_reader(file);
+                let klines = reader.deserialize().collect::<Result<Vec<Kline>, _>>()?;
+                Ok(klines)
+            });
+            let klines = match klines {
+                Ok(klines) => klines,
+                Err(e) => {
+                    let error = e.to_string();
+                    report.add_anomaly(&filename, KlineAnomaly::Unreadable { error });
+                    continue;
+                }
+            };
+
+            let compacted = compact_klines(klines, kline_file_month(&filename));
+            if compacted.changed {
+                let mut writer = csv::WriterBuilder::new()
+                    .has_headers(false)
+                    .from_writer(compression.create(&file_path)?);
+                for kline in &compacted.klines {
+                    writer.serialize(kline)?;
+                }
+                writer.into_inner().map_err(|e| e.into_error())?.finish()?;
+            }
+
+            if let Some(kline_key) = kline_file_key(&filename) {
+                manifests
+                    .entry(kline_key.to_string())
+                    .or_default()
+                    .record(&filename, &compacted.klines);
+            }
+
+            report.add_file(&filename, &compacted);
+        }
+
+        for (kline_key, manifest) in manifests {
+            manifest.save(&self.kline_manifest_path(&kline_key))?;
+        }
+
+        Ok(report)
+    }
+
+    /// Removes market data older than the retention policy allows. K-line files are removed
+    /// once all their k-lines expired and rewritten without the expired ones otherwise, daily
+    /// trade files are removed once their whole day expired.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - The `RetentionPolicy` to enforce.
+    ///
+    /// # Returns
+    ///
+    /// The `RetentionReport` of the removed data, or an error if a file can't be removed or
+    /// rewritten.
+    pub fn apply_retention_files(&self, policy: &RetentionPolicy) -> io::Result<RetentionReport> {
+        self.flush_kline_buffer()?;
+        let now = generate_ts();
+
+        // k-lines stored in both formats expire, e.g. after switching formats
+        #[cfg(feature = "parquet")]
+        let mut report = self.parquet_kline_store().apply_retention(policy, now)?;
+        #[cfg(not(feature = "parquet"))]
+        let mut report = RetentionReport::default();
+
+        let market_dir = self.data_directory.join("market").join("klines");
+        if market_dir.is_dir() {
+            let _write_guard = KLINE_WRITE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+            let mut kline_keys = BTreeSet::new();
+            for entry in fs::read_dir(&market_dir)? {
+                let filename = entry?.file_name().to_string_lossy().into_owned();
+                let filename = filename
+                    .strip_suffix(".gz")
+                    .or_else(|| filename.strip_suffix(".zst"))
+                    .unwrap_or(&filename);
+                if let Some(kline_key) = kline_file_key(filename) {
+                    kline_keys.insert(kline_key.to_string());
+                }
+            }
+
+            for kline_key in kline_keys {
+                let cutoff = match kline_key_interval(&kline_key)
+                    .and_then(|interval| policy.kline_cutoff(interval, now))
+                {
+                    Some(cutoff) => cutoff,
+                    None => continue,
+                };
+
+                let mut manifest = self.load_kline_manifest(&kline_key)?;
+                let expired: Vec<KlineFileEntry> = manifest
+                    .files_in_range(0, cutoff.saturating_sub(1))
+                    .into_iter()
+                    .cloned()
+                    .collect();
+                if expired.is_empty() {
+                    continue;
+                }
+
+                for entry in expired {
+                    let file_path = market_dir.join(&entry.filename);
+                    if entry.max_open_time < cutoff {
+                        remove_files(&file_path)?;
+                        manifest.record(&entry.filename, &[]);
+                        report.files_removed += 1;
+                        report.klines_removed += entry.rows;
+                        continue;
+                    }
+
+                    let (klines, compression) =
+                        match (self._load_klines(&entry.filename), find_file(&file_path)) {
+                            (Some(klines), Some((_, compression))) => (klines, compression),
+                            _ => continue,
+                        };
+                    let kept: Vec<Kline> = klines
+                        .iter()
+                        .filter(|kline| kline.open_time >= cutoff)
+                        .cloned()
+                        .collect();
+
+                    let mut writer = csv::WriterBuilder::new()
+                        .has_headers(false)
+                        .from_writer(compression.create(&file_path)?);
+                    for kline in &kept {
+                        writer.serialize(kline)?;
+                    }
+                    writer.into_inner().map_err(|e| e.into_error())?.finish()?;
+
+                    manifest.record(&entry.filename, &kept);
+                    report.files_rewritten += 1;
+                    report.klines_removed += klines.len() - kept.len();
+                }
+
+                manifest.save(&self.kline_manifest_path(&kline_key))?;
+            }
+        }
+
+        let trades_dir = self.data_directory.join("market").join("trades");
+        if let (Some(cutoff), true) = (policy.trade_cutoff(now), trades_dir.is_dir()) {
+            for entry in fs::read_dir(&trades_dir)? {
+                let path = entry?.path();
+                let filename = path.file_name().unwrap_or_default().to_string_lossy();
+                let expired = trade_file_day_ts(&filename)
+                    .is_some_and(|day_ts| day_ts + DAY_AS_MILI <= cutoff);
+                if !expired {
+                    continue;
+                }
+
+                let trades_removed = self
+                    ._load_trades(&filename)
+                    .map_or(0, |trades| trades.len());
+                fs::remove_file(&path)?;
+                report.files_removed += 1;
+                report.trades_removed += trades_removed;
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Archives the data directory into a compressed backup in the backup directory, which is
+    /// kept next to the data directory so backups aren't part of later backups.
+    ///
+    /// # Returns
+    ///
+    /// The `BackupInfo` of the archive, or an error if the archive can't be written.
+    pub fn create_backup_file(&self) -> io::Result<BackupInfo> {
+        self.flush_kline_buffer()?;
+        let backup_dir = self.backup_directory();
+        fs::create_dir_all(&backup_dir)?;
+
+        let created_at = generate_ts();
+        let name = build_backup_name(created_at);
+        let path = backup_dir.join(&name);
+
+        // a failed backup never leaves a partial archive behind
+        let tmp_path = path.with_extension("tmp");
+        archive_directory(&self.data_directory, File::create(&tmp_path)?)?.sync_all()?;
+        fs::rename(&tmp_path, &path)?;
+
+        Ok(BackupInfo {
+            name,
+            created_at,
+            size: fs::metadata(&path)?.len(),
+        })
+    }
+
+    /// Lists the backups in the backup directory, most recent first.
+    pub fn list_backup_files(&self) -> io::Result<Vec<BackupInfo>> {
+        let backup_dir = self.backup_directory();
+        if !backup_dir.is_dir() {
+            return Ok(vec![]);
+        }
+
+        let mut backups = vec![];
+        for entry in fs::read_dir(&backup_dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if let Some(created_at) = backup_created_at(&name) {
+                backups.push(BackupInfo {
+                    name,
+                    created_at,
+                    size: entry.metadata()?.len(),
+                });
+            }
+        }
+        backups.sort_by_key(|backup| Reverse(backup.created_at));
+
+        Ok(backups)
+    }
+
+    /// Replaces the data directory with the contents of a backup. The backup is extracted next
+    /// to the data directory first, so a broken archive leaves the data untouched.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the backup.
+    pub fn restore_backup_file(&self, name: &str) -> io::Result<()> {
+        let path = self.backup_directory().join(name);
+        if backup_created_at(name).is_none() || !path.is_file() {
+            let message = format!("Backup {name} not found");
+            return Err(io::Error::new(io::ErrorKind::NotFound, message));
+        }
+
+        let restore_dir = self.data_directory.with_extension("restore");
+        if restore_dir.exists() {
+            fs::remove_dir_all(&restore_dir)?;
+        }
+        fs::create_dir_all(&restore_dir)?;
+        if let Err(e) = extract_archive(File::open(&path)?, &restore_dir) {
+            fs::remove_dir_all(&restore_dir)?;
+            return Err(e);
         }
+
+        let _write_guard = KLINE_WRITE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let old_dir = self.data_directory.with_extension("old");
+        if old_dir.exists() {
+            fs::remove_dir_all(&old_dir)?;
+        }
+        fs::rename(&self.data_directory, &old_dir)?;
+        fs::rename(&restore_dir, &self.data_directory)?;
+        fs::remove_dir_all(&old_dir)
     }
 
     /// Loads klines from a specified file.
@@ -69,7 +571,7 @@ impl FsStorage {
         market_dir.push("klines");
         let file_path = market_dir.join(filename);
 
-        if let Ok(file) = fs::File::open(file_path) {
+        if let Ok(Some(file)) = open_file(&file_path) {
             let mut reader = ReaderBuilder::new().has_headers(false).from_reader(file);
 
             let mut klines: Vec<Kline> = Vec::new();
@@ -89,6 +591,61 @@ impl FsStorage {
         }
     }
 
+    /// Builds the path of the manifest of the k-line files of a kline key.
+    fn kline_manifest_path(&self, kline_key: &str) -> PathBuf {
+        self.data_directory
+            .join("market")
+            .join("manifests")
+            .join(format!("{kline_key}.json"))
+    }
+
+    /// Loads the manifest of the k-line files of a kline key, building it from the stored
+    /// files if it doesn't exist yet.
+    ///
+    /// # Arguments
+    ///
+    /// * `kline_key` - The kline key of the files.
+    ///
+    /// # Returns
+    ///
+    /// The `KlineManifest`, or an error if it can't be read or built.
+    fn load_kline_manifest(&self, kline_key: &str) -> io::Result<KlineManifest> {
+        let manifest_path = self.kline_manifest_path(kline_key);
+        if let Some(manifest) = KlineManifest::load(&manifest_path)? {
+            return Ok(manifest);
+        }
+
+        let mut manifest = KlineManifest::default();
+        let market_dir = self.data_directory.join("market").join("klines");
+        if market_dir.is_dir() {
+            // compressed files are found through the path of their plain file
+            let mut filenames = BTreeSet::new();
+            for entry in fs::read_dir(&market_dir)? {
+                let filename = entry?.file_name().to_string_lossy().into_owned();
+                let filename = filename
+                    .strip_suffix(".gz")
+                    .or_else(|| filename.strip_suffix(".zst"))
+                    .unwrap_or(&filename);
+                if kline_file_key(filename) == Some(kline_key) {
+                    filenames.insert(filename.to_string());
+                }
+            }
+
+            for filename in filenames {
+                if let Some(klines) = self._load_klines(&filename) {
+                    manifest.record(&filename, &klines);
+                }
+            }
+        }
+
+        info!(
+            "Built kline manifest of {kline_key}, {} klines",
+            manifest.rows()
+        );
+        manifest.save(&manifest_path)?;
+        Ok(manifest)
+    }
+
     // TODO: docs
     fn _load_trades(&self, filename: &str) -> Option<Vec<Trade>> {
         let mut market_dir = self.data_directory.join("market");
@@ -155,85 +712,10 @@ impl FsStorage {
         merged
     }
 
-    /// Creates the application directory in the user's home directory if it doesn't already exist.
-    ///
-    /// # Returns
-    ///
-    /// Returns the path to the application directory.
-
-    fn create_app_directory() -> PathBuf {
-        let user_dirs = UserDirs::new().expect("Failed to get user directories");
-        let home_dir = user_dirs.home_dir();
-        let app_directory = home_dir.join(".raderbot");
-
-        if !app_directory.exists() {
-            fs::create_dir_all(&app_directory).expect("Failed to create application directory");
-        }
-
-        app_directory
-    }
-
-    /// Builds the file path for storing strategy summary based on the strategy ID.
-    ///
-    /// # Arguments
-    ///
-    /// * `strategy_id` - The unique identifier of the strategy.
-    ///
-    /// # Returns
-    ///
-    /// Returns a `Result` containing the file path if successful, or an error if not.
-
-    fn strategy_summary_filepath(
-        &self,
-        strategy_id: StrategyId,
-    ) -> Result<PathBuf, Box<dyn Error>> {
-        // Build market directory and subdirectory for klines
-        let data_dir = self.data_directory.join("strategies");
-        std::fs::create_dir_all(&data_dir)?;
-        let filename = format!("{strategy_id}.json");
-
-        let filepath = data_dir.join(filename);
-
-        Ok(filepath)
-    }
-}
-
-impl Default for FsStorage {
-    fn default() -> Self {
-        // Create the default data directory path
-        let app_directory = Self::create_app_directory();
-        let data_directory = app_directory.join("default");
-
-        if !data_directory.exists() {
-            fs::create_dir_all(&data_directory).expect("Failed to create data directory");
-        }
-
-        Self {
-            app_directory,
-            data_directory,
-        }
-    }
-}
-
-#[async_trait]
-impl StorageManager for FsStorage {
-    /// Saves klines to the file system.
-    ///
-    /// # Arguments
-    ///
-    /// * `klines` - A slice of `Kline` to be saved.
-    /// * `kline_key` - A string slice that represents the key associated with the klines.
-    ///
-    /// # Returns
-    ///
-    /// Returns an `io::Result<()>` indicating the outcome of the operation.
-
-    async fn save_klines(
-        &self,
-        klines: &[Kline],
-        kline_key: &str,
-        is_bootstrap: bool,
-    ) -> io::Result<()> {
+    /// Writes k-lines to their monthly files. K-lines opened after the last k-line of a plain
+    /// file are appended to it, otherwise the file is rewritten with the k-lines merged in, e.g.
+    /// when the last candle is overwritten by its closed version.
+    fn write_klines(&self, klines: &[Kline], kline_key: &str) -> io::Result<()> {
         // Build market directory and subdirectory for klines
         let mut market_dir = self.data_directory.join("market");
         market_dir.push("klines");
@@ -251,16 +733,40 @@ impl StorageManager for FsStorage {
             }
         }
 
+        let _write_guard = KLINE_WRITE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut manifest = self.load_kline_manifest(kline_key)?;
+
         for (month_ts, mut klines) in klines_by_month {
-            let mut klines_to_save = vec![];
+            klines.sort_by_key(|k| k.open_time);
 
             let kline_filename = build_kline_filename(kline_key, month_ts);
-            let file_path = market_dir.join(kline_filename);
+            let file_path = market_dir.join(&kline_filename);
+
+            // compressed files can't be appended to, they are rewritten
+            let is_append = self.kline_compression == FileCompression::None
+                && matches!(find_file(&file_path), Some((_, FileCompression::None)))
+                && manifest
+                    .get(&kline_filename)
+                    .is_some_and(|entry| klines[0].open_time > entry.max_open_time);
+            if is_append {
+                let file = OpenOptions::new().append(true).open(&file_path)?;
+                let mut writer = csv::WriterBuilder::new()
+                    .has_headers(false)
+                    .from_writer(file);
+                for kline in &klines {
+                    writer.serialize(kline)?
+                }
+                writer.flush()?;
 
-            if file_path.exists() {
+                manifest.append(&kline_filename, &klines);
+                continue;
+            }
+
+            let mut klines_to_save = vec![];
+            if let Some(file) = open_file(&file_path)? {
                 let mut reader = csv::ReaderBuilder::new()
                     .has_headers(false)
-                    .from_path(&file_path)?;
+                    .from_reader(file);
 
                 // Read existing klines into a vector
                 let mut existing_klines: Vec<Kline> =
@@ -268,7 +774,6 @@ impl StorageManager for FsStorage {
 
                 // sort klines by open_time
                 existing_klines.sort_by_key(|k| k.open_time);
-                klines.sort_by_key(|k| k.open_time);
 
                 let merged = self._merge_klines(&existing_klines, &klines);
 
@@ -277,31 +782,302 @@ impl StorageManager for FsStorage {
                 klines_to_save.extend_from_slice(&klines);
             }
 
-            let file = OpenOptions::new()
-                .append(!is_bootstrap)
-                .write(true)
-                .create(true)
-                .open(&file_path)?;
+            // the merged klines include the stored ones, rewrite the file rather than append
+            let file = self.kline_compression.create(&file_path)?;
 
             let mut writer = csv::WriterBuilder::new()
                 .has_headers(false)
                 .from_writer(file);
 
-            for kline in klines_to_save {
+            for kline in &klines_to_save {
                 writer.serialize(kline)?
             }
 
-            writer.flush()?
+            writer.into_inner().map_err(|e| e.into_error())?.finish()?;
+            remove_other_files(&file_path, self.kline_compression)?;
+
+            manifest.record(&kline_filename, &klines_to_save);
         }
 
+        manifest.save(&self.kline_manifest_path(kline_key))?;
+
         Ok(())
     }
 
-    /// Retrieves klines based on the specified criteria.
-    ///
-    /// # Arguments
-    ///
-    /// * `symbol` - The symbol associated with the klines.
+    /// Writes the buffered k-lines of a kline key before they are loaded.
+    fn flush_buffered_klines(&self, kline_key: &str) {
+        let buffer = match &self.kline_buffer {
+            Some(buffer) => buffer,
+            None => return,
+        };
+
+        let klines = buffer.take(kline_key);
+        if klines.is_empty() {
+            return;
+        }
+        if let Err(e) = self.write_klines(&klines, kline_key) {
+            info!("Unable to flush buffered klines of {kline_key}, e: {e}");
+            buffer.push(kline_key, &klines);
+        }
+    }
+
+    /// Reads every saved strategy summary.
+    fn load_strategy_summaries(&self) -> io::Result<Vec<StrategySummary>> {
+        let mut data = vec![];
+
+        let data_dir = self.data_directory.join("strategies");
+
+        if data_dir.is_dir() {
+            for entry in fs::read_dir(data_dir)? {
+                let entry = entry?;
+                let path = entry.path();
+                if let Some(extension) = path.extension() {
+                    if extension == "json" {
+                        let file_content = fs::read_to_string(path)?;
+                        let strategy_summary: StrategySummary =
+                            serde_json::from_str(&file_content)?;
+                        data.push(strategy_summary);
+                    }
+                }
+            }
+        }
+
+        Ok(data)
+    }
+
+    /// The directory backups of the data directory are kept in.
+    fn backup_directory(&self) -> PathBuf {
+        let data_dir_name = self.data_directory.file_name().unwrap_or_default();
+        self.app_directory.join("backups").join(data_dir_name)
+    }
+
+    /// Creates the application directory in the user's home directory if it doesn't already exist.
+    ///
+    /// # Returns
+    ///
+    /// Returns the path to the application directory.
+
+    fn create_app_directory() -> PathBuf {
+        let user_dirs = UserDirs::new().expect("Failed to get user directories");
+        let home_dir = user_dirs.home_dir();
+        let app_directory = home_dir.join(".raderbot");
+
+        if !app_directory.exists() {
+            fs::create_dir_all(&app_directory).expect("Failed to create application directory");
+        }
+
+        app_directory
+    }
+
+    /// Builds the file path for storing strategy summary based on the strategy ID.
+    ///
+    /// # Arguments
+    ///
+    /// * `strategy_id` - The unique identifier of the strategy.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` containing the file path if successful, or an error if not.
+
+    fn strategy_summary_filepath(&self, strategy_id: StrategyId) -> StorageResult<PathBuf> {
+        // Build market directory and subdirectory for klines
+        let data_dir = self.data_directory.join("strategies");
+        std::fs::create_dir_all(&data_dir)?;
+        let filename = format!("{strategy_id}.json");
+
+        let filepath = data_dir.join(filename);
+
+        Ok(filepath)
+    }
+
+    /// Builds the file path for storing an active strategy definition based on the strategy ID.
+    ///
+    /// # Arguments
+    ///
+    /// * `strategy_id` - The unique identifier of the strategy.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` containing the file path if successful, or an error if not.
+    fn active_strategy_filepath(&self, strategy_id: StrategyId) -> StorageResult<PathBuf> {
+        let data_dir = self.data_directory.join("active_strategies");
+        std::fs::create_dir_all(&data_dir)?;
+        let filename = format!("{strategy_id}.json");
+
+        Ok(data_dir.join(filename))
+    }
+
+    /// Builds the file path for storing the algorithm parameter history of a strategy.
+    ///
+    /// # Arguments
+    ///
+    /// * `strategy_id` - The unique identifier of the strategy.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` containing the file path if successful, or an error if not.
+    fn strategy_params_filepath(&self, strategy_id: StrategyId) -> StorageResult<PathBuf> {
+        let data_dir = self.data_directory.join("strategy_params");
+        std::fs::create_dir_all(&data_dir)?;
+        let filename = format!("{strategy_id}.json");
+
+        Ok(data_dir.join(filename))
+    }
+
+    /// Builds the file path for storing a backtest run.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The strategy ID of the backtest summary.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` containing the file path if successful, or an error if not.
+    fn back_test_filepath(&self, id: StrategyId) -> StorageResult<PathBuf> {
+        let data_dir = self.data_directory.join("back_tests");
+        std::fs::create_dir_all(&data_dir)?;
+        let filename = format!("{id}.json");
+
+        Ok(data_dir.join(filename))
+    }
+
+    fn back_test_header_filepath(&self, id: StrategyId) -> StorageResult<PathBuf> {
+        let data_dir = self.data_directory.join("back_tests").join("headers");
+        std::fs::create_dir_all(&data_dir)?;
+        let filename = format!("{id}.json");
+
+        Ok(data_dir.join(filename))
+    }
+
+    #[cfg(feature = "parquet")]
+    fn parquet_kline_store(&self) -> ParquetKlineStore {
+        ParquetKlineStore::new(self.data_directory.join("market").join("klines_parquet"))
+    }
+
+    fn back_test_report_filepath(
+        &self,
+        id: StrategyId,
+        format: ReportFormat,
+    ) -> StorageResult<PathBuf> {
+        let data_dir = self.data_directory.join("back_tests").join("reports");
+        std::fs::create_dir_all(&data_dir)?;
+        let filename = format!("{id}.{}", format.extension());
+
+        Ok(data_dir.join(filename))
+    }
+
+    /// Builds the directory holding the history of an account, creating it if needed.
+    ///
+    /// # Arguments
+    ///
+    /// * `account` - The name of the account.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` containing the directory path if successful, or an error if not.
+    fn account_directory(&self, account: &str) -> StorageResult<PathBuf> {
+        let data_dir = self.data_directory.join("accounts").join(account);
+        std::fs::create_dir_all(data_dir.join("positions"))?;
+
+        Ok(data_dir)
+    }
+
+    /// Appends a record to a JSON lines file, so saving a record never rewrites the history.
+    fn append_json_line<T: Serialize>(filepath: &Path, record: &T) -> StorageResult<()> {
+        let mut json_str = serde_json::to_string(record)?;
+        json_str.push('\n');
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(filepath)?;
+        file.write_all(json_str.as_bytes())?;
+
+        Ok(())
+    }
+
+    fn read_json_lines<T: DeserializeOwned>(filepath: &Path) -> StorageResult<Vec<T>> {
+        if !filepath.exists() {
+            return Ok(vec![]);
+        }
+
+        let mut records = vec![];
+        for line in fs::read_to_string(filepath)?.lines() {
+            if !line.trim().is_empty() {
+                records.push(serde_json::from_str(line)?);
+            }
+        }
+
+        Ok(records)
+    }
+}
+
+impl Default for FsStorage {
+    fn default() -> Self {
+        // Create the default data directory path
+        let app_directory = Self::create_app_directory();
+        let data_directory = app_directory.join("default");
+
+        if !data_directory.exists() {
+            fs::create_dir_all(&data_directory).expect("Failed to create data directory");
+        }
+
+        Self {
+            app_directory,
+            data_directory,
+            kline_format: KlineFileFormat::default(),
+            kline_compression: FileCompression::default(),
+            kline_buffer: None,
+        }
+    }
+}
+
+#[async_trait]
+impl StorageManager for FsStorage {
+    /// Saves klines to the file system.
+    ///
+    /// K-lines are merged with the stored ones of their month, replacing those of the same open
+    /// time. K-lines opened after the stored ones are appended to plain files, other saves
+    /// rewrite the file, see `write_klines`. Saving already stored k-lines doesn't duplicate
+    /// them, regardless of `is_bootstrap`.
+    ///
+    /// # Arguments
+    ///
+    /// * `klines` - A slice of `Kline` to be saved.
+    /// * `kline_key` - A string slice that represents the key associated with the klines.
+    /// * `is_bootstrap` - Whether the klines are bootstrapped, written right away instead of
+    ///   buffered.
+    ///
+    /// # Returns
+    ///
+    /// Returns an `io::Result<()>` indicating the outcome of the operation.
+
+    async fn save_klines(
+        &self,
+        klines: &[Kline],
+        kline_key: &str,
+        is_bootstrap: bool,
+    ) -> io::Result<()> {
+        #[cfg(feature = "parquet")]
+        if self.kline_format == KlineFileFormat::Parquet {
+            return self.parquet_kline_store().save_klines(klines);
+        }
+
+        // streamed klines are written by the periodic flush, bootstrapped ones right away
+        match &self.kline_buffer {
+            Some(buffer) if !is_bootstrap => {
+                buffer.push(kline_key, klines);
+                Ok(())
+            }
+            _ => self.write_klines(klines, kline_key),
+        }
+    }
+
+    /// Retrieves klines based on the specified criteria.
+    ///
+    /// # Arguments
+    ///
+    /// * `symbol` - The symbol associated with the klines.
     /// * `interval` - The interval of the klines.
     /// * `from_ts` - Optional start timestamp for filtering.
     /// * `to_ts` - Optional end timestamp for filtering.
@@ -314,37 +1090,59 @@ impl StorageManager for FsStorage {
     async fn get_klines(
         &self,
         symbol: &str,
-        interval: &str,
+        interval: Interval,
         from_ts: Option<u64>,
         to_ts: Option<u64>,
     ) -> Vec<Kline> {
+        #[cfg(feature = "parquet")]
+        if self.kline_format == KlineFileFormat::Parquet {
+            return match from_ts {
+                Some(from_ts) => self.parquet_kline_store().get_klines(
+                    symbol,
+                    interval,
+                    from_ts,
+                    to_ts.unwrap_or_else(generate_ts),
+                ),
+                None => vec![],
+            };
+        }
+
         let kline_key = build_kline_key(symbol, interval);
+        self.flush_buffered_klines(&kline_key);
 
         // create filtered klines to hold all klines which are filtered
         let mut filtered_klines: Vec<Kline> = Vec::new();
 
-        let filenames = match from_ts {
-            Some(from_ts) => match to_ts {
-                Some(to_ts) => Some(generate_kline_filenames_in_range(
-                    &kline_key, from_ts, to_ts,
-                )),
-                None => Some(generate_kline_filenames_in_range(
-                    &kline_key,
-                    from_ts,
-                    generate_ts(),
-                )),
-            },
-            None => None,
+        let from_ts = match from_ts {
+            Some(from_ts) => from_ts,
+            None => return filtered_klines,
         };
+        let to_ts = to_ts.unwrap_or_else(generate_ts);
 
-        if let Some(filenames) = filenames {
-            for kline_filename in filenames {
-                if let Some(klines) = self._load_klines(&kline_filename) {
-                    filtered_klines.extend_from_slice(&klines);
-                }
+        // the manifest lists only files that exist and hold klines of the range, fall back to
+        // every month of the range if it can't be read
+        let manifest = {
+            let _write_guard = KLINE_WRITE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+            self.load_kline_manifest(&kline_key)
+        };
+        let filenames: Vec<String> = match manifest {
+            Ok(manifest) => manifest
+                .files_in_range(from_ts, to_ts)
+                .into_iter()
+                .map(|entry| entry.filename.clone())
+                .collect(),
+            Err(e) => {
+                info!("Unable to load kline manifest of {kline_key}, e: {e}");
+                generate_kline_filenames_in_range(&kline_key, from_ts, to_ts)
             }
         };
 
+        for kline_filename in filenames {
+            if let Some(klines) = self._load_klines(&kline_filename) {
+                filtered_klines.extend_from_slice(&klines);
+            }
+        }
+
         filtered_klines
     }
 
@@ -358,7 +1156,7 @@ impl StorageManager for FsStorage {
     ///
     /// Returns a `Result` indicating the outcome of the operation.
 
-    async fn save_strategy_summary(&self, summary: StrategySummary) -> Result<(), Box<dyn Error>> {
+    async fn save_strategy_summary(&self, summary: StrategySummary) -> StorageResult<()> {
         let filepath = self.strategy_summary_filepath(summary.info.id)?;
         let json_str = serde_json::to_string(&summary)?;
 
@@ -369,33 +1167,11 @@ impl StorageManager for FsStorage {
         Ok(())
     }
 
-    /// Lists all saved strategy summaries.
-    ///
-    /// # Returns
-    ///
-    /// Returns a `Result` containing a vector of `StrategyInfo` if successful, or an error if not.
-
-    async fn list_saved_strategies(&self) -> Result<Vec<StrategyInfo>, Box<dyn Error>> {
-        let mut data = vec![];
-
-        let data_dir = self.data_directory.join("strategies");
-
-        if data_dir.is_dir() {
-            for entry in fs::read_dir(data_dir)? {
-                let entry = entry?;
-                let path = entry.path();
-                if let Some(extension) = path.extension() {
-                    if extension == "json" {
-                        let file_content = fs::read_to_string(path)?;
-                        let strategy_summary: StrategySummary =
-                            serde_json::from_str(&file_content)?;
-                        data.push(strategy_summary.info);
-                    }
-                }
-            }
-        }
-
-        Ok(data)
+    async fn list_strategy_summaries(
+        &self,
+        query: &StrategySummaryQuery,
+    ) -> StorageResult<Vec<StrategySummary>> {
+        Ok(query.apply(self.load_strategy_summaries()?))
     }
 
     /// Retrieves a strategy summary based on a strategy ID.
@@ -411,7 +1187,7 @@ impl StorageManager for FsStorage {
     async fn get_strategy_summary(
         &self,
         strategy_id: StrategyId,
-    ) -> Result<StrategySummary, Box<dyn Error>> {
+    ) -> StorageResult<StrategySummary> {
         let filepath = self.strategy_summary_filepath(strategy_id)?;
 
         let mut file = File::open(filepath)?;
@@ -425,6 +1201,55 @@ impl StorageManager for FsStorage {
         Ok(data)
     }
 
+    /// Retrieves a page of klines, opening only the files needed to fill it.
+    ///
+    /// # Arguments
+    ///
+    /// * `symbol` - The symbol associated with the klines.
+    /// * `interval` - The interval of the klines.
+    /// * `query` - The range and page of the klines.
+    ///
+    /// # Returns
+    ///
+    /// Returns a vector of `Kline` of the page in the order of the query.
+    async fn query_klines(
+        &self,
+        symbol: &str,
+        interval: Interval,
+        query: &KlineQuery,
+    ) -> Vec<Kline> {
+        #[cfg(feature = "parquet")]
+        if self.kline_format == KlineFileFormat::Parquet {
+            return self
+                .parquet_kline_store()
+                .query_klines(symbol, interval, query);
+        }
+
+        let kline_key = build_kline_key(symbol, interval);
+        self.flush_buffered_klines(&kline_key);
+        let manifest = {
+            let _write_guard = KLINE_WRITE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+            self.load_kline_manifest(&kline_key)
+        };
+        let manifest = match manifest {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                info!("Unable to load kline manifest of {kline_key}, e: {e}");
+                return vec![];
+            }
+        };
+
+        let filenames: Vec<String> = manifest
+            .files_in_range(query.from_ts.unwrap_or(0), query.to_ts.unwrap_or(u64::MAX))
+            .into_iter()
+            .map(|entry| entry.filename.clone())
+            .collect();
+
+        query.load_partitions(filenames, |filename| {
+            self._load_klines(filename).unwrap_or_default()
+        })
+    }
+
     // TODO: Docs
     async fn get_trades(
         &self,
@@ -529,4 +1354,959 @@ impl StorageManager for FsStorage {
 
         Ok(())
     }
+
+    /// Removes expired market data, see `apply_retention_files`.
+    async fn apply_retention(&self, policy: &RetentionPolicy) -> StorageResult<RetentionReport> {
+        let storage = self.clone();
+        let policy = policy.clone();
+        let report =
+            tokio::task::spawn_blocking(move || storage.apply_retention_files(&policy)).await??;
+
+        Ok(report)
+    }
+
+    async fn create_backup(&self) -> StorageResult<BackupInfo> {
+        let storage = self.clone();
+        let backup = tokio::task::spawn_blocking(move || storage.create_backup_file()).await??;
+
+        Ok(backup)
+    }
+
+    async fn list_backups(&self) -> StorageResult<Vec<BackupInfo>> {
+        Ok(self.list_backup_files()?)
+    }
+
+    async fn restore_backup(&self, name: &str) -> StorageResult<()> {
+        let storage = self.clone();
+        let name = name.to_string();
+        tokio::task::spawn_blocking(move || storage.restore_backup_file(&name)).await??;
+
+        Ok(())
+    }
+
+    /// Writes the buffered k-lines to their files, see `flush_kline_buffer`.
+    async fn flush(&self) -> StorageResult<()> {
+        let storage = self.clone();
+        tokio::task::spawn_blocking(move || storage.flush_kline_buffer()).await??;
+
+        Ok(())
+    }
+
+    /// Compacts the CSV k-line files, see `compact_kline_files`.
+    async fn compact_klines(&self) -> StorageResult<KlineCompactionReport> {
+        let storage = self.clone();
+        let report = tokio::task::spawn_blocking(move || storage.compact_kline_files()).await??;
+
+        Ok(report)
+    }
+
+    /// Saves an active strategy definition to the file system.
+    ///
+    /// # Arguments
+    ///
+    /// * `info` - The `StrategyInfo` of the running strategy.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` indicating the outcome of the operation.
+    async fn save_active_strategy(&self, info: StrategyInfo) -> StorageResult<()> {
+        let filepath = self.active_strategy_filepath(info.id)?;
+        let json_str = serde_json::to_string(&info)?;
+
+        let mut file = File::create(filepath)?;
+        file.write_all(json_str.as_bytes())?;
+
+        Ok(())
+    }
+
+    /// Removes an active strategy definition from the file system.
+    ///
+    /// # Arguments
+    ///
+    /// * `strategy_id` - The unique identifier of the strategy.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` indicating the outcome of the operation.
+    async fn remove_active_strategy(&self, strategy_id: StrategyId) -> StorageResult<()> {
+        let filepath = self.active_strategy_filepath(strategy_id)?;
+
+        if filepath.exists() {
+            fs::remove_file(filepath)?;
+        }
+
+        Ok(())
+    }
+
+    /// Lists all active strategy definitions saved on the file system.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` containing a vector of `StrategyInfo` if successful, or an error if not.
+    async fn list_active_strategies(&self) -> StorageResult<Vec<StrategyInfo>> {
+        let mut data = vec![];
+
+        let data_dir = self.data_directory.join("active_strategies");
+
+        if data_dir.is_dir() {
+            for entry in fs::read_dir(data_dir)? {
+                let path = entry?.path();
+                if let Some(extension) = path.extension() {
+                    if extension == "json" {
+                        let file_content = fs::read_to_string(path)?;
+                        let info: StrategyInfo = serde_json::from_str(&file_content)?;
+                        data.push(info);
+                    }
+                }
+            }
+        }
+
+        Ok(data)
+    }
+
+    /// Appends a parameter change record to the strategy's parameter history file.
+    ///
+    /// # Arguments
+    ///
+    /// * `change` - The `StrategyParamsChange` to record.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` indicating the outcome of the operation.
+    async fn save_strategy_params_change(&self, change: StrategyParamsChange) -> StorageResult<()> {
+        let mut changes = self
+            .list_strategy_params_changes(change.strategy_id)
+            .await?;
+        let filepath = self.strategy_params_filepath(change.strategy_id)?;
+
+        changes.push(change);
+        let json_str = serde_json::to_string(&changes)?;
+
+        let mut file = File::create(filepath)?;
+        file.write_all(json_str.as_bytes())?;
+
+        Ok(())
+    }
+
+    /// Reads the parameter history of a strategy from the file system.
+    ///
+    /// # Arguments
+    ///
+    /// * `strategy_id` - The unique identifier of the strategy.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` containing the recorded changes, empty if none were saved.
+    async fn list_strategy_params_changes(
+        &self,
+        strategy_id: StrategyId,
+    ) -> StorageResult<Vec<StrategyParamsChange>> {
+        let filepath = self.strategy_params_filepath(strategy_id)?;
+
+        if !filepath.exists() {
+            return Ok(vec![]);
+        }
+
+        let file_content = fs::read_to_string(filepath)?;
+        let changes: Vec<StrategyParamsChange> = serde_json::from_str(&file_content)?;
+
+        Ok(changes)
+    }
+
+    /// Saves a backtest run to the file system.
+    ///
+    /// # Arguments
+    ///
+    /// * `record` - The `BackTestRecord` to save.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` indicating the outcome of the operation.
+    async fn save_back_test(&self, record: BackTestRecord) -> StorageResult<()> {
+        let filepath = self.back_test_filepath(record.id())?;
+        let json_str = serde_json::to_string(&record)?;
+
+        let mut file = File::create(filepath)?;
+        file.write_all(json_str.as_bytes())?;
+
+        // the header is written last, runs are only listed once fully saved
+        let filepath = self.back_test_header_filepath(record.id())?;
+        let json_str = serde_json::to_string(&record.header())?;
+
+        let mut file = File::create(filepath)?;
+        file.write_all(json_str.as_bytes())?;
+
+        Ok(())
+    }
+
+    /// Reads a backtest run from the file system.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The strategy ID of the backtest summary.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` containing the `BackTestRecord` if found, or an error if not.
+    async fn get_back_test(&self, id: StrategyId) -> StorageResult<BackTestRecord> {
+        let filepath = self.back_test_filepath(id)?;
+
+        let file_content = fs::read_to_string(filepath)?;
+        let record: BackTestRecord = serde_json::from_str(&file_content)?;
+
+        Ok(record)
+    }
+
+    /// Lists all backtest runs saved on the file system from their headers, see
+    /// `BackTestRecord::header`. Runs saved before headers were written are read in full, and
+    /// unreadable runs are skipped with a warning.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` containing the `BackTestRecord` headers ordered by creation time.
+    async fn list_back_tests(&self) -> StorageResult<Vec<BackTestRecord>> {
+        let mut records = vec![];
+
+        let data_dir = self.data_directory.join("back_tests");
+        let header_dir = data_dir.join("headers");
+
+        if data_dir.is_dir() {
+            for entry in fs::read_dir(&data_dir)? {
+                let path = entry?.path();
+                if path.extension().is_none_or(|extension| extension != "json") {
+                    continue;
+                }
+
+                let header_path = match path.file_name() {
+                    Some(filename) => header_dir.join(filename),
+                    None => continue,
+                };
+                let read_record = |path: &Path| -> StorageResult<BackTestRecord> {
+                    let file_content = fs::read_to_string(path)?;
+                    Ok(serde_json::from_str(&file_content)?)
+                };
+                let record = if header_path.is_file() {
+                    read_record(&header_path)
+                } else {
+                    read_record(&path).map(|record| record.header())
+                };
+
+                match record {
+                    Ok(record) => records.push(record),
+                    Err(e) => warn!("Skipping unreadable back test {}, e: {e}", path.display()),
+                }
+            }
+        }
+
+        records.sort_by_key(|record| record.created_at);
+
+        Ok(records)
+    }
+
+    /// Writes a backtest report to the file system as a plain HTML or Markdown file, so it can
+    /// be opened directly.
+    ///
+    /// # Arguments
+    ///
+    /// * `report` - The `BackTestReport` to be saved.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` indicating success or failure.
+    async fn save_back_test_report(&self, report: BackTestReport) -> StorageResult<()> {
+        let filepath = self.back_test_report_filepath(report.id, report.format)?;
+
+        let mut file = File::create(filepath)?;
+        file.write_all(report.content.as_bytes())?;
+
+        Ok(())
+    }
+
+    /// Reads a backtest report from the file system, its creation time is the modification time
+    /// of the file.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The strategy ID of the backtest summary.
+    /// * `format` - The format of the report.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` containing the `BackTestReport` if found, or an error if not.
+    async fn get_back_test_report(
+        &self,
+        id: StrategyId,
+        format: ReportFormat,
+    ) -> StorageResult<BackTestReport> {
+        let filepath = self.back_test_report_filepath(id, format)?;
+
+        let content = fs::read_to_string(&filepath)?;
+        let created_at = fs::metadata(&filepath)?
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+
+        Ok(BackTestReport {
+            id,
+            format,
+            created_at,
+            content,
+        })
+    }
+
+    /// Saves an open position of an account as a JSON file, removed once the position is
+    /// closed.
+    ///
+    /// # Arguments
+    ///
+    /// * `account` - The name of the account.
+    /// * `position` - The `Position` to be saved.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` indicating the outcome of the operation.
+    async fn save_position(&self, account: &str, position: Position) -> StorageResult<()> {
+        let filepath = self
+            .account_directory(account)?
+            .join("positions")
+            .join(format!("{}.json", position.id));
+        let json_str = serde_json::to_string(&position)?;
+
+        let mut file = File::create(filepath)?;
+        file.write_all(json_str.as_bytes())?;
+
+        Ok(())
+    }
+
+    /// Removes a closed position of an account from the file system.
+    ///
+    /// # Arguments
+    ///
+    /// * `account` - The name of the account.
+    /// * `position_id` - The ID of the position.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` indicating the outcome of the operation.
+    async fn remove_position(&self, account: &str, position_id: PositionId) -> StorageResult<()> {
+        let filepath = self
+            .account_directory(account)?
+            .join("positions")
+            .join(format!("{position_id}.json"));
+
+        if filepath.exists() {
+            fs::remove_file(filepath)?;
+        }
+
+        Ok(())
+    }
+
+    /// Lists the open positions of an account saved on the file system.
+    ///
+    /// # Arguments
+    ///
+    /// * `account` - The name of the account.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` containing a vector of `Position` if successful, or an error if not.
+    async fn list_positions(&self, account: &str) -> StorageResult<Vec<Position>> {
+        let mut positions = vec![];
+
+        let data_dir = self.account_directory(account)?.join("positions");
+        for entry in fs::read_dir(data_dir)? {
+            let path = entry?.path();
+            if path
+                .extension()
+                .is_some_and(|extension| extension == "json")
+            {
+                let file_content = fs::read_to_string(path)?;
+                positions.push(serde_json::from_str(&file_content)?);
+            }
+        }
+
+        Ok(positions)
+    }
+
+    /// Appends a trade transaction to the trade history of an account.
+    ///
+    /// # Arguments
+    ///
+    /// * `account` - The name of the account.
+    /// * `trade_tx` - The `TradeTx` to be saved.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` indicating the outcome of the operation.
+    async fn save_trade_tx(&self, account: &str, trade_tx: TradeTx) -> StorageResult<()> {
+        let filepath = self.account_directory(account)?.join("trades.jsonl");
+        Self::append_json_line(&filepath, &trade_tx)
+    }
+
+    /// Reads the trade history of an account.
+    ///
+    /// # Arguments
+    ///
+    /// * `account` - The name of the account.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` containing a vector of `TradeTx` if successful, or an error if not.
+    async fn list_trade_txs(&self, account: &str) -> StorageResult<Vec<TradeTx>> {
+        let filepath = self.account_directory(account)?.join("trades.jsonl");
+        Self::read_json_lines(&filepath)
+    }
+
+    /// Appends an order to the order history of an account.
+    ///
+    /// # Arguments
+    ///
+    /// * `account` - The name of the account.
+    /// * `order` - The `OrderRecord` to be saved.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` indicating the outcome of the operation.
+    async fn save_order(&self, account: &str, order: OrderRecord) -> StorageResult<()> {
+        let filepath = self.account_directory(account)?.join("orders.jsonl");
+        Self::append_json_line(&filepath, &order)
+    }
+
+    /// Reads the order history of an account.
+    ///
+    /// # Arguments
+    ///
+    /// * `account` - The name of the account.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` containing a vector of `OrderRecord` if successful, or an error if not.
+    async fn list_orders(&self, account: &str) -> StorageResult<Vec<OrderRecord>> {
+        let filepath = self.account_directory(account)?.join("orders.jsonl");
+        Self::read_json_lines(&filepath)
+    }
+
+    /// Appends a handled signal to the signal history of its strategy.
+    ///
+    /// # Arguments
+    ///
+    /// * `record` - The `SignalRecord` to be saved.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` indicating the outcome of the operation.
+    async fn save_signal_record(&self, record: SignalRecord) -> StorageResult<()> {
+        let signals_dir = self.data_directory.join("signals");
+        std::fs::create_dir_all(&signals_dir)?;
+
+        let filepath = signals_dir.join(format!("{}.jsonl", record.signal.strategy_id));
+        Self::append_json_line(&filepath, &record)
+    }
+
+    /// Reads the signal history of one or all strategies.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The strategy, time range and limit of the records to read.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` containing a vector of `SignalRecord` if successful, or an error if not.
+    async fn list_signal_records(
+        &self,
+        query: SignalHistoryQuery,
+    ) -> StorageResult<Vec<SignalRecord>> {
+        let signals_dir = self.data_directory.join("signals");
+
+        let filepaths = match query.strategy_id {
+            Some(strategy_id) => vec![signals_dir.join(format!("{strategy_id}.jsonl"))],
+            None if signals_dir.exists() => fs::read_dir(&signals_dir)?
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == "jsonl"))
+                .collect(),
+            None => vec![],
+        };
+
+        let mut records = vec![];
+        for filepath in filepaths {
+            records.extend(Self::read_json_lines::<SignalRecord>(&filepath)?);
+        }
+
+        Ok(query.apply(records))
+    }
+
+    /// Appends a daily report to `daily_reports.jsonl`.
+    async fn save_daily_report(&self, report: DailyReport) -> StorageResult<()> {
+        std::fs::create_dir_all(&self.data_directory)?;
+
+        let filepath = self.data_directory.join("daily_reports.jsonl");
+        Self::append_json_line(&filepath, &report)
+    }
+
+    async fn list_daily_reports(&self) -> StorageResult<Vec<DailyReport>> {
+        let filepath = self.data_directory.join("daily_reports.jsonl");
+        let mut reports = Self::read_json_lines::<DailyReport>(&filepath)?;
+        reports.sort_by_key(|report| report.to_ts);
+
+        Ok(reports)
+    }
+
+    /// Appends an entry to `audit_log.jsonl`.
+    async fn append_audit_entry(&self, entry: AuditEntry) -> StorageResult<()> {
+        std::fs::create_dir_all(&self.data_directory)?;
+
+        let filepath = self.data_directory.join("audit_log.jsonl");
+        Self::append_json_line(&filepath, &entry)
+    }
+
+    async fn list_audit_entries(&self, query: AuditQuery) -> StorageResult<Vec<AuditEntry>> {
+        let filepath = self.data_directory.join("audit_log.jsonl");
+        let entries = Self::read_json_lines::<AuditEntry>(&filepath)?;
+
+        Ok(query.apply(entries))
+    }
+
+    /// Appends the values of a series to `external_data/{series}.jsonl`.
+    async fn save_external_data(
+        &self,
+        series: &str,
+        points: &[ExternalDataPoint],
+    ) -> StorageResult<()> {
+        let external_dir = self.data_directory.join("external_data");
+        std::fs::create_dir_all(&external_dir)?;
+
+        let filepath = external_dir.join(format!("{series}.jsonl"));
+        for point in points {
+            Self::append_json_line(&filepath, point)?;
+        }
+        Ok(())
+    }
+
+    async fn list_external_data(
+        &self,
+        from_ts: Option<u64>,
+        to_ts: Option<u64>,
+    ) -> StorageResult<HashMap<String, Vec<ExternalDataPoint>>> {
+        let external_dir = self.data_directory.join("external_data");
+        if !external_dir.exists() {
+            return Ok(HashMap::new());
+        }
+
+        let mut series = HashMap::new();
+        for entry in fs::read_dir(&external_dir)? {
+            let filepath = entry?.path();
+            if filepath.extension().is_none_or(|ext| ext != "jsonl") {
+                continue;
+            }
+            let Some(name) = filepath.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+
+            let mut points = Self::read_json_lines::<ExternalDataPoint>(&filepath)?;
+            points.retain(|point| {
+                from_ts.is_none_or(|from_ts| point.timestamp >= from_ts)
+                    && to_ts.is_none_or(|to_ts| point.timestamp <= to_ts)
+            });
+            // a stable sort keeps values saved again for a timestamp after the earlier ones
+            points.sort_by_key(|point| point.timestamp);
+            series.insert(name.to_string(), points);
+        }
+
+        Ok(series)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::account::{account::Account, trade::OrderSide};
+    use crate::events::EventPublisher;
+    use crate::exchange::{api::ExchangeApi, mock::MockExchangeApi};
+    use crate::market::{
+        kline::test_kline, market::Market, messages::MarketMessage, symbol::Symbol,
+    };
+    use crate::strategy::backer::{BackTestMode, BackTestOptions};
+    use crate::strategy::metrics::EquityPoint;
+    use crate::strategy::strategy::{Strategy, StrategySettings, StrategySummary};
+    use crate::strategy::types::SignalMessage;
+    use crate::utils::channel::{
+        build_arc_channel, OverflowPolicy, DEFAULT_MARKET_CHANNEL_CAPACITY,
+    };
+    use serde_json::json;
+    use uuid::Uuid;
+
+    /// Tests an active strategy saved to the file system is listed and resumed with its id,
+    /// settings and params, and isn't listed once removed.
+    #[tokio::test]
+    async fn test_resume_active_strategy() {
+        let directory = std::env::temp_dir().join(format!("raderbot-active-{}", Uuid::new_v4()));
+        let storage = FsStorage::new(&directory);
+
+        let info = StrategyInfo {
+            name: "Rsi".to_string(),
+            symbol: "BTCUSDT".to_string(),
+            interval: Interval::Min1,
+            settings: StrategySettings {
+                margin_usd: 250.0,
+                ..Default::default()
+            },
+            params: json!({"rsi_period": 7}),
+            running: true,
+            start_time: Some("2024-01-01 00:00:00".to_string()),
+            ..Default::default()
+        };
+        storage.save_active_strategy(info.clone()).await.unwrap();
+
+        let listed = storage.list_active_strategies().await.unwrap();
+        assert_eq!(listed.len(), 1);
+
+        let (_, market_rx) = build_arc_channel::<MarketMessage>(
+            DEFAULT_MARKET_CHANNEL_CAPACITY,
+            OverflowPolicy::DropOldest,
+        );
+        let exchange_api: Arc<Box<dyn ExchangeApi>> =
+            Arc::new(Box::new(MockExchangeApi::default()));
+        let storage_manager: Arc<Box<dyn StorageManager>> =
+            Arc::new(Box::new(FsStorage::new(&directory)));
+        let market = Arc::new(
+            Market::new(
+                market_rx,
+                exchange_api,
+                storage_manager,
+                EventPublisher::new(),
+                false,
+            )
+            .await,
+        );
+        let (strategy_tx, _) = build_arc_channel::<SignalMessage>(16, OverflowPolicy::DropOldest);
+
+        let strategy = Strategy::from_info(listed[0].clone(), strategy_tx, market).unwrap();
+        let resumed = strategy.info().await;
+        assert_eq!(resumed.id, info.id);
+        assert_eq!(resumed.name, info.name);
+        assert_eq!(resumed.symbol, info.symbol);
+        assert_eq!(resumed.params, info.params);
+        assert_eq!(resumed.settings.margin_usd, 250.0);
+        assert_eq!(resumed.start_time, info.start_time);
+
+        storage.remove_active_strategy(info.id).await.unwrap();
+        assert!(storage.list_active_strategies().await.unwrap().is_empty());
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    /// Tests parameter changes of a strategy are appended to its history in order and kept
+    /// apart from the history of other strategies.
+    #[tokio::test]
+    async fn test_strategy_params_history() {
+        let directory = std::env::temp_dir().join(format!("raderbot-params-{}", Uuid::new_v4()));
+        let storage = FsStorage::new(&directory);
+        let (strategy_id, other_id) = (Uuid::new_v4(), Uuid::new_v4());
+
+        let change = |strategy_id, old_period: u64, new_period: u64| StrategyParamsChange {
+            strategy_id,
+            timestamp: new_period,
+            old_params: json!({"rsi_period": old_period}),
+            new_params: json!({"rsi_period": new_period}),
+            changed_by: "api".to_string(),
+        };
+        for change in [
+            change(strategy_id, 14, 7),
+            change(other_id, 14, 21),
+            change(strategy_id, 7, 9),
+        ] {
+            storage.save_strategy_params_change(change).await.unwrap();
+        }
+
+        let history = storage
+            .list_strategy_params_changes(strategy_id)
+            .await
+            .unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].new_params, json!({"rsi_period": 7}));
+        assert_eq!(history[1].old_params, json!({"rsi_period": 7}));
+        assert_eq!(history[1].new_params, json!({"rsi_period": 9}));
+        assert!(storage
+            .list_strategy_params_changes(Uuid::new_v4())
+            .await
+            .unwrap()
+            .is_empty());
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    /// Tests the open positions, trades and orders of an account are saved to the file system
+    /// as they change and restored by an account of the same name only.
+    #[tokio::test]
+    async fn test_account_history_round_trip() {
+        let directory = std::env::temp_dir().join(format!("raderbot-account-{}", Uuid::new_v4()));
+        let exchange_api: Arc<Box<dyn ExchangeApi>> = Arc::new(Box::new(MockExchangeApi {}));
+        let storage_manager: Arc<Box<dyn StorageManager>> =
+            Arc::new(Box::new(FsStorage::new(&directory)));
+
+        let mut account = Account::new(exchange_api.clone(), false, true).await;
+        account
+            .set_storage_manager(storage_manager.clone(), "live")
+            .await
+            .unwrap();
+        let closed = account
+            .open_position(
+                "BTCUSDT",
+                1000.0,
+                10,
+                OrderSide::Buy,
+                50000.0.into(),
+                None,
+                None,
+            )
+            .await
+            .unwrap()
+            .clone();
+        let open = account
+            .open_position(
+                "ETHUSDT",
+                500.0,
+                5,
+                OrderSide::Sell,
+                2000.0.into(),
+                None,
+                None,
+            )
+            .await
+            .unwrap()
+            .clone();
+        let trade_tx = account
+            .close_position(closed.id, 55000.0.into())
+            .await
+            .unwrap()
+            .clone();
+
+        let positions = storage_manager.list_positions("live").await.unwrap();
+        assert_eq!(positions.len(), 1);
+        assert_eq!(positions[0].id, open.id);
+        assert_eq!(positions[0].open_price, open.open_price);
+        let trade_txs = storage_manager.list_trade_txs("live").await.unwrap();
+        assert_eq!(trade_txs.len(), 1);
+        assert_eq!(trade_txs[0].id, trade_tx.id);
+        assert_eq!(trade_txs[0].close_price, trade_tx.close_price);
+        assert_eq!(trade_txs[0].position.id, closed.id);
+        assert_eq!(storage_manager.list_orders("live").await.unwrap().len(), 3);
+
+        let mut restored = Account::new(exchange_api.clone(), false, true).await;
+        restored
+            .set_storage_manager(storage_manager.clone(), "live")
+            .await
+            .unwrap();
+        assert!(restored.get_position(&open.id).is_some());
+        assert!(restored.get_position(&closed.id).is_none());
+        assert_eq!(restored.trades().len(), 1);
+        assert_eq!(restored.orders(), account.orders());
+
+        assert!(storage_manager
+            .list_positions("shadow")
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(storage_manager
+            .list_trade_txs("shadow")
+            .await
+            .unwrap()
+            .is_empty());
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    /// Tests backtest runs are listed from their headers, falling back to the full record for
+    /// runs without one, and unreadable runs are skipped.
+    #[tokio::test]
+    async fn test_list_back_tests() {
+        let directory = std::env::temp_dir().join(format!("raderbot-backtests-{}", Uuid::new_v4()));
+        let storage = FsStorage::new(&directory);
+
+        let record = |created_at: u64| {
+            let mut summary = StrategySummary::default();
+            summary.info.id = Uuid::new_v4();
+            summary.equity_curve = vec![EquityPoint {
+                timestamp: created_at,
+                equity: 1000.0,
+                fill: false,
+            }];
+            BackTestRecord {
+                created_at,
+                ..BackTestRecord::new(
+                    0,
+                    created_at,
+                    BackTestMode::Klines,
+                    BackTestOptions::default(),
+                    summary,
+                )
+            }
+        };
+        let (saved, legacy) = (record(1), record(2));
+        storage.save_back_test(saved.clone()).await.unwrap();
+        // saved without a header, as done before headers were written
+        fs::write(
+            storage.back_test_filepath(legacy.id()).unwrap(),
+            serde_json::to_string(&legacy).unwrap(),
+        )
+        .unwrap();
+        fs::write(
+            storage.back_test_filepath(Uuid::new_v4()).unwrap(),
+            "{ truncated",
+        )
+        .unwrap();
+
+        let records = storage.list_back_tests().await.unwrap();
+        let ids: Vec<StrategyId> = records.iter().map(|record| record.id()).collect();
+        assert_eq!(ids, vec![saved.id(), legacy.id()]);
+        assert!(records
+            .iter()
+            .all(|record| record.summary.equity_curve.is_empty()));
+
+        let full = storage.get_back_test(saved.id()).await.unwrap();
+        assert_eq!(full.summary.equity_curve.len(), 1);
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    /// Tests later k-lines are appended to a plain file, while overwritten and compressed files
+    /// are rewritten with the k-lines merged in, without duplicates.
+    #[tokio::test]
+    async fn test_save_klines() {
+        let directory = std::env::temp_dir().join(format!("raderbot-klines-{}", Uuid::new_v4()));
+        let storage = FsStorage::new(&directory);
+        let kline_key = build_kline_key("BTCUSDT", Interval::Hour1);
+        // 2023-11-01
+        let (month_ts, hour) = (1_698_796_800_000, 3_600_000);
+        let file_path = directory
+            .join("market")
+            .join("klines")
+            .join(build_kline_filename(&kline_key, month_ts));
+        let closes = |klines: Vec<Kline>| -> Vec<f64> { klines.iter().map(|k| k.close).collect() };
+        let load = || storage.get_klines("BTCUSDT", Interval::Hour1, Some(month_ts), None);
+
+        let klines: Vec<Kline> = (0..2)
+            .map(|i| test_kline(Interval::Hour1, month_ts + i * hour, 1.0))
+            .collect();
+        storage
+            .save_klines(&klines, &kline_key, true)
+            .await
+            .unwrap();
+        let written = fs::read(&file_path).unwrap();
+
+        storage
+            .save_klines(
+                &[test_kline(Interval::Hour1, month_ts + 2 * hour, 2.0)],
+                &kline_key,
+                false,
+            )
+            .await
+            .unwrap();
+        assert!(fs::read(&file_path).unwrap().starts_with(&written));
+        assert_eq!(closes(load().await), vec![1.0, 1.0, 2.0]);
+
+        // the last candle closes at another price
+        storage
+            .save_klines(
+                &[test_kline(Interval::Hour1, month_ts + 2 * hour, 3.0)],
+                &kline_key,
+                false,
+            )
+            .await
+            .unwrap();
+        assert_eq!(closes(load().await), vec![1.0, 1.0, 3.0]);
+
+        // files are read in any compression
+        let compressed_storage = storage
+            .clone()
+            .with_kline_compression(FileCompression::Zstd);
+        compressed_storage
+            .save_klines(
+                &[test_kline(Interval::Hour1, month_ts + 3 * hour, 4.0)],
+                &kline_key,
+                false,
+            )
+            .await
+            .unwrap();
+        assert!(!file_path.exists());
+        assert_eq!(closes(load().await), vec![1.0, 1.0, 3.0, 4.0]);
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    /// Tests k-lines of spot markets are saved in the k-line directory and loaded back by their
+    /// canonical symbol.
+    #[tokio::test]
+    async fn test_save_spot_klines() {
+        let directory = std::env::temp_dir().join(format!("raderbot-spot-{}", Uuid::new_v4()));
+        let storage = FsStorage::new(&directory);
+        let symbol = Symbol::normalize("ETH/BTC");
+        let kline_key = build_kline_key(&symbol, Interval::Hour1);
+        // 2023-11-01
+        let month_ts = 1_698_796_800_000;
+
+        let mut kline = test_kline(Interval::Hour1, month_ts, 1.5);
+        kline.symbol = symbol.clone();
+        storage
+            .save_klines(&[kline], &kline_key, true)
+            .await
+            .unwrap();
+
+        let kline_file = directory
+            .join("market")
+            .join("klines")
+            .join(build_kline_filename(&kline_key, month_ts));
+        assert!(kline_file.is_file());
+        let klines = storage
+            .get_klines(&symbol, Interval::Hour1, Some(month_ts), None)
+            .await;
+        assert_eq!(klines.len(), 1);
+        assert_eq!(klines[0].close, 1.5);
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    /// Tests external series are listed by name, oldest first and within the time range, with
+    /// values saved again for a timestamp after the earlier ones.
+    #[tokio::test]
+    async fn test_external_data() {
+        let directory = std::env::temp_dir().join(format!("raderbot-external-{}", Uuid::new_v4()));
+        let storage = FsStorage::new(&directory);
+        let point = |timestamp: u64, value: f64| ExternalDataPoint { timestamp, value };
+
+        assert!(storage
+            .list_external_data(None, None)
+            .await
+            .unwrap()
+            .is_empty());
+        storage
+            .save_external_data("fear_greed", &[point(3_000, 60.0), point(1_000, 20.0)])
+            .await
+            .unwrap();
+        storage
+            .save_external_data("fear_greed", &[point(1_000, 25.0)])
+            .await
+            .unwrap();
+        storage
+            .save_external_data("sentiment", &[point(2_000, -0.5)])
+            .await
+            .unwrap();
+
+        let series = storage.list_external_data(None, None).await.unwrap();
+        assert_eq!(
+            series["fear_greed"],
+            vec![point(1_000, 20.0), point(1_000, 25.0), point(3_000, 60.0)]
+        );
+        assert_eq!(series["sentiment"], vec![point(2_000, -0.5)]);
+
+        let series = storage
+            .list_external_data(Some(1_500), Some(2_500))
+            .await
+            .unwrap();
+        assert!(series["fear_greed"].is_empty());
+        assert_eq!(series["sentiment"], vec![point(2_000, -0.5)]);
+
+        fs::remove_dir_all(directory).unwrap();
+    }
 }
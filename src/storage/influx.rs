@@ -1,24 +1,49 @@
+use crate::error::{StorageError, StorageResult};
 use async_trait::async_trait;
-use futures::{TryFutureExt, TryStreamExt};
+use chrono::{DateTime, SecondsFormat};
 use log::info;
 use reqwest::Client;
-use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::io;
-use std::{any, error::Error};
-use uuid::Uuid;
 
-use super::manager::StorageManager;
+use super::{
+    backup::BackupInfo,
+    compaction::KlineCompactionReport,
+    kline_query::KlineQuery,
+    manager::StorageManager,
+    retention::{RetentionPolicy, RetentionReport},
+    summary_query::StrategySummaryQuery,
+};
+use crate::audit::{AuditEntry, AuditQuery};
+use crate::market::external::ExternalDataPoint;
 use crate::{
-    account::trade::OrderSide,
-    market::{kline::Kline, trade::Trade},
-    strategy::strategy::{StrategyId, StrategyInfo, StrategySummary},
-    utils::{
-        kline::build_kline_key,
-        time::{elapsed_time, start_timer, timestamp_to_datetime},
-        trade::build_market_trade_key,
+    account::{
+        account::AccountEquity,
+        daily_report::DailyReport,
+        trade::{OrderRecord, OrderSide, Position, PositionId, TradeTx},
+    },
+    market::{interval::Interval, kline::Kline, trade::Trade},
+    strategy::{
+        backer::BackTestRecord,
+        report::{BackTestReport, ReportFormat},
+        signal::{SignalHistoryQuery, SignalRecord},
+        strategy::{StrategyId, StrategyInfo, StrategyParamsChange, StrategySummary},
     },
+    utils::time::{generate_ts, timestamp_to_datetime},
 };
 
+/// Measurement k-lines are written to, tagged by symbol and interval.
+const KLINE_MEASUREMENT: &str = "klines";
+/// Measurement market trades are written to, tagged by symbol and order side.
+const TRADE_MEASUREMENT: &str = "trades";
+/// Measurement account equity is written to, tagged by account.
+const EQUITY_MEASUREMENT: &str = "account_equity";
+
+/// Time-series storage writing k-lines, market trades and account equity to InfluxDB 2.x, so
+/// Grafana dashboards can chart bot data directly.
+///
+/// Only market data is stored, strategies and back tests need another `StorageManager`. Use it
+/// as the storage backend or mirror another backend into it with `MirroredStorage`.
 pub struct InfluxStorage {
     client: Client,
     uri: String, // Base URI for InfluxDB
@@ -28,44 +53,113 @@ pub struct InfluxStorage {
 }
 
 impl InfluxStorage {
-    pub async fn new(uri: &str, token: &str) -> Result<Self, Box<dyn Error>> {
+    pub async fn new(uri: &str, token: &str) -> StorageResult<Self> {
         let client = Client::new();
         Ok(InfluxStorage {
             client,
-            uri: uri.to_string(),
+            uri: uri.trim_end_matches('/').to_string(),
             token: token.to_string(),
             bucket: "trade_data".to_string(),
             org: "raderbot".to_string(),
         })
     }
 
-    pub async fn make_request(&self, uri: &str, body: &str) -> Result<(), String> {
+    /// Sets the organization and bucket data is written to, `raderbot` and `trade_data` by
+    /// default.
+    pub fn with_bucket(mut self, org: &str, bucket: &str) -> Self {
+        self.org = org.to_string();
+        self.bucket = bucket.to_string();
+        self
+    }
+
+    /// Writes a sample of an account's equity.
+    ///
+    /// # Arguments
+    ///
+    /// * `account` - The name the account is tagged with, e.g. `live` or `shadow`.
+    /// * `equity` - The equity of the account.
+    pub async fn save_account_equity(
+        &self,
+        account: &str,
+        equity: &AccountEquity,
+    ) -> StorageResult<()> {
+        let line = build_equity_line(account, equity);
+        self.write_lines(&[line]).await
+    }
+
+    // ---
+    // Private Methods
+    // ---
+
+    /// Writes points in line protocol with nanosecond timestamps.
+    async fn write_lines(&self, lines: &[String]) -> StorageResult<()> {
+        if lines.is_empty() {
+            return Ok(());
+        }
+
+        let uri = format!("{}/api/v2/write", self.uri);
         self.client
             .post(uri)
-            .body(body.to_string())
+            .query(&[
+                ("org", self.org.as_str()),
+                ("bucket", self.bucket.as_str()),
+                ("precision", "ns"),
+            ])
             .header("Authorization", format!("Token {}", &self.token))
-            .header("Accept-Encoding", "gzip")
-            .header("Content-type", "application/vnd.flux")
-            .header("Accept", "application/csv")
+            .header("Content-Type", "text/plain; charset=utf-8")
+            .body(lines.join("\n"))
             .send()
-            .await
-            .map_err(|e| e.to_string())?;
+            .await?
+            .error_for_status()?;
 
         Ok(())
     }
 
-    pub fn build_kline_body(&self, klines: &[Kline]) -> String {
-        let str_data = klines
+    /// Runs a Flux query, returning the rows of the CSV response.
+    async fn query(&self, flux: &str) -> StorageResult<Vec<HashMap<String, String>>> {
+        let uri = format!("{}/api/v2/query", self.uri);
+        let body = self
+            .client
+            .post(uri)
+            .query(&[("org", self.org.as_str())])
+            .header("Authorization", format!("Token {}", &self.token))
+            .header("Content-Type", "application/vnd.flux")
+            .header("Accept", "application/csv")
+            .body(flux.to_string())
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+
+        parse_csv_rows(&body)
+    }
+
+    /// Builds a Flux query of a measurement's points in a time range, one row per timestamp
+    /// with a column per field.
+    fn build_range_query(
+        &self,
+        measurement: &str,
+        tags: &[(&str, &str)],
+        from_ts: u64,
+        to_ts: u64,
+    ) -> String {
+        let filter = tags
             .iter()
-            .map(|kline| {
-                format!(
-                    "symbol={} open={},high={},low={},close={},volume={}",
-                    kline.symbol, kline.open, kline.high, kline.low, kline.close, kline.volume,
-                )
-            })
-            .collect::<Vec<_>>()
-            .join("\n");
-        str_data
+            .map(|(key, value)| format!(" and r.{key} == \"{}\"", value.replace('"', "\\\"")))
+            .collect::<String>();
+
+        format!(
+            "from(bucket: \"{}\")\n\
+             |> range(start: {}, stop: {})\n\
+             |> filter(fn: (r) => r._measurement == \"{measurement}\"{filter})\n\
+             |> pivot(rowKey: [\"_time\"], columnKey: [\"_field\"], valueColumn: \"_value\")\n\
+             |> sort(columns: [\"_time\"])",
+            self.bucket,
+            flux_time(from_ts),
+            // the stop of a range is exclusive
+            flux_time(to_ts + 1),
+        )
     }
 }
 
@@ -74,60 +168,470 @@ impl StorageManager for InfluxStorage {
     async fn get_klines(
         &self,
         symbol: &str,
-        interval: &str,
+        interval: Interval,
         from_ts: Option<u64>,
         to_ts: Option<u64>,
     ) -> Vec<Kline> {
-        let write_uri = format!(
-            "{}/api/v2/write?bucket={}&org={}",
-            self.uri, self.bucket, self.org
+        let from_ts = match from_ts {
+            Some(from_ts) => from_ts,
+            None => return vec![],
+        };
+        let to_ts = to_ts.unwrap_or_else(generate_ts);
+
+        let query = self.build_range_query(
+            KLINE_MEASUREMENT,
+            &[("symbol", symbol), ("interval", interval.as_str())],
+            from_ts,
+            to_ts,
         );
-        unimplemented!()
+        match self.query(&query).await {
+            Ok(rows) => rows.iter().filter_map(parse_kline_row).collect(),
+            Err(e) => {
+                info!("Unable to query klines from InfluxDB, e: {e}");
+                vec![]
+            }
+        }
     }
 
     async fn save_klines(
         &self,
         klines: &[Kline],
-        kline_key: &str,
-        is_bootstrap: bool,
+        _kline_key: &str,
+        _is_bootstrap: bool,
     ) -> io::Result<()> {
-        let query =
-            "q=SELECT used_percent FROM example-db.example-rp.example-measurement WHERE host=host1";
-        unimplemented!()
+        // points of the same series and time replace each other, saving klines again is safe
+        let lines: Vec<String> = klines.iter().map(build_kline_line).collect();
+
+        for chunk in lines.chunks(5000) {
+            self.write_lines(chunk)
+                .await
+                .map_err(|e| io::Error::other(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    async fn query_klines(
+        &self,
+        symbol: &str,
+        interval: Interval,
+        query: &KlineQuery,
+    ) -> Vec<Kline> {
+        let from_ts = query.from_ts.unwrap_or(0);
+        query.apply(
+            self.get_klines(symbol, interval, Some(from_ts), query.to_ts)
+                .await,
+        )
     }
 
-    // TODO: Docs
     async fn get_trades(
         &self,
         symbol: &str,
         from_ts: Option<u64>,
         to_ts: Option<u64>,
     ) -> Vec<Trade> {
-        unimplemented!()
+        let from_ts = match from_ts {
+            Some(from_ts) => from_ts,
+            None => return vec![],
+        };
+        let to_ts = to_ts.unwrap_or_else(generate_ts);
+
+        let query =
+            self.build_range_query(TRADE_MEASUREMENT, &[("symbol", symbol)], from_ts, to_ts);
+        match self.query(&query).await {
+            Ok(rows) => {
+                let mut trades: Vec<Trade> = rows.iter().filter_map(parse_trade_row).collect();
+                // every order side is a table of its own
+                trades.sort_by_key(|trade| trade.timestamp);
+                trades
+            }
+            Err(e) => {
+                info!("Unable to query trades from InfluxDB, e: {e}");
+                vec![]
+            }
+        }
     }
 
-    // TODO: docs
     async fn save_trades(
         &self,
         trades: &[Trade],
-        trade_key: &str,
-        is_bootstrap: bool,
+        _trade_key: &str,
+        _is_bootstrap: bool,
     ) -> std::io::Result<()> {
-        unimplemented!()
+        let lines = build_trade_lines(trades);
+
+        for chunk in lines.chunks(5000) {
+            self.write_lines(chunk)
+                .await
+                .map_err(|e| io::Error::other(e.to_string()))?;
+        }
+        Ok(())
     }
 
-    async fn list_saved_strategies(&self) -> Result<Vec<StrategyInfo>, Box<dyn Error>> {
-        unimplemented!()
+    async fn flush(&self) -> StorageResult<()> {
+        // points are written on save, nothing is buffered
+        Ok(())
+    }
+
+    async fn compact_klines(&self) -> StorageResult<KlineCompactionReport> {
+        // points of the same series and time replace each other, there are no duplicates
+        Ok(KlineCompactionReport::default())
+    }
+
+    async fn apply_retention(&self, _policy: &RetentionPolicy) -> StorageResult<RetentionReport> {
+        // TODO: Implement retention on InfluxStorage
+        Err(StorageError::Unsupported("InfluxStorage"))
+    }
+
+    async fn create_backup(&self) -> StorageResult<BackupInfo> {
+        // TODO: Implement backups on InfluxStorage, until then use `influx backup`
+        Err(StorageError::Unsupported("InfluxStorage"))
+    }
+
+    async fn list_backups(&self) -> StorageResult<Vec<BackupInfo>> {
+        // TODO: Implement backups on InfluxStorage
+        Err(StorageError::Unsupported("InfluxStorage"))
+    }
+
+    async fn restore_backup(&self, _name: &str) -> StorageResult<()> {
+        // TODO: Implement backups on InfluxStorage
+        Err(StorageError::Unsupported("InfluxStorage"))
+    }
+
+    async fn save_strategy_summary(&self, _summary: StrategySummary) -> StorageResult<()> {
+        // TODO: Implement save strategy summary on InfluxStorage
+        Err(StorageError::Unsupported("InfluxStorage"))
     }
-    async fn save_strategy_summary(&self, _summary: StrategySummary) -> Result<(), Box<dyn Error>> {
-        // TODO: Implement save strategy summary on DBStorageManager
-        unimplemented!()
+    async fn list_strategy_summaries(
+        &self,
+        _query: &StrategySummaryQuery,
+    ) -> StorageResult<Vec<StrategySummary>> {
+        // TODO: Implement list strategy summaries on InfluxStorage
+        Err(StorageError::Unsupported("InfluxStorage"))
     }
     async fn get_strategy_summary(
         &self,
         _strategy_id: StrategyId,
-    ) -> Result<StrategySummary, Box<dyn Error>> {
-        // TODO: Implement get strategy summary on DBStorageManager
-        unimplemented!()
+    ) -> StorageResult<StrategySummary> {
+        // TODO: Implement get strategy summary on InfluxStorage
+        Err(StorageError::Unsupported("InfluxStorage"))
+    }
+
+    async fn save_active_strategy(&self, _info: StrategyInfo) -> StorageResult<()> {
+        // TODO: Implement save active strategy on InfluxStorage
+        Err(StorageError::Unsupported("InfluxStorage"))
+    }
+    async fn remove_active_strategy(&self, _strategy_id: StrategyId) -> StorageResult<()> {
+        // TODO: Implement remove active strategy on InfluxStorage
+        Err(StorageError::Unsupported("InfluxStorage"))
+    }
+    async fn list_active_strategies(&self) -> StorageResult<Vec<StrategyInfo>> {
+        // TODO: Implement list active strategies on InfluxStorage
+        Err(StorageError::Unsupported("InfluxStorage"))
+    }
+
+    async fn save_strategy_params_change(
+        &self,
+        _change: StrategyParamsChange,
+    ) -> StorageResult<()> {
+        // TODO: Implement save strategy params change on InfluxStorage
+        Err(StorageError::Unsupported("InfluxStorage"))
+    }
+    async fn list_strategy_params_changes(
+        &self,
+        _strategy_id: StrategyId,
+    ) -> StorageResult<Vec<StrategyParamsChange>> {
+        // TODO: Implement list strategy params changes on InfluxStorage
+        Err(StorageError::Unsupported("InfluxStorage"))
+    }
+
+    async fn save_back_test(&self, _record: BackTestRecord) -> StorageResult<()> {
+        // TODO: Implement save back test on InfluxStorage
+        Err(StorageError::Unsupported("InfluxStorage"))
+    }
+    async fn get_back_test(&self, _id: StrategyId) -> StorageResult<BackTestRecord> {
+        // TODO: Implement get back test on InfluxStorage
+        Err(StorageError::Unsupported("InfluxStorage"))
+    }
+    async fn list_back_tests(&self) -> StorageResult<Vec<BackTestRecord>> {
+        // TODO: Implement list back tests on InfluxStorage
+        Err(StorageError::Unsupported("InfluxStorage"))
+    }
+
+    async fn save_back_test_report(&self, _report: BackTestReport) -> StorageResult<()> {
+        // TODO: Implement save back test report on InfluxStorage
+        Err(StorageError::Unsupported("InfluxStorage"))
+    }
+    async fn get_back_test_report(
+        &self,
+        _id: StrategyId,
+        _format: ReportFormat,
+    ) -> StorageResult<BackTestReport> {
+        // TODO: Implement get back test report on InfluxStorage
+        Err(StorageError::Unsupported("InfluxStorage"))
+    }
+
+    async fn save_position(&self, _account: &str, _position: Position) -> StorageResult<()> {
+        // TODO: Implement save position on InfluxStorage
+        Err(StorageError::Unsupported("InfluxStorage"))
+    }
+    async fn remove_position(&self, _account: &str, _position_id: PositionId) -> StorageResult<()> {
+        // TODO: Implement remove position on InfluxStorage
+        Err(StorageError::Unsupported("InfluxStorage"))
+    }
+    async fn list_positions(&self, _account: &str) -> StorageResult<Vec<Position>> {
+        // TODO: Implement list positions on InfluxStorage
+        Err(StorageError::Unsupported("InfluxStorage"))
+    }
+
+    async fn save_trade_tx(&self, _account: &str, _trade_tx: TradeTx) -> StorageResult<()> {
+        // TODO: Implement save trade tx on InfluxStorage
+        Err(StorageError::Unsupported("InfluxStorage"))
+    }
+    async fn list_trade_txs(&self, _account: &str) -> StorageResult<Vec<TradeTx>> {
+        // TODO: Implement list trade txs on InfluxStorage
+        Err(StorageError::Unsupported("InfluxStorage"))
+    }
+
+    async fn save_order(&self, _account: &str, _order: OrderRecord) -> StorageResult<()> {
+        // TODO: Implement save order on InfluxStorage
+        Err(StorageError::Unsupported("InfluxStorage"))
+    }
+    async fn list_orders(&self, _account: &str) -> StorageResult<Vec<OrderRecord>> {
+        // TODO: Implement list orders on InfluxStorage
+        Err(StorageError::Unsupported("InfluxStorage"))
+    }
+
+    async fn save_signal_record(&self, _record: SignalRecord) -> StorageResult<()> {
+        // TODO: Implement save signal record on InfluxStorage
+        Err(StorageError::Unsupported("InfluxStorage"))
+    }
+
+    async fn list_signal_records(
+        &self,
+        _query: SignalHistoryQuery,
+    ) -> StorageResult<Vec<SignalRecord>> {
+        // TODO: Implement list signal records on InfluxStorage
+        Err(StorageError::Unsupported("InfluxStorage"))
+    }
+
+    async fn save_daily_report(&self, _report: DailyReport) -> StorageResult<()> {
+        // TODO: Implement save daily report on InfluxStorage
+        Err(StorageError::Unsupported("InfluxStorage"))
+    }
+
+    async fn list_daily_reports(&self) -> StorageResult<Vec<DailyReport>> {
+        // TODO: Implement list daily reports on InfluxStorage
+        Err(StorageError::Unsupported("InfluxStorage"))
+    }
+
+    async fn append_audit_entry(&self, _entry: AuditEntry) -> StorageResult<()> {
+        // TODO: Implement append audit entry on InfluxStorage
+        Err(StorageError::Unsupported("InfluxStorage"))
+    }
+
+    async fn list_audit_entries(&self, _query: AuditQuery) -> StorageResult<Vec<AuditEntry>> {
+        // TODO: Implement list audit entries on InfluxStorage
+        Err(StorageError::Unsupported("InfluxStorage"))
+    }
+
+    async fn save_external_data(
+        &self,
+        _series: &str,
+        _points: &[ExternalDataPoint],
+    ) -> StorageResult<()> {
+        // TODO: Implement save external data on InfluxStorage
+        Err(StorageError::Unsupported("InfluxStorage"))
+    }
+
+    async fn list_external_data(
+        &self,
+        _from_ts: Option<u64>,
+        _to_ts: Option<u64>,
+    ) -> StorageResult<HashMap<String, Vec<ExternalDataPoint>>> {
+        // TODO: Implement list external data on InfluxStorage
+        Err(StorageError::Unsupported("InfluxStorage"))
+    }
+}
+
+/// Escapes commas, spaces and equal signs in tag values of line protocol.
+fn escape_tag(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(',', "\\,")
+        .replace(' ', "\\ ")
+        .replace('=', "\\=")
+}
+
+fn flux_time(timestamp: u64) -> String {
+    timestamp_to_datetime(timestamp).to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+fn build_kline_line(kline: &Kline) -> String {
+    format!(
+        "{KLINE_MEASUREMENT},symbol={},interval={} open={},high={},low={},close={},volume={},close_time={}i {}",
+        escape_tag(&kline.symbol),
+        escape_tag(kline.interval.as_str()),
+        kline.open,
+        kline.high,
+        kline.low,
+        kline.close,
+        kline.volume,
+        kline.close_time,
+        kline.open_time * 1_000_000,
+    )
+}
+
+/// Builds the lines of market trades. Points of the same series and time replace each other,
+/// trades within the same millisecond are spread over its nanoseconds to keep all of them.
+fn build_trade_lines(trades: &[Trade]) -> Vec<String> {
+    let mut seen: HashMap<(&str, OrderSide, u64), u64> = HashMap::new();
+
+    trades
+        .iter()
+        .map(|trade| {
+            let offset = seen
+                .entry((trade.symbol.as_str(), trade.order_side, trade.timestamp))
+                .or_insert(0);
+            let time = trade.timestamp * 1_000_000 + *offset;
+            *offset += 1;
+
+            format!(
+                "{TRADE_MEASUREMENT},symbol={},order_side={:?} qty={},price={} {time}",
+                escape_tag(&trade.symbol),
+                trade.order_side,
+                trade.qty,
+                trade.price,
+            )
+        })
+        .collect()
+}
+
+fn build_equity_line(account: &str, equity: &AccountEquity) -> String {
+    format!(
+        "{EQUITY_MEASUREMENT},account={} equity={},realized_profit={},unrealized_profit={},open_positions={}i {}",
+        escape_tag(account),
+        equity.equity(),
+        equity.realized_profit,
+        equity.unrealized_profit,
+        equity.open_positions,
+        equity.timestamp * 1_000_000,
+    )
+}
+
+/// Parses the CSV of a Flux query. Every table of the response starts with a header row, rows
+/// are keyed by the column names of their table.
+fn parse_csv_rows(body: &str) -> StorageResult<Vec<HashMap<String, String>>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(body.as_bytes());
+
+    let mut header: Vec<String> = vec![];
+    let mut rows = vec![];
+    for record in reader.records() {
+        let record = record?;
+        if record.iter().any(|column| column == "_time") {
+            header = record.iter().map(|column| column.to_string()).collect();
+            continue;
+        }
+        rows.push(
+            header
+                .iter()
+                .cloned()
+                .zip(record.iter().map(|value| value.to_string()))
+                .collect(),
+        );
+    }
+
+    Ok(rows)
+}
+
+/// Parses the `_time` of a row into a timestamp in milliseconds.
+fn parse_row_time(row: &HashMap<String, String>) -> Option<u64> {
+    let time = DateTime::parse_from_rfc3339(row.get("_time")?).ok()?;
+    u64::try_from(time.timestamp_millis()).ok()
+}
+
+fn parse_kline_row(row: &HashMap<String, String>) -> Option<Kline> {
+    let field = |name: &str| row.get(name)?.parse::<f64>().ok();
+
+    Some(Kline {
+        symbol: row.get("symbol")?.clone(),
+        interval: row.get("interval")?.parse().ok()?,
+        open: field("open")?,
+        high: field("high")?,
+        low: field("low")?,
+        close: field("close")?,
+        volume: field("volume")?,
+        open_time: parse_row_time(row)?,
+        close_time: row.get("close_time")?.parse().ok()?,
+    })
+}
+
+fn parse_trade_row(row: &HashMap<String, String>) -> Option<Trade> {
+    let order_side = match row.get("order_side")?.as_str() {
+        "Buy" => OrderSide::Buy,
+        "Sell" => OrderSide::Sell,
+        _ => return None,
+    };
+
+    Some(Trade {
+        symbol: row.get("symbol")?.clone(),
+        timestamp: parse_row_time(row)?,
+        qty: row.get("qty")?.parse().ok()?,
+        price: row.get("price")?.parse().ok()?,
+        order_side,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests k-lines and trades are written as line protocol and parsed back from query CSV.
+    #[test]
+    fn test_influx_lines_and_rows() {
+        // 2023-11-15T00:00:00Z
+        let open_time = 1_700_006_400_000;
+        let kline = Kline {
+            symbol: "BTCUSDT".to_string(),
+            interval: Interval::Min1,
+            open: 1.0,
+            high: 2.0,
+            low: 0.5,
+            close: 1.5,
+            volume: 10.0,
+            open_time,
+            close_time: open_time + 59_999,
+        };
+        assert_eq!(
+            build_kline_line(&kline),
+            "klines,symbol=BTCUSDT,interval=1m open=1,high=2,low=0.5,close=1.5,volume=10,close_time=1700006459999i 1700006400000000000"
+        );
+
+        let trade = Trade {
+            symbol: "BTCUSDT".to_string(),
+            timestamp: open_time,
+            qty: 0.1,
+            price: 37000.0,
+            order_side: OrderSide::Buy,
+        };
+        let lines = build_trade_lines(&[trade.clone(), trade.clone()]);
+        assert!(lines[0].ends_with(" 1700006400000000000"));
+        assert!(lines[1].ends_with(" 1700006400000000001"));
+
+        let body = ",result,table,_start,_stop,_time,_measurement,interval,symbol,close,close_time,high,low,open,volume\r\n\
+            ,_result,0,2023-11-15T00:00:00Z,2023-11-16T00:00:00Z,2023-11-15T00:00:00Z,klines,1m,BTCUSDT,1.5,1700006459999,2,0.5,1,10\r\n\
+            \r\n\
+            ,result,table,_start,_stop,_time,_measurement,order_side,symbol,price,qty\r\n\
+            ,_result,1,2023-11-15T00:00:00Z,2023-11-16T00:00:00Z,2023-11-15T00:00:00.000000001Z,trades,Buy,BTCUSDT,37000,0.1\r\n";
+        let rows = parse_csv_rows(body).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(parse_kline_row(&rows[0]), Some(kline));
+        assert_eq!(
+            parse_trade_row(&rows[1]).unwrap().timestamp,
+            trade.timestamp
+        );
+        assert!(parse_kline_row(&rows[1]).is_none());
     }
 }
@@ -0,0 +1,200 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+use crate::market::kline::Kline;
+
+/// Order k-lines are returned in by their open time.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+/// Selects a page of the stored k-lines of a symbol and interval.
+///
+/// Pages are counted from the oldest k-line of the range when `from_ts` is given and from the
+/// most recent one otherwise, unless `order` says otherwise. So a query with only a `limit`
+/// returns the most recent k-lines.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct KlineQuery {
+    /// The earliest open time, inclusive.
+    pub from_ts: Option<u64>,
+    /// The latest open time, inclusive.
+    pub to_ts: Option<u64>,
+    pub limit: Option<usize>,
+    /// The number of k-lines skipped before the page.
+    #[serde(default)]
+    pub offset: usize,
+    pub order: Option<SortOrder>,
+}
+
+impl KlineQuery {
+    /// The order of the page, ascending for a range with a start and descending otherwise.
+    pub fn sort_order(&self) -> SortOrder {
+        match (self.order, self.from_ts) {
+            (Some(order), _) => order,
+            (None, Some(_)) => SortOrder::Asc,
+            (None, None) => SortOrder::Desc,
+        }
+    }
+
+    /// Checks whether a k-line opened within the range of the query.
+    pub fn contains(&self, kline: &Kline) -> bool {
+        self.from_ts
+            .is_none_or(|from_ts| kline.open_time >= from_ts)
+            && self.to_ts.is_none_or(|to_ts| kline.open_time <= to_ts)
+    }
+
+    /// The number of k-lines that have to be loaded in the order of the query to fill the
+    /// page, `None` without a limit.
+    pub fn required_len(&self) -> Option<usize> {
+        self.limit.map(|limit| self.offset + limit)
+    }
+
+    /// Keeps the k-lines of the range, removing duplicate open times, and cuts the page out of
+    /// them in the order of the query.
+    ///
+    /// # Arguments
+    ///
+    /// * `klines` - The loaded k-lines in any order, later k-lines win over earlier ones of
+    ///   the same open time.
+    ///
+    /// # Returns
+    ///
+    /// The k-lines of the page.
+    pub fn apply(&self, klines: Vec<Kline>) -> Vec<Kline> {
+        let mut klines: Vec<Kline> = klines
+            .into_iter()
+            .filter(|kline| self.contains(kline))
+            .collect();
+
+        // stable sort, so the last loaded kline of an open time is the last of its run
+        klines.sort_by_key(|kline| kline.open_time);
+        klines.reverse();
+        klines.dedup_by_key(|kline| kline.open_time);
+        if self.sort_order() == SortOrder::Asc {
+            klines.reverse();
+        }
+
+        let page = klines.into_iter().skip(self.offset);
+        match self.limit {
+            Some(limit) => page.take(limit).collect(),
+            None => page.collect(),
+        }
+    }
+
+    /// Loads partitions of k-lines in the order of the query until the page is filled, so a
+    /// limited query opens only the partitions it needs.
+    ///
+    /// # Arguments
+    ///
+    /// * `partitions` - Partitions holding k-lines of separate time ranges, oldest first.
+    /// * `load` - Loads the k-lines of a partition.
+    ///
+    /// # Returns
+    ///
+    /// The k-lines of the page.
+    pub fn load_partitions<P>(
+        &self,
+        mut partitions: Vec<P>,
+        mut load: impl FnMut(&P) -> Vec<Kline>,
+    ) -> Vec<Kline> {
+        if self.sort_order() == SortOrder::Desc {
+            partitions.reverse();
+        }
+
+        let mut klines = vec![];
+        let mut loaded = 0;
+        for partition in &partitions {
+            if self.is_filled(loaded) {
+                break;
+            }
+            let partition_klines = load(partition);
+            loaded += self.count_in_range(&partition_klines);
+            klines.extend(partition_klines);
+        }
+
+        self.apply(klines)
+    }
+
+    /// Checks whether a number of loaded k-lines of the range fills the page.
+    pub fn is_filled(&self, loaded: usize) -> bool {
+        self.required_len().is_some_and(|len| loaded >= len)
+    }
+
+    /// Counts the distinct open times of k-lines of the range.
+    pub fn count_in_range(&self, klines: &[Kline]) -> usize {
+        klines
+            .iter()
+            .filter(|kline| self.contains(kline))
+            .map(|kline| kline.open_time)
+            .collect::<BTreeSet<u64>>()
+            .len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::interval::Interval;
+    use crate::market::kline::test_kline;
+
+    /// Tests pages are counted from the most recent k-line without a range start and from the
+    /// oldest with one.
+    #[test]
+    fn test_kline_query() {
+        // 2023-11-15T00:00:00Z
+        let day_ts = 1_700_006_400_000;
+        let hour = 3_600_000;
+        let mut klines: Vec<Kline> = (0..5)
+            .map(|i| test_kline(Interval::Hour1, day_ts + i * hour, 1.0))
+            .collect();
+        klines.push(test_kline(Interval::Hour1, day_ts + 4 * hour, 2.0));
+        let open_times = |klines: Vec<Kline>| -> Vec<u64> {
+            klines
+                .iter()
+                .map(|kline| (kline.open_time - day_ts) / hour)
+                .collect()
+        };
+
+        let latest = KlineQuery {
+            limit: Some(2),
+            ..Default::default()
+        }
+        .apply(klines.clone());
+        assert_eq!(open_times(latest.clone()), vec![4, 3]);
+        assert_eq!(latest[0].close, 2.0);
+
+        let query = KlineQuery {
+            from_ts: Some(day_ts + hour),
+            limit: Some(2),
+            offset: 1,
+            ..Default::default()
+        };
+        assert_eq!(query.required_len(), Some(3));
+        assert_eq!(open_times(query.apply(klines.clone())), vec![2, 3]);
+
+        let query = KlineQuery {
+            to_ts: Some(day_ts + 3 * hour),
+            offset: 1,
+            order: Some(SortOrder::Asc),
+            ..Default::default()
+        };
+        assert_eq!(open_times(query.apply(klines.clone())), vec![1, 2, 3]);
+
+        // the most recent partition fills the page, the older one is never loaded
+        let mut loaded_partitions = vec![];
+        let partitions = vec![klines[..3].to_vec(), klines[3..].to_vec()];
+        let latest = KlineQuery {
+            limit: Some(2),
+            ..Default::default()
+        }
+        .load_partitions(partitions, |partition| {
+            loaded_partitions.push(partition[0].open_time);
+            partition.clone()
+        });
+        assert_eq!(open_times(latest), vec![4, 3]);
+        assert_eq!(loaded_partitions, vec![day_ts + 3 * hour]);
+    }
+}
@@ -1,11 +1,24 @@
+use crate::error::StorageResult;
 use async_trait::async_trait;
-use std::error::Error;
+use std::collections::HashMap;
 use std::io::{self};
 
+use crate::account::daily_report::DailyReport;
+use crate::account::trade::{OrderRecord, Position, PositionId, TradeTx};
+use crate::audit::{AuditEntry, AuditQuery};
+use crate::market::external::ExternalDataPoint;
 use crate::market::trade::Trade;
-use crate::strategy::strategy::StrategyInfo;
+use crate::storage::backup::BackupInfo;
+use crate::storage::compaction::KlineCompactionReport;
+use crate::storage::kline_query::KlineQuery;
+use crate::storage::retention::{RetentionPolicy, RetentionReport};
+use crate::storage::summary_query::StrategySummaryQuery;
+use crate::strategy::backer::BackTestRecord;
+use crate::strategy::report::{BackTestReport, ReportFormat};
+use crate::strategy::signal::{SignalHistoryQuery, SignalRecord};
+use crate::strategy::strategy::{StrategyInfo, StrategyParamsChange};
 use crate::{
-    market::kline::Kline,
+    market::{interval::Interval, kline::Kline},
     strategy::strategy::{StrategyId, StrategySummary},
 };
 
@@ -32,11 +45,22 @@ pub trait StorageManager: Send + Sync {
     async fn get_klines(
         &self,
         symbol: &str,
-        interval: &str,
+        interval: Interval,
         from_ts: Option<u64>,
         to_ts: Option<u64>,
     ) -> Vec<Kline>;
 
+    /// Retrieves a page of kline data from storage.
+    ///
+    /// Honors the range, limit, offset and order of the `KlineQuery`, a query without a start
+    /// returns the most recent klines. Returns the klines of the page in the order of the query.
+    async fn query_klines(
+        &self,
+        symbol: &str,
+        interval: Interval,
+        query: &KlineQuery,
+    ) -> Vec<Kline>;
+
     // TODO: Docs
     async fn get_trades(
         &self,
@@ -52,21 +76,171 @@ pub trait StorageManager: Send + Sync {
         is_bootstrap: bool,
     ) -> io::Result<()>;
 
-    /// Lists saved strategy information.
+    /// Writes market data buffered in memory to storage, called before shutting down.
+    ///
+    /// Returns an error if buffered data couldn't be written.
+    async fn flush(&self) -> StorageResult<()>;
+
+    /// Compacts stored klines, removing duplicate open times and sorting them by open time.
     ///
-    /// Returns a list of `StrategyInfo` detailing saved strategies or an error if retrieval fails.
-    async fn list_saved_strategies(&self) -> Result<Vec<StrategyInfo>, Box<dyn Error>>;
+    /// Returns a `KlineCompactionReport` including the anomalies found, such as gaps and invalid prices.
+    async fn compact_klines(&self) -> StorageResult<KlineCompactionReport>;
+
+    /// Removes market data older than the retention policy allows.
+    ///
+    /// Returns a `RetentionReport` of the removed data or an error if removing fails.
+    async fn apply_retention(&self, policy: &RetentionPolicy) -> StorageResult<RetentionReport>;
+
+    /// Creates a compressed backup archive of the stored data, including market data, strategy
+    /// summaries and account state.
+    ///
+    /// Returns the `BackupInfo` of the archive or an error if the backup fails.
+    async fn create_backup(&self) -> StorageResult<BackupInfo>;
+
+    /// Lists the backup archives, most recent first.
+    async fn list_backups(&self) -> StorageResult<Vec<BackupInfo>>;
+
+    /// Replaces the stored data with the contents of a backup archive.
+    ///
+    /// Returns an error if the backup doesn't exist or can't be restored.
+    async fn restore_backup(&self, name: &str) -> StorageResult<()>;
 
     /// Saves a strategy summary.
     ///
     /// Persists a given `StrategySummary` to storage, returning success or error.
-    async fn save_strategy_summary(&self, summary: StrategySummary) -> Result<(), Box<dyn Error>>;
+    async fn save_strategy_summary(&self, summary: StrategySummary) -> StorageResult<()>;
+
+    /// Lists saved strategy summaries.
+    ///
+    /// Returns the page of summaries passing the filters of the query, sorted as requested, or
+    /// an error if retrieval fails.
+    async fn list_strategy_summaries(
+        &self,
+        query: &StrategySummaryQuery,
+    ) -> StorageResult<Vec<StrategySummary>>;
 
     /// Retrieves a strategy summary by its ID.
     ///
     /// Fetches the summary for a given strategy identified by `StrategyId`. Returns the summary or an error if not found.
-    async fn get_strategy_summary(
+    async fn get_strategy_summary(&self, strategy_id: StrategyId)
+        -> StorageResult<StrategySummary>;
+
+    /// Persists the definition of an actively running strategy.
+    ///
+    /// Stores the `StrategyInfo` so the strategy can be relaunched after a restart. Saving an
+    /// existing strategy overwrites its previous definition.
+    async fn save_active_strategy(&self, info: StrategyInfo) -> StorageResult<()>;
+
+    /// Removes a persisted active strategy definition.
+    ///
+    /// Called when a strategy is stopped so it is not resumed on the next restart.
+    async fn remove_active_strategy(&self, strategy_id: StrategyId) -> StorageResult<()>;
+
+    /// Lists all persisted active strategy definitions.
+    ///
+    /// Returns the `StrategyInfo` of every strategy that was running when last persisted.
+    async fn list_active_strategies(&self) -> StorageResult<Vec<StrategyInfo>>;
+
+    /// Appends a record to the algorithm parameter history of a strategy.
+    ///
+    /// Persists the given `StrategyParamsChange`, returning success or error.
+    async fn save_strategy_params_change(&self, change: StrategyParamsChange) -> StorageResult<()>;
+
+    /// Lists the algorithm parameter history of a strategy.
+    ///
+    /// Returns every `StrategyParamsChange` recorded for the strategy, ordered by timestamp.
+    async fn list_strategy_params_changes(
         &self,
         strategy_id: StrategyId,
-    ) -> Result<StrategySummary, Box<dyn Error>>;
+    ) -> StorageResult<Vec<StrategyParamsChange>>;
+
+    /// Saves the result of a backtest run with its configuration.
+    ///
+    /// Persists the given `BackTestRecord`, returning success or error.
+    async fn save_back_test(&self, record: BackTestRecord) -> StorageResult<()>;
+
+    /// Retrieves a saved backtest run by the strategy ID of its summary.
+    ///
+    /// Returns the `BackTestRecord` or an error if not found.
+    async fn get_back_test(&self, id: StrategyId) -> StorageResult<BackTestRecord>;
+
+    /// Lists all saved backtest runs.
+    ///
+    /// Returns every `BackTestRecord`, ordered by creation time. Records may leave out the
+    /// trades, positions and curves of their summary, see `BackTestRecord::header`.
+    async fn list_back_tests(&self) -> StorageResult<Vec<BackTestRecord>>;
+
+    /// Saves a rendered backtest report.
+    ///
+    /// Saving a report of a run in a format it was already rendered in overwrites the previous one.
+    async fn save_back_test_report(&self, report: BackTestReport) -> StorageResult<()>;
+
+    /// Retrieves the report of a backtest run in a given format.
+    ///
+    /// Returns the `BackTestReport` or an error if the run has no report in that format.
+    async fn get_back_test_report(
+        &self,
+        id: StrategyId,
+        format: ReportFormat,
+    ) -> StorageResult<BackTestReport>;
+
+    /// Saves an open position of an account, replacing a saved position of the same ID.
+    ///
+    /// Accounts are identified by name, e.g. `live` or `shadow`.
+    async fn save_position(&self, account: &str, position: Position) -> StorageResult<()>;
+
+    /// Removes a position of an account once it's closed.
+    async fn remove_position(&self, account: &str, position_id: PositionId) -> StorageResult<()>;
+
+    /// Lists the open positions of an account.
+    async fn list_positions(&self, account: &str) -> StorageResult<Vec<Position>>;
+
+    /// Saves a trade transaction of a closed position of an account.
+    async fn save_trade_tx(&self, account: &str, trade_tx: TradeTx) -> StorageResult<()>;
+
+    /// Lists the trade transactions of an account, oldest first.
+    async fn list_trade_txs(&self, account: &str) -> StorageResult<Vec<TradeTx>>;
+
+    /// Saves an order filled for an account.
+    async fn save_order(&self, account: &str, order: OrderRecord) -> StorageResult<()>;
+
+    /// Lists the orders of an account, oldest first.
+    async fn list_orders(&self, account: &str) -> StorageResult<Vec<OrderRecord>>;
+
+    /// Saves a signal handled by the signal manager with the action taken on it.
+    async fn save_signal_record(&self, record: SignalRecord) -> StorageResult<()>;
+
+    /// Lists the handled signals matching the query, ordered by signal timestamp.
+    async fn list_signal_records(
+        &self,
+        query: SignalHistoryQuery,
+    ) -> StorageResult<Vec<SignalRecord>>;
+
+    /// Saves a daily report.
+    async fn save_daily_report(&self, report: DailyReport) -> StorageResult<()>;
+
+    /// Lists the daily reports, oldest first.
+    async fn list_daily_reports(&self) -> StorageResult<Vec<DailyReport>>;
+
+    /// Appends an entry to the audit log, entries are never changed or removed.
+    async fn append_audit_entry(&self, entry: AuditEntry) -> StorageResult<()>;
+
+    /// Lists the audit entries matching the query, oldest first.
+    async fn list_audit_entries(&self, query: AuditQuery) -> StorageResult<Vec<AuditEntry>>;
+
+    /// Saves values of an external series, replacing values of the same timestamp.
+    async fn save_external_data(
+        &self,
+        series: &str,
+        points: &[ExternalDataPoint],
+    ) -> StorageResult<()>;
+
+    /// Lists the values of every external series published in a time range, by series and
+    /// oldest first. A timestamp saved more than once may be listed again, the latest save
+    /// last.
+    async fn list_external_data(
+        &self,
+        from_ts: Option<u64>,
+        to_ts: Option<u64>,
+    ) -> StorageResult<HashMap<String, Vec<ExternalDataPoint>>>;
 }
@@ -0,0 +1,180 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::market::kline::Kline;
+
+/// The k-lines stored in a monthly k-line file.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct KlineFileEntry {
+    pub filename: String,
+    pub min_open_time: u64,
+    pub max_open_time: u64,
+    pub rows: usize,
+}
+
+/// Index of the k-line files of a symbol and interval, so loading a time range opens only
+/// the files holding k-lines of that range.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct KlineManifest {
+    /// Entries by filename.
+    files: BTreeMap<String, KlineFileEntry>,
+}
+
+impl KlineManifest {
+    /// Reads a manifest, `None` if it doesn't exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the manifest file.
+    ///
+    /// # Returns
+    ///
+    /// The `KlineManifest`, or an error if it can't be read or parsed.
+    pub fn load(path: &Path) -> io::Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let manifest = serde_json::from_str(&fs::read_to_string(path)?)?;
+        Ok(Some(manifest))
+    }
+
+    /// Writes the manifest, replacing it through a temporary file so a failed write never
+    /// leaves a partial manifest behind.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, serde_json::to_string(self)?)?;
+        fs::rename(tmp_path, path)
+    }
+
+    /// Records the k-lines a file holds after it was written, a file without k-lines is
+    /// removed from the manifest.
+    pub fn record(&mut self, filename: &str, klines: &[Kline]) {
+        let min_open_time = klines.iter().map(|kline| kline.open_time).min();
+        let max_open_time = klines.iter().map(|kline| kline.open_time).max();
+
+        match (min_open_time, max_open_time) {
+            (Some(min_open_time), Some(max_open_time)) => {
+                let entry = KlineFileEntry {
+                    filename: filename.to_string(),
+                    min_open_time,
+                    max_open_time,
+                    rows: klines.len(),
+                };
+                self.files.insert(filename.to_string(), entry);
+            }
+            _ => {
+                self.files.remove(filename);
+            }
+        }
+    }
+
+    /// Records k-lines appended to a file, all opened after the k-lines it already holds.
+    pub fn append(&mut self, filename: &str, klines: &[Kline]) {
+        let max_open_time = klines.iter().map(|kline| kline.open_time).max();
+
+        match (self.files.get_mut(filename), max_open_time) {
+            (Some(entry), Some(max_open_time)) => {
+                entry.max_open_time = entry.max_open_time.max(max_open_time);
+                entry.rows += klines.len();
+            }
+            (None, _) => self.record(filename, klines),
+            (Some(_), None) => {}
+        }
+    }
+
+    /// The entry of a file, `None` if it holds no k-lines.
+    pub fn get(&self, filename: &str) -> Option<&KlineFileEntry> {
+        self.files.get(filename)
+    }
+
+    /// Lists the files holding k-lines opened between two timestamps, oldest first.
+    ///
+    /// # Arguments
+    ///
+    /// * `from_ts` - The start of the range, inclusive.
+    /// * `to_ts` - The end of the range, inclusive.
+    pub fn files_in_range(&self, from_ts: u64, to_ts: u64) -> Vec<&KlineFileEntry> {
+        let mut entries: Vec<&KlineFileEntry> = self
+            .files
+            .values()
+            .filter(|entry| entry.min_open_time <= to_ts && entry.max_open_time >= from_ts)
+            .collect();
+        entries.sort_by_key(|entry| entry.min_open_time);
+
+        entries
+    }
+
+    /// The number of k-lines stored in all files.
+    pub fn rows(&self) -> usize {
+        self.files.values().map(|entry| entry.rows).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::interval::Interval;
+    use crate::market::kline::test_kline;
+
+    /// Tests only files overlapping a range are listed and empty files are dropped.
+    #[test]
+    fn test_kline_manifest() {
+        // 2023-11-15T00:00:00Z
+        let day_ts = 1_700_006_400_000;
+        let hour = 3_600_000;
+        // 2023-12-01T00:00:00Z
+        let month_ts = 1_701_388_800_000;
+
+        let mut manifest = KlineManifest::default();
+        manifest.record(
+            "BTCUSDT@kline_1h-2023-12.csv",
+            &[
+                test_kline(Interval::Hour1, month_ts, 1.5),
+                test_kline(Interval::Hour1, month_ts + hour, 1.5),
+            ],
+        );
+        manifest.record(
+            "BTCUSDT@kline_1h-2023-11.csv",
+            &[
+                test_kline(Interval::Hour1, day_ts, 1.5),
+                test_kline(Interval::Hour1, day_ts + hour, 1.5),
+                test_kline(Interval::Hour1, day_ts + 2 * hour, 1.5),
+            ],
+        );
+        assert_eq!(manifest.rows(), 5);
+
+        let filenames = |entries: Vec<&KlineFileEntry>| -> Vec<String> {
+            entries.iter().map(|entry| entry.filename.clone()).collect()
+        };
+        assert_eq!(
+            filenames(manifest.files_in_range(day_ts + hour, month_ts)),
+            vec![
+                "BTCUSDT@kline_1h-2023-11.csv".to_string(),
+                "BTCUSDT@kline_1h-2023-12.csv".to_string()
+            ]
+        );
+        assert_eq!(
+            filenames(manifest.files_in_range(day_ts + 3 * hour, month_ts - 1)),
+            Vec::<String>::new()
+        );
+
+        manifest.append(
+            "BTCUSDT@kline_1h-2023-11.csv",
+            &[test_kline(Interval::Hour1, day_ts + 3 * hour, 1.5)],
+        );
+        let entry = manifest.get("BTCUSDT@kline_1h-2023-11.csv").unwrap();
+        assert_eq!((entry.max_open_time, entry.rows), (day_ts + 3 * hour, 4));
+
+        manifest.record("BTCUSDT@kline_1h-2023-12.csv", &[]);
+        assert_eq!(manifest.rows(), 4);
+        assert!(manifest.files_in_range(month_ts, u64::MAX).is_empty());
+    }
+}
@@ -0,0 +1,272 @@
+use crate::error::StorageResult;
+use async_trait::async_trait;
+use log::info;
+use std::collections::HashMap;
+use std::io;
+use std::sync::Arc;
+
+use super::{
+    backup::BackupInfo,
+    compaction::KlineCompactionReport,
+    influx::InfluxStorage,
+    kline_query::KlineQuery,
+    manager::StorageManager,
+    retention::{RetentionPolicy, RetentionReport},
+    summary_query::StrategySummaryQuery,
+};
+use crate::audit::{AuditEntry, AuditQuery};
+use crate::market::external::ExternalDataPoint;
+use crate::{
+    account::{
+        daily_report::DailyReport,
+        trade::{OrderRecord, Position, PositionId, TradeTx},
+    },
+    market::{interval::Interval, kline::Kline, trade::Trade},
+    strategy::{
+        backer::BackTestRecord,
+        report::{BackTestReport, ReportFormat},
+        signal::{SignalHistoryQuery, SignalRecord},
+        strategy::{StrategyId, StrategyInfo, StrategyParamsChange, StrategySummary},
+    },
+};
+
+/// Storage manager mirroring the market data saved to a primary storage into InfluxDB, so
+/// Grafana dashboards can chart it next to any storage backend.
+///
+/// Everything is read from and saved to the primary storage. Failing to write to InfluxDB is
+/// logged, it never fails a save.
+pub struct MirroredStorage {
+    primary: Arc<Box<dyn StorageManager>>,
+    sink: Arc<InfluxStorage>,
+}
+
+impl MirroredStorage {
+    pub fn new(primary: Arc<Box<dyn StorageManager>>, sink: Arc<InfluxStorage>) -> Self {
+        Self { primary, sink }
+    }
+}
+
+#[async_trait]
+impl StorageManager for MirroredStorage {
+    async fn save_klines(
+        &self,
+        klines: &[Kline],
+        kline_key: &str,
+        is_bootstrap: bool,
+    ) -> io::Result<()> {
+        self.primary
+            .save_klines(klines, kline_key, is_bootstrap)
+            .await?;
+
+        if let Err(e) = self.sink.save_klines(klines, kline_key, is_bootstrap).await {
+            info!("Unable to mirror klines to InfluxDB, e: {e}");
+        }
+        Ok(())
+    }
+
+    async fn get_klines(
+        &self,
+        symbol: &str,
+        interval: Interval,
+        from_ts: Option<u64>,
+        to_ts: Option<u64>,
+    ) -> Vec<Kline> {
+        self.primary
+            .get_klines(symbol, interval, from_ts, to_ts)
+            .await
+    }
+
+    async fn query_klines(
+        &self,
+        symbol: &str,
+        interval: Interval,
+        query: &KlineQuery,
+    ) -> Vec<Kline> {
+        self.primary.query_klines(symbol, interval, query).await
+    }
+
+    async fn get_trades(
+        &self,
+        symbol: &str,
+        from_ts: Option<u64>,
+        to_ts: Option<u64>,
+    ) -> Vec<Trade> {
+        self.primary.get_trades(symbol, from_ts, to_ts).await
+    }
+
+    async fn save_trades(
+        &self,
+        trades: &[Trade],
+        trade_key: &str,
+        is_bootstrap: bool,
+    ) -> io::Result<()> {
+        self.primary
+            .save_trades(trades, trade_key, is_bootstrap)
+            .await?;
+
+        if let Err(e) = self.sink.save_trades(trades, trade_key, is_bootstrap).await {
+            info!("Unable to mirror trades to InfluxDB, e: {e}");
+        }
+        Ok(())
+    }
+
+    async fn flush(&self) -> StorageResult<()> {
+        self.primary.flush().await
+    }
+
+    async fn compact_klines(&self) -> StorageResult<KlineCompactionReport> {
+        self.primary.compact_klines().await
+    }
+
+    async fn apply_retention(&self, policy: &RetentionPolicy) -> StorageResult<RetentionReport> {
+        self.primary.apply_retention(policy).await
+    }
+
+    async fn create_backup(&self) -> StorageResult<BackupInfo> {
+        self.primary.create_backup().await
+    }
+
+    async fn list_backups(&self) -> StorageResult<Vec<BackupInfo>> {
+        self.primary.list_backups().await
+    }
+
+    async fn restore_backup(&self, name: &str) -> StorageResult<()> {
+        self.primary.restore_backup(name).await
+    }
+
+    async fn save_strategy_summary(&self, summary: StrategySummary) -> StorageResult<()> {
+        self.primary.save_strategy_summary(summary).await
+    }
+
+    async fn list_strategy_summaries(
+        &self,
+        query: &StrategySummaryQuery,
+    ) -> StorageResult<Vec<StrategySummary>> {
+        self.primary.list_strategy_summaries(query).await
+    }
+
+    async fn get_strategy_summary(
+        &self,
+        strategy_id: StrategyId,
+    ) -> StorageResult<StrategySummary> {
+        self.primary.get_strategy_summary(strategy_id).await
+    }
+
+    async fn save_active_strategy(&self, info: StrategyInfo) -> StorageResult<()> {
+        self.primary.save_active_strategy(info).await
+    }
+
+    async fn remove_active_strategy(&self, strategy_id: StrategyId) -> StorageResult<()> {
+        self.primary.remove_active_strategy(strategy_id).await
+    }
+
+    async fn list_active_strategies(&self) -> StorageResult<Vec<StrategyInfo>> {
+        self.primary.list_active_strategies().await
+    }
+
+    async fn save_strategy_params_change(&self, change: StrategyParamsChange) -> StorageResult<()> {
+        self.primary.save_strategy_params_change(change).await
+    }
+
+    async fn list_strategy_params_changes(
+        &self,
+        strategy_id: StrategyId,
+    ) -> StorageResult<Vec<StrategyParamsChange>> {
+        self.primary.list_strategy_params_changes(strategy_id).await
+    }
+
+    async fn save_back_test(&self, record: BackTestRecord) -> StorageResult<()> {
+        self.primary.save_back_test(record).await
+    }
+
+    async fn get_back_test(&self, id: StrategyId) -> StorageResult<BackTestRecord> {
+        self.primary.get_back_test(id).await
+    }
+
+    async fn list_back_tests(&self) -> StorageResult<Vec<BackTestRecord>> {
+        self.primary.list_back_tests().await
+    }
+
+    async fn save_back_test_report(&self, report: BackTestReport) -> StorageResult<()> {
+        self.primary.save_back_test_report(report).await
+    }
+
+    async fn get_back_test_report(
+        &self,
+        id: StrategyId,
+        format: ReportFormat,
+    ) -> StorageResult<BackTestReport> {
+        self.primary.get_back_test_report(id, format).await
+    }
+
+    async fn save_position(&self, account: &str, position: Position) -> StorageResult<()> {
+        self.primary.save_position(account, position).await
+    }
+
+    async fn remove_position(&self, account: &str, position_id: PositionId) -> StorageResult<()> {
+        self.primary.remove_position(account, position_id).await
+    }
+
+    async fn list_positions(&self, account: &str) -> StorageResult<Vec<Position>> {
+        self.primary.list_positions(account).await
+    }
+
+    async fn save_trade_tx(&self, account: &str, trade_tx: TradeTx) -> StorageResult<()> {
+        self.primary.save_trade_tx(account, trade_tx).await
+    }
+
+    async fn list_trade_txs(&self, account: &str) -> StorageResult<Vec<TradeTx>> {
+        self.primary.list_trade_txs(account).await
+    }
+
+    async fn save_order(&self, account: &str, order: OrderRecord) -> StorageResult<()> {
+        self.primary.save_order(account, order).await
+    }
+
+    async fn list_orders(&self, account: &str) -> StorageResult<Vec<OrderRecord>> {
+        self.primary.list_orders(account).await
+    }
+
+    async fn save_signal_record(&self, record: SignalRecord) -> StorageResult<()> {
+        self.primary.save_signal_record(record).await
+    }
+
+    async fn list_signal_records(
+        &self,
+        query: SignalHistoryQuery,
+    ) -> StorageResult<Vec<SignalRecord>> {
+        self.primary.list_signal_records(query).await
+    }
+
+    async fn save_daily_report(&self, report: DailyReport) -> StorageResult<()> {
+        self.primary.save_daily_report(report).await
+    }
+
+    async fn list_daily_reports(&self) -> StorageResult<Vec<DailyReport>> {
+        self.primary.list_daily_reports().await
+    }
+
+    async fn append_audit_entry(&self, entry: AuditEntry) -> StorageResult<()> {
+        self.primary.append_audit_entry(entry).await
+    }
+
+    async fn list_audit_entries(&self, query: AuditQuery) -> StorageResult<Vec<AuditEntry>> {
+        self.primary.list_audit_entries(query).await
+    }
+
+    async fn save_external_data(
+        &self,
+        series: &str,
+        points: &[ExternalDataPoint],
+    ) -> StorageResult<()> {
+        self.primary.save_external_data(series, points).await
+    }
+
+    async fn list_external_data(
+        &self,
+        from_ts: Option<u64>,
+        to_ts: Option<u64>,
+    ) -> StorageResult<HashMap<String, Vec<ExternalDataPoint>>> {
+        self.primary.list_external_data(from_ts, to_ts).await
+    }
+}
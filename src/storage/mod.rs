@@ -1,4 +1,22 @@
+pub mod backup;
+pub mod compaction;
+pub mod compression;
 pub mod fs;
+#[cfg(feature = "influx")]
 pub mod influx;
+pub mod kline_query;
 pub mod manager;
+pub mod manifest;
+#[cfg(feature = "influx")]
+pub mod mirror;
+#[cfg(feature = "mongo")]
 pub mod mongo;
+#[cfg(feature = "object-store")]
+pub mod object_store;
+#[cfg(feature = "parquet")]
+pub mod parquet;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+pub mod retention;
+pub mod summary_query;
+pub mod write_buffer;
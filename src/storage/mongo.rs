@@ -1,8 +1,25 @@
-use super::manager::StorageManager;
+use super::{
+    backup::BackupInfo,
+    compaction::KlineCompactionReport,
+    kline_query::KlineQuery,
+    manager::StorageManager,
+    retention::{RetentionPolicy, RetentionReport},
+    summary_query::StrategySummaryQuery,
+};
+use crate::audit::{AuditEntry, AuditQuery};
+use crate::market::external::ExternalDataPoint;
 use crate::{
-    account::trade::OrderSide,
-    market::{kline::Kline, trade::Trade},
-    strategy::strategy::{StrategyId, StrategyInfo, StrategySummary},
+    account::{
+        daily_report::DailyReport,
+        trade::{OrderRecord, OrderSide, Position, PositionId, TradeTx},
+    },
+    market::{interval::Interval, kline::Kline, trade::Trade},
+    strategy::{
+        backer::BackTestRecord,
+        report::{BackTestReport, ReportFormat},
+        signal::{SignalHistoryQuery, SignalRecord},
+        strategy::{StrategyId, StrategyInfo, StrategyParamsChange, StrategySummary},
+    },
     utils::{
         bson::{build_bson_kline_meta, build_bson_trade_meta},
         kline::build_kline_key,
@@ -12,6 +29,7 @@ use crate::{
 };
 use uuid::Uuid;
 
+use crate::error::{StorageError, StorageResult};
 use async_trait::async_trait;
 use futures::{TryFutureExt, TryStreamExt};
 use futures_util::StreamExt;
@@ -27,12 +45,13 @@ use mongodb::{
 use mongodb::{
     bson::{DateTime, Uuid as BsonUuid},
     options::{
-        CreateCollectionOptions, DeleteOptions, InsertOneOptions, TimeseriesOptions, UpdateOptions,
+        CreateCollectionOptions, DeleteOptions, FindOptions, InsertOneOptions, ReplaceOptions,
+        TimeseriesOptions, UpdateOptions,
     },
 };
 use mongodb::{Client, Collection};
 use serde::{Deserialize, Serialize};
-use std::error::Error;
+use std::collections::HashMap;
 use std::io;
 
 pub struct MongoDbStorage {
@@ -40,7 +59,7 @@ pub struct MongoDbStorage {
 }
 
 impl MongoDbStorage {
-    pub async fn new(uri: &str) -> Result<Self, Box<dyn Error>> {
+    pub async fn new(uri: &str) -> StorageResult<Self> {
         let client = Client::with_uri_str(uri).await?;
         let mut _self = MongoDbStorage { client };
 
@@ -82,6 +101,66 @@ impl MongoDbStorage {
             .collection("strategy_summary")
     }
 
+    fn active_strategy_collection(&self) -> Collection<StrategyInfo> {
+        self.client
+            .database("trading_db")
+            .collection("active_strategies")
+    }
+
+    fn back_test_collection(&self) -> Collection<BackTestRecord> {
+        self.client.database("trading_db").collection("back_tests")
+    }
+
+    fn back_test_report_collection(&self) -> Collection<BackTestReport> {
+        self.client
+            .database("trading_db")
+            .collection("back_test_reports")
+    }
+
+    fn strategy_params_collection(&self) -> Collection<StrategyParamsChange> {
+        self.client
+            .database("trading_db")
+            .collection("strategy_params_history")
+    }
+
+    fn position_collection(&self) -> Collection<BsonAccountRecord<Position>> {
+        self.client
+            .database("trading_db")
+            .collection("account_positions")
+    }
+
+    fn trade_tx_collection(&self) -> Collection<BsonAccountRecord<TradeTx>> {
+        self.client
+            .database("trading_db")
+            .collection("account_trades")
+    }
+
+    fn order_collection(&self) -> Collection<BsonAccountRecord<OrderRecord>> {
+        self.client
+            .database("trading_db")
+            .collection("account_orders")
+    }
+
+    /// Lists the records of an account in the order they were inserted.
+    async fn list_account_records<T>(
+        &self,
+        collection: Collection<BsonAccountRecord<T>>,
+        account: &str,
+    ) -> StorageResult<Vec<T>>
+    where
+        T: for<'de> Deserialize<'de> + Unpin + Send + Sync,
+    {
+        let options = FindOptions::builder().sort(doc! {"_id": 1}).build();
+        let mut cursor = collection.find(doc! {"account": account}, options).await?;
+
+        let mut records = vec![];
+        while let Some(document) = cursor.try_next().await? {
+            records.push(document.record);
+        }
+
+        Ok(records)
+    }
+
     async fn init_timeseries_collection(
         &self,
         collection_name: &str,
@@ -119,7 +198,7 @@ impl StorageManager for MongoDbStorage {
     async fn get_klines(
         &self,
         symbol: &str,
-        interval: &str,
+        interval: Interval,
         from_ts: Option<u64>,
         to_ts: Option<u64>,
     ) -> Vec<Kline> {
@@ -134,7 +213,7 @@ impl StorageManager for MongoDbStorage {
 
         let mut query = doc! {
             "symbol": symbol,
-            "interval": interval,
+            "interval": interval.as_str(),
         };
 
         if let Some(from_ts) = from_ts {
@@ -196,6 +275,18 @@ impl StorageManager for MongoDbStorage {
         Ok(())
     }
 
+    async fn query_klines(
+        &self,
+        symbol: &str,
+        interval: Interval,
+        query: &KlineQuery,
+    ) -> Vec<Kline> {
+        query.apply(
+            self.get_klines(symbol, interval, query.from_ts, query.to_ts)
+                .await,
+        )
+    }
+
     // TODO: Docs
     async fn get_trades(
         &self,
@@ -340,27 +431,288 @@ impl StorageManager for MongoDbStorage {
         Ok(())
     }
 
-    async fn list_saved_strategies(&self) -> Result<Vec<StrategyInfo>, Box<dyn Error>> {
-        unimplemented!()
+    async fn flush(&self) -> StorageResult<()> {
+        // market data is written on save, nothing is buffered
+        Ok(())
+    }
+
+    async fn compact_klines(&self) -> StorageResult<KlineCompactionReport> {
+        // TODO: Implement compact klines on MongoDbStorage
+        Err(StorageError::Unsupported("MongoDbStorage"))
+    }
+
+    async fn apply_retention(&self, _policy: &RetentionPolicy) -> StorageResult<RetentionReport> {
+        // TODO: Implement retention on MongoDbStorage
+        Err(StorageError::Unsupported("MongoDbStorage"))
+    }
+
+    async fn create_backup(&self) -> StorageResult<BackupInfo> {
+        // TODO: Implement backups on MongoDbStorage, until then use `mongodump`
+        Err(StorageError::Unsupported("MongoDbStorage"))
+    }
+
+    async fn list_backups(&self) -> StorageResult<Vec<BackupInfo>> {
+        // TODO: Implement backups on MongoDbStorage
+        Err(StorageError::Unsupported("MongoDbStorage"))
     }
-    async fn save_strategy_summary(&self, _summary: StrategySummary) -> Result<(), Box<dyn Error>> {
+
+    async fn restore_backup(&self, _name: &str) -> StorageResult<()> {
+        // TODO: Implement backups on MongoDbStorage
+        Err(StorageError::Unsupported("MongoDbStorage"))
+    }
+
+    async fn save_strategy_summary(&self, _summary: StrategySummary) -> StorageResult<()> {
         // TODO: Implement save strategy summary on DBStorageManager
         unimplemented!()
     }
+    async fn list_strategy_summaries(
+        &self,
+        _query: &StrategySummaryQuery,
+    ) -> StorageResult<Vec<StrategySummary>> {
+        // TODO: Implement list strategy summaries on MongoDbStorage
+        Err(StorageError::Unsupported("MongoDbStorage"))
+    }
     async fn get_strategy_summary(
         &self,
         _strategy_id: StrategyId,
-    ) -> Result<StrategySummary, Box<dyn Error>> {
+    ) -> StorageResult<StrategySummary> {
         // TODO: Implement get strategy summary on DBStorageManager
         unimplemented!()
     }
+
+    async fn save_active_strategy(&self, info: StrategyInfo) -> StorageResult<()> {
+        let collection = self.active_strategy_collection();
+        let query = doc! {"id": to_bson(&info.id)?};
+        let options = ReplaceOptions::builder().upsert(true).build();
+
+        collection.replace_one(query, info, options).await?;
+
+        Ok(())
+    }
+
+    async fn remove_active_strategy(&self, strategy_id: StrategyId) -> StorageResult<()> {
+        let collection = self.active_strategy_collection();
+        let query = doc! {"id": to_bson(&strategy_id)?};
+
+        collection.delete_one(query, None).await?;
+
+        Ok(())
+    }
+
+    async fn list_active_strategies(&self) -> StorageResult<Vec<StrategyInfo>> {
+        let collection = self.active_strategy_collection();
+        let mut cursor = collection.find(None, None).await?;
+
+        let mut strategies = vec![];
+        while let Some(info) = cursor.try_next().await? {
+            strategies.push(info);
+        }
+
+        Ok(strategies)
+    }
+
+    async fn save_strategy_params_change(&self, change: StrategyParamsChange) -> StorageResult<()> {
+        self.strategy_params_collection()
+            .insert_one(change, None)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn list_strategy_params_changes(
+        &self,
+        strategy_id: StrategyId,
+    ) -> StorageResult<Vec<StrategyParamsChange>> {
+        let collection = self.strategy_params_collection();
+        let query = doc! {"strategy_id": to_bson(&strategy_id)?};
+        let options = FindOptions::builder().sort(doc! {"timestamp": 1}).build();
+        let mut cursor = collection.find(query, options).await?;
+
+        let mut changes = vec![];
+        while let Some(change) = cursor.try_next().await? {
+            changes.push(change);
+        }
+
+        Ok(changes)
+    }
+
+    async fn save_back_test(&self, record: BackTestRecord) -> StorageResult<()> {
+        self.back_test_collection().insert_one(record, None).await?;
+
+        Ok(())
+    }
+
+    async fn get_back_test(&self, id: StrategyId) -> StorageResult<BackTestRecord> {
+        let query = doc! {"summary.info.id": to_bson(&id)?};
+
+        match self.back_test_collection().find_one(query, None).await? {
+            Some(record) => Ok(record),
+            None => Err(StorageError::NotFound(format!("Back test {id}"))),
+        }
+    }
+
+    async fn list_back_tests(&self) -> StorageResult<Vec<BackTestRecord>> {
+        let options = FindOptions::builder().sort(doc! {"created_at": 1}).build();
+        let mut cursor = self.back_test_collection().find(None, options).await?;
+
+        let mut records = vec![];
+        while let Some(record) = cursor.try_next().await? {
+            records.push(record);
+        }
+
+        Ok(records)
+    }
+
+    async fn save_back_test_report(&self, report: BackTestReport) -> StorageResult<()> {
+        let query = doc! {"id": to_bson(&report.id)?, "format": to_bson(&report.format)?};
+        let options = ReplaceOptions::builder().upsert(true).build();
+
+        self.back_test_report_collection()
+            .replace_one(query, report, options)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_back_test_report(
+        &self,
+        id: StrategyId,
+        format: ReportFormat,
+    ) -> StorageResult<BackTestReport> {
+        let query = doc! {"id": to_bson(&id)?, "format": to_bson(&format)?};
+
+        match self
+            .back_test_report_collection()
+            .find_one(query, None)
+            .await?
+        {
+            Some(report) => Ok(report),
+            None => Err(StorageError::NotFound(format!("Back test report {id}"))),
+        }
+    }
+
+    async fn save_position(&self, account: &str, position: Position) -> StorageResult<()> {
+        let query = doc! {"account": account, "record.id": to_bson(&position.id)?};
+        let options = ReplaceOptions::builder().upsert(true).build();
+        let document = BsonAccountRecord {
+            account: account.to_string(),
+            record: position,
+        };
+
+        self.position_collection()
+            .replace_one(query, document, options)
+            .await?;
+
+        Ok(())
+    }
+    async fn remove_position(&self, account: &str, position_id: PositionId) -> StorageResult<()> {
+        let query = doc! {"account": account, "record.id": to_bson(&position_id)?};
+
+        self.position_collection().delete_one(query, None).await?;
+
+        Ok(())
+    }
+    async fn list_positions(&self, account: &str) -> StorageResult<Vec<Position>> {
+        self.list_account_records(self.position_collection(), account)
+            .await
+    }
+
+    async fn save_trade_tx(&self, account: &str, trade_tx: TradeTx) -> StorageResult<()> {
+        let document = BsonAccountRecord {
+            account: account.to_string(),
+            record: trade_tx,
+        };
+
+        self.trade_tx_collection()
+            .insert_one(document, None)
+            .await?;
+
+        Ok(())
+    }
+    async fn list_trade_txs(&self, account: &str) -> StorageResult<Vec<TradeTx>> {
+        self.list_account_records(self.trade_tx_collection(), account)
+            .await
+    }
+
+    async fn save_order(&self, account: &str, order: OrderRecord) -> StorageResult<()> {
+        let document = BsonAccountRecord {
+            account: account.to_string(),
+            record: order,
+        };
+
+        self.order_collection().insert_one(document, None).await?;
+
+        Ok(())
+    }
+    async fn list_orders(&self, account: &str) -> StorageResult<Vec<OrderRecord>> {
+        self.list_account_records(self.order_collection(), account)
+            .await
+    }
+
+    async fn save_signal_record(&self, _record: SignalRecord) -> StorageResult<()> {
+        // TODO: Implement save signal record on MongoDbStorage
+        Err(StorageError::Unsupported("MongoDbStorage"))
+    }
+
+    async fn list_signal_records(
+        &self,
+        _query: SignalHistoryQuery,
+    ) -> StorageResult<Vec<SignalRecord>> {
+        // TODO: Implement list signal records on MongoDbStorage
+        Err(StorageError::Unsupported("MongoDbStorage"))
+    }
+
+    async fn save_daily_report(&self, _report: DailyReport) -> StorageResult<()> {
+        // TODO: Implement save daily report on MongoDbStorage
+        Err(StorageError::Unsupported("MongoDbStorage"))
+    }
+
+    async fn list_daily_reports(&self) -> StorageResult<Vec<DailyReport>> {
+        // TODO: Implement list daily reports on MongoDbStorage
+        Err(StorageError::Unsupported("MongoDbStorage"))
+    }
+
+    async fn append_audit_entry(&self, _entry: AuditEntry) -> StorageResult<()> {
+        // TODO: Implement append audit entry on MongoDbStorage
+        Err(StorageError::Unsupported("MongoDbStorage"))
+    }
+
+    async fn list_audit_entries(&self, _query: AuditQuery) -> StorageResult<Vec<AuditEntry>> {
+        // TODO: Implement list audit entries on MongoDbStorage
+        Err(StorageError::Unsupported("MongoDbStorage"))
+    }
+
+    async fn save_external_data(
+        &self,
+        _series: &str,
+        _points: &[ExternalDataPoint],
+    ) -> StorageResult<()> {
+        // TODO: Implement save external data on MongoDbStorage
+        Err(StorageError::Unsupported("MongoDbStorage"))
+    }
+
+    async fn list_external_data(
+        &self,
+        _from_ts: Option<u64>,
+        _to_ts: Option<u64>,
+    ) -> StorageResult<HashMap<String, Vec<ExternalDataPoint>>> {
+        // TODO: Implement list external data on MongoDbStorage
+        Err(StorageError::Unsupported("MongoDbStorage"))
+    }
+}
+
+/// A position, trade or order stored with the name of the account it belongs to.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BsonAccountRecord<T> {
+    pub account: String,
+    pub record: T,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct BsonKline {
     pub metadata: String,
     pub symbol: String,
-    pub interval: String,
+    pub interval: Interval,
     pub open: f64,
     pub high: f64,
     pub low: f64,
@@ -0,0 +1,1074 @@
+use crate::error::{StorageError, StorageResult};
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use log::info;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::sync::Arc;
+use url::Url;
+use uuid::Uuid;
+
+use ::object_store::{parse_url_opts, path::Path, ObjectMeta, ObjectStore, PutPayload};
+
+use super::{
+    backup::{archive_files, backup_created_at, build_backup_name, read_archive_files, BackupInfo},
+    compaction::{
+        compact_klines, kline_file_key, kline_file_month, KlineAnomaly, KlineCompactionReport,
+    },
+    kline_query::{KlineQuery, SortOrder},
+    manager::StorageManager,
+    retention::{
+        kline_key_interval, month_start_ts, trade_file_day_ts, RetentionPolicy, RetentionReport,
+    },
+    summary_query::StrategySummaryQuery,
+};
+use crate::audit::{AuditEntry, AuditQuery};
+use crate::market::external::ExternalDataPoint;
+use crate::{
+    account::{
+        daily_report::DailyReport,
+        trade::{OrderRecord, Position, PositionId, TradeTx},
+    },
+    market::{interval::Interval, kline::Kline, trade::Trade},
+    strategy::{
+        backer::BackTestRecord,
+        report::{BackTestReport, ReportFormat},
+        signal::{SignalHistoryQuery, SignalRecord},
+        strategy::{StrategyId, StrategyInfo, StrategyParamsChange, StrategySummary},
+    },
+    utils::{
+        kline::{
+            build_kline_filename, build_kline_key, build_kline_month_string,
+            generate_kline_filenames_in_range,
+        },
+        time::{floor_mili_ts, floor_month_ts, generate_ts, DAY_AS_MILI},
+        trade::{
+            build_market_trade_filename, build_market_trade_key, generate_trade_filenames_in_range,
+        },
+    },
+};
+
+/// Storage manager keeping data in an object store such as S3, GCS or MinIO, so bots on
+/// ephemeral machines don't lose their k-line archives and strategy summaries.
+///
+/// Objects are laid out like the directories of `FsStorage` below the prefix of the store URL:
+/// k-lines as monthly and market trades as daily CSV objects, everything else as JSON objects.
+/// Object stores can't append, saving market data rewrites the objects it falls into.
+pub struct ObjectStoreStorage {
+    store: Arc<dyn ObjectStore>,
+    prefix: Path,
+}
+
+impl ObjectStoreStorage {
+    /// Creates a new `ObjectStoreStorage` from a store URL.
+    ///
+    /// Credentials and endpoints are read from `AWS_*` and `GOOGLE_*` environment variables,
+    /// e.g. `AWS_ACCESS_KEY_ID`, `AWS_ENDPOINT` and `AWS_ALLOW_HTTP` for MinIO or
+    /// `GOOGLE_SERVICE_ACCOUNT`.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL of the store and prefix, e.g. `s3://bucket/raderbot` or `gs://bucket`.
+    ///
+    /// # Returns
+    ///
+    /// The `ObjectStoreStorage`, or an error if the URL or configuration is invalid.
+    pub fn new(url: &str) -> StorageResult<Self> {
+        let url = Url::parse(url)
+            .map_err(|e| StorageError::Backend(format!("Invalid URL {url}, e: {e}")))?;
+        let options = std::env::vars()
+            .filter(|(key, _)| key.starts_with("AWS_") || key.starts_with("GOOGLE_"))
+            .map(|(key, value)| (key.to_ascii_lowercase(), value));
+
+        let (store, prefix) = parse_url_opts(&url, options)?;
+
+        Ok(Self::with_store(Arc::from(store), prefix))
+    }
+
+    /// Creates a new `ObjectStoreStorage` on an existing store.
+    ///
+    /// # Arguments
+    ///
+    /// * `store` - The object store.
+    /// * `prefix` - The path all objects are stored below.
+    pub fn with_store(store: Arc<dyn ObjectStore>, prefix: Path) -> Self {
+        Self { store, prefix }
+    }
+
+    // ---
+    // Private Methods
+    // ---
+
+    fn path(&self, parts: &[&str]) -> Path {
+        parts
+            .iter()
+            .fold(self.prefix.clone(), |path, part| path.child(*part))
+    }
+
+    /// Reads an object, `None` if it doesn't exist.
+    async fn get_bytes(&self, path: &Path) -> StorageResult<Option<Vec<u8>>> {
+        match self.store.get(path).await {
+            Ok(result) => Ok(Some(result.bytes().await?.to_vec())),
+            Err(::object_store::Error::NotFound { .. }) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn put_bytes(&self, path: &Path, bytes: Vec<u8>) -> StorageResult<()> {
+        self.store.put(path, PutPayload::from(bytes)).await?;
+        Ok(())
+    }
+
+    async fn get_json<T: DeserializeOwned>(&self, path: &Path) -> StorageResult<T> {
+        match self.get_bytes(path).await? {
+            Some(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            None => Err(StorageError::NotFound(format!("Object {path}"))),
+        }
+    }
+
+    async fn put_json<T: Serialize>(&self, path: &Path, value: &T) -> StorageResult<()> {
+        self.put_bytes(path, serde_json::to_vec(value)?).await
+    }
+
+    /// Reads every JSON object directly below a directory.
+    async fn list_json<T: DeserializeOwned>(&self, dir: &Path) -> StorageResult<Vec<T>> {
+        let listing = self.store.list_with_delimiter(Some(dir)).await?;
+
+        let mut values = vec![];
+        for object in listing.objects {
+            if object.location.extension() == Some("json") {
+                values.push(self.get_json(&object.location).await?);
+            }
+        }
+        Ok(values)
+    }
+
+    /// Lists every object below the prefix except the backups, with their paths relative to
+    /// the prefix.
+    async fn list_data_objects(&self) -> StorageResult<Vec<(String, ObjectMeta)>> {
+        let objects: Vec<ObjectMeta> = self.store.list(Some(&self.prefix)).try_collect().await?;
+
+        let mut data_objects = vec![];
+        for object in objects {
+            let parts: Vec<String> = match object.location.prefix_match(&self.prefix) {
+                Some(parts) => parts.map(|part| part.as_ref().to_string()).collect(),
+                None => continue,
+            };
+            if parts.first().is_some_and(|part| part == "backups") {
+                continue;
+            }
+            data_objects.push((parts.join("/"), object));
+        }
+        Ok(data_objects)
+    }
+
+    async fn get_csv<T: DeserializeOwned>(&self, path: &Path) -> StorageResult<Vec<T>> {
+        let bytes = match self.get_bytes(path).await? {
+            Some(bytes) => bytes,
+            None => return Ok(vec![]),
+        };
+
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .from_reader(bytes.as_slice());
+        let rows = reader.deserialize().collect::<Result<Vec<T>, _>>()?;
+        Ok(rows)
+    }
+
+    async fn put_csv<T: Serialize>(&self, path: &Path, rows: &[T]) -> StorageResult<()> {
+        let mut writer = csv::WriterBuilder::new()
+            .has_headers(false)
+            .from_writer(vec![]);
+        for row in rows {
+            writer.serialize(row)?;
+        }
+        let bytes = writer
+            .into_inner()
+            .map_err(|e| StorageError::Serialization(e.to_string()))?;
+        self.put_bytes(path, bytes).await
+    }
+}
+
+#[async_trait]
+impl StorageManager for ObjectStoreStorage {
+    async fn save_klines(
+        &self,
+        klines: &[Kline],
+        kline_key: &str,
+        _is_bootstrap: bool,
+    ) -> io::Result<()> {
+        let mut klines_by_month: BTreeMap<u64, Vec<&Kline>> = BTreeMap::new();
+        for kline in klines {
+            klines_by_month
+                .entry(floor_month_ts(kline.open_time))
+                .or_default()
+                .push(kline);
+        }
+
+        for (month_ts, klines) in klines_by_month {
+            let path = self.path(&[
+                "market",
+                "klines",
+                &build_kline_filename(kline_key, month_ts),
+            ]);
+
+            // key by open time, saved klines replace stored ones
+            let result = async {
+                let mut merged: BTreeMap<u64, Kline> = self
+                    .get_csv::<Kline>(&path)
+                    .await?
+                    .into_iter()
+                    .map(|kline| (kline.open_time, kline))
+                    .collect();
+                for kline in klines {
+                    merged.insert(kline.open_time, kline.clone());
+                }
+
+                let klines: Vec<Kline> = merged.into_values().collect();
+                self.put_csv(&path, &klines).await
+            }
+            .await;
+
+            result.map_err(|e| io::Error::other(e.to_string()))?;
+        }
+
+        Ok(())
+    }
+
+    async fn get_klines(
+        &self,
+        symbol: &str,
+        interval: Interval,
+        from_ts: Option<u64>,
+        to_ts: Option<u64>,
+    ) -> Vec<Kline> {
+        let from_ts = match from_ts {
+            Some(from_ts) => from_ts,
+            None => return vec![],
+        };
+        let to_ts = to_ts.unwrap_or_else(generate_ts);
+        let kline_key = build_kline_key(symbol, interval);
+
+        let mut klines = vec![];
+        for filename in generate_kline_filenames_in_range(&kline_key, from_ts, to_ts) {
+            let path = self.path(&["market", "klines", &filename]);
+            match self.get_csv::<Kline>(&path).await {
+                Ok(month) => klines.extend(
+                    month
+                        .into_iter()
+                        .filter(|kline| kline.open_time >= from_ts && kline.open_time <= to_ts),
+                ),
+                Err(e) => info!("Unable to load klines from {path}, e: {e}"),
+            }
+        }
+
+        klines
+    }
+
+    async fn query_klines(
+        &self,
+        symbol: &str,
+        interval: Interval,
+        query: &KlineQuery,
+    ) -> Vec<Kline> {
+        let kline_key = build_kline_key(symbol, interval);
+        let from_month = query.from_ts.map(build_kline_month_string);
+        let to_month = query.to_ts.map(build_kline_month_string);
+
+        let klines_dir = self.path(&["market", "klines"]);
+        let listing = match self.store.list_with_delimiter(Some(&klines_dir)).await {
+            Ok(listing) => listing,
+            Err(e) => {
+                info!("Unable to list klines in {klines_dir}, e: {e}");
+                return vec![];
+            }
+        };
+
+        // monthly objects of the kline key within the range, oldest first
+        let mut months: Vec<(String, Path)> = listing
+            .objects
+            .into_iter()
+            .filter_map(|object| {
+                let filename = object.location.filename()?;
+                let month = kline_file_month(filename)?.to_string();
+                (kline_file_key(filename)? == kline_key).then_some((month, object.location))
+            })
+            .filter(|(month, _)| {
+                from_month
+                    .as_ref()
+                    .is_none_or(|from_month| month >= from_month)
+                    && to_month.as_ref().is_none_or(|to_month| month <= to_month)
+            })
+            .collect();
+        months.sort();
+        if query.sort_order() == SortOrder::Desc {
+            months.reverse();
+        }
+
+        let mut klines = vec![];
+        let mut loaded = 0;
+        for (_, path) in months {
+            if query.is_filled(loaded) {
+                break;
+            }
+            match self.get_csv::<Kline>(&path).await {
+                Ok(month) => {
+                    loaded += query.count_in_range(&month);
+                    klines.extend(month);
+                }
+                Err(e) => info!("Unable to load klines from {path}, e: {e}"),
+            }
+        }
+
+        query.apply(klines)
+    }
+
+    async fn get_trades(
+        &self,
+        symbol: &str,
+        from_ts: Option<u64>,
+        to_ts: Option<u64>,
+    ) -> Vec<Trade> {
+        let from_ts = match from_ts {
+            Some(from_ts) => from_ts,
+            None => return vec![],
+        };
+        let to_ts = to_ts.unwrap_or_else(generate_ts);
+        let trade_key = build_market_trade_key(symbol);
+
+        let mut trades = vec![];
+        for filename in generate_trade_filenames_in_range(&trade_key, from_ts, to_ts) {
+            let path = self.path(&["market", "trades", &filename]);
+            match self.get_csv::<Trade>(&path).await {
+                Ok(day) => trades.extend(
+                    day.into_iter()
+                        .filter(|trade| trade.timestamp >= from_ts && trade.timestamp <= to_ts),
+                ),
+                Err(e) => info!("Unable to load trades from {path}, e: {e}"),
+            }
+        }
+
+        trades
+    }
+
+    async fn save_trades(
+        &self,
+        trades: &[Trade],
+        trade_key: &str,
+        _is_bootstrap: bool,
+    ) -> io::Result<()> {
+        let mut trades_by_day: BTreeMap<u64, Vec<Trade>> = BTreeMap::new();
+        for trade in trades {
+            trades_by_day
+                .entry(floor_mili_ts(trade.timestamp, DAY_AS_MILI))
+                .or_default()
+                .push(trade.clone());
+        }
+
+        for (day_ts, mut trades) in trades_by_day {
+            let path = self.path(&[
+                "market",
+                "trades",
+                &build_market_trade_filename(trade_key, day_ts),
+            ]);
+
+            // market trades have no ID, keep stored trades before the first saved one like
+            // `FsStorage`
+            let result = async {
+                trades.sort_by_key(|trade| trade.timestamp);
+                let first_ts = trades[0].timestamp;
+
+                let mut merged: Vec<Trade> = self
+                    .get_csv::<Trade>(&path)
+                    .await?
+                    .into_iter()
+                    .filter(|trade| trade.timestamp < first_ts)
+                    .collect();
+                merged.sort_by_key(|trade| trade.timestamp);
+                merged.extend(trades);
+
+                self.put_csv(&path, &merged).await
+            }
+            .await;
+
+            result.map_err(|e| io::Error::other(e.to_string()))?;
+        }
+
+        Ok(())
+    }
+
+    async fn flush(&self) -> StorageResult<()> {
+        // objects are put on save, nothing is buffered
+        Ok(())
+    }
+
+    async fn compact_klines(&self) -> StorageResult<KlineCompactionReport> {
+        let mut report = KlineCompactionReport::default();
+
+        let listing = self
+            .store
+            .list_with_delimiter(Some(&self.path(&["market", "klines"])))
+            .await?;
+        for object in listing.objects {
+            let filename = match object.location.filename() {
+                Some(filename) if filename.ends_with(".csv") => filename.to_string(),
+                _ => continue,
+            };
+
+            let klines = match self.get_csv::<Kline>(&object.location).await {
+                Ok(klines) => klines,
+                Err(e) => {
+                    let error = e.to_string();
+                    report.add_anomaly(&filename, KlineAnomaly::Unreadable { error });
+                    continue;
+                }
+            };
+
+            let compacted = compact_klines(klines, kline_file_month(&filename));
+            if compacted.changed {
+                self.put_csv(&object.location, &compacted.klines).await?;
+            }
+            report.add_file(&filename, &compacted);
+        }
+
+        Ok(report)
+    }
+
+    async fn apply_retention(&self, policy: &RetentionPolicy) -> StorageResult<RetentionReport> {
+        let now = generate_ts();
+        let mut report = RetentionReport::default();
+
+        let listing = self
+            .store
+            .list_with_delimiter(Some(&self.path(&["market", "klines"])))
+            .await?;
+        for object in listing.objects {
+            let filename = object.location.filename().unwrap_or_default();
+            let cutoff = kline_file_key(filename)
+                .and_then(kline_key_interval)
+                .and_then(|interval| policy.kline_cutoff(interval, now));
+            let month_ts = kline_file_month(filename).and_then(month_start_ts);
+            let cutoff = match (cutoff, month_ts) {
+                // objects of months starting after the cutoff hold no expired klines
+                (Some(cutoff), Some(month_ts)) if month_ts < cutoff => cutoff,
+                _ => continue,
+            };
+
+            let klines = self.get_csv::<Kline>(&object.location).await?;
+            let kept: Vec<Kline> = klines
+                .iter()
+                .filter(|kline| kline.open_time >= cutoff)
+                .cloned()
+                .collect();
+            if kept.len() == klines.len() {
+                continue;
+            }
+
+            report.klines_removed += klines.len() - kept.len();
+            if kept.is_empty() {
+                self.store.delete(&object.location).await?;
+                report.files_removed += 1;
+            } else {
+                self.put_csv(&object.location, &kept).await?;
+                report.files_rewritten += 1;
+            }
+        }
+
+        if let Some(cutoff) = policy.trade_cutoff(now) {
+            let listing = self
+                .store
+                .list_with_delimiter(Some(&self.path(&["market", "trades"])))
+                .await?;
+            for object in listing.objects {
+                let expired = object
+                    .location
+                    .filename()
+                    .and_then(trade_file_day_ts)
+                    .is_some_and(|day_ts| day_ts + DAY_AS_MILI <= cutoff);
+                if !expired {
+                    continue;
+                }
+
+                report.trades_removed += self.get_csv::<Trade>(&object.location).await?.len();
+                self.store.delete(&object.location).await?;
+                report.files_removed += 1;
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Archives every object into a backup object below `backups`.
+    async fn create_backup(&self) -> StorageResult<BackupInfo> {
+        let objects = self.list_data_objects().await?;
+        let mut files = vec![];
+        for (relative_path, object) in objects {
+            if let Some(bytes) = self.get_bytes(&object.location).await? {
+                files.push((relative_path, bytes));
+            }
+        }
+
+        let archive = archive_files(&files)?;
+        let created_at = generate_ts();
+        let name = build_backup_name(created_at);
+        let size = archive.len() as u64;
+        self.put_bytes(&self.path(&["backups", &name]), archive)
+            .await?;
+
+        Ok(BackupInfo {
+            name,
+            created_at,
+            size,
+        })
+    }
+
+    async fn list_backups(&self) -> StorageResult<Vec<BackupInfo>> {
+        let listing = self
+            .store
+            .list_with_delimiter(Some(&self.path(&["backups"])))
+            .await?;
+
+        let mut backups: Vec<BackupInfo> = listing
+            .objects
+            .into_iter()
+            .filter_map(|object| {
+                let name = object.location.filename()?.to_string();
+                Some(BackupInfo {
+                    created_at: backup_created_at(&name)?,
+                    name,
+                    size: object.size as u64,
+                })
+            })
+            .collect();
+        backups.sort_by_key(|backup| Reverse(backup.created_at));
+
+        Ok(backups)
+    }
+
+    /// Replaces every object with the objects of a backup. The archive is read before anything
+    /// is removed, so a broken archive leaves the data untouched.
+    async fn restore_backup(&self, name: &str) -> StorageResult<()> {
+        let bytes = match backup_created_at(name) {
+            Some(_) => self.get_bytes(&self.path(&["backups", name])).await?,
+            None => None,
+        };
+        let files = read_archive_files(
+            &bytes.ok_or_else(|| StorageError::NotFound(format!("Backup {name}")))?,
+        )?;
+
+        // objects created after the backup must not survive the restore
+        let objects = self.list_data_objects().await?;
+        for (_, object) in objects {
+            self.store.delete(&object.location).await?;
+        }
+
+        for (relative_path, bytes) in files {
+            // archived paths are already encoded, parse them instead of encoding them again
+            let path: Path = self
+                .prefix
+                .parts()
+                .chain(Path::parse(&relative_path)?.parts())
+                .collect();
+            self.put_bytes(&path, bytes).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn list_strategy_summaries(
+        &self,
+        query: &StrategySummaryQuery,
+    ) -> StorageResult<Vec<StrategySummary>> {
+        let summaries: Vec<StrategySummary> = self.list_json(&self.path(&["strategies"])).await?;
+
+        Ok(query.apply(summaries))
+    }
+
+    async fn save_strategy_summary(&self, summary: StrategySummary) -> StorageResult<()> {
+        let path = self.path(&["strategies", &format!("{}.json", summary.info.id)]);
+        self.put_json(&path, &summary).await
+    }
+
+    async fn get_strategy_summary(
+        &self,
+        strategy_id: StrategyId,
+    ) -> StorageResult<StrategySummary> {
+        let path = self.path(&["strategies", &format!("{strategy_id}.json")]);
+        self.get_json(&path).await
+    }
+
+    async fn save_active_strategy(&self, info: StrategyInfo) -> StorageResult<()> {
+        let path = self.path(&["active_strategies", &format!("{}.json", info.id)]);
+        self.put_json(&path, &info).await
+    }
+
+    async fn remove_active_strategy(&self, strategy_id: StrategyId) -> StorageResult<()> {
+        let path = self.path(&["active_strategies", &format!("{strategy_id}.json")]);
+        match self.store.delete(&path).await {
+            Ok(()) | Err(::object_store::Error::NotFound { .. }) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn list_active_strategies(&self) -> StorageResult<Vec<StrategyInfo>> {
+        self.list_json(&self.path(&["active_strategies"])).await
+    }
+
+    async fn save_strategy_params_change(&self, change: StrategyParamsChange) -> StorageResult<()> {
+        let mut changes = self
+            .list_strategy_params_changes(change.strategy_id)
+            .await?;
+        let path = self.path(&["strategy_params", &format!("{}.json", change.strategy_id)]);
+
+        changes.push(change);
+        changes.sort_by_key(|change| change.timestamp);
+        self.put_json(&path, &changes).await
+    }
+
+    async fn list_strategy_params_changes(
+        &self,
+        strategy_id: StrategyId,
+    ) -> StorageResult<Vec<StrategyParamsChange>> {
+        let path = self.path(&["strategy_params", &format!("{strategy_id}.json")]);
+        match self.get_bytes(&path).await? {
+            Some(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            None => Ok(vec![]),
+        }
+    }
+
+    async fn save_back_test(&self, record: BackTestRecord) -> StorageResult<()> {
+        let path = self.path(&["back_tests", &format!("{}.json", record.id())]);
+        self.put_json(&path, &record).await
+    }
+
+    async fn get_back_test(&self, id: StrategyId) -> StorageResult<BackTestRecord> {
+        let path = self.path(&["back_tests", &format!("{id}.json")]);
+        self.get_json(&path).await
+    }
+
+    async fn list_back_tests(&self) -> StorageResult<Vec<BackTestRecord>> {
+        let mut records: Vec<BackTestRecord> = self.list_json(&self.path(&["back_tests"])).await?;
+        records.sort_by_key(|record| record.created_at);
+
+        Ok(records)
+    }
+
+    async fn save_back_test_report(&self, report: BackTestReport) -> StorageResult<()> {
+        let path = self.path(&[
+            "back_tests",
+            "reports",
+            &format!("{}.{}.json", report.id, report.format.extension()),
+        ]);
+        self.put_json(&path, &report).await
+    }
+
+    async fn get_back_test_report(
+        &self,
+        id: StrategyId,
+        format: ReportFormat,
+    ) -> StorageResult<BackTestReport> {
+        let path = self.path(&[
+            "back_tests",
+            "reports",
+            &format!("{id}.{}.json", format.extension()),
+        ]);
+        self.get_json(&path).await
+    }
+
+    async fn save_position(&self, account: &str, position: Position) -> StorageResult<()> {
+        let path = self.path(&[
+            "accounts",
+            account,
+            "positions",
+            &format!("{}.json", position.id),
+        ]);
+        self.put_json(&path, &position).await
+    }
+
+    async fn remove_position(&self, account: &str, position_id: PositionId) -> StorageResult<()> {
+        let path = self.path(&[
+            "accounts",
+            account,
+            "positions",
+            &format!("{position_id}.json"),
+        ]);
+        match self.store.delete(&path).await {
+            Ok(()) | Err(::object_store::Error::NotFound { .. }) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn list_positions(&self, account: &str) -> StorageResult<Vec<Position>> {
+        self.list_json(&self.path(&["accounts", account, "positions"]))
+            .await
+    }
+
+    // objects can't be appended to, every trade and order is an object of its own
+
+    async fn save_trade_tx(&self, account: &str, trade_tx: TradeTx) -> StorageResult<()> {
+        let path = self.path(&[
+            "accounts",
+            account,
+            "trades",
+            &format!("{}.json", trade_tx.id),
+        ]);
+        self.put_json(&path, &trade_tx).await
+    }
+
+    async fn list_trade_txs(&self, account: &str) -> StorageResult<Vec<TradeTx>> {
+        let mut trade_txs: Vec<TradeTx> = self
+            .list_json(&self.path(&["accounts", account, "trades"]))
+            .await?;
+        trade_txs.sort_by(|a, b| a.close_time.cmp(&b.close_time));
+
+        Ok(trade_txs)
+    }
+
+    async fn save_order(&self, account: &str, order: OrderRecord) -> StorageResult<()> {
+        let path = self.path(&["accounts", account, "orders", &format!("{}.json", order.id)]);
+        self.put_json(&path, &order).await
+    }
+
+    async fn list_orders(&self, account: &str) -> StorageResult<Vec<OrderRecord>> {
+        let mut orders: Vec<OrderRecord> = self
+            .list_json(&self.path(&["accounts", account, "orders"]))
+            .await?;
+        orders.sort_by_key(|order| order.timestamp);
+
+        Ok(orders)
+    }
+
+    async fn save_signal_record(&self, record: SignalRecord) -> StorageResult<()> {
+        let path = self.path(&[
+            "signals",
+            &record.signal.strategy_id.to_string(),
+            &format!("{}.json", record.id),
+        ]);
+        self.put_json(&path, &record).await
+    }
+
+    async fn list_signal_records(
+        &self,
+        query: SignalHistoryQuery,
+    ) -> StorageResult<Vec<SignalRecord>> {
+        let mut records = vec![];
+        match query.strategy_id {
+            Some(strategy_id) => {
+                let path = self.path(&["signals", &strategy_id.to_string()]);
+                records.extend(self.list_json::<SignalRecord>(&path).await?);
+            }
+            None => {
+                let listing = self
+                    .store
+                    .list_with_delimiter(Some(&self.path(&["signals"])))
+                    .await?;
+                for prefix in listing.common_prefixes {
+                    records.extend(self.list_json::<SignalRecord>(&prefix).await?);
+                }
+            }
+        }
+
+        Ok(query.apply(records))
+    }
+
+    async fn save_daily_report(&self, report: DailyReport) -> StorageResult<()> {
+        let path = self.path(&["daily_reports", &format!("{}.json", report.id)]);
+        self.put_json(&path, &report).await
+    }
+
+    async fn list_daily_reports(&self) -> StorageResult<Vec<DailyReport>> {
+        let mut reports = self
+            .list_json::<DailyReport>(&self.path(&["daily_reports"]))
+            .await?;
+        reports.sort_by_key(|report| report.to_ts);
+
+        Ok(reports)
+    }
+
+    async fn append_audit_entry(&self, entry: AuditEntry) -> StorageResult<()> {
+        // objects are named by time, so listings return the log in order
+        let path = self.path(&[
+            "audit",
+            &format!("{:013}-{}.json", entry.timestamp, entry.id),
+        ]);
+        self.put_json(&path, &entry).await
+    }
+
+    async fn list_audit_entries(&self, query: AuditQuery) -> StorageResult<Vec<AuditEntry>> {
+        let entries = self.list_json::<AuditEntry>(&self.path(&["audit"])).await?;
+
+        Ok(query.apply(entries))
+    }
+
+    async fn save_external_data(
+        &self,
+        series: &str,
+        points: &[ExternalDataPoint],
+    ) -> StorageResult<()> {
+        // objects are named by the time they were saved, so listings return later saves last
+        let path = self.path(&[
+            "external_data",
+            &format!("{:013}-{}.json", generate_ts(), Uuid::new_v4()),
+        ]);
+        let batch = ExternalDataBatch {
+            series: series.to_string(),
+            points: points.to_vec(),
+        };
+        self.put_json(&path, &batch).await
+    }
+
+    async fn list_external_data(
+        &self,
+        from_ts: Option<u64>,
+        to_ts: Option<u64>,
+    ) -> StorageResult<HashMap<String, Vec<ExternalDataPoint>>> {
+        let batches = self
+            .list_json::<ExternalDataBatch>(&self.path(&["external_data"]))
+            .await?;
+
+        let mut series: HashMap<String, Vec<ExternalDataPoint>> = HashMap::new();
+        for batch in batches {
+            series
+                .entry(batch.series)
+                .or_default()
+                .extend(batch.points.into_iter().filter(|point| {
+                    from_ts.is_none_or(|from_ts| point.timestamp >= from_ts)
+                        && to_ts.is_none_or(|to_ts| point.timestamp <= to_ts)
+                }));
+        }
+        for points in series.values_mut() {
+            points.sort_by_key(|point| point.timestamp);
+        }
+
+        Ok(series)
+    }
+}
+
+/// The values of an external series saved at once.
+#[derive(Serialize, Deserialize)]
+struct ExternalDataBatch {
+    series: String,
+    points: Vec<ExternalDataPoint>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::kline::test_kline;
+    use crate::{
+        account::trade::OrderSide,
+        strategy::{signal::SignalAction, types::SignalMessage},
+    };
+
+    /// Tests klines are merged into monthly objects and JSON documents round trip.
+    #[tokio::test]
+    async fn test_object_store_storage() {
+        let storage = ObjectStoreStorage::new("memory:///raderbot").unwrap();
+        let kline_key = build_kline_key("BTCUSDT", Interval::Hour1);
+
+        // 2024-01-31T22:00:00Z, the klines span two months
+        let from_ts = 1_706_738_400_000;
+        let hour = 3_600_000;
+        let klines: Vec<Kline> = (0..4)
+            .map(|i| test_kline(Interval::Hour1, from_ts + i * hour, 1.0))
+            .collect();
+        storage
+            .save_klines(&klines, &kline_key, false)
+            .await
+            .unwrap();
+        storage
+            .save_klines(
+                &[test_kline(Interval::Hour1, from_ts + hour, 42.0)],
+                &kline_key,
+                false,
+            )
+            .await
+            .unwrap();
+
+        let loaded = storage
+            .get_klines(
+                "BTCUSDT",
+                Interval::Hour1,
+                Some(from_ts),
+                Some(from_ts + 3 * hour),
+            )
+            .await;
+        assert_eq!(loaded.len(), 4);
+        assert_eq!(loaded[1].close, 42.0);
+        assert_eq!(loaded[3], klines[3]);
+
+        // the most recent klines are in the February object
+        let latest = storage
+            .query_klines(
+                "BTCUSDT",
+                Interval::Hour1,
+                &KlineQuery {
+                    limit: Some(3),
+                    offset: 1,
+                    ..Default::default()
+                },
+            )
+            .await;
+        let open_times: Vec<u64> = latest.iter().map(|kline| kline.open_time).collect();
+        assert_eq!(
+            open_times,
+            vec![from_ts + 2 * hour, from_ts + hour, from_ts]
+        );
+        assert_eq!(latest[1].close, 42.0);
+
+        let info = StrategyInfo {
+            name: "Rsi".to_string(),
+            ..Default::default()
+        };
+        storage.save_active_strategy(info.clone()).await.unwrap();
+        assert_eq!(
+            storage.list_active_strategies().await.unwrap()[0].name,
+            "Rsi"
+        );
+
+        storage.remove_active_strategy(info.id).await.unwrap();
+        assert!(storage.list_active_strategies().await.unwrap().is_empty());
+        assert!(storage.get_back_test(info.id).await.is_err());
+    }
+
+    /// Tests signal records are filtered by strategy and time and cut to the latest ones.
+    #[tokio::test]
+    async fn test_signal_history() {
+        let storage = ObjectStoreStorage::new("memory:///raderbot").unwrap();
+
+        // 2023-11-15T00:00:00Z
+        let day_ts = 1_700_006_400_000;
+        let hour = 3_600_000;
+        let strategy_ids = [StrategyId::new_v4(), StrategyId::new_v4()];
+        for (i, strategy_id) in strategy_ids.iter().cycle().take(6).enumerate() {
+            let signal = SignalMessage {
+                strategy_id: *strategy_id,
+                order_side: OrderSide::Buy,
+                symbol: "BTCUSDT".to_string(),
+                interval: Interval::Hour1,
+                price: 1.0,
+                confidence: 1.0,
+                is_back_test: false,
+                timestamp: day_ts + i as u64 * hour,
+                stop_loss: None,
+            };
+            let action = SignalAction::Ignored {
+                reason: format!("signal {i}"),
+            };
+            storage
+                .save_signal_record(SignalRecord::new(signal, action))
+                .await
+                .unwrap();
+        }
+
+        let all = storage
+            .list_signal_records(SignalHistoryQuery::default())
+            .await
+            .unwrap();
+        assert_eq!(all.len(), 6);
+        assert!(all
+            .windows(2)
+            .all(|pair| pair[0].signal.timestamp < pair[1].signal.timestamp));
+
+        let query = SignalHistoryQuery {
+            strategy_id: Some(strategy_ids[0]),
+            from_ts: Some(day_ts + hour),
+            limit: Some(1),
+            ..Default::default()
+        };
+        let latest = storage.list_signal_records(query).await.unwrap();
+        assert_eq!(latest.len(), 1);
+        assert_eq!(
+            latest[0].action,
+            SignalAction::Ignored {
+                reason: "signal 4".to_string()
+            }
+        );
+    }
+
+    /// Tests expired klines are removed while recent ones of other objects are kept.
+    #[tokio::test]
+    async fn test_apply_retention() {
+        let storage = ObjectStoreStorage::new("memory:///raderbot").unwrap();
+        let kline_key = build_kline_key("BTCUSDT", Interval::Hour1);
+
+        // 2024-01-31T22:00:00Z, the klines span two months
+        let from_ts = 1_706_738_400_000;
+        let hour = 3_600_000;
+        let old: Vec<Kline> = (0..4)
+            .map(|i| test_kline(Interval::Hour1, from_ts + i * hour, 1.0))
+            .collect();
+        let recent_ts = generate_ts() - 2 * hour;
+        let recent = vec![
+            test_kline(Interval::Hour1, recent_ts, 1.0),
+            test_kline(Interval::Hour1, recent_ts + hour, 1.0),
+        ];
+        for klines in [&old, &recent] {
+            storage
+                .save_klines(klines, &kline_key, false)
+                .await
+                .unwrap();
+        }
+
+        let policy = RetentionPolicy::parse("1h=30", "").unwrap();
+        let report = storage.apply_retention(&policy).await.unwrap();
+        assert_eq!(report.klines_removed, 4);
+        assert_eq!(report.files_removed, 2);
+
+        let loaded = storage
+            .get_klines("BTCUSDT", Interval::Hour1, Some(from_ts), None)
+            .await;
+        assert_eq!(loaded.len(), 2);
+
+        let report = storage.apply_retention(&policy).await.unwrap();
+        assert_eq!(report, RetentionReport::default());
+    }
+
+    /// Tests a restore brings back the objects of a backup and removes objects created since.
+    #[tokio::test]
+    async fn test_backup_restore() {
+        let storage = ObjectStoreStorage::new("memory:///raderbot").unwrap();
+        let kline_key = build_kline_key("BTCUSDT", Interval::Hour1);
+
+        // 2023-11-15T00:00:00Z
+        let day_ts = 1_700_006_400_000;
+        let klines = vec![test_kline(Interval::Hour1, day_ts, 1.0)];
+        storage
+            .save_klines(&klines, &kline_key, false)
+            .await
+            .unwrap();
+        let backup = storage.create_backup().await.unwrap();
+        assert_eq!(storage.list_backups().await.unwrap(), vec![backup.clone()]);
+
+        storage
+            .save_klines(
+                &[test_kline(Interval::Hour1, day_ts, 42.0)],
+                &build_kline_key("ETHUSDT", Interval::Hour1),
+                false,
+            )
+            .await
+            .unwrap();
+        storage
+            .save_klines(
+                &[test_kline(Interval::Hour1, day_ts, 2.0)],
+                &kline_key,
+                false,
+            )
+            .await
+            .unwrap();
+
+        storage.restore_backup(&backup.name).await.unwrap();
+        let loaded = storage
+            .get_klines("BTCUSDT", Interval::Hour1, Some(day_ts), None)
+            .await;
+        assert_eq!(loaded, klines);
+        assert!(storage
+            .get_klines("ETHUSDT", Interval::Hour1, Some(day_ts), None)
+            .await
+            .is_empty());
+        assert_eq!(storage.list_backups().await.unwrap().len(), 1);
+        assert!(storage.restore_backup("../backups").await.is_err());
+    }
+}
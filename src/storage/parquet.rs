@@ -0,0 +1,408 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use log::info;
+
+use arrow::{
+    array::{ArrayRef, Float64Array, UInt64Array},
+    datatypes::{DataType, Field, Schema},
+    record_batch::RecordBatch,
+};
+use parquet::{
+    arrow::{arrow_reader::ParquetRecordBatchReaderBuilder, ArrowWriter},
+    basic::{Compression, ZstdLevel},
+    errors::ParquetError,
+    file::properties::WriterProperties,
+};
+
+use super::{
+    kline_query::KlineQuery,
+    retention::{RetentionPolicy, RetentionReport},
+};
+use crate::{
+    market::{interval::Interval, kline::Kline},
+    utils::time::{floor_mili_ts, timestamp_to_datetime, year_month_day_to_ts, DAY_AS_MILI},
+};
+
+/// Columnar k-line store writing zstd compressed Parquet files, partitioned by symbol, interval
+/// and day: `{root}/{symbol}/{interval}/{YYYY-MM-DD}.parquet`.
+///
+/// Symbol and interval are part of the path rather than columns. Parquet files can't be
+/// appended to, saving k-lines rewrites the files of their days merged with the stored k-lines,
+/// saved k-lines replace stored ones of the same open time.
+#[derive(Debug, Clone)]
+pub struct ParquetKlineStore {
+    root: PathBuf,
+}
+
+impl ParquetKlineStore {
+    pub fn new(root: impl AsRef<Path>) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Saves k-lines into the partitions of their days.
+    ///
+    /// # Arguments
+    ///
+    /// * `klines` - The k-lines to save.
+    ///
+    /// # Returns
+    ///
+    /// An `io::Result` indicating the outcome of the operation.
+    pub fn save_klines(&self, klines: &[Kline]) -> io::Result<()> {
+        let mut partitions: BTreeMap<PathBuf, Vec<&Kline>> = BTreeMap::new();
+        for kline in klines {
+            let path = self.partition_path(&kline.symbol, kline.interval, kline.open_time);
+            partitions.entry(path).or_default().push(kline);
+        }
+
+        for (path, klines) in partitions {
+            // key by open time, fresh k-lines replace stored ones
+            let mut merged: BTreeMap<u64, Kline> = BTreeMap::new();
+            if path.exists() {
+                let (symbol, interval) = (&klines[0].symbol, klines[0].interval);
+                for kline in read_partition(&path, symbol, interval).map_err(to_io_error)? {
+                    merged.insert(kline.open_time, kline);
+                }
+            }
+            for kline in klines {
+                merged.insert(kline.open_time, kline.clone());
+            }
+
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            let klines: Vec<Kline> = merged.into_values().collect();
+            write_partition(&path, &klines).map_err(to_io_error)?;
+        }
+
+        Ok(())
+    }
+
+    /// Loads the k-lines of a symbol and interval opening within a time range, only reading
+    /// the partitions of the days in the range.
+    ///
+    /// # Arguments
+    ///
+    /// * `symbol` - The symbol of the k-lines.
+    /// * `interval` - The interval of the k-lines.
+    /// * `from_ts` - The earliest open time, inclusive.
+    /// * `to_ts` - The latest open time, inclusive.
+    ///
+    /// # Returns
+    ///
+    /// The k-lines ordered by open time, unreadable partitions are skipped.
+    pub fn get_klines(
+        &self,
+        symbol: &str,
+        interval: Interval,
+        from_ts: u64,
+        to_ts: u64,
+    ) -> Vec<Kline> {
+        let mut klines = vec![];
+
+        let mut day_ts = floor_mili_ts(from_ts, DAY_AS_MILI);
+        while day_ts <= to_ts {
+            let path = self.partition_path(symbol, interval, day_ts);
+            if path.exists() {
+                match read_partition(&path, symbol, interval) {
+                    Ok(partition) => klines.extend(
+                        partition
+                            .into_iter()
+                            .filter(|kline| kline.open_time >= from_ts && kline.open_time <= to_ts),
+                    ),
+                    Err(e) => info!("Unable to read klines in {}, e: {e}", path.display()),
+                }
+            }
+            day_ts += DAY_AS_MILI;
+        }
+
+        klines
+    }
+
+    /// Loads a page of the k-lines of a symbol and interval, reading the stored partitions in
+    /// the order of the query until the page is filled.
+    ///
+    /// # Arguments
+    ///
+    /// * `symbol` - The symbol of the k-lines.
+    /// * `interval` - The interval of the k-lines.
+    /// * `query` - The range and page of the k-lines.
+    ///
+    /// # Returns
+    ///
+    /// The k-lines of the page, unreadable partitions are skipped.
+    pub fn query_klines(&self, symbol: &str, interval: Interval, query: &KlineQuery) -> Vec<Kline> {
+        let mut partitions =
+            match read_partition_days(&self.root.join(symbol).join(interval.as_str())) {
+                Ok(partitions) => partitions,
+                Err(_) => return vec![],
+            };
+        partitions.retain(|(day_ts, _)| {
+            query
+                .from_ts
+                .is_none_or(|from_ts| day_ts + DAY_AS_MILI > from_ts)
+                && query.to_ts.is_none_or(|to_ts| *day_ts <= to_ts)
+        });
+
+        query.load_partitions(partitions, |(_, path)| {
+            read_partition(path, symbol, interval).unwrap_or_else(|e| {
+                info!("Unable to read klines in {}, e: {e}", path.display());
+                vec![]
+            })
+        })
+    }
+
+    /// Removes k-lines opened before the cutoff of their interval, see `RetentionPolicy`.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - The `RetentionPolicy` to enforce.
+    /// * `now` - The current timestamp.
+    ///
+    /// # Returns
+    ///
+    /// The `RetentionReport` of the removed k-lines, or an error if a partition can't be
+    /// removed or rewritten.
+    pub fn apply_retention(
+        &self,
+        policy: &RetentionPolicy,
+        now: u64,
+    ) -> io::Result<RetentionReport> {
+        let mut report = RetentionReport::default();
+        if !self.root.is_dir() {
+            return Ok(report);
+        }
+
+        for symbol_entry in fs::read_dir(&self.root)? {
+            let symbol_dir = symbol_entry?.path();
+            if !symbol_dir.is_dir() {
+                continue;
+            }
+            let symbol = symbol_dir
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned();
+
+            for interval_entry in fs::read_dir(&symbol_dir)? {
+                let interval_dir = interval_entry?.path();
+                // partitions of unknown intervals aren't raderbot's
+                let Ok(interval) = interval_dir
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .parse::<Interval>()
+                else {
+                    continue;
+                };
+                let cutoff = match policy.kline_cutoff(interval.as_str(), now) {
+                    Some(cutoff) if interval_dir.is_dir() => cutoff,
+                    _ => continue,
+                };
+
+                for (day_ts, path) in read_partition_days(&interval_dir)? {
+                    if day_ts >= cutoff {
+                        continue;
+                    }
+
+                    let klines = read_partition(&path, &symbol, interval).map_err(to_io_error)?;
+                    let kept: Vec<Kline> = klines
+                        .iter()
+                        .filter(|kline| kline.open_time >= cutoff)
+                        .cloned()
+                        .collect();
+                    report.klines_removed += klines.len() - kept.len();
+
+                    if kept.is_empty() {
+                        fs::remove_file(&path)?;
+                        report.files_removed += 1;
+                    } else if kept.len() < klines.len() {
+                        write_partition(&path, &kept).map_err(to_io_error)?;
+                        report.files_rewritten += 1;
+                    }
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    // ---
+    // Private Methods
+    // ---
+
+    fn partition_path(&self, symbol: &str, interval: Interval, open_time: u64) -> PathBuf {
+        let day = timestamp_to_datetime(open_time).format("%Y-%m-%d");
+        self.root
+            .join(symbol)
+            .join(interval.as_str())
+            .join(format!("{day}.parquet"))
+    }
+}
+
+/// Lists the day partitions of a symbol and interval with the start of their day, oldest first.
+fn read_partition_days(interval_dir: &Path) -> io::Result<Vec<(u64, PathBuf)>> {
+    let mut partitions: Vec<(u64, PathBuf)> = fs::read_dir(interval_dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter_map(|path| {
+            let day = path.file_name()?.to_str()?.strip_suffix(".parquet")?;
+            let mut parts = day.splitn(3, '-').map(|part| part.parse::<u32>().ok());
+            let day_ts = year_month_day_to_ts(parts.next()??, parts.next()??, parts.next()??)?;
+            Some((day_ts, path))
+        })
+        .collect();
+    partitions.sort_by_key(|(day_ts, _)| *day_ts);
+
+    Ok(partitions)
+}
+
+fn schema() -> Arc<Schema> {
+    Arc::new(Schema::new(vec![
+        Field::new("open_time", DataType::UInt64, false),
+        Field::new("close_time", DataType::UInt64, false),
+        Field::new("open", DataType::Float64, false),
+        Field::new("high", DataType::Float64, false),
+        Field::new("low", DataType::Float64, false),
+        Field::new("close", DataType::Float64, false),
+        Field::new("volume", DataType::Float64, false),
+    ]))
+}
+
+fn write_partition(path: &Path, klines: &[Kline]) -> Result<(), ParquetError> {
+    let time_column = |value: fn(&Kline) -> u64| -> ArrayRef {
+        Arc::new(UInt64Array::from_iter_values(klines.iter().map(value)))
+    };
+    let column = |value: fn(&Kline) -> f64| -> ArrayRef {
+        Arc::new(Float64Array::from_iter_values(klines.iter().map(value)))
+    };
+
+    let batch = RecordBatch::try_new(
+        schema(),
+        vec![
+            time_column(|kline| kline.open_time),
+            time_column(|kline| kline.close_time),
+            column(|kline| kline.open),
+            column(|kline| kline.high),
+            column(|kline| kline.low),
+            column(|kline| kline.close),
+            column(|kline| kline.volume),
+        ],
+    )?;
+
+    let properties = WriterProperties::builder()
+        .set_compression(Compression::ZSTD(ZstdLevel::default()))
+        .build();
+
+    // write next to the partition and rename, so readers never see a partially written file
+    let tmp_path = path.with_extension("parquet.tmp");
+    let mut writer = ArrowWriter::try_new(File::create(&tmp_path)?, schema(), Some(properties))?;
+    writer.write(&batch)?;
+    writer.close()?;
+    fs::rename(tmp_path, path)?;
+
+    Ok(())
+}
+
+fn read_partition(
+    path: &Path,
+    symbol: &str,
+    interval: Interval,
+) -> Result<Vec<Kline>, ParquetError> {
+    let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(path)?)?.build()?;
+
+    let mut klines = vec![];
+    for batch in reader {
+        let batch = batch?;
+        let u64_column = |i: usize| {
+            batch
+                .column(i)
+                .as_any()
+                .downcast_ref::<UInt64Array>()
+                .ok_or_else(|| ParquetError::General(format!("Unexpected type of column {i}")))
+        };
+        let f64_column = |i: usize| {
+            batch
+                .column(i)
+                .as_any()
+                .downcast_ref::<Float64Array>()
+                .ok_or_else(|| ParquetError::General(format!("Unexpected type of column {i}")))
+        };
+        let (open_time, close_time) = (u64_column(0)?, u64_column(1)?);
+        let (open, high, low, close, volume) = (
+            f64_column(2)?,
+            f64_column(3)?,
+            f64_column(4)?,
+            f64_column(5)?,
+            f64_column(6)?,
+        );
+
+        for i in 0..batch.num_rows() {
+            klines.push(Kline {
+                symbol: symbol.to_string(),
+                interval,
+                open: open.value(i),
+                high: high.value(i),
+                low: low.value(i),
+                close: close.value(i),
+                volume: volume.value(i),
+                open_time: open_time.value(i),
+                close_time: close_time.value(i),
+            });
+        }
+    }
+
+    Ok(klines)
+}
+
+fn to_io_error(e: ParquetError) -> io::Error {
+    io::Error::other(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::kline::test_kline;
+    use uuid::Uuid;
+
+    /// Tests k-lines are partitioned by day, merged on save and filtered by open time on load.
+    #[test]
+    fn test_parquet_kline_store() {
+        let root = std::env::temp_dir().join(format!("raderbot-parquet-{}", Uuid::new_v4()));
+        let store = ParquetKlineStore::new(&root);
+
+        // 2023-11-15T00:00:00Z
+        let day_ts = 1_700_006_400_000;
+        let hour = 3_600_000;
+        let klines: Vec<Kline> = (0..30)
+            .map(|i| test_kline(Interval::Hour1, day_ts + i * hour, i as f64))
+            .collect();
+        store.save_klines(&klines).unwrap();
+        assert!(root.join("BTCUSDT/1h/2023-11-15.parquet").exists());
+        assert!(root.join("BTCUSDT/1h/2023-11-16.parquet").exists());
+
+        store
+            .save_klines(&[test_kline(Interval::Hour1, day_ts + 2 * hour, 42.0)])
+            .unwrap();
+
+        let loaded = store.get_klines(
+            "BTCUSDT",
+            Interval::Hour1,
+            day_ts + hour,
+            day_ts + 25 * hour,
+        );
+        assert_eq!(loaded.len(), 25);
+        assert_eq!(loaded[0], klines[1]);
+        assert_eq!(loaded[1].close, 42.0);
+        assert_eq!(loaded[24], klines[25]);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,177 @@
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::utils::env::{env_lookup, Lookup};
+use crate::utils::time::{year_month_day_to_ts, DAY_AS_MILI};
+
+/// How long stored market data is kept, older data is removed by the retention job.
+///
+/// K-lines are kept per interval, e.g. 1m k-lines for 90 days and 1h k-lines for a year,
+/// market trades for a number of days. Data without a configured age is kept forever.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RetentionPolicy {
+    /// Days k-lines are kept by interval.
+    kline_days: HashMap<String, u64>,
+    /// Days k-lines of intervals without their own age are kept.
+    default_kline_days: Option<u64>,
+    trade_days: Option<u64>,
+}
+
+impl RetentionPolicy {
+    /// Reads the policy from the `KLINE_RETENTION_DAYS` and `TRADE_RETENTION_DAYS` environment
+    /// variables, see `parse`.
+    ///
+    /// # Returns
+    ///
+    /// The `RetentionPolicy`, or an error if a variable can't be parsed.
+    pub fn from_env() -> Result<Self, String> {
+        Self::from_lookup(env_lookup)
+    }
+
+    /// Reads the policy from `KLINE_RETENTION_DAYS` and `TRADE_RETENTION_DAYS` through `lookup`,
+    /// see `from_env`.
+    pub fn from_lookup(lookup: impl Lookup) -> Result<Self, String> {
+        Self::parse(
+            &lookup("KLINE_RETENTION_DAYS").unwrap_or_default(),
+            &lookup("TRADE_RETENTION_DAYS").unwrap_or_default(),
+        )
+    }
+
+    /// Parses a policy.
+    ///
+    /// # Arguments
+    ///
+    /// * `kline_days` - Days k-lines are kept by interval, e.g. `1m=90,1h=365`. `*=30` or a
+    ///   bare `30` applies to every other interval.
+    /// * `trade_days` - Days market trades are kept, e.g. `14`.
+    ///
+    /// # Returns
+    ///
+    /// The `RetentionPolicy`, empty for empty settings, or an error if a setting can't be parsed.
+    pub fn parse(kline_days: &str, trade_days: &str) -> Result<Self, String> {
+        let parse_days = |days: &str| {
+            days.trim()
+                .parse::<u64>()
+                .map_err(|_| format!("Invalid retention days: {days}"))
+        };
+
+        let mut policy = Self::default();
+        for setting in kline_days.split(',').filter(|s| !s.trim().is_empty()) {
+            match setting.split_once('=') {
+                Some((interval, days)) if interval.trim() != "*" => {
+                    policy
+                        .kline_days
+                        .insert(interval.trim().to_string(), parse_days(days)?);
+                }
+                Some((_, days)) => policy.default_kline_days = Some(parse_days(days)?),
+                None => policy.default_kline_days = Some(parse_days(setting)?),
+            }
+        }
+        if !trade_days.trim().is_empty() {
+            policy.trade_days = Some(parse_days(trade_days)?);
+        }
+
+        Ok(policy)
+    }
+
+    /// Whether the policy keeps all data.
+    pub fn is_empty(&self) -> bool {
+        self.kline_days.is_empty() && self.default_kline_days.is_none() && self.trade_days.is_none()
+    }
+
+    /// The open time before which k-lines of an interval are removed, `None` if they're kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `interval` - The interval of the k-lines.
+    /// * `now` - The current timestamp.
+    pub fn kline_cutoff(&self, interval: &str, now: u64) -> Option<u64> {
+        let days = self
+            .kline_days
+            .get(interval)
+            .copied()
+            .or(self.default_kline_days)?;
+        Some(now.saturating_sub(days * DAY_AS_MILI))
+    }
+
+    /// The timestamp before which market trades are removed, `None` if they're kept.
+    pub fn trade_cutoff(&self, now: u64) -> Option<u64> {
+        self.trade_days
+            .map(|days| now.saturating_sub(days * DAY_AS_MILI))
+    }
+
+    /// The intervals with an age of their own and the days their k-lines are kept.
+    pub fn kline_days(&self) -> &HashMap<String, u64> {
+        &self.kline_days
+    }
+
+    /// The days k-lines of intervals without an age of their own are kept.
+    pub fn default_kline_days(&self) -> Option<u64> {
+        self.default_kline_days
+    }
+}
+
+/// Outcome of a retention run.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct RetentionReport {
+    /// Files or objects removed as all their data expired.
+    pub files_removed: usize,
+    /// Files or objects rewritten without their expired data.
+    pub files_rewritten: usize,
+    pub klines_removed: usize,
+    pub trades_removed: usize,
+}
+
+/// Reads the interval of a kline key, e.g. `1m` of `BTCUSDT@kline_1m`.
+pub fn kline_key_interval(kline_key: &str) -> Option<&str> {
+    kline_key
+        .split_once("@kline_")
+        .map(|(_, interval)| interval)
+}
+
+/// Reads the start of the month of a monthly k-line file, e.g. of `2024-01`.
+pub fn month_start_ts(month: &str) -> Option<u64> {
+    let (year, month) = month.split_once('-')?;
+    year_month_day_to_ts(year.parse().ok()?, month.parse().ok()?, 1)
+}
+
+/// Reads the start of the day of a daily market trade file name, e.g. of
+/// `BTCUSDT@trade-2024-01-31.csv`.
+pub fn trade_file_day_ts(filename: &str) -> Option<u64> {
+    let stem = filename.strip_suffix(".csv")?;
+    let day = stem.get(stem.len().checked_sub(10)?..)?;
+
+    let mut parts = day.splitn(3, '-').map(|part| part.parse::<u32>().ok());
+    year_month_day_to_ts(parts.next()??, parts.next()??, parts.next()??)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests retention settings are parsed and expired data is found by file name.
+    #[test]
+    fn test_retention_policy() {
+        // 2023-11-15T00:00:00Z
+        let now = 1_700_006_400_000;
+
+        let policy = RetentionPolicy::parse("1m=90, *=365", "14").unwrap();
+        assert_eq!(policy.kline_cutoff("1m", now), Some(now - 90 * DAY_AS_MILI));
+        assert_eq!(
+            policy.kline_cutoff("1h", now),
+            Some(now - 365 * DAY_AS_MILI)
+        );
+        assert_eq!(policy.trade_cutoff(now), Some(now - 14 * DAY_AS_MILI));
+
+        let policy = RetentionPolicy::parse("1m=90", "").unwrap();
+        assert_eq!(policy.kline_cutoff("1h", now), None);
+        assert_eq!(policy.trade_cutoff(now), None);
+        assert!(RetentionPolicy::parse("", "").unwrap().is_empty());
+        assert!(RetentionPolicy::parse("1m=ninety", "").is_err());
+
+        assert_eq!(kline_key_interval("BTCUSDT@kline_1m"), Some("1m"));
+        // 2023-11-01T00:00:00Z
+        assert_eq!(month_start_ts("2023-11"), Some(1_698_796_800_000));
+        assert_eq!(trade_file_day_ts("BTCUSDT@trade-2023-11-15.csv"), Some(now));
+    }
+}
@@ -0,0 +1,285 @@
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::kline_query::SortOrder;
+use crate::market::interval::Interval;
+use crate::strategy::strategy::{StrategyId, StrategySummary};
+use crate::utils::time::deserialize_opt_timestamp;
+
+/// Field saved strategy summaries are sorted by.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SummarySortField {
+    #[default]
+    StartTime,
+    Profit,
+    MaxDrawdown,
+    TradeCount,
+}
+
+/// Filters, sorts and pages saved strategy summaries. Every filter is optional, an empty query
+/// lists all summaries, most recently started first.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct StrategySummaryQuery {
+    pub symbol: Option<String>,
+    /// The name of the algorithm the strategy ran.
+    pub name: Option<String>,
+    /// The credential profile the strategy traded on.
+    pub profile: Option<String>,
+    /// The earliest start time, inclusive, in milliseconds or as a date.
+    #[serde(default, deserialize_with = "deserialize_opt_timestamp")]
+    pub from_ts: Option<u64>,
+    /// The latest start time, inclusive, in milliseconds or as a date.
+    #[serde(default, deserialize_with = "deserialize_opt_timestamp")]
+    pub to_ts: Option<u64>,
+    pub min_profit: Option<f64>,
+    #[serde(default)]
+    pub sort_by: SummarySortField,
+    /// Descending by default, so the most recent or most profitable summaries come first.
+    pub order: Option<SortOrder>,
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub offset: usize,
+}
+
+impl StrategySummaryQuery {
+    /// Checks whether a summary passes the filters of the query.
+    pub fn matches(&self, summary: &StrategySummary) -> bool {
+        let info = &summary.info;
+        if self
+            .symbol
+            .as_ref()
+            .is_some_and(|symbol| *symbol != info.symbol)
+            || self.name.as_ref().is_some_and(|name| *name != info.name)
+            || self
+                .profile
+                .as_ref()
+                .is_some_and(|profile| info.settings.profile.as_ref() != Some(profile))
+            || self
+                .min_profit
+                .is_some_and(|min_profit| summary.profit < min_profit)
+        {
+            return false;
+        }
+
+        if self.from_ts.is_none() && self.to_ts.is_none() {
+            return true;
+        }
+        // summaries without a start time are outside of every range
+        summary_start_ts(summary).is_some_and(|start_ts| {
+            self.from_ts.is_none_or(|from_ts| start_ts >= from_ts)
+                && self.to_ts.is_none_or(|to_ts| start_ts <= to_ts)
+        })
+    }
+
+    /// Keeps the summaries passing the filters, sorted as requested.
+    pub fn filter(&self, summaries: Vec<StrategySummary>) -> Vec<StrategySummary> {
+        let mut summaries: Vec<StrategySummary> = summaries
+            .into_iter()
+            .filter(|summary| self.matches(summary))
+            .collect();
+
+        summaries.sort_by(|a, b| match self.sort_by {
+            SummarySortField::StartTime => summary_start_ts(a).cmp(&summary_start_ts(b)),
+            SummarySortField::Profit => a.profit.total_cmp(&b.profit),
+            SummarySortField::MaxDrawdown => a.max_drawdown.total_cmp(&b.max_drawdown),
+            SummarySortField::TradeCount => a.trades.len().cmp(&b.trades.len()),
+        });
+        if self.order.unwrap_or(SortOrder::Desc) == SortOrder::Desc {
+            summaries.reverse();
+        }
+
+        summaries
+    }
+
+    /// Cuts the page of the query out of filtered summaries.
+    pub fn page(&self, summaries: Vec<StrategySummary>) -> Vec<StrategySummary> {
+        let page = summaries.into_iter().skip(self.offset);
+        match self.limit {
+            Some(limit) => page.take(limit).collect(),
+            None => page.collect(),
+        }
+    }
+
+    /// Filters, sorts and pages summaries, see `filter` and `page`.
+    pub fn apply(&self, summaries: Vec<StrategySummary>) -> Vec<StrategySummary> {
+        self.page(self.filter(summaries))
+    }
+}
+
+/// A lightweight list item of a saved strategy summary, the full summary with its trades and
+/// positions is fetched by id.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct StrategySummaryItem {
+    pub id: StrategyId,
+    pub name: String,
+    pub symbol: String,
+    pub interval: Interval,
+    pub profit: f64,
+    pub max_drawdown: f64,
+    pub trade_count: usize,
+    pub start_time: Option<String>,
+    pub end_time: Option<String>,
+}
+
+impl From<&StrategySummary> for StrategySummaryItem {
+    fn from(summary: &StrategySummary) -> Self {
+        Self {
+            id: summary.info.id,
+            name: summary.info.name.clone(),
+            symbol: summary.info.symbol.clone(),
+            interval: summary.info.interval,
+            profit: summary.profit,
+            max_drawdown: summary.max_drawdown,
+            trade_count: summary.trades.len(),
+            start_time: summary.info.start_time.clone(),
+            end_time: summary.info.end_time.clone(),
+        }
+    }
+}
+
+/// Aggregated results of the saved strategies running an algorithm.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct AlgorithmStats {
+    pub name: String,
+    pub strategy_count: usize,
+    /// Strategies which ended with a profit.
+    pub profitable_count: usize,
+    pub trade_count: usize,
+    pub total_profit: f64,
+    pub average_profit: f64,
+    /// The lowest profit of a strategy.
+    pub worst_profit: f64,
+    /// The highest profit of a strategy.
+    pub best_profit: f64,
+}
+
+/// Aggregates the results of strategy summaries by algorithm.
+///
+/// # Arguments
+///
+/// * `summaries` - The summaries to aggregate.
+///
+/// # Returns
+///
+/// The `AlgorithmStats` of each algorithm, sorted by name.
+pub fn summarize_by_algorithm(summaries: &[StrategySummary]) -> Vec<AlgorithmStats> {
+    let mut stats_by_name: BTreeMap<&str, AlgorithmStats> = BTreeMap::new();
+    for summary in summaries {
+        let stats = stats_by_name
+            .entry(&summary.info.name)
+            .or_insert_with(|| AlgorithmStats {
+                name: summary.info.name.clone(),
+                worst_profit: f64::MAX,
+                best_profit: f64::MIN,
+                ..Default::default()
+            });
+
+        stats.strategy_count += 1;
+        if summary.profit > 0.0 {
+            stats.profitable_count += 1;
+        }
+        stats.trade_count += summary.trades.len();
+        stats.total_profit += summary.profit;
+        stats.worst_profit = stats.worst_profit.min(summary.profit);
+        stats.best_profit = stats.best_profit.max(summary.profit);
+    }
+
+    stats_by_name
+        .into_values()
+        .map(|mut stats| {
+            stats.average_profit = stats.total_profit / stats.strategy_count as f64;
+            stats
+        })
+        .collect()
+}
+
+/// Reads the start time of a summary as a timestamp, `None` if it never started.
+fn summary_start_ts(summary: &StrategySummary) -> Option<u64> {
+    let start_time = summary.info.start_time.as_ref()?;
+    let start_time = DateTime::parse_from_rfc3339(start_time).ok()?;
+
+    Some(start_time.timestamp_millis() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::time::timestamp_to_string;
+
+    fn summary(name: &str, symbol: &str, start_ts: u64, profit: f64) -> StrategySummary {
+        let mut summary = StrategySummary {
+            profit,
+            ..Default::default()
+        };
+        summary.info.name = name.to_string();
+        summary.info.symbol = symbol.to_string();
+        summary.info.start_time = Some(timestamp_to_string(start_ts));
+        summary
+    }
+
+    /// Tests summaries are filtered, sorted and paged and profits are aggregated by algorithm.
+    #[test]
+    fn test_strategy_summary_query() {
+        // 2023-11-15T00:00:00Z
+        let day_ts = 1_700_006_400_000;
+        let hour = 3_600_000;
+        let summaries = vec![
+            summary("rsi", "BTCUSDT", day_ts, 10.0),
+            summary("rsi", "ETHUSDT", day_ts + hour, -4.0),
+            summary("ema", "BTCUSDT", day_ts + 2 * hour, 3.0),
+            summary("rsi", "BTCUSDT", day_ts + 3 * hour, 6.0),
+        ];
+        let profits = |summaries: Vec<StrategySummary>| -> Vec<f64> {
+            summaries.iter().map(|summary| summary.profit).collect()
+        };
+
+        let latest = StrategySummaryQuery {
+            limit: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(profits(latest.apply(summaries.clone())), vec![6.0, 3.0]);
+
+        let query = StrategySummaryQuery {
+            symbol: Some("BTCUSDT".to_string()),
+            name: Some("rsi".to_string()),
+            min_profit: Some(0.0),
+            sort_by: SummarySortField::Profit,
+            order: Some(SortOrder::Asc),
+            ..Default::default()
+        };
+        assert_eq!(profits(query.apply(summaries.clone())), vec![6.0, 10.0]);
+
+        let query = StrategySummaryQuery {
+            from_ts: Some(day_ts + hour),
+            to_ts: Some(day_ts + 2 * hour),
+            offset: 1,
+            ..Default::default()
+        };
+        assert_eq!(profits(query.apply(summaries.clone())), vec![-4.0]);
+
+        let query: StrategySummaryQuery = serde_json::from_value(serde_json::json!({
+            "from_ts": day_ts + hour,
+            "to_ts": "2023-11-15T02:00:00Z",
+        }))
+        .unwrap();
+        assert_eq!(query.from_ts, Some(day_ts + hour));
+        assert_eq!(query.to_ts, Some(day_ts + 2 * hour));
+
+        let item = StrategySummaryItem::from(&summaries[1]);
+        assert_eq!(item.symbol, "ETHUSDT");
+        assert_eq!(item.profit, -4.0);
+        assert_eq!(item.start_time, Some(timestamp_to_string(day_ts + hour)));
+
+        let stats = summarize_by_algorithm(&summaries);
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[1].name, "rsi");
+        assert_eq!(stats[1].strategy_count, 3);
+        assert_eq!(stats[1].profitable_count, 2);
+        assert_eq!(stats[1].total_profit, 12.0);
+        assert_eq!(stats[1].average_profit, 4.0);
+        assert_eq!(stats[1].worst_profit, -4.0);
+        assert_eq!(stats[1].best_profit, 10.0);
+    }
+}
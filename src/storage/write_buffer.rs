@@ -0,0 +1,96 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+use crate::market::kline::Kline;
+
+/// Write-behind buffer of k-lines saved while streaming, so they are written to their files in
+/// batches by a periodic flush instead of on every save.
+#[derive(Debug, Default)]
+pub struct KlineWriteBuffer {
+    /// Buffered k-lines by kline key and open time, later saves of an open time win.
+    klines: Mutex<HashMap<String, BTreeMap<u64, Kline>>>,
+}
+
+impl KlineWriteBuffer {
+    /// Buffers k-lines of a kline key.
+    pub fn push(&self, kline_key: &str, klines: &[Kline]) {
+        let mut buffered = self.klines.lock().unwrap_or_else(|e| e.into_inner());
+        let buffered = buffered.entry(kline_key.to_string()).or_default();
+        for kline in klines {
+            buffered.insert(kline.open_time, kline.clone());
+        }
+    }
+
+    /// Removes the buffered k-lines of a kline key.
+    ///
+    /// # Returns
+    ///
+    /// The k-lines sorted by open time.
+    pub fn take(&self, kline_key: &str) -> Vec<Kline> {
+        let mut buffered = self.klines.lock().unwrap_or_else(|e| e.into_inner());
+        buffered
+            .remove(kline_key)
+            .map(|klines| klines.into_values().collect())
+            .unwrap_or_default()
+    }
+
+    /// Removes all buffered k-lines.
+    ///
+    /// # Returns
+    ///
+    /// The kline keys with their k-lines sorted by open time.
+    pub fn take_all(&self) -> Vec<(String, Vec<Kline>)> {
+        let mut buffered = self.klines.lock().unwrap_or_else(|e| e.into_inner());
+        buffered
+            .drain()
+            .map(|(kline_key, klines)| (kline_key, klines.into_values().collect()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::interval::Interval;
+    use crate::market::kline::test_kline;
+
+    /// Tests buffered k-lines are sorted and later saves of an open time replace earlier ones.
+    #[test]
+    fn test_kline_write_buffer() {
+        // 2023-11-15T00:00:00Z
+        let day_ts = 1_700_006_400_000;
+        let minute = 60_000;
+
+        let buffer = KlineWriteBuffer::default();
+        buffer.push(
+            "BTCUSDT@kline_1m",
+            &[
+                test_kline(Interval::Min1, day_ts + minute, 1.0),
+                test_kline(Interval::Min1, day_ts, 1.0),
+            ],
+        );
+        buffer.push(
+            "BTCUSDT@kline_1m",
+            &[test_kline(Interval::Min1, day_ts + minute, 2.0)],
+        );
+        buffer.push(
+            "ETHUSDT@kline_1m",
+            &[test_kline(Interval::Min1, day_ts, 1.0)],
+        );
+
+        let klines = buffer.take("BTCUSDT@kline_1m");
+        assert_eq!(
+            klines,
+            vec![
+                test_kline(Interval::Min1, day_ts, 1.0),
+                test_kline(Interval::Min1, day_ts + minute, 2.0)
+            ]
+        );
+        assert!(buffer.take("BTCUSDT@kline_1m").is_empty());
+
+        let all = buffer.take_all();
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].0, "ETHUSDT@kline_1m");
+        assert!(buffer.take_all().is_empty());
+    }
+}
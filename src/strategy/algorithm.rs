@@ -1,38 +1,51 @@
 use std::time::Duration;
 
+use serde::Serialize;
 use serde_json::Value;
 
+#[cfg(feature = "plugins")]
+use crate::algorithm::plugin::{find_plugin, list_plugins, PluginAlgorithm};
 use crate::{
     algorithm::{
-        bollinger_bands::BollingerBands, ma_crossover::EmaSmaCrossover,
-        ma_simple::SimpleMovingAverage, ma_three_crossover::ThreeMaCrossover, macd::Macd,
-        macd_bollinger::MacdBollingerBands, rsi::Rsi,
+        bollinger_bands::BollingerBands, cci::Cci, chaikin_money_flow::ChaikinMoneyFlow,
+        composite::Composite, dca_accumulator::DcaAccumulator, fib_retracement::FibRetracement,
+        hull_ma::HullMa, ma_crossover::EmaSmaCrossover, ma_simple::SimpleMovingAverage,
+        ma_three_crossover::ThreeMaCrossover, macd::Macd, macd_bollinger::MacdBollingerBands,
+        obv::Obv, rsi::Rsi, trix::Trix, volume_profile::VolumeProfile,
+        vwap_reversion::VwapReversion,
     },
-    market::kline::Kline,
-    utils::time::build_interval,
+    market::{interval::Interval, kline::Kline},
 };
 
-use super::types::{AlgorithmError, AlgorithmEvalResult};
+use super::{
+    data_points::AlgorithmDataPointManager,
+    types::{AlgorithmError, AlgorithmEvalResult},
+};
 
 /// Defines a trait for algorithm implementations used in trading strategies.
 ///
 /// This trait outlines the necessary functionality for any algorithm used to evaluate trading
 /// signals based on historical k-line (candlestick) data. It includes methods for evaluating  
-/// trading signals, setting and retrieving algorithm parameters, and managing historical data
-/// points.
-
+/// trading signals, setting and retrieving algorithm parameters, and reporting how much
+/// history it needs. Historical data points are owned by the strategy through an
+/// `AlgorithmDataPointManager`.
 pub trait Algorithm: Send + Sync {
     /// Evaluates a single k-line (candlestick) data point to generate a trading signal.
     ///
     /// # Arguments
     ///
     /// * `kline` - A `Kline` struct representing the k-line data to evaluate.
+    /// * `data_points` - The historical k-lines of the strategy, already including `kline`.
     ///
     /// # Returns
     ///
     /// An `AlgorithmEvalResult` indicating the trading signal generated by the algorithm.
 
-    fn evaluate(&mut self, kline: Kline) -> AlgorithmEvalResult;
+    fn evaluate(
+        &mut self,
+        kline: Kline,
+        data_points: &AlgorithmDataPointManager,
+    ) -> AlgorithmEvalResult;
 
     /// Returns the time interval that the algorithm operates on.
     ///
@@ -54,6 +67,14 @@ pub trait Algorithm: Send + Sync {
 
     fn set_params(&mut self, params: Value) -> Result<(), AlgorithmError>;
 
+    /// Rebuilds the indicator state of the algorithm from the retained k-lines after its
+    /// parameters are set, so indicators reset by new parameters don't restart cold.
+    ///
+    /// # Arguments
+    ///
+    /// * `data_points` - The historical k-lines of the strategy, up to the last evaluated k-line.
+    fn warm_up(&mut self, _data_points: &AlgorithmDataPointManager) {}
+
     /// Retrieves the current parameters of the algorithm.
     ///
     /// # Returns
@@ -62,29 +83,77 @@ pub trait Algorithm: Send + Sync {
 
     fn get_params(&self) -> &Value;
 
-    /// Provides access to the historical k-line data points the algorithm has evaluated.
+    /// Returns the stop loss price for the signal generated by the last evaluation, if the
+    /// algorithm places one.
     ///
     /// # Returns
     ///
-    /// A vector of `Kline` structs representing the historical data points.
+    /// The stop loss price, or `None` to open positions without a stop loss.
+    fn stop_loss(&self) -> Option<f64> {
+        None
+    }
+
+    /// Returns the largest period, in k-lines, the algorithm looks back over.
+    ///
+    /// Used to derive how many historical k-lines the strategy retains for the algorithm.
+    fn max_period(&self) -> usize;
+}
 
-    // TODO: Create AlgorithmDataPointManager to handle data points
-    // It will manage cleaning of data if data points length is too long,
-    // to manage memory more efficiently as also prevent any bugs creeping
-    // up that could occur when implementing a custom algorithm
-    fn data_points(&self) -> Vec<Kline>;
+/// Describes an algorithm accepted by `AlgorithmBuilder` and the parameters it accepts.
+///
+/// `params_schema` is a JSON schema of the algorithm parameters including their defaults, so
+/// clients can render configuration forms.
+#[derive(Serialize, Debug, Clone)]
+pub struct AlgorithmDescription {
+    pub name: String,
+    pub params_schema: Value,
+}
 
-    /// Cleans historical data points to manage memory usage efficiently.
+/// A built-in algorithm with the parameters it accepts and how to build it.
+struct BuiltInAlgorithm {
+    name: &'static str,
+    params_schema: fn() -> Value,
+    build: fn(Duration, Value) -> Result<Box<dyn Algorithm>, AlgorithmError>,
+}
 
-    fn clean_data_points(&mut self);
+macro_rules! built_in {
+    ($name:literal, $algorithm:ty) => {
+        BuiltInAlgorithm {
+            name: $name,
+            params_schema: <$algorithm>::params_schema,
+            build: |interval, params| Ok(Box::new(<$algorithm>::new(interval, params)?)),
+        }
+    };
 }
 
+/// Every built-in algorithm by name, both built and listed from here so they can't drift apart.
+const BUILT_IN_ALGORITHMS: &[BuiltInAlgorithm] = &[
+    built_in!("EmaSmaCrossover", EmaSmaCrossover),
+    built_in!("SimpleMovingAverage", SimpleMovingAverage),
+    built_in!("ThreeMaCrossover", ThreeMaCrossover),
+    built_in!("Rsi", Rsi),
+    built_in!("RsiEmaSma", Rsi),
+    built_in!("BollingerBands", BollingerBands),
+    built_in!("Macd", Macd),
+    built_in!("MacdBollingerBands", MacdBollingerBands),
+    built_in!("Cci", Cci),
+    built_in!("Obv", Obv),
+    built_in!("VwapReversion", VwapReversion),
+    built_in!("DcaAccumulator", DcaAccumulator),
+    built_in!("VolumeProfile", VolumeProfile),
+    built_in!("FibRetracement", FibRetracement),
+    built_in!("HullMa", HullMa),
+    built_in!("Trix", Trix),
+    built_in!("ChaikinMoneyFlow", ChaikinMoneyFlow),
+    built_in!("Composite", Composite),
+];
+
 /// A builder for constructing instances of algorithms based on their names and parameters.
 ///
 /// This struct provides a method to build various trading algorithm instances dynamically
 /// based on the algorithm's name, the desired interval for evaluation, and any specific
-/// parameters required by the algorithm.
-
+/// parameters required by the algorithm. Names not matching a built-in algorithm are looked up
+/// in the registry of loaded algorithm plugins.
 pub struct AlgorithmBuilder {}
 
 impl AlgorithmBuilder {
@@ -93,7 +162,7 @@ impl AlgorithmBuilder {
     /// # Arguments
     ///
     /// * `algorithm_name` - A string slice representing the name of the algorithm to construct.
-    /// * `interval` - A string slice representing the interval between k-lines for the algorithm's operation.
+    /// * `interval` - The interval between k-lines for the algorithm's operation.
     /// * `algorithm_params` - A `Value` containing any specific parameters required by the algorithm.
     ///
     /// # Returns
@@ -103,49 +172,80 @@ impl AlgorithmBuilder {
 
     pub fn build_algorithm(
         algorithm_name: &str,
-        interval: &str,
+        interval: Interval,
         algorithm_params: Value,
     ) -> Result<Box<dyn Algorithm>, AlgorithmError> {
-        let interval = match build_interval(interval) {
-            Ok(interval) => interval,
-            Err(e) => return Err(AlgorithmError::UnknownInterval(e.to_string())),
-        };
-        match algorithm_name {
-            "EmaSmaCrossover" => {
-                let algo = EmaSmaCrossover::new(interval, algorithm_params)?;
-                Ok(Box::new(algo))
-            }
-            "SimpleMovingAverage" => {
-                let algo = SimpleMovingAverage::new(interval, algorithm_params)?;
-                Ok(Box::new(algo))
-            }
-            "ThreeMaCrossover" => {
-                let algo = ThreeMaCrossover::new(interval, algorithm_params)?;
-                Ok(Box::new(algo))
-            }
-            "Rsi" => {
-                let algo = Rsi::new(interval, algorithm_params)?;
-                Ok(Box::new(algo))
-            }
-            "RsiEmaSma" => {
-                let algo = Rsi::new(interval, algorithm_params)?;
-                Ok(Box::new(algo))
-            }
-            "BollingerBands" => {
-                let algo = BollingerBands::new(interval, algorithm_params)?;
-                Ok(Box::new(algo))
-            }
-            "Macd" => {
-                let algo = Macd::new(interval, algorithm_params)?;
-                Ok(Box::new(algo))
-            }
-            "MacdBollingerBands" => {
-                let algo = MacdBollingerBands::new(interval, algorithm_params)?;
-                Ok(Box::new(algo))
-            }
-            _ => Err(AlgorithmError::UnkownName(
-                format!("Strategy name {algorithm_name} is incorrect").to_string(),
-            )),
+        let interval = interval.duration();
+        if let Some(built_in) = BUILT_IN_ALGORITHMS
+            .iter()
+            .find(|built_in| built_in.name == algorithm_name)
+        {
+            return (built_in.build)(interval, algorithm_params);
         }
+
+        #[cfg(feature = "plugins")]
+        if let Some(plugin) = find_plugin(algorithm_name) {
+            let algo = PluginAlgorithm::new(plugin, interval, algorithm_params)?;
+            return Ok(Box::new(algo));
+        }
+
+        Err(AlgorithmError::UnkownName(
+            format!("Strategy name {algorithm_name} is incorrect").to_string(),
+        ))
+    }
+
+    /// Lists every algorithm that can be built, including loaded algorithm plugins.
+    ///
+    /// # Returns
+    ///
+    /// A vector of `AlgorithmDescription` containing the name and parameter schema of each algorithm.
+    pub fn list_algorithms() -> Vec<AlgorithmDescription> {
+        #[cfg_attr(not(feature = "plugins"), allow(unused_mut))]
+        let mut algorithms: Vec<AlgorithmDescription> = BUILT_IN_ALGORITHMS
+            .iter()
+            .map(|built_in| AlgorithmDescription {
+                name: built_in.name.to_string(),
+                params_schema: (built_in.params_schema)(),
+            })
+            .collect();
+
+        // plugins don't expose their parameters
+        #[cfg(feature = "plugins")]
+        for name in list_plugins() {
+            algorithms.push(AlgorithmDescription {
+                name,
+                params_schema: serde_json::json!({ "type": "object" }),
+            });
+        }
+
+        algorithms
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Tests every listed algorithm is built by its name, failing on its parameters at most.
+    #[test]
+    fn test_build_listed_algorithms() {
+        let algorithms = AlgorithmBuilder::list_algorithms();
+        assert_eq!(algorithms.len(), BUILT_IN_ALGORITHMS.len());
+
+        for algorithm in algorithms {
+            let result =
+                AlgorithmBuilder::build_algorithm(&algorithm.name, Interval::Min1, json!({}));
+            assert!(
+                !matches!(result, Err(AlgorithmError::UnkownName(_))),
+                "{} isn't built",
+                algorithm.name
+            );
+        }
+
+        assert!(matches!(
+            AlgorithmBuilder::build_algorithm("Unknown", Interval::Min1, json!({})),
+            Err(AlgorithmError::UnkownName(_))
+        ));
     }
 }
@@ -1,21 +1,155 @@
-use std::sync::Arc;
+use std::{
+    collections::{hash_map::Entry, HashMap, VecDeque},
+    sync::Arc,
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 use crate::{
     account::{
         account::Account,
+        money::Price,
         trade::{OrderSide, PositionId, TradeTx},
     },
+    events::EventPublisher,
     exchange::{api::ExchangeApi, mock::MockExchangeApi},
-    market::{kline::KlineData, market::Market, messages::MarketMessage, types::ArcMutex},
+    market::{
+        external::ExternalDataStore,
+        interval::Interval,
+        kline::{Kline, KlineData},
+        kline_stream::KlineStream,
+        market::Market,
+        messages::MarketMessage,
+        symbol::canonical_symbol,
+        trade::TradeData,
+        types::{ArcMutex, ArcSender},
+    },
     storage::{fs::FsStorage, manager::StorageManager},
     strategy::{
+        engine::{self, EquityCurve, DEFAULT_MAX_CURVE_POINTS},
+        fills::{FillModel, FillSimulator},
+        funding::FundingModel,
+        jobs::BackTestProgress,
+        metrics::{
+            calc_period_returns, periods_per_year, Benchmark, EquityPoint, PerformanceMetrics,
+        },
         signal::SignalManager,
-        strategy::{Strategy, StrategySummary},
-        types::{AlgorithmEvalResult, SignalMessage},
+        strategy::{Strategy, StrategyId, StrategySettings, StrategySummary},
+        types::{AlgorithmError, SignalMessage},
+    },
+    utils::{
+        channel::{build_arc_channel, OverflowPolicy, DEFAULT_MARKET_CHANNEL_CAPACITY},
+        pool::run_parallel,
+        time::{generate_ts, timestamp_to_string, SEC_AS_MILI},
     },
-    utils::channel::build_arc_channel,
 };
 
+/// Historical data replayed by a backtest.
+///
+/// `Trades` replays stored market trades, building k-lines on the fly and filling orders at
+/// traded prices, see `BackTest::run_trades`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub enum BackTestMode {
+    #[default]
+    Klines,
+    Trades,
+}
+
+/// Simulation settings of a backtest beyond the strategy settings.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct BackTestOptions {
+    /// Funding payments applied to open positions of perpetual symbols, none by default.
+    #[serde(default)]
+    pub funding: Option<FundingModel>,
+    /// How signals are filled, instantly at the signal price by default.
+    #[serde(default)]
+    pub fill_model: FillModel,
+    /// Milliseconds between a signal and its execution. K-line backtests fill delayed signals
+    /// at the open of the first k-line opening after the delay, e.g. `0` fills at the next
+    /// k-line's open. Without a delay signals fill at the close of the evaluated k-line.
+    #[serde(default)]
+    pub latency: Option<u64>,
+    /// Maximum number of points of the equity and benchmark curves, see `EquityCurve`. Streamed
+    /// runs keep `DEFAULT_MAX_CURVE_POINTS` by default, others every point.
+    #[serde(default)]
+    pub max_curve_points: Option<usize>,
+    /// Share of the value of every fill paid as fee, e.g. `0.0004`, reported by the trade
+    /// export. Fees aren't deducted from the simulated profit, none by default.
+    #[serde(default)]
+    pub fee_rate: Option<f64>,
+}
+
+/// A signal waiting for the execution delay to pass.
+struct PendingSignal {
+    order_side: OrderSide,
+    confidence: f64,
+    due_ts: u64,
+}
+
+/// A strategy configuration of a batch of backtests run in parallel.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BatchBackTest {
+    pub strategy_name: String,
+    #[serde(deserialize_with = "canonical_symbol")]
+    pub symbol: String,
+    pub interval: Interval,
+    pub settings: StrategySettings,
+    pub algorithm_params: Value,
+}
+
+/// A persisted backtest run with the configuration it ran with.
+///
+/// The strategy of every backtest is built fresh, so the strategy id of the summary also
+/// identifies the run. The strategy name, symbol, interval, settings and algorithm parameters
+/// are part of the summary info.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BackTestRecord {
+    pub from_ts: u64,
+    pub to_ts: u64,
+    pub mode: BackTestMode,
+    #[serde(default)]
+    pub options: BackTestOptions,
+    pub created_at: u64,
+    pub summary: StrategySummary,
+}
+
+impl BackTestRecord {
+    pub fn new(
+        from_ts: u64,
+        to_ts: u64,
+        mode: BackTestMode,
+        options: BackTestOptions,
+        summary: StrategySummary,
+    ) -> Self {
+        Self {
+            from_ts,
+            to_ts,
+            mode,
+            options,
+            created_at: generate_ts(),
+            summary,
+        }
+    }
+
+    pub fn id(&self) -> StrategyId {
+        self.summary.info.id
+    }
+
+    /// Returns the record without the trades, positions and curves of its summary, enough to
+    /// list runs without loading their full results.
+    pub fn header(&self) -> Self {
+        let mut header = self.clone();
+        header.summary.trades.clear();
+        header.summary.positions.clear();
+        header.summary.equity_curve.clear();
+        if let Some(benchmark) = header.summary.benchmark.as_mut() {
+            benchmark.equity_curve.clear();
+        }
+        header
+    }
+}
+
 /// Represents a backtest environment for a trading strategy.
 ///
 /// This struct encapsulates the logic to simulate the execution of a trading strategy over
@@ -27,9 +161,20 @@ pub struct BackTest {
     pub signals: Vec<SignalMessage>,
     pub signal_manager: SignalManager,
     account: ArcMutex<Account>,
-    market: ArcMutex<Market>,
+    market: Arc<Market>,
     period_start_price: f64,
     period_end_price: f64,
+    position_open_times: HashMap<PositionId, u64>,
+    interval_ms: u64,
+    equity_curve: EquityCurve,
+    benchmark_curve: EquityCurve,
+    progress: Option<BackTestProgress>,
+    options: BackTestOptions,
+    last_funding_ts: Option<u64>,
+    fill_simulator: FillSimulator,
+    pending_signals: VecDeque<PendingSignal>,
+    stamped_trade_count: usize,
+    external_data: Option<ExternalDataStore>,
 }
 
 impl BackTest {
@@ -46,21 +191,25 @@ impl BackTest {
 
     pub async fn new(
         strategy: Strategy,
-        market: ArcMutex<Market>,
+        _market: Arc<Market>,
         _initial_balance: Option<f64>,
     ) -> Self {
-        let (_, market_rx) = build_arc_channel::<MarketMessage>();
+        let (_, market_rx) = build_arc_channel::<MarketMessage>(
+            DEFAULT_MARKET_CHANNEL_CAPACITY,
+            OverflowPolicy::DropOldest,
+        );
         let exchange_api: Arc<Box<dyn ExchangeApi>> =
             Arc::new(Box::new(MockExchangeApi::default()));
 
         let storage_manager: Arc<Box<dyn StorageManager>> =
             Arc::new(Box::new(FsStorage::default()));
 
-        let market = ArcMutex::new(
+        let market = Arc::new(
             Market::new(
                 market_rx,
                 exchange_api.clone(),
                 storage_manager.clone(),
+                EventPublisher::new(),
                 false,
             )
             .await,
@@ -80,44 +229,216 @@ impl BackTest {
             account,
             period_end_price: 0.0,
             period_start_price: 0.0,
+            position_open_times: HashMap::new(),
+            interval_ms: 0,
+            equity_curve: EquityCurve::new(None),
+            benchmark_curve: EquityCurve::new(None),
+            progress: None,
+            options: BackTestOptions::default(),
+            last_funding_ts: None,
+            fill_simulator: FillSimulator::default(),
+            pending_signals: VecDeque::new(),
+            stamped_trade_count: 0,
+            external_data: None,
+        }
+    }
+
+    /// Feeds the strategy the values external series had at the close of every k-line from the
+    /// given values, rather than from the values retained by the market.
+    pub fn set_external_data(&mut self, external_data: ExternalDataStore) {
+        self.external_data = Some(external_data);
+    }
+
+    /// Sets the simulation settings of the backtest, see `BackTestOptions`.
+    pub fn set_options(&mut self, options: BackTestOptions) {
+        self.fill_simulator = FillSimulator::new(options.fill_model.clone());
+        self.equity_curve = EquityCurve::new(options.max_curve_points);
+        self.benchmark_curve = EquityCurve::new(options.max_curve_points);
+        self.options = options;
+    }
+
+    /// Reports the progress of the backtest, which also stops early once cancelled.
+    pub fn set_progress(&mut self, progress: BackTestProgress) {
+        self.progress = Some(progress);
+    }
+
+    /// Fetches the historical data of a range from the market and runs the backtest over it.
+    ///
+    /// # Arguments
+    ///
+    /// * `market` - The market holding the historical data.
+    /// * `from_ts` - The start of the range.
+    /// * `to_ts` - The end of the range.
+    /// * `mode` - Whether to replay k-lines or trades.
+    pub async fn run_range(
+        &mut self,
+        market: Arc<Market>,
+        from_ts: u64,
+        to_ts: u64,
+        mode: BackTestMode,
+    ) {
+        let symbol = self.strategy.symbol.clone();
+        let interval = self.strategy.info().await.interval;
+        self.set_external_data(market.external_data_until(to_ts).await);
+
+        match mode {
+            BackTestMode::Klines => {
+                let stream = market.kline_stream(&symbol, interval, from_ts, to_ts).await;
+                self.run_stream(stream).await;
+            }
+            BackTestMode::Trades => {
+                let trade_data = market
+                    .trade_data_range(&symbol, Some(from_ts), Some(to_ts), None)
+                    .await;
+                if let Some(trade_data) = trade_data {
+                    self.run_trades(trade_data).await;
+                }
+            }
         }
     }
 
     /// Executes the backtest over a set of historical k-line data.
     ///
+    /// Signals are handled as they are generated, so positions exceeding the strategy's
+    /// `max_position_duration` are closed in k-line order. With a `latency` signals are
+    /// delayed to the open of a later k-line, avoiding fills at a close price only known once
+    /// the k-line is complete. With a `FillModel::Limit` signals rest as limit orders, filled
+    /// by a later k-line trading through them. An equity point is recorded after every fill
+    /// and, marking open positions to market, at the close of every k-line.
+    ///
     /// # Arguments
     ///
     /// * `kline_data` - Historical k-line data over which the backtest will be run.
 
     pub async fn run(&mut self, kline_data: KlineData) {
-        if let Some(first) = kline_data.klines().first() {
-            self.period_start_price = first.open
+        let klines = kline_data.klines();
+        self.start_progress(klines.len());
+
+        for kline in klines {
+            if self.is_cancelled() {
+                break;
+            }
+            self.process_kline(kline).await;
+        }
+    }
+
+    /// Executes the backtest over a stream of historical k-lines, see `run`.
+    ///
+    /// Only a month of k-lines is held in memory at a time and the equity curves are capped at
+    /// `DEFAULT_MAX_CURVE_POINTS` unless `max_curve_points` is set, so long ranges run with
+    /// bounded memory.
+    ///
+    /// # Arguments
+    ///
+    /// * `stream` - The stream of historical k-lines over which the backtest will be run.
+    pub async fn run_stream(&mut self, mut stream: KlineStream) {
+        if self.options.max_curve_points.is_none() {
+            self.equity_curve = EquityCurve::new(Some(DEFAULT_MAX_CURVE_POINTS));
+            self.benchmark_curve = EquityCurve::new(Some(DEFAULT_MAX_CURVE_POINTS));
+        }
+        self.start_progress(stream.estimated_len());
+
+        while let Some(kline) = stream.next().await {
+            if self.is_cancelled() {
+                break;
+            }
+            self.process_kline(kline).await;
+        }
+    }
+
+    /// Executes the backtest over historical market trades instead of k-lines.
+    ///
+    /// Trades are aggregated into k-lines of the strategy interval as they are replayed. A
+    /// k-line is evaluated once the first trade of a later k-line arrives and its signal is
+    /// filled at the price of the first trade after the `latency`, rather than at the close of
+    /// the evaluated k-line. With a `FillModel::Limit` the signal rests as a limit order
+    /// instead, placed at that trade's price and matched against every later trade. Stop losses
+    /// and the `max_position_duration` are checked on every trade. Intervals without trades
+    /// produce no k-line.
+    ///
+    /// # Arguments
+    ///
+    /// * `trade_data` - Historical trade data over which the backtest will be run.
+    pub async fn run_trades(&mut self, trade_data: TradeData) {
+        let interval = self.strategy.info().await.interval;
+        self.interval_ms = interval.to_millis();
+        if self.interval_ms == 0 {
+            return;
+        }
+
+        let trades = trade_data.trades();
+        if let Some(first) = trades.first() {
+            self.period_start_price = first.price;
         }
-        if let Some(last) = kline_data.klines().last() {
-            self.period_end_price = last.close
+        if let Some(last) = trades.last() {
+            self.period_end_price = last.price;
         }
 
-        for kline in kline_data.klines() {
-            let eval_result = self.strategy.algorithm.lock().await.evaluate(kline.clone());
+        let latency = self.options.latency.unwrap_or(0);
+        let mut candle: Option<Kline> = None;
+        self.start_progress(trades.len());
+
+        for trade in trades {
+            if self.is_cancelled() {
+                break;
+            }
+            let fills = self.fill_count().await;
+            let open_time = trade.timestamp - trade.timestamp % self.interval_ms;
 
-            let order_side = match eval_result {
-                AlgorithmEvalResult::Buy => OrderSide::Buy,
-                AlgorithmEvalResult::Sell => OrderSide::Sell,
-                AlgorithmEvalResult::Ignore => {
-                    continue;
+            if let Some(kline) = candle.take_if(|kline| kline.open_time != open_time) {
+                self.close_candle(kline, latency).await;
+            }
+
+            self.apply_funding(trade.timestamp, trade.price).await;
+            self.close_stopped_positions(trade.price).await;
+            self.stamp_fills(trade.timestamp).await;
+            self.close_expired_positions(trade.timestamp, trade.price)
+                .await;
+
+            self.execute_due_signals(interval, trade.price, trade.timestamp)
+                .await;
+            if let Some(order) = self.fill_simulator.on_trade(&trade) {
+                self.handle_signal(
+                    interval,
+                    order.price,
+                    trade.timestamp,
+                    order.order_side,
+                    order.confidence,
+                )
+                .await;
+            }
+
+            match candle.as_mut() {
+                Some(kline) => {
+                    kline.high = kline.high.max(trade.price);
+                    kline.low = kline.low.min(trade.price);
+                    kline.close = trade.price;
+                    kline.volume += trade.qty;
+                }
+                None => {
+                    candle = Some(Kline {
+                        symbol: trade.symbol.clone(),
+                        interval,
+                        open: trade.price,
+                        high: trade.price,
+                        low: trade.price,
+                        close: trade.price,
+                        volume: trade.qty,
+                        open_time,
+                        close_time: open_time + self.interval_ms - 1,
+                    })
                 }
-            };
+            }
 
-            let signal = SignalMessage {
-                strategy_id: self.strategy.id,
-                order_side,
-                symbol: self.strategy.symbol.to_string(),
-                price: kline.close.clone(),
-                is_back_test: true,
-                timestamp: kline.close_time,
-            };
+            if self.fill_count().await != fills {
+                self.record_equity(trade.timestamp, trade.price, true).await;
+            }
+            self.advance_progress();
+        }
 
-            self.add_signal(signal)
+        // the last candle is partial, it closes at the end of the trades
+        if let Some(kline) = candle.filter(|_| !self.is_cancelled()) {
+            self.close_candle(kline, latency).await;
         }
     }
 
@@ -136,23 +457,15 @@ impl BackTest {
     /// # Returns
     ///
     /// Returns a `StrategySummary` detailing the results of the backtest, including profit, drawdown,
-    /// trade counts, and other relevant metrics.
-
+    /// trade counts, risk adjusted metrics and other relevant metrics.
     pub async fn result(&mut self) -> StrategySummary {
-        for signal in &self.signals {
-            self.signal_manager
-                .handle_signal(signal.clone(), self.market.clone(), self.account.clone())
-                .await
-        }
-
         let info = self.strategy.info().await;
 
-        let active_positions: Vec<(PositionId, f64)> = self
+        let active_positions: Vec<(PositionId, Price)> = self
             .account
             .lock()
             .await
             .positions()
-            .into_iter()
             .map(|item| (item.id, item.open_price))
             .collect();
 
@@ -164,6 +477,9 @@ impl BackTest {
                 .close_position(id, open_price)
                 .await;
         }
+        if let Some(last) = self.equity_curve.points().last() {
+            self.stamp_fills(last.timestamp).await;
+        }
 
         // get all trade txs
         let trades: Vec<TradeTx> = self.account.lock().await.trades();
@@ -173,6 +489,28 @@ impl BackTest {
         let long_trade_count = Strategy::calc_trade_count(&trades, OrderSide::Buy);
         let short_trade_count = Strategy::calc_trade_count(&trades, OrderSide::Sell);
         let profit: f64 = Strategy::calc_profit(&trades);
+        let funding_paid = Strategy::calc_funding_paid(&trades, &[]);
+        // per-period returns only use the k-line close points, fills would skew the periods
+        let candle_equity: Vec<f64> = self
+            .equity_curve
+            .points()
+            .iter()
+            .filter(|point| !point.fill)
+            .map(|point| point.equity)
+            .collect();
+        // a point of a downsampled curve spans several k-lines
+        let period_ms = self.interval_ms * self.equity_curve.period_len();
+        let metrics = PerformanceMetrics::new(
+            &trades,
+            &calc_period_returns(&candle_equity),
+            periods_per_year(period_ms),
+        );
+        let benchmark = Benchmark::new(
+            self.capital(),
+            &candle_equity,
+            self.benchmark_curve.points().to_vec(),
+            periods_per_year(period_ms),
+        );
 
         StrategySummary {
             info,
@@ -189,6 +527,735 @@ impl BackTest {
             period_start_price: self.period_start_price,
             max_drawdown,
             max_profit,
+            funding_paid,
+            metrics,
+            equity_curve: self.equity_curve.points().to_vec(),
+            benchmark,
+        }
+    }
+
+    // ---
+    // Private Methods
+    // ---
+
+    /// Builds a signal from an algorithm evaluation and hands it to the signal manager.
+    ///
+    /// # Arguments
+    ///
+    /// * `interval` - The interval of the k-line the signal was generated on.
+    /// * `price` - The price the signal is filled at.
+    /// * `timestamp` - The backtest time of the fill.
+    /// * `order_side` - The side of the signal.
+    /// * `confidence` - The confidence of the signal.
+    async fn handle_signal(
+        &mut self,
+        interval: Interval,
+        price: f64,
+        timestamp: u64,
+        order_side: OrderSide,
+        confidence: f64,
+    ) {
+        let signal = SignalMessage {
+            strategy_id: self.strategy.id,
+            order_side,
+            symbol: self.strategy.symbol.to_string(),
+            interval,
+            price,
+            confidence,
+            is_back_test: true,
+            timestamp,
+            stop_loss: self.strategy.algorithm.lock().await.stop_loss(),
+        };
+
+        self.add_signal(signal.clone());
+
+        self.signal_manager
+            .handle_signal(signal, self.market.clone(), self.account.clone())
+            .await;
+
+        self.stamp_fills(timestamp).await;
+    }
+
+    /// Stamps new positions and trades with the backtest time instead of the wall clock time
+    /// they were filled at, and tracks the open time of new positions.
+    async fn stamp_fills(&mut self, timestamp: u64) {
+        let mut account = self.account.lock().await;
+        for position in account.positions_mut() {
+            if let Entry::Vacant(open_time) = self.position_open_times.entry(position.id) {
+                position.open_time = timestamp_to_string(timestamp);
+                open_time.insert(timestamp);
+            }
+        }
+
+        let trades = account.trades_mut();
+        let trade_count = trades.len();
+        for trade in trades.skip(self.stamped_trade_count) {
+            trade.close_time = timestamp_to_string(timestamp);
+        }
+        self.stamped_trade_count = trade_count;
+    }
+
+    /// Replays a k-line: settles funding, closes expired positions, fills due signals and
+    /// resting orders, closes positions stopped within the k-line, evaluates the strategy and
+    /// records the equity.
+    async fn process_kline(&mut self, kline: Kline) {
+        if self.equity_curve.points().is_empty() {
+            self.period_start_price = kline.open;
+            self.interval_ms = kline.interval.to_millis();
         }
+        self.period_end_price = kline.close;
+        let fills = self.fill_count().await;
+
+        self.apply_funding(kline.close_time, kline.close).await;
+        self.close_expired_positions(kline.close_time, kline.close)
+            .await;
+
+        self.execute_due_signals(kline.interval, kline.open, kline.open_time)
+            .await;
+        if let Some(order) = self.fill_simulator.on_kline(&kline) {
+            self.handle_signal(
+                kline.interval,
+                order.price,
+                kline.close_time,
+                order.order_side,
+                order.confidence,
+            )
+            .await;
+        }
+        self.close_kline_stopped_positions(&kline).await;
+
+        let eval_result =
+            engine::evaluate(&self.strategy, kline.clone(), self.external_data.as_ref()).await;
+
+        if let Some((order_side, confidence)) = eval_result.order_side() {
+            match self.options.latency {
+                Some(latency) => self.pending_signals.push_back(PendingSignal {
+                    order_side,
+                    confidence,
+                    due_ts: kline.close_time + latency,
+                }),
+                None => {
+                    self.submit_signal(
+                        kline.interval,
+                        kline.close,
+                        kline.close_time,
+                        order_side,
+                        confidence,
+                    )
+                    .await
+                }
+            }
+        }
+
+        if self.fill_count().await != fills {
+            self.record_equity(kline.close_time, kline.close, true)
+                .await;
+        }
+        self.record_equity(kline.close_time, kline.close, false)
+            .await;
+        self.advance_progress();
+    }
+
+    /// Records the equity at the close of a candle aggregated from trades and evaluates it,
+    /// queueing its signal to be filled by the first trade after the latency.
+    ///
+    /// # Arguments
+    ///
+    /// * `kline` - The aggregated candle.
+    /// * `latency` - The delay between the close of the candle and the fill of its signal.
+    async fn close_candle(&mut self, kline: Kline, latency: u64) {
+        self.record_equity(kline.close_time, kline.close, false)
+            .await;
+
+        let close_time = kline.close_time;
+        let eval_result =
+            engine::evaluate(&self.strategy, kline, self.external_data.as_ref()).await;
+        if let Some((order_side, confidence)) = eval_result.order_side() {
+            self.pending_signals.push_back(PendingSignal {
+                order_side,
+                confidence,
+                due_ts: close_time + latency,
+            });
+        }
+    }
+
+    /// Fills a signal at a price, or places it as a limit order with a `FillModel::Limit`.
+    async fn submit_signal(
+        &mut self,
+        interval: Interval,
+        price: f64,
+        timestamp: u64,
+        order_side: OrderSide,
+        confidence: f64,
+    ) {
+        if self.fill_simulator.is_instant() {
+            self.handle_signal(interval, price, timestamp, order_side, confidence)
+                .await;
+        } else {
+            self.fill_simulator
+                .place(order_side, confidence, price, timestamp);
+        }
+    }
+
+    /// Submits the pending signals whose execution delay has passed.
+    ///
+    /// # Arguments
+    ///
+    /// * `interval` - The interval of the strategy.
+    /// * `price` - The current price, signals are submitted at.
+    /// * `timestamp` - The current backtest timestamp.
+    async fn execute_due_signals(&mut self, interval: Interval, price: f64, timestamp: u64) {
+        while self
+            .pending_signals
+            .front()
+            .is_some_and(|signal| signal.due_ts <= timestamp)
+        {
+            // SAFETY: the queue isn't empty, checked above
+            let signal = self.pending_signals.pop_front().unwrap();
+            self.submit_signal(
+                interval,
+                price,
+                timestamp,
+                signal.order_side,
+                signal.confidence,
+            )
+            .await;
+        }
+    }
+
+    fn start_progress(&self, total: usize) {
+        if let Some(progress) = &self.progress {
+            progress.set_total(total);
+        }
+    }
+
+    fn advance_progress(&self) {
+        if let Some(progress) = &self.progress {
+            progress.increment();
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.progress
+            .as_ref()
+            .is_some_and(|progress| progress.is_cancelled())
+    }
+
+    /// Counts the closed trades and open positions, a change in either means an order was filled.
+    async fn fill_count(&self) -> (usize, usize) {
+        engine::fill_count(&self.account, &[self.strategy.id]).await
+    }
+
+    /// Records the equity of the strategy, the capital it can deploy plus the realized profit of
+    /// closed trades and the unrealized profit of open positions. K-line close points are also
+    /// recorded for the buy-and-hold benchmark.
+    ///
+    /// # Arguments
+    ///
+    /// * `timestamp` - The current backtest timestamp.
+    /// * `price` - The price open positions are marked at.
+    /// * `fill` - Whether the point is recorded after a fill.
+    async fn record_equity(&mut self, timestamp: u64, price: f64, fill: bool) {
+        let capital = self.capital();
+        let equity =
+            engine::calc_equity(&self.account, &[self.strategy.id], capital, |_| Some(price)).await;
+
+        self.equity_curve.push(EquityPoint {
+            timestamp,
+            equity,
+            fill,
+        });
+
+        // the benchmark holds the capital worth of the symbol since the period start
+        if !fill && self.period_start_price > 0.0 {
+            self.benchmark_curve.push(EquityPoint {
+                timestamp,
+                equity: capital * price / self.period_start_price,
+                fill,
+            });
+        }
+    }
+
+    /// Settles the funding of the configured `FundingModel` due since the previous call on all
+    /// open positions, marking their notional at the current price.
+    ///
+    /// # Arguments
+    ///
+    /// * `timestamp` - The current backtest timestamp.
+    /// * `price` - The current price of the symbol.
+    async fn apply_funding(&mut self, timestamp: u64, price: f64) {
+        let funding = match &self.options.funding {
+            Some(funding) => funding,
+            None => return,
+        };
+        let from_ts = self.last_funding_ts.unwrap_or(timestamp);
+        self.last_funding_ts = Some(timestamp);
+
+        let settlements = funding.settlements(from_ts, timestamp);
+        if settlements.is_empty() {
+            return;
+        }
+
+        let mut account = self.account.lock().await;
+        for (_, rate) in settlements {
+            for position in account.positions_mut() {
+                position.apply_funding(rate, price.into());
+            }
+        }
+    }
+
+    /// The capital the strategy can deploy, the margin of all its allowed open orders.
+    fn capital(&self) -> f64 {
+        let settings = self.strategy.settings();
+        settings.margin_usd * settings.max_open_orders as f64
+    }
+
+    /// Closes positions whose stop loss or take profit is reached by a traded price, at that
+    /// price.
+    async fn close_stopped_positions(&mut self, price: f64) {
+        let price = Price::from_f64(price);
+        let mut account = self.account.lock().await;
+        let stopped_ids: Vec<PositionId> = account
+            .strategy_positions(self.strategy.id)
+            .iter()
+            .filter(|position| {
+                position.is_stop_loss_hit(price) || position.is_take_profit_hit(price)
+            })
+            .map(|position| position.id)
+            .collect();
+
+        for id in stopped_ids {
+            account.close_position(id, price).await;
+        }
+    }
+
+    /// Closes positions whose stop loss or take profit lies within the range of a k-line, at the
+    /// stop loss or take profit price. The stop loss is assumed to be reached first when the
+    /// k-line spans both, since the order of prices within a k-line is unknown.
+    ///
+    /// # Arguments
+    ///
+    /// * `kline` - The k-line being replayed.
+    async fn close_kline_stopped_positions(&mut self, kline: &Kline) {
+        let (low, high) = (Price::from_f64(kline.low), Price::from_f64(kline.high));
+        let mut account = self.account.lock().await;
+        let stopped: Vec<(PositionId, Price)> = account
+            .strategy_positions(self.strategy.id)
+            .iter()
+            .filter_map(|position| {
+                if position.is_stop_loss_hit(low) || position.is_stop_loss_hit(high) {
+                    position.stop_loss.map(|price| (position.id, price))
+                } else if position.is_take_profit_hit(low) || position.is_take_profit_hit(high) {
+                    position.take_profit.map(|price| (position.id, price))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        if stopped.is_empty() {
+            return;
+        }
+
+        for (id, price) in stopped {
+            account.close_position(id, price).await;
+        }
+        drop(account);
+        self.stamp_fills(kline.close_time).await;
+    }
+
+    /// Closes positions that have been open longer than the `max_position_duration` setting
+    /// of the strategy, measured in backtest time.
+    ///
+    /// # Arguments
+    ///
+    /// * `timestamp` - The current backtest timestamp.
+    /// * `close_price` - The price used to close expired positions.
+    async fn close_expired_positions(&mut self, timestamp: u64, close_price: f64) {
+        let max_duration = match self.strategy.settings().max_position_duration {
+            Some(max_duration) => max_duration * SEC_AS_MILI,
+            None => return,
+        };
+
+        engine::close_expired_positions(
+            &self.account,
+            &mut self.position_open_times,
+            self.strategy.id,
+            max_duration,
+            timestamp,
+            close_price,
+        )
+        .await;
+        self.stamp_fills(timestamp).await;
+    }
+}
+
+/// Backtests a strategy repeatedly with different algorithm parameters, as done by walk-forward
+/// analysis and parameter optimization.
+///
+/// Every run builds a fresh strategy, so the first `max_period` k-lines of each run are used to
+/// warm the algorithm up.
+#[derive(Clone)]
+pub struct BackTestRunner {
+    strategy_name: String,
+    symbol: String,
+    interval: Interval,
+    strategy_tx: ArcSender<SignalMessage>,
+    market: Arc<Market>,
+    settings: StrategySettings,
+}
+
+impl BackTestRunner {
+    pub fn new(
+        strategy_name: &str,
+        symbol: &str,
+        interval: Interval,
+        strategy_tx: ArcSender<SignalMessage>,
+        market: Arc<Market>,
+        settings: StrategySettings,
+    ) -> Self {
+        Self {
+            strategy_name: strategy_name.to_string(),
+            symbol: symbol.to_string(),
+            interval,
+            strategy_tx,
+            market,
+            settings,
+        }
+    }
+
+    pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    pub fn interval(&self) -> Interval {
+        self.interval
+    }
+
+    /// Backtests the strategy with the given algorithm parameters over a slice of k-lines.
+    ///
+    /// # Arguments
+    ///
+    /// * `algorithm_params` - The algorithm parameters of the strategy.
+    /// * `klines` - The k-lines to backtest over, sorted by open time.
+    ///
+    /// # Returns
+    ///
+    /// The `StrategySummary` of the backtest, or an `AlgorithmError` if the strategy can't be
+    /// built with the given parameters.
+    pub async fn run(
+        &self,
+        algorithm_params: Value,
+        klines: &[Kline],
+    ) -> Result<StrategySummary, AlgorithmError> {
+        self.run_with_warm_up(algorithm_params, &[], klines).await
+    }
+
+    /// Backtests the strategy over a slice of k-lines, see `run`, after warming the algorithm
+    /// up with the k-lines preceding it instead of the first k-lines of the slice.
+    ///
+    /// # Arguments
+    ///
+    /// * `algorithm_params` - The algorithm parameters of the strategy.
+    /// * `warm_up` - The k-lines preceding the slice, sorted by open time.
+    /// * `klines` - The k-lines to backtest over, sorted by open time.
+    ///
+    /// # Returns
+    ///
+    /// The `StrategySummary` of the backtest, or an `AlgorithmError` if the strategy can't be
+    /// built with the given parameters.
+    pub async fn run_with_warm_up(
+        &self,
+        algorithm_params: Value,
+        warm_up: &[Kline],
+        klines: &[Kline],
+    ) -> Result<StrategySummary, AlgorithmError> {
+        let strategy = Strategy::new(
+            &self.strategy_name,
+            &self.symbol,
+            self.interval,
+            self.strategy_tx.clone(),
+            self.market.clone(),
+            self.settings.clone(),
+            algorithm_params,
+        )?;
+        strategy.warm_up_with(warm_up).await;
+
+        let mut kline_data = KlineData::new(&self.symbol, self.interval);
+        for kline in klines {
+            kline_data.add_kline(kline.clone());
+        }
+
+        let mut back_test = BackTest::new(strategy, self.market.clone(), None).await;
+        back_test.run(kline_data).await;
+
+        Ok(back_test.result().await)
+    }
+
+    /// Backtests the strategy with several sets of algorithm parameters in parallel, see
+    /// `run_parallel`. The k-lines are shared by all backtests rather than copied per worker.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The algorithm parameters of every backtest.
+    /// * `klines` - The k-lines to backtest over, sorted by open time.
+    ///
+    /// # Returns
+    ///
+    /// The `StrategySummary` of every backtest in input order, or the `AlgorithmError` of a
+    /// backtest whose strategy can't be built, e.g. with invalid parameters.
+    pub async fn run_batch(
+        &self,
+        params: Vec<Value>,
+        klines: Arc<[Kline]>,
+    ) -> Vec<Result<StrategySummary, AlgorithmError>> {
+        let futures: Vec<_> = params
+            .into_iter()
+            .map(|algorithm_params| {
+                let runner = self.clone();
+                let klines = klines.clone();
+                async move { runner.run(algorithm_params, &klines).await }
+            })
+            .collect();
+
+        run_parallel(futures)
+            .await
+            .into_iter()
+            .map(|result| result.map_err(|e| AlgorithmError::InvalidParams(e.to_string()))?)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        algorithm::harness::Fixture,
+        market::{external::ExternalDataPoint, trade::Trade},
+    };
+    use serde_json::json;
+
+    async fn market() -> Arc<Market> {
+        let (_, market_rx) = build_arc_channel::<MarketMessage>(
+            DEFAULT_MARKET_CHANNEL_CAPACITY,
+            OverflowPolicy::DropOldest,
+        );
+        let exchange_api: Arc<Box<dyn ExchangeApi>> =
+            Arc::new(Box::new(MockExchangeApi::default()));
+        let storage_manager: Arc<Box<dyn StorageManager>> =
+            Arc::new(Box::new(FsStorage::default()));
+        Arc::new(
+            Market::new(
+                market_rx,
+                exchange_api,
+                storage_manager,
+                EventPublisher::new(),
+                false,
+            )
+            .await,
+        )
+    }
+
+    async fn back_test(
+        latency: Option<u64>,
+        settings: StrategySettings,
+    ) -> Result<BackTest, AlgorithmError> {
+        let market = market().await;
+        let (strategy_tx, _) = build_arc_channel::<SignalMessage>(16, OverflowPolicy::DropOldest);
+
+        // only enters on the first k-line
+        let strategy = Strategy::new(
+            "DcaAccumulator",
+            "BTCUSDT",
+            Interval::Min1,
+            strategy_tx,
+            market.clone(),
+            settings,
+            json!({ "buy_interval_mins": 100_000 }),
+        )?;
+
+        let mut back_test = BackTest::new(strategy, market, None).await;
+        back_test.set_options(BackTestOptions {
+            latency,
+            ..Default::default()
+        });
+        Ok(back_test)
+    }
+
+    async fn open_prices(back_test: &BackTest) -> Vec<f64> {
+        back_test
+            .account
+            .lock()
+            .await
+            .positions()
+            .map(|position| position.open_price.to_f64())
+            .collect()
+    }
+
+    fn kline_data(step: f64) -> KlineData {
+        let mut kline_data = KlineData::new("BTCUSDT", Interval::Min1);
+        for i in 0..4 {
+            let open = 100.0 + i as f64 * step;
+            kline_data.add_kline(Kline {
+                symbol: "BTCUSDT".to_string(),
+                interval: Interval::Min1,
+                open,
+                high: open + 2.0,
+                low: open - 1.0,
+                close: open + 1.0,
+                volume: 1.0,
+                open_time: i * 60_000,
+                close_time: i * 60_000 + 59_999,
+            });
+        }
+        kline_data
+    }
+
+    /// Tests k-line signals fill at the evaluated close without a latency and at the open of the
+    /// first k-line opening after the latency otherwise.
+    #[tokio::test]
+    async fn test_kline_latency() {
+        for (latency, open_price) in [(None, 101.0), (Some(0), 105.0), (Some(90_000), 115.0)] {
+            let mut back_test = back_test(latency, StrategySettings::default())
+                .await
+                .unwrap();
+            back_test.run(kline_data(5.0)).await;
+            assert_eq!(open_prices(&back_test).await, vec![open_price]);
+        }
+    }
+
+    /// Tests runs warmed up on the preceding k-lines signal from their first k-line.
+    #[tokio::test]
+    async fn test_run_with_warm_up() {
+        let (strategy_tx, _) = build_arc_channel::<SignalMessage>(16, OverflowPolicy::DropOldest);
+        let runner = BackTestRunner::new(
+            "Composite",
+            "BTCUSDT",
+            Interval::Min1,
+            strategy_tx,
+            market().await,
+            StrategySettings::default(),
+        );
+        let params = json!({
+            "indicators": { "sma": { "type": "Sma", "period": 3 } },
+            "buy": { "compare": { "left": "close", "op": ">=", "right": "sma" } }
+        });
+        let klines = Fixture::TrendingUp.klines(6);
+        let first_open_price = |summary: StrategySummary| {
+            // positions left open are closed at the end of the run
+            summary
+                .trades
+                .iter()
+                .map(|trade| trade.position.open_price.to_f64())
+                .fold(f64::INFINITY, f64::min)
+        };
+
+        let summary = runner.run(params.clone(), &klines[3..]).await.unwrap();
+        assert_eq!(first_open_price(summary), 105.0);
+
+        let summary = runner
+            .run_with_warm_up(params, &klines[..3], &klines[3..])
+            .await
+            .unwrap();
+        assert_eq!(first_open_price(summary), 103.0);
+    }
+
+    /// Tests k-line replay closes positions at their stop loss or take profit once a k-line
+    /// reaches it.
+    #[tokio::test]
+    async fn test_kline_stops() {
+        let settings = StrategySettings {
+            stop_loss: Some(3.0),
+            take_profit: Some(5.0),
+            ..Default::default()
+        };
+        // opened at 101, stopped at 97.97 by a low of 94 or taken at 106.05 by a high of 107
+        for (step, close_price) in [(-5.0, 97.97), (5.0, 106.05)] {
+            let mut back_test = back_test(None, settings.clone()).await.unwrap();
+            back_test.run(kline_data(step)).await;
+
+            let account = back_test.account.lock().await;
+            assert_eq!(account.positions().count(), 0);
+            let close_prices: Vec<f64> = account
+                .trades()
+                .iter()
+                .map(|trade| trade.close_price.to_f64())
+                .collect();
+            assert_eq!(close_prices.len(), 1);
+            assert!((close_prices[0] - close_price).abs() < 1e-6);
+        }
+    }
+
+    /// Tests trade replay signals fill at the price of the first trade after the latency.
+    #[tokio::test]
+    async fn test_trade_latency() {
+        let mut trade_data = TradeData::new("BTCUSDT");
+        for i in 0..18 {
+            trade_data.add_trade(&mut Trade {
+                symbol: "BTCUSDT".to_string(),
+                timestamp: i * 10_000,
+                qty: 1.0,
+                price: 100.0 + i as f64,
+                order_side: OrderSide::Buy,
+            });
+        }
+
+        for (latency, open_price) in [(None, 106.0), (Some(30_000), 109.0)] {
+            let mut back_test = back_test(latency, StrategySettings::default())
+                .await
+                .unwrap();
+            back_test.run_trades(trade_data.clone()).await;
+            assert_eq!(open_prices(&back_test).await, vec![open_price]);
+        }
+    }
+
+    /// Tests trade replay evaluates the partial candle the trades end in.
+    #[tokio::test]
+    async fn test_trade_last_candle() {
+        let mut trade_data = TradeData::new("BTCUSDT");
+        for i in 0..5 {
+            trade_data.add_trade(&mut Trade {
+                symbol: "BTCUSDT".to_string(),
+                timestamp: i * 10_000,
+                qty: 1.0,
+                price: 100.0 + i as f64,
+                order_side: OrderSide::Buy,
+            });
+        }
+
+        let mut back_test = back_test(None, StrategySettings::default()).await.unwrap();
+        back_test.run_trades(trade_data).await;
+
+        let candle_closes: Vec<u64> = back_test
+            .equity_curve
+            .points()
+            .iter()
+            .filter(|point| !point.fill)
+            .map(|point| point.timestamp)
+            .collect();
+        assert_eq!(candle_closes, vec![59_999]);
+        // the signal of the candle is left without a trade to fill it
+        assert_eq!(back_test.pending_signals.len(), 1);
+    }
+
+    /// Tests k-lines are evaluated with the values external series had at their close, from
+    /// the values loaded by the backtest.
+    #[tokio::test]
+    async fn test_external_data() {
+        let point = |timestamp: u64, value: f64| ExternalDataPoint { timestamp, value };
+        let mut external_data = ExternalDataStore::new(usize::MAX);
+        external_data.insert(
+            "fear_greed",
+            &[point(0, 20.0), point(120_000, 60.0), point(300_000, 90.0)],
+        );
+
+        let mut back_test = back_test(None, StrategySettings::default()).await.unwrap();
+        back_test.set_external_data(external_data);
+        back_test.run(kline_data(1.0)).await;
+
+        // the last k-line closes at 239_999, before the last value was published
+        assert_eq!(
+            back_test.strategy.external_value("fear_greed").await,
+            Some(60.0)
+        );
     }
 }
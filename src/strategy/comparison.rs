@@ -0,0 +1,166 @@
+use serde::{Deserialize, Serialize};
+
+use crate::strategy::{
+    backer::BackTestRecord,
+    metrics::{EquityPoint, PerformanceMetrics},
+    strategy::{StrategyId, StrategyInfo},
+};
+
+/// Differences of a backtest run's results to the baseline run, `None` when a ratio is
+/// undefined for either run.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct MetricDeltas {
+    pub profit: f64,
+    pub max_drawdown: f64,
+    pub trade_count: i64,
+    pub sharpe_ratio: Option<f64>,
+    pub sortino_ratio: Option<f64>,
+    pub calmar_ratio: Option<f64>,
+    pub profit_factor: Option<f64>,
+    pub win_rate: f64,
+    pub expectancy: f64,
+}
+
+/// A backtest run compared against the baseline, with the k-line close points of its equity
+/// curve so runs can be overlaid.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ComparedBackTest {
+    pub id: StrategyId,
+    pub info: StrategyInfo,
+    pub from_ts: u64,
+    pub to_ts: u64,
+    pub profit: f64,
+    pub max_drawdown: f64,
+    pub trade_count: usize,
+    pub metrics: PerformanceMetrics,
+    pub deltas: MetricDeltas,
+    pub equity_curve: Vec<EquityPoint>,
+}
+
+/// Side by side comparison of backtest runs, deltas are relative to the first run.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BackTestComparison {
+    pub baseline: StrategyId,
+    pub back_tests: Vec<ComparedBackTest>,
+}
+
+/// Compares saved backtest runs side by side.
+///
+/// # Arguments
+///
+/// * `records` - The runs to compare, the first one is the baseline.
+///
+/// # Returns
+///
+/// The `BackTestComparison`, or `None` without any run.
+pub fn compare_back_tests(records: &[BackTestRecord]) -> Option<BackTestComparison> {
+    let baseline = records.first()?;
+
+    let back_tests = records
+        .iter()
+        .map(|record| {
+            let summary = &record.summary;
+            ComparedBackTest {
+                id: record.id(),
+                info: summary.info.clone(),
+                from_ts: record.from_ts,
+                to_ts: record.to_ts,
+                profit: summary.profit,
+                max_drawdown: summary.max_drawdown,
+                trade_count: summary.trades.len(),
+                metrics: summary.metrics.clone(),
+                deltas: calc_deltas(baseline, record),
+                equity_curve: summary
+                    .equity_curve
+                    .iter()
+                    .filter(|point| !point.fill)
+                    .cloned()
+                    .collect(),
+            }
+        })
+        .collect();
+
+    Some(BackTestComparison {
+        baseline: baseline.id(),
+        back_tests,
+    })
+}
+
+fn calc_deltas(baseline: &BackTestRecord, record: &BackTestRecord) -> MetricDeltas {
+    let (base, run) = (&baseline.summary, &record.summary);
+    let (base_metrics, run_metrics) = (&base.metrics, &run.metrics);
+
+    MetricDeltas {
+        profit: run.profit - base.profit,
+        max_drawdown: run.max_drawdown - base.max_drawdown,
+        trade_count: run.trades.len() as i64 - base.trades.len() as i64,
+        sharpe_ratio: delta(run_metrics.sharpe_ratio, base_metrics.sharpe_ratio),
+        sortino_ratio: delta(run_metrics.sortino_ratio, base_metrics.sortino_ratio),
+        calmar_ratio: delta(run_metrics.calmar_ratio, base_metrics.calmar_ratio),
+        profit_factor: delta(run_metrics.profit_factor, base_metrics.profit_factor),
+        win_rate: run_metrics.win_rate - base_metrics.win_rate,
+        expectancy: run_metrics.expectancy - base_metrics.expectancy,
+    }
+}
+
+fn delta(value: Option<f64>, baseline: Option<f64>) -> Option<f64> {
+    Some(value? - baseline?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::{
+        backer::{BackTestMode, BackTestOptions},
+        strategy::StrategySummary,
+    };
+    use uuid::Uuid;
+
+    fn record(profit: f64, sharpe_ratio: Option<f64>) -> BackTestRecord {
+        let mut summary = StrategySummary::default();
+        summary.info.id = Uuid::new_v4();
+        summary.profit = profit;
+        summary.metrics.sharpe_ratio = sharpe_ratio;
+        summary.equity_curve = vec![
+            EquityPoint {
+                timestamp: 1,
+                equity: 100.0,
+                fill: false,
+            },
+            EquityPoint {
+                timestamp: 1,
+                equity: 101.0,
+                fill: true,
+            },
+        ];
+        BackTestRecord::new(
+            0,
+            1,
+            BackTestMode::Klines,
+            BackTestOptions::default(),
+            summary,
+        )
+    }
+
+    /// Tests deltas are relative to the first run and fill points are left out.
+    #[test]
+    fn test_compare_back_tests() {
+        assert!(compare_back_tests(&[]).is_none());
+
+        let records = vec![
+            record(100.0, Some(1.0)),
+            record(150.0, Some(1.5)),
+            record(50.0, None),
+        ];
+        let comparison = compare_back_tests(&records).unwrap();
+
+        assert_eq!(comparison.baseline, records[0].id());
+        assert_eq!(comparison.back_tests[0].deltas.profit, 0.0);
+        assert_eq!(comparison.back_tests[0].deltas.sharpe_ratio, Some(0.0));
+        assert_eq!(comparison.back_tests[1].deltas.profit, 50.0);
+        assert_eq!(comparison.back_tests[1].deltas.sharpe_ratio, Some(0.5));
+        assert_eq!(comparison.back_tests[2].deltas.profit, -50.0);
+        assert_eq!(comparison.back_tests[2].deltas.sharpe_ratio, None);
+        assert_eq!(comparison.back_tests[1].equity_curve.len(), 1);
+    }
+}
@@ -0,0 +1,191 @@
+use std::{
+    collections::{vec_deque::Iter, HashMap, VecDeque},
+    ops::Index,
+};
+
+use crate::market::{
+    kline::Kline,
+    regime::{detect_regime, MarketRegime, REGIME_PERIOD},
+};
+
+/// Number of largest periods of k-lines retained for an algorithm, a margin over the
+/// largest period keeps smoothed indicators such as EMAs accurate.
+pub const DATA_POINT_RETENTION_MULTIPLIER: usize = 10;
+
+/// Minimum number of k-lines retained, regardless of the algorithm periods.
+pub const MIN_DATA_POINT_RETENTION: usize = 100;
+
+/// Calculates the number of k-lines retained for an algorithm.
+///
+/// # Arguments
+///
+/// * `max_period` - The largest period used by the algorithm.
+///
+/// # Returns
+///
+/// The number of k-lines to retain.
+pub fn retention_for_period(max_period: usize) -> usize {
+    max_period
+        .saturating_mul(DATA_POINT_RETENTION_MULTIPLIER)
+        .max(MIN_DATA_POINT_RETENTION)
+}
+
+/// Ring buffer of the historical k-lines evaluated by a strategy's algorithm.
+///
+/// Owned by the `Strategy` and lent to the algorithm on every evaluation, so algorithms no
+/// longer copy and prune their own history. The oldest k-lines are dropped once the
+/// retention is exceeded.
+///
+/// Alongside the k-lines it holds the values external series had when the latest k-line
+/// closed, e.g. news sentiment or the fear and greed index, see `market::external`.
+pub struct AlgorithmDataPointManager {
+    data_points: VecDeque<Kline>,
+    retention: usize,
+    external: HashMap<String, f64>,
+}
+
+impl AlgorithmDataPointManager {
+    /// Creates an empty data point manager.
+    ///
+    /// # Arguments
+    ///
+    /// * `retention` - The maximum number of k-lines retained.
+    pub fn new(retention: usize) -> Self {
+        Self {
+            data_points: VecDeque::with_capacity(retention),
+            retention: retention.max(1),
+            external: HashMap::new(),
+        }
+    }
+
+    /// Appends a k-line, dropping the oldest k-lines beyond the retention.
+    ///
+    /// # Arguments
+    ///
+    /// * `kline` - The k-line to append.
+    pub fn push(&mut self, kline: Kline) {
+        self.data_points.push_back(kline);
+        self.trim();
+    }
+
+    /// Updates the maximum number of k-lines retained, e.g. after algorithm params change.
+    ///
+    /// # Arguments
+    ///
+    /// * `retention` - The maximum number of k-lines retained.
+    pub fn set_retention(&mut self, retention: usize) {
+        self.retention = retention.max(1);
+        self.trim();
+    }
+
+    pub fn retention(&self) -> usize {
+        self.retention
+    }
+
+    pub fn len(&self) -> usize {
+        self.data_points.len()
+    }
+
+    /// Iterates the retained k-lines from oldest to newest.
+    pub fn iter(&self) -> Iter<'_, Kline> {
+        self.data_points.iter()
+    }
+
+    /// Sets the values of the external series as of the k-line evaluated next.
+    ///
+    /// # Arguments
+    ///
+    /// * `external` - The value of each series by name.
+    pub fn set_external(&mut self, external: HashMap<String, f64>) {
+        self.external = external;
+    }
+
+    /// Retrieves the value of an external series as of the latest k-line, `None` if the series
+    /// isn't fed or had no value yet.
+    pub fn external(&self, series: &str) -> Option<f64> {
+        self.external.get(series).copied()
+    }
+
+    /// Classifies the market regime of the most recent retained k-lines.
+    ///
+    /// # Returns
+    ///
+    /// The `MarketRegime`, or `None` if fewer than `REGIME_PERIOD + 1` k-lines are retained.
+    pub fn regime(&self) -> Option<MarketRegime> {
+        let skip = self.data_points.len().saturating_sub(REGIME_PERIOD + 1);
+        let klines: Vec<Kline> = self.data_points.iter().skip(skip).cloned().collect();
+
+        detect_regime(&klines)
+    }
+
+    // ---
+    // Private Methods
+    // ---
+
+    fn trim(&mut self) {
+        while self.data_points.len() > self.retention {
+            self.data_points.pop_front();
+        }
+    }
+}
+
+/// Indexes the retained k-lines, where `0` is the oldest retained k-line.
+impl Index<usize> for AlgorithmDataPointManager {
+    type Output = Kline;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.data_points[index]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::interval::Interval;
+
+    fn kline(close: f64) -> Kline {
+        Kline {
+            symbol: "BTCUSDT".to_string(),
+            interval: Interval::Min1,
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 1.0,
+            open_time: 0,
+            close_time: 0,
+        }
+    }
+
+    /// Tests the oldest data points are dropped beyond the retention.
+    #[test]
+    fn test_data_point_retention() {
+        let mut data_points = AlgorithmDataPointManager::new(3);
+        for close in 1..=5 {
+            data_points.push(kline(close as f64));
+        }
+
+        let closes = |data_points: &AlgorithmDataPointManager| -> Vec<f64> {
+            data_points.iter().map(|kline| kline.close).collect()
+        };
+        assert_eq!(closes(&data_points), vec![3.0, 4.0, 5.0]);
+
+        data_points.set_retention(2);
+        assert_eq!(closes(&data_points), vec![4.0, 5.0]);
+        assert_eq!(data_points[1].close, 5.0);
+
+        data_points.set_external(HashMap::from([("fear_greed".to_string(), 40.0)]));
+        assert_eq!(data_points.external("fear_greed"), Some(40.0));
+        assert_eq!(data_points.external("sentiment"), None);
+    }
+
+    /// Tests retention is derived from the largest algorithm period.
+    #[test]
+    fn test_retention_for_period() {
+        assert_eq!(retention_for_period(0), MIN_DATA_POINT_RETENTION);
+        assert_eq!(
+            retention_for_period(50),
+            50 * DATA_POINT_RETENTION_MULTIPLIER
+        );
+    }
+}
@@ -0,0 +1,315 @@
+use std::collections::HashMap;
+
+use crate::{
+    account::{
+        account::Account,
+        trade::{Position, PositionId},
+    },
+    market::{external::ExternalDataStore, kline::Kline, types::ArcMutex},
+    strategy::{
+        metrics::EquityPoint,
+        strategy::{Strategy, StrategyId},
+        types::AlgorithmEvalResult,
+    },
+};
+
+/// Default maximum number of points of the equity curves of streamed backtests.
+pub const DEFAULT_MAX_CURVE_POINTS: usize = 10_000;
+
+/// An equity curve of a backtest, optionally capped at a maximum number of points.
+///
+/// Uncapped curves keep every point. Capped curves only keep k-line close points, one per
+/// period of `period_len` closes. Once the cap is exceeded every other point is dropped and the
+/// period doubles, so memory stays bounded however long the run. The last point is always
+/// the latest close, even before its period is complete.
+#[derive(Debug, Clone)]
+pub struct EquityCurve {
+    points: Vec<EquityPoint>,
+    max_points: Option<usize>,
+    period_len: u64,
+    close_count: u64,
+}
+
+impl EquityCurve {
+    /// Creates a new `EquityCurve`.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_points` - The maximum number of points, every point is kept without one.
+    ///
+    /// # Returns
+    ///
+    /// Returns a new instance of `EquityCurve`.
+    pub fn new(max_points: Option<usize>) -> Self {
+        Self {
+            points: vec![],
+            // a single point can't be halved
+            max_points: max_points.map(|max_points| max_points.max(2)),
+            period_len: 1,
+            close_count: 0,
+        }
+    }
+
+    /// Adds a point to the curve, replacing the latest close of an incomplete period.
+    pub fn push(&mut self, point: EquityPoint) {
+        let max_points = match self.max_points {
+            Some(max_points) => max_points,
+            None => return self.points.push(point),
+        };
+        if point.fill {
+            return;
+        }
+
+        // the previous close is only kept once its period is complete
+        if !self.close_count.is_multiple_of(self.period_len) {
+            self.points.pop();
+        }
+        self.points.push(point);
+        self.close_count += 1;
+
+        // halving keeps every other period end, which needs an even number of complete periods
+        if self.close_count.is_multiple_of(2 * self.period_len) && self.points.len() > max_points {
+            let mut index = 0;
+            self.points.retain(|_| {
+                index += 1;
+                index % 2 == 0
+            });
+            self.period_len *= 2;
+        }
+    }
+
+    /// Returns the points of the curve in time order.
+    pub fn points(&self) -> &[EquityPoint] {
+        &self.points
+    }
+
+    /// Returns the number of k-line closes a point of the curve spans.
+    pub fn period_len(&self) -> u64 {
+        self.period_len
+    }
+}
+
+/// Evaluates a k-line of a backtest with a strategy, reading the values external series had at
+/// its close from the values the backtest loaded, or from the market if it loaded none.
+pub async fn evaluate(
+    strategy: &Strategy,
+    kline: Kline,
+    external_data: Option<&ExternalDataStore>,
+) -> AlgorithmEvalResult {
+    match external_data {
+        Some(external_data) => {
+            let external = external_data.values_at(kline.close_time);
+            strategy.evaluate_with_external(kline, external).await
+        }
+        None => strategy.evaluate(kline).await,
+    }
+}
+
+/// Counts the closed trades and open positions of strategies on a simulated account, a change
+/// in either means an order was filled.
+///
+/// # Arguments
+///
+/// * `account` - The simulated account.
+/// * `strategy_ids` - The strategies whose trades and positions are counted.
+///
+/// # Returns
+///
+/// The number of closed trades and the number of open positions.
+pub async fn fill_count(
+    account: &ArcMutex<Account>,
+    strategy_ids: &[StrategyId],
+) -> (usize, usize) {
+    let account = account.lock().await;
+    strategy_ids
+        .iter()
+        .fold((0, 0), |(trades, positions), strategy_id| {
+            (
+                trades + account.strategy_trades(*strategy_id).len(),
+                positions + account.strategy_positions(*strategy_id).len(),
+            )
+        })
+}
+
+/// Calculates the equity of strategies on a simulated account, a capital plus the realized
+/// profit of their closed trades and the unrealized profit of their open positions.
+///
+/// # Arguments
+///
+/// * `account` - The simulated account.
+/// * `strategy_ids` - The strategies whose trades and positions are counted.
+/// * `capital` - The capital the strategies started with.
+/// * `mark_price` - The price an open position is marked at, positions without a price don't
+///   count.
+///
+/// # Returns
+///
+/// The equity in USD.
+pub async fn calc_equity(
+    account: &ArcMutex<Account>,
+    strategy_ids: &[StrategyId],
+    capital: f64,
+    mark_price: impl Fn(&Position) -> Option<f64>,
+) -> f64 {
+    let account = account.lock().await;
+    let mut equity = capital;
+    for strategy_id in strategy_ids {
+        equity += account
+            .strategy_trades(*strategy_id)
+            .iter()
+            .map(|trade| trade.calc_profit())
+            .sum::<f64>();
+        equity += account
+            .strategy_positions(*strategy_id)
+            .iter()
+            .filter_map(|position| {
+                let price = mark_price(position)?;
+                Some(position.calc_unrealized_profit(price.into()))
+            })
+            .sum::<f64>();
+    }
+    equity
+}
+
+/// Closes the positions of a strategy that have been open longer than a maximum duration,
+/// measured in backtest time, and forgets the open times of positions no longer open.
+///
+/// # Arguments
+///
+/// * `account` - The simulated account.
+/// * `position_open_times` - The backtest open time of every tracked position.
+/// * `strategy_id` - The strategy whose positions are closed.
+/// * `max_duration` - The maximum duration of a position in milliseconds.
+/// * `timestamp` - The current backtest timestamp.
+/// * `close_price` - The price expired positions are closed at.
+pub async fn close_expired_positions(
+    account: &ArcMutex<Account>,
+    position_open_times: &mut HashMap<PositionId, u64>,
+    strategy_id: StrategyId,
+    max_duration: u64,
+    timestamp: u64,
+    close_price: f64,
+) {
+    let mut account = account.lock().await;
+    let expired_ids: Vec<PositionId> = account
+        .strategy_positions(strategy_id)
+        .iter()
+        .filter(|position| {
+            position_open_times
+                .get(&position.id)
+                .is_some_and(|open_ts| timestamp.saturating_sub(*open_ts) >= max_duration)
+        })
+        .map(|position| position.id)
+        .collect();
+
+    for id in expired_ids {
+        account.close_position(id, close_price.into()).await;
+    }
+
+    // drop positions closed by signals
+    position_open_times.retain(|id, _| account.get_position(id).is_some());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        account::trade::OrderSide,
+        exchange::{api::ExchangeApi, mock::MockExchangeApi},
+    };
+    use std::sync::Arc;
+    use uuid::Uuid;
+
+    /// Tests a capped curve halves its resolution instead of growing past the cap.
+    #[test]
+    fn test_equity_curve_cap() {
+        let point = |timestamp: u64, fill: bool| EquityPoint {
+            timestamp,
+            equity: timestamp as f64,
+            fill,
+        };
+        let timestamps = |curve: &EquityCurve| -> Vec<u64> {
+            curve.points().iter().map(|point| point.timestamp).collect()
+        };
+
+        let mut curve = EquityCurve::new(None);
+        for timestamp in 1..=5 {
+            curve.push(point(timestamp, timestamp == 3));
+        }
+        assert_eq!(timestamps(&curve), vec![1, 2, 3, 4, 5]);
+
+        let mut curve = EquityCurve::new(Some(4));
+        for timestamp in 1..=6 {
+            curve.push(point(timestamp, false));
+        }
+        // 6 closes exceed the cap, every other close is kept
+        assert_eq!(timestamps(&curve), vec![2, 4, 6]);
+        assert_eq!(curve.period_len(), 2);
+
+        // fills are dropped and the latest close stands in for its incomplete period
+        curve.push(point(7, true));
+        curve.push(point(7, false));
+        assert_eq!(timestamps(&curve), vec![2, 4, 6, 7]);
+
+        for timestamp in 8..=100_000 {
+            curve.push(point(timestamp, false));
+        }
+        assert!(curve.points().len() <= 5);
+        assert_eq!(curve.points().last().unwrap().timestamp, 100_000);
+        assert_eq!(curve.period_len(), 32_768);
+    }
+
+    /// Tests fills, equity and expiry only count the positions of the given strategies.
+    #[tokio::test]
+    async fn test_strategy_bookkeeping() {
+        let exchange_api: Arc<Box<dyn ExchangeApi>> =
+            Arc::new(Box::new(MockExchangeApi::default()));
+        let account = ArcMutex::new(Account::new(exchange_api, false, true).await);
+        let (strategy_a, strategy_b) = (Uuid::new_v4(), Uuid::new_v4());
+
+        let mut position_open_times = HashMap::new();
+        for (strategy_id, open_ts) in [(strategy_a, 0), (strategy_a, 50), (strategy_b, 0)] {
+            let position_id = account
+                .lock()
+                .await
+                .open_position(
+                    "BTCUSDT",
+                    100.0,
+                    1,
+                    OrderSide::Buy,
+                    100.0.into(),
+                    Some(strategy_id),
+                    None,
+                )
+                .await
+                .unwrap()
+                .id;
+            position_open_times.insert(position_id, open_ts);
+        }
+
+        assert_eq!(fill_count(&account, &[strategy_a]).await, (0, 2));
+        assert_eq!(
+            fill_count(&account, &[strategy_a, strategy_b]).await,
+            (0, 3)
+        );
+
+        // one position of a is open for 100ms and closed at 110, a 10% profit on 100 USD
+        close_expired_positions(
+            &account,
+            &mut position_open_times,
+            strategy_a,
+            100,
+            100,
+            110.0,
+        )
+        .await;
+        assert_eq!(fill_count(&account, &[strategy_a]).await, (1, 1));
+        assert_eq!(fill_count(&account, &[strategy_b]).await, (0, 1));
+        assert_eq!(position_open_times.len(), 2);
+
+        let equity = calc_equity(&account, &[strategy_a], 1000.0, |_| Some(120.0)).await;
+        assert!((equity - 1030.0).abs() < 1e-9);
+        let equity = calc_equity(&account, &[strategy_a], 1000.0, |_| None).await;
+        assert!((equity - 1010.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,321 @@
+//! # Order Execution
+//!
+//! How the entries and exits of live signals reach the exchange. By default a signal trades its
+//! full size at once. Large orders move the market against themselves, so a strategy may instead
+//! split them into child orders traded over time:
+//!
+//! - `Twap` trades equal slices at equal intervals.
+//! - `Vwap` sizes the slices by the volume traded in the same window of the day before, trading
+//!   more while the market is more liquid, and falls back to equal slices without volume data.
+//! - `Iceberg` shows a single slice at a time as a limit order `offset_pct` better than the
+//!   market price, placing the next slice at the new market price once it fills. The slices
+//!   are tracked by the account as one `IcebergOrder`, which expires after `timeout_secs`.
+//!   Slices are filled by watching the market price rather than by resting limit orders on the
+//!   exchange, so strategies trading on live accounts can't be started, updated or resumed with
+//!   iceberg execution. Should their account leave dry run while they run, their entries are
+//!   ignored and their exits close at once.
+//!
+//! Entries open a position per child order, exits close the positions of the strategy across
+//! the same schedule. The child orders of an entry count against the `max_open_orders` of the
+//! strategy and keep their margin reserved until they are traded, and the strategy opens no
+//! other position while they are. Back tests always trade at once, as their signals carry the price of a
+//! closed kline rather than a price to work an order at.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{account::trade::OrderSide, market::kline::Kline};
+
+/// The largest number of child orders an order can be split into.
+pub const MAX_EXECUTION_SLICES: u32 = 100;
+
+/// How the orders of a strategy's signals are executed, see the module documentation.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ExecutionStyle {
+    /// A single order for the full size.
+    #[default]
+    Market,
+    /// `slices` equal child orders spread evenly over `duration_secs`.
+    Twap { slices: u32, duration_secs: u64 },
+    /// `slices` child orders spread evenly over `duration_secs`, sized by the volume of the
+    /// day before.
+    Vwap { slices: u32, duration_secs: u64 },
+    /// `slices` equal limit orders placed one at a time `offset_pct` percent better than the
+    /// market price, the unfilled slices expire after `timeout_secs`.
+    Iceberg {
+        slices: u32,
+        #[serde(default)]
+        offset_pct: f64,
+        timeout_secs: u64,
+    },
+}
+
+impl ExecutionStyle {
+    /// Whether orders are traded at once, as they are by `Market` or a single slice over time.
+    /// Iceberg orders always rest on the book.
+    pub fn is_immediate(&self) -> bool {
+        !matches!(self, Self::Iceberg { .. }) && self.slices() <= 1
+    }
+
+    /// The number of child orders an order is split into.
+    pub fn slices(&self) -> u32 {
+        match self {
+            Self::Market => 1,
+            Self::Twap { slices, .. }
+            | Self::Vwap { slices, .. }
+            | Self::Iceberg { slices, .. } => *slices,
+        }
+    }
+
+    /// The time over which the child orders are spread, or an iceberg order is worked.
+    pub fn duration(&self) -> Duration {
+        match self {
+            Self::Market => Duration::ZERO,
+            Self::Twap { duration_secs, .. } | Self::Vwap { duration_secs, .. } => {
+                Duration::from_secs(*duration_secs)
+            }
+            Self::Iceberg { timeout_secs, .. } => Duration::from_secs(*timeout_secs),
+        }
+    }
+
+    /// Checks the style can be executed.
+    ///
+    /// # Returns
+    ///
+    /// An error if a split order has no slices, more than `MAX_EXECUTION_SLICES` or no time to
+    /// trade them in.
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Self::Market => Ok(()),
+            Self::Twap { slices: 0, .. }
+            | Self::Vwap { slices: 0, .. }
+            | Self::Iceberg { slices: 0, .. } => {
+                Err("execution slices must be at least 1".to_string())
+            }
+            _ if self.slices() > MAX_EXECUTION_SLICES => Err(format!(
+                "execution slices must be at most {MAX_EXECUTION_SLICES}"
+            )),
+            Self::Twap {
+                duration_secs: 0, ..
+            }
+            | Self::Vwap {
+                duration_secs: 0, ..
+            } => Err("execution duration_secs must be positive".to_string()),
+            Self::Iceberg {
+                timeout_secs: 0, ..
+            } => Err("execution timeout_secs must be positive".to_string()),
+            Self::Iceberg { offset_pct, .. } if !(0.0..100.0).contains(offset_pct) => {
+                Err("execution offset_pct must be at least 0 and below 100".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Plans the child orders of an order.
+    ///
+    /// # Arguments
+    ///
+    /// * `volumes` - The volume traded in each window of the schedule the day before, used by
+    ///   `Vwap` only. Missing or empty volumes split the order equally.
+    ///
+    /// # Returns
+    ///
+    /// The child orders in the order they are traded, the first one at once. Windows without
+    /// volume get no child order.
+    pub fn schedule(&self, volumes: &[f64]) -> Vec<ChildOrder> {
+        let slices = self.slices().max(1);
+        let interval = self.duration() / slices;
+
+        let total_volume: f64 = volumes.iter().filter(|volume| **volume > 0.0).sum();
+        let use_volume = matches!(self, Self::Vwap { .. })
+            && volumes.len() == slices as usize
+            && total_volume > 0.0;
+
+        let mut children: Vec<ChildOrder> = (0..slices)
+            .map(|slice| ChildOrder {
+                delay: interval * slice,
+                weight: if use_volume {
+                    volumes[slice as usize].max(0.0) / total_volume
+                } else {
+                    1.0 / slices as f64
+                },
+            })
+            .filter(|child| child.weight > 0.0)
+            .collect();
+
+        // the order starts at once, even if the first window of the day before had no volume
+        if let Some(first) = children.first_mut() {
+            first.delay = Duration::ZERO;
+        }
+        children
+    }
+}
+
+/// A child order of a split order.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChildOrder {
+    /// The time after the signal the child order is traded at.
+    pub delay: Duration,
+    /// The share of the full order traded by the child order.
+    pub weight: f64,
+}
+
+/// Sums the volume of klines into the windows of a schedule.
+///
+/// # Arguments
+///
+/// * `klines` - The klines traded in the windows, e.g. of the same time the day before.
+/// * `from_ts` - The start of the first window in milliseconds.
+/// * `window` - The length of each window.
+/// * `slices` - The number of windows.
+///
+/// # Returns
+///
+/// The volume of each window, klines are counted in the window they open in.
+pub fn volume_profile(klines: &[Kline], from_ts: u64, window: Duration, slices: u32) -> Vec<f64> {
+    let window_ms = (window.as_millis() as u64).max(1);
+    let mut volumes = vec![0.0; slices as usize];
+
+    for kline in klines {
+        let Some(offset) = kline.open_time.checked_sub(from_ts) else {
+            continue;
+        };
+        if let Some(volume) = volumes.get_mut((offset / window_ms) as usize) {
+            *volume += kline.volume;
+        }
+    }
+    volumes
+}
+
+/// The limit price of an iceberg slice, `offset_pct` percent below the market price for buys
+/// and above it for sells.
+pub fn iceberg_limit(order_side: OrderSide, price: f64, offset_pct: f64) -> f64 {
+    match order_side {
+        OrderSide::Buy => price * (1.0 - offset_pct / 100.0),
+        OrderSide::Sell => price * (1.0 + offset_pct / 100.0),
+    }
+}
+
+/// Checks whether the market price reached the limit price of a resting order.
+pub fn is_limit_filled(order_side: OrderSide, limit_price: f64, price: f64) -> bool {
+    match order_side {
+        OrderSide::Buy => price <= limit_price,
+        OrderSide::Sell => price >= limit_price,
+    }
+}
+
+/// Spreads the closing of positions over the child orders of an exit.
+///
+/// # Returns
+///
+/// The number of positions closed once each child order was traded, the last of which closes
+/// every position. The first child order closes at least one position.
+pub fn exit_counts(children: &[ChildOrder], positions: usize) -> Vec<usize> {
+    let mut traded = 0.0;
+    children
+        .iter()
+        .map(|child| {
+            traded += child.weight;
+            ((traded * positions as f64 - 1e-9).ceil() as usize).min(positions)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests orders are split equally over time by TWAP and by the volume of each window by
+    /// VWAP, and exits close every position by the last child order.
+    #[test]
+    fn test_execution_schedule() {
+        assert!(ExecutionStyle::Market.is_immediate());
+        assert_eq!(ExecutionStyle::Market.schedule(&[]).len(), 1);
+
+        let twap = ExecutionStyle::Twap {
+            slices: 4,
+            duration_secs: 600,
+        };
+        let children = twap.schedule(&[]);
+        assert_eq!(children.len(), 4);
+        assert_eq!(children[0].delay, Duration::ZERO);
+        assert_eq!(children[3].delay, Duration::from_secs(450));
+        assert!(children.iter().all(|child| child.weight == 0.25));
+
+        let vwap = ExecutionStyle::Vwap {
+            slices: 4,
+            duration_secs: 600,
+        };
+        let children = vwap.schedule(&[0.0, 10.0, 30.0, 60.0]);
+        assert_eq!(children.len(), 3);
+        assert_eq!(children[0].delay, Duration::ZERO);
+        assert_eq!(children[1].delay, Duration::from_secs(300));
+        assert_eq!(children[2].weight, 0.6);
+        // without volume data VWAP splits equally
+        assert_eq!(vwap.schedule(&[0.0; 4]), twap.schedule(&[]));
+
+        let kline = |open_time: u64, volume: f64| Kline {
+            open_time,
+            volume,
+            ..Default::default()
+        };
+        let klines = [kline(1_000, 1.0), kline(61_000, 2.0), kline(150_000, 4.0)];
+        assert_eq!(
+            volume_profile(&klines, 1_000, Duration::from_secs(60), 2),
+            vec![1.0, 2.0]
+        );
+
+        assert_eq!(exit_counts(&twap.schedule(&[]), 4), vec![1, 2, 3, 4]);
+        assert_eq!(exit_counts(&twap.schedule(&[]), 1), vec![1, 1, 1, 1]);
+        assert_eq!(exit_counts(&twap.schedule(&[]), 6), vec![2, 3, 5, 6]);
+
+        assert!(ExecutionStyle::Twap {
+            slices: 0,
+            duration_secs: 60
+        }
+        .validate()
+        .is_err());
+        assert!(ExecutionStyle::Twap {
+            slices: u32::MAX,
+            duration_secs: 60
+        }
+        .validate()
+        .is_err());
+        let json = r#"{"type": "vwap", "slices": 4, "duration_secs": 600}"#;
+        assert_eq!(serde_json::from_str::<ExecutionStyle>(json).unwrap(), vwap);
+    }
+
+    /// Tests iceberg slices rest below the market price for buys and above it for sells, and
+    /// fill once the price reaches them.
+    #[test]
+    fn test_iceberg_limit() {
+        let json = r#"{"type": "iceberg", "slices": 5, "timeout_secs": 3600}"#;
+        let iceberg = serde_json::from_str::<ExecutionStyle>(json).unwrap();
+        assert_eq!(
+            iceberg,
+            ExecutionStyle::Iceberg {
+                slices: 5,
+                offset_pct: 0.0,
+                timeout_secs: 3600
+            }
+        );
+        assert!(!iceberg.is_immediate());
+        assert!(iceberg.validate().is_ok());
+        assert!(ExecutionStyle::Iceberg {
+            slices: 5,
+            offset_pct: -1.0,
+            timeout_secs: 3600
+        }
+        .validate()
+        .is_err());
+
+        assert_eq!(iceberg_limit(OrderSide::Buy, 200.0, 25.0), 150.0);
+        assert_eq!(iceberg_limit(OrderSide::Sell, 200.0, 25.0), 250.0);
+        assert!(is_limit_filled(OrderSide::Buy, 199.0, 198.5));
+        assert!(!is_limit_filled(OrderSide::Buy, 199.0, 199.5));
+        assert!(is_limit_filled(OrderSide::Sell, 201.0, 201.0));
+        assert!(!is_limit_filled(OrderSide::Sell, 201.0, 200.0));
+    }
+}
@@ -0,0 +1,126 @@
+use std::error::Error;
+
+use rust_decimal::prelude::ToPrimitive;
+use serde::Serialize;
+
+use crate::account::money::{Price, Qty};
+use crate::account::trade::{OrderSide, TradeTx};
+
+/// A row of the trade list export, one per closed trade.
+///
+/// `profit` is net of `funding_paid`, `return_pct` is the profit relative to the margin of the
+/// position. `fees` are estimated from the value of the entry and exit fills and the fee rate of
+/// the backtest, they aren't deducted from `profit` and prices are the fill prices.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TradeRow {
+    pub trade_id: String,
+    pub position_id: String,
+    pub symbol: String,
+    pub order_side: OrderSide,
+    pub entry_time: String,
+    pub entry_price: Price,
+    pub exit_time: String,
+    pub exit_price: Price,
+    pub quantity: Qty,
+    pub margin_usd: f64,
+    pub leverage: u32,
+    pub funding_paid: f64,
+    pub fees: f64,
+    pub profit: f64,
+    pub return_pct: f64,
+}
+
+impl TradeRow {
+    /// Creates the row of a closed trade.
+    ///
+    /// # Arguments
+    ///
+    /// * `trade` - The closed trade.
+    /// * `fee_rate` - The share of the value of a fill paid as fee.
+    ///
+    /// # Returns
+    ///
+    /// Returns a new instance of `TradeRow`.
+    pub fn new(trade: &TradeTx, fee_rate: f64) -> Self {
+        let position = &trade.position;
+        let fill_value = position.open_price.notional(position.quantity)
+            + trade.close_price.notional(position.quantity);
+        let profit = trade.calc_profit();
+        let return_pct = if position.margin_usd > 0.0 {
+            profit / position.margin_usd * 100.0
+        } else {
+            0.0
+        };
+
+        Self {
+            trade_id: trade.id.to_string(),
+            position_id: position.id.to_string(),
+            symbol: position.symbol.clone(),
+            order_side: position.order_side,
+            entry_time: position.open_time.clone(),
+            entry_price: position.open_price,
+            exit_time: trade.close_time.clone(),
+            exit_price: trade.close_price,
+            quantity: position.quantity,
+            margin_usd: position.margin_usd,
+            leverage: position.leverage,
+            funding_paid: position.funding_paid,
+            fees: fill_value.to_f64().unwrap_or(0.0) * fee_rate,
+            profit,
+            return_pct,
+        }
+    }
+}
+
+/// Renders trades as CSV with a header row, see `TradeRow`.
+///
+/// # Arguments
+///
+/// * `trades` - The trades to export.
+/// * `fee_rate` - The share of the value of a fill paid as fee, `0.0` without fees.
+///
+/// # Returns
+///
+/// The CSV document, or an error if a row can't be written.
+pub fn trades_to_csv(trades: &[TradeTx], fee_rate: f64) -> Result<String, Box<dyn Error>> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    for trade in trades {
+        writer.serialize(TradeRow::new(trade, fee_rate))?;
+    }
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::account::trade::Position;
+
+    /// Tests trades are exported with a header and their net profit.
+    #[test]
+    fn test_trades_to_csv() {
+        let mut position = Position::new(
+            "BTCUSDT",
+            100.0.into(),
+            OrderSide::Buy,
+            100.0,
+            10,
+            None,
+            Default::default(),
+        );
+        position.funding_paid = 1.0;
+        let trade = TradeTx::new(110.0.into(), 0, position);
+
+        let csv = trades_to_csv(std::slice::from_ref(&trade), 0.0).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("trade_id,position_id,symbol,order_side,entry_time"));
+        assert!(lines[0].ends_with(",funding_paid,fees,profit,return_pct"));
+        // 10 units gaining 10 each, minus 1 funding
+        assert!(lines[1].ends_with(",1.0,0.0,99.0,99.0"));
+
+        // fills worth 1000 and 1100 at 0.1%
+        let csv = trades_to_csv(&[trade], 0.001).unwrap();
+        assert!(csv.lines().nth(1).unwrap().ends_with(",1.0,2.1,99.0,99.0"));
+    }
+}
@@ -0,0 +1,281 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    account::{money::DEFAULT_PRECISION, trade::OrderSide},
+    market::{kline::Kline, trade::Trade},
+    utils::time::SEC_AS_MILI,
+};
+
+/// How signals of a backtest are turned into fills.
+///
+/// `Instant` fills every signal at its price. `Limit` rests a limit order `offset` (a fraction
+/// of the signal price, e.g. `0.001` for 0.1%) below the price for buys and above it for sells,
+/// which only fills once the market trades through it, see `FillSimulator`. The limit price is
+/// rounded to the `tick_size` of the symbol, so trades can be matched at it.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FillModel {
+    #[default]
+    Instant,
+    Limit {
+        #[serde(default)]
+        offset: f64,
+        /// Quantity resting ahead of the order at its price, traded before the order fills.
+        #[serde(default)]
+        queue_ahead: f64,
+        /// Seconds after which an unfilled order is cancelled, never by default.
+        #[serde(default)]
+        expire_after: Option<u64>,
+        /// Price step of the symbol, e.g. `0.1` for BTCUSDT, the smallest step of
+        /// `DEFAULT_PRECISION` decimals by default.
+        #[serde(default)]
+        tick_size: Option<f64>,
+    },
+}
+
+/// A limit order placed for a signal, waiting to be filled.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RestingOrder {
+    pub order_side: OrderSide,
+    pub confidence: f64,
+    pub price: f64,
+    pub created_at: u64,
+    queue_ahead: f64,
+    tick_size: f64,
+}
+
+impl RestingOrder {
+    /// Whether a price is at the order's limit price, within half a tick.
+    fn is_at_price(&self, price: f64) -> bool {
+        (price - self.price).abs() < self.tick_size / 2.0
+    }
+
+    /// Whether a price is beyond the order's limit price, filling it regardless of the queue.
+    fn is_traded_through(&self, price: f64) -> bool {
+        if self.is_at_price(price) {
+            return false;
+        }
+        match self.order_side {
+            OrderSide::Buy => price < self.price,
+            OrderSide::Sell => price > self.price,
+        }
+    }
+}
+
+/// Simulates the fills of resting limit orders of a backtest.
+///
+/// A new signal replaces the resting order. Orders fill at their limit price once a price
+/// trades through them. With trade data, trades exactly at the limit price first consume the
+/// queue ahead of the order and fill it once the queue is gone. K-lines carry no volume at
+/// price, so touching the limit price without trading through never fills. Partial fills are
+/// not modelled, orders fill completely.
+#[derive(Debug, Clone, Default)]
+pub struct FillSimulator {
+    model: FillModel,
+    order: Option<RestingOrder>,
+}
+
+impl FillSimulator {
+    pub fn new(model: FillModel) -> Self {
+        Self { model, order: None }
+    }
+
+    /// Whether signals fill instantly at their price instead of resting.
+    pub fn is_instant(&self) -> bool {
+        self.model == FillModel::Instant
+    }
+
+    /// Places a limit order for a signal, replacing any resting order.
+    ///
+    /// # Arguments
+    ///
+    /// * `order_side` - The side of the signal.
+    /// * `confidence` - The confidence of the signal.
+    /// * `price` - The price of the signal, the limit price is offset from it and rounded to
+    ///   the nearest tick.
+    /// * `timestamp` - The backtest time the order is placed at.
+    pub fn place(&mut self, order_side: OrderSide, confidence: f64, price: f64, timestamp: u64) {
+        let (offset, queue_ahead, tick_size) = match &self.model {
+            FillModel::Limit {
+                offset,
+                queue_ahead,
+                tick_size,
+                ..
+            } => (*offset, *queue_ahead, *tick_size),
+            FillModel::Instant => (0.0, 0.0, None),
+        };
+        let tick_size = tick_size
+            .filter(|tick_size| *tick_size > 0.0)
+            .unwrap_or(10f64.powi(-(DEFAULT_PRECISION as i32)));
+        let price = match order_side {
+            OrderSide::Buy => price * (1.0 - offset),
+            OrderSide::Sell => price * (1.0 + offset),
+        };
+
+        self.order = Some(RestingOrder {
+            order_side,
+            confidence,
+            price: (price / tick_size).round() * tick_size,
+            created_at: timestamp,
+            queue_ahead,
+            tick_size,
+        });
+    }
+
+    /// Matches the resting order against a market trade.
+    ///
+    /// # Returns
+    ///
+    /// The order if the trade filled it.
+    pub fn on_trade(&mut self, trade: &Trade) -> Option<RestingOrder> {
+        if self.expire(trade.timestamp) {
+            return None;
+        }
+        let order = self.order.as_mut()?;
+
+        if !order.is_traded_through(trade.price) {
+            if !order.is_at_price(trade.price) {
+                return None;
+            }
+            order.queue_ahead -= trade.qty;
+            if order.queue_ahead >= 0.0 {
+                return None;
+            }
+        }
+        self.order.take()
+    }
+
+    /// Matches the resting order against the price range of a k-line.
+    ///
+    /// # Returns
+    ///
+    /// The order if the k-line traded through it.
+    pub fn on_kline(&mut self, kline: &Kline) -> Option<RestingOrder> {
+        if self.expire(kline.close_time) {
+            return None;
+        }
+        let order = self.order.as_ref()?;
+
+        let traded_through = match order.order_side {
+            OrderSide::Buy => order.is_traded_through(kline.low),
+            OrderSide::Sell => order.is_traded_through(kline.high),
+        };
+        if !traded_through {
+            return None;
+        }
+        self.order.take()
+    }
+
+    // ---
+    // Private Methods
+    // ---
+
+    /// Cancels the resting order once it is older than `expire_after`.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the order was cancelled.
+    fn expire(&mut self, timestamp: u64) -> bool {
+        let expire_after = match &self.model {
+            FillModel::Limit {
+                expire_after: Some(expire_after),
+                ..
+            } => expire_after * SEC_AS_MILI,
+            _ => return false,
+        };
+
+        let expired = self
+            .order
+            .as_ref()
+            .is_some_and(|order| timestamp.saturating_sub(order.created_at) >= expire_after);
+        if expired {
+            self.order = None;
+        }
+        expired
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(price: f64, qty: f64, timestamp: u64) -> Trade {
+        Trade {
+            symbol: "BTCUSDT".to_string(),
+            timestamp,
+            qty,
+            price,
+            order_side: OrderSide::Sell,
+        }
+    }
+
+    /// Tests orders only fill once traded through or once the queue ahead is consumed.
+    #[test]
+    fn test_fill_simulator_trades() {
+        let mut simulator = FillSimulator::new(FillModel::Limit {
+            offset: 0.01,
+            queue_ahead: 2.0,
+            expire_after: Some(60),
+            tick_size: None,
+        });
+        simulator.place(OrderSide::Buy, 1.0, 100.0, 0);
+        assert_eq!(simulator.order.as_ref().unwrap().price, 99.0);
+
+        assert!(simulator.on_trade(&trade(99.5, 10.0, 1)).is_none());
+        assert!(simulator.on_trade(&trade(99.0, 1.5, 2)).is_none());
+        assert!(simulator.on_trade(&trade(99.0, 1.0, 3)).is_some());
+        assert!(simulator.order.is_none());
+
+        simulator.place(OrderSide::Sell, 1.0, 100.0, 0);
+        assert!(simulator.on_trade(&trade(101.5, 0.1, 4)).is_some());
+
+        simulator.place(OrderSide::Sell, 1.0, 100.0, 0);
+        assert!(simulator.on_trade(&trade(102.0, 1.0, 60_000)).is_none());
+        assert!(simulator.order.is_none());
+    }
+
+    /// Tests k-lines touching the limit price don't fill the order.
+    #[test]
+    fn test_fill_simulator_klines() {
+        let mut simulator = FillSimulator::new(FillModel::Limit {
+            offset: 0.0,
+            queue_ahead: 0.0,
+            expire_after: None,
+            tick_size: None,
+        });
+        simulator.place(OrderSide::Buy, 1.0, 100.0, 0);
+
+        let mut kline = Kline {
+            high: 105.0,
+            low: 100.0,
+            ..Default::default()
+        };
+        assert!(simulator.on_kline(&kline).is_none());
+
+        kline.low = 99.9;
+        assert_eq!(simulator.on_kline(&kline).unwrap().price, 100.0);
+    }
+
+    /// Tests limit prices are rounded to the tick and trades within half a tick of it consume
+    /// the queue.
+    #[test]
+    fn test_fill_simulator_tick_size() {
+        let mut simulator = FillSimulator::new(FillModel::Limit {
+            offset: 0.0013,
+            queue_ahead: 1.0,
+            expire_after: None,
+            tick_size: Some(0.1),
+        });
+        // 30000 * (1 - 0.0013) = 29961.0000...04 in floating point
+        simulator.place(OrderSide::Buy, 1.0, 30000.0, 0);
+        assert!((simulator.order.as_ref().unwrap().price - 29961.0).abs() < 1e-9);
+
+        assert!(simulator.on_trade(&trade(29961.0, 0.5, 1)).is_none());
+        assert!(simulator.on_trade(&trade(29961.0, 0.6, 2)).is_some());
+
+        // 100.13 rounds to 100.1, a trade at 100.1 is at the price, not through it
+        simulator.place(OrderSide::Sell, 1.0, 100.0, 0);
+        assert!(simulator.on_trade(&trade(100.1, 0.5, 3)).is_none());
+        assert!(simulator.on_trade(&trade(100.1 + 1e-9, 0.6, 4)).is_some());
+    }
+}
@@ -0,0 +1,919 @@
+use std::collections::{BTreeMap, HashMap};
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::{
+    account::{
+        account::Account,
+        money::Price,
+        router::AccountRouter,
+        trade::{OrderSide, PositionId},
+    },
+    exchange::types::{ApiError, ApiResult},
+    market::{
+        symbol::{MarketKind, Symbol},
+        types::ArcMutex,
+    },
+    strategy::strategy::StrategyId,
+    utils::{
+        env::{env_lookup, Lookup},
+        number::parse_f64_from_value,
+        time::{funding_timestamps, generate_ts, FUNDING_INTERVAL_HOURS, HOUR_AS_MILI},
+    },
+};
+
+/// Number of hours in a (non leap) year, used to annualize funding rates.
+const HOURS_PER_YEAR: f64 = 24.0 * 365.0;
+
+/// The strategy id the legs of funding-rate arbitrage positions are opened with, so they are
+/// told apart from the positions of strategies and found again after a restart.
+pub const FUNDING_ARBITRAGE_ID: StrategyId =
+    Uuid::from_u128(0x66756e64_696e_6700_0000_000000000001);
+
+/// A perpetual futures funding rate observation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FundingRate {
+    pub symbol: String,
+    /// Funding rate paid per funding interval, e.g. `0.0001` for 0.01%.
+    pub rate: f64,
+    /// Time the funding is settled, in milliseconds.
+    pub funding_time: u64,
+    /// Hours between funding settlements, usually 8.
+    pub interval_hours: f64,
+}
+
+impl FundingRate {
+    /// Parses the funding rate of a perpetual futures premium index, which Binance and BingX
+    /// both report as `lastFundingRate` settled at `nextFundingTime`.
+    ///
+    /// # Arguments
+    ///
+    /// * `symbol` - The symbol of the premium index.
+    /// * `data` - The premium index returned by the exchange.
+    ///
+    /// # Returns
+    ///
+    /// The `FundingRate`, or an `ApiError` if a field is missing.
+    pub fn from_premium_index(symbol: &str, data: &Value) -> ApiResult<Self> {
+        let rate = parse_f64_from_value("lastFundingRate", data)?;
+        let funding_time = data["nextFundingTime"]
+            .as_u64()
+            .ok_or_else(|| ApiError::Parsing("Missing nextFundingTime".to_string()))?;
+
+        Ok(Self {
+            symbol: symbol.to_string(),
+            rate,
+            funding_time,
+            interval_hours: FUNDING_INTERVAL_HOURS as f64,
+        })
+    }
+
+    /// Annualizes the funding rate, ignoring compounding.
+    ///
+    /// # Returns
+    ///
+    /// The annualized funding rate, e.g. `0.1095` for 0.01% paid every 8 hours.
+    pub fn annualized(&self) -> f64 {
+        if self.interval_hours <= 0.0 {
+            return 0.0;
+        }
+        self.rate * HOURS_PER_YEAR / self.interval_hours
+    }
+}
+
+/// Funding payments applied to open positions of a perpetual futures backtest.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FundingModel {
+    /// The same `rate` settled every `interval_hours`, aligned to UTC midnight like exchange
+    /// settlements.
+    Flat { rate: f64, interval_hours: f64 },
+    /// Recorded funding rates, each settled at its `funding_time`.
+    Historical { rates: Vec<FundingRate> },
+}
+
+impl FundingModel {
+    /// Returns the funding settlements within a time range.
+    ///
+    /// # Arguments
+    ///
+    /// * `from_ts` - The start of the range, exclusive.
+    /// * `to_ts` - The end of the range, inclusive.
+    ///
+    /// # Returns
+    ///
+    /// The settlement time and rate of every settlement in the range, in time order.
+    pub fn settlements(&self, from_ts: u64, to_ts: u64) -> Vec<(u64, f64)> {
+        match self {
+            FundingModel::Flat {
+                rate,
+                interval_hours,
+            } => {
+                let interval_ms = (interval_hours * HOUR_AS_MILI as f64) as u64;
+                funding_timestamps(from_ts, to_ts, interval_ms)
+                    .into_iter()
+                    .map(|funding_time| (funding_time, *rate))
+                    .collect()
+            }
+            FundingModel::Historical { rates } => {
+                let mut settlements: Vec<(u64, f64)> = rates
+                    .iter()
+                    .filter(|rate| rate.funding_time > from_ts && rate.funding_time <= to_ts)
+                    .map(|rate| (rate.funding_time, rate.rate))
+                    .collect();
+                settlements.sort_by_key(|(funding_time, _)| *funding_time);
+                settlements
+            }
+        }
+    }
+}
+
+/// Thresholds used to open and close funding-rate arbitrage positions, and the size and
+/// accounts of their legs.
+///
+/// Positions open when the absolute annualized funding exceeds `open_threshold` and close
+/// once it falls back below `close_threshold`, the gap between both avoids churning positions
+/// when funding hovers around a single level.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FundingArbitrageSettings {
+    pub open_threshold: f64,
+    pub close_threshold: f64,
+    /// Margin of each leg in USD, legs are only traded once it is set.
+    pub margin_usd: Option<f64>,
+    pub leverage: u32,
+    /// Credential profile whose account holds the hedge leg as a perpetual swap on its
+    /// exchange. Without one, the hedge leg is the spot market on the live account.
+    pub hedge_profile: Option<String>,
+}
+
+impl Default for FundingArbitrageSettings {
+    fn default() -> Self {
+        Self {
+            open_threshold: 0.2,
+            close_threshold: 0.05,
+            margin_usd: None,
+            leverage: 1,
+            hedge_profile: None,
+        }
+    }
+}
+
+impl FundingArbitrageSettings {
+    /// Reads the settings from `FUNDING_ARBITRAGE_OPEN_THRESHOLD`, 0.2 by default,
+    /// `FUNDING_ARBITRAGE_CLOSE_THRESHOLD`, 0.05 by default, `FUNDING_ARBITRAGE_MARGIN_USD`,
+    /// `FUNDING_ARBITRAGE_LEVERAGE`, 1 by default, and `FUNDING_ARBITRAGE_HEDGE_PROFILE`.
+    pub fn from_env() -> Self {
+        Self::from_lookup(env_lookup)
+    }
+
+    /// Reads the settings through `lookup`, see `from_env`.
+    pub fn from_lookup(lookup: impl Lookup) -> Self {
+        let default = Self::default();
+        let number = |key: &str| lookup(key).and_then(|value| value.parse::<f64>().ok());
+
+        Self {
+            open_threshold: number("FUNDING_ARBITRAGE_OPEN_THRESHOLD")
+                .unwrap_or(default.open_threshold),
+            close_threshold: number("FUNDING_ARBITRAGE_CLOSE_THRESHOLD")
+                .unwrap_or(default.close_threshold),
+            margin_usd: number("FUNDING_ARBITRAGE_MARGIN_USD").filter(|margin| *margin > 0.0),
+            leverage: lookup("FUNDING_ARBITRAGE_LEVERAGE")
+                .and_then(|leverage| leverage.parse().ok())
+                .filter(|leverage| *leverage > 0)
+                .unwrap_or(default.leverage),
+            hedge_profile: lookup("FUNDING_ARBITRAGE_HEDGE_PROFILE")
+                .filter(|profile| !profile.is_empty()),
+        }
+    }
+
+    /// Picks the account and symbol the hedge leg of a perpetual swap trades on.
+    ///
+    /// # Returns
+    ///
+    /// The account of the hedge profile with the same perpetual swap, or the live account with
+    /// the spot market of the symbol, e.g. `BTCUSDT.SPOT`. An error if the hedge profile is
+    /// unknown, or if the spot market would be traded on a live account: the exchange adapters
+    /// only trade perpetual swaps, so spot legs are simulated on dry run accounts.
+    pub async fn hedge_leg(
+        &self,
+        symbol: &str,
+        accounts: &AccountRouter,
+    ) -> Result<(ArcMutex<Account>, String), String> {
+        if let Some(profile) = &self.hedge_profile {
+            let account = accounts
+                .profiles
+                .get(profile)
+                .ok_or_else(|| format!("unknown hedge profile {profile}"))?;
+            return Ok((account.clone(), symbol.to_string()));
+        }
+
+        if !accounts.live.lock().await.is_dry_run() {
+            return Err(
+                "spot hedge legs are only simulated on dry run accounts, set a hedge profile"
+                    .to_string(),
+            );
+        }
+        let symbol: Symbol = symbol.parse()?;
+        let spot = Symbol::new(&symbol.base, &symbol.quote, MarketKind::Spot);
+
+        Ok((accounts.live.clone(), spot.to_string()))
+    }
+}
+
+/// Delta-neutral legs of a funding-rate arbitrage position.
+///
+/// The perpetual leg is hedged by the opposite spot (or other exchange perpetual) leg, so the
+/// position only earns the funding paid by the side of the market it is against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FundingArbitrageSide {
+    /// Funding is positive, longs pay shorts: short the perpetual, long the hedge.
+    ShortPerpLongHedge,
+    /// Funding is negative, shorts pay longs: long the perpetual, short the hedge.
+    LongPerpShortHedge,
+}
+
+impl FundingArbitrageSide {
+    /// The order sides of the perpetual and the hedge leg.
+    pub fn order_sides(&self) -> (OrderSide, OrderSide) {
+        match self {
+            Self::ShortPerpLongHedge => (OrderSide::Sell, OrderSide::Buy),
+            Self::LongPerpShortHedge => (OrderSide::Buy, OrderSide::Sell),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FundingArbitrageAction {
+    Open(FundingArbitrageSide),
+    Close,
+    Hold,
+}
+
+/// Tracks funding rates of a symbol and decides when to open and close a funding-rate
+/// arbitrage position.
+///
+/// The bot polls the funding rates of its traded symbols into a `FundingArbitrageTracker`, which
+/// opens and closes the legs of the positions the monitors call for.
+pub struct FundingArbitrageMonitor {
+    settings: FundingArbitrageSettings,
+    open_side: Option<FundingArbitrageSide>,
+}
+
+impl FundingArbitrageMonitor {
+    pub fn new(settings: FundingArbitrageSettings) -> Self {
+        Self {
+            settings,
+            open_side: None,
+        }
+    }
+
+    pub fn open_side(&self) -> Option<FundingArbitrageSide> {
+        self.open_side
+    }
+
+    /// Evaluates a new funding rate observation.
+    ///
+    /// # Arguments
+    ///
+    /// * `funding_rate` - The latest funding rate of the monitored symbol.
+    ///
+    /// # Returns
+    ///
+    /// The `FundingArbitrageAction` to take. A position is closed when funding normalizes or
+    /// flips sign, and can be reopened on the opposite side on a later observation.
+    pub fn on_funding_rate(&mut self, funding_rate: &FundingRate) -> FundingArbitrageAction {
+        let annualized = funding_rate.annualized();
+        let side = if annualized > 0.0 {
+            FundingArbitrageSide::ShortPerpLongHedge
+        } else {
+            FundingArbitrageSide::LongPerpShortHedge
+        };
+
+        match self.open_side {
+            Some(open_side) => {
+                if open_side != side || annualized.abs() < self.settings.close_threshold {
+                    self.open_side = None;
+                    FundingArbitrageAction::Close
+                } else {
+                    FundingArbitrageAction::Hold
+                }
+            }
+            None => {
+                if annualized.abs() > self.settings.open_threshold {
+                    self.open_side = Some(side);
+                    FundingArbitrageAction::Open(side)
+                } else {
+                    FundingArbitrageAction::Hold
+                }
+            }
+        }
+    }
+}
+
+/// The positions of the legs of a funding-rate arbitrage position.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FundingArbitrageLegs {
+    pub side: FundingArbitrageSide,
+    /// The perpetual swap position on the live account.
+    pub perp_position: PositionId,
+    /// The hedge position, on the account of the hedge profile or the live account.
+    pub hedge_position: PositionId,
+    pub hedge_symbol: String,
+}
+
+/// The latest funding rate of a symbol and the funding-rate arbitrage position it calls for.
+#[derive(Debug, Clone, Serialize)]
+pub struct FundingOpportunity {
+    pub funding_rate: FundingRate,
+    pub annualized: f64,
+    /// The side of the arbitrage position called for, `None` while funding is normal.
+    pub open_side: Option<FundingArbitrageSide>,
+    /// The legs held, `None` unless legs are traded and opened.
+    pub legs: Option<FundingArbitrageLegs>,
+    /// The time the funding rate was observed.
+    pub updated_at: u64,
+}
+
+/// Monitors the funding rates of the traded symbols, one `FundingArbitrageMonitor` per symbol,
+/// trades the legs of the positions they call for and keeps the latest opportunity of each for
+/// operators.
+///
+/// The perpetual leg trades on the live account and the hedge leg on the other side, see
+/// `FundingArbitrageSettings::hedge_leg`. Both are opened at the last price of the perpetual
+/// swap, and should the hedge leg fail to open the perpetual leg is closed again, so no leg is
+/// held unhedged. Legs which fail to open or close are retried on the next funding rate.
+pub struct FundingArbitrageTracker {
+    settings: FundingArbitrageSettings,
+    monitors: HashMap<String, FundingArbitrageMonitor>,
+    opportunities: BTreeMap<String, FundingOpportunity>,
+    legs: BTreeMap<String, FundingArbitrageLegs>,
+}
+
+impl FundingArbitrageTracker {
+    pub fn new(settings: FundingArbitrageSettings) -> Self {
+        Self {
+            settings,
+            monitors: HashMap::new(),
+            opportunities: BTreeMap::new(),
+            legs: BTreeMap::new(),
+        }
+    }
+
+    /// Checks the legs can be traded on the accounts, if legs are traded at all.
+    pub async fn check_accounts(&self, accounts: &AccountRouter) -> Result<(), String> {
+        if self.settings.margin_usd.is_none() {
+            return Ok(());
+        }
+        self.settings
+            .hedge_leg("BTCUSDT", accounts)
+            .await
+            .map(|_| ())
+    }
+
+    /// Finds the legs opened before a restart among the positions of the accounts, so they
+    /// are closed once funding normalizes.
+    pub async fn restore(&mut self, accounts: &AccountRouter) {
+        let perp_positions: Vec<_> = accounts
+            .live
+            .lock()
+            .await
+            .positions()
+            .filter(|position| position.strategy_id == Some(FUNDING_ARBITRAGE_ID))
+            .filter(|position| {
+                position
+                    .symbol
+                    .parse::<Symbol>()
+                    .is_ok_and(|symbol| symbol.kind == MarketKind::Perpetual)
+            })
+            .cloned()
+            .collect();
+
+        for perp in perp_positions {
+            let side = match perp.order_side {
+                OrderSide::Sell => FundingArbitrageSide::ShortPerpLongHedge,
+                OrderSide::Buy => FundingArbitrageSide::LongPerpShortHedge,
+            };
+            let hedge = match self.settings.hedge_leg(&perp.symbol, accounts).await {
+                Ok((account, hedge_symbol)) => account
+                    .lock()
+                    .await
+                    .positions()
+                    .find(|position| {
+                        position.strategy_id == Some(FUNDING_ARBITRAGE_ID)
+                            && position.symbol == hedge_symbol
+                            && position.order_side == side.order_sides().1
+                    })
+                    .map(|position| (position.id, hedge_symbol)),
+                Err(_) => None,
+            };
+            let Some((hedge_position, hedge_symbol)) = hedge else {
+                warn!(
+                    "Funding arbitrage position {} of {} has no hedge leg, it isn't managed",
+                    perp.id, perp.symbol
+                );
+                continue;
+            };
+
+            self.monitors
+                .entry(perp.symbol.clone())
+                .or_insert_with(|| FundingArbitrageMonitor::new(self.settings.clone()))
+                .open_side = Some(side);
+            self.legs.insert(
+                perp.symbol.clone(),
+                FundingArbitrageLegs {
+                    side,
+                    perp_position: perp.id,
+                    hedge_position,
+                    hedge_symbol,
+                },
+            );
+        }
+    }
+
+    /// The symbols legs are held of, which are monitored whether or not they are traded.
+    pub fn hedged_symbols(&self) -> Vec<String> {
+        self.legs.keys().cloned().collect()
+    }
+
+    /// Evaluates the latest funding rate of a symbol.
+    ///
+    /// # Arguments
+    ///
+    /// * `funding_rate` - The latest funding rate of the symbol.
+    ///
+    /// # Returns
+    ///
+    /// The `FundingArbitrageAction` to take for the symbol.
+    pub fn update(&mut self, funding_rate: FundingRate) -> FundingArbitrageAction {
+        let monitor = self
+            .monitors
+            .entry(funding_rate.symbol.clone())
+            .or_insert_with(|| FundingArbitrageMonitor::new(self.settings.clone()));
+        let action = monitor.on_funding_rate(&funding_rate);
+
+        self.opportunities.insert(
+            funding_rate.symbol.clone(),
+            FundingOpportunity {
+                annualized: funding_rate.annualized(),
+                open_side: monitor.open_side(),
+                legs: self.legs.get(&funding_rate.symbol).cloned(),
+                funding_rate,
+                updated_at: generate_ts(),
+            },
+        );
+
+        action
+    }
+
+    /// Opens or closes the legs of a symbol as an action of `update` asks, once legs are traded.
+    ///
+    /// # Arguments
+    ///
+    /// * `symbol` - The perpetual swap the action is for.
+    /// * `action` - The action returned by `update`.
+    /// * `price` - The last price of the perpetual swap, which both legs trade at.
+    /// * `accounts` - The accounts the legs trade on.
+    ///
+    /// # Returns
+    ///
+    /// An error if a leg couldn't be opened or closed, the action is taken again on the next
+    /// funding rate.
+    pub async fn execute(
+        &mut self,
+        symbol: &str,
+        action: FundingArbitrageAction,
+        price: Option<f64>,
+        accounts: &AccountRouter,
+    ) -> Result<(), String> {
+        let Some(margin_usd) = self.settings.margin_usd else {
+            return Ok(());
+        };
+
+        let result = match action {
+            FundingArbitrageAction::Hold => return Ok(()),
+            FundingArbitrageAction::Open(side) => {
+                let price = price.ok_or("no price to open the legs at")?;
+                self.open_legs(symbol, side, margin_usd, price, accounts)
+                    .await
+                    .map(|legs| {
+                        self.legs.insert(symbol.to_string(), legs);
+                    })
+            }
+            FundingArbitrageAction::Close => match self.legs.get(symbol).cloned() {
+                Some(legs) => {
+                    let price = price.ok_or("no price to close the legs at")?;
+                    self.close_legs(symbol, &legs, price, accounts)
+                        .await
+                        .map(|_| {
+                            self.legs.remove(symbol);
+                        })
+                }
+                None => Ok(()),
+            },
+        };
+
+        // the monitor takes the action again on the next funding rate
+        if result.is_err() {
+            if let Some(monitor) = self.monitors.get_mut(symbol) {
+                monitor.open_side = self.legs.get(symbol).map(|legs| legs.side);
+            }
+        }
+        if let Some(opportunity) = self.opportunities.get_mut(symbol) {
+            opportunity.open_side = self.monitors.get(symbol).and_then(|m| m.open_side());
+            opportunity.legs = self.legs.get(symbol).cloned();
+        }
+
+        result
+    }
+
+    /// Opens the perpetual leg and then the hedge leg, closing the perpetual leg again if the
+    /// hedge leg fails to open.
+    async fn open_legs(
+        &self,
+        symbol: &str,
+        side: FundingArbitrageSide,
+        margin_usd: f64,
+        price: f64,
+        accounts: &AccountRouter,
+    ) -> Result<FundingArbitrageLegs, String> {
+        let (hedge_account, hedge_symbol) = self.settings.hedge_leg(symbol, accounts).await?;
+        let (perp_side, hedge_side) = side.order_sides();
+        let leverage = self.settings.leverage;
+        let price = Price::from_f64(price);
+
+        let perp_position = accounts
+            .live
+            .lock()
+            .await
+            .open_position(
+                symbol,
+                margin_usd,
+                leverage,
+                perp_side,
+                price,
+                Some(FUNDING_ARBITRAGE_ID),
+                None,
+            )
+            .await
+            .map(|position| position.id)
+            .ok_or("perpetual leg not opened")?;
+
+        let hedge_position = hedge_account
+            .lock()
+            .await
+            .open_position(
+                &hedge_symbol,
+                margin_usd,
+                leverage,
+                hedge_side,
+                price,
+                Some(FUNDING_ARBITRAGE_ID),
+                None,
+            )
+            .await
+            .map(|position| position.id);
+        let Some(hedge_position) = hedge_position else {
+            let closed = accounts
+                .live
+                .lock()
+                .await
+                .close_position(perp_position, price)
+                .await
+                .is_some();
+            return Err(if closed {
+                "hedge leg not opened, the perpetual leg was closed again".to_string()
+            } else {
+                format!("hedge leg not opened and perpetual leg {perp_position} left open")
+            });
+        };
+
+        Ok(FundingArbitrageLegs {
+            side,
+            perp_position,
+            hedge_position,
+            hedge_symbol,
+        })
+    }
+
+    /// Closes both legs, legs no longer held, e.g. closed by a flatten, count as closed.
+    async fn close_legs(
+        &self,
+        symbol: &str,
+        legs: &FundingArbitrageLegs,
+        price: f64,
+        accounts: &AccountRouter,
+    ) -> Result<(), String> {
+        let (hedge_account, _) = self.settings.hedge_leg(symbol, accounts).await?;
+        let price = Price::from_f64(price);
+
+        let mut closed = true;
+        for (account, position_id) in [
+            (&accounts.live, legs.perp_position),
+            (&hedge_account, legs.hedge_position),
+        ] {
+            let mut account = account.lock().await;
+            if account
+                .positions()
+                .any(|position| position.id == position_id)
+            {
+                closed &= account.close_position(position_id, price).await.is_some();
+            }
+        }
+
+        if closed {
+            Ok(())
+        } else {
+            Err("legs not closed by the exchange".to_string())
+        }
+    }
+
+    /// Stops monitoring symbols which aren't traded anymore, unless legs are held of them.
+    pub fn retain_symbols(&mut self, symbols: &[String]) {
+        let legs = &self.legs;
+        let keep = |symbol: &String| symbols.contains(symbol) || legs.contains_key(symbol);
+        self.monitors.retain(|symbol, _| keep(symbol));
+        self.opportunities.retain(|symbol, _| keep(symbol));
+    }
+
+    /// Lists the latest opportunity of every monitored symbol, by symbol.
+    pub fn opportunities(&self) -> Vec<FundingOpportunity> {
+        self.opportunities.values().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{
+        exchange::{api::ExchangeApi, mock::MockExchangeApi},
+        utils::env::vars_lookup,
+    };
+
+    fn funding_rate(rate: f64) -> FundingRate {
+        FundingRate {
+            symbol: "BTCUSDT".to_string(),
+            rate,
+            funding_time: 0,
+            interval_hours: 8.0,
+        }
+    }
+
+    /// Tests funding rates are annualized by their funding interval.
+    #[test]
+    fn test_annualized_funding_rate() {
+        let annualized = funding_rate(0.0001).annualized();
+        assert!((annualized - 0.1095).abs() < 1e-9);
+    }
+
+    /// Tests flat funding settles on interval boundaries and recorded rates within the range.
+    #[test]
+    fn test_funding_model_settlements() {
+        let flat = FundingModel::Flat {
+            rate: 0.0001,
+            interval_hours: 8.0,
+        };
+        let eight_hours = 8 * HOUR_AS_MILI;
+        assert_eq!(
+            flat.settlements(eight_hours, 3 * eight_hours),
+            vec![(2 * eight_hours, 0.0001), (3 * eight_hours, 0.0001)]
+        );
+        assert!(flat.settlements(1, eight_hours - 1).is_empty());
+
+        let mut later = funding_rate(0.0002);
+        later.funding_time = 20;
+        let mut earlier = funding_rate(-0.0001);
+        earlier.funding_time = 10;
+        let historical = FundingModel::Historical {
+            rates: vec![later, earlier, funding_rate(0.0003)],
+        };
+        assert_eq!(
+            historical.settlements(0, 20),
+            vec![(10, -0.0001), (20, 0.0002)]
+        );
+    }
+
+    /// Tests positions open above the open threshold and close once funding normalizes.
+    #[test]
+    fn test_funding_arbitrage_monitor() {
+        let mut monitor = FundingArbitrageMonitor::new(FundingArbitrageSettings::default());
+
+        // 0.01% every 8 hours, ~11% annualized
+        assert_eq!(
+            monitor.on_funding_rate(&funding_rate(0.0001)),
+            FundingArbitrageAction::Hold
+        );
+        // 0.03% every 8 hours, ~33% annualized
+        assert_eq!(
+            monitor.on_funding_rate(&funding_rate(0.0003)),
+            FundingArbitrageAction::Open(FundingArbitrageSide::ShortPerpLongHedge)
+        );
+        // still above the close threshold
+        assert_eq!(
+            monitor.on_funding_rate(&funding_rate(0.0001)),
+            FundingArbitrageAction::Hold
+        );
+        assert_eq!(
+            monitor.on_funding_rate(&funding_rate(0.00001)),
+            FundingArbitrageAction::Close
+        );
+        assert_eq!(monitor.open_side(), None);
+
+        assert_eq!(
+            monitor.on_funding_rate(&funding_rate(-0.0003)),
+            FundingArbitrageAction::Open(FundingArbitrageSide::LongPerpShortHedge)
+        );
+        // funding flipped sign
+        assert_eq!(
+            monitor.on_funding_rate(&funding_rate(0.0003)),
+            FundingArbitrageAction::Close
+        );
+    }
+
+    /// Tests funding rates are parsed from a premium index.
+    #[test]
+    fn test_funding_rate_from_premium_index() {
+        let data = serde_json::json!({
+            "symbol": "BTCUSDT",
+            "lastFundingRate": "0.00010000",
+            "nextFundingTime": 1597392000000u64
+        });
+        let funding_rate = FundingRate::from_premium_index("BTCUSDT", &data).unwrap();
+
+        assert_eq!(funding_rate.rate, 0.0001);
+        assert_eq!(funding_rate.funding_time, 1597392000000);
+        assert_eq!(funding_rate.interval_hours, 8.0);
+        assert!(FundingRate::from_premium_index("BTCUSDT", &serde_json::json!({})).is_err());
+    }
+
+    /// Tests the tracker keeps a monitor per symbol and forgets symbols no longer traded.
+    #[test]
+    fn test_funding_arbitrage_tracker() {
+        let mut tracker = FundingArbitrageTracker::new(FundingArbitrageSettings::default());
+        let mut eth_rate = funding_rate(-0.0003);
+        eth_rate.symbol = "ETHUSDT".to_string();
+
+        assert_eq!(
+            tracker.update(funding_rate(0.0003)),
+            FundingArbitrageAction::Open(FundingArbitrageSide::ShortPerpLongHedge)
+        );
+        assert_eq!(
+            tracker.update(eth_rate),
+            FundingArbitrageAction::Open(FundingArbitrageSide::LongPerpShortHedge)
+        );
+        assert_eq!(
+            tracker.update(funding_rate(0.0003)),
+            FundingArbitrageAction::Hold
+        );
+
+        let opportunities = tracker.opportunities();
+        assert_eq!(opportunities.len(), 2);
+        assert_eq!(opportunities[0].funding_rate.symbol, "BTCUSDT");
+        assert_eq!(
+            opportunities[0].open_side,
+            Some(FundingArbitrageSide::ShortPerpLongHedge)
+        );
+
+        tracker.retain_symbols(&["ETHUSDT".to_string()]);
+        assert_eq!(tracker.opportunities().len(), 1);
+        assert_eq!(
+            tracker.update(funding_rate(0.0003)),
+            FundingArbitrageAction::Open(FundingArbitrageSide::ShortPerpLongHedge)
+        );
+    }
+
+    /// Tests the settings are read from the environment.
+    #[test]
+    fn test_funding_arbitrage_settings_from_lookup() {
+        let settings = FundingArbitrageSettings::from_lookup(vars_lookup(&[]));
+        assert_eq!(settings.open_threshold, 0.2);
+        assert_eq!(settings.margin_usd, None);
+        assert_eq!(settings.leverage, 1);
+
+        let settings = FundingArbitrageSettings::from_lookup(vars_lookup(&[
+            ("FUNDING_ARBITRAGE_OPEN_THRESHOLD", "0.3"),
+            ("FUNDING_ARBITRAGE_MARGIN_USD", "100"),
+            ("FUNDING_ARBITRAGE_LEVERAGE", "2"),
+            ("FUNDING_ARBITRAGE_HEDGE_PROFILE", "hedge"),
+        ]));
+        assert_eq!(settings.open_threshold, 0.3);
+        assert_eq!(settings.close_threshold, 0.05);
+        assert_eq!(settings.margin_usd, Some(100.0));
+        assert_eq!(settings.leverage, 2);
+        assert_eq!(settings.hedge_profile.as_deref(), Some("hedge"));
+    }
+
+    async fn accounts(dry_run: bool) -> AccountRouter {
+        let exchange_api: Arc<Box<dyn ExchangeApi>> =
+            Arc::new(Box::new(MockExchangeApi::default()));
+        let account = |dry_run: bool| {
+            let exchange_api = exchange_api.clone();
+            async move { ArcMutex::new(Account::new(exchange_api, false, dry_run).await) }
+        };
+        AccountRouter {
+            live: account(dry_run).await,
+            shadow: account(true).await,
+            profiles: BTreeMap::from([("hedge".to_string(), account(true).await)]),
+        }
+    }
+
+    fn traded_settings(hedge_profile: Option<&str>) -> FundingArbitrageSettings {
+        FundingArbitrageSettings {
+            margin_usd: Some(100.0),
+            hedge_profile: hedge_profile.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    /// Tests spot hedge legs are refused on live accounts and unknown hedge profiles rejected.
+    #[tokio::test]
+    async fn test_funding_arbitrage_check_accounts() {
+        let live = accounts(false).await;
+        let dry_run = accounts(true).await;
+
+        let monitor = FundingArbitrageTracker::new(FundingArbitrageSettings::default());
+        assert!(monitor.check_accounts(&live).await.is_ok());
+
+        let spot = FundingArbitrageTracker::new(traded_settings(None));
+        assert!(spot.check_accounts(&live).await.is_err());
+        assert!(spot.check_accounts(&dry_run).await.is_ok());
+
+        let profile = FundingArbitrageTracker::new(traded_settings(Some("hedge")));
+        assert!(profile.check_accounts(&live).await.is_ok());
+        let unknown = FundingArbitrageTracker::new(traded_settings(Some("unknown")));
+        assert!(unknown.check_accounts(&live).await.is_err());
+    }
+
+    /// Tests both legs open on funding spikes and close once funding normalizes.
+    #[tokio::test]
+    async fn test_funding_arbitrage_execute() {
+        let accounts = accounts(true).await;
+        let mut tracker = FundingArbitrageTracker::new(traded_settings(None));
+
+        let action = tracker.update(funding_rate(0.0003));
+        tracker
+            .execute("BTCUSDT", action, Some(100.0), &accounts)
+            .await
+            .unwrap();
+
+        let legs = tracker.opportunities()[0].legs.clone().unwrap();
+        assert_eq!(legs.hedge_symbol, "BTCUSDT.SPOT");
+        {
+            let live = accounts.live.lock().await;
+            let sides: BTreeMap<_, _> = live
+                .positions()
+                .map(|position| (position.symbol.clone(), position.order_side))
+                .collect();
+            assert_eq!(
+                sides,
+                BTreeMap::from([
+                    ("BTCUSDT".to_string(), OrderSide::Sell),
+                    ("BTCUSDT.SPOT".to_string(), OrderSide::Buy),
+                ])
+            );
+            assert!(live
+                .positions()
+                .all(|position| position.strategy_id == Some(FUNDING_ARBITRAGE_ID)));
+        }
+
+        let action = tracker.update(funding_rate(0.00001));
+        assert_eq!(action, FundingArbitrageAction::Close);
+        tracker
+            .execute("BTCUSDT", action, Some(101.0), &accounts)
+            .await
+            .unwrap();
+
+        assert_eq!(accounts.live.lock().await.positions().count(), 0);
+        assert!(tracker.opportunities()[0].legs.is_none());
+        assert!(tracker.hedged_symbols().is_empty());
+    }
+
+    /// Tests legs opened before a restart are found again, and kept monitored when untraded.
+    #[tokio::test]
+    async fn test_funding_arbitrage_restore() {
+        let accounts = accounts(true).await;
+        let mut tracker = FundingArbitrageTracker::new(traded_settings(Some("hedge")));
+        let action = tracker.update(funding_rate(-0.0003));
+        tracker
+            .execute("BTCUSDT", action, Some(100.0), &accounts)
+            .await
+            .unwrap();
+        let legs = tracker.opportunities()[0].legs.clone().unwrap();
+        assert_eq!(legs.hedge_symbol, "BTCUSDT");
+
+        let mut restored = FundingArbitrageTracker::new(traded_settings(Some("hedge")));
+        restored.restore(&accounts).await;
+        restored.retain_symbols(&[]);
+        assert_eq!(restored.hedged_symbols(), vec!["BTCUSDT".to_string()]);
+        assert_eq!(restored.legs.get("BTCUSDT"), Some(&legs));
+
+        assert_eq!(
+            restored.update(funding_rate(0.00001)),
+            FundingArbitrageAction::Close
+        );
+    }
+}
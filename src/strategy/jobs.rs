@@ -0,0 +1,299 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    market::interval::Interval,
+    strategy::strategy::StrategySummary,
+    utils::time::{generate_ts, DAY_AS_MILI},
+};
+
+pub type JobId = Uuid;
+
+/// Maximum number of finished jobs kept, the oldest are evicted first.
+const MAX_FINISHED_JOBS: usize = 50;
+
+/// Time in milliseconds finished jobs are kept for.
+const FINISHED_JOB_TTL: u64 = DAY_AS_MILI;
+
+/// Progress and cancellation flag shared between a running backtest and its job.
+#[derive(Debug, Clone, Default)]
+pub struct BackTestProgress {
+    processed: Arc<AtomicUsize>,
+    total: Arc<AtomicUsize>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl BackTestProgress {
+    /// Sets the number of k-lines or trades the backtest replays.
+    pub fn set_total(&self, total: usize) {
+        self.total.store(total, Ordering::Relaxed);
+    }
+
+    /// Marks one more k-line or trade as replayed.
+    pub fn increment(&self) {
+        self.processed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns the replayed share of the data, from `0` to `100`.
+    pub fn percentage(&self) -> f64 {
+        let total = self.total.load(Ordering::Relaxed);
+        if total == 0 {
+            return 0.0;
+        }
+        self.processed.load(Ordering::Relaxed) as f64 / total as f64 * 100.0
+    }
+
+    /// Requests the backtest to stop at the next k-line or trade.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum JobStatus {
+    Running,
+    Completed,
+    Cancelled,
+    /// The backtest task panicked.
+    Failed {
+        error: String,
+    },
+}
+
+/// Information about a backtest job, `progress` is a percentage.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BackTestJobInfo {
+    pub id: JobId,
+    pub strategy_name: String,
+    pub symbol: String,
+    pub interval: Interval,
+    pub status: JobStatus,
+    pub progress: f64,
+    pub created_at: u64,
+    pub finished_at: Option<u64>,
+}
+
+struct BackTestJob {
+    info: BackTestJobInfo,
+    progress: BackTestProgress,
+    result: Option<StrategySummary>,
+}
+
+/// Keeps track of backtests running as background tasks and their results.
+///
+/// Finished jobs are kept for `FINISHED_JOB_TTL`, and at most `MAX_FINISHED_JOBS` of them, so
+/// the summaries of old runs don't accumulate.
+#[derive(Default)]
+pub struct BackTestJobManager {
+    jobs: HashMap<JobId, BackTestJob>,
+}
+
+impl BackTestJobManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a new running job.
+    ///
+    /// # Returns
+    ///
+    /// The id of the job and the progress to hand to its backtest.
+    pub fn add_job(
+        &mut self,
+        strategy_name: &str,
+        symbol: &str,
+        interval: Interval,
+    ) -> (JobId, BackTestProgress) {
+        let id = Uuid::new_v4();
+        let progress = BackTestProgress::default();
+
+        self.jobs.insert(
+            id,
+            BackTestJob {
+                info: BackTestJobInfo {
+                    id,
+                    strategy_name: strategy_name.to_string(),
+                    symbol: symbol.to_string(),
+                    interval,
+                    status: JobStatus::Running,
+                    progress: 0.0,
+                    created_at: generate_ts(),
+                    finished_at: None,
+                },
+                progress: progress.clone(),
+                result: None,
+            },
+        );
+
+        (id, progress)
+    }
+
+    /// Stores the result of a finished job, jobs cancelled while running keep their status and
+    /// discard the partial result.
+    pub fn finish_job(&mut self, id: JobId, result: StrategySummary) {
+        if let Some(job) = self.jobs.get_mut(&id) {
+            job.info.finished_at = Some(generate_ts());
+            if job.progress.is_cancelled() {
+                job.info.status = JobStatus::Cancelled;
+            } else {
+                job.info.status = JobStatus::Completed;
+                job.result = Some(result);
+            }
+        }
+        self.evict_finished_jobs();
+    }
+
+    /// Marks a job whose backtest task failed.
+    pub fn fail_job(&mut self, id: JobId, error: &str) {
+        if let Some(job) = self.jobs.get_mut(&id) {
+            job.info.finished_at = Some(generate_ts());
+            job.info.status = JobStatus::Failed {
+                error: error.to_string(),
+            };
+        }
+        self.evict_finished_jobs();
+    }
+
+    /// Requests a running job to stop.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the job exists and was still running.
+    pub fn cancel_job(&mut self, id: JobId) -> bool {
+        match self.jobs.get(&id) {
+            Some(job) if job.info.status == JobStatus::Running => {
+                job.progress.cancel();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the information of a job and its result once completed.
+    pub fn get_job(&self, id: JobId) -> Option<(BackTestJobInfo, Option<StrategySummary>)> {
+        self.jobs
+            .get(&id)
+            .map(|job| (Self::job_info(job), job.result.clone()))
+    }
+
+    pub fn list_jobs(&self) -> Vec<BackTestJobInfo> {
+        let mut infos: Vec<BackTestJobInfo> = self.jobs.values().map(Self::job_info).collect();
+        infos.sort_by_key(|info| info.created_at);
+        infos
+    }
+
+    // ---
+    // Private Methods
+    // ---
+
+    /// Removes finished jobs older than `FINISHED_JOB_TTL` and the oldest finished jobs beyond
+    /// `MAX_FINISHED_JOBS`.
+    fn evict_finished_jobs(&mut self) {
+        let now = generate_ts();
+        self.jobs.retain(|_, job| {
+            job.info
+                .finished_at
+                .is_none_or(|finished_at| now.saturating_sub(finished_at) < FINISHED_JOB_TTL)
+        });
+
+        let mut finished: Vec<(u64, JobId)> = self
+            .jobs
+            .values()
+            .filter_map(|job| Some((job.info.finished_at?, job.info.id)))
+            .collect();
+        if finished.len() <= MAX_FINISHED_JOBS {
+            return;
+        }
+        finished.sort();
+        for (_, id) in &finished[..finished.len() - MAX_FINISHED_JOBS] {
+            self.jobs.remove(id);
+        }
+    }
+
+    fn job_info(job: &BackTestJob) -> BackTestJobInfo {
+        let mut info = job.info.clone();
+        info.progress = match info.status {
+            JobStatus::Completed => 100.0,
+            _ => job.progress.percentage(),
+        };
+        info
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests progress is reported while running and cancelled jobs drop their result.
+    #[test]
+    fn test_job_lifecycle() {
+        let mut manager = BackTestJobManager::new();
+
+        let (id, progress) = manager.add_job("Rsi", "BTCUSDT", Interval::Min1);
+        progress.set_total(4);
+        progress.increment();
+        let (info, result) = manager.get_job(id).unwrap();
+        assert_eq!(info.status, JobStatus::Running);
+        assert_eq!(info.progress, 25.0);
+        assert!(result.is_none());
+
+        manager.finish_job(id, StrategySummary::default());
+        let (info, result) = manager.get_job(id).unwrap();
+        assert_eq!(info.status, JobStatus::Completed);
+        assert_eq!(info.progress, 100.0);
+        assert!(result.is_some());
+        assert!(!manager.cancel_job(id));
+
+        let (id, _) = manager.add_job("Rsi", "BTCUSDT", Interval::Min1);
+        assert!(manager.cancel_job(id));
+        manager.finish_job(id, StrategySummary::default());
+        let (info, result) = manager.get_job(id).unwrap();
+        assert_eq!(info.status, JobStatus::Cancelled);
+        assert!(result.is_none());
+
+        let (id, _) = manager.add_job("Rsi", "BTCUSDT", Interval::Min1);
+        manager.fail_job(id, "division by 0");
+        let (info, result) = manager.get_job(id).unwrap();
+        assert_eq!(
+            info.status,
+            JobStatus::Failed {
+                error: "division by 0".to_string()
+            }
+        );
+        assert!(result.is_none());
+        assert!(!manager.cancel_job(id));
+    }
+
+    /// Tests finished jobs are evicted once expired or beyond the maximum, running jobs are kept.
+    #[test]
+    fn test_evict_finished_jobs() {
+        let mut manager = BackTestJobManager::new();
+        let (running_id, _) = manager.add_job("Rsi", "BTCUSDT", Interval::Min1);
+
+        let (expired_id, _) = manager.add_job("Rsi", "BTCUSDT", Interval::Min1);
+        manager.finish_job(expired_id, StrategySummary::default());
+        manager.jobs.get_mut(&expired_id).unwrap().info.finished_at =
+            Some(generate_ts() - FINISHED_JOB_TTL);
+
+        for _ in 0..MAX_FINISHED_JOBS + 1 {
+            let (id, _) = manager.add_job("Rsi", "BTCUSDT", Interval::Min1);
+            manager.finish_job(id, StrategySummary::default());
+        }
+
+        assert!(manager.get_job(running_id).is_some());
+        assert!(manager.get_job(expired_id).is_none());
+        assert_eq!(manager.list_jobs().len(), MAX_FINISHED_JOBS + 1);
+    }
+}
@@ -0,0 +1,356 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{account::trade::TradeTx, utils::time::DAY_AS_MILI};
+
+/// Number of milliseconds in a (non leap) year, used to annualize period returns.
+const YEAR_AS_MILI: u64 = DAY_AS_MILI * 365;
+
+/// Risk adjusted performance metrics of a strategy.
+///
+/// Ratios are `None` when they are undefined, e.g. the Sharpe ratio of fewer than two returns
+/// or the profit factor of a strategy without losing trades. `average_loss` is negative and
+/// `expectancy` is the average profit per trade in USD.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct PerformanceMetrics {
+    pub sharpe_ratio: Option<f64>,
+    pub sortino_ratio: Option<f64>,
+    pub calmar_ratio: Option<f64>,
+    pub profit_factor: Option<f64>,
+    pub win_rate: f64,
+    pub average_win: f64,
+    pub average_loss: f64,
+    pub expectancy: f64,
+}
+
+impl PerformanceMetrics {
+    /// Calculates the performance metrics of a strategy.
+    ///
+    /// # Arguments
+    ///
+    /// * `trades` - The closed trades of the strategy, used for the trade statistics.
+    /// * `returns` - The return series, per period or per trade, used for the risk adjusted ratios.
+    /// * `periods_per_year` - The number of returns in a year, used to annualize the ratios.
+    ///
+    /// # Returns
+    ///
+    /// The `PerformanceMetrics` of the strategy.
+    pub fn new(trades: &[TradeTx], returns: &[f64], periods_per_year: f64) -> Self {
+        let profits: Vec<f64> = trades.iter().map(|trade| trade.calc_profit()).collect();
+        let wins: Vec<f64> = profits.iter().copied().filter(|p| *p > 0.0).collect();
+        let losses: Vec<f64> = profits.iter().copied().filter(|p| *p < 0.0).collect();
+
+        let gross_profit: f64 = wins.iter().sum();
+        let gross_loss: f64 = -losses.iter().sum::<f64>();
+
+        Self {
+            sharpe_ratio: calc_sharpe_ratio(returns, periods_per_year),
+            sortino_ratio: calc_sortino_ratio(returns, periods_per_year),
+            calmar_ratio: calc_calmar_ratio(returns, periods_per_year),
+            profit_factor: (gross_loss > 0.0).then(|| gross_profit / gross_loss),
+            win_rate: ratio(wins.len() as f64, profits.len() as f64),
+            average_win: ratio(gross_profit, wins.len() as f64),
+            average_loss: ratio(-gross_loss, losses.len() as f64),
+            expectancy: ratio(profits.iter().sum(), profits.len() as f64),
+        }
+    }
+}
+
+/// Equity of a strategy at a point in time.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct EquityPoint {
+    pub timestamp: u64,
+    /// Capital plus the realized profit of closed trades and the unrealized profit of open
+    /// positions.
+    pub equity: f64,
+    /// Whether the point was recorded after a fill rather than at a k-line close.
+    pub fill: bool,
+}
+
+/// Buy-and-hold benchmark of a backtest, investing the strategy capital without leverage at the
+/// start of the period.
+///
+/// `excess_return_pct` is the strategy return minus the benchmark return and `captured_pct` the
+/// strategy profit as a percentage of the benchmark profit, `None` when the benchmark is flat
+/// or losing, since a share of a loss has no meaning.
+/// `alpha` is the annualized Jensen's alpha of the per-period returns, the strategy return not
+/// explained by its `beta` exposure to the benchmark.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Benchmark {
+    pub profit: f64,
+    pub return_pct: f64,
+    pub excess_return_pct: f64,
+    pub captured_pct: Option<f64>,
+    pub alpha: Option<f64>,
+    pub beta: Option<f64>,
+    pub equity_curve: Vec<EquityPoint>,
+}
+
+impl Benchmark {
+    /// Calculates the benchmark of a strategy.
+    ///
+    /// # Arguments
+    ///
+    /// * `capital` - The capital of the strategy, invested in the benchmark at the start.
+    /// * `strategy_equity` - The strategy equity at the end of every period.
+    /// * `equity_curve` - The benchmark equity at the end of the same periods.
+    /// * `periods_per_year` - The number of periods in a year, used to annualize alpha.
+    ///
+    /// # Returns
+    ///
+    /// The `Benchmark`, or `None` without capital or periods.
+    pub fn new(
+        capital: f64,
+        strategy_equity: &[f64],
+        equity_curve: Vec<EquityPoint>,
+        periods_per_year: f64,
+    ) -> Option<Self> {
+        if capital <= 0.0 {
+            return None;
+        }
+        let strategy_profit = strategy_equity.last()? - capital;
+        let profit = equity_curve.last()?.equity - capital;
+
+        let benchmark_equity: Vec<f64> = equity_curve.iter().map(|point| point.equity).collect();
+        let strategy_returns = calc_period_returns(strategy_equity);
+        let benchmark_returns = calc_period_returns(&benchmark_equity);
+
+        let beta = calc_beta(&strategy_returns, &benchmark_returns);
+        let alpha = beta.and_then(|beta| {
+            let excess = mean(&strategy_returns)? - beta * mean(&benchmark_returns)?;
+            Some(excess * periods_per_year)
+        });
+
+        let return_pct = profit / capital * 100.0;
+        Some(Self {
+            profit,
+            return_pct,
+            excess_return_pct: strategy_profit / capital * 100.0 - return_pct,
+            captured_pct: (profit > 0.0).then(|| strategy_profit / profit * 100.0),
+            alpha,
+            beta,
+            equity_curve,
+        })
+    }
+}
+
+/// Calculates the number of periods of a given interval in a year.
+///
+/// # Arguments
+///
+/// * `interval_ms` - The period length in milliseconds.
+///
+/// # Returns
+///
+/// The number of periods in a year, or `1.0` for an empty interval.
+pub fn periods_per_year(interval_ms: u64) -> f64 {
+    if interval_ms == 0 {
+        return 1.0;
+    }
+    YEAR_AS_MILI as f64 / interval_ms as f64
+}
+
+/// Calculates the return of every trade on the margin it used.
+///
+/// # Arguments
+///
+/// * `trades` - The closed trades.
+///
+/// # Returns
+///
+/// The per-trade returns, e.g. `0.1` for a 10% profit on margin.
+pub fn calc_trade_returns(trades: &[TradeTx]) -> Vec<f64> {
+    trades
+        .iter()
+        .filter(|trade| trade.position.margin_usd > 0.0)
+        .map(|trade| trade.calc_profit() / trade.position.margin_usd)
+        .collect()
+}
+
+/// Calculates the period over period returns of an equity series.
+///
+/// # Arguments
+///
+/// * `equity` - The equity at the end of every period.
+///
+/// # Returns
+///
+/// One return less than the number of equity values, periods starting without equity are skipped.
+pub fn calc_period_returns(equity: &[f64]) -> Vec<f64> {
+    equity
+        .windows(2)
+        .filter(|pair| pair[0] > 0.0)
+        .map(|pair| pair[1] / pair[0] - 1.0)
+        .collect()
+}
+
+/// Annualized mean return over the standard deviation of returns.
+fn calc_sharpe_ratio(returns: &[f64], periods_per_year: f64) -> Option<f64> {
+    let std_dev = std_dev(returns)?;
+    if std_dev == 0.0 {
+        return None;
+    }
+    Some(mean(returns)? / std_dev * periods_per_year.sqrt())
+}
+
+/// Annualized mean return over the downside deviation, only penalizing negative returns.
+fn calc_sortino_ratio(returns: &[f64], periods_per_year: f64) -> Option<f64> {
+    let downside_variance = mean(
+        &returns
+            .iter()
+            .map(|r| r.min(0.0).powi(2))
+            .collect::<Vec<f64>>(),
+    )?;
+    if returns.len() < 2 || downside_variance == 0.0 {
+        return None;
+    }
+    Some(mean(returns)? / downside_variance.sqrt() * periods_per_year.sqrt())
+}
+
+/// Annualized compounded return over the maximum drawdown of the compounded returns.
+fn calc_calmar_ratio(returns: &[f64], periods_per_year: f64) -> Option<f64> {
+    if returns.is_empty() {
+        return None;
+    }
+
+    let mut equity = 1.0;
+    let mut peak = 1.0;
+    let mut max_drawdown: f64 = 0.0;
+    for r in returns {
+        equity *= 1.0 + r;
+        peak = f64::max(peak, equity);
+        max_drawdown = max_drawdown.max((peak - equity) / peak);
+    }
+
+    if max_drawdown == 0.0 || equity <= 0.0 {
+        return None;
+    }
+
+    let annualized_return = equity.powf(periods_per_year / returns.len() as f64) - 1.0;
+    Some(annualized_return / max_drawdown)
+}
+
+/// Covariance of the strategy and benchmark returns over the variance of the benchmark returns.
+fn calc_beta(returns: &[f64], benchmark_returns: &[f64]) -> Option<f64> {
+    if returns.len() != benchmark_returns.len() {
+        return None;
+    }
+    let benchmark_std_dev = std_dev(benchmark_returns)?;
+    if benchmark_std_dev == 0.0 {
+        return None;
+    }
+
+    let (mean, benchmark_mean) = (mean(returns)?, mean(benchmark_returns)?);
+    let covariance = returns
+        .iter()
+        .zip(benchmark_returns)
+        .map(|(r, b)| (r - mean) * (b - benchmark_mean))
+        .sum::<f64>()
+        / (returns.len() - 1) as f64;
+
+    Some(covariance / benchmark_std_dev.powi(2))
+}
+
+fn mean(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    Some(values.iter().sum::<f64>() / values.len() as f64)
+}
+
+/// Sample standard deviation.
+fn std_dev(values: &[f64]) -> Option<f64> {
+    if values.len() < 2 {
+        return None;
+    }
+    let mean = mean(values)?;
+    let variance =
+        values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (values.len() - 1) as f64;
+    Some(variance.sqrt())
+}
+
+fn ratio(numerator: f64, denominator: f64) -> f64 {
+    if denominator == 0.0 {
+        return 0.0;
+    }
+    numerator / denominator
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::account::trade::{OrderSide, Position};
+
+    fn trade(open_price: f64, close_price: f64) -> TradeTx {
+        let position = Position::new(
+            "BTCUSDT",
+            open_price.into(),
+            OrderSide::Buy,
+            100.0,
+            1,
+            None,
+            Default::default(),
+        );
+        TradeTx::new(close_price.into(), 0, position)
+    }
+
+    /// Tests trade statistics of winning and losing trades.
+    #[test]
+    fn test_trade_statistics() {
+        let trades = vec![trade(100.0, 120.0), trade(100.0, 90.0), trade(100.0, 110.0)];
+        let metrics = PerformanceMetrics::new(&trades, &calc_trade_returns(&trades), 1.0);
+
+        assert!((metrics.win_rate - 2.0 / 3.0).abs() < 1e-9);
+        assert!((metrics.average_win - 15.0).abs() < 1e-9);
+        assert!((metrics.average_loss + 10.0).abs() < 1e-9);
+        assert!((metrics.expectancy - 20.0 / 3.0).abs() < 1e-9);
+        assert!((metrics.profit_factor.unwrap() - 3.0).abs() < 1e-9);
+    }
+
+    /// Tests risk adjusted ratios of a return series.
+    #[test]
+    fn test_risk_adjusted_ratios() {
+        let returns = calc_period_returns(&[100.0, 110.0, 99.0, 108.9]);
+        assert_eq!(returns.len(), 3);
+
+        let metrics = PerformanceMetrics::new(&[], &returns, 1.0);
+
+        // returns: 0.1, -0.1, 0.1
+        let mean: f64 = 0.1 / 3.0;
+        let variance = (2.0 * (0.1 - mean).powi(2) + (-0.1 - mean).powi(2)) / 2.0;
+        assert!((metrics.sharpe_ratio.unwrap() - mean / variance.sqrt()).abs() < 1e-9);
+        let downside = (0.01 / 3.0_f64).sqrt();
+        assert!((metrics.sortino_ratio.unwrap() - mean / downside).abs() < 1e-9);
+        // compounded 1.089 over three periods with a 10% drawdown
+        let annualized = 1.089_f64.powf(1.0 / 3.0) - 1.0;
+        assert!((metrics.calmar_ratio.unwrap() - annualized / 0.1).abs() < 1e-9);
+        assert_eq!(metrics.profit_factor, None);
+    }
+
+    /// Tests a strategy with twice the benchmark's returns has a beta of two and no alpha.
+    #[test]
+    fn test_benchmark() {
+        let point = |equity: f64| EquityPoint {
+            timestamp: 0,
+            equity,
+            fill: false,
+        };
+        let benchmark_curve = vec![point(100.0), point(110.0), point(99.0)];
+        let strategy_equity = [100.0, 120.0, 96.0];
+
+        let benchmark = Benchmark::new(100.0, &strategy_equity, benchmark_curve, 1.0).unwrap();
+
+        assert!((benchmark.profit + 1.0).abs() < 1e-9);
+        assert!((benchmark.excess_return_pct + 3.0).abs() < 1e-9);
+        // the benchmark loses, so there is no profit to capture
+        assert!(benchmark.captured_pct.is_none());
+        assert!((benchmark.beta.unwrap() - 2.0).abs() < 1e-9);
+        assert!(benchmark.alpha.unwrap().abs() < 1e-9);
+        assert!(Benchmark::new(0.0, &strategy_equity, vec![], 1.0).is_none());
+
+        // a 10 USD profit against a 5 USD benchmark profit captures 200%
+        let benchmark_curve = vec![point(100.0), point(110.0), point(105.0)];
+        let benchmark =
+            Benchmark::new(100.0, &[100.0, 120.0, 110.0], benchmark_curve, 1.0).unwrap();
+        assert!((benchmark.captured_pct.unwrap() - 200.0).abs() < 1e-9);
+    }
+}
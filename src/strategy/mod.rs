@@ -1,5 +1,20 @@
 pub mod algorithm;
 pub mod backer;
+pub mod comparison;
+pub mod data_points;
+pub mod engine;
+pub mod execution;
+pub mod export;
+pub mod fills;
+pub mod funding;
+pub mod jobs;
+pub mod metrics;
+pub mod optimizer;
+pub mod portfolio;
+pub mod report;
 pub mod signal;
 pub mod strategy;
+pub mod supervisor;
 pub mod types;
+pub mod walk_forward;
+pub mod webhook;
@@ -0,0 +1,454 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    market::kline::Kline,
+    strategy::{
+        backer::BackTestRunner, metrics::PerformanceMetrics, types::AlgorithmError,
+        walk_forward::OptimizationObjective,
+    },
+};
+
+/// Number of candidates competing for every parent selection.
+const TOURNAMENT_SIZE: usize = 3;
+
+/// Maximum mutation step, as a fraction of a parameter's range.
+const MUTATION_SCALE: f64 = 0.1;
+
+/// Search space of a single algorithm parameter, bounds are inclusive.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ParamRange {
+    Integer { min: i64, max: i64 },
+    Float { min: f64, max: f64 },
+}
+
+impl ParamRange {
+    fn validate(&self, name: &str) -> Result<(), AlgorithmError> {
+        let valid = match self {
+            ParamRange::Integer { min, max } => min <= max,
+            ParamRange::Float { min, max } => min.is_finite() && max.is_finite() && min <= max,
+        };
+        if !valid {
+            return Err(AlgorithmError::InvalidParams(format!(
+                "Invalid range for parameter {}",
+                name
+            )));
+        }
+        Ok(())
+    }
+
+    /// Samples a uniformly distributed value in the range.
+    fn sample(&self, rng: &mut StdRng) -> Value {
+        match self {
+            ParamRange::Integer { min, max } => Value::from(rng.gen_range(*min..=*max)),
+            ParamRange::Float { min, max } => Value::from(rng.gen_range(*min..=*max)),
+        }
+    }
+
+    /// Moves a value by a random step of up to `MUTATION_SCALE` of the range, at least one for
+    /// integers, clamped to the range.
+    fn mutate(&self, value: &Value, rng: &mut StdRng) -> Value {
+        match self {
+            ParamRange::Integer { min, max } => {
+                // widened so ranges spanning most of i64 don't overflow
+                let (min, max) = (*min as i128, *max as i128);
+                let value = value.as_i64().map_or(min, i128::from);
+                let step = (((max - min) as f64 * MUTATION_SCALE) as i128).max(1);
+                let value = (value + rng.gen_range(-step..=step)).clamp(min, max);
+                Value::from(value as i64)
+            }
+            ParamRange::Float { min, max } => {
+                let value = value.as_f64().unwrap_or(*min);
+                let step = (max - min) * MUTATION_SCALE;
+                if step == 0.0 {
+                    return Value::from(*min);
+                }
+                Value::from((value + rng.gen_range(-step..=step)).clamp(*min, *max))
+            }
+        }
+    }
+}
+
+/// Settings of the genetic parameter optimizer.
+///
+/// `budget` is the total number of backtests, including the initial population. Every
+/// generation keeps its `elite_count` best candidates and breeds the rest from parents chosen
+/// by tournament selection, with uniform crossover and a `mutation_rate` chance to mutate every
+/// parameter. A `seed` makes the search reproducible.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OptimizerSettings {
+    #[serde(default = "default_population_size")]
+    pub population_size: usize,
+    #[serde(default = "default_budget")]
+    pub budget: usize,
+    #[serde(default = "default_mutation_rate")]
+    pub mutation_rate: f64,
+    #[serde(default = "default_elite_count")]
+    pub elite_count: usize,
+    #[serde(default)]
+    pub seed: Option<u64>,
+    #[serde(default)]
+    pub objective: OptimizationObjective,
+}
+
+fn default_population_size() -> usize {
+    20
+}
+
+fn default_budget() -> usize {
+    100
+}
+
+fn default_mutation_rate() -> f64 {
+    0.2
+}
+
+fn default_elite_count() -> usize {
+    2
+}
+
+impl Default for OptimizerSettings {
+    fn default() -> Self {
+        Self {
+            population_size: default_population_size(),
+            budget: default_budget(),
+            mutation_rate: default_mutation_rate(),
+            elite_count: default_elite_count(),
+            seed: None,
+            objective: OptimizationObjective::default(),
+        }
+    }
+}
+
+/// A backtested set of algorithm parameters. Parameters the strategy can't be built with score
+/// lowest and hold the `error`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OptimizationTrial {
+    pub params: Value,
+    pub score: f64,
+    pub profit: f64,
+    pub metrics: PerformanceMetrics,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// Result of a parameter optimization, trials are in evaluation order.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OptimizationResult {
+    pub best: OptimizationTrial,
+    pub trials: Vec<OptimizationTrial>,
+}
+
+/// Searches integer and continuous parameter spaces of an algorithm with a genetic algorithm,
+/// scoring every candidate with a backtest. The candidates of a generation are backtested in
+/// parallel.
+pub struct GeneticOptimizer {
+    runner: BackTestRunner,
+    settings: OptimizerSettings,
+    rng: StdRng,
+}
+
+impl GeneticOptimizer {
+    pub fn new(runner: BackTestRunner, settings: OptimizerSettings) -> Self {
+        let rng = match settings.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+
+        Self {
+            runner,
+            settings,
+            rng,
+        }
+    }
+
+    /// Runs the optimization.
+    ///
+    /// # Arguments
+    ///
+    /// * `klines` - The k-lines every candidate is backtested over, sorted by open time.
+    /// * `base_params` - Algorithm parameters shared by every candidate.
+    /// * `param_space` - The searched range of every optimized parameter.
+    ///
+    /// # Returns
+    ///
+    /// The `OptimizationResult`, or an `AlgorithmError` if the settings or ranges are invalid or
+    /// no candidate strategy can be built.
+    pub async fn run(
+        &mut self,
+        klines: &[Kline],
+        base_params: Value,
+        param_space: &BTreeMap<String, ParamRange>,
+    ) -> Result<OptimizationResult, AlgorithmError> {
+        if self.settings.population_size < 2 || self.settings.budget == 0 {
+            return Err(AlgorithmError::InvalidParams(
+                "population_size must be at least 2 and budget positive".to_string(),
+            ));
+        }
+        for (name, range) in param_space {
+            range.validate(name)?;
+        }
+
+        let base = base_params.as_object().cloned().unwrap_or_default();
+        let population_size = self.settings.population_size.min(self.settings.budget);
+        let elite_count = self.settings.elite_count.min(population_size - 1);
+
+        let klines: Arc<[Kline]> = Arc::from(klines);
+
+        let genomes = (0..population_size)
+            .map(|_| {
+                param_space
+                    .iter()
+                    .map(|(name, range)| (name.clone(), range.sample(&mut self.rng)))
+                    .collect()
+            })
+            .collect();
+        let mut population = self.evaluate(&base, genomes, klines.clone()).await;
+        let mut trials: Vec<OptimizationTrial> = population.clone();
+
+        while trials.len() < self.settings.budget {
+            sort_by_score(&mut population);
+
+            let child_count =
+                (population_size - elite_count).min(self.settings.budget - trials.len());
+            let genomes = (0..child_count)
+                .map(|_| {
+                    let first = self.select(&population);
+                    let second = self.select(&population);
+                    self.breed(&first.params, &second.params, param_space)
+                })
+                .collect();
+            let children = self.evaluate(&base, genomes, klines.clone()).await;
+            trials.extend(children.iter().cloned());
+
+            population.truncate(elite_count);
+            population.extend(children);
+        }
+
+        let best = trials
+            .iter()
+            .filter(|trial| trial.error.is_none())
+            .max_by(|a, b| a.score.total_cmp(&b.score))
+            .cloned();
+
+        match best {
+            Some(best) => Ok(OptimizationResult { best, trials }),
+            None => {
+                let error = trials
+                    .iter()
+                    .find_map(|trial| trial.error.clone())
+                    .unwrap_or_default();
+                Err(AlgorithmError::InvalidParams(format!(
+                    "No candidate strategy could be built, {error}"
+                )))
+            }
+        }
+    }
+
+    // ---
+    // Private Methods
+    // ---
+
+    /// Backtests the base parameters overridden by the genes of every candidate.
+    async fn evaluate(
+        &self,
+        base: &Map<String, Value>,
+        genomes: Vec<Map<String, Value>>,
+        klines: Arc<[Kline]>,
+    ) -> Vec<OptimizationTrial> {
+        let params: Vec<Value> = genomes
+            .into_iter()
+            .map(|genes| {
+                let mut params = base.clone();
+                params.extend(genes);
+                Value::Object(params)
+            })
+            .collect();
+
+        let summaries = self.runner.run_batch(params.clone(), klines).await;
+
+        params
+            .into_iter()
+            .zip(summaries)
+            .map(|(params, summary)| match summary {
+                Ok(summary) => OptimizationTrial {
+                    score: self.settings.objective.score(&summary),
+                    params,
+                    profit: summary.profit,
+                    metrics: summary.metrics,
+                    error: None,
+                },
+                Err(e) => OptimizationTrial {
+                    params,
+                    score: f64::NEG_INFINITY,
+                    profit: 0.0,
+                    metrics: PerformanceMetrics::default(),
+                    error: Some(e.to_string()),
+                },
+            })
+            .collect()
+    }
+
+    /// Picks the best of `TOURNAMENT_SIZE` random candidates.
+    fn select<'a>(&mut self, population: &'a [OptimizationTrial]) -> &'a OptimizationTrial {
+        population
+            .choose_multiple(&mut self.rng, TOURNAMENT_SIZE)
+            .max_by(|a, b| a.score.total_cmp(&b.score))
+            .unwrap_or(&population[0])
+    }
+
+    /// Breeds the genes of a child with uniform crossover of two parents and mutation.
+    fn breed(
+        &mut self,
+        first: &Value,
+        second: &Value,
+        param_space: &BTreeMap<String, ParamRange>,
+    ) -> Map<String, Value> {
+        param_space
+            .iter()
+            .map(|(name, range)| {
+                let parent = if self.rng.gen_bool(0.5) {
+                    first
+                } else {
+                    second
+                };
+                let mut value = parent[name].clone();
+                if self.rng.gen::<f64>() < self.settings.mutation_rate {
+                    value = range.mutate(&value, &mut self.rng);
+                }
+                (name.clone(), value)
+            })
+            .collect()
+    }
+}
+
+/// Sorts trials best first.
+fn sort_by_score(trials: &mut [OptimizationTrial]) {
+    trials.sort_by(|a, b| b.score.total_cmp(&a.score));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        algorithm::harness::Fixture,
+        events::EventPublisher,
+        exchange::{api::ExchangeApi, mock::MockExchangeApi},
+        market::{interval::Interval, market::Market, messages::MarketMessage},
+        storage::{fs::FsStorage, manager::StorageManager},
+        strategy::{strategy::StrategySettings, types::SignalMessage},
+        utils::channel::{build_arc_channel, OverflowPolicy, DEFAULT_MARKET_CHANNEL_CAPACITY},
+    };
+    use serde_json::json;
+
+    async fn runner() -> BackTestRunner {
+        let (_, market_rx) = build_arc_channel::<MarketMessage>(
+            DEFAULT_MARKET_CHANNEL_CAPACITY,
+            OverflowPolicy::DropOldest,
+        );
+        let exchange_api: Arc<Box<dyn ExchangeApi>> =
+            Arc::new(Box::new(MockExchangeApi::default()));
+        let storage_manager: Arc<Box<dyn StorageManager>> =
+            Arc::new(Box::new(FsStorage::default()));
+        let market = Market::new(
+            market_rx,
+            exchange_api,
+            storage_manager,
+            EventPublisher::new(),
+            false,
+        )
+        .await;
+        let (strategy_tx, _) = build_arc_channel::<SignalMessage>(16, OverflowPolicy::DropOldest);
+
+        BackTestRunner::new(
+            "DcaAccumulator",
+            "BTCUSDT",
+            Interval::Min1,
+            strategy_tx,
+            Arc::new(market),
+            StrategySettings::default(),
+        )
+    }
+
+    /// Tests sampled and mutated values stay within their range.
+    #[test]
+    fn test_param_range_bounds() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let integer = ParamRange::Integer { min: 5, max: 50 };
+        let float = ParamRange::Float { min: 0.1, max: 0.9 };
+
+        for _ in 0..100 {
+            let value = integer.mutate(&integer.sample(&mut rng), &mut rng);
+            assert!((5..=50).contains(&value.as_i64().unwrap()));
+
+            let value = float.mutate(&float.sample(&mut rng), &mut rng);
+            assert!((0.1..=0.9).contains(&value.as_f64().unwrap()));
+        }
+
+        assert!(ParamRange::Integer { min: 2, max: 1 }
+            .validate("period")
+            .is_err());
+        assert!(ParamRange::Float {
+            min: 0.0,
+            max: f64::NAN
+        }
+        .validate("threshold")
+        .is_err());
+    }
+
+    /// Tests mutating ranges spanning the whole of i64 doesn't overflow.
+    #[test]
+    fn test_param_range_mutate_overflow() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let integer = ParamRange::Integer {
+            min: i64::MIN,
+            max: i64::MAX,
+        };
+
+        for value in [i64::MIN, 0, i64::MAX] {
+            assert!(integer.mutate(&Value::from(value), &mut rng).is_i64());
+        }
+    }
+
+    /// Tests candidates with invalid params score lowest without ending the search, which only
+    /// fails when no candidate is valid.
+    #[tokio::test]
+    async fn test_optimizer_failed_candidates() {
+        let klines = Fixture::TrendingUp.klines(10);
+        let settings = OptimizerSettings {
+            population_size: 4,
+            budget: 8,
+            seed: Some(1),
+            ..Default::default()
+        };
+        // buy_interval_mins of 0 is invalid
+        let param_space = BTreeMap::from([(
+            "buy_interval_mins".to_string(),
+            ParamRange::Integer { min: 0, max: 1 },
+        )]);
+
+        let mut optimizer = GeneticOptimizer::new(runner().await, settings.clone());
+        let result = optimizer
+            .run(&klines, json!({}), &param_space)
+            .await
+            .unwrap();
+        assert_eq!(result.trials.len(), 8);
+        assert!(result.trials.iter().any(|trial| trial.error.is_some()));
+        assert!(result.best.error.is_none());
+        assert_eq!(result.best.params["buy_interval_mins"], 1);
+
+        let param_space = BTreeMap::from([(
+            "buy_interval_mins".to_string(),
+            ParamRange::Integer { min: 0, max: 0 },
+        )]);
+        let mut optimizer = GeneticOptimizer::new(runner().await, settings);
+        assert!(matches!(
+            optimizer.run(&klines, json!({}), &param_space).await,
+            Err(AlgorithmError::InvalidParams(_))
+        ));
+    }
+}
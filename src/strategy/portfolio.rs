@@ -0,0 +1,412 @@
+use std::{collections::HashMap, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    account::{account::Account, money::Price, trade::PositionId},
+    events::EventPublisher,
+    exchange::{api::ExchangeApi, mock::MockExchangeApi},
+    market::{
+        external::ExternalDataStore, interval::Interval, kline::Kline, kline_stream::KlineStream,
+        market::Market, messages::MarketMessage, symbol::canonical_symbol, types::ArcMutex,
+    },
+    storage::{fs::FsStorage, manager::StorageManager},
+    strategy::{
+        engine::{self, EquityCurve, DEFAULT_MAX_CURVE_POINTS},
+        metrics::{calc_period_returns, periods_per_year, EquityPoint, PerformanceMetrics},
+        signal::SignalManager,
+        strategy::{Strategy, StrategyId, StrategySettings, StrategySummary},
+        types::SignalMessage,
+    },
+    utils::{
+        channel::{build_arc_channel, OverflowPolicy, DEFAULT_MARKET_CHANNEL_CAPACITY},
+        time::SEC_AS_MILI,
+    },
+};
+
+/// A strategy of a portfolio backtest.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PortfolioStrategy {
+    pub strategy_name: String,
+    #[serde(deserialize_with = "canonical_symbol")]
+    pub symbol: String,
+    pub interval: Interval,
+    pub settings: StrategySettings,
+    pub algorithm_params: Value,
+}
+
+/// Results of a portfolio backtest.
+///
+/// `max_drawdown` is the largest peak to trough drop of the portfolio equity in USD, including
+/// unrealized losses of concurrently open positions across strategies. It is measured on the
+/// equity curve, capped at `DEFAULT_MAX_CURVE_POINTS`, see `EquityCurve`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PortfolioSummary {
+    pub strategies: Vec<StrategySummary>,
+    pub capital: f64,
+    pub profit: f64,
+    pub max_drawdown: f64,
+    pub metrics: PerformanceMetrics,
+    pub equity_curve: Vec<EquityPoint>,
+}
+
+/// Backtests one or more strategies across multiple symbols against a shared simulated account.
+///
+/// K-lines of all symbols are replayed in close time order, so positions of different
+/// strategies are open at the same time. New positions are only opened while the account has
+/// enough free capital for their margin, the initial capital plus realized profit minus the
+/// margin of open positions.
+pub struct PortfolioBackTest {
+    strategies: Vec<Strategy>,
+    capital: f64,
+    signal_manager: SignalManager,
+    account: ArcMutex<Account>,
+    market: Arc<Market>,
+    position_open_times: HashMap<PositionId, u64>,
+    last_prices: HashMap<String, f64>,
+    period_prices: HashMap<usize, (f64, f64)>,
+    interval_ms: u64,
+    equity_curve: EquityCurve,
+    external_data: Option<ExternalDataStore>,
+}
+
+impl PortfolioBackTest {
+    /// Creates a new `PortfolioBackTest`.
+    ///
+    /// # Arguments
+    ///
+    /// * `strategies` - The strategies to backtest, each trading its own symbol and interval.
+    /// * `capital` - The initial capital of the shared account in USD.
+    ///
+    /// # Returns
+    ///
+    /// Returns a new instance of `PortfolioBackTest`.
+    pub async fn new(strategies: Vec<Strategy>, capital: f64) -> Self {
+        let (_, market_rx) = build_arc_channel::<MarketMessage>(
+            DEFAULT_MARKET_CHANNEL_CAPACITY,
+            OverflowPolicy::DropOldest,
+        );
+        let exchange_api: Arc<Box<dyn ExchangeApi>> =
+            Arc::new(Box::new(MockExchangeApi::default()));
+
+        let storage_manager: Arc<Box<dyn StorageManager>> =
+            Arc::new(Box::new(FsStorage::default()));
+
+        let market = Arc::new(
+            Market::new(
+                market_rx,
+                exchange_api.clone(),
+                storage_manager.clone(),
+                EventPublisher::new(),
+                false,
+            )
+            .await,
+        );
+
+        let account = ArcMutex::new(Account::new(exchange_api.clone(), false, true).await);
+
+        let mut signal_manager = SignalManager::new();
+        signal_manager.set_capital(Some(capital));
+        for strategy in strategies.iter() {
+            signal_manager.add_strategy_settings(&strategy.id, strategy.settings());
+        }
+
+        Self {
+            strategies,
+            capital,
+            signal_manager,
+            account,
+            market,
+            position_open_times: HashMap::new(),
+            last_prices: HashMap::new(),
+            period_prices: HashMap::new(),
+            interval_ms: 0,
+            equity_curve: EquityCurve::new(Some(DEFAULT_MAX_CURVE_POINTS)),
+            external_data: None,
+        }
+    }
+
+    /// Feeds the strategies the values external series had at the close of every k-line from
+    /// the given values, rather than from the values retained by the market.
+    pub fn set_external_data(&mut self, external_data: ExternalDataStore) {
+        self.external_data = Some(external_data);
+    }
+
+    /// Executes the backtest over the historical k-lines of every symbol.
+    ///
+    /// The streams are merged in close time order, holding only a month of k-lines per stream
+    /// in memory at a time. Every k-line is evaluated by the strategies trading its symbol and
+    /// interval. One equity point is recorded per close time, marking all open positions to
+    /// their symbol's last price, downsampled once the curve exceeds
+    /// `DEFAULT_MAX_CURVE_POINTS`, see `EquityCurve`.
+    ///
+    /// # Arguments
+    ///
+    /// * `streams` - Streams of historical k-lines, one per symbol and interval.
+    pub async fn run(&mut self, mut streams: Vec<KlineStream>) {
+        let routes = self.routes().await;
+        self.interval_ms = routes
+            .iter()
+            .map(|(_, interval)| interval.to_millis())
+            .min()
+            .unwrap_or(0);
+
+        loop {
+            // pick the stream with the earliest next kline
+            let mut next: Option<(usize, u64, String)> = None;
+            for (index, stream) in streams.iter_mut().enumerate() {
+                if let Some(kline) = stream.peek().await {
+                    let is_earlier = match &next {
+                        Some((_, close_time, symbol)) => {
+                            (kline.close_time, &kline.symbol) < (*close_time, symbol)
+                        }
+                        None => true,
+                    };
+                    if is_earlier {
+                        next = Some((index, kline.close_time, kline.symbol.clone()));
+                    }
+                }
+            }
+
+            let kline = match next {
+                Some((index, _, _)) => match streams[index].next().await {
+                    Some(kline) => kline,
+                    None => break,
+                },
+                None => break,
+            };
+
+            let mut closes_period = true;
+            for stream in streams.iter_mut() {
+                if let Some(next) = stream.peek().await {
+                    if next.close_time == kline.close_time {
+                        closes_period = false;
+                    }
+                }
+            }
+            self.process_kline(&routes, &kline, closes_period).await;
+        }
+    }
+
+    /// Computes and returns a summary of the portfolio backtest results.
+    ///
+    /// Positions still open are closed at the last price of their symbol.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `PortfolioSummary` with the summary of every strategy and the portfolio-level
+    /// profit, drawdown and risk adjusted metrics.
+    pub async fn result(&mut self) -> PortfolioSummary {
+        let open_positions: Vec<(PositionId, Price)> = self
+            .account
+            .lock()
+            .await
+            .positions()
+            .map(|position| {
+                let price = self
+                    .last_prices
+                    .get(&position.symbol)
+                    .map(|price| Price::from_f64(*price))
+                    .unwrap_or(position.open_price);
+                (position.id, price)
+            })
+            .collect();
+
+        for (id, price) in open_positions {
+            self.account.lock().await.close_position(id, price).await;
+        }
+
+        let mut strategies = vec![];
+        for (index, strategy) in self.strategies.iter().enumerate() {
+            let mut summary = strategy.summary(self.account.clone()).await;
+            if let Some((start, end)) = self.period_prices.get(&index) {
+                summary.period_start_price = *start;
+                summary.period_end_price = *end;
+            }
+            strategies.push(summary);
+        }
+
+        let trades = self.account.lock().await.trades();
+        let candle_equity: Vec<f64> = self
+            .equity_curve
+            .points()
+            .iter()
+            .filter(|point| !point.fill)
+            .map(|point| point.equity)
+            .collect();
+        // a point of a downsampled curve spans several periods
+        let metrics = PerformanceMetrics::new(
+            &trades,
+            &calc_period_returns(&candle_equity),
+            periods_per_year(self.interval_ms * self.equity_curve.period_len()),
+        );
+
+        PortfolioSummary {
+            strategies,
+            capital: self.capital,
+            profit: Strategy::calc_profit(&trades),
+            max_drawdown: calc_max_equity_drawdown(&candle_equity),
+            metrics,
+            equity_curve: self.equity_curve.points().to_vec(),
+        }
+    }
+
+    // ---
+    // Private Methods
+    // ---
+
+    /// Returns the symbol and interval traded by every strategy.
+    async fn routes(&self) -> Vec<(String, Interval)> {
+        let mut routes = vec![];
+        for strategy in self.strategies.iter() {
+            let info = strategy.info().await;
+            routes.push((info.symbol, info.interval));
+        }
+        routes
+    }
+
+    /// Evaluates a k-line with the strategies trading its symbol and interval.
+    ///
+    /// # Arguments
+    ///
+    /// * `routes` - The symbol and interval of every strategy.
+    /// * `kline` - The k-line to evaluate.
+    /// * `closes_period` - Whether no other k-line closes at the same time, recording an
+    ///   equity point for the period.
+    async fn process_kline(
+        &mut self,
+        routes: &[(String, Interval)],
+        kline: &Kline,
+        closes_period: bool,
+    ) {
+        let fills = self.fill_count().await;
+        self.last_prices.insert(kline.symbol.clone(), kline.close);
+
+        for (index, (symbol, interval)) in routes.iter().enumerate().take(self.strategies.len()) {
+            if *symbol != kline.symbol || *interval != kline.interval {
+                continue;
+            }
+
+            self.period_prices
+                .entry(index)
+                .and_modify(|(_, end)| *end = kline.close)
+                .or_insert((kline.open, kline.close));
+
+            self.close_expired_positions(index, kline).await;
+
+            let eval_result = engine::evaluate(
+                &self.strategies[index],
+                kline.clone(),
+                self.external_data.as_ref(),
+            )
+            .await;
+            if let Some((order_side, confidence)) = eval_result.order_side() {
+                let strategy = &self.strategies[index];
+                let signal = SignalMessage {
+                    strategy_id: strategy.id,
+                    order_side,
+                    symbol: strategy.symbol.to_string(),
+                    interval: kline.interval,
+                    price: kline.close,
+                    confidence,
+                    is_back_test: true,
+                    timestamp: kline.close_time,
+                    stop_loss: strategy.algorithm.lock().await.stop_loss(),
+                };
+
+                self.signal_manager
+                    .handle_signal(signal, self.market.clone(), self.account.clone())
+                    .await;
+            }
+        }
+
+        // track open time of new positions using kline time instead of wall clock time
+        for position in self.account.lock().await.positions() {
+            self.position_open_times
+                .entry(position.id)
+                .or_insert(kline.close_time);
+        }
+
+        if self.fill_count().await != fills {
+            self.record_equity(kline.close_time, true).await;
+        }
+        if closes_period {
+            self.record_equity(kline.close_time, false).await;
+        }
+    }
+
+    /// Counts the closed trades and open positions, a change in either means an order was filled.
+    async fn fill_count(&self) -> (usize, usize) {
+        engine::fill_count(&self.account, &self.strategy_ids()).await
+    }
+
+    /// Records the equity of the portfolio, the capital plus the realized profit of all closed
+    /// trades and the unrealized profit of all open positions at their symbol's last price.
+    async fn record_equity(&mut self, timestamp: u64, fill: bool) {
+        let equity = engine::calc_equity(
+            &self.account,
+            &self.strategy_ids(),
+            self.capital,
+            |position| self.last_prices.get(&position.symbol).copied(),
+        )
+        .await;
+
+        self.equity_curve.push(EquityPoint {
+            timestamp,
+            equity,
+            fill,
+        });
+    }
+
+    /// Returns the ids of the strategies sharing the account.
+    fn strategy_ids(&self) -> Vec<StrategyId> {
+        self.strategies.iter().map(|strategy| strategy.id).collect()
+    }
+
+    /// Closes positions of a strategy that have been open longer than its
+    /// `max_position_duration`, measured in backtest time.
+    async fn close_expired_positions(&mut self, index: usize, kline: &Kline) {
+        let strategy = &self.strategies[index];
+        let max_duration = match strategy.settings().max_position_duration {
+            Some(max_duration) => max_duration * SEC_AS_MILI,
+            None => return,
+        };
+
+        engine::close_expired_positions(
+            &self.account,
+            &mut self.position_open_times,
+            strategy.id,
+            max_duration,
+            kline.close_time,
+            kline.close,
+        )
+        .await;
+    }
+}
+
+/// Calculates the largest peak to trough drop of an equity series.
+fn calc_max_equity_drawdown(equity: &[f64]) -> f64 {
+    let mut peak = f64::MIN;
+    let mut max_drawdown: f64 = 0.0;
+    for value in equity {
+        peak = peak.max(*value);
+        max_drawdown = max_drawdown.max(peak - value);
+    }
+    max_drawdown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests the drawdown is measured from the highest preceding peak.
+    #[test]
+    fn test_calc_max_equity_drawdown() {
+        assert_eq!(calc_max_equity_drawdown(&[]), 0.0);
+        assert_eq!(
+            calc_max_equity_drawdown(&[100.0, 120.0, 90.0, 130.0, 110.0]),
+            30.0
+        );
+    }
+}
@@ -0,0 +1,543 @@
+use std::fmt::Write;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    strategy::{
+        backer::BackTestRecord,
+        metrics::{calc_trade_returns, EquityPoint},
+        strategy::StrategyId,
+    },
+    utils::time::{generate_ts, timestamp_to_string},
+};
+
+/// Maximum number of points drawn per chart, longer curves are downsampled.
+const MAX_CHART_POINTS: usize = 500;
+
+/// Maximum number of rows of the text charts of Markdown reports.
+const MAX_TEXT_CHART_ROWS: usize = 30;
+
+/// Number of buckets of the trade return distribution.
+const DISTRIBUTION_BUCKETS: usize = 10;
+
+const CHART_WIDTH: f64 = 800.0;
+const CHART_HEIGHT: f64 = 240.0;
+const CHART_PADDING: f64 = 40.0;
+
+/// File format of a backtest report.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportFormat {
+    #[default]
+    Html,
+    Markdown,
+}
+
+impl ReportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ReportFormat::Html => "html",
+            ReportFormat::Markdown => "md",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ReportFormat::Html => "text/html; charset=utf-8",
+            ReportFormat::Markdown => "text/markdown; charset=utf-8",
+        }
+    }
+}
+
+/// A rendered report of a saved backtest run, identified by the strategy ID of the run and its
+/// format.
+///
+/// Reports are self-contained: HTML reports draw their charts as inline SVG and Markdown reports
+/// as text bar charts, neither loads external resources.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BackTestReport {
+    pub id: StrategyId,
+    pub format: ReportFormat,
+    pub created_at: u64,
+    pub content: String,
+}
+
+impl BackTestReport {
+    /// Renders the report of a backtest run, with its metrics table, equity curve, drawdown
+    /// chart and trade return distribution.
+    ///
+    /// # Arguments
+    ///
+    /// * `record` - The backtest run.
+    /// * `format` - The format to render.
+    ///
+    /// # Returns
+    ///
+    /// The rendered `BackTestReport`.
+    pub fn new(record: &BackTestRecord, format: ReportFormat) -> Self {
+        let content = match format {
+            ReportFormat::Html => render_html(record),
+            ReportFormat::Markdown => render_markdown(record),
+        };
+
+        Self {
+            id: record.id(),
+            format,
+            created_at: generate_ts(),
+            content,
+        }
+    }
+
+    /// The file name the report is downloaded as.
+    pub fn filename(&self) -> String {
+        format!("back-test-{}.{}", self.id, self.format.extension())
+    }
+}
+
+/// A bucket of the trade return distribution, returns in percent on margin.
+#[derive(Debug, Clone, PartialEq)]
+struct Bucket {
+    from: f64,
+    to: f64,
+    count: usize,
+}
+
+fn render_html(record: &BackTestRecord) -> String {
+    let summary = &record.summary;
+    let title = escape_html(&format!(
+        "{} {} {} backtest",
+        summary.info.name, summary.info.symbol, summary.info.interval
+    ));
+
+    let mut html = String::new();
+    // SAFETY: writing to a `String` never fails
+    write!(
+        html,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n\
+         <style>\
+         body {{ font-family: sans-serif; margin: 2em auto; max-width: 860px; color: #222; }}\
+         table {{ border-collapse: collapse; }}\
+         td {{ border-bottom: 1px solid #ddd; padding: 4px 12px; }}\
+         td:last-child {{ text-align: right; }}\
+         svg {{ background: #fafafa; border: 1px solid #ddd; }}\
+         </style>\n</head>\n<body>\n<h1>{title}</h1>\n"
+    )
+    .unwrap();
+
+    html.push_str("<h2>Metrics</h2>\n<table>\n");
+    for (name, value) in metric_rows(record) {
+        // SAFETY: writing to a `String` never fails
+        writeln!(
+            html,
+            "<tr><td>{name}</td><td>{}</td></tr>",
+            escape_html(&value)
+        )
+        .unwrap();
+    }
+    html.push_str("</table>\n");
+
+    let equity = equity_series(&summary.equity_curve);
+    html.push_str("<h2>Equity curve</h2>\n");
+    html.push_str(&svg_line_chart(&equity, "#2b6cb0"));
+
+    html.push_str("<h2>Drawdown (%)</h2>\n");
+    html.push_str(&svg_line_chart(
+        &drawdown_series(&summary.equity_curve),
+        "#c53030",
+    ));
+
+    html.push_str("<h2>Trade return distribution (% on margin)</h2>\n");
+    html.push_str(&svg_bar_chart(&trade_distribution(record)));
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn render_markdown(record: &BackTestRecord) -> String {
+    let summary = &record.summary;
+    let mut markdown = format!(
+        "# {} {} {} backtest\n\n## Metrics\n\n| Metric | Value |\n| --- | ---: |\n",
+        summary.info.name, summary.info.symbol, summary.info.interval
+    );
+    for (name, value) in metric_rows(record) {
+        // SAFETY: writing to a `String` never fails
+        writeln!(markdown, "| {name} | {value} |").unwrap();
+    }
+
+    let rows = |series: Vec<(u64, f64)>| {
+        downsample(&series, MAX_TEXT_CHART_ROWS)
+            .into_iter()
+            .map(|(timestamp, value)| (timestamp_to_string(timestamp), value))
+            .collect::<Vec<_>>()
+    };
+
+    markdown.push_str("\n## Equity curve\n\n");
+    markdown.push_str(&text_bar_chart(&rows(equity_series(&summary.equity_curve))));
+
+    markdown.push_str("\n## Drawdown (%)\n\n");
+    markdown.push_str(&text_bar_chart(&rows(drawdown_series(
+        &summary.equity_curve,
+    ))));
+
+    markdown.push_str("\n## Trade return distribution (% on margin)\n\n");
+    let buckets: Vec<(String, f64)> = trade_distribution(record)
+        .into_iter()
+        .map(|bucket| {
+            (
+                format!("{:.2} to {:.2}", bucket.from, bucket.to),
+                bucket.count as f64,
+            )
+        })
+        .collect();
+    markdown.push_str(&text_bar_chart(&buckets));
+
+    markdown
+}
+
+fn metric_rows(record: &BackTestRecord) -> Vec<(&'static str, String)> {
+    let summary = &record.summary;
+    let metrics = &summary.metrics;
+    let ratio = |value: Option<f64>| value.map_or("n/a".to_string(), |value| format!("{value:.2}"));
+
+    let mut rows = vec![
+        ("Strategy", summary.info.name.clone()),
+        ("Symbol", summary.info.symbol.clone()),
+        ("Interval", summary.info.interval.to_string()),
+        (
+            "Period",
+            format!(
+                "{} to {}",
+                timestamp_to_string(record.from_ts),
+                timestamp_to_string(record.to_ts)
+            ),
+        ),
+        ("Mode", format!("{:?}", record.mode)),
+        ("Profit (USD)", format!("{:.2}", summary.profit)),
+        ("Max drawdown (USD)", format!("{:.2}", summary.max_drawdown)),
+        ("Max profit (USD)", format!("{:.2}", summary.max_profit)),
+        ("Funding paid (USD)", format!("{:.2}", summary.funding_paid)),
+        (
+            "Trades (long / short)",
+            format!(
+                "{} ({} / {})",
+                summary.trades.len(),
+                summary.long_trade_count,
+                summary.short_trade_count
+            ),
+        ),
+        ("Win rate", format!("{:.2}%", metrics.win_rate * 100.0)),
+        ("Average win (USD)", format!("{:.2}", metrics.average_win)),
+        ("Average loss (USD)", format!("{:.2}", metrics.average_loss)),
+        ("Expectancy (USD)", format!("{:.2}", metrics.expectancy)),
+        ("Profit factor", ratio(metrics.profit_factor)),
+        ("Sharpe ratio", ratio(metrics.sharpe_ratio)),
+        ("Sortino ratio", ratio(metrics.sortino_ratio)),
+        ("Calmar ratio", ratio(metrics.calmar_ratio)),
+    ];
+
+    if let Some(benchmark) = &summary.benchmark {
+        rows.push((
+            "Buy and hold return",
+            format!("{:.2}%", benchmark.return_pct),
+        ));
+        rows.push((
+            "Excess return",
+            format!("{:.2}%", benchmark.excess_return_pct),
+        ));
+    }
+
+    rows
+}
+
+fn equity_series(equity_curve: &[EquityPoint]) -> Vec<(u64, f64)> {
+    equity_curve
+        .iter()
+        .map(|point| (point.timestamp, point.equity))
+        .collect()
+}
+
+/// Percentage below the running equity peak at every point, `0.0` or negative.
+fn drawdown_series(equity_curve: &[EquityPoint]) -> Vec<(u64, f64)> {
+    let mut peak = f64::MIN;
+    equity_curve
+        .iter()
+        .map(|point| {
+            peak = peak.max(point.equity);
+            let drawdown = if peak > 0.0 {
+                (point.equity / peak - 1.0) * 100.0
+            } else {
+                0.0
+            };
+            (point.timestamp, drawdown)
+        })
+        .collect()
+}
+
+/// Buckets the trade returns on margin into equally wide buckets between the lowest and
+/// highest return.
+fn trade_distribution(record: &BackTestRecord) -> Vec<Bucket> {
+    let returns: Vec<f64> = calc_trade_returns(&record.summary.trades)
+        .into_iter()
+        .map(|trade_return| trade_return * 100.0)
+        .collect();
+    if returns.is_empty() {
+        return vec![];
+    }
+
+    let min = returns.iter().copied().fold(f64::MAX, f64::min);
+    let max = returns.iter().copied().fold(f64::MIN, f64::max);
+    let width = (max - min) / DISTRIBUTION_BUCKETS as f64;
+    if width == 0.0 {
+        return vec![Bucket {
+            from: min,
+            to: max,
+            count: returns.len(),
+        }];
+    }
+
+    let mut buckets: Vec<Bucket> = (0..DISTRIBUTION_BUCKETS)
+        .map(|i| Bucket {
+            from: min + width * i as f64,
+            to: min + width * (i + 1) as f64,
+            count: 0,
+        })
+        .collect();
+    for trade_return in returns {
+        let i = (((trade_return - min) / width) as usize).min(DISTRIBUTION_BUCKETS - 1);
+        buckets[i].count += 1;
+    }
+    buckets
+}
+
+/// Keeps at most `max` evenly spaced points, always including the last one.
+fn downsample<T: Clone>(points: &[T], max: usize) -> Vec<T> {
+    if points.len() <= max || max < 2 {
+        return points.to_vec();
+    }
+
+    let step = (points.len() - 1) as f64 / (max - 1) as f64;
+    (0..max)
+        .map(|i| points[(i as f64 * step).round() as usize].clone())
+        .collect()
+}
+
+fn svg_line_chart(series: &[(u64, f64)], color: &str) -> String {
+    let series = downsample(series, MAX_CHART_POINTS);
+    let (first, last) = match (series.first(), series.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return "<p>No data</p>\n".to_string(),
+    };
+
+    let min = series
+        .iter()
+        .map(|(_, value)| *value)
+        .fold(f64::MAX, f64::min);
+    let max = series
+        .iter()
+        .map(|(_, value)| *value)
+        .fold(f64::MIN, f64::max);
+    let time_range = (last.0 - first.0).max(1) as f64;
+    let value_range = if max > min { max - min } else { 1.0 };
+
+    let plot_width = CHART_WIDTH - 2.0 * CHART_PADDING;
+    let plot_height = CHART_HEIGHT - 2.0 * CHART_PADDING;
+    let points: Vec<String> = series
+        .iter()
+        .map(|(timestamp, value)| {
+            let x = CHART_PADDING + (timestamp - first.0) as f64 / time_range * plot_width;
+            let y = CHART_PADDING + (max - value) / value_range * plot_height;
+            format!("{x:.1},{y:.1}")
+        })
+        .collect();
+
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{CHART_WIDTH}\" height=\"{CHART_HEIGHT}\" \
+         font-size=\"11\">\n\
+         <polyline fill=\"none\" stroke=\"{color}\" stroke-width=\"1.5\" points=\"{}\"/>\n\
+         <text x=\"4\" y=\"{CHART_PADDING}\">{max:.2}</text>\n\
+         <text x=\"4\" y=\"{}\">{min:.2}</text>\n\
+         <text x=\"{CHART_PADDING}\" y=\"{}\">{}</text>\n\
+         <text x=\"{}\" y=\"{}\" text-anchor=\"end\">{}</text>\n\
+         </svg>\n",
+        points.join(" "),
+        CHART_HEIGHT - CHART_PADDING,
+        CHART_HEIGHT - 8.0,
+        timestamp_to_string(first.0),
+        CHART_WIDTH - CHART_PADDING,
+        CHART_HEIGHT - 8.0,
+        timestamp_to_string(last.0),
+    )
+}
+
+fn svg_bar_chart(buckets: &[Bucket]) -> String {
+    let max_count = match buckets.iter().map(|bucket| bucket.count).max() {
+        Some(max_count) if max_count > 0 => max_count as f64,
+        _ => return "<p>No trades</p>\n".to_string(),
+    };
+
+    let plot_height = CHART_HEIGHT - 2.0 * CHART_PADDING;
+    let bar_width = (CHART_WIDTH - 2.0 * CHART_PADDING) / buckets.len() as f64;
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{CHART_WIDTH}\" height=\"{CHART_HEIGHT}\" \
+         font-size=\"11\">\n"
+    );
+    for (i, bucket) in buckets.iter().enumerate() {
+        let height = bucket.count as f64 / max_count * plot_height;
+        let x = CHART_PADDING + i as f64 * bar_width;
+        let y = CHART_PADDING + plot_height - height;
+        let color = if bucket.to <= 0.0 {
+            "#c53030"
+        } else {
+            "#2f855a"
+        };
+        // SAFETY: writing to a `String` never fails
+        write!(
+            svg,
+            "<rect x=\"{x:.1}\" y=\"{y:.1}\" width=\"{:.1}\" height=\"{height:.1}\" fill=\"{color}\">\
+             <title>{:.2}% to {:.2}%: {}</title></rect>\n\
+             <text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\">{}</text>\n\
+             <text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\">{:.1}</text>\n",
+            bar_width - 2.0,
+            bucket.from,
+            bucket.to,
+            bucket.count,
+            x + bar_width / 2.0,
+            y - 4.0,
+            bucket.count,
+            x + bar_width / 2.0,
+            CHART_HEIGHT - CHART_PADDING + 14.0,
+            (bucket.from + bucket.to) / 2.0,
+        )
+        .unwrap();
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Renders labelled values as a fenced block of horizontal bars, scaled to the largest
+/// absolute value.
+fn text_bar_chart(rows: &[(String, f64)]) -> String {
+    const BAR_WIDTH: f64 = 40.0;
+
+    if rows.is_empty() {
+        return "No data\n".to_string();
+    }
+
+    let max = rows
+        .iter()
+        .map(|(_, value)| value.abs())
+        .fold(0.0, f64::max);
+    let label_width = rows.iter().map(|(label, _)| label.len()).max().unwrap_or(0);
+
+    let mut chart = "```\n".to_string();
+    for (label, value) in rows {
+        let length = if max > 0.0 {
+            (value.abs() / max * BAR_WIDTH).round() as usize
+        } else {
+            0
+        };
+        // SAFETY: writing to a `String` never fails
+        writeln!(
+            chart,
+            "{label:<label_width$} | {:<bar_width$} {value:.2}",
+            "#".repeat(length),
+            bar_width = BAR_WIDTH as usize
+        )
+        .unwrap();
+    }
+    chart.push_str("```\n");
+    chart
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        account::trade::{OrderSide, Position, TradeTx},
+        strategy::{
+            backer::{BackTestMode, BackTestOptions},
+            strategy::StrategySummary,
+        },
+    };
+
+    fn record() -> BackTestRecord {
+        let mut summary = StrategySummary::default();
+        summary.info.name = "<Rsi>".to_string();
+        summary.equity_curve = [100.0, 120.0, 90.0, 130.0]
+            .iter()
+            .enumerate()
+            .map(|(i, equity)| EquityPoint {
+                timestamp: i as u64 * 60_000,
+                equity: *equity,
+                fill: false,
+            })
+            .collect();
+        summary.trades = [110.0, 90.0, 105.0]
+            .iter()
+            .map(|close_price| {
+                let position = Position::new(
+                    "BTCUSDT",
+                    100.0.into(),
+                    OrderSide::Buy,
+                    100.0,
+                    1,
+                    None,
+                    Default::default(),
+                );
+                TradeTx::new((*close_price).into(), 0, position)
+            })
+            .collect();
+
+        BackTestRecord::new(
+            0,
+            180_000,
+            BackTestMode::Klines,
+            BackTestOptions::default(),
+            summary,
+        )
+    }
+
+    /// Tests drawdowns are relative to the running equity peak.
+    #[test]
+    fn test_drawdown_series() {
+        let drawdowns: Vec<f64> = drawdown_series(&record().summary.equity_curve)
+            .into_iter()
+            .map(|(_, drawdown)| drawdown)
+            .collect();
+
+        assert_eq!(drawdowns, vec![0.0, 0.0, -25.0, 0.0]);
+    }
+
+    /// Tests reports of both formats contain every section and HTML is escaped.
+    #[test]
+    fn test_back_test_report() {
+        let record = record();
+
+        let html = BackTestReport::new(&record, ReportFormat::Html);
+        assert_eq!(html.filename(), format!("back-test-{}.html", record.id()));
+        assert!(html.content.contains("&lt;Rsi&gt;"));
+        assert_eq!(html.content.matches("<svg").count(), 3);
+        assert_eq!(html.content.matches("<rect").count(), DISTRIBUTION_BUCKETS);
+
+        let markdown = BackTestReport::new(&record, ReportFormat::Markdown);
+        for section in [
+            "## Metrics",
+            "## Equity curve",
+            "## Drawdown (%)",
+            "## Trade return distribution",
+        ] {
+            assert!(markdown.content.contains(section));
+        }
+        assert!(markdown
+            .content
+            .contains("| Trades (long / short) | 3 (0 / 0) |"));
+    }
+}
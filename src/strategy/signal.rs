@@ -1,17 +1,132 @@
-use std::{collections::HashMap, marker};
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
 
 use log::info;
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+use uuid::Uuid;
 
 use crate::{
-    account::{account::Account, trade::Position},
-    market::{market::Market, types::ArcMutex},
+    account::{
+        account::Account,
+        money::Price,
+        trade::{IcebergOrder, IcebergStatus, OrderAction, OrderSide, Position, PositionId},
+    },
+    events::{BotEvent, EventPublisher},
+    market::{correlation::CORRELATION_THRESHOLD, market::Market, types::ArcMutex},
+    storage::manager::StorageManager,
+    utils::{
+        kline::{calc_atr, calc_candle_body_pct, calc_quote_volume},
+        time::{generate_ts, string_to_timestamp, DAY_AS_MILI, SEC_AS_MILI},
+    },
 };
 
 use super::{
+    execution::{
+        exit_counts, iceberg_limit, is_limit_filled, volume_profile, ChildOrder, ExecutionStyle,
+    },
     strategy::{StrategyId, StrategySettings},
     types::SignalMessage,
 };
 
+/// Number of klines used to calculate the ATR signal filter.
+const ATR_PERIOD: usize = 14;
+
+/// How often the market price is checked against the limit price of an iceberg slice.
+const ICEBERG_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often the positions a split exit left open after its last child order are closed again.
+const EXIT_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The background task trading the child orders of a split entry or exit.
+struct Execution {
+    handle: JoinHandle<()>,
+    /// The margin of the child orders of an entry not traded yet, kept free for them.
+    reserved_margin: Arc<Mutex<f64>>,
+}
+
+/// The action taken by the `SignalManager` on a signal.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum SignalAction {
+    /// A new position was opened.
+    Opened { position_id: PositionId },
+    /// The open positions of the strategy were closed by an opposite signal.
+    Closed { position_ids: Vec<PositionId> },
+    /// The entry or exit was split into child orders traded over time by the execution style
+    /// of the strategy.
+    Scheduled { child_orders: usize },
+    /// An iceberg order was placed for the entry or exit, whose slices are filled over time.
+    IcebergPlaced { order_id: Uuid },
+    /// No position was opened or closed.
+    Ignored { reason: String },
+}
+
+/// A handled signal and the action taken on it.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SignalRecord {
+    pub id: Uuid,
+    pub signal: SignalMessage,
+    #[serde(flatten)]
+    pub action: SignalAction,
+    /// When the signal was handled, in milliseconds.
+    pub handled_at: u64,
+}
+
+impl SignalRecord {
+    pub fn new(signal: SignalMessage, action: SignalAction) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            signal,
+            action,
+            handled_at: generate_ts(),
+        }
+    }
+}
+
+/// Filters the signal history by strategy and time of the signal.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct SignalHistoryQuery {
+    pub strategy_id: Option<StrategyId>,
+    pub from_ts: Option<u64>,
+    pub to_ts: Option<u64>,
+    /// Returns only the latest records.
+    pub limit: Option<usize>,
+}
+
+impl SignalHistoryQuery {
+    /// Checks whether a record matches the strategy and time range of the query.
+    pub fn matches(&self, record: &SignalRecord) -> bool {
+        let signal = &record.signal;
+        self.strategy_id
+            .is_none_or(|strategy_id| signal.strategy_id == strategy_id)
+            && self
+                .from_ts
+                .is_none_or(|from_ts| signal.timestamp >= from_ts)
+            && self.to_ts.is_none_or(|to_ts| signal.timestamp <= to_ts)
+    }
+
+    /// Keeps the records matching the query, sorted by signal time and cut to the latest
+    /// `limit` records.
+    pub fn apply(&self, records: Vec<SignalRecord>) -> Vec<SignalRecord> {
+        let mut records: Vec<SignalRecord> = records
+            .into_iter()
+            .filter(|record| self.matches(record))
+            .collect();
+        records.sort_by_key(|record| (record.signal.timestamp, record.handled_at));
+
+        if let Some(limit) = self.limit {
+            let skip = records.len().saturating_sub(limit);
+            records.drain(..skip);
+        }
+        records
+    }
+}
+
 /// Manages the handling of trading signals for active trading strategies.
 ///
 /// This manager is responsible for executing trading signals by opening or closing positions
@@ -20,6 +135,13 @@ use super::{
 
 pub struct SignalManager {
     active_strategy_settings: HashMap<StrategyId, StrategySettings>,
+    capital: Option<f64>,
+    storage_manager: Option<Arc<Box<dyn StorageManager>>>,
+    events: Option<EventPublisher>,
+    /// Live signals are ignored while paused, e.g. after flattening all positions.
+    paused: bool,
+    /// The tasks trading the child orders of split entries and exits, by strategy.
+    executions: Mutex<HashMap<StrategyId, Vec<Execution>>>,
 }
 
 impl SignalManager {
@@ -37,9 +159,49 @@ impl SignalManager {
     pub fn new() -> Self {
         Self {
             active_strategy_settings: HashMap::new(),
+            capital: None,
+            storage_manager: None,
+            events: None,
+            paused: false,
+            executions: Mutex::new(HashMap::new()),
         }
     }
 
+    /// Records every handled signal with the action taken to the given storage, backtest
+    /// signals aren't recorded.
+    pub fn set_storage_manager(&mut self, storage_manager: Arc<Box<dyn StorageManager>>) {
+        self.storage_manager = Some(storage_manager);
+    }
+
+    /// Publishes every handled live signal with the action taken.
+    pub fn set_event_publisher(&mut self, events: EventPublisher) {
+        self.events = Some(events);
+    }
+
+    /// Limits the margin of open positions to the given capital plus realized profit, `None`
+    /// allows positions as long as the exchange accepts them.
+    pub fn set_capital(&mut self, capital: Option<f64>) {
+        self.capital = capital;
+    }
+
+    /// Pauses or resumes trading, live signals are ignored while paused. Pausing also cancels
+    /// the remaining child orders of split entries and exits.
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        if paused {
+            for (_, executions) in self.executions().drain() {
+                executions
+                    .iter()
+                    .for_each(|execution| execution.handle.abort());
+            }
+        }
+    }
+
+    /// Whether trading is paused, see `set_paused`.
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
     /// Processes a trading signal, potentially opening or closing positions based on the strategy's settings.
     ///
     /// # Arguments
@@ -48,15 +210,152 @@ impl SignalManager {
     ///
     /// This method considers the current active positions, the strategy settings, and the nature of the signal
     /// to decide on the appropriate trading action.
-
+    ///
+    /// # Returns
+    ///
+    /// The `SignalAction` taken, which is also recorded to storage if one is set.
     pub async fn handle_signal(
         &self,
         signal: SignalMessage,
-        market: ArcMutex<Market>,
+        market: Arc<Market>,
         account: ArcMutex<Account>,
-    ) {
-        let account = account.clone();
-        let market = market.clone();
+    ) -> SignalAction {
+        let action = self
+            .execute_signal(&signal, market, account)
+            .await
+            .unwrap_or_else(|reason| SignalAction::Ignored { reason });
+
+        if !signal.is_back_test {
+            let record = SignalRecord::new(signal, action.clone());
+            if let Some(events) = &self.events {
+                events.publish(BotEvent::Signal(record.clone()));
+            }
+            if let Some(storage_manager) = &self.storage_manager {
+                if let Err(e) = storage_manager.save_signal_record(record).await {
+                    info!("Unable to save signal record, e: {e}");
+                }
+            }
+        }
+
+        action
+    }
+
+    /// Closes positions that have been open longer than the `max_position_duration` of their strategy.
+    ///
+    /// # Arguments
+    ///
+    /// * `market` - A shared, thread-safe reference to the market data.
+    /// * `account` - A shared, thread-safe reference to the trading account.
+    ///
+    /// Positions are closed at the last market price regardless of any incoming signals.
+    pub async fn close_expired_positions(&self, market: Arc<Market>, account: ArcMutex<Account>) {
+        let now = generate_ts();
+
+        for (strategy_id, settings) in self.active_strategy_settings.iter() {
+            let max_duration = match settings.max_position_duration {
+                Some(max_duration) => max_duration * SEC_AS_MILI,
+                None => continue,
+            };
+
+            let expired_positions: Vec<Position> = account
+                .lock()
+                .await
+                .strategy_positions(*strategy_id)
+                .into_iter()
+                .filter(|position| match string_to_timestamp(&position.open_time) {
+                    Ok(open_ts) => now.saturating_sub(open_ts) >= max_duration,
+                    Err(_) => false,
+                })
+                .cloned()
+                .collect();
+
+            for position in expired_positions {
+                if let Some(close_price) = market.last_price(&position.symbol).await {
+                    info!(
+                        "Closing position {} of strategy {strategy_id}, max position duration reached",
+                        position.id
+                    );
+                    account
+                        .lock()
+                        .await
+                        .close_position(position.id, close_price.into())
+                        .await;
+                }
+            }
+        }
+    }
+
+    /// Adds settings for a trading strategy to the manager.
+    ///
+    /// # Arguments
+    ///
+    /// * `strategy_id` - The unique identifier of the strategy.
+    /// * `settings` - The trading settings for the strategy.
+    ///
+    /// This allows the `SignalManager` to enforce strategy-specific trading parameters.
+
+    pub fn add_strategy_settings(&mut self, strategy_id: &StrategyId, settings: StrategySettings) {
+        self.active_strategy_settings
+            .insert(strategy_id.clone(), settings);
+    }
+
+    /// Retrieves the trading settings of an active strategy, which decide the account its
+    /// signals trade on.
+    ///
+    /// # Arguments
+    ///
+    /// * `strategy_id` - The unique identifier of the strategy.
+    ///
+    /// # Returns
+    ///
+    /// Returns the settings, or `None` if the strategy isn't active.
+    pub fn strategy_settings(&self, strategy_id: &StrategyId) -> Option<&StrategySettings> {
+        self.active_strategy_settings.get(strategy_id)
+    }
+
+    /// Removes the trading settings associated with a strategy from the manager.
+    ///
+    /// # Arguments
+    ///
+    /// * `strategy_id` - The unique identifier of the strategy whose settings are to be removed.
+    ///
+    /// This is used when a strategy is no longer active or has been removed, which also cancels
+    /// the remaining child orders of its split entries and exits.
+    pub fn remove_strategy_settings(&mut self, strategy_id: &StrategyId) {
+        self.active_strategy_settings.remove(&strategy_id);
+        self.cancel_executions(strategy_id);
+    }
+
+    /// Retrieves the trading settings of every active strategy, including the settings of
+    /// TradingView alerts.
+    pub fn all_strategy_settings(&self) -> &HashMap<StrategyId, StrategySettings> {
+        &self.active_strategy_settings
+    }
+
+    /// Replaces the trading settings of every strategy, e.g. by those of a snapshot.
+    pub fn replace_strategy_settings(&mut self, settings: HashMap<StrategyId, StrategySettings>) {
+        self.active_strategy_settings = settings;
+    }
+
+    // ---
+    // Private Methods
+    // ---
+
+    /// Opens or closes positions for a signal.
+    ///
+    /// # Returns
+    ///
+    /// The `SignalAction` taken, or the reason the signal was ignored.
+    async fn execute_signal(
+        &self,
+        signal: &SignalMessage,
+        market: Arc<Market>,
+        account: ArcMutex<Account>,
+    ) -> Result<SignalAction, String> {
+        if self.paused && !signal.is_back_test {
+            return Err("trading is paused".to_string());
+        }
+
         let active_positions: Vec<Position> = account
             .lock()
             .await
@@ -70,100 +369,1250 @@ impl SignalManager {
         let trigger_price = if signal.is_back_test {
             Some(signal.price)
         } else {
-            market.lock().await.last_price(&signal.symbol).await
+            market.last_price(&signal.symbol).await
         };
 
-        if self
-            .active_strategy_settings
-            .get(&signal.strategy_id)
-            .is_none()
-        {
-            return;
-        }
-
-        // SAFETY: None check above, used to make method more clear
         let settings = self
             .active_strategy_settings
             .get(&signal.strategy_id)
-            .unwrap();
+            .ok_or("strategy is not active")?;
+
+        let margin_usd = if settings.scale_by_confidence {
+            settings.margin_usd * signal.confidence
+        } else {
+            settings.margin_usd
+        };
+
+        // iceberg slices are simulated against the market price, a live exchange would fill
+        // them with market orders at whatever price it reached
+        let simulated_only = matches!(settings.execution, ExecutionStyle::Iceberg { .. })
+            && !signal.is_back_test
+            && !account.lock().await.is_dry_run();
 
         // get last open position
         if let Some(last) = active_positions.last() {
             // if last.signal is different to new signal then close all positions
             if signal.order_side != last.order_side {
-                if let Some(close_price) = trigger_price {
-                    for position in &active_positions {
-                        account
-                            .lock()
-                            .await
-                            .close_position(position.id, close_price)
-                            .await;
-                    }
+                // the exit supersedes the remaining child orders of a split entry
+                self.cancel_executions(&signal.strategy_id);
+                // live iceberg exits close at once rather than leave the positions open
+                if !signal.is_back_test && !settings.execution.is_immediate() && !simulated_only {
+                    let action = self
+                        .schedule_exit(signal, settings, active_positions, market, account)
+                        .await;
+                    return Ok(action);
                 }
 
-            // if is same signal as last position and settings allow more than one
-            // open position
-            } else if active_positions.len() < settings.max_open_orders as usize {
-                if let Some(close_price) = trigger_price {
-                    account
+                let close_price =
+                    Price::from_f64(trigger_price.ok_or("no market price to close positions")?);
+                let mut position_ids = vec![];
+                for position in &active_positions {
+                    let closed = account
                         .lock()
                         .await
-                        .open_position(
-                            &signal.symbol,
-                            settings.margin_usd,
-                            settings.leverage,
-                            signal.order_side.clone(),
-                            close_price,
-                            Some(signal.strategy_id),
-                            None,
-                        )
-                        .await;
+                        .close_position(position.id, close_price)
+                        .await
+                        .is_some();
+                    if closed {
+                        position_ids.push(position.id);
+                    }
+                }
+                if position_ids.is_empty() {
+                    return Err("positions not closed by the exchange".to_string());
                 }
+                return Ok(SignalAction::Closed { position_ids });
             }
 
-        // no open positions yet for given strategy
-        } else {
-            if let Some(last_price) = trigger_price {
-                account
-                    .lock()
-                    .await
-                    .open_position(
+            // if is same signal as last position and settings allow more than one
+            // open position in the signal direction
+            if active_positions.len() >= settings.max_open_orders as usize {
+                return Err(format!(
+                    "max open orders {} reached",
+                    settings.max_open_orders
+                ));
+            }
+        }
+
+        // the child orders of a split entry may still add to the positions
+        if self.has_active_execution(&signal.strategy_id) {
+            return Err("an execution of the strategy is in progress".to_string());
+        }
+
+        // low confidence signals still close opposite positions, but don't open new ones
+        if let Some(min_confidence) = settings.min_confidence {
+            if signal.confidence < min_confidence {
+                return Err(format!(
+                    "confidence {} below {min_confidence}",
+                    signal.confidence
+                ));
+            }
+        }
+
+        // signals in a disallowed direction are close-only
+        if !settings.direction.allows(&signal.order_side) {
+            return Err(format!(
+                "order side {:?} not allowed by direction",
+                signal.order_side
+            ));
+        }
+
+        // an empty or NaN margin would open a position poisoning the profit sums
+        if margin_usd.is_nan() || margin_usd <= 0.0 {
+            return Err(format!("margin {margin_usd} isn't positive"));
+        }
+        Self::passes_filters(signal, settings, market.clone()).await?;
+        Self::check_correlated_margin(signal, settings, margin_usd, &market, &account).await?;
+        if !self.has_free_margin(account.clone(), margin_usd).await {
+            return Err(format!("not enough free capital for margin {margin_usd}"));
+        }
+        if simulated_only {
+            return Err("iceberg orders are only placed on dry run accounts".to_string());
+        }
+
+        if !signal.is_back_test && !settings.execution.is_immediate() {
+            return self
+                .schedule_entry(
+                    signal,
+                    settings,
+                    margin_usd,
+                    active_positions.len(),
+                    market,
+                    account,
+                )
+                .await;
+        }
+
+        let open_price = trigger_price.ok_or("no market price to open a position")?;
+        let position_id =
+            Self::open_position(&account, signal, settings, margin_usd, open_price).await?;
+
+        Ok(SignalAction::Opened { position_id })
+    }
+
+    /// Opens a position for a signal, with the stop loss and take profit of the strategy.
+    ///
+    /// # Returns
+    ///
+    /// The id of the opened position, or the reason it wasn't opened.
+    async fn open_position(
+        account: &ArcMutex<Account>,
+        signal: &SignalMessage,
+        settings: &StrategySettings,
+        margin_usd: f64,
+        open_price: f64,
+    ) -> Result<PositionId, String> {
+        let (stop_loss, take_profit) = settings.exit_prices(&signal.order_side, open_price);
+        let mut account = account.lock().await;
+        let position = account
+            .open_position(
+                &signal.symbol,
+                margin_usd,
+                settings.leverage,
+                signal.order_side,
+                open_price.into(),
+                Some(signal.strategy_id),
+                signal.stop_loss.or(stop_loss).map(Price::from_f64),
+            )
+            .await
+            .ok_or("position not opened by the exchange")?;
+
+        let (position_id, stop_loss) = (position.id, position.stop_loss);
+        if take_profit.is_some() {
+            account
+                .set_position_exits(position_id, stop_loss, take_profit.map(Price::from_f64))
+                .await;
+        }
+
+        Ok(position_id)
+    }
+
+    /// Splits the entry of a signal into child orders, each opening a position with its share
+    /// of the margin at the market price of its time. The margin of a child order stays
+    /// reserved until it is traded.
+    ///
+    /// # Returns
+    ///
+    /// The `SignalAction` taken, or the reason the entry wasn't scheduled if the positions of
+    /// the child orders would exceed the `max_open_orders` of the strategy.
+    async fn schedule_entry(
+        &self,
+        signal: &SignalMessage,
+        settings: &StrategySettings,
+        margin_usd: f64,
+        open_positions: usize,
+        market: Arc<Market>,
+        account: ArcMutex<Account>,
+    ) -> Result<SignalAction, String> {
+        let children = match settings.execution {
+            ExecutionStyle::Iceberg { .. } => vec![],
+            execution => Self::plan_execution(signal, execution, &market).await,
+        };
+        let child_orders = match settings.execution {
+            ExecutionStyle::Iceberg { slices, .. } => slices as usize,
+            _ => children.len(),
+        };
+        if open_positions + child_orders > settings.max_open_orders as usize {
+            return Err(format!(
+                "{child_orders} child orders exceed max open orders {}",
+                settings.max_open_orders
+            ));
+        }
+
+        let reserved_margin = Arc::new(Mutex::new(margin_usd));
+        if let ExecutionStyle::Iceberg { offset_pct, .. } = settings.execution {
+            let target = IcebergTarget::Open { margin_usd };
+            let action = self
+                .place_iceberg(
+                    signal,
+                    settings,
+                    target,
+                    offset_pct,
+                    reserved_margin,
+                    market,
+                    account,
+                )
+                .await;
+            return Ok(action);
+        }
+
+        let (signal, settings) = (signal.clone(), settings.clone());
+        let reserved = reserved_margin.clone();
+
+        self.start_execution(signal.strategy_id, reserved_margin, async move {
+            let started = tokio::time::Instant::now();
+            for child in children {
+                tokio::time::sleep_until(started + child.delay).await;
+                let margin_usd = margin_usd * child.weight;
+                match market.last_price(&signal.symbol).await {
+                    Some(open_price) => {
+                        let opened = Self::open_position(
+                            &account, &signal, &settings, margin_usd, open_price,
+                        );
+                        if let Err(e) = opened.await {
+                            info!("Child order of {} not opened, e: {e}", signal.symbol);
+                        }
+                    }
+                    None => info!("No market price for a child order of {}", signal.symbol),
+                }
+                // released once opened, so the margin is never free in between
+                release_margin(&reserved, margin_usd);
+            }
+        });
+
+        Ok(SignalAction::Scheduled { child_orders })
+    }
+
+    /// Splits the exit of a signal into child orders, each closing its share of the positions
+    /// at the market price of its time. Positions a child order can't close, e.g. without a
+    /// market price, are closed by the next one, and once the last child order is traded the
+    /// positions left open are closed again every `EXIT_RETRY_INTERVAL` until none is left.
+    async fn schedule_exit(
+        &self,
+        signal: &SignalMessage,
+        settings: &StrategySettings,
+        positions: Vec<Position>,
+        market: Arc<Market>,
+        account: ArcMutex<Account>,
+    ) -> SignalAction {
+        let reserved_margin = Arc::new(Mutex::new(0.0));
+        if let ExecutionStyle::Iceberg { offset_pct, .. } = settings.execution {
+            let target = IcebergTarget::Close { positions };
+            return self
+                .place_iceberg(
+                    signal,
+                    settings,
+                    target,
+                    offset_pct,
+                    reserved_margin,
+                    market,
+                    account,
+                )
+                .await;
+        }
+
+        let children = Self::plan_execution(signal, settings.execution, &market).await;
+        let counts = exit_counts(&children, positions.len());
+        let child_orders = children.len();
+        let symbol = signal.symbol.clone();
+
+        self.start_execution(signal.strategy_id, reserved_margin, async move {
+            let started = tokio::time::Instant::now();
+            let (mut scheduled, mut open) = (0, vec![]);
+            for (child, count) in children.into_iter().zip(counts) {
+                tokio::time::sleep_until(started + child.delay).await;
+                open.extend_from_slice(&positions[scheduled..count]);
+                scheduled = count;
+                open = Self::close_exit_positions(&symbol, open, &market, &account).await;
+            }
+
+            while !open.is_empty() {
+                tokio::time::sleep(EXIT_RETRY_INTERVAL).await;
+                open = Self::close_exit_positions(&symbol, open, &market, &account).await;
+            }
+        });
+
+        SignalAction::Scheduled { child_orders }
+    }
+
+    /// Closes positions of a split exit at the market price.
+    ///
+    /// # Returns
+    ///
+    /// The positions left open, all of them without a market price, or those the account
+    /// failed to close. Positions the account no longer holds, e.g. closed by their stop loss,
+    /// aren't returned.
+    async fn close_exit_positions(
+        symbol: &str,
+        mut positions: Vec<Position>,
+        market: &Market,
+        account: &ArcMutex<Account>,
+    ) -> Vec<Position> {
+        let close_price = market.last_price(symbol).await;
+        let mut account = account.lock().await;
+        positions.retain(|position| account.get_position(&position.id).is_some());
+
+        let Some(close_price) = close_price.filter(|_| !positions.is_empty()) else {
+            if !positions.is_empty() {
+                info!("No market price for a child order of {symbol}");
+            }
+            return positions;
+        };
+
+        let mut open = vec![];
+        for position in positions {
+            let closed = account
+                .close_position(position.id, Price::from_f64(close_price))
+                .await;
+            if closed.is_none() {
+                open.push(position);
+            }
+        }
+        open
+    }
+
+    /// Places an iceberg order for the entry or exit of a signal with the account and works it
+    /// in the background. The visible slice rests at its limit price until the market price
+    /// reaches it, is filled at the limit price and replaced by the next slice at the new
+    /// market price. Slices that fail to open are placed again.
+    ///
+    /// No limit order reaches the exchange, the fills are simulated, so only dry run accounts
+    /// place iceberg orders.
+    ///
+    /// The margin of an entry's slices is released from `reserved_margin` as they fill.
+    #[allow(clippy::too_many_arguments)]
+    async fn place_iceberg(
+        &self,
+        signal: &SignalMessage,
+        settings: &StrategySettings,
+        target: IcebergTarget,
+        offset_pct: f64,
+        reserved_margin: Arc<Mutex<f64>>,
+        market: Arc<Market>,
+        account: ArcMutex<Account>,
+    ) -> SignalAction {
+        let (slices, timeout) = (settings.execution.slices(), settings.execution.duration());
+        let (action, counts) = match &target {
+            IcebergTarget::Open { .. } => (OrderAction::Open, vec![]),
+            IcebergTarget::Close { positions } => {
+                let children = settings.execution.schedule(&[]);
+                (OrderAction::Close, exit_counts(&children, positions.len()))
+            }
+        };
+        let order = IcebergOrder::new(
+            &signal.symbol,
+            Some(signal.strategy_id),
+            signal.order_side,
+            action,
+            slices,
+        );
+        let order_id = order.id;
+        account.lock().await.place_iceberg_order(order);
+        let (signal, settings) = (signal.clone(), settings.clone());
+        let reserved = reserved_margin.clone();
+
+        self.start_execution(signal.strategy_id, reserved_margin, async move {
+            let _guard = IcebergGuard {
+                order_id,
+                account: account.clone(),
+            };
+            let deadline = tokio::time::Instant::now() + timeout;
+            let mut ticks = tokio::time::interval(ICEBERG_POLL_INTERVAL);
+            let mut limit_price = None;
+            let mut filled = 0;
+
+            while filled < slices as usize {
+                ticks.tick().await;
+                if tokio::time::Instant::now() >= deadline {
+                    info!("Iceberg order {order_id} of {} expired", signal.symbol);
+                    if let Some(order) = account.lock().await.iceberg_order_mut(&order_id) {
+                        order.finish(IcebergStatus::Expired);
+                    }
+                    return;
+                }
+                let Some(price) = market.last_price(&signal.symbol).await else {
+                    continue;
+                };
+
+                let Some(limit) = limit_price else {
+                    let limit = iceberg_limit(signal.order_side, price, offset_pct);
+                    limit_price = Some(limit);
+                    if let Some(order) = account.lock().await.iceberg_order_mut(&order_id) {
+                        order.limit_price = Some(Price::from_f64(limit));
+                    }
+                    continue;
+                };
+                if !is_limit_filled(signal.order_side, limit, price) {
+                    continue;
+                }
+                limit_price = None;
+
+                let position_ids = match &target {
+                    IcebergTarget::Open { margin_usd } => {
+                        let margin_usd = margin_usd / slices as f64;
+                        let opened =
+                            Self::open_position(&account, &signal, &settings, margin_usd, limit);
+                        match opened.await {
+                            Ok(position_id) => {
+                                release_margin(&reserved, margin_usd);
+                                vec![position_id]
+                            }
+                            Err(e) => {
+                                info!("Iceberg slice of {} not opened, e: {e}", signal.symbol);
+                                continue;
+                            }
+                        }
+                    }
+                    IcebergTarget::Close { positions } => {
+                        let closed = filled.checked_sub(1).map_or(0, |slice| counts[slice]);
+                        let mut position_ids = vec![];
+                        let mut account = account.lock().await;
+                        for position in &positions[closed..counts[filled]] {
+                            let close_price = Price::from_f64(limit);
+                            if account
+                                .close_position(position.id, close_price)
+                                .await
+                                .is_some()
+                            {
+                                position_ids.push(position.id);
+                            }
+                        }
+                        position_ids
+                    }
+                };
+
+                filled += 1;
+                if let Some(order) = account.lock().await.iceberg_order_mut(&order_id) {
+                    order.record_fill(position_ids);
+                }
+            }
+        });
+
+        SignalAction::IcebergPlaced { order_id }
+    }
+
+    /// Plans the child orders of a split order. VWAP weighs them by the volume of the same
+    /// window the day before, profiled from klines of the signal's interval, so its windows
+    /// should be at least as long as the interval.
+    async fn plan_execution(
+        signal: &SignalMessage,
+        execution: ExecutionStyle,
+        market: &Market,
+    ) -> Vec<ChildOrder> {
+        let volumes = match execution {
+            ExecutionStyle::Vwap { slices, .. } => {
+                let from_ts = generate_ts().saturating_sub(DAY_AS_MILI);
+                let to_ts = from_ts + execution.duration().as_millis() as u64;
+                market
+                    .kline_data_range(
                         &signal.symbol,
-                        settings.margin_usd,
-                        settings.leverage,
-                        signal.order_side.clone(),
-                        last_price,
-                        Some(signal.strategy_id),
+                        signal.interval,
+                        Some(from_ts),
+                        Some(to_ts),
                         None,
                     )
-                    .await;
+                    .await
+                    .map(|kline_data| {
+                        let window = execution.duration() / slices;
+                        volume_profile(&kline_data.klines(), from_ts, window, slices)
+                    })
+                    .unwrap_or_default()
             }
+            _ => vec![],
+        };
+        execution.schedule(&volumes)
+    }
+
+    /// Runs the child orders of a split order in the background, keeping `reserved_margin`
+    /// free while it runs. Finished executions of the strategy are forgotten.
+    fn start_execution(
+        &self,
+        strategy_id: StrategyId,
+        reserved_margin: Arc<Mutex<f64>>,
+        execution: impl Future<Output = ()> + Send + 'static,
+    ) {
+        let handle = tokio::spawn(execution);
+        let mut executions = self.executions();
+        let executions = executions.entry(strategy_id).or_default();
+        executions.retain(|execution| !execution.handle.is_finished());
+        executions.push(Execution {
+            handle,
+            reserved_margin,
+        });
+    }
+
+    /// Cancels the remaining child orders of the split entries and exits of a strategy.
+    fn cancel_executions(&self, strategy_id: &StrategyId) {
+        if let Some(executions) = self.executions().remove(strategy_id) {
+            executions
+                .iter()
+                .for_each(|execution| execution.handle.abort());
         }
     }
 
-    /// Adds settings for a trading strategy to the manager.
+    /// Whether the child orders of a split entry or exit of a strategy are still being traded.
+    fn has_active_execution(&self, strategy_id: &StrategyId) -> bool {
+        self.executions()
+            .get(strategy_id)
+            .is_some_and(|executions| {
+                executions
+                    .iter()
+                    .any(|execution| !execution.handle.is_finished())
+            })
+    }
+
+    /// The margin reserved for the child orders of running split entries of every strategy.
+    fn reserved_margin(&self) -> f64 {
+        self.executions()
+            .values()
+            .flatten()
+            .filter(|execution| !execution.handle.is_finished())
+            .map(|execution| *lock_margin(&execution.reserved_margin))
+            .sum()
+    }
+
+    fn executions(&self) -> MutexGuard<'_, HashMap<StrategyId, Vec<Execution>>> {
+        // the map is never left half changed, so it stays usable after a panic
+        self.executions
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Checks whether the account has enough free capital for the margin of a new position.
     ///
     /// # Arguments
     ///
-    /// * `strategy_id` - The unique identifier of the strategy.
-    /// * `settings` - The trading settings for the strategy.
+    /// * `account` - A shared, thread-safe reference to the trading account.
+    /// * `margin_usd` - The margin of the new position.
     ///
-    /// This allows the `SignalManager` to enforce strategy-specific trading parameters.
+    /// # Returns
+    ///
+    /// Returns `true` without a capital limit, otherwise whether the capital plus realized
+    /// profit minus the margin of open positions and of scheduled child orders covers
+    /// `margin_usd`.
+    async fn has_free_margin(&self, account: ArcMutex<Account>, margin_usd: f64) -> bool {
+        let capital = match self.capital {
+            Some(capital) => capital,
+            None => return true,
+        };
 
-    pub fn add_strategy_settings(&mut self, strategy_id: &StrategyId, settings: StrategySettings) {
-        self.active_strategy_settings
-            .insert(strategy_id.clone(), settings);
+        let account = account.lock().await;
+        let realized: f64 = account
+            .trades()
+            .iter()
+            .map(|trade| trade.calc_profit())
+            .sum();
+        let used_margin: f64 = account
+            .positions()
+            .map(|position| position.margin_usd)
+            .sum();
+
+        capital + realized - used_margin - self.reserved_margin() >= margin_usd
     }
 
-    /// Removes the trading settings associated with a strategy from the manager.
+    /// Checks the margin of the open positions correlated with a new position stays within the
+    /// `max_correlated_margin` of the strategy settings.
+    ///
+    /// Positions of the signal's symbol count in full. Positions of other symbols count if
+    /// their returns at the signal's interval correlate by at least `CORRELATION_THRESHOLD` in
+    /// the direction of the new position, e.g. a short of a symbol moving against the symbol of
+    /// a new long. Correlations are tracked live, so back tests only count the signal's symbol.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok` if the correlated margin plus `margin_usd` is within the limit, or the reason
+    /// the signal is ignored otherwise.
+    async fn check_correlated_margin(
+        signal: &SignalMessage,
+        settings: &StrategySettings,
+        margin_usd: f64,
+        market: &Market,
+        account: &ArcMutex<Account>,
+    ) -> Result<(), String> {
+        let Some(max_correlated_margin) = settings.max_correlated_margin else {
+            return Ok(());
+        };
+        let correlations = if signal.is_back_test {
+            None
+        } else {
+            market.correlations(signal.interval).await
+        };
+        let direction = |order_side: OrderSide| match order_side {
+            OrderSide::Buy => 1.0,
+            OrderSide::Sell => -1.0,
+        };
+
+        let correlated_margin: f64 = account
+            .lock()
+            .await
+            .positions()
+            .filter(|position| {
+                let correlation = if position.symbol == signal.symbol {
+                    Some(1.0)
+                } else {
+                    correlations
+                        .as_ref()
+                        .and_then(|matrix| matrix.get(&position.symbol, &signal.symbol))
+                };
+                correlation.is_some_and(|correlation| {
+                    correlation * direction(position.order_side) * direction(signal.order_side)
+                        >= CORRELATION_THRESHOLD
+                })
+            })
+            .map(|position| position.margin_usd)
+            .sum();
+
+        if correlated_margin + margin_usd > max_correlated_margin {
+            let reason = format!(
+                "correlated margin {correlated_margin} plus {margin_usd} above {max_correlated_margin}"
+            );
+            info!("Signal filtered for {}, {reason}", signal.symbol);
+            return Err(reason);
+        }
+        Ok(())
+    }
+
+    /// Checks the market regime, volume and volatility filters of the strategy settings against
+    /// the market conditions at the time of the signal.
+    ///
+    /// Filters only gate the opening of new positions, closing positions on an opposite
+    /// signal is always allowed.
     ///
     /// # Arguments
     ///
-    /// * `strategy_id` - The unique identifier of the strategy whose settings are to be removed.
+    /// * `signal` - The trading signal being processed.
+    /// * `settings` - The trading settings for the strategy.
+    /// * `market` - A shared, thread-safe reference to the market data.
+    ///
+    /// # Returns
     ///
-    /// This is used when a strategy is no longer active or has been removed.
+    /// Returns `Ok` if every configured filter passes, or the reason the signal is filtered
+    /// otherwise.
+    async fn passes_filters(
+        signal: &SignalMessage,
+        settings: &StrategySettings,
+        market: Arc<Market>,
+    ) -> Result<(), String> {
+        if let Some(allowed_regimes) = &settings.allowed_regimes {
+            let regime = if signal.is_back_test {
+                market
+                    .regime_at(&signal.symbol, signal.interval, signal.timestamp)
+                    .await
+            } else {
+                market.regime(&signal.symbol, signal.interval).await
+            };
+            match regime {
+                Some(regime) if allowed_regimes.contains(&regime) => {}
+                _ => {
+                    let reason = format!("market regime {regime:?} not allowed");
+                    info!("Signal filtered for {}, {reason}", signal.symbol);
+                    return Err(reason);
+                }
+            }
+        }
 
-    pub fn remove_strategy_settings(&mut self, strategy_id: &StrategyId) {
-        self.active_strategy_settings.remove(&strategy_id);
+        if settings.min_volume_24h.is_none()
+            && settings.min_atr.is_none()
+            && settings.max_atr.is_none()
+            && settings.min_candle_body.is_none()
+        {
+            return Ok(());
+        }
+
+        let lookback = DAY_AS_MILI.max(signal.interval.to_millis() * (ATR_PERIOD as u64 + 1));
+        let from_ts = signal.timestamp.saturating_sub(lookback);
+
+        let klines = match market
+            .kline_data_range(
+                &signal.symbol,
+                signal.interval,
+                Some(from_ts),
+                Some(signal.timestamp),
+                None,
+            )
+            .await
+        {
+            Some(kline_data) => kline_data.klines(),
+            None => {
+                info!(
+                    "No kline data for {} {}, unable to apply signal filters",
+                    signal.symbol, signal.interval
+                );
+                return Err("no kline data to apply filters".to_string());
+            }
+        };
+
+        if let Some(min_volume_24h) = settings.min_volume_24h {
+            let day_from_ts = signal.timestamp.saturating_sub(DAY_AS_MILI);
+            let day_klines: Vec<_> = klines
+                .iter()
+                .filter(|k| k.open_time >= day_from_ts)
+                .cloned()
+                .collect();
+            let volume = calc_quote_volume(&day_klines);
+            if volume < min_volume_24h {
+                let reason = format!("24h volume {volume} below {min_volume_24h}");
+                info!("Signal filtered for {}, {reason}", signal.symbol);
+                return Err(reason);
+            }
+        }
+
+        if settings.min_atr.is_some() || settings.max_atr.is_some() {
+            let atr_pct = match (calc_atr(&klines, ATR_PERIOD), klines.last()) {
+                (Some(atr), Some(last)) if last.close > 0.0 => atr / last.close * 100.0,
+                _ => return Err("unable to calculate ATR".to_string()),
+            };
+            if let Some(min_atr) = settings.min_atr {
+                if atr_pct < min_atr {
+                    let reason = format!("ATR {atr_pct}% below {min_atr}%");
+                    info!("Signal filtered for {}, {reason}", signal.symbol);
+                    return Err(reason);
+                }
+            }
+            if let Some(max_atr) = settings.max_atr {
+                if atr_pct > max_atr {
+                    let reason = format!("ATR {atr_pct}% above {max_atr}%");
+                    info!("Signal filtered for {}, {reason}", signal.symbol);
+                    return Err(reason);
+                }
+            }
+        }
+
+        if let Some(min_candle_body) = settings.min_candle_body {
+            let body_pct = klines.last().map(calc_candle_body_pct).unwrap_or(0.0);
+            if body_pct < min_candle_body {
+                let reason = format!("candle body {body_pct}% below {min_candle_body}%");
+                info!("Signal filtered for {}, {reason}", signal.symbol);
+                return Err(reason);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// What the filled slices of an iceberg order do.
+enum IcebergTarget {
+    /// Each slice opens a position with its share of the margin.
+    Open { margin_usd: f64 },
+    /// Each slice closes its share of the positions.
+    Close { positions: Vec<Position> },
+}
+
+/// Cancels an iceberg order in the account once the task working it ends, e.g. when it is
+/// aborted by an opposite signal. Filled and expired orders keep their status.
+struct IcebergGuard {
+    order_id: Uuid,
+    account: ArcMutex<Account>,
+}
+
+impl Drop for IcebergGuard {
+    fn drop(&mut self) {
+        let (order_id, account) = (self.order_id, self.account.clone());
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            runtime.spawn(async move {
+                if let Some(order) = account.lock().await.iceberg_order_mut(&order_id) {
+                    order.finish(IcebergStatus::Cancelled);
+                }
+            });
+        }
+    }
+}
+
+/// Locks the margin reserved by an execution, which is never left half changed, so it stays
+/// usable after a panic.
+fn lock_margin(reserved_margin: &Mutex<f64>) -> MutexGuard<'_, f64> {
+    reserved_margin
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Releases the margin of a traded child order from the margin reserved by its execution.
+fn release_margin(reserved_margin: &Mutex<f64>, margin_usd: f64) {
+    let mut reserved_margin = lock_margin(reserved_margin);
+    *reserved_margin = (*reserved_margin - margin_usd).max(0.0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchange::{api::ExchangeApi, mock::MockExchangeApi};
+    use crate::market::{
+        interval::Interval, kline::Kline, messages::MarketMessage, ticker::Ticker,
+    };
+    use crate::storage::fs::FsStorage;
+    use crate::strategy::strategy::StrategyDirection;
+    use crate::utils::channel::{
+        build_arc_channel, OverflowPolicy, DEFAULT_MARKET_CHANNEL_CAPACITY,
+    };
+
+    async fn setup() -> (Arc<Market>, ArcMutex<Account>) {
+        let (_, market_rx) = build_arc_channel::<MarketMessage>(
+            DEFAULT_MARKET_CHANNEL_CAPACITY,
+            OverflowPolicy::DropOldest,
+        );
+        let exchange_api: Arc<Box<dyn ExchangeApi>> =
+            Arc::new(Box::new(MockExchangeApi::default()));
+        let storage_manager: Arc<Box<dyn StorageManager>> =
+            Arc::new(Box::new(FsStorage::default()));
+        let market = Market::new(
+            market_rx,
+            exchange_api.clone(),
+            storage_manager,
+            EventPublisher::new(),
+            false,
+        )
+        .await;
+        let account = Account::new(exchange_api, false, true).await;
+
+        (Arc::new(market), ArcMutex::new(account))
+    }
+
+    fn signal(strategy_id: StrategyId, order_side: OrderSide, confidence: f64) -> SignalMessage {
+        SignalMessage {
+            strategy_id,
+            order_side,
+            symbol: "BTCUSDT".to_string(),
+            interval: Interval::Min1,
+            price: 100.0,
+            confidence,
+            is_back_test: true,
+            timestamp: 0,
+            stop_loss: None,
+        }
+    }
+
+    /// Tests signals below the minimum confidence don't open positions, but still close the
+    /// positions of the opposite side.
+    #[tokio::test]
+    async fn test_min_confidence() {
+        let (market, account) = setup().await;
+        let strategy_id = Uuid::new_v4();
+        let mut signal_manager = SignalManager::new();
+        signal_manager.add_strategy_settings(
+            &strategy_id,
+            StrategySettings {
+                min_confidence: Some(0.6),
+                ..Default::default()
+            },
+        );
+
+        let handle = |order_side, confidence| {
+            signal_manager.handle_signal(
+                signal(strategy_id, order_side, confidence),
+                market.clone(),
+                account.clone(),
+            )
+        };
+
+        let action = handle(OrderSide::Buy, 0.5).await;
+        assert!(matches!(action, SignalAction::Ignored { .. }));
+        let action = handle(OrderSide::Buy, 0.8).await;
+        assert!(matches!(action, SignalAction::Opened { .. }));
+
+        let action = handle(OrderSide::Sell, 0.1).await;
+        assert!(matches!(action, SignalAction::Closed { .. }));
+        assert!(account
+            .lock()
+            .await
+            .strategy_positions(strategy_id)
+            .is_empty());
+    }
+
+    /// Tests long-only strategies ignore short entries but still close their longs.
+    #[tokio::test]
+    async fn test_direction() {
+        let (market, account) = setup().await;
+        let strategy_id = Uuid::new_v4();
+        let mut signal_manager = SignalManager::new();
+        signal_manager.add_strategy_settings(
+            &strategy_id,
+            StrategySettings {
+                direction: StrategyDirection::LongOnly,
+                ..Default::default()
+            },
+        );
+
+        let handle = |order_side| {
+            signal_manager.handle_signal(
+                signal(strategy_id, order_side, 1.0),
+                market.clone(),
+                account.clone(),
+            )
+        };
+
+        let action = handle(OrderSide::Sell).await;
+        assert!(matches!(action, SignalAction::Ignored { .. }));
+        let action = handle(OrderSide::Buy).await;
+        assert!(matches!(action, SignalAction::Opened { .. }));
+        let action = handle(OrderSide::Sell).await;
+        assert!(matches!(action, SignalAction::Closed { .. }));
+        assert!(account.lock().await.positions().next().is_none());
+    }
+
+    /// Tests the margin of a position is scaled by the confidence of its signal.
+    #[tokio::test]
+    async fn test_scale_by_confidence() {
+        let (market, account) = setup().await;
+        let strategy_id = Uuid::new_v4();
+        let mut signal_manager = SignalManager::new();
+        signal_manager.add_strategy_settings(
+            &strategy_id,
+            StrategySettings {
+                margin_usd: 200.0,
+                scale_by_confidence: true,
+                ..Default::default()
+            },
+        );
+
+        // signals scaling the margin to nothing don't open positions
+        for confidence in [0.0, f64::NAN] {
+            let action = signal_manager
+                .handle_signal(
+                    signal(strategy_id, OrderSide::Buy, confidence),
+                    market.clone(),
+                    account.clone(),
+                )
+                .await;
+            assert!(matches!(action, SignalAction::Ignored { .. }));
+        }
+
+        let action = signal_manager
+            .handle_signal(
+                signal(strategy_id, OrderSide::Buy, 0.25),
+                market,
+                account.clone(),
+            )
+            .await;
+        assert!(matches!(action, SignalAction::Opened { .. }));
+
+        let account = account.lock().await;
+        let positions = account.strategy_positions(strategy_id);
+        assert_eq!(positions[0].margin_usd, 50.0);
+    }
+
+    /// Tests signals are filtered without kline data and by the candle body of the last kline.
+    #[tokio::test]
+    async fn test_signal_filters() {
+        let (market, account) = setup().await;
+        let strategy_id = Uuid::new_v4();
+        let mut signal_manager = SignalManager::new();
+        signal_manager.add_strategy_settings(
+            &strategy_id,
+            StrategySettings {
+                min_candle_body: Some(1.0),
+                ..Default::default()
+            },
+        );
+
+        let now = generate_ts();
+        let mut buy = signal(strategy_id, OrderSide::Buy, 1.0);
+        buy.timestamp = now;
+
+        let action = signal_manager
+            .handle_signal(buy.clone(), market.clone(), account.clone())
+            .await;
+        assert!(matches!(action, SignalAction::Ignored { .. }));
+
+        let kline = |close: f64| Kline {
+            symbol: "BTCUSDT".to_string(),
+            interval: Interval::Min1,
+            open: 100.0,
+            high: 110.0,
+            low: 90.0,
+            close,
+            volume: 1.0,
+            open_time: now - 60_000,
+            close_time: now - 1,
+        };
+        let market_data = market.market_data().await;
+        market_data.write().await.update_kline(kline(100.5)).await;
+        let action = signal_manager
+            .handle_signal(buy.clone(), market.clone(), account.clone())
+            .await;
+        assert!(matches!(action, SignalAction::Ignored { .. }));
+
+        market_data.write().await.update_kline(kline(102.0)).await;
+        let action = signal_manager
+            .handle_signal(buy, market.clone(), account.clone())
+            .await;
+        assert!(matches!(action, SignalAction::Opened { .. }));
+    }
+
+    /// Tests the child orders of a split entry count against the max open orders, keep their
+    /// margin reserved and block further entries of the strategy while they are traded.
+    #[tokio::test]
+    async fn test_scheduled_entry_limits() {
+        let (market, account) = setup().await;
+        let (split_id, other_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut signal_manager = SignalManager::new();
+        signal_manager.set_capital(Some(100.0));
+        let settings = |max_open_orders: u32| StrategySettings {
+            margin_usd: 60.0,
+            max_open_orders,
+            execution: ExecutionStyle::Twap {
+                slices: 4,
+                duration_secs: 3600,
+            },
+            ..Default::default()
+        };
+        let live_buy = |strategy_id: StrategyId| SignalMessage {
+            is_back_test: false,
+            ..signal(strategy_id, OrderSide::Buy, 1.0)
+        };
+        let ticker = Ticker {
+            time: generate_ts(),
+            symbol: "BTCUSDT".to_string(),
+            high: 100.0,
+            low: 100.0,
+            traded_vol: 1.0,
+            last_price: 100.0,
+            open_price: 100.0,
+        };
+        market
+            .market_data()
+            .await
+            .write()
+            .await
+            .update_ticker(ticker)
+            .await;
+
+        signal_manager.add_strategy_settings(&split_id, settings(2));
+        let action = signal_manager
+            .handle_signal(live_buy(split_id), market.clone(), account.clone())
+            .await;
+        assert!(matches!(action, SignalAction::Ignored { .. }));
+
+        signal_manager.add_strategy_settings(&split_id, settings(4));
+        let action = signal_manager
+            .handle_signal(live_buy(split_id), market.clone(), account.clone())
+            .await;
+        assert_eq!(action, SignalAction::Scheduled { child_orders: 4 });
+        let action = signal_manager
+            .handle_signal(live_buy(split_id), market.clone(), account.clone())
+            .await;
+        assert!(matches!(action, SignalAction::Ignored { .. }));
+
+        // the margin of the remaining child orders isn't free for other strategies
+        signal_manager.add_strategy_settings(
+            &other_id,
+            StrategySettings {
+                margin_usd: 60.0,
+                ..Default::default()
+            },
+        );
+        let other_buy = signal(other_id, OrderSide::Buy, 1.0);
+        let action = signal_manager
+            .handle_signal(other_buy.clone(), market.clone(), account.clone())
+            .await;
+        assert!(matches!(action, SignalAction::Ignored { .. }));
+
+        signal_manager.remove_strategy_settings(&split_id);
+        let action = signal_manager
+            .handle_signal(other_buy, market, account)
+            .await;
+        assert!(matches!(action, SignalAction::Opened { .. }));
+    }
+
+    /// Tests positions a split exit can't close without a market price stay open until a price
+    /// is available, also after its last child order.
+    #[tokio::test]
+    async fn test_scheduled_exit_without_price() {
+        let (market, account) = setup().await;
+        let strategy_id = Uuid::new_v4();
+        let mut signal_manager = SignalManager::new();
+        signal_manager.add_strategy_settings(
+            &strategy_id,
+            StrategySettings {
+                max_open_orders: 2,
+                execution: ExecutionStyle::Twap {
+                    slices: 2,
+                    duration_secs: 1,
+                },
+                ..Default::default()
+            },
+        );
+        for _ in 0..2 {
+            let action = signal_manager
+                .handle_signal(
+                    signal(strategy_id, OrderSide::Buy, 1.0),
+                    market.clone(),
+                    account.clone(),
+                )
+                .await;
+            assert!(matches!(action, SignalAction::Opened { .. }));
+        }
+
+        let live_sell = SignalMessage {
+            is_back_test: false,
+            ..signal(strategy_id, OrderSide::Sell, 1.0)
+        };
+        let action = signal_manager
+            .handle_signal(live_sell, market.clone(), account.clone())
+            .await;
+        assert_eq!(action, SignalAction::Scheduled { child_orders: 2 });
+
+        // both child orders are due, but there is no market price to close at
+        tokio::time::sleep(Duration::from_millis(1200)).await;
+        assert_eq!(
+            account.lock().await.strategy_positions(strategy_id).len(),
+            2
+        );
+
+        let ticker = Ticker {
+            time: generate_ts(),
+            symbol: "BTCUSDT".to_string(),
+            high: 100.0,
+            low: 100.0,
+            traded_vol: 1.0,
+            last_price: 100.0,
+            open_price: 100.0,
+        };
+        market
+            .market_data()
+            .await
+            .write()
+            .await
+            .update_ticker(ticker)
+            .await;
+        tokio::time::sleep(EXIT_RETRY_INTERVAL + Duration::from_millis(500)).await;
+
+        let account = account.lock().await;
+        assert!(account.strategy_positions(strategy_id).is_empty());
+        assert_eq!(account.strategy_trades(strategy_id).len(), 2);
+    }
+
+    /// Tests live accounts ignore iceberg entries and close positions at once on iceberg exits,
+    /// as the slices would be filled at the market price.
+    #[tokio::test]
+    async fn test_live_iceberg() {
+        let (market, _) = setup().await;
+        let exchange_api: Arc<Box<dyn ExchangeApi>> =
+            Arc::new(Box::new(MockExchangeApi::default()));
+        let account = ArcMutex::new(Account::new(exchange_api, false, false).await);
+        let strategy_id = Uuid::new_v4();
+        let mut signal_manager = SignalManager::new();
+        signal_manager.add_strategy_settings(
+            &strategy_id,
+            StrategySettings {
+                execution: ExecutionStyle::Iceberg {
+                    slices: 1,
+                    offset_pct: 0.1,
+                    timeout_secs: 60,
+                },
+                ..Default::default()
+            },
+        );
+        let ticker = Ticker {
+            time: generate_ts(),
+            symbol: "BTCUSDT".to_string(),
+            high: 100.0,
+            low: 100.0,
+            traded_vol: 1.0,
+            last_price: 100.0,
+            open_price: 100.0,
+        };
+        market
+            .market_data()
+            .await
+            .write()
+            .await
+            .update_ticker(ticker)
+            .await;
+        let live = |order_side: OrderSide| SignalMessage {
+            is_back_test: false,
+            ..signal(strategy_id, order_side, 1.0)
+        };
+
+        let action = signal_manager
+            .handle_signal(live(OrderSide::Buy), market.clone(), account.clone())
+            .await;
+        assert!(matches!(action, SignalAction::Ignored { .. }));
+        assert!(account.lock().await.iceberg_orders().is_empty());
+
+        let action = signal_manager
+            .handle_signal(
+                signal(strategy_id, OrderSide::Buy, 1.0),
+                market.clone(),
+                account.clone(),
+            )
+            .await;
+        assert!(matches!(action, SignalAction::Opened { .. }));
+        let action = signal_manager
+            .handle_signal(live(OrderSide::Sell), market, account.clone())
+            .await;
+        assert!(matches!(action, SignalAction::Closed { .. }));
+    }
+
+    /// Tests positions open longer than the max position duration of their strategy are closed
+    /// at the last price, and positions of strategies without one are kept.
+    #[tokio::test]
+    async fn test_close_expired_positions() {
+        let (market, account) = setup().await;
+        let (expiring_id, holding_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut signal_manager = SignalManager::new();
+        signal_manager.add_strategy_settings(
+            &expiring_id,
+            StrategySettings {
+                max_position_duration: Some(0),
+                ..Default::default()
+            },
+        );
+        signal_manager.add_strategy_settings(&holding_id, StrategySettings::default());
+
+        for strategy_id in [expiring_id, holding_id] {
+            let action = signal_manager
+                .handle_signal(
+                    signal(strategy_id, OrderSide::Buy, 1.0),
+                    market.clone(),
+                    account.clone(),
+                )
+                .await;
+            assert!(matches!(action, SignalAction::Opened { .. }));
+        }
+
+        let ticker = Ticker {
+            time: generate_ts(),
+            symbol: "BTCUSDT".to_string(),
+            high: 111.0,
+            low: 99.0,
+            traded_vol: 1.0,
+            last_price: 110.0,
+            open_price: 100.0,
+        };
+        market
+            .market_data()
+            .await
+            .write()
+            .await
+            .update_ticker(ticker)
+            .await;
+        signal_manager
+            .close_expired_positions(market, account.clone())
+            .await;
+
+        let account = account.lock().await;
+        assert!(account.strategy_positions(expiring_id).is_empty());
+        assert_eq!(account.strategy_positions(holding_id).len(), 1);
+        assert_eq!(account.strategy_trades(expiring_id).len(), 1);
     }
 }
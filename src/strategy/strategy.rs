@@ -1,5 +1,10 @@
+use std::collections::HashMap;
+use std::panic::AssertUnwindSafe;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
+use futures_util::FutureExt;
+
 use log::info;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
@@ -12,12 +17,22 @@ use crate::{
         account::Account,
         trade::{OrderSide, Position, TradeTx},
     },
+    health,
     market::{
+        interval::Interval,
         kline::{self, Kline},
         market::Market,
+        regime::MarketRegime,
+        symbol::canonical_symbol,
         types::{ArcMutex, ArcSender},
     },
-    strategy::algorithm::{Algorithm, AlgorithmBuilder},
+    strategy::{
+        algorithm::{Algorithm, AlgorithmBuilder},
+        data_points::{retention_for_period, AlgorithmDataPointManager},
+        execution::ExecutionStyle,
+        metrics::{calc_trade_returns, Benchmark, EquityPoint, PerformanceMetrics},
+        supervisor::panic_message,
+    },
     utils::time::{floor_mili_ts, generate_ts, timestamp_to_string, MIN_AS_MILI, SEC_AS_MILI},
 };
 
@@ -35,15 +50,18 @@ pub struct Strategy {
     pub id: StrategyId,
     pub symbol: String,
     pub name: String,
-    interval: String,
-    market: ArcMutex<Market>,
+    interval: Interval,
+    market: Arc<Market>,
     strategy_tx: ArcSender<SignalMessage>,
     pub algorithm: ArcMutex<Box<dyn Algorithm>>,
+    data_points: ArcMutex<AlgorithmDataPointManager>,
     settings: StrategySettings,
     start_time: Option<String>,
     end_time: Option<String>,
     kline_manager: ArcMutex<StrategyKlineManager>,
     running: bool,
+    /// Why the task of the strategy last ended, if its algorithm panicked.
+    failure: Arc<Mutex<Option<String>>>,
 }
 
 impl Strategy {
@@ -66,32 +84,71 @@ impl Strategy {
     pub fn new(
         strategy_name: &str,
         symbol: &str,
-        interval: &str,
+        interval: Interval,
         strategy_tx: ArcSender<SignalMessage>,
-        market: ArcMutex<Market>,
+        market: Arc<Market>,
         settings: StrategySettings,
         algorithm_params: Value,
     ) -> Result<Self, AlgorithmError> {
         let algorithm =
             AlgorithmBuilder::build_algorithm(strategy_name, interval, algorithm_params)?;
+        let data_points =
+            AlgorithmDataPointManager::new(retention_for_period(algorithm.max_period()));
 
         Ok(Self {
             id: Uuid::new_v4(),
             name: strategy_name.to_string(),
             market,
-            interval: interval.to_string(),
+            interval,
             symbol: symbol.to_string(),
             strategy_tx,
             algorithm: ArcMutex::new(algorithm),
+            data_points: ArcMutex::new(data_points),
             settings,
             start_time: None,
             end_time: None,
             kline_manager: ArcMutex::new(StrategyKlineManager::new()),
             running: false,
+            failure: Arc::new(Mutex::new(None)),
         })
     }
 
-    /// Starts the execution of the strategy in an asynchronous task.
+    /// Rebuilds a trading strategy from a previously persisted `StrategyInfo`.
+    ///
+    /// The rebuilt strategy keeps the original strategy ID and start time so that positions and
+    /// trades opened before a restart remain associated with it.
+    ///
+    /// # Arguments
+    ///
+    /// * `info` - The persisted information of the strategy.
+    /// * `strategy_tx` - A channel for sending signal messages generated by the strategy.
+    /// * `market` - Shared access to market data.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the rebuilt `Strategy` instance or an `AlgorithmError` if an error occurs.
+    pub fn from_info(
+        info: StrategyInfo,
+        strategy_tx: ArcSender<SignalMessage>,
+        market: Arc<Market>,
+    ) -> Result<Self, AlgorithmError> {
+        let mut strategy = Self::new(
+            &info.name,
+            &info.symbol,
+            info.interval,
+            strategy_tx,
+            market,
+            info.settings,
+            info.params,
+        )?;
+        strategy.id = info.id;
+        strategy.start_time = info.start_time;
+
+        Ok(strategy)
+    }
+
+    /// Starts the execution of the strategy in an asynchronous task. Strategies restarted after a
+    /// failure continue with the state of their algorithm and data points.
     ///
     /// # Returns
     ///
@@ -99,20 +156,28 @@ impl Strategy {
 
     pub async fn start(&mut self) -> JoinHandle<()> {
         self.running = true;
-        self.start_time = Some(timestamp_to_string(generate_ts()));
+        // resumed strategies keep the time they were first started
+        if self.start_time.is_none() {
+            self.start_time = Some(timestamp_to_string(generate_ts()));
+        }
         // let market = self.market.clone();
         let strategy_tx = self.strategy_tx.clone();
 
         let id = self.id.clone();
         let symbol = self.symbol.clone();
         let algorithm = self.algorithm.clone();
-        let interval_str = self.interval.clone();
+        let data_points = self.data_points.clone();
+        let interval = self.interval;
         let interval_duration = algorithm.lock().await.interval();
 
         let market = self.market.clone();
         let kline_manager = self.kline_manager.clone();
+        let task = format!("strategy:{id}");
+        health::runtime().register_task(&task, Some(interval_duration));
+        let failure = self.failure.clone();
+        *failure.lock().unwrap_or_else(|e| e.into_inner()) = None;
 
-        tokio::spawn(async move {
+        let run = async move {
             // let market = market.clone();
             // wait until last 5 seconds of minute, to ensure getting latest kline
             // data from market, ie. each request for fresh kline will
@@ -133,10 +198,11 @@ impl Strategy {
                 // wait for duration of strategy interval first,
                 // to ensure at least one kline of data is populated in the market
                 time::sleep(interval_duration).await;
+                let _iteration = health::time_iteration(&task);
 
                 // perform some house keeping with klines before evaluating the data
                 // check kline is fresh otherwise continue to next interval
-                if let Some(kline) = market.lock().await.last_kline(&symbol, &interval_str).await {
+                if let Some(kline) = market.last_kline(&symbol, interval).await {
                     if kline_manager.lock().await.must_continue(kline) {
                         continue;
                     }
@@ -147,13 +213,20 @@ impl Strategy {
                 // ---
                 // let market = market.clone();
 
-                if let Some(kline) = market.lock().await.last_kline(&symbol, &interval_str).await {
-                    let order_side = algorithm.lock().await.evaluate(kline.clone());
+                if let Some(kline) = market.last_kline(&symbol, interval).await {
+                    let external = market.external_values(kline.close_time).await;
+                    let (eval_result, stop_loss) = {
+                        let mut data_points = data_points.lock().await;
+                        data_points.set_external(external);
+                        data_points.push(kline.clone());
+                        let mut algorithm = algorithm.lock().await;
+                        let eval_result = algorithm.evaluate(kline.clone(), &data_points);
+                        (eval_result, algorithm.stop_loss())
+                    };
 
-                    let order_side = match order_side {
-                        AlgorithmEvalResult::Buy => OrderSide::Buy,
-                        AlgorithmEvalResult::Sell => OrderSide::Sell,
-                        AlgorithmEvalResult::Ignore => {
+                    let (order_side, confidence) = match eval_result.order_side() {
+                        Some(order_side) => order_side,
+                        None => {
                             continue;
                         }
                     };
@@ -162,26 +235,46 @@ impl Strategy {
                         strategy_id: id,
                         order_side,
                         symbol: symbol.clone(),
+                        interval,
                         price: kline.close,
+                        confidence,
                         is_back_test: false,
                         timestamp: kline.close_time,
+                        stop_loss,
                     };
 
                     if strategy_tx.is_closed() {
                         break;
                     }
 
-                    // send signal back to bot
-                    if let Err(e) = strategy_tx.send(signal) {
+                    // send signal back to bot, waiting for room if the bot is behind
+                    if let Err(e) = strategy_tx.send(signal).await {
                         log::warn!("Unable to send signal back to RaderBot, {e}")
                     }
                 } else {
                     continue;
                 };
             }
+        };
+
+        tokio::spawn(async move {
+            // the task ends either way, the panic is kept for the supervisor to report
+            if let Err(panic) = AssertUnwindSafe(run).catch_unwind().await {
+                let message = panic_message(panic.as_ref());
+                log::error!("Strategy {id} panicked, e: {message}");
+                *failure.lock().unwrap_or_else(|e| e.into_inner()) = Some(message);
+            }
         })
     }
 
+    /// Why the task of the strategy ended, if its algorithm panicked, see `start`.
+    pub fn failure(&self) -> Option<String> {
+        self.failure
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
     /// Stops the execution of the strategy and optionally closes all open positions associated with it.
     ///
     /// # Arguments
@@ -198,6 +291,8 @@ impl Strategy {
         account: ArcMutex<Account>,
         close_positions: bool,
     ) -> StrategySummary {
+        health::runtime().remove_task(&format!("strategy:{}", self.id));
+
         let account = account.clone();
         // Get all positions associated with the strategy
         let positions: Vec<Position> = account
@@ -211,18 +306,11 @@ impl Strategy {
         // Close all positions on account attached to this strategy
         if close_positions {
             for position in positions {
-                if let Some(close_price) = self
-                    .market
-                    .clone()
-                    .lock()
-                    .await
-                    .last_price(&position.symbol)
-                    .await
-                {
+                if let Some(close_price) = self.market.last_price(&position.symbol).await {
                     account
                         .lock()
                         .await
-                        .close_position(position.id, close_price)
+                        .close_position(position.id, close_price.into())
                         .await;
                 }
             }
@@ -292,7 +380,101 @@ impl Strategy {
     /// A result indicating success or containing an `AlgorithmError`.
 
     pub async fn set_algorithm_params(&self, params: Value) -> Result<(), AlgorithmError> {
-        self.algorithm.lock().await.set_params(params)
+        let mut data_points = self.data_points.lock().await;
+        let mut algorithm = self.algorithm.lock().await;
+        algorithm.set_params(params)?;
+
+        data_points.set_retention(retention_for_period(algorithm.max_period()));
+        algorithm.warm_up(&data_points);
+
+        Ok(())
+    }
+
+    /// Evaluates a k-line with the strategy's algorithm, retaining it as a historical data point
+    /// along with the values external series had when it closed.
+    ///
+    /// # Arguments
+    ///
+    /// * `kline` - The k-line to evaluate.
+    ///
+    /// # Returns
+    ///
+    /// The `AlgorithmEvalResult` generated by the algorithm.
+    pub async fn evaluate(&self, kline: Kline) -> AlgorithmEvalResult {
+        let external = self.market.external_values(kline.close_time).await;
+        self.evaluate_with_external(kline, external).await
+    }
+
+    /// Evaluates a k-line, see `evaluate`, with the given values of the external series
+    /// instead of the values retained by the market, e.g. values a back test loaded from
+    /// storage.
+    pub async fn evaluate_with_external(
+        &self,
+        kline: Kline,
+        external: HashMap<String, f64>,
+    ) -> AlgorithmEvalResult {
+        let mut data_points = self.data_points.lock().await;
+        data_points.set_external(external);
+        data_points.push(kline.clone());
+
+        self.algorithm.lock().await.evaluate(kline, &data_points)
+    }
+
+    /// Retrieves the value of an external series the latest k-line was evaluated with.
+    #[cfg(test)]
+    pub async fn external_value(&self, series: &str) -> Option<f64> {
+        self.data_points.lock().await.external(series)
+    }
+
+    /// Warms the algorithm up with the most recent stored k-lines closed before now, so a
+    /// strategy resumed after a restart evaluates its next k-line with its history rather than
+    /// from scratch. Signals of the warm-up k-lines are discarded.
+    ///
+    /// # Returns
+    ///
+    /// The number of k-lines evaluated.
+    pub async fn warm_up(&self) -> usize {
+        let retention = self.data_points.lock().await.retention();
+        let now = generate_ts();
+        let klines = self
+            .market
+            .kline_data_range(
+                &self.symbol,
+                self.interval,
+                None,
+                Some(now),
+                Some(retention),
+            )
+            .await
+            .map(|kline_data| kline_data.klines())
+            .unwrap_or_default();
+
+        let klines: Vec<Kline> = klines
+            .into_iter()
+            .filter(|kline| kline.close_time < now)
+            .collect();
+
+        self.warm_up_with(&klines).await
+    }
+
+    /// Warms the algorithm up with the most recent of the given k-lines, e.g. the k-lines
+    /// preceding a backtested range. Signals of the warm-up k-lines are discarded.
+    ///
+    /// # Arguments
+    ///
+    /// * `klines` - The k-lines to warm up with, sorted by open time.
+    ///
+    /// # Returns
+    ///
+    /// The number of k-lines evaluated, at most the retention of the data points.
+    pub async fn warm_up_with(&self, klines: &[Kline]) -> usize {
+        let retention = self.data_points.lock().await.retention();
+        let klines = &klines[klines.len().saturating_sub(retention)..];
+        for kline in klines {
+            self.evaluate(kline.clone()).await;
+        }
+
+        klines.len()
     }
 
     /// Provides information about the strategy including its identifier, name, and configuration.
@@ -308,7 +490,7 @@ impl Strategy {
             settings: self.settings.clone(),
             params: self.algorithm.lock().await.get_params().clone(),
             symbol: self.symbol.clone(),
-            interval: self.interval.clone(),
+            interval: self.interval,
             running: self.running,
             start_time: self.start_time.clone(),
             end_time: self.end_time.clone(),
@@ -344,6 +526,8 @@ impl Strategy {
         let long_trade_count = Strategy::calc_trade_count(&trades, OrderSide::Buy);
         let short_trade_count = Strategy::calc_trade_count(&trades, OrderSide::Sell);
         let profit: f64 = Strategy::calc_profit(&trades);
+        // live strategies don't track equity per period, so ratios use per-trade returns
+        let metrics = PerformanceMetrics::new(trades, &calc_trade_returns(trades), 1.0);
 
         let start_price = match self
             .kline_manager
@@ -379,6 +563,10 @@ impl Strategy {
             period_start_price: start_price,
             max_drawdown,
             max_profit,
+            funding_paid: Strategy::calc_funding_paid(trades, positions),
+            metrics,
+            equity_curve: vec![],
+            benchmark: None,
         }
     }
 
@@ -481,6 +669,23 @@ impl Strategy {
     pub fn calc_profit(trades: &Vec<TradeTx>) -> f64 {
         trades.iter().map(|trade| trade.calc_profit()).sum()
     }
+
+    /// Computes the total funding paid by closed trades and open positions, negative when
+    /// funding was received.
+    ///
+    /// # Arguments
+    ///
+    /// * `trades` - The executed trades.
+    /// * `positions` - The open positions.
+    ///
+    /// # Returns
+    ///
+    /// Returns the total funding in USD.
+    pub fn calc_funding_paid(trades: &[TradeTx], positions: &[Position]) -> f64 {
+        let trades_funding: f64 = trades.iter().map(|trade| trade.position.funding_paid).sum();
+        let positions_funding: f64 = positions.iter().map(|position| position.funding_paid).sum();
+        trades_funding + positions_funding
+    }
 }
 
 /// Contains information about a trading strategy including its configuration and state.
@@ -494,7 +699,7 @@ pub struct StrategyInfo {
     pub id: StrategyId,
     pub name: String,
     pub symbol: String,
-    pub interval: String,
+    pub interval: Interval,
     pub settings: StrategySettings,
     pub params: Value,
     pub running: bool,
@@ -513,7 +718,7 @@ impl Default for StrategyInfo {
             id: Uuid::new_v4(),
             name: "".to_string(),
             symbol: "".to_string(),
-            interval: "".to_string(),
+            interval: Interval::default(),
             settings: StrategySettings::default(),
             params: json!({}),
             start_time: None,
@@ -523,17 +728,86 @@ impl Default for StrategyInfo {
     }
 }
 
+/// Records a change of the algorithm parameters of a trading strategy.
+///
+/// Keeps the parameters in effect before and after the change, when it happened and what
+/// triggered it, so that strategy performance can be attributed to a given parameter set.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StrategyParamsChange {
+    pub strategy_id: StrategyId,
+    pub timestamp: u64,
+    pub old_params: Value,
+    pub new_params: Value,
+    pub changed_by: String,
+}
+
 /// Configuration settings for a trading strategy.
 ///
 /// This struct defines essential settings that control the execution of a trading strategy,
 /// including the maximum number of open orders, margin usage, leverage, and an optional stop loss.
-
+///
+/// The optional market filters are checked by the `SignalManager` before opening new positions:
+/// `min_volume_24h` is the minimum quote volume traded over the last 24 hours, `min_atr` and
+/// `max_atr` bound the ATR as a percentage of the last close price, and `min_candle_body` is the
+/// minimum body of the signal candle as a percentage of its open price.
+///
+/// Signals with a confidence below `min_confidence` are ignored, and when `scale_by_confidence`
+/// is enabled the margin of new positions is scaled by the signal confidence.
+///
+/// The `direction` restricts which side new positions can be opened on, signals in a disallowed
+/// direction only close existing positions.
+///
+/// Positions open for longer than `max_position_duration` (in seconds) are closed regardless of signals.
+///
+/// When `allowed_regimes` is set, new positions are only opened while the market regime of the
+/// signal's symbol and interval is one of the allowed regimes. Live signals read the regime kept
+/// up to date by the market, backtests classify the k-lines closed up to the signal.
+///
+/// Strategies in `shadow` mode trade on a dedicated mock account instead of the live account.
+///
+/// `stop_loss` and `take_profit` are distances from the open price, as percentages, at which new
+/// positions exit. A stop loss given by the signal takes precedence over `stop_loss`.
+///
+/// The `execution` splits the orders of live signals into child orders traded over time, see
+/// `ExecutionStyle`.
+///
+/// `max_correlated_margin` caps the margin of the open positions whose returns move with a new
+/// live position, including the new one, see the `correlation` module.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct StrategySettings {
     pub max_open_orders: u32,
     pub margin_usd: f64,
     pub leverage: u32,
     pub stop_loss: Option<f64>,
+    #[serde(default)]
+    pub take_profit: Option<f64>,
+    #[serde(default)]
+    pub min_volume_24h: Option<f64>,
+    #[serde(default)]
+    pub min_atr: Option<f64>,
+    #[serde(default)]
+    pub max_atr: Option<f64>,
+    #[serde(default)]
+    pub min_candle_body: Option<f64>,
+    #[serde(default)]
+    pub min_confidence: Option<f64>,
+    #[serde(default)]
+    pub scale_by_confidence: bool,
+    #[serde(default)]
+    pub direction: StrategyDirection,
+    #[serde(default)]
+    pub max_position_duration: Option<u64>,
+    #[serde(default)]
+    pub shadow: bool,
+    #[serde(default)]
+    pub allowed_regimes: Option<Vec<MarketRegime>>,
+    /// The credential profile whose account the strategy trades on, the live account if unset.
+    #[serde(default)]
+    pub profile: Option<String>,
+    #[serde(default)]
+    pub execution: ExecutionStyle,
+    #[serde(default)]
+    pub max_correlated_margin: Option<f64>,
 }
 
 /// Provides default values for `StrategySettings`.
@@ -548,6 +822,181 @@ impl Default for StrategySettings {
             margin_usd: 100.0,
             leverage: 1,
             stop_loss: None,
+            take_profit: None,
+            min_volume_24h: None,
+            min_atr: None,
+            max_atr: None,
+            min_candle_body: None,
+            min_confidence: None,
+            scale_by_confidence: false,
+            direction: StrategyDirection::Both,
+            max_position_duration: None,
+            shadow: false,
+            allowed_regimes: None,
+            profile: None,
+            execution: ExecutionStyle::Market,
+            max_correlated_margin: None,
+        }
+    }
+}
+
+impl StrategySettings {
+    /// Calculates the stop loss and take profit prices of a new position from the percentages
+    /// of the settings.
+    ///
+    /// # Arguments
+    ///
+    /// * `order_side` - The side of the new position.
+    /// * `open_price` - The price the position opens at.
+    ///
+    /// # Returns
+    ///
+    /// The stop loss and take profit prices, `None` where the settings have no percentage.
+    pub fn exit_prices(
+        &self,
+        order_side: &OrderSide,
+        open_price: f64,
+    ) -> (Option<f64>, Option<f64>) {
+        // longs exit below the open price at a loss and above it at a profit, shorts the opposite
+        let direction = match order_side {
+            OrderSide::Buy => 1.0,
+            OrderSide::Sell => -1.0,
+        };
+        let stop_loss = self
+            .stop_loss
+            .map(|pct| open_price * (1.0 - direction * pct / 100.0));
+        let take_profit = self
+            .take_profit
+            .map(|pct| open_price * (1.0 + direction * pct / 100.0));
+
+        (stop_loss, take_profit)
+    }
+}
+
+/// How the margin of new positions is sized.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SizingMode {
+    /// Every position uses `margin_usd`.
+    Fixed,
+    /// `margin_usd` is scaled by the confidence of the signal.
+    Confidence,
+}
+
+/// Changes to the risk and execution settings of a running strategy, omitted fields keep their
+/// value.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct StrategySettingsUpdate {
+    pub margin_usd: Option<f64>,
+    pub leverage: Option<u32>,
+    pub max_open_orders: Option<u32>,
+    /// Stop loss distance from the open price, as a percentage.
+    pub stop_loss: Option<f64>,
+    /// Take profit distance from the open price, as a percentage.
+    pub take_profit: Option<f64>,
+    pub sizing_mode: Option<SizingMode>,
+    pub execution: Option<ExecutionStyle>,
+    pub max_correlated_margin: Option<f64>,
+}
+
+impl StrategySettingsUpdate {
+    /// Applies the changes to settings.
+    ///
+    /// # Returns
+    ///
+    /// The updated settings, or an error if a changed value is out of range.
+    pub fn apply(&self, settings: &StrategySettings) -> Result<StrategySettings, String> {
+        if self.margin_usd.is_some_and(|margin_usd| margin_usd <= 0.0) {
+            return Err("margin_usd must be positive".to_string());
+        }
+        if self.leverage == Some(0) {
+            return Err("leverage must be at least 1".to_string());
+        }
+        if self.max_open_orders == Some(0) {
+            return Err("max_open_orders must be at least 1".to_string());
+        }
+        if self.stop_loss.is_some_and(|pct| pct <= 0.0 || pct >= 100.0) {
+            return Err("stop_loss must be a percentage between 0 and 100".to_string());
+        }
+        if self.take_profit.is_some_and(|pct| pct <= 0.0) {
+            return Err("take_profit must be a positive percentage".to_string());
+        }
+        if let Some(execution) = &self.execution {
+            execution.validate()?;
+        }
+        if self
+            .max_correlated_margin
+            .is_some_and(|margin| margin <= 0.0)
+        {
+            return Err("max_correlated_margin must be positive".to_string());
+        }
+
+        let mut settings = settings.clone();
+        if let Some(margin_usd) = self.margin_usd {
+            settings.margin_usd = margin_usd;
+        }
+        if let Some(leverage) = self.leverage {
+            settings.leverage = leverage;
+        }
+        if let Some(max_open_orders) = self.max_open_orders {
+            settings.max_open_orders = max_open_orders;
+        }
+        if self.stop_loss.is_some() {
+            settings.stop_loss = self.stop_loss;
+        }
+        if self.take_profit.is_some() {
+            settings.take_profit = self.take_profit;
+        }
+        if let Some(sizing_mode) = self.sizing_mode {
+            settings.scale_by_confidence = sizing_mode == SizingMode::Confidence;
+        }
+        if let Some(execution) = self.execution {
+            settings.execution = execution;
+        }
+        if self.max_correlated_margin.is_some() {
+            settings.max_correlated_margin = self.max_correlated_margin;
+        }
+
+        Ok(settings)
+    }
+}
+
+/// A strategy to start, as given when deploying several strategies at once.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StrategyDefinition {
+    pub strategy_name: String,
+    #[serde(deserialize_with = "canonical_symbol")]
+    pub symbol: String,
+    pub interval: Interval,
+    #[serde(default)]
+    pub settings: StrategySettings,
+    pub algorithm_params: Value,
+}
+
+/// Restricts the side on which a trading strategy is allowed to open positions.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StrategyDirection {
+    #[default]
+    Both,
+    LongOnly,
+    ShortOnly,
+}
+
+impl StrategyDirection {
+    /// Checks whether positions can be opened on the given order side.
+    ///
+    /// # Arguments
+    ///
+    /// * `order_side` - The side of the position to open.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the direction allows opening positions on the given side.
+    pub fn allows(&self, order_side: &OrderSide) -> bool {
+        match self {
+            StrategyDirection::Both => true,
+            StrategyDirection::LongOnly => *order_side == OrderSide::Buy,
+            StrategyDirection::ShortOnly => *order_side == OrderSide::Sell,
         }
     }
 }
@@ -556,8 +1005,7 @@ impl Default for StrategySettings {
 ///
 /// Includes details about performance, such as profit, trades, positions, trade counts, and price
 /// information at the start and end of execution. Also covers maximum profit and drawdown
-/// experienced.
-
+/// experienced, the risk adjusted `metrics` and, for back tests, the `equity_curve`.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct StrategySummary {
     pub info: StrategyInfo,
@@ -574,6 +1022,14 @@ pub struct StrategySummary {
     pub symbol: String,
     pub max_drawdown: f64,
     pub max_profit: f64,
+    #[serde(default)]
+    pub funding_paid: f64,
+    #[serde(default)]
+    pub metrics: PerformanceMetrics,
+    #[serde(default)]
+    pub equity_curve: Vec<EquityPoint>,
+    #[serde(default)]
+    pub benchmark: Option<Benchmark>,
 }
 
 /// Sets default values for `StrategySummary`.
@@ -595,6 +1051,10 @@ impl Default for StrategySummary {
             symbol: "".to_string(),
             max_drawdown: 0.0,
             max_profit: 0.0,
+            funding_paid: 0.0,
+            metrics: PerformanceMetrics::default(),
+            equity_curve: vec![],
+            benchmark: None,
         }
     }
 }
@@ -650,3 +1110,136 @@ impl StrategyKlineManager {
         must_continue
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        events::EventPublisher,
+        exchange::{api::ExchangeApi, mock::MockExchangeApi},
+        market::messages::MarketMessage,
+        storage::{fs::FsStorage, manager::StorageManager},
+        utils::channel::{build_arc_channel, OverflowPolicy, DEFAULT_MARKET_CHANNEL_CAPACITY},
+    };
+
+    /// Tests settings updates keep omitted fields, reject invalid values and set exit prices.
+    #[test]
+    fn test_strategy_settings_update() {
+        let settings = StrategySettings::default();
+        let update = StrategySettingsUpdate {
+            margin_usd: Some(250.0),
+            stop_loss: Some(2.0),
+            take_profit: Some(5.0),
+            sizing_mode: Some(SizingMode::Confidence),
+            ..Default::default()
+        };
+
+        let updated = update.apply(&settings).unwrap();
+        assert_eq!(updated.margin_usd, 250.0);
+        assert_eq!(updated.leverage, settings.leverage);
+        assert_eq!(updated.max_open_orders, settings.max_open_orders);
+        assert!(updated.scale_by_confidence);
+
+        let (stop_loss, take_profit) = updated.exit_prices(&OrderSide::Buy, 100.0);
+        assert_eq!(stop_loss, Some(98.0));
+        assert_eq!(take_profit, Some(105.0));
+        let (stop_loss, take_profit) = updated.exit_prices(&OrderSide::Sell, 100.0);
+        assert_eq!(stop_loss, Some(102.0));
+        assert_eq!(take_profit, Some(95.0));
+        assert_eq!(settings.exit_prices(&OrderSide::Buy, 100.0), (None, None));
+
+        let invalid = StrategySettingsUpdate {
+            leverage: Some(0),
+            ..Default::default()
+        };
+        assert!(invalid.apply(&settings).is_err());
+
+        let twap = ExecutionStyle::Twap {
+            slices: 4,
+            duration_secs: 600,
+        };
+        let update = StrategySettingsUpdate {
+            execution: Some(twap),
+            ..Default::default()
+        };
+        assert_eq!(update.apply(&settings).unwrap().execution, twap);
+        let invalid = StrategySettingsUpdate {
+            execution: Some(ExecutionStyle::Vwap {
+                slices: 4,
+                duration_secs: 0,
+            }),
+            ..Default::default()
+        };
+        assert!(invalid.apply(&settings).is_err());
+
+        let update = StrategySettingsUpdate {
+            max_correlated_margin: Some(5000.0),
+            ..Default::default()
+        };
+        let updated = update.apply(&settings).unwrap();
+        assert_eq!(updated.max_correlated_margin, Some(5000.0));
+        let invalid = StrategySettingsUpdate {
+            max_correlated_margin: Some(0.0),
+            ..Default::default()
+        };
+        assert!(invalid.apply(&settings).is_err());
+    }
+
+    /// Tests new parameters are warmed up with the retained k-lines, so the algorithm signals
+    /// on the next k-line instead of waiting for a full period again.
+    #[tokio::test]
+    async fn test_set_algorithm_params_warm_up() {
+        let (_, market_rx) = build_arc_channel::<MarketMessage>(
+            DEFAULT_MARKET_CHANNEL_CAPACITY,
+            OverflowPolicy::DropOldest,
+        );
+        let exchange_api: Arc<Box<dyn ExchangeApi>> =
+            Arc::new(Box::new(MockExchangeApi::default()));
+        let storage_manager: Arc<Box<dyn StorageManager>> =
+            Arc::new(Box::new(FsStorage::default()));
+        let market = Market::new(
+            market_rx,
+            exchange_api,
+            storage_manager,
+            EventPublisher::new(),
+            false,
+        )
+        .await;
+        let (strategy_tx, _) = build_arc_channel::<SignalMessage>(16, OverflowPolicy::DropOldest);
+        let strategy = Strategy::new(
+            "Rsi",
+            "BTCUSDT",
+            Interval::Min1,
+            strategy_tx,
+            Arc::new(market),
+            StrategySettings::default(),
+            json!({"rsi_period": 14}),
+        )
+        .unwrap();
+
+        // rising prices, overbought once the RSI is warmed up
+        let kline = |i: u64| Kline {
+            symbol: "BTCUSDT".to_string(),
+            interval: Interval::Min1,
+            close: 100.0 + i as f64,
+            open_time: i * MIN_AS_MILI,
+            close_time: (i + 1) * MIN_AS_MILI - 1,
+            ..Default::default()
+        };
+        for i in 0..10 {
+            assert!(matches!(
+                strategy.evaluate(kline(i)).await,
+                AlgorithmEvalResult::Ignore
+            ));
+        }
+
+        strategy
+            .set_algorithm_params(json!({"rsi_period": 5}))
+            .await
+            .unwrap();
+        assert!(matches!(
+            strategy.evaluate(kline(10)).await,
+            AlgorithmEvalResult::Short(_)
+        ));
+    }
+}
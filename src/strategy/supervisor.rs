@@ -0,0 +1,321 @@
+//! # Strategy Supervisor
+//!
+//! The task of a strategy ends when its algorithm panics, and the strategy would silently stop
+//! trading. The supervisor detects ended strategy tasks, publishes an error event and restarts
+//! them with the state they had, their algorithm and data points, after a backoff doubling with
+//! every restart. A strategy failing more than `STRATEGY_MAX_RESTARTS` times in a row is stopped,
+//! keeping its positions open.
+
+use std::any::Any;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::task::JoinHandle;
+
+use crate::bot::RaderBot;
+use crate::health;
+use crate::market::types::ArcMutex;
+use crate::strategy::strategy::StrategyId;
+use crate::utils::env::{env_lookup, Lookup};
+
+/// Time between checks of the strategy tasks.
+const SUPERVISOR_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Longest wait before a restart, however often the strategy failed.
+const MAX_BACKOFF: Duration = Duration::from_secs(600);
+
+/// A strategy running this long since its last restart starts over with no restarts.
+const RESET_AFTER: Duration = Duration::from_secs(3600);
+
+/// How often and how fast failed strategies are restarted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RestartPolicy {
+    /// Restarts in a row before the strategy is stopped.
+    pub max_restarts: u32,
+    /// Wait before the first restart, doubled on every further restart.
+    pub backoff: Duration,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            max_restarts: 5,
+            backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RestartPolicy {
+    /// Reads the policy from `STRATEGY_MAX_RESTARTS`, 5 by default, and
+    /// `STRATEGY_RESTART_BACKOFF_SECS`, 5 by default.
+    pub fn from_env() -> Self {
+        Self::from_lookup(env_lookup)
+    }
+
+    /// Reads the policy through `lookup`, see `from_env`.
+    pub fn from_lookup(lookup: impl Lookup) -> Self {
+        let default = Self::default();
+
+        Self {
+            max_restarts: lookup("STRATEGY_MAX_RESTARTS")
+                .and_then(|restarts| restarts.parse().ok())
+                .unwrap_or(default.max_restarts),
+            backoff: lookup("STRATEGY_RESTART_BACKOFF_SECS")
+                .and_then(|secs| secs.parse().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(default.backoff),
+        }
+    }
+
+    /// The wait before a restart, given the restarts so far.
+    pub fn backoff(&self, restarts: u32) -> Duration {
+        self.backoff
+            .saturating_mul(2u32.saturating_pow(restarts))
+            .min(MAX_BACKOFF)
+    }
+}
+
+/// The failures and restarts of a strategy.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct StrategyRestarts {
+    pub strategy_id: StrategyId,
+    /// Restarts in a row, reset once the strategy runs for an hour.
+    pub restarts: u32,
+    pub last_failure: String,
+    pub last_failure_at: u64,
+    pub last_restart_at: Option<u64>,
+    /// When the strategy is restarted next, `None` while it runs.
+    pub next_restart_at: Option<u64>,
+    /// Whether the strategy failed too often and was stopped.
+    pub gave_up: bool,
+}
+
+/// What happens to a failed strategy.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RestartDecision {
+    /// The strategy is restarted at a time, as the nth restart in a row.
+    Restart { at: u64, attempt: u32 },
+    /// The strategy failed too often and is stopped.
+    GiveUp { restarts: u32 },
+}
+
+/// Decides when failed strategies are restarted, see the module documentation.
+#[derive(Debug, Clone, Default)]
+pub struct StrategySupervisor {
+    policy: RestartPolicy,
+    strategies: HashMap<StrategyId, StrategyRestarts>,
+}
+
+impl StrategySupervisor {
+    pub fn new(policy: RestartPolicy) -> Self {
+        Self {
+            policy,
+            strategies: HashMap::new(),
+        }
+    }
+
+    /// Whether a failed strategy is waiting for its restart.
+    pub fn is_pending(&self, strategy_id: &StrategyId) -> bool {
+        self.strategies
+            .get(strategy_id)
+            .is_some_and(|restarts| restarts.next_restart_at.is_some())
+    }
+
+    /// Records the failure of a strategy and decides whether it is restarted.
+    ///
+    /// # Arguments
+    ///
+    /// * `strategy_id` - The failed strategy.
+    /// * `message` - Why the strategy failed, e.g. the panic message of its algorithm.
+    /// * `now` - The time of the failure.
+    pub fn record_failure(
+        &mut self,
+        strategy_id: StrategyId,
+        message: &str,
+        now: u64,
+    ) -> RestartDecision {
+        let restarts = self
+            .strategies
+            .entry(strategy_id)
+            .or_insert_with(|| StrategyRestarts {
+                strategy_id,
+                restarts: 0,
+                last_failure: String::new(),
+                last_failure_at: now,
+                last_restart_at: None,
+                next_restart_at: None,
+                gave_up: false,
+            });
+
+        let ran_since = restarts.last_restart_at.unwrap_or(restarts.last_failure_at);
+        if now.saturating_sub(ran_since) >= RESET_AFTER.as_millis() as u64 {
+            restarts.restarts = 0;
+        }
+        restarts.last_failure = message.to_string();
+        restarts.last_failure_at = now;
+
+        if restarts.restarts >= self.policy.max_restarts {
+            restarts.next_restart_at = None;
+            restarts.gave_up = true;
+            return RestartDecision::GiveUp {
+                restarts: restarts.restarts,
+            };
+        }
+
+        let at = now + self.policy.backoff(restarts.restarts).as_millis() as u64;
+        restarts.next_restart_at = Some(at);
+        restarts.gave_up = false;
+
+        RestartDecision::Restart {
+            at,
+            attempt: restarts.restarts + 1,
+        }
+    }
+
+    /// Lists the strategies due for a restart.
+    pub fn due(&self, now: u64) -> Vec<StrategyId> {
+        self.strategies
+            .values()
+            .filter(|restarts| restarts.next_restart_at.is_some_and(|at| at <= now))
+            .map(|restarts| restarts.strategy_id)
+            .collect()
+    }
+
+    /// Records the restart of a strategy.
+    pub fn record_restart(&mut self, strategy_id: &StrategyId, now: u64) {
+        if let Some(restarts) = self.strategies.get_mut(strategy_id) {
+            restarts.restarts += 1;
+            restarts.last_restart_at = Some(now);
+            restarts.next_restart_at = None;
+        }
+    }
+
+    /// Forgets a strategy, e.g. one stopped while waiting for its restart.
+    pub fn forget(&mut self, strategy_id: &StrategyId) {
+        self.strategies.remove(strategy_id);
+    }
+
+    /// Lists the strategies that failed, most recent failure first.
+    pub fn list(&self) -> Vec<StrategyRestarts> {
+        let mut strategies: Vec<StrategyRestarts> = self.strategies.values().cloned().collect();
+        strategies.sort_by_key(|restarts| Reverse(restarts.last_failure_at));
+
+        strategies
+    }
+}
+
+/// Describes the payload of a panic, which is the message for panics raised with one.
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+/// Supervises the strategies of a bot, checking their tasks every few seconds.
+///
+/// # Returns
+///
+/// The handle of the task supervising the strategies.
+pub fn spawn_supervisor(bot: ArcMutex<RaderBot>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        health::runtime().register_task("strategy_supervisor", Some(SUPERVISOR_INTERVAL));
+
+        loop {
+            tokio::time::sleep(SUPERVISOR_INTERVAL).await;
+            let _iteration = health::time_iteration("strategy_supervisor");
+
+            bot.lock().await.supervise_strategies().await;
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::env::vars_lookup;
+    use uuid::Uuid;
+
+    /// Tests failed strategies are restarted with a growing backoff until they fail too often,
+    /// and start over once they run long enough.
+    #[test]
+    fn test_strategy_supervisor() {
+        let policy = RestartPolicy {
+            max_restarts: 2,
+            backoff: Duration::from_secs(5),
+        };
+        assert_eq!(policy.backoff(0), Duration::from_secs(5));
+        assert_eq!(policy.backoff(3), Duration::from_secs(40));
+        assert_eq!(policy.backoff(20), MAX_BACKOFF);
+
+        let mut supervisor = StrategySupervisor::new(policy);
+        let strategy_id = Uuid::new_v4();
+        let now = 1_000_000;
+
+        let decision = supervisor.record_failure(strategy_id, "index out of bounds", now);
+        assert_eq!(
+            decision,
+            RestartDecision::Restart {
+                at: now + 5_000,
+                attempt: 1
+            }
+        );
+        assert!(supervisor.is_pending(&strategy_id));
+        assert!(supervisor.due(now + 4_000).is_empty());
+        assert_eq!(supervisor.due(now + 5_000), vec![strategy_id]);
+        supervisor.record_restart(&strategy_id, now + 5_000);
+        assert!(!supervisor.is_pending(&strategy_id));
+
+        let decision = supervisor.record_failure(strategy_id, "index out of bounds", now + 6_000);
+        assert_eq!(
+            decision,
+            RestartDecision::Restart {
+                at: now + 16_000,
+                attempt: 2
+            }
+        );
+        supervisor.record_restart(&strategy_id, now + 16_000);
+        let decision = supervisor.record_failure(strategy_id, "index out of bounds", now + 17_000);
+        assert_eq!(decision, RestartDecision::GiveUp { restarts: 2 });
+        assert!(supervisor.list()[0].gave_up);
+
+        // a strategy running for long enough starts over
+        let later = now + 17_000 + RESET_AFTER.as_millis() as u64;
+        supervisor.record_restart(&strategy_id, now + 17_000);
+        let decision = supervisor.record_failure(strategy_id, "index out of bounds", later);
+        assert!(matches!(
+            decision,
+            RestartDecision::Restart { attempt: 1, .. }
+        ));
+
+        let payload: Box<dyn Any + Send> = Box::new(format!("division by {}", 0));
+        assert_eq!(panic_message(payload.as_ref()), "division by 0");
+        let payload: Box<dyn Any + Send> = Box::new("static message");
+        assert_eq!(panic_message(payload.as_ref()), "static message");
+    }
+
+    /// Tests the policy is read from its variables, falling back to the defaults.
+    #[test]
+    fn test_restart_policy_from_lookup() {
+        let lookup = vars_lookup(&[
+            ("STRATEGY_MAX_RESTARTS", "3"),
+            ("STRATEGY_RESTART_BACKOFF_SECS", "10"),
+        ]);
+        assert_eq!(
+            RestartPolicy::from_lookup(lookup),
+            RestartPolicy {
+                max_restarts: 3,
+                backoff: Duration::from_secs(10),
+            }
+        );
+        assert_eq!(
+            RestartPolicy::from_lookup(vars_lookup(&[])),
+            RestartPolicy::default()
+        );
+    }
+}
@@ -1,37 +1,68 @@
-use std::fmt::{self};
-
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
 use crate::account::trade::OrderSide;
+use crate::market::interval::Interval;
 
 use super::strategy::StrategyId;
 
 /// Encapsulates a message signaling a trading decision based on a strategy's evaluation.
 ///
-/// It contains the strategy's identification, the intended order side (buy/sell), the target trading symbol,
-/// the price at which the signal was generated, a flag indicating if this signal is part of a backtest, and
-/// the timestamp marking when the signal was created.
-
+/// It contains the strategy's identification, the intended order side (buy/sell), the target trading symbol
+/// and interval, the price at which the signal was generated, the confidence of the signal, a flag indicating if this signal is part of a backtest,
+/// the timestamp marking when the signal was created, and an optional stop loss price suggested by the algorithm.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SignalMessage {
     pub strategy_id: StrategyId,
     pub order_side: OrderSide,
     pub symbol: String,
+    pub interval: Interval,
     pub price: f64,
+    pub confidence: f64,
     pub is_back_test: bool,
     pub timestamp: u64,
+    #[serde(default)]
+    pub stop_loss: Option<f64>,
 }
 
 /// Outlines the potential outcomes of a trading algorithm's evaluation of market data.
 ///
 /// This can indicate a recommendation to enter a long position, enter a short position, or to make no trade (ignore).
-
+/// `Long` and `Short` carry a confidence score between `0.0` and `1.0`, `Buy` and `Sell` are treated as
+/// full confidence. A confidence that isn't a finite number, e.g. NaN, is ignored.
 pub enum AlgorithmEvalResult {
     Buy,
     Sell,
+    Long(f64),
+    Short(f64),
     Ignore,
 }
 
+impl AlgorithmEvalResult {
+    /// Converts the evaluation result into an order side and its confidence score.
+    ///
+    /// # Returns
+    ///
+    /// The `OrderSide` and confidence clamped between `0.0` and `1.0`, or `None` if the result is `Ignore`
+    /// or its confidence isn't finite.
+    pub fn order_side(&self) -> Option<(OrderSide, f64)> {
+        match self {
+            AlgorithmEvalResult::Buy => Some((OrderSide::Buy, 1.0)),
+            AlgorithmEvalResult::Sell => Some((OrderSide::Sell, 1.0)),
+            // clamping would let NaN through, e.g. from a plugin
+            AlgorithmEvalResult::Long(confidence) if confidence.is_finite() => {
+                Some((OrderSide::Buy, confidence.clamp(0.0, 1.0)))
+            }
+            AlgorithmEvalResult::Short(confidence) if confidence.is_finite() => {
+                Some((OrderSide::Sell, confidence.clamp(0.0, 1.0)))
+            }
+            AlgorithmEvalResult::Long(_)
+            | AlgorithmEvalResult::Short(_)
+            | AlgorithmEvalResult::Ignore => None,
+        }
+    }
+}
+
 /// Specifies selection between the first or last element in a sequence.
 ///
 /// Useful in contexts where it's necessary to distinguish between the initial and concluding elements of a dataset.
@@ -43,25 +74,31 @@ pub enum FirstLastEnum {
 
 /// Enumerates errors that can arise within trading algorithms.
 ///
-/// Covers scenarios such as unrecognized strategy names, unsupported intervals for analysis, and improperly configured parameters.
-
-#[derive(Debug)]
+/// Covers scenarios such as unrecognized strategy names and improperly configured parameters.
+#[derive(Debug, Error)]
 pub enum AlgorithmError {
+    #[error("Unknown Name error: {0}")]
     UnkownName(String),
-    UnknownInterval(String),
+    #[error("Invalid Params error: {0}")]
     InvalidParams(String),
 }
 
-/// Implements display formatting for `AlgorithmError`, providing clearer error descriptions.
-///
-/// This method formats different types of algorithm errors into human-readable strings, enhancing error reporting and debugging.
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-impl fmt::Display for AlgorithmError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            AlgorithmError::UnkownName(msg) => write!(f, "Unknown Name error: {}", msg),
-            AlgorithmError::UnknownInterval(msg) => write!(f, "Unknown Interval error: {}", msg),
-            AlgorithmError::InvalidParams(msg) => write!(f, "Invalid Params error: {}", msg),
-        }
+    /// Tests confidences are clamped and results without a finite confidence are ignored.
+    #[test]
+    fn test_order_side() {
+        assert_eq!(
+            AlgorithmEvalResult::Long(1.5).order_side(),
+            Some((OrderSide::Buy, 1.0))
+        );
+        assert_eq!(
+            AlgorithmEvalResult::Short(-0.5).order_side(),
+            Some((OrderSide::Sell, 0.0))
+        );
+        assert_eq!(AlgorithmEvalResult::Long(f64::NAN).order_side(), None);
+        assert_eq!(AlgorithmEvalResult::Short(f64::INFINITY).order_side(), None);
     }
 }
@@ -0,0 +1,345 @@
+use std::{ops::Range, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    account::trade::TradeTx,
+    market::{interval::Interval, kline::Kline},
+    strategy::{
+        backer::BackTestRunner,
+        metrics::{calc_period_returns, periods_per_year, PerformanceMetrics},
+        strategy::StrategySummary,
+        types::AlgorithmError,
+    },
+};
+
+/// Maximum number of parameter combinations of a grid search.
+pub const MAX_PARAM_GRID_SIZE: usize = 1_000;
+
+/// Metric maximized when optimizing algorithm parameters on in-sample data.
+///
+/// Undefined ratios, e.g. the Sharpe ratio of a window without trades, score lowest.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub enum OptimizationObjective {
+    #[default]
+    Profit,
+    SharpeRatio,
+    SortinoRatio,
+    CalmarRatio,
+    ProfitFactor,
+}
+
+impl OptimizationObjective {
+    /// Scores a backtest result, higher is better.
+    pub fn score(&self, summary: &StrategySummary) -> f64 {
+        let metrics = &summary.metrics;
+        let value = match self {
+            OptimizationObjective::Profit => Some(summary.profit),
+            OptimizationObjective::SharpeRatio => metrics.sharpe_ratio,
+            OptimizationObjective::SortinoRatio => metrics.sortino_ratio,
+            OptimizationObjective::CalmarRatio => metrics.calmar_ratio,
+            OptimizationObjective::ProfitFactor => metrics.profit_factor,
+        };
+        value.filter(|v| v.is_finite()).unwrap_or(f64::NEG_INFINITY)
+    }
+}
+
+/// Settings of a walk-forward analysis.
+///
+/// The k-line range is split into rolling windows of `in_sample_klines` followed by
+/// `out_of_sample_klines`, every window moving forward by `out_of_sample_klines` so the
+/// out-of-sample slices are contiguous and don't overlap.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WalkForwardSettings {
+    pub in_sample_klines: usize,
+    pub out_of_sample_klines: usize,
+    #[serde(default)]
+    pub objective: OptimizationObjective,
+}
+
+/// Result of a single walk-forward window.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WalkForwardWindow {
+    pub in_sample_from: u64,
+    pub in_sample_to: u64,
+    pub out_of_sample_from: u64,
+    pub out_of_sample_to: u64,
+    /// Parameters with the best in-sample score, used for the out-of-sample slice.
+    pub params: Value,
+    pub in_sample_score: f64,
+    pub out_of_sample_profit: f64,
+    pub out_of_sample_trade_count: usize,
+    pub out_of_sample_metrics: PerformanceMetrics,
+}
+
+/// Aggregated out-of-sample performance of a walk-forward analysis.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WalkForwardResult {
+    pub symbol: String,
+    pub interval: Interval,
+    pub windows: Vec<WalkForwardWindow>,
+    pub profit: f64,
+    pub trade_count: usize,
+    /// Metrics of the out-of-sample trades and the chained per-period out-of-sample returns.
+    pub metrics: PerformanceMetrics,
+}
+
+/// Runs a walk-forward analysis of a strategy, optimizing its algorithm parameters with a grid
+/// search on every in-sample slice and backtesting the best parameters on the following
+/// out-of-sample slice. The candidates of a grid search are backtested in parallel.
+pub struct WalkForward {
+    runner: BackTestRunner,
+    walk_forward_settings: WalkForwardSettings,
+}
+
+impl WalkForward {
+    pub fn new(runner: BackTestRunner, walk_forward_settings: WalkForwardSettings) -> Self {
+        Self {
+            runner,
+            walk_forward_settings,
+        }
+    }
+
+    /// Runs the walk-forward analysis.
+    ///
+    /// # Arguments
+    ///
+    /// * `klines` - The k-lines of the analysed range, sorted by open time.
+    /// * `base_params` - Algorithm parameters shared by every candidate.
+    /// * `param_grid` - Candidate values per parameter, see `expand_param_grid`.
+    ///
+    /// # Returns
+    ///
+    /// The `WalkForwardResult`, or an `AlgorithmError` if the strategy can't be built, the
+    /// range is too short for a single window or the grid is too large.
+    pub async fn run(
+        &self,
+        klines: &[Kline],
+        base_params: Value,
+        param_grid: &Map<String, Value>,
+    ) -> Result<WalkForwardResult, AlgorithmError> {
+        let ranges = split_windows(klines.len(), &self.walk_forward_settings);
+        if ranges.is_empty() {
+            return Err(AlgorithmError::InvalidParams(
+                "Not enough klines for a walk-forward window".to_string(),
+            ));
+        }
+
+        let candidates = expand_param_grid(&base_params, param_grid)?;
+        let objective = self.walk_forward_settings.objective;
+
+        let mut windows = vec![];
+        let mut trades: Vec<TradeTx> = vec![];
+        let mut returns: Vec<f64> = vec![];
+
+        for (in_sample, out_of_sample) in ranges {
+            let in_sample = &klines[in_sample];
+            let out_of_sample = &klines[out_of_sample];
+
+            let summaries = self
+                .runner
+                .run_batch(candidates.clone(), Arc::from(in_sample))
+                .await;
+
+            let mut best: Option<(Value, f64)> = None;
+            for (params, summary) in candidates.iter().zip(summaries.iter()) {
+                // candidates with invalid params score lowest instead of ending the search
+                let score = summary
+                    .as_ref()
+                    .map_or(f64::NEG_INFINITY, |summary| objective.score(summary));
+                let is_better = match &best {
+                    Some((_, best_score)) => score > *best_score,
+                    None => true,
+                };
+                if is_better {
+                    best = Some((params.clone(), score));
+                }
+            }
+
+            // SAFETY: the grid always contains at least the base params
+            let (params, in_sample_score) = best.unwrap();
+            // warm up on the in-sample tail so the out-of-sample slice trades from its start
+            let summary = self
+                .runner
+                .run_with_warm_up(params.clone(), in_sample, out_of_sample)
+                .await?;
+
+            let candle_equity: Vec<f64> = summary
+                .equity_curve
+                .iter()
+                .filter(|point| !point.fill)
+                .map(|point| point.equity)
+                .collect();
+            returns.extend(calc_period_returns(&candle_equity));
+            trades.extend(summary.trades.iter().cloned());
+
+            windows.push(WalkForwardWindow {
+                in_sample_from: in_sample[0].open_time,
+                in_sample_to: in_sample[in_sample.len() - 1].close_time,
+                out_of_sample_from: out_of_sample[0].open_time,
+                out_of_sample_to: out_of_sample[out_of_sample.len() - 1].close_time,
+                params,
+                in_sample_score,
+                out_of_sample_profit: summary.profit,
+                out_of_sample_trade_count: summary.trades.len(),
+                out_of_sample_metrics: summary.metrics,
+            });
+        }
+
+        let metrics = PerformanceMetrics::new(
+            &trades,
+            &returns,
+            periods_per_year(self.runner.interval().to_millis()),
+        );
+
+        Ok(WalkForwardResult {
+            symbol: self.runner.symbol().to_string(),
+            interval: self.runner.interval(),
+            profit: windows
+                .iter()
+                .map(|window| window.out_of_sample_profit)
+                .sum(),
+            trade_count: trades.len(),
+            windows,
+            metrics,
+        })
+    }
+}
+
+/// Splits a range of k-lines into rolling in-sample and out-of-sample windows.
+///
+/// # Arguments
+///
+/// * `len` - The number of k-lines in the range.
+/// * `settings` - The walk-forward window sizes.
+///
+/// # Returns
+///
+/// The in-sample and out-of-sample index ranges of every window. The last out-of-sample slice
+/// is shortened to the end of the range, windows without out-of-sample k-lines are dropped.
+pub fn split_windows(
+    len: usize,
+    settings: &WalkForwardSettings,
+) -> Vec<(Range<usize>, Range<usize>)> {
+    let in_sample = settings.in_sample_klines;
+    let out_of_sample = settings.out_of_sample_klines;
+    if in_sample == 0 || out_of_sample == 0 {
+        return vec![];
+    }
+
+    let mut windows = vec![];
+    let mut start = 0;
+    while start + in_sample < len {
+        let split = start + in_sample;
+        let end = (split + out_of_sample).min(len);
+        windows.push((start..split, split..end));
+        start += out_of_sample;
+    }
+
+    windows
+}
+
+/// Expands a parameter grid into every combination of candidate values.
+///
+/// # Arguments
+///
+/// * `base_params` - Parameters shared by every combination, overridden by the grid.
+/// * `param_grid` - Candidate values per parameter name, an array of values or a single value.
+///
+/// # Returns
+///
+/// The cartesian product of the grid merged into the base parameters, at least the base
+/// parameters themselves, or an `AlgorithmError` if it has more than `MAX_PARAM_GRID_SIZE`
+/// combinations.
+pub fn expand_param_grid(
+    base_params: &Value,
+    param_grid: &Map<String, Value>,
+) -> Result<Vec<Value>, AlgorithmError> {
+    let base = base_params.as_object().cloned().unwrap_or_default();
+    let mut combinations = vec![base];
+
+    for (name, candidates) in param_grid {
+        let candidates = match candidates {
+            Value::Array(values) if !values.is_empty() => values.clone(),
+            Value::Array(_) => continue,
+            value => vec![value.clone()],
+        };
+        if combinations.len() * candidates.len() > MAX_PARAM_GRID_SIZE {
+            return Err(AlgorithmError::InvalidParams(format!(
+                "Parameter grid exceeds {MAX_PARAM_GRID_SIZE} combinations"
+            )));
+        }
+
+        combinations = combinations
+            .into_iter()
+            .flat_map(|params| {
+                candidates.iter().map(move |candidate| {
+                    let mut params = params.clone();
+                    params.insert(name.clone(), candidate.clone());
+                    params
+                })
+            })
+            .collect();
+    }
+
+    Ok(combinations.into_iter().map(Value::Object).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Tests grid expansion produces every combination over the base parameters.
+    #[test]
+    fn test_expand_param_grid() {
+        let grid = json!({ "short_period": [5, 10], "long_period": [20, 30, 40] });
+        let combinations = expand_param_grid(
+            &json!({ "threshold": 0.5, "short_period": 1 }),
+            grid.as_object().unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(combinations.len(), 6);
+        assert!(combinations
+            .contains(&json!({ "threshold": 0.5, "short_period": 10, "long_period": 30 })));
+        assert!(combinations.iter().all(|params| params["threshold"] == 0.5));
+
+        let combinations = expand_param_grid(&json!({ "period": 14 }), &Map::new()).unwrap();
+        assert_eq!(combinations, vec![json!({ "period": 14 })]);
+    }
+
+    /// Tests grids with more combinations than the cap are rejected.
+    #[test]
+    fn test_expand_param_grid_cap() {
+        let values: Vec<usize> = (0..100).collect();
+        let grid = json!({ "short_period": values, "long_period": values });
+
+        assert!(matches!(
+            expand_param_grid(&json!({}), grid.as_object().unwrap()),
+            Err(AlgorithmError::InvalidParams(_))
+        ));
+    }
+
+    /// Tests windows roll forward by the out-of-sample size.
+    #[test]
+    fn test_split_windows() {
+        let settings = WalkForwardSettings {
+            in_sample_klines: 10,
+            out_of_sample_klines: 4,
+            objective: OptimizationObjective::Profit,
+        };
+
+        assert_eq!(
+            split_windows(25, &settings),
+            vec![
+                (0..10, 10..14),
+                (4..14, 14..18),
+                (8..18, 18..22),
+                (12..22, 22..25)
+            ]
+        );
+        assert!(split_windows(10, &settings).is_empty());
+    }
+}
@@ -0,0 +1,268 @@
+//! # TradingView Webhook
+//!
+//! TradingView alerts posted to the webhook are turned into signals of a pseudo-strategy, so
+//! chart alerts trade through the `SignalManager` with the same settings and risk checks as
+//! signals of running strategies.
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::account::trade::OrderSide;
+use crate::market::interval::Interval;
+use crate::market::symbol::Symbol;
+use crate::strategy::strategy::{StrategyDirection, StrategyId, StrategySettings};
+use crate::strategy::types::SignalMessage;
+use crate::utils::crypt::constant_time_eq;
+use crate::utils::env::{env_lookup, Lookup};
+
+/// Id of the pseudo-strategy webhook signals are sent for, fixed so positions opened by alerts
+/// are still managed after a restart.
+pub const WEBHOOK_STRATEGY_ID: StrategyId =
+    Uuid::from_u128(0x7261_6465_7262_6f74_7476_7765_6268_6f6b);
+
+/// Interval of alerts which don't send one.
+const DEFAULT_INTERVAL: Interval = Interval::Min1;
+
+/// Configuration of the TradingView webhook, read from `TRADINGVIEW_*` variables.
+#[derive(Debug, Clone)]
+pub struct WebhookConfig {
+    /// Secret alerts must send in their `secret` field.
+    pub secret: String,
+    /// Settings of the pseudo-strategy alerts trade with.
+    pub settings: StrategySettings,
+}
+
+impl WebhookConfig {
+    /// Reads the configuration from the environment.
+    ///
+    /// # Returns
+    ///
+    /// `None` if `TRADINGVIEW_WEBHOOK_SECRET` isn't set, which disables the webhook, or an
+    /// error if a setting can't be parsed.
+    pub fn from_env() -> Result<Option<Self>, String> {
+        Self::from_lookup(env_lookup)
+    }
+
+    /// Reads the secret alerts are checked against and the settings they trade with through
+    /// `lookup`, see `from_env`.
+    pub fn from_lookup(lookup: impl Lookup) -> Result<Option<Self>, String> {
+        let Some(secret) = lookup("TRADINGVIEW_WEBHOOK_SECRET").filter(|secret| !secret.is_empty())
+        else {
+            return Ok(None);
+        };
+
+        let mut settings = StrategySettings::default();
+        if let Some(margin_usd) = parse_var(&lookup, "TRADINGVIEW_MARGIN_USD")? {
+            settings.margin_usd = margin_usd;
+        }
+        if let Some(leverage) = parse_var(&lookup, "TRADINGVIEW_LEVERAGE")? {
+            settings.leverage = leverage;
+        }
+        if let Some(max_open_orders) = parse_var(&lookup, "TRADINGVIEW_MAX_OPEN_ORDERS")? {
+            settings.max_open_orders = max_open_orders;
+        }
+        settings.stop_loss = parse_var(&lookup, "TRADINGVIEW_STOP_LOSS")?;
+        settings.take_profit = parse_var(&lookup, "TRADINGVIEW_TAKE_PROFIT")?;
+        settings.min_confidence = parse_var(&lookup, "TRADINGVIEW_MIN_CONFIDENCE")?;
+        if let Some(direction) = lookup("TRADINGVIEW_DIRECTION") {
+            settings.direction = match direction.trim() {
+                "both" => StrategyDirection::Both,
+                "long_only" => StrategyDirection::LongOnly,
+                "short_only" => StrategyDirection::ShortOnly,
+                _ => return Err(format!("Invalid TRADINGVIEW_DIRECTION: {direction}")),
+            };
+        }
+        settings.shadow = lookup("TRADINGVIEW_SHADOW").is_some_and(|shadow| shadow == "true");
+
+        if settings.margin_usd <= 0.0 {
+            return Err("TRADINGVIEW_MARGIN_USD must be positive".to_string());
+        }
+        if settings.leverage == 0 || settings.max_open_orders == 0 {
+            return Err(
+                "TRADINGVIEW_LEVERAGE and TRADINGVIEW_MAX_OPEN_ORDERS must be at least 1"
+                    .to_string(),
+            );
+        }
+
+        Ok(Some(Self { secret, settings }))
+    }
+
+    /// Checks an alert carries the webhook secret.
+    pub fn is_authorized(&self, alert: &TradingViewAlert) -> bool {
+        constant_time_eq(&alert.secret, &self.secret)
+    }
+}
+
+/// An alert posted by TradingView, e.g. the message
+/// `{"secret": "...", "ticker": "{{ticker}}", "action": "{{strategy.order.action}}",
+/// "price": {{close}}, "interval": "{{interval}}"}`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct TradingViewAlert {
+    #[serde(default)]
+    pub secret: String,
+    /// The ticker of the chart, exchange prefixes such as `BINANCE:` and the `.P` suffix of
+    /// perpetuals are removed.
+    #[serde(alias = "ticker")]
+    pub symbol: String,
+    /// `buy` or `long` opens longs, `sell` or `short` opens shorts.
+    #[serde(alias = "side")]
+    pub action: String,
+    /// The price the alert triggered at, live positions open at the last market price.
+    #[serde(default)]
+    pub price: f64,
+    /// The chart interval, in TradingView's format such as `15`, `240` or `D`, or raderbot's.
+    pub interval: Option<String>,
+    /// Confidence between `0.0` and `1.0`, `1.0` if not sent.
+    pub confidence: Option<f64>,
+    /// Stop loss price, taking precedence over the configured stop loss distance.
+    pub stop_loss: Option<f64>,
+}
+
+impl TradingViewAlert {
+    /// Converts the alert into a signal of the webhook pseudo-strategy.
+    ///
+    /// # Returns
+    ///
+    /// The signal, or an error if the symbol, action, confidence or interval is invalid.
+    pub fn to_signal(&self, timestamp: u64) -> Result<SignalMessage, String> {
+        let symbol = normalize_symbol(&self.symbol);
+        if symbol.is_empty() {
+            return Err("symbol is missing".to_string());
+        }
+
+        let order_side = match self.action.trim().to_lowercase().as_str() {
+            "buy" | "long" => OrderSide::Buy,
+            "sell" | "short" => OrderSide::Sell,
+            action => return Err(format!("unknown action: {action}")),
+        };
+
+        let confidence = self.confidence.unwrap_or(1.0);
+        if !(0.0..=1.0).contains(&confidence) {
+            return Err(format!("confidence {confidence} not between 0 and 1"));
+        }
+
+        let interval = match &self.interval {
+            Some(interval) => normalize_interval(interval)?,
+            None => DEFAULT_INTERVAL,
+        };
+
+        Ok(SignalMessage {
+            strategy_id: WEBHOOK_STRATEGY_ID,
+            order_side,
+            symbol,
+            interval,
+            price: self.price,
+            confidence,
+            is_back_test: false,
+            timestamp,
+            stop_loss: self.stop_loss,
+        })
+    }
+}
+
+// ---
+// Private Methods
+// ---
+
+fn parse_var<T: std::str::FromStr>(
+    lookup: &impl Fn(&str) -> Option<String>,
+    key: &str,
+) -> Result<Option<T>, String> {
+    lookup(key)
+        .map(|value| {
+            value
+                .trim()
+                .parse()
+                .map_err(|_| format!("Invalid {key}: {value}"))
+        })
+        .transpose()
+}
+
+/// Turns a TradingView ticker such as `BINANCE:BTCUSDT.P` into an exchange symbol.
+fn normalize_symbol(ticker: &str) -> String {
+    let symbol = ticker.rsplit(':').next().unwrap_or_default().trim();
+    Symbol::normalize(&symbol.trim_end_matches(".P").to_uppercase())
+}
+
+/// Turns a TradingView interval, minutes or a `D`, `W` or `M` period, or raderbot's into an
+/// `Interval`.
+fn normalize_interval(interval: &str) -> Result<Interval, String> {
+    let interval = interval.trim();
+    if let Ok(minutes) = interval.parse::<u64>() {
+        return Interval::from_minutes(minutes)
+            .ok_or_else(|| format!("unsupported interval: {minutes} minutes"));
+    }
+
+    let period_start = interval.char_indices().last().map_or(0, |(i, _)| i);
+    let (count, period) = interval.split_at(period_start);
+    let count = if count.is_empty() { "1" } else { count };
+    let interval = match period {
+        "D" => format!("{count}d"),
+        "W" => format!("{count}w"),
+        "M" => format!("{count}M"),
+        _ => interval.to_string(),
+    };
+    interval.parse()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::env::vars_lookup;
+
+    fn config(vars: &[(&str, &str)]) -> Result<Option<WebhookConfig>, String> {
+        WebhookConfig::from_lookup(vars_lookup(vars))
+    }
+
+    /// Tests the webhook is enabled by its secret and alerts are converted into signals.
+    #[test]
+    fn test_tradingview_alert() {
+        assert!(config(&[]).unwrap().is_none());
+        assert!(config(&[
+            ("TRADINGVIEW_WEBHOOK_SECRET", "s3cret"),
+            ("TRADINGVIEW_LEVERAGE", "x")
+        ])
+        .is_err());
+
+        let config = config(&[
+            ("TRADINGVIEW_WEBHOOK_SECRET", "s3cret"),
+            ("TRADINGVIEW_MARGIN_USD", "50"),
+            ("TRADINGVIEW_STOP_LOSS", "2.5"),
+            ("TRADINGVIEW_DIRECTION", "long_only"),
+        ])
+        .unwrap()
+        .unwrap();
+        assert_eq!(config.settings.margin_usd, 50.0);
+        assert_eq!(config.settings.stop_loss, Some(2.5));
+        assert_eq!(config.settings.direction, StrategyDirection::LongOnly);
+
+        let alert: TradingViewAlert = serde_json::from_str(
+            r#"{"secret": "s3cret", "ticker": "BINANCE:btcusdt.P", "action": "Buy",
+                "price": 42000.5, "interval": "240"}"#,
+        )
+        .unwrap();
+        assert!(config.is_authorized(&alert));
+        assert!(!config.is_authorized(&TradingViewAlert::default()));
+
+        let signal = alert.to_signal(1_700_000_000_000).unwrap();
+        assert_eq!(signal.strategy_id, WEBHOOK_STRATEGY_ID);
+        assert_eq!(signal.symbol, "BTCUSDT");
+        assert_eq!(signal.order_side, OrderSide::Buy);
+        assert_eq!(signal.interval, Interval::Hour4);
+        assert_eq!(signal.price, 42000.5);
+        assert_eq!(signal.confidence, 1.0);
+        assert!(!signal.is_back_test);
+
+        let alert = TradingViewAlert {
+            action: "hold".to_string(),
+            ..alert
+        };
+        assert!(alert.to_signal(0).is_err());
+
+        assert_eq!(normalize_interval("15").unwrap(), Interval::Min15);
+        assert_eq!(normalize_interval("D").unwrap(), Interval::Day1);
+        assert_eq!(normalize_interval("1h").unwrap(), Interval::Hour1);
+        assert!(normalize_interval("x").is_err());
+        assert!(normalize_interval("7").is_err());
+    }
+}
@@ -0,0 +1,315 @@
+//! # Tenants
+//!
+//! Isolated users of one bot, for running raderbot as a small managed service. Every tenant
+//! trades on the account of a credential profile of its own, so its exchange credentials,
+//! positions, trades and account history, stored under the profile name, are kept apart from
+//! those of other tenants. The strategies of a tenant are the ones whose settings reference its
+//! profile.
+//!
+//! Tenants call the HTTP API with a token of their own, which scopes the account and strategy
+//! routes to the tenant, see `api::tenant`.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::{Arc, RwLock};
+
+use serde::Serialize;
+
+use crate::account::account::Account;
+use crate::market::types::ArcMutex;
+use crate::secrets::SecretsProvider;
+use crate::strategy::strategy::StrategySettings;
+use crate::utils::crypt::constant_time_eq;
+use crate::utils::env::{env_lookup, Lookup};
+
+/// A tenant of the bot, see the module documentation. The token is never serialized.
+#[derive(Serialize, Clone)]
+pub struct Tenant {
+    pub name: String,
+    /// The credential profile the tenant trades on.
+    pub profile: String,
+    #[serde(skip)]
+    token: String,
+    #[serde(skip)]
+    pub account: ArcMutex<Account>,
+}
+
+impl fmt::Debug for Tenant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tenant")
+            .field("name", &self.name)
+            .field("profile", &self.profile)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Tenant {
+    /// Checks whether a strategy with the settings belongs to the tenant.
+    pub fn owns(&self, settings: &StrategySettings) -> bool {
+        !settings.shadow && settings.profile.as_deref() == Some(self.profile.as_str())
+    }
+
+    /// Makes the settings of a strategy trade on the account of the tenant.
+    ///
+    /// # Returns
+    ///
+    /// An error if the settings reference the profile of another tenant or ask for shadow mode,
+    /// whose account is shared by every strategy of the bot.
+    pub fn scope_settings(&self, settings: &mut StrategySettings) -> Result<(), String> {
+        if settings.shadow {
+            return Err("Shadow mode isn't available to tenants".to_string());
+        }
+        match &settings.profile {
+            Some(profile) if *profile != self.profile => Err(format!(
+                "Credential profile {profile} belongs to another tenant"
+            )),
+            _ => {
+                settings.profile = Some(self.profile.clone());
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The tenants of the bot, empty when it isn't multi-tenant.
+#[derive(Debug, Clone, Default)]
+pub struct Tenants {
+    tenants: Vec<Tenant>,
+}
+
+impl Tenants {
+    /// Reads the tenants listed in `TENANTS`, separated by commas.
+    ///
+    /// Each tenant is configured by variables prefixed with its name in upper case, dashes
+    /// replaced by underscores, e.g. for `acme-capital`: `TENANT_ACME_CAPITAL_TOKEN`, read from
+    /// the secrets provider, and optionally `TENANT_ACME_CAPITAL_PROFILE`, the credential
+    /// profile the tenant trades on, which defaults to the name of the tenant.
+    ///
+    /// # Arguments
+    ///
+    /// * `secrets` - Provides the API tokens of the tenants.
+    /// * `accounts` - The accounts of the credential profiles by profile name.
+    ///
+    /// # Returns
+    ///
+    /// The tenants in the order they are listed, none if `TENANTS` isn't set, or an error if a
+    /// tenant has no token, references an unknown profile or shares its profile.
+    pub async fn from_env(
+        secrets: &dyn SecretsProvider,
+        accounts: &BTreeMap<String, ArcMutex<Account>>,
+    ) -> Result<Self, String> {
+        let mut tokens = HashMap::new();
+        let names = env_lookup("TENANTS").unwrap_or_default();
+        for name in names.split(',').map(str::trim) {
+            let key = format!("{}TOKEN", var_prefix(name));
+            if let Some(token) = secrets.get(&key).await? {
+                tokens.insert(key, token);
+            }
+        }
+
+        // tokens never fall back to the environment of providers other than `env`
+        Self::from_lookup(
+            |key| {
+                if key.ends_with("_TOKEN") {
+                    tokens.get(key).cloned()
+                } else {
+                    env_lookup(key)
+                }
+            },
+            accounts,
+        )
+    }
+
+    /// Reads the token and profile of every listed tenant through `lookup`, checking the profile
+    /// has an account, see `from_env`.
+    pub fn from_lookup(
+        lookup: impl Lookup,
+        accounts: &BTreeMap<String, ArcMutex<Account>>,
+    ) -> Result<Self, String> {
+        let Some(names) = lookup("TENANTS") else {
+            return Ok(Self::default());
+        };
+
+        let mut tenants: Vec<Tenant> = vec![];
+        for name in names
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+        {
+            if tenants.iter().any(|tenant| tenant.name == name) {
+                return Err(format!("Tenant {name} is listed twice"));
+            }
+
+            let prefix = var_prefix(name);
+            let token = lookup(&format!("{prefix}TOKEN"))
+                .filter(|token| !token.is_empty())
+                .ok_or_else(|| format!("Tenant {name} is missing {prefix}TOKEN"))?;
+            if tenants
+                .iter()
+                .any(|tenant| constant_time_eq(&tenant.token, &token))
+            {
+                return Err(format!(
+                    "Tenant {name} shares its token with another tenant"
+                ));
+            }
+
+            let profile = lookup(&format!("{prefix}PROFILE")).unwrap_or(name.to_string());
+            let Some(account) = accounts.get(&profile) else {
+                return Err(format!(
+                    "Tenant {name} trades on unknown credential profile {profile}"
+                ));
+            };
+            if let Some(other) = tenants.iter().find(|tenant| tenant.profile == profile) {
+                return Err(format!(
+                    "Tenants {} and {name} share credential profile {profile}",
+                    other.name
+                ));
+            }
+
+            tenants.push(Tenant {
+                name: name.to_string(),
+                profile,
+                token,
+                account: account.clone(),
+            });
+        }
+
+        Ok(Self { tenants })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tenants.is_empty()
+    }
+
+    pub fn list(&self) -> &[Tenant] {
+        &self.tenants
+    }
+
+    /// Finds the tenant an API token belongs to.
+    pub fn authenticate(&self, token: &str) -> Option<&Tenant> {
+        self.tenants
+            .iter()
+            .find(|tenant| constant_time_eq(&tenant.token, token))
+    }
+}
+
+/// The token set in `ADMIN_API_TOKEN`, which grants access to the admin routes and to the
+/// routes of every tenant. Clones share the token, so a config reload replaces it for the API.
+#[derive(Clone, Default)]
+pub struct AdminToken(Arc<RwLock<Option<String>>>);
+
+impl fmt::Debug for AdminToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("AdminToken").finish_non_exhaustive()
+    }
+}
+
+impl AdminToken {
+    /// Reads the token through `lookup`, an empty token counts as not set.
+    pub fn from_lookup(lookup: impl Lookup) -> Self {
+        Self(Arc::new(RwLock::new(Self::read(lookup))))
+    }
+
+    /// Reads the token again through `lookup`, e.g. after the config is reloaded.
+    pub fn reload(&self, lookup: impl Lookup) {
+        *self.0.write().unwrap_or_else(|e| e.into_inner()) = Self::read(lookup);
+    }
+
+    pub fn is_set(&self) -> bool {
+        self.0.read().unwrap_or_else(|e| e.into_inner()).is_some()
+    }
+
+    /// Checks whether a token is the admin token, never while no token is set.
+    pub fn matches(&self, token: &str) -> bool {
+        self.0
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_deref()
+            .is_some_and(|admin_token| constant_time_eq(token, admin_token))
+    }
+
+    fn read(lookup: impl Lookup) -> Option<String> {
+        lookup("ADMIN_API_TOKEN").filter(|token| !token.is_empty())
+    }
+}
+
+// ---
+// Private Methods
+// ---
+
+/// The prefix of the variables of a tenant, e.g. `TENANT_ACME_CAPITAL_` for `acme-capital`.
+fn var_prefix(name: &str) -> String {
+    format!(
+        "TENANT_{}_",
+        name.to_uppercase()
+            .replace(|c: char| !c.is_ascii_alphanumeric(), "_")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    use crate::exchange::{api::ExchangeApi, mock::MockExchangeApi};
+    use crate::utils::env::vars_lookup;
+
+    /// Tests tenants are read by name, trade on their profile and are found by their token.
+    #[tokio::test]
+    async fn test_tenants() {
+        let api: Arc<Box<dyn ExchangeApi>> = Arc::new(Box::new(MockExchangeApi {}));
+        let mut accounts = BTreeMap::new();
+        for profile in ["acme", "globex-main"] {
+            let account = Account::new(api.clone(), true, true).await;
+            accounts.insert(profile.to_string(), ArcMutex::new(account));
+        }
+        let tenants = |vars: &[(&str, &str)]| Tenants::from_lookup(vars_lookup(vars), &accounts);
+
+        assert!(tenants(&[]).unwrap().is_empty());
+
+        let vars = [
+            ("TENANTS", "acme, globex"),
+            ("TENANT_ACME_TOKEN", "acme-token"),
+            ("TENANT_GLOBEX_TOKEN", "globex-token"),
+            ("TENANT_GLOBEX_PROFILE", "globex-main"),
+        ];
+        let loaded = tenants(&vars).unwrap();
+        assert_eq!(loaded.list().len(), 2);
+        assert_eq!(loaded.list()[0].profile, "acme");
+        let globex = loaded.authenticate("globex-token").unwrap();
+        assert_eq!(globex.name, "globex");
+        assert!(loaded.authenticate("globex").is_none());
+        assert!(serde_json::to_value(globex).unwrap().get("token").is_none());
+
+        let mut settings = StrategySettings::default();
+        globex.scope_settings(&mut settings).unwrap();
+        assert_eq!(settings.profile.as_deref(), Some("globex-main"));
+        assert!(globex.owns(&settings));
+        assert!(!loaded.list()[0].owns(&settings));
+        assert!(loaded.list()[0].scope_settings(&mut settings).is_err());
+
+        assert!(tenants(&vars[..3]).is_err());
+        assert!(tenants(&[("TENANTS", "initech"), ("TENANT_INITECH_TOKEN", "t")]).is_err());
+        assert!(tenants(&[
+            ("TENANTS", "acme,acme-2"),
+            ("TENANT_ACME_TOKEN", "a"),
+            ("TENANT_ACME_2_TOKEN", "b"),
+            ("TENANT_ACME_2_PROFILE", "acme"),
+        ])
+        .is_err());
+    }
+
+    /// Tests the admin token is only matched while set, and replaced by a reload for every clone.
+    #[test]
+    fn test_admin_token() {
+        let admin_token = AdminToken::from_lookup(vars_lookup(&[("ADMIN_API_TOKEN", "")]));
+        assert!(!admin_token.is_set());
+        assert!(!admin_token.matches(""));
+
+        let shared = admin_token.clone();
+        admin_token.reload(vars_lookup(&[("ADMIN_API_TOKEN", "s3cret")]));
+        assert!(shared.is_set());
+        assert!(shared.matches("s3cret"));
+        assert!(!shared.matches("s3crex"));
+    }
+}
@@ -1,66 +1,302 @@
+//! # Bounded Channels
+//!
+//! The channels between the tasks of the bot hold a limited number of messages, so a consumer
+//! falling behind can't grow the backlog until the process runs out of memory. What happens once
+//! a channel is full depends on its `OverflowPolicy`: market data is superseded by the next
+//! update and drops its oldest messages, while signals must not be lost and make their sender
+//! wait for room. Every channel counts the messages waiting in it and the messages it dropped,
+//! which the receivers report to `GET /api/metrics`.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 
 use futures_util::lock::Mutex;
-use tokio::sync::mpsc::unbounded_channel;
+use serde::Serialize;
+use tokio::sync::mpsc::error::SendError;
+use tokio::sync::Notify;
 
 use crate::market::types::{ArcReceiver, ArcSender};
+use crate::utils::env::Lookup;
 
-/// Creates a new asynchronous, unbounded channel with both sender and receiver wrapped in `Arc` and `Mutex`.
-///
-/// This utility function simplifies the creation of channels for message passing in asynchronous contexts,
-/// especially when shared ownership and thread-safety are required. The function wraps both ends of the channel
-/// (`sender` and `receiver`) in `Arc` and `Mutex` to facilitate safe sharing across threads and async tasks.
-///
-/// # Type Parameters
-///
-/// * `T`: The type of messages that can be sent through the channel.
+/// Messages the market channel holds unless `MARKET_CHANNEL_CAPACITY` is set.
+pub const DEFAULT_MARKET_CHANNEL_CAPACITY: usize = 10_000;
+
+/// Signals the signal channel holds unless `SIGNAL_CHANNEL_CAPACITY` is set.
+pub const DEFAULT_SIGNAL_CHANNEL_CAPACITY: usize = 1_000;
+
+/// What a channel does with a message sent while it is full.
+#[derive(Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    /// Drops the oldest waiting message, for data superseded by newer data, e.g. market data.
+    DropOldest,
+    /// Waits until the receiver takes a message, for messages that must not be lost, e.g.
+    /// signals.
+    #[default]
+    Block,
+}
+
+/// The backlog of a channel, see `ChannelReceiver::stats`.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct ChannelStats {
+    /// Messages waiting to be received.
+    pub depth: usize,
+    pub capacity: usize,
+    pub policy: OverflowPolicy,
+    /// Messages dropped because the channel was full, since it was created.
+    pub dropped: u64,
+}
+
+struct Shared<T> {
+    queue: std::sync::Mutex<VecDeque<T>>,
+    capacity: usize,
+    policy: OverflowPolicy,
+    dropped: AtomicU64,
+    sender_closed: AtomicBool,
+    receiver_closed: AtomicBool,
+    /// Notifies the receiver of a sent message.
+    sent: Notify,
+    /// Notifies blocked senders of a received message.
+    received: Notify,
+}
+
+impl<T> Shared<T> {
+    fn queue(&self) -> std::sync::MutexGuard<'_, VecDeque<T>> {
+        // the queue is never left half changed, so it stays usable after a panic
+        self.queue
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// The sending half of a bounded channel, shared through an `ArcSender`. The channel closes
+/// when it is dropped.
+pub struct ChannelSender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> ChannelSender<T> {
+    /// Sends a message, applying the overflow policy of the channel if it is full.
+    ///
+    /// # Returns
+    ///
+    /// The message back as an error if the receiver was dropped.
+    pub async fn send(&self, message: T) -> Result<(), SendError<T>> {
+        loop {
+            let received = self.shared.received.notified();
+            tokio::pin!(received);
+            // registered before the queue is checked, so a message received in between wakes it
+            received.as_mut().enable();
+
+            {
+                let mut queue = self.shared.queue();
+                if self.is_closed() {
+                    return Err(SendError(message));
+                }
+
+                if queue.len() < self.shared.capacity
+                    || self.shared.policy == OverflowPolicy::DropOldest
+                {
+                    if queue.len() >= self.shared.capacity {
+                        queue.pop_front();
+                        self.shared.dropped.fetch_add(1, Ordering::Relaxed);
+                    }
+                    queue.push_back(message);
+                    drop(queue);
+                    self.shared.sent.notify_one();
+                    return Ok(());
+                }
+            }
+
+            received.await;
+        }
+    }
+
+    /// Checks whether the receiver was dropped, after which every message fails to send.
+    pub fn is_closed(&self) -> bool {
+        self.shared.receiver_closed.load(Ordering::Acquire)
+    }
+}
+
+impl<T> Drop for ChannelSender<T> {
+    fn drop(&mut self) {
+        self.shared.sender_closed.store(true, Ordering::Release);
+        self.shared.sent.notify_one();
+    }
+}
+
+/// The receiving half of a bounded channel, shared through an `ArcReceiver`.
+pub struct ChannelReceiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> ChannelReceiver<T> {
+    /// Receives the next message, waiting for one if the channel is empty.
+    ///
+    /// # Returns
+    ///
+    /// The message, or `None` once the sender was dropped and every message was received.
+    pub async fn recv(&mut self) -> Option<T> {
+        loop {
+            {
+                let mut queue = self.shared.queue();
+                if let Some(message) = queue.pop_front() {
+                    drop(queue);
+                    self.shared.received.notify_waiters();
+                    return Some(message);
+                }
+                if self.shared.sender_closed.load(Ordering::Acquire) {
+                    return None;
+                }
+            }
+
+            // a message sent since the queue was checked left a permit, so this doesn't miss it
+            self.shared.sent.notified().await;
+        }
+    }
+
+    /// The backlog of the channel, as reported to the runtime health.
+    pub fn stats(&self) -> ChannelStats {
+        ChannelStats {
+            depth: self.shared.queue().len(),
+            capacity: self.shared.capacity,
+            policy: self.shared.policy,
+            dropped: self.shared.dropped.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl<T> Drop for ChannelReceiver<T> {
+    fn drop(&mut self) {
+        self.shared.receiver_closed.store(true, Ordering::Release);
+        self.shared.received.notify_waiters();
+    }
+}
+
+/// Creates a bounded channel with the sender wrapped in an `Arc` and the receiver in an `Arc`
+/// and `Mutex`, so both can be shared across tasks.
 ///
-/// # Returns
+/// # Arguments
 ///
-/// A tuple containing the `ArcSender<T>` and `ArcReceiver<T>`, which are the sender and receiver
-/// sides of the channel respectively.
+/// * `capacity` - The number of messages the channel holds, at least one.
+/// * `policy` - What the channel does with messages sent while it is full.
 ///
 /// # Examples
 ///
-/// ```
-/// use my_crate::utils::build_arc_channel;
-///
-/// #[tokio::main]
-/// async fn main() {
-///     let (sender, receiver) = build_arc_channel::<String>();
-///
-///     sender.send("Hello, world!".to_string()).unwrap();
-///     // Since the receiver is wrapped in an Arc and Mutex, we need to lock it before awaiting
-///     let received = receiver.lock().await.recv().await.unwrap();
+/// ```ignore
+/// let (sender, receiver) = build_arc_channel::<String>(100, OverflowPolicy::Block);
 ///
-///     println!("Received: {}", received);
-/// }
+/// sender.send("Hello, world!".to_string()).await.unwrap();
+/// let received = receiver.lock().await.recv().await.unwrap();
 /// ```
-pub fn build_arc_channel<T>() -> (ArcSender<T>, ArcReceiver<T>) {
-    let (sender, receiver) = unbounded_channel::<T>();
 
-    let receiver = Arc::new(Mutex::new(receiver));
-    let sender = Arc::new(sender);
+pub fn build_arc_channel<T>(
+    capacity: usize,
+    policy: OverflowPolicy,
+) -> (ArcSender<T>, ArcReceiver<T>) {
+    let shared = Arc::new(Shared {
+        queue: std::sync::Mutex::new(VecDeque::new()),
+        capacity: capacity.max(1),
+        policy,
+        dropped: AtomicU64::new(0),
+        sender_closed: AtomicBool::new(false),
+        receiver_closed: AtomicBool::new(false),
+        sent: Notify::new(),
+        received: Notify::new(),
+    });
+
+    let sender = Arc::new(ChannelSender {
+        shared: shared.clone(),
+    });
+    let receiver = Arc::new(Mutex::new(ChannelReceiver { shared }));
 
     (sender, receiver)
 }
 
+/// Reads the capacity of a channel from a variable, falling back to a default if it isn't set
+/// or isn't a positive number.
+pub fn channel_capacity(lookup: impl Lookup, var: &str, default: usize) -> usize {
+    lookup(var)
+        .and_then(|capacity| capacity.parse().ok())
+        .filter(|capacity| *capacity > 0)
+        .unwrap_or(default)
+}
+
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
     use super::*;
+    use crate::utils::env::vars_lookup;
+
+    #[test]
+    fn test_channel_capacity() {
+        let lookup = vars_lookup(&[("SET", "50"), ("ZERO", "0"), ("INVALID", "many")]);
+
+        assert_eq!(channel_capacity(&lookup, "SET", 10), 50);
+        assert_eq!(channel_capacity(&lookup, "ZERO", 10), 10);
+        assert_eq!(channel_capacity(&lookup, "INVALID", 10), 10);
+        assert_eq!(channel_capacity(&lookup, "UNSET", 10), 10);
+    }
 
     #[tokio::test]
     async fn test_build_arc_channel() {
         // Test building an ARC channel
-        let (sender, receiver) = build_arc_channel::<String>();
+        let (sender, receiver) = build_arc_channel::<String>(10, OverflowPolicy::Block);
 
         // Send a message through the channel
-        sender.send("Test Message".to_string()).unwrap();
+        sender.send("Test Message".to_string()).await.unwrap();
 
         // Receive the message from the channel
         let received_message = receiver.lock().await.recv().await.unwrap();
 
         // Assert that the sent and received messages match
         assert_eq!(received_message, "Test Message");
+
+        drop(sender);
+        assert_eq!(receiver.lock().await.recv().await, None);
+    }
+
+    /// Tests full channels drop their oldest messages or make the sender wait, depending on
+    /// their policy.
+    #[tokio::test]
+    async fn test_overflow_policy() {
+        let (sender, receiver) = build_arc_channel::<u32>(2, OverflowPolicy::DropOldest);
+        for message in 1..=5 {
+            sender.send(message).await.unwrap();
+        }
+        let mut rx = receiver.lock().await;
+        assert_eq!(
+            rx.stats(),
+            ChannelStats {
+                depth: 2,
+                capacity: 2,
+                policy: OverflowPolicy::DropOldest,
+                dropped: 3,
+            }
+        );
+        assert_eq!(rx.recv().await, Some(4));
+        assert_eq!(rx.recv().await, Some(5));
+        drop(rx);
+
+        let (sender, receiver) = build_arc_channel::<u32>(1, OverflowPolicy::Block);
+        sender.send(1).await.unwrap();
+        let blocked = tokio::spawn({
+            let sender = sender.clone();
+            async move { sender.send(2).await }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!blocked.is_finished());
+        assert_eq!(receiver.lock().await.stats().depth, 1);
+
+        assert_eq!(receiver.lock().await.recv().await, Some(1));
+        blocked.await.unwrap().unwrap();
+        assert_eq!(receiver.lock().await.recv().await, Some(2));
+        assert_eq!(receiver.lock().await.stats().dropped, 0);
+
+        drop(receiver);
+        assert!(sender.is_closed());
+        assert!(sender.send(3).await.is_err());
     }
 }
@@ -36,6 +36,16 @@ pub fn sign_hmac(secret: &str, message: &str) -> String {
     hex::encode(result.into_bytes())
 }
 
+/// Compares secrets in a time independent of where they differ, so response times don't leak
+/// how much of a guessed secret is right.
+pub fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -53,4 +63,11 @@ mod tests {
         // Further tests could include comparing the result against a known HMAC value,
         // but this would require a fixed secret and message, and the expected result pre-calculated.
     }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq("abc", "abc"));
+        assert!(!constant_time_eq("abc", "abd"));
+        assert!(!constant_time_eq("abc", "abcd"));
+    }
 }
@@ -0,0 +1,26 @@
+//! # Environment Lookups
+//!
+//! Configuration is read from variables looked up by name. The bot looks them up in the process
+//! environment with `env_lookup`, while tests pass fixed variables with `vars_lookup`, so parsing
+//! configuration doesn't depend on the environment of the test process.
+
+/// Looks a variable up by name, `None` if it isn't set.
+pub trait Lookup: Fn(&str) -> Option<String> {}
+
+impl<F: Fn(&str) -> Option<String>> Lookup for F {}
+
+/// Looks a variable up in the process environment.
+pub fn env_lookup(key: &str) -> Option<String> {
+    std::env::var(key).ok()
+}
+
+/// Looks variables up in the given list of names and values.
+#[cfg(test)]
+pub fn vars_lookup(vars: &[(&str, &str)]) -> impl Lookup {
+    let vars: std::collections::HashMap<String, String> = vars
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+
+    move |key: &str| vars.get(key).cloned()
+}
@@ -1,11 +1,16 @@
 use chrono::Datelike;
 use log::info;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::Deserialize;
 use uuid::Uuid;
 
 use std::fs::File;
 
 use crate::{
-    market::kline::{BinanceKline, Kline},
+    market::{
+        interval::Interval,
+        kline::{BinanceKline, Kline},
+    },
     utils::{csv::has_header, time::timestamp_to_datetime},
 };
 use csv::Reader;
@@ -29,7 +34,7 @@ use csv::Reader;
 pub fn load_binance_klines(
     file_path: std::path::PathBuf,
     symbol: &str,
-    interval: &str,
+    interval: Interval,
 ) -> Vec<Kline> {
     let filepath_str = file_path.as_os_str().to_str().unwrap();
     info!("Loading klines from file: {filepath_str}");
@@ -69,7 +74,7 @@ pub fn load_binance_klines(
 
         let kline = Kline {
             symbol: symbol.to_string(),
-            interval: interval.to_string(),
+            interval,
             open_time: binance_kline.open_time,
             open: binance_kline.open,
             high: binance_kline.high,
@@ -92,11 +97,15 @@ pub fn load_binance_klines(
 ///
 /// # Returns
 ///
-/// A tuple containing the symbol and interval as `String`.
-pub fn interval_symbol_from_binance_filename(filename: &str) -> (String, String) {
+/// A tuple containing the symbol and interval, or an error if the filename has no known interval.
+pub fn interval_symbol_from_binance_filename(filename: &str) -> Result<(String, Interval), String> {
     let parts = filename.split('-');
     let collection: Vec<&str> = parts.collect();
-    (collection[0].to_string(), collection[1].to_string())
+    let interval = collection
+        .get(1)
+        .ok_or_else(|| format!("Missing interval in filename: {filename}"))?
+        .parse()?;
+    Ok((collection[0].to_string(), interval))
 }
 
 /// Saves k-line data to a specified CSV file.
@@ -187,7 +196,7 @@ pub fn get_min_max_open_time(klines: &[Kline]) -> (u64, u64) {
     (min_time, max_time)
 }
 
-pub fn build_kline_key(symbol: &str, interval: &str) -> String {
+pub fn build_kline_key(symbol: &str, interval: Interval) -> String {
     format!("{}@kline_{}", symbol, interval)
 }
 
@@ -209,7 +218,376 @@ pub fn build_kline_month_string(timestamp: u64) -> String {
     timestamp.format("%Y-%m").to_string()
 }
 
+/// Calculates the total traded quote volume (volume * close price) of the given klines.
+///
+/// # Arguments
+///
+/// * `klines` - A slice of `Kline` structs.
+///
+/// # Returns
+///
+/// The sum of the quote volume of every kline.
+pub fn calc_quote_volume(klines: &[Kline]) -> f64 {
+    klines.iter().map(|k| k.volume * k.close).sum()
+}
+
+/// Calculates the Average True Range (ATR) of the last `period` klines.
+///
+/// # Arguments
+///
+/// * `klines` - A slice of `Kline` structs sorted by open time.
+/// * `period` - The number of true ranges averaged.
+///
+/// # Returns
+///
+/// The ATR value, or `None` if there are not enough klines to fill the period.
+pub fn calc_atr(klines: &[Kline], period: usize) -> Option<f64> {
+    if period == 0 || klines.len() < period + 1 {
+        return None;
+    }
+
+    let window = &klines[klines.len() - period - 1..];
+    let true_ranges: Vec<f64> = window
+        .windows(2)
+        .map(|pair| {
+            let prev_close = pair[0].close;
+            let kline = &pair[1];
+            (kline.high - kline.low)
+                .max((kline.high - prev_close).abs())
+                .max((kline.low - prev_close).abs())
+        })
+        .collect();
+
+    Some(true_ranges.iter().sum::<f64>() / period as f64)
+}
+
+/// Calculates the body size of a kline as a percentage of its open price.
+///
+/// # Arguments
+///
+/// * `kline` - The kline to measure.
+///
+/// # Returns
+///
+/// The absolute difference between close and open, expressed as a percentage of the open price.
+pub fn calc_candle_body_pct(kline: &Kline) -> f64 {
+    if kline.open == 0.0 {
+        return 0.0;
+    }
+    (kline.close - kline.open).abs() / kline.open * 100.0
+}
+
+/// Lowest price of a synthetic kline, keeping every price process positive.
+const MIN_SYNTHETIC_PRICE: f64 = 1e-8;
+
+/// The prefix of the symbols synthetic klines are stored under, keeping them apart from the
+/// recorded klines of exchange symbols.
+pub const SYNTHETIC_SYMBOL_PREFIX: &str = "SYNTH";
+
+/// The process moving the price of synthetic klines from one kline to the next. Each adds noise
+/// of the `volatility` of `SyntheticKlines` to the move.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PriceModel {
+    /// Moves the price by `slope`, in the quote asset, every kline.
+    Trend { slope: f64 },
+    /// Pulls the price towards `mean` by a share `speed` between 0 and 1 of the distance every
+    /// kline, an Ornstein-Uhlenbeck process.
+    MeanReverting { mean: f64, speed: f64 },
+    /// Geometric Brownian motion, whose log returns average `drift` per kline.
+    Gbm { drift: f64 },
+    /// Volatility following a GARCH(1, 1) process, so large moves cluster. `shock` is the weight
+    /// of the last move, `persistence` of the last volatility, together below 1.
+    VolatilityClusters { shock: f64, persistence: f64 },
+}
+
+/// A parameterized series of synthetic klines, for developing algorithms, fuzzing them and
+/// validating back tests against a market whose behaviour is known. The same `seed` always
+/// generates the same series.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct SyntheticKlines {
+    pub symbol: String,
+    pub interval: Interval,
+    /// The open time of the first kline, floored to the interval.
+    pub start_ts: u64,
+    /// The number of intervals generated, including the missing ones.
+    pub count: usize,
+    pub start_price: f64,
+    /// The standard deviation of the log return of a kline, e.g. `0.01` for 1%.
+    pub volatility: f64,
+    pub model: PriceModel,
+    /// The chance of a kline opening away from the close of the previous one.
+    pub gap_probability: f64,
+    /// The largest share of the price a kline opens away from the previous close.
+    pub gap_size: f64,
+    /// The chance of a kline missing from the series, as when an exchange had an outage.
+    pub missing_probability: f64,
+    /// The average volume of a kline without a move, klines with larger moves trade more.
+    pub base_volume: f64,
+    pub seed: Option<u64>,
+}
+
+impl Default for SyntheticKlines {
+    fn default() -> Self {
+        Self {
+            symbol: "SYNTHUSDT".to_string(),
+            interval: Interval::Min1,
+            // 2024-01-01T00:00:00Z
+            start_ts: 1_704_067_200_000,
+            count: 500,
+            start_price: 100.0,
+            volatility: 0.01,
+            model: PriceModel::Gbm { drift: 0.0 },
+            gap_probability: 0.0,
+            gap_size: 0.05,
+            missing_probability: 0.0,
+            base_volume: 1000.0,
+            seed: None,
+        }
+    }
+}
+
+impl SyntheticKlines {
+    /// Checks the symbol of the series is in the namespace of synthetic symbols, e.g.
+    /// `SYNTHUSDT`, so storing it can't overwrite recorded klines.
+    pub fn has_synthetic_symbol(&self) -> bool {
+        self.symbol.len() > SYNTHETIC_SYMBOL_PREFIX.len()
+            && self.symbol.starts_with(SYNTHETIC_SYMBOL_PREFIX)
+    }
+
+    /// Generates the klines of the series.
+    ///
+    /// # Returns
+    ///
+    /// The klines sorted by open time, without the missing ones. Probabilities outside of 0 and
+    /// 1 are clamped and prices never drop below zero.
+    pub fn generate(&self) -> Vec<Kline> {
+        let mut rng = match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        let volatility = self.volatility.abs();
+        let gap_probability = self.gap_probability.clamp(0.0, 1.0);
+        let missing_probability = self.missing_probability.clamp(0.0, 1.0);
+        let interval_ms = self.interval.to_millis();
+        let start_ts = self.interval.floor(self.start_ts);
+
+        // the long run variance of the clusters matches the volatility of the other models
+        let (mut variance, mut last_return) = (volatility.powi(2), 0.0);
+        let mut close = self.start_price.max(MIN_SYNTHETIC_PRICE);
+        let mut klines = Vec::with_capacity(self.count);
+
+        for i in 0..self.count {
+            let sigma = match self.model {
+                PriceModel::VolatilityClusters { shock, persistence } => {
+                    let shock = shock.clamp(0.0, 1.0);
+                    let persistence = persistence.clamp(0.0, 1.0 - shock);
+                    let base = volatility.powi(2) * (1.0 - shock - persistence);
+                    variance = base + shock * last_return * last_return + persistence * variance;
+                    variance.sqrt()
+                }
+                _ => volatility,
+            };
+
+            let mut open = close;
+            if rng.gen_bool(gap_probability) {
+                let gap = rng.gen_range(-1.0..=1.0) * self.gap_size;
+                open = (open * (1.0 + gap)).max(MIN_SYNTHETIC_PRICE);
+            }
+
+            let noise = sigma * standard_normal(&mut rng);
+            close = match self.model {
+                PriceModel::Trend { slope } => open + slope + open * noise,
+                PriceModel::MeanReverting { mean, speed } => {
+                    open + speed.clamp(0.0, 1.0) * (mean - open) + open * noise
+                }
+                PriceModel::Gbm { drift } => open * (drift - sigma * sigma / 2.0 + noise).exp(),
+                PriceModel::VolatilityClusters { .. } => open * noise.exp(),
+            }
+            .max(MIN_SYNTHETIC_PRICE);
+            last_return = (close / open).ln();
+
+            // wicks reach past the body by up to about half the volatility of the kline
+            let high = open.max(close) * (1.0 + sigma * standard_normal(&mut rng).abs() / 2.0);
+            let low = (open.min(close) * (1.0 - sigma * standard_normal(&mut rng).abs() / 2.0))
+                .max(MIN_SYNTHETIC_PRICE);
+            let activity = if volatility > 0.0 {
+                1.0 + last_return.abs() / volatility
+            } else {
+                1.0
+            };
+            let volume = self.base_volume * rng.gen_range(0.5..1.5) * activity;
+
+            if rng.gen_bool(missing_probability) {
+                continue;
+            }
+
+            let open_time = start_ts + i as u64 * interval_ms;
+            klines.push(Kline {
+                symbol: self.symbol.clone(),
+                interval: self.interval,
+                open,
+                high,
+                low,
+                close,
+                volume,
+                open_time,
+                close_time: open_time + interval_ms - 1,
+            });
+        }
+
+        klines
+    }
+}
+
+/// Samples the standard normal distribution by the Box-Muller transform.
+fn standard_normal(rng: &mut StdRng) -> f64 {
+    // the first sample is excluded from zero, whose logarithm is infinite
+    let u1: f64 = 1.0 - rng.gen::<f64>();
+    let u2: f64 = rng.gen();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+}
+
 #[cfg(test)]
 mod tests {
-    // TODO: Write tests
+    use super::*;
+
+    fn build_kline(open: f64, high: f64, low: f64, close: f64, volume: f64) -> Kline {
+        Kline {
+            symbol: "BTCUSDT".to_string(),
+            interval: Interval::Min1,
+            open,
+            high,
+            low,
+            close,
+            volume,
+            open_time: 0,
+            close_time: 0,
+        }
+    }
+
+    /// Tests quote volume is the sum of volume times close price.
+    #[test]
+    fn test_calc_quote_volume() {
+        let klines = vec![
+            build_kline(10.0, 12.0, 9.0, 11.0, 2.0),
+            build_kline(11.0, 13.0, 10.0, 12.0, 3.0),
+        ];
+        assert_eq!(calc_quote_volume(&klines), 58.0);
+        assert_eq!(calc_quote_volume(&[]), 0.0);
+    }
+
+    /// Tests ATR uses true range including gaps from the previous close.
+    #[test]
+    fn test_calc_atr() {
+        let klines = vec![
+            build_kline(10.0, 11.0, 9.0, 10.0, 1.0),
+            build_kline(10.0, 12.0, 10.0, 11.0, 1.0),
+            build_kline(14.0, 15.0, 14.0, 15.0, 1.0),
+        ];
+        // true ranges: 2.0 and 4.0 (gap from previous close of 11.0)
+        assert_eq!(calc_atr(&klines, 2), Some(3.0));
+        assert_eq!(calc_atr(&klines, 1), Some(4.0));
+        assert_eq!(calc_atr(&klines, 3), None);
+        assert_eq!(calc_atr(&klines, 0), None);
+    }
+
+    /// Tests candle body size is relative to the open price.
+    #[test]
+    fn test_calc_candle_body_pct() {
+        assert_eq!(
+            calc_candle_body_pct(&build_kline(100.0, 110.0, 90.0, 105.0, 1.0)),
+            5.0
+        );
+        assert_eq!(
+            calc_candle_body_pct(&build_kline(100.0, 110.0, 90.0, 95.0, 1.0)),
+            5.0
+        );
+        assert_eq!(
+            calc_candle_body_pct(&build_kline(0.0, 0.0, 0.0, 0.0, 1.0)),
+            0.0
+        );
+    }
+
+    /// Tests synthetic series are reproducible from their seed, consistent klines of
+    /// consecutive intervals, and follow their price model.
+    #[test]
+    fn test_synthetic_klines() {
+        let series = SyntheticKlines {
+            interval: Interval::Min5,
+            start_ts: 1_704_067_230_000,
+            seed: Some(7),
+            ..Default::default()
+        };
+        let klines = series.generate();
+        assert_eq!(klines.len(), 500);
+        assert_eq!(klines[0].open_time, 1_704_067_200_000);
+        assert_eq!(klines[0].open, 100.0);
+        assert_eq!(series.generate()[499].close, klines[499].close);
+        for pair in klines.windows(2) {
+            assert_eq!(pair[1].open_time, pair[0].close_time + 1);
+            assert_eq!(pair[1].open, pair[0].close);
+        }
+        for kline in &klines {
+            assert!(kline.low <= kline.open.min(kline.close));
+            assert!(kline.high >= kline.open.max(kline.close));
+            assert!(kline.low > 0.0 && kline.volume > 0.0);
+        }
+
+        let trend = SyntheticKlines {
+            model: PriceModel::Trend { slope: 1.0 },
+            volatility: 0.0,
+            seed: Some(7),
+            ..Default::default()
+        };
+        assert_eq!(trend.generate()[99].close, 200.0);
+        let json = r#"{"model": {"type": "trend", "slope": 1.0}, "volatility": 0, "count": 100}"#;
+        let parsed: SyntheticKlines = serde_json::from_str(json).unwrap();
+        assert_eq!(parsed.model, trend.model);
+        assert_eq!(parsed.generate()[99].close, 200.0);
+
+        let reverting = SyntheticKlines {
+            model: PriceModel::MeanReverting {
+                mean: 50.0,
+                speed: 0.2,
+            },
+            volatility: 0.001,
+            seed: Some(7),
+            ..Default::default()
+        };
+        assert!((reverting.generate()[499].close - 50.0).abs() < 2.0);
+
+        let clustered = SyntheticKlines {
+            model: PriceModel::VolatilityClusters {
+                shock: 0.1,
+                persistence: 0.85,
+            },
+            seed: Some(7),
+            ..Default::default()
+        };
+        assert_eq!(clustered.generate().len(), 500);
+
+        assert!(SyntheticKlines::default().has_synthetic_symbol());
+        for symbol in ["BTCUSDT", "SYNTH", "synthUSDT"] {
+            let series = SyntheticKlines {
+                symbol: symbol.to_string(),
+                ..Default::default()
+            };
+            assert!(!series.has_synthetic_symbol());
+        }
+
+        let gapped = SyntheticKlines {
+            gap_probability: 1.0,
+            missing_probability: 0.5,
+            seed: Some(7),
+            ..Default::default()
+        };
+        let klines = gapped.generate();
+        assert!(klines.len() < 500 && !klines.is_empty());
+        assert!(klines.windows(2).any(|pair| pair[1].open != pair[0].close));
+        assert!(klines
+            .windows(2)
+            .any(|pair| pair[1].open_time > pair[0].close_time + 1));
+    }
 }
@@ -3,9 +3,11 @@ pub mod bson;
 pub mod channel;
 pub mod crypt;
 pub mod csv;
+pub mod env;
 #[doc(hidden)]
 pub mod json;
 pub mod kline;
 pub mod number;
+pub mod pool;
 pub mod time;
 pub mod trade;
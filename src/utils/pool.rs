@@ -0,0 +1,87 @@
+use std::{
+    future::Future,
+    sync::{Arc, OnceLock},
+    thread::available_parallelism,
+};
+
+use tokio::{
+    runtime::{Builder, Runtime},
+    sync::Semaphore,
+    task::JoinError,
+};
+
+/// Multi-threaded runtime executing CPU bound work, such as backtests, next to the server's
+/// single threaded workers. Created on first use and kept for the lifetime of the process.
+static WORKER_RUNTIME: OnceLock<Runtime> = OnceLock::new();
+
+/// Returns the number of worker threads, the available cores or one if unknown.
+pub fn worker_count() -> usize {
+    available_parallelism()
+        .map(|count| count.get())
+        .unwrap_or(1)
+}
+
+fn worker_runtime() -> &'static Runtime {
+    WORKER_RUNTIME.get_or_init(|| {
+        Builder::new_multi_thread()
+            .worker_threads(worker_count())
+            .thread_name("raderbot-worker")
+            .enable_all()
+            .build()
+            .expect("Failed to build worker runtime")
+    })
+}
+
+/// Runs futures on the worker runtime, with at most `worker_count` of them in flight so the
+/// memory of queued futures isn't allocated all at once.
+///
+/// # Arguments
+///
+/// * `futures` - The futures to run.
+///
+/// # Returns
+///
+/// The output of every future in input order, or the `JoinError` of a future that panicked.
+pub async fn run_parallel<F, T>(futures: Vec<F>) -> Vec<Result<T, JoinError>>
+where
+    F: Future<Output = T> + Send + 'static,
+    T: Send + 'static,
+{
+    let permits = Arc::new(Semaphore::new(worker_count()));
+
+    let handles: Vec<_> = futures
+        .into_iter()
+        .map(|future| {
+            let permits = permits.clone();
+            worker_runtime().spawn(async move {
+                // SAFETY: the semaphore is never closed
+                let _permit = permits.acquire().await.unwrap();
+                future.await
+            })
+        })
+        .collect();
+
+    let mut outputs = vec![];
+    for handle in handles {
+        outputs.push(handle.await);
+    }
+    outputs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests outputs are returned in input order.
+    #[tokio::test]
+    async fn test_run_parallel() {
+        let futures: Vec<_> = (0..10).map(|i| async move { i * 2 }).collect();
+        let outputs: Vec<i32> = run_parallel(futures)
+            .await
+            .into_iter()
+            .map(|output| output.unwrap())
+            .collect();
+
+        assert_eq!(outputs, (0..10).map(|i| i * 2).collect::<Vec<_>>());
+    }
+}
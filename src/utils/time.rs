@@ -1,19 +1,36 @@
 use actix_web::cookie::time::Instant;
 use chrono::prelude::DateTime;
 use chrono::Datelike;
+use chrono::FixedOffset;
 use chrono::NaiveDate;
 use chrono::NaiveDateTime;
 use chrono::TimeZone;
 use chrono::Utc;
-use dateparser::parse;
+use dateparser::parse_with_timezone;
+use serde::{de, Deserialize, Deserializer};
 
-use std::time::{Duration, SystemTime};
+use std::time::SystemTime;
 
 pub const SEC_AS_MILI: u64 = 1000;
 pub const MIN_AS_MILI: u64 = SEC_AS_MILI * 60; // 60000
 pub const HOUR_AS_MILI: u64 = MIN_AS_MILI * 60; // 3600000
 pub const DAY_AS_MILI: u64 = HOUR_AS_MILI * 24; // 86400000
 
+/// Hours between the funding settlements of most perpetual swaps, at 00:00, 08:00 and 16:00 UTC.
+pub const FUNDING_INTERVAL_HOURS: u64 = 8;
+
+/// Timestamps below this are read as seconds rather than milliseconds, it's 1973 in milliseconds
+/// and the year 5138 in seconds.
+const MAX_SECONDS_TS: u64 = 100_000_000_000;
+
+/// Formats of dates and times without a UTC offset, tried in order.
+const NAIVE_DATETIME_FORMATS: [&str; 4] = [
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M",
+];
+
 /// Generates a current timestamp in milliseconds since the UNIX epoch.
 ///
 /// # Returns
@@ -55,6 +72,11 @@ pub fn timestamp_to_datetime(timestamp: u64) -> DateTime<Utc> {
 
 /// Converts a date string to a UNIX timestamp in milliseconds.
 ///
+/// Accepts UNIX timestamps in milliseconds or seconds, RFC 3339 and ISO 8601 dates such as
+/// `2024-01-01T08:00:00+08:00` or `2024-01-01T00:00:00.000Z`, and dates and times without an
+/// offset such as `2024-01-01` or `2024-01-01 08:00`, which are read as UTC. Other formats are
+/// left to `dateparser`.
+///
 /// # Arguments
 ///
 /// * `date_str` - The date string to convert.
@@ -63,21 +85,93 @@ pub fn timestamp_to_datetime(timestamp: u64) -> DateTime<Utc> {
 ///
 /// A `Result<u64, &'static str>` which is Ok containing the timestamp in milliseconds if successful, or an Err with an error message.
 pub fn string_to_timestamp(date_str: &str) -> Result<u64, &'static str> {
-    if let Ok(date) = parse(date_str) {
-        let date = date.timestamp_millis();
-        let mut s_ts = format!("{}", date);
-        while s_ts.len() < 13 {
-            s_ts.push('0');
-        }
+    string_to_timestamp_in(date_str, utc_offset())
+}
 
-        if let Ok(ts) = s_ts.parse::<u64>() {
-            return Ok(ts);
+/// Converts a date string to a UNIX timestamp in milliseconds like `string_to_timestamp`, reading
+/// dates and times without an offset in the timezone of `offset`.
+///
+/// # Arguments
+///
+/// * `date_str` - The date string to convert.
+/// * `offset` - The UTC offset of dates without one, e.g. of `parse_utc_offset("+08:00")`.
+pub fn string_to_timestamp_in(date_str: &str, offset: FixedOffset) -> Result<u64, &'static str> {
+    const PARSE_ERROR: &str = "Unable to parse date string";
+    let date_str = date_str.trim();
+
+    if !date_str.is_empty() && date_str.bytes().all(|b| b.is_ascii_digit()) {
+        let ts = date_str.parse::<u64>().map_err(|_| PARSE_ERROR)?;
+        return Ok(if ts < MAX_SECONDS_TS {
+            ts * SEC_AS_MILI
         } else {
-            return Err("Unable to parse date string");
-        }
+            ts
+        });
+    }
+
+    let date = if let Ok(date) = DateTime::parse_from_rfc3339(date_str) {
+        date.timestamp_millis()
+    } else if let Some(naive) = parse_naive_datetime(date_str) {
+        let date = offset
+            .from_local_datetime(&naive)
+            .single()
+            .ok_or(PARSE_ERROR)?;
+        date.timestamp_millis()
+    } else {
+        let date = parse_with_timezone(date_str, &offset).map_err(|_| PARSE_ERROR)?;
+        date.timestamp_millis()
     };
 
-    Err("Unable to parse date string")
+    u64::try_from(date).map_err(|_| PARSE_ERROR)
+}
+
+/// Deserializes an optional timestamp given in milliseconds or as a date string in any of the
+/// formats of `string_to_timestamp`, e.g. for the time filters of API queries.
+pub fn deserialize_opt_timestamp<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<u64>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Timestamp {
+        Millis(u64),
+        Date(String),
+    }
+
+    match Option::<Timestamp>::deserialize(deserializer)? {
+        Some(Timestamp::Millis(ts)) => Ok(Some(ts)),
+        Some(Timestamp::Date(date)) => string_to_timestamp(&date)
+            .map(Some)
+            .map_err(de::Error::custom),
+        None => Ok(None),
+    }
+}
+
+/// Parses a UTC offset such as `Z`, `UTC`, `+08:00`, `-0530` or `+8`.
+///
+/// # Returns
+///
+/// The offset, `None` if it isn't a valid offset.
+pub fn parse_utc_offset(offset: &str) -> Option<FixedOffset> {
+    let offset = offset.trim();
+    if matches!(offset.to_uppercase().as_str(), "Z" | "UTC" | "GMT") {
+        return Some(utc_offset());
+    }
+
+    let sign = match offset.chars().next()? {
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+    };
+    let offset = offset[1..].replace(':', "");
+    let (hours, minutes) = match offset.len() {
+        1 | 2 => (offset.as_str(), "0"),
+        4 => offset.split_at(2),
+        _ => return None,
+    };
+    let (hours, minutes) = (hours.parse::<i32>().ok()?, minutes.parse::<i32>().ok()?);
+    if minutes >= 60 {
+        return None;
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
 }
 
 /// Converts a UNIX timestamp in milliseconds to a date string in ISO 8601 format.
@@ -96,6 +190,18 @@ pub fn timestamp_to_string(ts: u64) -> String {
     timestamp_str
 }
 
+/// Converts a UNIX timestamp in milliseconds to an RFC 3339 date in a timezone, e.g.
+/// `2024-01-01T08:00:00+08:00`.
+///
+/// # Arguments
+///
+/// * `ts` - A UNIX timestamp in milliseconds.
+/// * `offset` - The UTC offset of the timezone.
+pub fn timestamp_to_string_in(ts: u64, offset: FixedOffset) -> String {
+    let datetime = timestamp_to_datetime(ts).with_timezone(&offset);
+    datetime.format("%Y-%m-%dT%H:%M:%S%:z").to_string()
+}
+
 /// Converts a year, month, and day to a UNIX timestamp in milliseconds.
 ///
 /// # Arguments
@@ -147,52 +253,6 @@ pub fn get_time_difference(from_ts: u64, to_ts: u64) -> u64 {
     }
 }
 
-/// Calculates the open time of a k-line based on its close time and interval.
-///
-/// # Arguments
-///
-/// * `close_time` - The closing time of the k-line in milliseconds.
-/// * `interval` - The interval of the k-line (e.g., "1m", "5m").
-///
-/// # Returns
-///
-/// A `u64` representing the open time of the k-line in milliseconds.
-pub fn calculate_kline_open_time(close_time: u64, interval: &str) -> u64 {
-    // Convert the interval to seconds
-    let interval_seconds = match interval {
-        "1m" => 60,
-        "5m" => 5 * 60,
-        "15m" => 15 * 60,
-        // Add more interval cases as needed
-        _ => {
-            println!("Unsupported interval: {}", interval);
-            return 0; // Return 0 if interval is unsupported
-        }
-    };
-
-    // Calculate the open time by subtracting interval seconds from the close time
-    (close_time + 1) - (interval_seconds * 1000)
-}
-
-/// Builds a `Duration` representing the interval specified by a string.
-///
-/// # Arguments
-///
-/// * `interval` - The interval as a string (e.g., "1m", "5m").
-///
-/// # Returns
-///
-/// A `Result<Duration, &'static str>` which is Ok containing the `Duration` if the interval is supported, or an Err with an error message.
-pub fn build_interval(interval: &str) -> Result<Duration, &'static str> {
-    match interval {
-        "1m" => Ok(Duration::from_secs(60)),
-        "5m" => Ok(Duration::from_secs(300)),
-        "15m" => Ok(Duration::from_secs(900)),
-        "1h" => Ok(Duration::from_secs(3600)),
-        _ => Err("Unsupported interval"),
-    }
-}
-
 // TODO: docs
 pub fn floor_mili_ts(timestamp_millis: u64, mili_sec: u64) -> u64 {
     let floored_intervals = timestamp_millis / mili_sec;
@@ -221,6 +281,60 @@ pub fn floor_month_ts(open_time: u64) -> u64 {
     start_of_month.timestamp_millis() as u64
 }
 
+/// The start of the exchange day a timestamp falls in. Exchanges roll their days, e.g. of daily
+/// k-lines and 24 hour statistics, over at 00:00 UTC.
+///
+/// # Arguments
+///
+/// * `ts` - A UNIX timestamp in milliseconds.
+pub fn day_start_ts(ts: u64) -> u64 {
+    floor_mili_ts(ts, DAY_AS_MILI)
+}
+
+/// The start of the day a timestamp falls in, in a timezone.
+///
+/// # Arguments
+///
+/// * `ts` - A UNIX timestamp in milliseconds.
+/// * `offset` - The UTC offset of the timezone.
+///
+/// # Returns
+///
+/// The timestamp of the local midnight, which is the UTC midnight for a zero offset.
+pub fn day_start_ts_in(ts: u64, offset: FixedOffset) -> u64 {
+    let offset_ms = offset.local_minus_utc() as i64 * SEC_AS_MILI as i64;
+    let local_ts = ts as i64 + offset_ms;
+    let local_day_start = local_ts.div_euclid(DAY_AS_MILI as i64) * DAY_AS_MILI as i64;
+    (local_day_start - offset_ms).max(0) as u64
+}
+
+/// The first funding settlement after a timestamp. Settlements are aligned to the exchange day,
+/// every 8 hours on most perpetual swaps, see `FUNDING_INTERVAL_HOURS`.
+///
+/// # Arguments
+///
+/// * `ts` - A UNIX timestamp in milliseconds.
+/// * `interval_ms` - The time between settlements in milliseconds, greater than zero.
+pub fn next_funding_ts(ts: u64, interval_ms: u64) -> u64 {
+    floor_mili_ts(ts, interval_ms) + interval_ms
+}
+
+/// The funding settlements after `from_ts` up to and including `to_ts`, in time order.
+///
+/// # Arguments
+///
+/// * `from_ts` - The start of the range in milliseconds, exclusive.
+/// * `to_ts` - The end of the range in milliseconds, inclusive.
+/// * `interval_ms` - The time between settlements in milliseconds, none are returned for zero.
+pub fn funding_timestamps(from_ts: u64, to_ts: u64, interval_ms: u64) -> Vec<u64> {
+    if interval_ms == 0 {
+        return vec![];
+    }
+    (next_funding_ts(from_ts, interval_ms)..=to_ts)
+        .step_by(interval_ms as usize)
+        .collect()
+}
+
 // TODO: docs
 pub fn add_month_to_timestamp(timestamp: i64) -> i64 {
     let datetime = Utc.timestamp_millis_opt(timestamp).unwrap();
@@ -245,14 +359,23 @@ pub fn elapsed_time(start: Instant) -> actix_web::cookie::time::Duration {
     start.elapsed()
 }
 
-pub fn interval_to_millis(interval: &str) -> u64 {
-    match interval {
-        "1m" => MIN_AS_MILI,
-        "5m" => MIN_AS_MILI * 5,
-        "15m" => MIN_AS_MILI * 15,
-        "1h" => HOUR_AS_MILI,
-        _ => SEC_AS_MILI,
-    }
+// ---
+// Private Methods
+// ---
+
+fn utc_offset() -> FixedOffset {
+    FixedOffset::east_opt(0).unwrap()
+}
+
+fn parse_naive_datetime(date_str: &str) -> Option<NaiveDateTime> {
+    NAIVE_DATETIME_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(date_str, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(date_str, "%Y-%m-%d")
+                .ok()?
+                .and_hms_opt(0, 0, 0)
+        })
 }
 
 #[cfg(test)]
@@ -295,6 +418,75 @@ mod tests {
         assert_eq!(result.unwrap(), ts);
     }
 
+    #[test]
+    fn test_string_to_timestamp_formats() {
+        let ts = 1640995200000;
+
+        for date_str in [
+            "1640995200000",
+            "1640995200",
+            "2022-01-01",
+            "2022-01-01 00:00",
+            "2022-01-01T00:00:00.000Z",
+            "2022-01-01T08:00:00+08:00",
+            " 2022-01-01T00:00:00 ",
+        ] {
+            assert_eq!(string_to_timestamp(date_str), Ok(ts), "{date_str}");
+        }
+        assert!(string_to_timestamp("").is_err());
+        assert!(string_to_timestamp("1969-12-31T00:00:00Z").is_err());
+
+        let offset = parse_utc_offset("+08:00").unwrap();
+        assert_eq!(string_to_timestamp_in("2022-01-01 08:00", offset), Ok(ts));
+        assert_eq!(
+            string_to_timestamp_in("2022-01-01T00:00:00Z", offset),
+            Ok(ts)
+        );
+    }
+
+    #[test]
+    fn test_utc_offsets() {
+        let ts = 1640995200000;
+
+        assert_eq!(parse_utc_offset("Z"), FixedOffset::east_opt(0));
+        assert_eq!(
+            parse_utc_offset("-0530"),
+            FixedOffset::west_opt(5 * 3600 + 1800)
+        );
+        assert_eq!(parse_utc_offset("+8"), FixedOffset::east_opt(8 * 3600));
+        assert!(parse_utc_offset("08:00").is_none());
+        assert!(parse_utc_offset("+08:60").is_none());
+
+        let offset = parse_utc_offset("+08:00").unwrap();
+        assert_eq!(
+            timestamp_to_string_in(ts, offset),
+            "2022-01-01T08:00:00+08:00"
+        );
+
+        // 2022-01-01T20:00:00Z is already the 2nd in UTC+8, which started at 16:00 UTC
+        let evening = ts + 20 * HOUR_AS_MILI;
+        assert_eq!(day_start_ts(evening), ts);
+        assert_eq!(day_start_ts_in(evening, offset), ts + 16 * HOUR_AS_MILI);
+        assert_eq!(
+            day_start_ts_in(evening, parse_utc_offset("-05:00").unwrap()),
+            ts + 5 * HOUR_AS_MILI
+        );
+    }
+
+    #[test]
+    fn test_funding_timestamps() {
+        let ts = 1640995200000;
+        let interval_ms = FUNDING_INTERVAL_HOURS * HOUR_AS_MILI;
+
+        assert_eq!(next_funding_ts(ts, interval_ms), ts + interval_ms);
+        assert_eq!(next_funding_ts(ts + 1, interval_ms), ts + interval_ms);
+        assert_eq!(
+            funding_timestamps(ts, ts + DAY_AS_MILI, interval_ms),
+            vec![ts + interval_ms, ts + 2 * interval_ms, ts + DAY_AS_MILI]
+        );
+        assert!(funding_timestamps(ts, ts + DAY_AS_MILI, 0).is_empty());
+    }
+
     #[test]
     fn test_timestamp_to_string() {
         let ts = 1640995200000;